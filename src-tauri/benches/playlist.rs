@@ -0,0 +1,151 @@
+//! Parsing the items of a very large playlist.
+//!
+//! Run with `cargo bench --features bench --bench playlist`. The bytes each
+//! run allocates are printed before timing; `clone_attributes` is what every
+//! included resource cost before the parser borrowed from the response.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use serde_json::{json, Value};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tauritidal_lib::bench::parse_items_from_included;
+
+/// Items in the playlist; every tenth one is a video
+const ITEMS: usize = 1_000;
+/// Tracks share artists and albums, as they do in a real playlist
+const ARTISTS: usize = 300;
+const ALBUMS: usize = 400;
+
+/// Counts the bytes allocated, so a run can report what it costs in memory.
+struct Counting;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+fn relationship(kind: &str, id: String) -> Value {
+    json!({ "data": [{ "id": id, "type": kind }] })
+}
+
+fn artwork(id: String) -> Value {
+    let files: Vec<Value> = [160, 320, 640, 1280]
+        .iter()
+        .map(|size| {
+            json!({
+                "href": format!("https://resources.tidal.com/images/{}/{}x{}.jpg", id, size, size),
+                "meta": { "width": size, "height": size },
+            })
+        })
+        .collect();
+    json!({ "id": id, "type": "artworks", "attributes": { "files": files } })
+}
+
+/// The `data` and `included` of a playlist items response.
+fn response() -> (Vec<Value>, Vec<Value>) {
+    let mut data = Vec::new();
+    let mut included = Vec::new();
+    for i in 0..ITEMS {
+        if i % 10 == 9 {
+            data.push(json!({ "id": format!("v{}", i), "type": "videos" }));
+            included.push(json!({
+                "id": format!("v{}", i),
+                "type": "videos",
+                "attributes": { "title": format!("Video {}", i), "duration": "PT4M2S" },
+            }));
+            continue;
+        }
+        data.push(json!({
+            "id": format!("t{}", i),
+            "type": "tracks",
+            "meta": { "addedAt": "2024-05-06T07:08:09Z" },
+        }));
+        included.push(json!({
+            "id": format!("t{}", i),
+            "type": "tracks",
+            "attributes": {
+                "title": format!("Track {}", i),
+                "duration": "PT3M21S",
+                "explicit": i % 7 == 0,
+                "isrc": format!("USRC1{:07}", i),
+                "mediaTags": ["LOSSLESS", "HIRES_LOSSLESS"],
+                "popularity": 0.5,
+                "copyright": { "text": format!("(P) {} Records", i % ARTISTS) },
+            },
+            "relationships": {
+                "artists": relationship("artists", format!("ar{}", i % ARTISTS)),
+                "albums": relationship("albums", format!("al{}", i % ALBUMS)),
+            }
+        }));
+    }
+    for i in 0..ALBUMS {
+        included.push(json!({
+            "id": format!("al{}", i),
+            "type": "albums",
+            "attributes": {
+                "title": format!("Album {}", i),
+                "releaseDate": "2021-03-04",
+                "numberOfItems": 12,
+                "barcodeId": format!("{:013}", i),
+            },
+            "relationships": {
+                "artists": relationship("artists", format!("ar{}", i % ARTISTS)),
+                "coverArt": relationship("artworks", format!("aw{}", i)),
+            }
+        }));
+        included.push(artwork(format!("aw{}", i)));
+    }
+    for i in 0..ARTISTS {
+        included.push(json!({
+            "id": format!("ar{}", i),
+            "type": "artists",
+            "attributes": { "name": format!("Artist {}", i), "popularity": 0.3 },
+        }));
+    }
+    (data, included)
+}
+
+/// Bytes allocated by one call of `f`.
+fn allocated_by<R>(f: impl FnOnce() -> R) -> usize {
+    let before = ALLOCATED.load(Ordering::Relaxed);
+    black_box(f());
+    ALLOCATED.load(Ordering::Relaxed) - before
+}
+
+fn clone_attributes(included: &[Value]) -> Vec<Value> {
+    included
+        .iter()
+        .filter_map(|item| item.get("attributes").cloned())
+        .collect()
+}
+
+fn bench_playlist(c: &mut Criterion) {
+    let (data, included) = response();
+    eprintln!(
+        "{} included resources; parse_items allocates {} KiB, cloning their attributes {} KiB",
+        included.len(),
+        allocated_by(|| parse_items_from_included(Some(&data), Some(&included))) / 1024,
+        allocated_by(|| clone_attributes(&included)) / 1024,
+    );
+
+    c.bench_function("parse_items", |b| {
+        b.iter(|| black_box(parse_items_from_included(Some(&data), Some(&included))))
+    });
+    c.bench_function("clone_attributes", |b| {
+        b.iter(|| black_box(clone_attributes(&included)))
+    });
+}
+
+criterion_group!(benches, bench_playlist);
+criterion_main!(benches);
@@ -0,0 +1,76 @@
+//! Queue operations on a whole-library sized queue.
+//!
+//! Run with `cargo bench --features bench --bench queue`. `clone_all_tracks`
+//! is what every reorder cost before tracks were shared behind `Arc`.
+
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use tauritidal_lib::bench::{PlaybackQueue, Track};
+
+const QUEUE_LEN: usize = 5_000;
+
+fn library() -> Vec<Track> {
+    (0..QUEUE_LEN)
+        .map(|i| Track {
+            id: i.to_string(),
+            title: format!("Track {}", i),
+            duration: 200.0,
+            track_number: Some((i % 12) as u32 + 1),
+            volume_number: Some(1),
+            isrc: Some(format!("USRC1{:07}", i)),
+            artist_name: format!("Artist {}", i % 300),
+            artist_id: Some((i % 300).to_string()),
+            album_name: format!("Album {}", i % 400),
+            album_id: Some((i % 400).to_string()),
+            artwork_url: Some(format!(
+                "https://resources.tidal.com/images/{:08x}/640x640.jpg",
+                i
+            )),
+            media_tags: vec!["LOSSLESS".into(), "HIRES_LOSSLESS".into()],
+            explicit: false,
+            release_date: None,
+            is_new: false,
+            streamable: true,
+            language: None,
+        })
+        .collect()
+}
+
+fn queue() -> PlaybackQueue {
+    let mut queue = PlaybackQueue::new();
+    queue.set_tracks(library(), 0);
+    queue
+}
+
+fn bench_queue(c: &mut Criterion) {
+    let tracks = library();
+    c.bench_function("clone_all_tracks", |b| b.iter(|| black_box(tracks.clone())));
+
+    c.bench_function("set_tracks", |b| {
+        b.iter_batched(
+            library,
+            |tracks| {
+                let mut queue = PlaybackQueue::new();
+                queue.set_tracks(tracks, 0);
+                queue
+            },
+            BatchSize::LargeInput,
+        )
+    });
+
+    let mut shuffled = queue();
+    c.bench_function("shuffle_unshuffle", |b| {
+        b.iter(|| {
+            shuffled.shuffle(Some(1));
+            shuffled.unshuffle();
+        })
+    });
+
+    let queue = queue();
+    c.bench_function("state_full", |b| b.iter(|| black_box(queue.state())));
+    c.bench_function("state_window_100", |b| {
+        b.iter(|| black_box(queue.state_window(2_500, Some(100))))
+    });
+}
+
+criterion_group!(benches, bench_queue);
+criterion_main!(benches);
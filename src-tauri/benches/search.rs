@@ -0,0 +1,100 @@
+//! Parsing a large search response.
+//!
+//! Run with `cargo bench --features bench --bench search`. Suggestions only
+//! need a handful of results; `limit_5` stops long before `unlimited`, which
+//! walks every included resource.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use serde_json::{json, Value};
+use tauritidal_lib::bench::parse_search_results;
+
+/// Resources of each kind in the response; the API can return 200+ in all.
+const PER_KIND: usize = 60;
+
+fn relationship(kind: &str, id: String) -> Value {
+    json!({ "data": [{ "id": id, "type": kind }] })
+}
+
+fn artwork(id: String) -> Value {
+    json!({
+        "id": id,
+        "type": "artworks",
+        "attributes": {
+            "files": [
+                { "href": "https://resources.tidal.com/images/a/160x160.jpg", "meta": { "width": 160, "height": 160 } },
+                { "href": "https://resources.tidal.com/images/a/640x640.jpg", "meta": { "width": 640, "height": 640 } },
+            ]
+        }
+    })
+}
+
+fn response() -> Value {
+    let mut included = Vec::new();
+    for i in 0..PER_KIND {
+        included.push(json!({
+            "id": format!("t{}", i),
+            "type": "tracks",
+            "attributes": { "title": format!("Track {}", i), "duration": "PT3M21S", "explicit": false },
+            "relationships": {
+                "artists": relationship("artists", format!("ar{}", i)),
+                "albums": relationship("albums", format!("al{}", i)),
+            }
+        }));
+        included.push(json!({
+            "id": format!("al{}", i),
+            "type": "albums",
+            "attributes": { "title": format!("Album {}", i), "releaseDate": "2021-03-04", "numberOfItems": 12 },
+            "relationships": {
+                "artists": relationship("artists", format!("ar{}", i)),
+                "coverArt": relationship("artworks", format!("aw{}", i)),
+            }
+        }));
+        included.push(json!({
+            "id": format!("ar{}", i),
+            "type": "artists",
+            "attributes": { "name": format!("Artist {}", i) },
+            "relationships": { "profileArt": relationship("artworks", format!("aw{}", i)) }
+        }));
+        included.push(json!({
+            "id": format!("p{}", i),
+            "type": "playlists",
+            "attributes": { "name": format!("Playlist {}", i), "numberOfItems": 40 },
+            "relationships": { "coverArt": relationship("artworks", format!("aw{}", i)) }
+        }));
+        included.push(artwork(format!("aw{}", i)));
+    }
+    let listed = |kind: &str, prefix: &str| {
+        let refs: Vec<Value> = (0..PER_KIND)
+            .map(|i| json!({ "id": format!("{}{}", prefix, i), "type": kind }))
+            .collect();
+        json!({ "data": refs })
+    };
+    json!({
+        "data": {
+            "relationships": {
+                "tracks": listed("tracks", "t"),
+                "albums": listed("albums", "al"),
+                "artists": listed("artists", "ar"),
+                "playlists": listed("playlists", "p"),
+                "videos": { "data": [] },
+            }
+        },
+        "included": included,
+    })
+}
+
+fn bench_search(c: &mut Criterion) {
+    let body = response();
+    c.bench_function("limit_5", |b| {
+        b.iter(|| black_box(parse_search_results(&body, 5)))
+    });
+    c.bench_function("limit_20", |b| {
+        b.iter(|| black_box(parse_search_results(&body, 20)))
+    });
+    c.bench_function("unlimited", |b| {
+        b.iter(|| black_box(parse_search_results(&body, usize::MAX)))
+    });
+}
+
+criterion_group!(benches, bench_search);
+criterion_main!(benches);
@@ -0,0 +1,69 @@
+//! Seeking in a long FLAC download without a SEEKTABLE.
+//!
+//! Run with `cargo bench --features bench --bench seek`. `seek_unindexed` is
+//! symphonia bisecting the stream on its own; `seek_indexed` starts from the
+//! frame index that `index_build` scans once the download has finished.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use std::io::Cursor;
+use std::time::{Duration, Instant};
+use tauritidal_lib::bench::{flac, AudioDecoder, FlacSeekIndex, HttpStreamSource};
+
+const SAMPLE_RATE: u32 = 44_100;
+/// Ten minutes of stereo audio, about 106 MB of uncompressed frames
+const SECONDS: u32 = 600;
+
+/// Seek targets spread over the whole track, visited in turn.
+const TARGETS: [f64; 8] = [310.0, 45.0, 580.0, 120.0, 455.0, 5.0, 250.0, 399.5];
+
+/// A finished download of `file`, with its frame index built.
+fn indexed_download(file: &[u8]) -> HttpStreamSource {
+    let (source, writer, _abort) = HttpStreamSource::new();
+    writer.set_total_length(file.len() as u64);
+    writer.write_bytes(file).unwrap();
+    writer.finish();
+
+    let index = source.indexed_seek();
+    let deadline = Instant::now() + Duration::from_secs(60);
+    while index.view_at(0).is_none() {
+        assert!(Instant::now() < deadline, "index not built");
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    source
+}
+
+fn seek_round(decoder: &mut AudioDecoder) {
+    for target in TARGETS {
+        decoder.seek(target).unwrap();
+        black_box(decoder.decode_next().unwrap());
+    }
+}
+
+fn bench_seek(c: &mut Criterion) {
+    let file = flac(SAMPLE_RATE, 2, SAMPLE_RATE * SECONDS);
+
+    c.bench_function("index_build", |b| {
+        b.iter(|| {
+            FlacSeekIndex::build(|offset, len, buf| {
+                let start = offset.min(file.len());
+                let end = offset.saturating_add(len).min(file.len());
+                buf.clear();
+                buf.extend_from_slice(&file[start..end]);
+                true
+            })
+        })
+    });
+
+    let mut unindexed =
+        AudioDecoder::new(Box::new(Cursor::new(file.clone())), Some("FLAC")).unwrap();
+    c.bench_function("seek_unindexed", |b| b.iter(|| seek_round(&mut unindexed)));
+
+    let source = indexed_download(&file);
+    let index = source.indexed_seek();
+    let mut indexed = AudioDecoder::new(Box::new(source), Some("FLAC")).unwrap();
+    indexed.set_indexed_seek(index);
+    c.bench_function("seek_indexed", |b| b.iter(|| seek_round(&mut indexed)));
+}
+
+criterion_group!(benches, bench_seek);
+criterion_main!(benches);
@@ -0,0 +1,278 @@
+use serde::Serialize;
+use std::time::Duration;
+
+/// Consecutive slow downloads after which quality steps down.
+const DOWNGRADE_AFTER: u32 = 3;
+
+/// Consecutive fast downloads after which quality probes one step back up.
+const UPGRADE_AFTER: u32 = 3;
+
+/// How much faster than a quality's bitrate a download must run to keep up
+/// with it, leaving room for seeks and throughput dips.
+const HEADROOM: f64 = 1.5;
+
+/// Smaller downloads finish before the rate settles and are ignored.
+const MIN_SAMPLE_BYTES: u64 = 512 * 1024;
+
+/// Streaming qualities from lowest to highest. `HI_RES` ranks with `HI_RES_LOSSLESS`.
+const LADDER: &[&str] = &["LOW", "HIGH", "LOSSLESS", "HI_RES_LOSSLESS"];
+
+fn rank(quality: &str) -> usize {
+    match quality {
+        "HI_RES" | "HI_RES_LOSSLESS" => 3,
+        "LOSSLESS" => 2,
+        "HIGH" => 1,
+        _ => 0,
+    }
+}
+
+/// Rough sustained bitrate of each quality, in bytes per second.
+fn required_rate(rank: usize) -> f64 {
+    match rank {
+        3 => 400_000.0,
+        2 => 130_000.0,
+        1 => 40_000.0,
+        _ => 12_000.0,
+    }
+}
+
+/// A change to the quality manifests are fetched at.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QualityAdaptation {
+    pub from: String,
+    pub to: String,
+    /// Rate of the download that triggered the change
+    pub throughput_kbps: u64,
+    pub reason: String,
+}
+
+/// Steps streaming quality down when downloads can't keep up with it and back
+/// up once they can. Only affects manifests fetched afterwards, so a playing
+/// track never changes quality.
+#[derive(Debug, Default)]
+pub struct AdaptiveQuality {
+    /// Highest rank to fetch at while stepped down; `None` uses the configured quality
+    cap: Option<usize>,
+    slow_streak: u32,
+    fast_streak: u32,
+}
+
+impl AdaptiveQuality {
+    /// The quality to fetch at, given the configured one.
+    pub fn effective(&self, configured: &str) -> String {
+        match self.cap {
+            Some(cap) if cap < rank(configured) => LADDER[cap].to_string(),
+            _ => configured.to_string(),
+        }
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Record one finished track download. Returns the change if this sample
+    /// completed a slow or fast streak.
+    pub fn record(
+        &mut self,
+        configured: &str,
+        bytes: u64,
+        elapsed: Duration,
+    ) -> Option<QualityAdaptation> {
+        if bytes < MIN_SAMPLE_BYTES || elapsed.is_zero() {
+            return None;
+        }
+        let rate = bytes as f64 / elapsed.as_secs_f64();
+        let ceiling = rank(configured);
+        let current = self.cap.map_or(ceiling, |cap| cap.min(ceiling));
+
+        if rate < required_rate(current) * HEADROOM {
+            self.fast_streak = 0;
+            self.slow_streak += 1;
+            if self.slow_streak < DOWNGRADE_AFTER || current == 0 {
+                return None;
+            }
+            self.slow_streak = 0;
+            self.cap = Some(current - 1);
+            return Some(self.adaptation(
+                configured,
+                current,
+                rate,
+                format!(
+                    "The last {} downloads were too slow for {}",
+                    DOWNGRADE_AFTER, LADDER[current]
+                ),
+            ));
+        }
+
+        if current < ceiling && rate >= required_rate(current + 1) * HEADROOM {
+            self.slow_streak = 0;
+            self.fast_streak += 1;
+            if self.fast_streak < UPGRADE_AFTER {
+                return None;
+            }
+            self.fast_streak = 0;
+            self.cap = Some(current + 1).filter(|next| *next < ceiling);
+            return Some(self.adaptation(
+                configured,
+                current,
+                rate,
+                format!(
+                    "The last {} downloads were fast enough for {}",
+                    UPGRADE_AFTER,
+                    LADDER[current + 1]
+                ),
+            ));
+        }
+
+        self.slow_streak = 0;
+        self.fast_streak = 0;
+        None
+    }
+
+    fn adaptation(
+        &self,
+        configured: &str,
+        from: usize,
+        rate: f64,
+        reason: String,
+    ) -> QualityAdaptation {
+        let from = if from == rank(configured) {
+            configured.to_string()
+        } else {
+            LADDER[from].to_string()
+        };
+        QualityAdaptation {
+            from,
+            to: self.effective(configured),
+            throughput_kbps: (rate * 8.0 / 1000.0) as u64,
+            reason,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MB: u64 = 1_000_000;
+
+    /// Record a 1 MB download at `rate` bytes per second.
+    fn download(
+        adaptive: &mut AdaptiveQuality,
+        configured: &str,
+        rate: f64,
+    ) -> Option<QualityAdaptation> {
+        adaptive.record(configured, MB, Duration::from_secs_f64(MB as f64 / rate))
+    }
+
+    /// Too slow for LOSSLESS, fine for HIGH
+    const SLOW: f64 = 100_000.0;
+    /// Enough for LOSSLESS, not for HI_RES
+    const MEDIUM: f64 = 300_000.0;
+    /// Enough for everything
+    const FAST: f64 = 1_000_000.0;
+
+    #[test]
+    fn short_or_instant_downloads_are_ignored() {
+        let mut adaptive = AdaptiveQuality::default();
+        for _ in 0..10 {
+            assert!(adaptive
+                .record("LOSSLESS", MIN_SAMPLE_BYTES - 1, Duration::from_secs(60))
+                .is_none());
+            assert!(adaptive
+                .record("LOSSLESS", 10 * MB, Duration::ZERO)
+                .is_none());
+        }
+        assert_eq!(adaptive.effective("LOSSLESS"), "LOSSLESS");
+    }
+
+    #[test]
+    fn a_slow_streak_steps_down_one_level() {
+        let mut adaptive = AdaptiveQuality::default();
+        assert!(download(&mut adaptive, "LOSSLESS", SLOW).is_none());
+        assert!(download(&mut adaptive, "LOSSLESS", SLOW).is_none());
+
+        let change = download(&mut adaptive, "LOSSLESS", SLOW).unwrap();
+        assert_eq!(
+            (change.from.as_str(), change.to.as_str()),
+            ("LOSSLESS", "HIGH")
+        );
+        assert_eq!(change.throughput_kbps, 800);
+        assert_eq!(adaptive.effective("LOSSLESS"), "HIGH");
+    }
+
+    #[test]
+    fn a_download_that_keeps_up_breaks_the_streak() {
+        let mut adaptive = AdaptiveQuality::default();
+        download(&mut adaptive, "LOSSLESS", SLOW);
+        download(&mut adaptive, "LOSSLESS", SLOW);
+        download(&mut adaptive, "LOSSLESS", MEDIUM);
+        download(&mut adaptive, "LOSSLESS", SLOW);
+        assert!(download(&mut adaptive, "LOSSLESS", SLOW).is_none());
+        assert_eq!(adaptive.effective("LOSSLESS"), "LOSSLESS");
+    }
+
+    #[test]
+    fn quality_never_drops_below_the_lowest() {
+        let mut adaptive = AdaptiveQuality::default();
+        let changes: Vec<String> = (0..30)
+            .filter_map(|_| download(&mut adaptive, "HI_RES", 1_000.0))
+            .map(|change| change.to)
+            .collect();
+        assert_eq!(changes, ["LOSSLESS", "HIGH", "LOW"]);
+        assert_eq!(adaptive.effective("HI_RES"), "LOW");
+    }
+
+    #[test]
+    fn fast_downloads_probe_back_up_one_level_at_a_time() {
+        let mut adaptive = AdaptiveQuality::default();
+        for _ in 0..6 {
+            download(&mut adaptive, "HI_RES", SLOW);
+        }
+        assert_eq!(adaptive.effective("HI_RES"), "HIGH");
+
+        let changes: Vec<(String, String)> = (0..6)
+            .filter_map(|_| download(&mut adaptive, "HI_RES", FAST))
+            .map(|change| (change.from, change.to))
+            .collect();
+        assert_eq!(
+            changes,
+            [
+                ("HIGH".to_string(), "LOSSLESS".to_string()),
+                // Back at the configured quality, under its own name
+                ("LOSSLESS".to_string(), "HI_RES".to_string()),
+            ]
+        );
+        assert_eq!(adaptive.effective("HI_RES"), "HI_RES");
+        assert!(download(&mut adaptive, "HI_RES", FAST).is_none());
+    }
+
+    #[test]
+    fn upgrades_need_enough_for_the_next_level() {
+        let mut adaptive = AdaptiveQuality::default();
+        for _ in 0..3 {
+            download(&mut adaptive, "HI_RES", MEDIUM);
+        }
+        assert_eq!(adaptive.effective("HI_RES"), "LOSSLESS");
+
+        // Keeps up with LOSSLESS, not with HI_RES
+        for _ in 0..10 {
+            assert!(download(&mut adaptive, "HI_RES", MEDIUM).is_none());
+        }
+        assert_eq!(adaptive.effective("HI_RES"), "LOSSLESS");
+    }
+
+    #[test]
+    fn the_cap_never_raises_a_lower_configured_quality() {
+        let mut adaptive = AdaptiveQuality::default();
+        for _ in 0..3 {
+            download(&mut adaptive, "HI_RES", MEDIUM);
+        }
+        assert_eq!(adaptive.effective("HI_RES"), "LOSSLESS");
+        assert_eq!(adaptive.effective("HIGH"), "HIGH");
+
+        adaptive.reset();
+        assert_eq!(adaptive.effective("HI_RES"), "HI_RES");
+    }
+}
@@ -1,6 +1,8 @@
 use crate::api::client::TidalClient;
-use crate::api::models::{Album, Track};
-use crate::api::search::{get_first_relationship_id, parse_album, parse_tracks_from_included};
+use crate::api::models::{Album, NonTrackItem, Track};
+use crate::api::search::{
+    get_first_relationship_id, parse_album, parse_artwork_files, parse_items_from_included,
+};
 use crate::error::{AppError, AppResult};
 
 impl TidalClient {
@@ -63,14 +65,9 @@ impl TidalClient {
                         if item.get("type").and_then(|v| v.as_str()) == Some("artworks")
                             && item.get("id").and_then(|v| v.as_str()) == Some(&art_id)
                         {
-                            album.artwork_url = item
-                                .get("attributes")
-                                .and_then(|a| a.get("files"))
-                                .and_then(|v| v.as_array())
-                                .and_then(|arr| arr.last().or(arr.first()))
-                                .and_then(|f| f.get("href"))
-                                .and_then(|v| v.as_str())
-                                .map(|s| s.to_string());
+                            if let Some(attrs) = item.get("attributes") {
+                                album.set_artwork(parse_artwork_files(attrs));
+                            }
                             break;
                         }
                     }
@@ -82,6 +79,14 @@ impl TidalClient {
     }
 
     pub async fn get_album_tracks(&self, album_id: &str) -> AppResult<Vec<Track>> {
+        Ok(self.get_album_items(album_id).await?.0)
+    }
+
+    /// Album tracks plus entries that aren't tracks, such as videos.
+    pub async fn get_album_items(
+        &self,
+        album_id: &str,
+    ) -> AppResult<(Vec<Track>, Vec<NonTrackItem>)> {
         let config = self.config().read().await;
         let country = config.country_code.clone();
         drop(config);
@@ -101,8 +106,9 @@ impl TidalClient {
             .await?;
 
         let body: serde_json::Value = response.json().await?;
+        let data = body.get("data").and_then(|v| v.as_array());
         let included = body.get("included").and_then(|v| v.as_array());
 
-        Ok(parse_tracks_from_included(included))
+        Ok(parse_items_from_included(data, included))
     }
 }
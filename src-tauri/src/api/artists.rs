@@ -1,8 +1,20 @@
 use crate::api::client::TidalClient;
-use crate::api::models::{Album, Artist};
-use crate::api::search::{get_first_relationship_id, parse_album, parse_artist};
+use crate::api::models::{Album, Artist, ArtworkFile, Track, DEFAULT_ARTWORK_SIZE};
+use crate::api::search::{
+    artist_role, extract_artwork_href, get_first_relationship_id, parse_album, parse_artist,
+    parse_artwork_files, parse_tracks_from_included, ArtistRole,
+};
 use crate::error::{AppError, AppResult};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+
+/// An artist's albums split by how they're credited.
+pub struct ArtistAlbums {
+    /// Albums where the artist is the main artist
+    pub main: Vec<Album>,
+    /// Compilations and other artists' albums the artist is featured on,
+    /// without any that are also in `main`
+    pub appears_on: Vec<Album>,
+}
 
 impl TidalClient {
     pub async fn get_artist(&self, artist_id: &str) -> AppResult<Artist> {
@@ -44,12 +56,7 @@ impl TidalClient {
                         {
                             artist.picture_url = item
                                 .get("attributes")
-                                .and_then(|a| a.get("files"))
-                                .and_then(|v| v.as_array())
-                                .and_then(|arr| arr.last().or(arr.first()))
-                                .and_then(|f| f.get("href"))
-                                .and_then(|v| v.as_str())
-                                .map(|s| s.to_string());
+                                .and_then(|a| extract_artwork_href(a, DEFAULT_ARTWORK_SIZE));
                             break;
                         }
                     }
@@ -60,7 +67,19 @@ impl TidalClient {
         Ok(artist)
     }
 
+    /// The artist's own discography, without albums they only appear on.
     pub async fn get_artist_albums(&self, artist_id: &str) -> AppResult<Vec<Album>> {
+        Ok(self.get_artist_discography(artist_id).await?.main)
+    }
+
+    /// Compilations and other artists' albums the artist is featured on.
+    pub async fn get_artist_appears_on(&self, artist_id: &str) -> AppResult<Vec<Album>> {
+        Ok(self.get_artist_discography(artist_id).await?.appears_on)
+    }
+
+    /// Every album the artist is credited on, split into their own and the
+    /// ones they appear on. Albums without artist data count as their own.
+    pub async fn get_artist_discography(&self, artist_id: &str) -> AppResult<ArtistAlbums> {
         let config = self.config().read().await;
         let country = config.country_code.clone();
         drop(config);
@@ -81,7 +100,7 @@ impl TidalClient {
 
         // Build lookup maps
         let mut artist_map: HashMap<String, String> = HashMap::new();
-        let mut artwork_map: HashMap<String, String> = HashMap::new();
+        let mut artwork_map: HashMap<String, Vec<ArtworkFile>> = HashMap::new();
 
         if let Some(items) = included {
             for item in items {
@@ -102,15 +121,8 @@ impl TidalClient {
                         }
                     }
                     "artworks" => {
-                        if let Some(href) = item
-                            .get("attributes")
-                            .and_then(|a| a.get("files"))
-                            .and_then(|v| v.as_array())
-                            .and_then(|arr| arr.last().or(arr.first()))
-                            .and_then(|f| f.get("href"))
-                            .and_then(|v| v.as_str())
-                        {
-                            artwork_map.insert(rid, href.to_string());
+                        if let Some(attrs) = item.get("attributes") {
+                            artwork_map.insert(rid, parse_artwork_files(attrs));
                         }
                     }
                     _ => {}
@@ -118,7 +130,11 @@ impl TidalClient {
             }
         }
 
-        let mut albums = Vec::new();
+        let mut discography = ArtistAlbums {
+            main: Vec::new(),
+            appears_on: Vec::new(),
+        };
+        let mut seen = HashSet::new();
         if let Some(items) = included {
             for item in items {
                 let resource_type = item.get("type").and_then(|v| v.as_str()).unwrap_or("");
@@ -126,6 +142,9 @@ impl TidalClient {
                     let id = item.get("id").and_then(|v| v.as_str()).unwrap_or("");
                     let attrs = item.get("attributes").cloned().unwrap_or_default();
                     let rels = item.get("relationships");
+                    if !seen.insert(id) {
+                        continue;
+                    }
                     if let Some(mut album) = parse_album(id, &attrs) {
                         // Resolve artist
                         if let Some(aid) = get_first_relationship_id(rels, "artists") {
@@ -136,16 +155,101 @@ impl TidalClient {
                         }
                         // Resolve cover art
                         if album.artwork_url.is_none() {
-                            if let Some(art_id) = get_first_relationship_id(rels, "coverArt") {
-                                album.artwork_url = artwork_map.get(&art_id).cloned();
+                            if let Some(files) = get_first_relationship_id(rels, "coverArt")
+                                .and_then(|art_id| artwork_map.get(&art_id))
+                            {
+                                album.set_artwork(files.clone());
                             }
                         }
-                        albums.push(album);
+                        match artist_role(rels, artist_id) {
+                            Some(ArtistRole::Featured) => discography.appears_on.push(album),
+                            Some(ArtistRole::Main) | None => discography.main.push(album),
+                        }
                     }
                 }
             }
         }
 
-        Ok(albums)
+        Ok(discography)
+    }
+
+    pub async fn get_artist_top_tracks(&self, artist_id: &str) -> AppResult<Vec<Track>> {
+        let config = self.config().read().await;
+        let country = config.country_code.clone();
+        drop(config);
+
+        let path = format!("/artists/{}/relationships/tracks", artist_id);
+        let response = self
+            .get_with_query(
+                &path,
+                &[
+                    ("countryCode", country.as_str()),
+                    ("collapseBy", "FINGERPRINT"),
+                    (
+                        "include",
+                        "tracks,tracks.artists,tracks.albums,tracks.albums.coverArt",
+                    ),
+                ],
+            )
+            .await?;
+
+        let body: serde_json::Value = response.json().await?;
+        let included = body.get("included").and_then(|v| v.as_array());
+
+        Ok(parse_tracks_from_included(included))
+    }
+
+    pub async fn get_similar_artists(&self, artist_id: &str) -> AppResult<Vec<Artist>> {
+        let config = self.config().read().await;
+        let country = config.country_code.clone();
+        drop(config);
+
+        let path = format!("/artists/{}/relationships/similarArtists", artist_id);
+        let response = self
+            .get_with_query(
+                &path,
+                &[
+                    ("countryCode", country.as_str()),
+                    ("include", "similarArtists,similarArtists.profileArt"),
+                ],
+            )
+            .await?;
+
+        let body: serde_json::Value = response.json().await?;
+        let Some(items) = body.get("included").and_then(|v| v.as_array()) else {
+            return Ok(Vec::new());
+        };
+
+        let artwork_map: HashMap<&str, String> = items
+            .iter()
+            .filter(|item| item.get("type").and_then(|v| v.as_str()) == Some("artworks"))
+            .filter_map(|item| {
+                let id = item.get("id").and_then(|v| v.as_str())?;
+                let href = extract_artwork_href(item.get("attributes")?, DEFAULT_ARTWORK_SIZE)?;
+                Some((id, href))
+            })
+            .collect();
+
+        let mut artists = Vec::new();
+        for item in items {
+            if item.get("type").and_then(|v| v.as_str()) != Some("artists") {
+                continue;
+            }
+            let id = item.get("id").and_then(|v| v.as_str()).unwrap_or("");
+            if id == artist_id {
+                continue;
+            }
+            let attrs = item.get("attributes").cloned().unwrap_or_default();
+            if let Some(mut artist) = parse_artist(id, &attrs) {
+                if artist.picture_url.is_none() {
+                    artist.picture_url =
+                        get_first_relationship_id(item.get("relationships"), "profileArt")
+                            .and_then(|art_id| artwork_map.get(art_id.as_str()).cloned());
+                }
+                artists.push(artist);
+            }
+        }
+
+        Ok(artists)
     }
 }
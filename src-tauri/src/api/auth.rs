@@ -9,7 +9,7 @@ use std::sync::Arc;
 use tokio::sync::RwLock;
 
 const AUTH_URL: &str = "https://login.tidal.com/authorize";
-const TOKEN_URL: &str = "https://auth.tidal.com/v1/oauth2/token";
+pub(crate) const TOKEN_URL: &str = "https://auth.tidal.com/v1/oauth2/token";
 const REDIRECT_URI: &str = "tauritidal://auth/callback";
 
 pub struct PkceChallenge {
@@ -97,6 +97,7 @@ pub async fn exchange_code(
 /// Refresh an expired user token using the refresh_token grant.
 pub async fn refresh_user_token(
     http: &reqwest::Client,
+    token_url: &str,
     client_id: &str,
     refresh_token: &str,
 ) -> AppResult<TokenResponse> {
@@ -106,12 +107,16 @@ pub async fn refresh_user_token(
         ("client_id", client_id),
     ];
 
-    let response = http.post(TOKEN_URL).form(&params).send().await?;
+    let response = http.post(token_url).form(&params).send().await?;
 
-    if !response.status().is_success() {
+    let status = response.status();
+    if !status.is_success() {
         let body = response.text().await.unwrap_or_default();
+        if refresh_token_rejected(&body) {
+            return Err(AppError::ReauthRequired);
+        }
         return Err(AppError::TidalApi {
-            status: 401,
+            status: status.as_u16(),
             message: format!("Token refresh failed: {}", body),
         });
     }
@@ -120,6 +125,15 @@ pub async fn refresh_user_token(
     Ok(token)
 }
 
+/// Whether a failed refresh response says the refresh token itself is no
+/// good (revoked, expired or already used), so only logging in again helps.
+pub fn refresh_token_rejected(body: &str) -> bool {
+    serde_json::from_str::<serde_json::Value>(body)
+        .ok()
+        .and_then(|body| body.get("error")?.as_str().map(|e| e == "invalid_grant"))
+        .unwrap_or(false)
+}
+
 const DEVICE_AUTH_URL: &str = "https://auth.tidal.com/v1/oauth2/device_authorization";
 
 /// Step 1 of device code flow: request a device code + user code.
@@ -188,6 +202,7 @@ pub async fn poll_device_token(
 
 pub async fn client_credentials_token(
     http: &reqwest::Client,
+    token_url: &str,
     client_id: &str,
     client_secret: &str,
 ) -> AppResult<TokenResponse> {
@@ -199,7 +214,7 @@ pub async fn client_credentials_token(
     let params = [("grant_type", "client_credentials")];
 
     let response = http
-        .post(TOKEN_URL)
+        .post(token_url)
         .header("Authorization", format!("Basic {}", b64_creds))
         .form(&params)
         .send()
@@ -216,3 +231,61 @@ pub async fn client_credentials_token(
     let token: TokenResponse = response.json().await?;
     Ok(token)
 }
+
+/// Acquire a client-credentials token with the stored client id/secret and
+/// save it to the config. These tokens give catalog-only access.
+pub async fn acquire_client_credentials(
+    http: &reqwest::Client,
+    token_url: &str,
+    config: &RwLock<AppConfig>,
+) -> AppResult<()> {
+    let cfg = config.read().await;
+    let client_id = cfg.client_id.clone();
+    let client_secret = cfg.client_secret.clone();
+    drop(cfg);
+
+    if client_id.is_empty() || client_secret.is_empty() {
+        return Err(AppError::AuthRequired);
+    }
+
+    let token = client_credentials_token(http, token_url, &client_id, &client_secret).await?;
+
+    let mut cfg = config.write().await;
+    cfg.access_token = Some(token.access_token);
+    cfg.expires_at = Some(chrono::Utc::now() + chrono::Duration::seconds(token.expires_in as i64));
+    cfg.save()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_invalid_grant_rejects_the_refresh_token() {
+        assert!(refresh_token_rejected(
+            r#"{"error":"invalid_grant","error_description":"Token has been revoked"}"#
+        ));
+
+        // The token may still be good; these are worth retrying or reporting
+        for body in [
+            r#"{"error":"invalid_client"}"#,
+            r#"{"error":"server_error","error_description":"invalid_grant"}"#,
+            r#"{"error":400}"#,
+            r#"{"status":400,"userMessage":"invalid_grant"}"#,
+        ] {
+            assert!(!refresh_token_rejected(body), "{}", body);
+        }
+    }
+
+    #[test]
+    fn non_json_refresh_failures_are_not_rejections() {
+        for body in [
+            "",
+            "invalid_grant",
+            "<html><body>502 Bad Gateway</body></html>",
+        ] {
+            assert!(!refresh_token_rejected(body), "{:?}", body);
+        }
+    }
+}
@@ -1,15 +1,78 @@
+use crate::api::adaptive::{AdaptiveQuality, QualityAdaptation};
+use crate::api::health::{
+    HealthTracker, NetworkHealth, NetworkStatus, RegionBlock, RequestOutcome,
+};
+use crate::api::models::PublicProfile;
 use crate::config::AppConfig;
 use crate::error::{AppError, AppResult};
-use reqwest::header::{HeaderMap, HeaderValue, ACCEPT, AUTHORIZATION, CONTENT_TYPE};
+use reqwest::header::{HeaderMap, HeaderValue, ACCEPT, AUTHORIZATION, CONTENT_TYPE, RANGE};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use std::time::{Duration, Instant};
+use tokio::sync::{watch, Mutex, RwLock};
 
 const BASE_URL: &str = "https://openapi.tidal.com/v2";
 const JSONAPI_CONTENT_TYPE: &str = "application/vnd.api+json";
 
+/// How long API calls wait for the startup token refresh before sending anyway.
+const TOKEN_READY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Tries at the startup token refresh while it fails for network reasons,
+/// e.g. right after waking up before the Wi-Fi is back.
+const STARTUP_REFRESH_ATTEMPTS: u32 = 6;
+
+/// Wait before the first retry of the startup refresh; doubles after each.
+const STARTUP_REFRESH_BACKOFF: Duration = Duration::from_secs(2);
+
+/// Region refusals in a row after which the whole catalog counts as blocked.
+/// Single tracks can be region locked, so a few are expected.
+const REGION_BLOCK_AFTER: u32 = 5;
+
+/// How long catalog reads fail fast while blocked before one is let through
+/// to check again.
+const REGION_BLOCK_RETRY: Duration = Duration::from_secs(10 * 60);
+
+/// Whether a request was refused for legal or region reasons.
+fn is_region_refusal(error: &AppError) -> bool {
+    matches!(error.status(), Some(403 | 451)) && error.code() == Some("region_blocked")
+}
+
+/// The access token a request is about to be sent with.
+fn bearer_token(headers: &HeaderMap) -> Option<String> {
+    let value = headers.get(AUTHORIZATION)?.to_str().ok()?;
+    value.strip_prefix("Bearer ").map(str::to_string)
+}
+
 pub struct TidalClient {
     http: reqwest::Client,
+    /// API and token endpoints; only tests point them elsewhere
+    base_url: String,
+    token_url: String,
     config: Arc<RwLock<AppConfig>>,
+    /// Serializes every token refresh (startup, reactive, client credentials) so
+    /// a rotated refresh token is never used twice
+    token_lock: Mutex<()>,
+    /// Flips to true once the startup refresh has finished, successfully or not
+    token_ready: watch::Sender<bool>,
+    /// Search tracks shipped with an unknown artist after every enrichment step
+    unresolved_artists: AtomicU64,
+    /// Rolling latency/error stats over recent API requests
+    health: std::sync::Mutex<HealthTracker>,
+    /// Current health classification; changes are forwarded to the UI
+    network_health: watch::Sender<NetworkHealth>,
+    /// Steps manifest quality down while downloads are too slow for it
+    adaptive_quality: std::sync::Mutex<AdaptiveQuality>,
+    /// Latest adaptive quality change; forwarded to the UI
+    quality_adaptation: watch::Sender<Option<QualityAdaptation>>,
+    /// Other users' profiles looked up this session, keyed by user id
+    public_profiles: std::sync::Mutex<HashMap<String, PublicProfile>>,
+    /// Responses in a row refused with a legal or region block
+    region_refusals: AtomicU32,
+    /// Set while the catalog refuses the user's country; forwarded to the UI
+    region_block: watch::Sender<Option<RegionBlock>>,
+    /// Set once the saved login is rejected this session; forwarded to the UI
+    reauth_required: watch::Sender<bool>,
 }
 
 impl TidalClient {
@@ -18,7 +81,401 @@ impl TidalClient {
             .user_agent("TauriTidal/0.1.0")
             .build()?;
 
-        Ok(Self { http, config })
+        Ok(Self {
+            http,
+            base_url: BASE_URL.to_string(),
+            token_url: crate::api::auth::TOKEN_URL.to_string(),
+            config,
+            token_lock: Mutex::new(()),
+            token_ready: watch::Sender::new(false),
+            unresolved_artists: AtomicU64::new(0),
+            health: std::sync::Mutex::new(HealthTracker::default()),
+            network_health: watch::Sender::new(NetworkHealth::Good),
+            adaptive_quality: std::sync::Mutex::new(AdaptiveQuality::default()),
+            quality_adaptation: watch::Sender::new(None),
+            public_profiles: std::sync::Mutex::new(HashMap::new()),
+            region_refusals: AtomicU32::new(0),
+            region_block: watch::Sender::new(None),
+            reauth_required: watch::Sender::new(false),
+        })
+    }
+
+    /// A client talking to other endpoints, e.g. a mock server.
+    #[cfg(test)]
+    pub fn with_endpoints(
+        config: Arc<RwLock<AppConfig>>,
+        base_url: &str,
+        token_url: &str,
+    ) -> AppResult<Self> {
+        let mut client = Self::new(config)?;
+        client.base_url = base_url.to_string();
+        client.token_url = token_url.to_string();
+        Ok(client)
+    }
+
+    /// Let requests go out without waiting for a startup refresh.
+    #[cfg(test)]
+    pub fn skip_startup_refresh(&self) {
+        self.token_ready.send_replace(true);
+    }
+
+    pub fn network_status(&self) -> NetworkStatus {
+        self.health.lock().unwrap().status()
+    }
+
+    /// Whether recent requests couldn't reach the API. Background work
+    /// (preloads, cache refreshes) waits until this clears.
+    pub fn is_offline(&self) -> bool {
+        *self.network_health.borrow() == NetworkHealth::Offline
+    }
+
+    pub fn subscribe_network_health(&self) -> watch::Receiver<NetworkHealth> {
+        self.network_health.subscribe()
+    }
+
+    pub fn region_block(&self) -> Option<RegionBlock> {
+        self.region_block.borrow().clone()
+    }
+
+    pub fn subscribe_region_block(&self) -> watch::Receiver<Option<RegionBlock>> {
+        self.region_block.subscribe()
+    }
+
+    pub fn subscribe_reauth_required(&self) -> watch::Receiver<bool> {
+        self.reauth_required.subscribe()
+    }
+
+    /// Forget that the saved login was rejected, e.g. once it was wiped.
+    pub fn clear_reauth_required(&self) {
+        self.reauth_required.send_replace(false);
+    }
+
+    /// Leave region blocked mode, letting catalog reads through again.
+    pub fn clear_region_block(&self) {
+        self.region_refusals.store(0, Ordering::Relaxed);
+        self.region_block.send_if_modified(|block| {
+            let cleared = block.take().is_some();
+            if cleared {
+                log::info!("Region block lifted");
+            }
+            cleared
+        });
+    }
+
+    /// Fail fast while the catalog is region blocked. The block lifts when
+    /// the country changes; after `REGION_BLOCK_RETRY` one request goes
+    /// through and its response decides.
+    async fn check_region_block(&self) -> AppResult<()> {
+        let Some(block) = self.region_block() else {
+            return Ok(());
+        };
+        let country = self.config.read().await.country_code.clone();
+        if country != block.country {
+            log::info!("Country changed to {}", country);
+            self.clear_region_block();
+            return Ok(());
+        }
+        if Instant::now() < block.retry_at {
+            return Err(AppError::RegionBlocked { country });
+        }
+        // Not a change the UI needs to hear about
+        self.region_block.send_if_modified(|block| {
+            if let Some(block) = block {
+                block.retry_at = Instant::now() + REGION_BLOCK_RETRY;
+            }
+            false
+        });
+        Ok(())
+    }
+
+    /// Count a response refused for legal or region reasons, entering region
+    /// blocked mode after `REGION_BLOCK_AFTER` in a row.
+    async fn note_region_refusal(&self) {
+        let refusals = self.region_refusals.fetch_add(1, Ordering::Relaxed) + 1;
+        if refusals < REGION_BLOCK_AFTER || self.region_block.borrow().is_some() {
+            return;
+        }
+        let country = self.config.read().await.country_code.clone();
+        log::warn!(
+            "{} catalog requests in a row refused in {}, pausing catalog requests",
+            refusals,
+            country
+        );
+        self.region_block.send_replace(Some(RegionBlock {
+            country,
+            since: chrono::Utc::now(),
+            retry_at: Instant::now() + REGION_BLOCK_RETRY,
+        }));
+    }
+
+    /// Quality to fetch manifests at: the configured one, or lower while
+    /// adaptive quality has stepped down.
+    pub(crate) fn manifest_quality(&self, config: &AppConfig) -> String {
+        if !config.adaptive_quality {
+            return config.audio_quality.clone();
+        }
+        self.adaptive_quality
+            .lock()
+            .unwrap()
+            .effective(&config.audio_quality)
+    }
+
+    /// Record a finished audio download's throughput for adaptive quality.
+    pub async fn record_download(&self, bytes: u64, elapsed: Duration) {
+        let config = self.config.read().await;
+        if !config.adaptive_quality {
+            return;
+        }
+        let adaptation =
+            self.adaptive_quality
+                .lock()
+                .unwrap()
+                .record(&config.audio_quality, bytes, elapsed);
+        drop(config);
+
+        if let Some(adaptation) = adaptation {
+            log::info!(
+                "Adaptive quality: {} -> {} at {} kbps",
+                adaptation.from,
+                adaptation.to,
+                adaptation.throughput_kbps
+            );
+            self.quality_adaptation.send_replace(Some(adaptation));
+        }
+    }
+
+    /// Forget throughput history, going back to the configured quality.
+    pub fn reset_adaptive_quality(&self) {
+        self.adaptive_quality.lock().unwrap().reset();
+    }
+
+    pub fn subscribe_quality_adaptation(&self) -> watch::Receiver<Option<QualityAdaptation>> {
+        self.quality_adaptation.subscribe()
+    }
+
+    /// Send a request that only checks the API is reachable again. Any HTTP
+    /// response counts, so the status code is ignored.
+    pub async fn probe_connectivity(&self) {
+        let _ = self.send(self.http.head(&self.base_url)).await;
+    }
+
+    /// Send an API request, recording its latency and outcome in the health stats.
+    async fn send(&self, request: reqwest::RequestBuilder) -> AppResult<reqwest::Response> {
+        let started = Instant::now();
+        let result = request.send().await;
+        let outcome = match &result {
+            Ok(response) => RequestOutcome::Response {
+                latency: started.elapsed(),
+                ok: !response.status().is_server_error()
+                    && response.status() != reqwest::StatusCode::TOO_MANY_REQUESTS,
+            },
+            Err(_) => RequestOutcome::Failed,
+        };
+
+        let health = {
+            let mut tracker = self.health.lock().unwrap();
+            tracker.record(outcome);
+            tracker.status().health
+        };
+        self.network_health.send_if_modified(|current| {
+            if *current == health {
+                return false;
+            }
+            log::info!("Network health: {:?} -> {:?}", current, health);
+            *current = health;
+            true
+        });
+
+        Ok(result?)
+    }
+
+    /// Count tracks whose artist couldn't be resolved, returning the session total.
+    pub fn note_unresolved_artists(&self, count: usize) -> u64 {
+        self.unresolved_artists
+            .fetch_add(count as u64, Ordering::Relaxed)
+            + count as u64
+    }
+
+    pub fn unresolved_artist_count(&self) -> u64 {
+        self.unresolved_artists.load(Ordering::Relaxed)
+    }
+
+    pub fn cached_public_profile(&self, user_id: &str) -> Option<PublicProfile> {
+        self.public_profiles.lock().unwrap().get(user_id).cloned()
+    }
+
+    pub fn cached_public_profile_count(&self) -> usize {
+        self.public_profiles.lock().unwrap().len()
+    }
+
+    pub fn clear_public_profiles(&self) {
+        self.public_profiles.lock().unwrap().clear();
+    }
+
+    pub fn cache_public_profile(&self, profile: PublicProfile) {
+        self.public_profiles
+            .lock()
+            .unwrap()
+            .insert(profile.id.clone(), profile);
+    }
+
+    /// Wait for the startup token refresh so early requests don't go out with
+    /// the expired token. Gives up after `TOKEN_READY_TIMEOUT`.
+    async fn wait_token_ready(&self) {
+        let mut ready = self.token_ready.subscribe();
+        if tokio::time::timeout(TOKEN_READY_TIMEOUT, ready.wait_for(|r| *r))
+            .await
+            .is_err()
+        {
+            log::warn!("Startup token refresh still pending, sending request anyway");
+        }
+    }
+
+    /// Refresh the user token or acquire client credentials on startup,
+    /// retrying with backoff while the network is down. Requests waiting in
+    /// `wait_token_ready` are released after the first try.
+    pub async fn startup_token_refresh(&self) {
+        let stale = self.config.read().await.access_token.clone();
+        let mut backoff = STARTUP_REFRESH_BACKOFF;
+        for attempt in 1..=STARTUP_REFRESH_ATTEMPTS {
+            let guard = self.token_lock.lock().await;
+            // A reactive refresh may have succeeded since the last try
+            let retry = if attempt > 1 && self.config.read().await.access_token != stale {
+                false
+            } else {
+                self.startup_token_refresh_locked().await
+            };
+            drop(guard);
+            self.token_ready.send_replace(true);
+
+            if !retry {
+                return;
+            }
+            if attempt < STARTUP_REFRESH_ATTEMPTS {
+                log::info!(
+                    "Retrying startup token refresh in {}s ({}/{})",
+                    backoff.as_secs(),
+                    attempt,
+                    STARTUP_REFRESH_ATTEMPTS
+                );
+                tokio::time::sleep(backoff).await;
+                backoff *= 2;
+            }
+        }
+        log::warn!("Startup token refresh kept failing; the next request will try again");
+    }
+
+    /// One try at the startup refresh. Returns true when it failed in a way
+    /// worth retrying, like a timeout or a 5xx.
+    async fn startup_token_refresh_locked(&self) -> bool {
+        let config = self.config.read().await;
+        let client_id = config.client_id.clone();
+        let client_secret = config.client_secret.clone();
+        let refresh_token = config.refresh_token.clone();
+        let has_user_id = config.user_id.is_some();
+        drop(config);
+
+        if client_id.is_empty() {
+            return false;
+        }
+
+        // If user was previously logged in (has refresh_token), ALWAYS refresh.
+        // We cannot tell if the saved token is a user PKCE token or a client_credentials
+        // token just by looking at it. A previous bug could have overwritten the user
+        // token with a client_credentials one that appears "valid" but only gives
+        // 30-second previews. Refreshing always gives us a proper user token.
+        if let Some(ref rt) = refresh_token {
+            if has_user_id {
+                log::info!("Refreshing user PKCE token (always refresh for logged-in users)...");
+                match crate::api::auth::refresh_user_token(
+                    &self.http,
+                    &self.token_url,
+                    &client_id,
+                    rt,
+                )
+                .await
+                {
+                    Ok(token) => {
+                        let mut config = self.config.write().await;
+                        config.access_token = Some(token.access_token);
+                        config.expires_at = Some(
+                            chrono::Utc::now() + chrono::Duration::seconds(token.expires_in as i64),
+                        );
+                        // Update refresh token if a new one was provided
+                        if let Some(new_rt) = token.refresh_token {
+                            config.refresh_token = Some(new_rt);
+                        }
+                        if let Err(e) = config.save() {
+                            log::error!("Failed to save refreshed token: {}", e);
+                        } else {
+                            log::info!("User token refreshed successfully");
+                        }
+                    }
+                    Err(AppError::ReauthRequired) => {
+                        log::warn!("Refresh token was rejected, user needs to log in again");
+                        self.require_reauth(&mut *self.config.write().await);
+                    }
+                    // Keep the user logged in: the token is likely fine and
+                    // the network just isn't back yet
+                    Err(e) if e.is_retryable() => {
+                        log::warn!("Token refresh failed: {}", e);
+                        return true;
+                    }
+                    Err(e) => {
+                        log::warn!("Token refresh failed: {}. User will need to re-login.", e);
+                    }
+                }
+                // Do NOT fall through to client_credentials when a user was
+                // previously logged in. Client credentials tokens only give
+                // 30-second previews, silently degrading the experience.
+                return false;
+            }
+        }
+
+        // Client credentials require a client_secret and only provide
+        // catalog-only (30s preview) access. Skip if no secret or user was logged in.
+        if has_user_id || client_secret.is_empty() {
+            log::info!("Skipping client credentials (no secret or user was previously logged in)");
+            return false;
+        }
+
+        // Check if we already have a valid client credentials token
+        let config = self.config.read().await;
+        let needs_token = config.access_token.is_none() || config.is_token_expired();
+        drop(config);
+
+        if !needs_token {
+            log::info!("Client credentials token still valid, skipping");
+            return false;
+        }
+
+        log::info!("Acquiring client credentials token (no user login history)...");
+        match crate::api::auth::acquire_client_credentials(
+            &self.http,
+            &self.token_url,
+            &self.config,
+        )
+        .await
+        {
+            Ok(()) => {
+                log::info!("Client credentials token acquired (catalog-only access)");
+                false
+            }
+            Err(e) => {
+                log::error!("Failed to acquire client credentials: {}", e);
+                e.is_retryable()
+            }
+        }
+    }
+
+    /// The saved login was rejected: drop its tokens, save, and tell the UI
+    /// the user has to log in again.
+    fn require_reauth(&self, config: &mut AppConfig) {
+        config.require_reauth();
+        if let Err(e) = config.save() {
+            log::error!("Failed to save cleared tokens: {}", e);
+        }
+        self.reauth_required.send_replace(true);
     }
 
     pub fn config(&self) -> &Arc<RwLock<AppConfig>> {
@@ -26,6 +483,7 @@ impl TidalClient {
     }
 
     async fn auth_headers(&self) -> AppResult<HeaderMap> {
+        self.wait_token_ready().await;
         let config = self.config.read().await;
         let mut headers = HeaderMap::new();
         headers.insert(ACCEPT, HeaderValue::from_static(JSONAPI_CONTENT_TYPE));
@@ -59,19 +517,23 @@ impl TidalClient {
     }
 
     pub async fn get(&self, path: &str) -> AppResult<reqwest::Response> {
-        let url = format!("{}{}", BASE_URL, path);
+        self.check_region_block().await?;
+        let url = format!("{}{}", self.base_url, path);
         let headers = self.auth_headers().await?;
+        let sent = bearer_token(&headers);
 
-        let response = self.http.get(&url).headers(headers).send().await?;
+        let request = self.http.get(&url).headers(headers);
+        let response = self.send(request).await?;
 
         if response.status() == reqwest::StatusCode::UNAUTHORIZED {
             // Try refreshing the token
-            self.refresh_token().await?;
+            self.refresh_token(sent.as_deref()).await?;
             let headers = self.auth_headers().await?;
-            let response = self.http.get(&url).headers(headers).send().await?;
-            self.check_response(response).await
+            let request = self.http.get(&url).headers(headers);
+            let response = self.send(request).await?;
+            self.check_catalog_response(response).await
         } else {
-            self.check_response(response).await
+            self.check_catalog_response(response).await
         }
     }
 
@@ -80,94 +542,112 @@ impl TidalClient {
         path: &str,
         query: &[(&str, &str)],
     ) -> AppResult<reqwest::Response> {
-        let url = format!("{}{}", BASE_URL, path);
+        self.check_region_block().await?;
+        let url = format!("{}{}", self.base_url, path);
         let headers = self.auth_headers().await?;
+        let sent = bearer_token(&headers);
 
-        let response = self
-            .http
-            .get(&url)
-            .headers(headers)
-            .query(query)
-            .send()
-            .await?;
+        let request = self.http.get(&url).headers(headers).query(query);
+        let response = self.send(request).await?;
 
         if response.status() == reqwest::StatusCode::UNAUTHORIZED {
-            self.refresh_token().await?;
+            self.refresh_token(sent.as_deref()).await?;
             let headers = self.auth_headers().await?;
-            let response = self
-                .http
-                .get(&url)
-                .headers(headers)
-                .query(query)
-                .send()
-                .await?;
+            let request = self.http.get(&url).headers(headers).query(query);
+            let response = self.send(request).await?;
+            self.check_catalog_response(response).await
+        } else {
+            self.check_catalog_response(response).await
+        }
+    }
+
+    pub async fn post(&self, path: &str, body: &serde_json::Value) -> AppResult<reqwest::Response> {
+        let url = format!("{}{}", self.base_url, path);
+        let mut headers = self.auth_headers().await?;
+        let sent = bearer_token(&headers);
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static(JSONAPI_CONTENT_TYPE));
+
+        let request = self.http.post(&url).headers(headers).json(body);
+        let response = self.send(request).await?;
+
+        if response.status() == reqwest::StatusCode::UNAUTHORIZED {
+            self.refresh_token(sent.as_deref()).await?;
+            let mut headers = self.auth_headers().await?;
+            headers.insert(CONTENT_TYPE, HeaderValue::from_static(JSONAPI_CONTENT_TYPE));
+            let request = self.http.post(&url).headers(headers).json(body);
+            let response = self.send(request).await?;
             self.check_response(response).await
         } else {
             self.check_response(response).await
         }
     }
 
-    pub async fn post(&self, path: &str, body: &serde_json::Value) -> AppResult<reqwest::Response> {
-        let url = format!("{}{}", BASE_URL, path);
+    pub async fn post_with_query(
+        &self,
+        path: &str,
+        query: &[(&str, &str)],
+        body: &serde_json::Value,
+    ) -> AppResult<reqwest::Response> {
+        let url = format!("{}{}", self.base_url, path);
         let mut headers = self.auth_headers().await?;
+        let sent = bearer_token(&headers);
         headers.insert(CONTENT_TYPE, HeaderValue::from_static(JSONAPI_CONTENT_TYPE));
 
-        let response = self
+        let request = self
             .http
             .post(&url)
             .headers(headers)
-            .json(body)
-            .send()
-            .await?;
+            .query(query)
+            .json(body);
+        let response = self.send(request).await?;
 
         if response.status() == reqwest::StatusCode::UNAUTHORIZED {
-            self.refresh_token().await?;
+            self.refresh_token(sent.as_deref()).await?;
             let mut headers = self.auth_headers().await?;
             headers.insert(CONTENT_TYPE, HeaderValue::from_static(JSONAPI_CONTENT_TYPE));
-            let response = self
+            let request = self
                 .http
                 .post(&url)
                 .headers(headers)
-                .json(body)
-                .send()
-                .await?;
+                .query(query)
+                .json(body);
+            let response = self.send(request).await?;
             self.check_response(response).await
         } else {
             self.check_response(response).await
         }
     }
 
-    pub async fn post_with_query(
+    pub async fn patch_with_query(
         &self,
         path: &str,
         query: &[(&str, &str)],
         body: &serde_json::Value,
     ) -> AppResult<reqwest::Response> {
-        let url = format!("{}{}", BASE_URL, path);
+        let url = format!("{}{}", self.base_url, path);
         let mut headers = self.auth_headers().await?;
+        let sent = bearer_token(&headers);
         headers.insert(CONTENT_TYPE, HeaderValue::from_static(JSONAPI_CONTENT_TYPE));
 
-        let response = self
+        let request = self
             .http
-            .post(&url)
+            .patch(&url)
             .headers(headers)
             .query(query)
-            .json(body)
-            .send()
-            .await?;
+            .json(body);
+        let response = self.send(request).await?;
 
         if response.status() == reqwest::StatusCode::UNAUTHORIZED {
-            self.refresh_token().await?;
+            self.refresh_token(sent.as_deref()).await?;
             let mut headers = self.auth_headers().await?;
             headers.insert(CONTENT_TYPE, HeaderValue::from_static(JSONAPI_CONTENT_TYPE));
-            let response = self
+            let request = self
                 .http
-                .post(&url)
+                .patch(&url)
                 .headers(headers)
                 .query(query)
-                .json(body)
-                .send()
-                .await?;
+                .json(body);
+            let response = self.send(request).await?;
             self.check_response(response).await
         } else {
             self.check_response(response).await
@@ -175,15 +655,18 @@ impl TidalClient {
     }
 
     pub async fn delete(&self, path: &str) -> AppResult<reqwest::Response> {
-        let url = format!("{}{}", BASE_URL, path);
+        let url = format!("{}{}", self.base_url, path);
         let headers = self.auth_headers().await?;
+        let sent = bearer_token(&headers);
 
-        let response = self.http.delete(&url).headers(headers).send().await?;
+        let request = self.http.delete(&url).headers(headers);
+        let response = self.send(request).await?;
 
         if response.status() == reqwest::StatusCode::UNAUTHORIZED {
-            self.refresh_token().await?;
+            self.refresh_token(sent.as_deref()).await?;
             let headers = self.auth_headers().await?;
-            let response = self.http.delete(&url).headers(headers).send().await?;
+            let request = self.http.delete(&url).headers(headers);
+            let response = self.send(request).await?;
             self.check_response(response).await
         } else {
             self.check_response(response).await
@@ -195,38 +678,38 @@ impl TidalClient {
         path: &str,
         body: &serde_json::Value,
     ) -> AppResult<reqwest::Response> {
-        let url = format!("{}{}", BASE_URL, path);
+        let url = format!("{}{}", self.base_url, path);
         let mut headers = self.auth_headers().await?;
+        let sent = bearer_token(&headers);
         headers.insert(CONTENT_TYPE, HeaderValue::from_static(JSONAPI_CONTENT_TYPE));
 
-        let response = self
-            .http
-            .delete(&url)
-            .headers(headers)
-            .json(body)
-            .send()
-            .await?;
+        let request = self.http.delete(&url).headers(headers).json(body);
+        let response = self.send(request).await?;
 
         if response.status() == reqwest::StatusCode::UNAUTHORIZED {
-            self.refresh_token().await?;
+            self.refresh_token(sent.as_deref()).await?;
             let mut headers = self.auth_headers().await?;
             headers.insert(CONTENT_TYPE, HeaderValue::from_static(JSONAPI_CONTENT_TYPE));
-            let response = self
-                .http
-                .delete(&url)
-                .headers(headers)
-                .json(body)
-                .send()
-                .await?;
+            let request = self.http.delete(&url).headers(headers).json(body);
+            let response = self.send(request).await?;
             self.check_response(response).await
         } else {
             self.check_response(response).await
         }
     }
 
-    pub async fn get_stream_url(&self, url: &str) -> AppResult<reqwest::Response> {
+    /// Fetch a stream URL, optionally only its first `max_bytes` bytes.
+    pub async fn get_stream_url(
+        &self,
+        url: &str,
+        max_bytes: Option<u64>,
+    ) -> AppResult<reqwest::Response> {
         let headers = self.client_credentials_headers().await?;
-        let response = self.http.get(url).headers(headers).send().await?;
+        let mut request = self.http.get(url).headers(headers);
+        if let Some(max_bytes) = max_bytes {
+            request = request.header(RANGE, format!("bytes=0-{}", max_bytes.saturating_sub(1)));
+        }
+        let response = request.send().await?;
         self.check_response(response).await
     }
 
@@ -250,8 +733,55 @@ impl TidalClient {
         }
     }
 
-    async fn refresh_token(&self) -> AppResult<()> {
+    /// `check_response` for catalog reads, the requests that count toward
+    /// region blocked mode: a region refusal adds one, any other response
+    /// starts the count over, and a success also lifts the block.
+    async fn check_catalog_response(
+        &self,
+        response: reqwest::Response,
+    ) -> AppResult<reqwest::Response> {
+        let result = self.check_response(response).await;
+        match &result {
+            Ok(_) => {
+                let refused = self.region_refusals.swap(0, Ordering::Relaxed) > 0;
+                if refused || self.region_block.borrow().is_some() {
+                    self.clear_region_block();
+                }
+            }
+            Err(e) if is_region_refusal(e) => self.note_region_refusal().await,
+            Err(_) => self.region_refusals.store(0, Ordering::Relaxed),
+        }
+        result
+    }
+
+    /// Get a new client-credentials token to replace `stale`. If another task
+    /// already replaced it while we waited for the lock, reuse theirs.
+    pub async fn renew_client_credentials(&self, stale: Option<&str>) -> AppResult<()> {
+        let _guard = self.token_lock.lock().await;
+        if self.config.read().await.access_token.as_deref() != stale {
+            return Ok(());
+        }
+        log::info!("Acquiring client credentials token (catalog-only access)...");
+        crate::api::auth::acquire_client_credentials(&self.http, &self.token_url, &self.config)
+            .await
+    }
+
+    /// Refresh after `stale`, the token a request was sent with, got a 401.
+    /// A no-op if another refresh already replaced it: refresh tokens rotate,
+    /// so using the old one twice would log the user out.
+    async fn refresh_token(&self, stale: Option<&str>) -> AppResult<()> {
+        if self.config.read().await.user_id.is_none() {
+            // No user login, so the rejected token was a client-credentials one
+            // and there is no refresh token; acquire a fresh one instead.
+            return self.renew_client_credentials(stale).await;
+        }
+
+        let _guard = self.token_lock.lock().await;
         let mut config = self.config.write().await;
+        if config.access_token.as_deref() != stale {
+            // Another refresh rotated the tokens while we waited
+            return Ok(());
+        }
 
         let refresh_token = config
             .refresh_token
@@ -265,14 +795,15 @@ impl TidalClient {
             ("refresh_token", &refresh_token),
         ];
 
-        let response = self
-            .http
-            .post("https://auth.tidal.com/v1/oauth2/token")
-            .form(&params)
-            .send()
-            .await?;
+        let response = self.http.post(&self.token_url).form(&params).send().await?;
 
         if !response.status().is_success() {
+            let body = response.text().await.unwrap_or_default();
+            if crate::api::auth::refresh_token_rejected(&body) {
+                log::warn!("Refresh token was rejected, user needs to log in again");
+                self.require_reauth(&mut config);
+                return Err(AppError::ReauthRequired);
+            }
             return Err(AppError::TokenExpired);
         }
 
@@ -293,3 +824,306 @@ impl TidalClient {
         &self.http
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::temp_dirs;
+    use wiremock::matchers::{body_string_contains, header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn logged_in() -> AppConfig {
+        AppConfig {
+            client_id: "client".into(),
+            user_id: Some("1".into()),
+            access_token: Some("expired".into()),
+            refresh_token: Some("rt-1".into()),
+            ..AppConfig::default()
+        }
+    }
+
+    fn client_for(server: &MockServer, config: AppConfig) -> Arc<TidalClient> {
+        let token_url = format!("{}/oauth2/token", server.uri());
+        let config = Arc::new(RwLock::new(config));
+        Arc::new(TidalClient::with_endpoints(config, &server.uri(), &token_url).unwrap())
+    }
+
+    /// The token endpoint rotates refresh tokens: `rt-1` works once (slowly)
+    /// and is rejected after that, like a reused refresh token would be.
+    async fn mount_rotating_token(server: &MockServer) {
+        Mock::given(method("POST"))
+            .and(path("/oauth2/token"))
+            .and(body_string_contains("refresh_token=rt-1"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!({
+                        "access_token": "fresh",
+                        "refresh_token": "rt-2",
+                        "expires_in": 3600,
+                        "token_type": "Bearer",
+                    }))
+                    .set_delay(Duration::from_millis(200)),
+            )
+            .up_to_n_times(1)
+            .mount(server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/oauth2/token"))
+            .respond_with(ResponseTemplate::new(400).set_body_json(serde_json::json!({
+                "error": "invalid_grant",
+            })))
+            .mount(server)
+            .await;
+    }
+
+    /// `/ping` only accepts the refreshed token.
+    async fn mount_api(server: &MockServer) {
+        Mock::given(method("GET"))
+            .and(path("/ping"))
+            .and(header("authorization", "Bearer fresh"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/ping"))
+            .respond_with(ResponseTemplate::new(401))
+            .mount(server)
+            .await;
+    }
+
+    async fn token_requests(server: &MockServer) -> usize {
+        let requests = server.received_requests().await.unwrap();
+        requests
+            .iter()
+            .filter(|r| r.url.path() == "/oauth2/token")
+            .count()
+    }
+
+    #[tokio::test]
+    async fn early_requests_wait_for_the_startup_refresh() {
+        let _dirs = temp_dirs();
+        let server = MockServer::start().await;
+        mount_rotating_token(&server).await;
+        mount_api(&server).await;
+        let client = client_for(&server, logged_in());
+
+        let startup = tokio::spawn({
+            let client = Arc::clone(&client);
+            async move { client.startup_token_refresh().await }
+        });
+        let (first, second) = tokio::join!(client.get("/ping"), client.get("/ping"));
+        startup.await.unwrap();
+
+        assert_eq!(first.unwrap().status(), 200);
+        assert_eq!(second.unwrap().status(), 200);
+        // Nothing went out with the expired token, so nothing refreshed again
+        assert_eq!(token_requests(&server).await, 1);
+        let config = client.config().read().await;
+        assert_eq!(config.access_token.as_deref(), Some("fresh"));
+        assert_eq!(config.refresh_token.as_deref(), Some("rt-2"));
+        assert!(!*client.subscribe_reauth_required().borrow());
+    }
+
+    #[tokio::test]
+    async fn concurrent_401s_refresh_once() {
+        let _dirs = temp_dirs();
+        let server = MockServer::start().await;
+        mount_rotating_token(&server).await;
+        mount_api(&server).await;
+        let client = client_for(&server, logged_in());
+        // No startup refresh this time: both requests go out with the old token
+        client.skip_startup_refresh();
+
+        let (first, second) = tokio::join!(client.get("/ping"), client.get("/ping"));
+
+        assert_eq!(first.unwrap().status(), 200);
+        assert_eq!(second.unwrap().status(), 200);
+        // The second refresh saw the rotated token and didn't reuse rt-1
+        assert_eq!(token_requests(&server).await, 1);
+        assert_eq!(
+            client.config().read().await.refresh_token.as_deref(),
+            Some("rt-2")
+        );
+    }
+
+    #[tokio::test]
+    async fn requests_give_up_waiting_on_a_stuck_startup_refresh() {
+        let _dirs = temp_dirs();
+        let server = MockServer::start().await;
+        mount_api(&server).await;
+        let mut config = logged_in();
+        config.access_token = Some("fresh".into());
+        let client = client_for(&server, config);
+
+        tokio::time::pause();
+        let started = tokio::time::Instant::now();
+        let response = client.get("/ping").await.unwrap();
+        assert_eq!(response.status(), 200);
+        assert!(started.elapsed() >= TOKEN_READY_TIMEOUT);
+    }
+
+    /// Answer every token request with `status` and `body`.
+    async fn mount_token_failure(server: &MockServer, status: u16, body: serde_json::Value) {
+        Mock::given(method("POST"))
+            .and(path("/oauth2/token"))
+            .respond_with(ResponseTemplate::new(status).set_body_json(body))
+            .mount(server)
+            .await;
+    }
+
+    #[tokio::test]
+    async fn startup_refresh_retries_server_errors_until_it_gives_up() {
+        let _dirs = temp_dirs();
+        let server = MockServer::start().await;
+        mount_token_failure(&server, 503, serde_json::json!({})).await;
+        let client = client_for(&server, logged_in());
+
+        tokio::time::pause();
+        client.startup_token_refresh().await;
+
+        assert_eq!(
+            token_requests(&server).await,
+            STARTUP_REFRESH_ATTEMPTS as usize
+        );
+        // Still logged in for when the server is back
+        let config = client.config().read().await;
+        assert_eq!(config.access_token.as_deref(), Some("expired"));
+        assert_eq!(config.refresh_token.as_deref(), Some("rt-1"));
+        assert!(!*client.subscribe_reauth_required().borrow());
+    }
+
+    #[tokio::test]
+    async fn startup_refresh_retries_when_it_cannot_connect() {
+        let _dirs = temp_dirs();
+        // A port nothing listens on
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let token_url = format!("http://{}/oauth2/token", listener.local_addr().unwrap());
+        drop(listener);
+        let config = Arc::new(RwLock::new(logged_in()));
+        let client = TidalClient::with_endpoints(config, "http://127.0.0.1:1", &token_url).unwrap();
+
+        tokio::time::pause();
+        let started = tokio::time::Instant::now();
+        client.startup_token_refresh().await;
+
+        // Every backoff was waited out
+        let backoffs =
+            (1..STARTUP_REFRESH_ATTEMPTS).map(|i| STARTUP_REFRESH_BACKOFF * (1 << (i - 1)));
+        assert!(started.elapsed() >= backoffs.sum::<Duration>());
+        assert_eq!(
+            client.config().read().await.refresh_token.as_deref(),
+            Some("rt-1")
+        );
+        assert!(!*client.subscribe_reauth_required().borrow());
+    }
+
+    #[tokio::test]
+    async fn a_rejected_refresh_token_is_not_retried() {
+        let _dirs = temp_dirs();
+        let server = MockServer::start().await;
+        mount_token_failure(&server, 400, serde_json::json!({"error": "invalid_grant"})).await;
+        let client = client_for(&server, logged_in());
+
+        tokio::time::pause();
+        client.startup_token_refresh().await;
+
+        assert_eq!(token_requests(&server).await, 1);
+        assert!(*client.subscribe_reauth_required().borrow());
+    }
+
+    #[tokio::test]
+    async fn reauth_clears_the_tokens_but_keeps_the_user() {
+        let _dirs = temp_dirs();
+        let server = MockServer::start().await;
+        mount_token_failure(&server, 400, serde_json::json!({"error": "invalid_grant"})).await;
+        let client = client_for(&server, logged_in());
+        client.startup_token_refresh().await;
+
+        // In memory and on disk
+        let saved = AppConfig::load().unwrap();
+        for config in [&*client.config().read().await, &saved] {
+            assert_eq!(config.access_token, None);
+            assert_eq!(config.refresh_token, None);
+            assert_eq!(config.expires_at, None);
+            assert!(config.reauth_required);
+            assert_eq!(config.user_id.as_deref(), Some("1"));
+        }
+    }
+
+    #[tokio::test]
+    async fn a_reactive_refresh_ends_the_startup_retries() {
+        let _dirs = temp_dirs();
+        let server = MockServer::start().await;
+        // The startup refresh fails once; the reactive one gets through
+        Mock::given(method("POST"))
+            .and(path("/oauth2/token"))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        mount_rotating_token(&server).await;
+        mount_api(&server).await;
+        let client = client_for(&server, logged_in());
+
+        let startup = tokio::spawn({
+            let client = Arc::clone(&client);
+            async move { client.startup_token_refresh().await }
+        });
+        // Released after the first try, then refreshed on the 401
+        let response = client.get("/ping").await.unwrap();
+        assert_eq!(response.status(), 200);
+        tokio::time::timeout(STARTUP_REFRESH_BACKOFF * 2, startup)
+            .await
+            .expect("startup refresh kept retrying")
+            .unwrap();
+
+        assert_eq!(token_requests(&server).await, 2);
+        let config = client.config().read().await;
+        assert_eq!(config.access_token.as_deref(), Some("fresh"));
+        assert_eq!(config.refresh_token.as_deref(), Some("rt-2"));
+    }
+
+    #[tokio::test]
+    async fn only_catalog_refusals_in_a_row_block_the_region() {
+        let server = MockServer::start().await;
+        for (verb, route, status) in [
+            ("GET", "/refused", 451),
+            ("POST", "/refused", 451),
+            ("GET", "/missing", 404),
+            ("GET", "/ping", 200),
+        ] {
+            Mock::given(method(verb))
+                .and(path(route))
+                .respond_with(ResponseTemplate::new(status))
+                .mount(&server)
+                .await;
+        }
+        let client = crate::test_support::mock_client(&server);
+        let refuse = |times| {
+            let client = Arc::clone(&client);
+            async move {
+                for _ in 0..times {
+                    client.get("/refused").await.unwrap_err();
+                }
+            }
+        };
+
+        // Any other answer starts the count over
+        refuse(REGION_BLOCK_AFTER - 1).await;
+        client.get("/missing").await.unwrap_err();
+        refuse(REGION_BLOCK_AFTER - 1).await;
+        client.get("/ping").await.unwrap();
+        refuse(REGION_BLOCK_AFTER - 1).await;
+        // Writes neither count nor reset it
+        for _ in 0..REGION_BLOCK_AFTER {
+            let refused = client.post("/refused", &serde_json::json!({})).await;
+            assert_eq!(refused.unwrap_err().status(), Some(451));
+        }
+        assert!(client.region_block().is_none());
+
+        refuse(1).await;
+        assert_eq!(client.region_block().unwrap().country, "US");
+        let blocked = client.get("/ping").await.unwrap_err();
+        assert!(matches!(blocked, AppError::RegionBlocked { .. }));
+    }
+}
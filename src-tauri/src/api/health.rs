@@ -0,0 +1,114 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Requests kept for the rolling stats.
+const WINDOW: usize = 50;
+
+/// Consecutive connection failures after which the API is considered unreachable.
+const OFFLINE_AFTER_FAILURES: u32 = 3;
+
+/// Error rate above which the connection counts as degraded.
+const DEGRADED_ERROR_RATE: f32 = 0.2;
+
+/// Median latency above which the connection counts as degraded.
+const DEGRADED_LATENCY: Duration = Duration::from_millis(1500);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NetworkHealth {
+    Good,
+    Degraded,
+    Offline,
+}
+
+/// Outcome of one API request.
+#[derive(Debug, Clone, Copy)]
+pub enum RequestOutcome {
+    /// A response arrived; `ok` is false for 429 and 5xx
+    Response { latency: Duration, ok: bool },
+    /// The request never got a response (connect error, timeout)
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NetworkStatus {
+    pub health: NetworkHealth,
+    /// Median over the recent requests that got a response
+    pub median_latency_ms: Option<u64>,
+    pub error_rate: f32,
+    pub samples: usize,
+}
+
+/// The API refusing the whole catalog in the user's country, e.g. while
+/// traveling somewhere it isn't licensed.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RegionBlock {
+    pub country: String,
+    pub since: DateTime<Utc>,
+    /// When a catalog request is next let through to check again
+    #[serde(skip)]
+    pub retry_at: Instant,
+}
+
+/// Ring buffer of recent request outcomes.
+#[derive(Debug, Default)]
+pub struct HealthTracker {
+    recent: VecDeque<RequestOutcome>,
+    consecutive_failures: u32,
+}
+
+impl HealthTracker {
+    pub fn record(&mut self, outcome: RequestOutcome) {
+        if self.recent.len() == WINDOW {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(outcome);
+        self.consecutive_failures = match outcome {
+            RequestOutcome::Failed => self.consecutive_failures + 1,
+            RequestOutcome::Response { .. } => 0,
+        };
+    }
+
+    pub fn status(&self) -> NetworkStatus {
+        let mut latencies: Vec<Duration> = self
+            .recent
+            .iter()
+            .filter_map(|o| match o {
+                RequestOutcome::Response { latency, .. } => Some(*latency),
+                RequestOutcome::Failed => None,
+            })
+            .collect();
+        latencies.sort();
+        let median = latencies.get(latencies.len() / 2).copied();
+
+        let errors = self
+            .recent
+            .iter()
+            .filter(|o| !matches!(o, RequestOutcome::Response { ok: true, .. }))
+            .count();
+        let error_rate = if self.recent.is_empty() {
+            0.0
+        } else {
+            errors as f32 / self.recent.len() as f32
+        };
+
+        let health = if self.consecutive_failures >= OFFLINE_AFTER_FAILURES {
+            NetworkHealth::Offline
+        } else if error_rate > DEGRADED_ERROR_RATE || median.is_some_and(|m| m > DEGRADED_LATENCY) {
+            NetworkHealth::Degraded
+        } else {
+            NetworkHealth::Good
+        };
+
+        NetworkStatus {
+            health,
+            median_latency_ms: median.map(|m| m.as_millis() as u64),
+            error_rate,
+            samples: self.recent.len(),
+        }
+    }
+}
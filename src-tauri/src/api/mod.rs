@@ -1,10 +1,13 @@
+pub mod adaptive;
 pub mod auth;
 pub mod client;
+pub mod health;
 pub mod models;
 
 mod albums;
 mod artists;
 mod playlists;
 pub mod search;
-mod tracks;
-mod user;
+pub mod tracks;
+pub mod user;
+mod videos;
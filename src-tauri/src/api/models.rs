@@ -1,4 +1,9 @@
-use serde::{Deserialize, Serialize};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::HashSet;
+
+/// Releases at most this many days old count as new.
+pub const NEW_RELEASE_DAYS: i64 = 14;
 
 // JSON:API envelope types
 #[derive(Debug, Deserialize)]
@@ -61,6 +66,25 @@ pub struct Track {
     pub album_id: Option<String>,
     pub artwork_url: Option<String>,
     pub media_tags: Vec<String>,
+    #[serde(default)]
+    pub explicit: bool,
+    /// The track's own release date, or its album's
+    #[serde(default, deserialize_with = "deserialize_release_date")]
+    pub release_date: Option<NaiveDate>,
+    /// Released within `NEW_RELEASE_DAYS`. Worked out from `release_date` when
+    /// parsed or loaded (`refresh_is_new`); a saved value goes stale
+    #[serde(default)]
+    pub is_new: bool,
+    /// The API lists the track as streamable in the user's country
+    #[serde(default = "default_true")]
+    pub streamable: bool,
+    /// ISO 639 code of the sung or spoken language, when the API reports one
+    #[serde(default)]
+    pub language: Option<String>,
+}
+
+fn default_true() -> bool {
+    true
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -73,9 +97,40 @@ pub struct Album {
     pub duration: Option<f64>,
     pub number_of_tracks: Option<u32>,
     pub number_of_volumes: Option<u32>,
-    pub release_date: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_release_date")]
+    pub release_date: Option<NaiveDate>,
     pub artwork_url: Option<String>,
+    /// Every size of the cover the API offers; `artwork_url` is the default pick
+    #[serde(default)]
+    pub artwork: Vec<ArtworkFile>,
     pub media_tags: Vec<String>,
+    /// Released within `NEW_RELEASE_DAYS`, like `Track::is_new`
+    #[serde(default)]
+    pub is_new: bool,
+}
+
+/// An album or playlist entry that isn't a playable track, such as a music
+/// video. Listings show it in place so the numbering has no gaps.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NonTrackItem {
+    pub id: String,
+    pub title: String,
+    pub duration: f64,
+    /// Resource kind, e.g. "video"
+    pub kind: String,
+    /// Index among all of the collection's entries, tracks included
+    pub position: usize,
+}
+
+/// One size of an artwork image. Dimensions are missing when the API leaves
+/// them out.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArtworkFile {
+    pub url: String,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -86,6 +141,19 @@ pub struct Artist {
     pub picture_url: Option<String>,
 }
 
+/// A music video. Only its metadata is used; videos can't be played.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Video {
+    pub id: String,
+    pub title: String,
+    pub artist_name: String,
+    pub artist_id: Option<String>,
+    pub duration: f64,
+    /// Thumbnail, possibly with `{width}`/`{height}` placeholders
+    pub image_url: Option<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Playlist {
@@ -96,7 +164,45 @@ pub struct Playlist {
     pub number_of_items: Option<u32>,
     pub playlist_type: Option<String>,
     pub artwork_url: Option<String>,
+    /// Every size of the cover the API offers; `artwork_url` is the default pick
+    #[serde(default)]
+    pub artwork: Vec<ArtworkFile>,
     pub creator_id: Option<String>,
+    /// Pinned to the top of the sidebar; a local preference
+    #[serde(default)]
+    pub pinned: bool,
+    /// Created by the user, as opposed to followed
+    #[serde(default)]
+    pub owned: bool,
+    #[serde(default)]
+    pub last_updated: Option<DateTime<Utc>>,
+    /// Owners and collaborators; only filled in by `get_playlist`
+    #[serde(default)]
+    pub contributors: Vec<PlaylistContributor>,
+    /// Display name of `creator_id`; only filled in by `get_playlist`, and
+    /// None when the owner's profile is private
+    #[serde(default)]
+    pub owner_name: Option<String>,
+    /// Whether the current user may add, remove or reorder tracks
+    #[serde(default)]
+    pub can_edit: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlaylistContributor {
+    pub id: String,
+    pub name: String,
+}
+
+/// Which of the user's playlists `get_playlists` returns.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PlaylistScope {
+    Owned,
+    Followed,
+    #[default]
+    All,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -105,6 +211,32 @@ pub struct FavoritesPage {
     pub tracks: Vec<Track>,
     pub next_cursor: Option<String>,
     pub has_more: bool,
+    /// Size of the whole collection, when the response says
+    pub total: Option<u32>,
+}
+
+/// A kind of section shown on Home, in the order the user configured.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HomeSectionKind {
+    MyMixes,
+    Discovery,
+    NewArrivals,
+    FavoritesTeaser,
+    BecauseYouLike,
+    RecentlyPlayed,
+}
+
+impl HomeSectionKind {
+    /// The `userRecommendations` relationship holding this kind's mixes, if any.
+    pub fn mix_relationship(self) -> Option<&'static str> {
+        match self {
+            HomeSectionKind::MyMixes => Some("myMixes"),
+            HomeSectionKind::Discovery => Some("discoveryMixes"),
+            HomeSectionKind::NewArrivals => Some("newArrivalMixes"),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -115,6 +247,46 @@ pub struct RecommendationSection {
     pub tracks: Vec<Track>,
 }
 
+/// Everything the album page shows, loaded in one call.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AlbumPage {
+    pub album: Album,
+    pub tracks: Vec<Track>,
+    /// Other albums by the album's artist; empty if they couldn't be loaded
+    pub more_from_artist: Vec<Album>,
+}
+
+/// Everything the artist page shows, loaded in one call. Sections that fail
+/// to load are left empty.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArtistPage {
+    pub artist: Artist,
+    pub top_tracks: Vec<Track>,
+    pub albums: Vec<Album>,
+    /// Albums the artist is featured on but isn't the main artist of
+    pub appears_on: Vec<Album>,
+    pub similar_artists: Vec<Artist>,
+}
+
+/// A point inside a track, e.g. where the next song starts in a DJ mix.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrackMarker {
+    /// Seconds from the start of the track
+    pub time: f64,
+    pub label: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrackDetails {
+    pub track: Track,
+    /// Empty when the API has no segment data for the track
+    pub markers: Vec<TrackMarker>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SearchResults {
@@ -122,6 +294,8 @@ pub struct SearchResults {
     pub albums: Vec<Album>,
     pub artists: Vec<Artist>,
     pub playlists: Vec<Playlist>,
+    #[serde(default)]
+    pub videos: Vec<Video>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -158,9 +332,46 @@ pub struct DeviceAuthResponse {
 #[serde(rename_all = "camelCase")]
 pub struct AuthStatus {
     pub authenticated: bool,
+    /// Not authenticated because the saved login was rejected, e.g. the
+    /// refresh token was revoked. The user id and profile are the old login's.
+    pub reauth_required: bool,
     pub user_id: Option<String>,
     pub display_name: Option<String>,
     pub country_code: String,
+    pub email: Option<String>,
+    pub avatar_url: Option<String>,
+}
+
+/// The authenticated user's profile from `/users/me`.
+#[derive(Debug, Clone, Default)]
+pub struct UserProfile {
+    pub username: Option<String>,
+    pub first_name: Option<String>,
+    pub last_name: Option<String>,
+    pub email: Option<String>,
+    pub avatar_url: Option<String>,
+    pub country: Option<String>,
+}
+
+impl UserProfile {
+    /// Prefer "firstName lastName", falling back to the username.
+    pub fn display_name(&self) -> Option<String> {
+        match (&self.first_name, &self.last_name) {
+            (Some(f), Some(l)) if !f.is_empty() => Some(format!("{} {}", f, l)),
+            (Some(f), _) if !f.is_empty() => Some(f.clone()),
+            _ => self.username.clone(),
+        }
+    }
+}
+
+/// What anyone can see of another user. Both fields are None for private
+/// and unknown users.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PublicProfile {
+    pub id: String,
+    pub name: Option<String>,
+    pub avatar_url: Option<String>,
 }
 
 /// Resolve `{width}` and `{height}` placeholders in an artwork URL.
@@ -170,6 +381,211 @@ pub fn resolve_artwork_url(url: &str, width: u32, height: u32) -> String {
         .replace("{height}", &height.to_string())
 }
 
+/// Width in pixels that `artwork_url` is picked for when no size is asked for.
+pub const DEFAULT_ARTWORK_SIZE: u32 = 640;
+
+/// Artwork width for thumbnails such as the mini player, 2x for Retina.
+pub const SMALL_ARTWORK_SIZE: u32 = 320;
+
+/// Artwork width for full-screen views and the system now-playing widget.
+pub const LARGE_ARTWORK_SIZE: u32 = 1280;
+
+/// The file best suited to showing artwork `size` pixels wide: the smallest
+/// at least that wide, else the largest. Without any dimensions the last
+/// file is taken, as the API usually lists sizes smallest first.
+pub fn select_artwork(files: &[ArtworkFile], size: u32) -> Option<&ArtworkFile> {
+    let sized = files.iter().filter_map(|f| Some((f.width?, f)));
+    sized
+        .clone()
+        .filter(|(width, _)| *width >= size)
+        .min_by_key(|(width, _)| *width)
+        .or_else(|| sized.max_by_key(|(width, _)| *width))
+        .map(|(_, f)| f)
+        .or_else(|| files.last())
+}
+
+/// Parse a Tidal `releaseDate`. Besides full dates ("2024-05-17") the API
+/// returns timestamps ("2024-05-17T00:00:00Z"), and old catalog items may only
+/// have a year-month ("1969-09") or a year ("1969"); those resolve to their
+/// first day.
+pub fn parse_release_date(value: &str) -> Option<NaiveDate> {
+    let value = value.trim();
+    let day = value.get(..10).unwrap_or(value);
+    NaiveDate::parse_from_str(day, "%Y-%m-%d")
+        .or_else(|_| NaiveDate::parse_from_str(&format!("{}-01", value), "%Y-%m-%d"))
+        .ok()
+        .or_else(|| {
+            value
+                .parse::<i32>()
+                .ok()
+                .and_then(|year| NaiveDate::from_ymd_opt(year, 1, 1))
+        })
+}
+
+/// Read a saved release date leniently. Older queues and caches stored the
+/// API's raw string, which may be a timestamp or just a year.
+fn deserialize_release_date<'de, D>(deserializer: D) -> Result<Option<NaiveDate>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = Option::<String>::deserialize(deserializer)?;
+    Ok(value.as_deref().and_then(parse_release_date))
+}
+
+/// Three-letter ISO 639-2 codes, terminology and bibliographic forms, and
+/// withdrawn two-letter codes, for languages with a current ISO 639-1 code.
+const LANGUAGE_ALIASES: &[(&str, &str)] = &[
+    ("afr", "af"),
+    ("alb", "sq"),
+    ("amh", "am"),
+    ("ara", "ar"),
+    ("arm", "hy"),
+    ("baq", "eu"),
+    ("ben", "bn"),
+    ("bul", "bg"),
+    ("cat", "ca"),
+    ("ces", "cs"),
+    ("chi", "zh"),
+    ("cym", "cy"),
+    ("cze", "cs"),
+    ("dan", "da"),
+    ("deu", "de"),
+    ("dut", "nl"),
+    ("ell", "el"),
+    ("eng", "en"),
+    ("est", "et"),
+    ("eus", "eu"),
+    ("fas", "fa"),
+    ("fin", "fi"),
+    ("fra", "fr"),
+    ("fre", "fr"),
+    ("geo", "ka"),
+    ("ger", "de"),
+    ("gle", "ga"),
+    ("glg", "gl"),
+    ("gre", "el"),
+    ("heb", "he"),
+    ("hin", "hi"),
+    ("hrv", "hr"),
+    ("hun", "hu"),
+    ("hye", "hy"),
+    ("ice", "is"),
+    ("ind", "id"),
+    ("isl", "is"),
+    ("ita", "it"),
+    ("jpn", "ja"),
+    ("kat", "ka"),
+    ("kaz", "kk"),
+    ("kor", "ko"),
+    ("lat", "la"),
+    ("lav", "lv"),
+    ("lit", "lt"),
+    ("mac", "mk"),
+    ("may", "ms"),
+    ("mkd", "mk"),
+    ("msa", "ms"),
+    ("mlt", "mt"),
+    ("nld", "nl"),
+    ("nno", "nn"),
+    ("nob", "nb"),
+    ("nor", "no"),
+    ("pan", "pa"),
+    ("per", "fa"),
+    ("pol", "pl"),
+    ("por", "pt"),
+    ("ron", "ro"),
+    ("rum", "ro"),
+    ("rus", "ru"),
+    ("slk", "sk"),
+    ("slo", "sk"),
+    ("slv", "sl"),
+    ("spa", "es"),
+    ("sqi", "sq"),
+    ("srp", "sr"),
+    ("swa", "sw"),
+    ("swe", "sv"),
+    ("tam", "ta"),
+    ("tel", "te"),
+    ("tgl", "tl"),
+    ("tha", "th"),
+    ("tur", "tr"),
+    ("ukr", "uk"),
+    ("urd", "ur"),
+    ("vie", "vi"),
+    ("wel", "cy"),
+    ("yid", "yi"),
+    ("yor", "yo"),
+    ("zho", "zh"),
+    ("zul", "zu"),
+    ("in", "id"),
+    ("iw", "he"),
+    ("ji", "yi"),
+];
+
+/// Normalize a language tag ("en-US", "EN", "por") to its lowercase ISO 639-1
+/// code, or the ISO 639-2 code for a language without one. Anything that
+/// isn't a 2-3 letter code is ignored.
+pub fn parse_language(value: &str) -> Option<String> {
+    let primary = value.trim().split(['-', '_']).next()?;
+    if !(2..=3).contains(&primary.len()) || !primary.chars().all(|c| c.is_ascii_alphabetic()) {
+        return None;
+    }
+    let primary = primary.to_ascii_lowercase();
+    Some(
+        LANGUAGE_ALIASES
+            .iter()
+            .find(|(alias, _)| *alias == primary)
+            .map_or(primary, |(_, code)| code.to_string()),
+    )
+}
+
+/// Drop tracks in a language outside `allowlist`, returning how many went.
+/// Tracks without language metadata are kept, and an empty list allows all.
+pub fn retain_languages(tracks: &mut Vec<Track>, allowlist: &[String]) -> usize {
+    if allowlist.is_empty() {
+        return 0;
+    }
+    // Lists saved before codes were normalized may hold three-letter codes
+    let allowed: HashSet<String> = allowlist.iter().filter_map(|l| parse_language(l)).collect();
+    let before = tracks.len();
+    tracks.retain(|t| {
+        t.language
+            .as_ref()
+            .is_none_or(|lang| allowed.contains(lang))
+    });
+    before - tracks.len()
+}
+
+/// Whether a release date falls within the last `NEW_RELEASE_DAYS` days.
+pub fn is_new_release(date: Option<NaiveDate>) -> bool {
+    date.is_some_and(|date| {
+        let age = (Utc::now().date_naive() - date).num_days();
+        (0..=NEW_RELEASE_DAYS).contains(&age)
+    })
+}
+
+impl Track {
+    /// Use `date` (usually the album's) when the track has no release date of its own.
+    pub fn inherit_release_date(&mut self, date: Option<NaiveDate>) {
+        if self.release_date.is_none() {
+            self.release_date = date;
+            self.refresh_is_new();
+        }
+    }
+
+    /// Recompute `is_new` for today, e.g. for a track loaded from disk.
+    pub fn refresh_is_new(&mut self) {
+        self.is_new = is_new_release(self.release_date);
+    }
+}
+
+impl Album {
+    /// Recompute `is_new` for today, e.g. for an album loaded from disk.
+    pub fn refresh_is_new(&mut self) {
+        self.is_new = is_new_release(self.release_date);
+    }
+}
+
 // Artwork helpers
 impl Track {
     pub fn artwork_url_sized(&self, width: u32, height: u32) -> Option<String> {
@@ -189,6 +605,12 @@ impl Track {
 }
 
 impl Album {
+    /// Take the cover's files, picking `artwork_url` at the default size.
+    pub fn set_artwork(&mut self, files: Vec<ArtworkFile>) {
+        self.artwork_url = select_artwork(&files, DEFAULT_ARTWORK_SIZE).map(|f| f.url.clone());
+        self.artwork = files;
+    }
+
     pub fn artwork_url_sized(&self, width: u32, height: u32) -> Option<String> {
         self.artwork_url
             .as_ref()
@@ -214,7 +636,23 @@ impl Artist {
     }
 }
 
+impl Video {
+    pub fn resolve_artwork(&mut self) {
+        if let Some(ref url) = self.image_url {
+            if url.contains("{width}") || url.contains("{height}") {
+                self.image_url = Some(resolve_artwork_url(url, 640, 360));
+            }
+        }
+    }
+}
+
 impl Playlist {
+    /// Take the cover's files, picking `artwork_url` at the default size.
+    pub fn set_artwork(&mut self, files: Vec<ArtworkFile>) {
+        self.artwork_url = select_artwork(&files, DEFAULT_ARTWORK_SIZE).map(|f| f.url.clone());
+        self.artwork = files;
+    }
+
     pub fn resolve_artwork(&mut self) {
         if let Some(ref url) = self.artwork_url {
             if url.contains("{width}") || url.contains("{height}") {
@@ -239,5 +677,144 @@ impl SearchResults {
         for playlist in &mut self.playlists {
             playlist.resolve_artwork();
         }
+        for video in &mut self.videos {
+            video.resolve_artwork();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::track;
+    use chrono::Duration;
+
+    #[test]
+    fn language_tags_normalize_to_iso_639_1() {
+        for (tag, code) in [
+            ("en", "en"),
+            ("EN", "en"),
+            ("en-US", "en"),
+            ("pt_BR", "pt"),
+            ("por", "pt"),
+            ("ger", "de"),
+            ("deu", "de"),
+            ("zho-Hant", "zh"),
+            ("iw", "he"),
+            // No two-letter code exists for Cantonese
+            ("yue", "yue"),
+        ] {
+            assert_eq!(parse_language(tag).as_deref(), Some(code), "{}", tag);
+        }
+        for tag in ["", "e", "engl", "12", "x-klingon"] {
+            assert_eq!(parse_language(tag), None, "{}", tag);
+        }
+    }
+
+    fn in_language(id: &str, language: Option<&str>) -> Track {
+        Track {
+            language: language.map(String::from),
+            ..track(id)
+        }
+    }
+
+    #[test]
+    fn the_language_filter_keeps_allowed_and_unknown_languages() {
+        let mut tracks = vec![
+            in_language("1", Some("en")),
+            in_language("2", Some("ja")),
+            in_language("3", None),
+            in_language("4", Some("pt")),
+        ];
+        // Saved before codes were normalized
+        let allowlist = vec!["eng".to_string(), "por".to_string()];
+
+        assert_eq!(retain_languages(&mut tracks, &allowlist), 1);
+        let ids: Vec<&str> = tracks.iter().map(|t| t.id.as_str()).collect();
+        assert_eq!(ids, ["1", "3", "4"]);
+    }
+
+    #[test]
+    fn an_empty_allowlist_keeps_everything() {
+        let mut tracks = vec![in_language("1", Some("ja")), in_language("2", None)];
+        assert_eq!(retain_languages(&mut tracks, &[]), 0);
+        assert_eq!(tracks.len(), 2);
+    }
+
+    fn date(y: i32, m: u32, d: u32) -> Option<NaiveDate> {
+        NaiveDate::from_ymd_opt(y, m, d)
+    }
+
+    #[test]
+    fn release_dates_in_every_format_the_api_uses() {
+        let cases = [
+            ("2024-05-17", date(2024, 5, 17)),
+            ("2024-05-17T00:00:00Z", date(2024, 5, 17)),
+            ("2024-05-17T22:30:00.000+02:00", date(2024, 5, 17)),
+            (" 2024-05-17 ", date(2024, 5, 17)),
+            ("1969-09", date(1969, 9, 1)),
+            ("1969", date(1969, 1, 1)),
+            ("0001", date(1, 1, 1)),
+            ("", None),
+            ("unknown", None),
+            ("2024-13-01", None),
+            ("2023-02-29", None),
+            ("17/05/2024", None),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(parse_release_date(input), expected, "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn new_releases_are_at_most_fourteen_days_old() {
+        let today = Utc::now().date_naive();
+        assert!(is_new_release(Some(today)));
+        assert!(is_new_release(Some(
+            today - Duration::days(NEW_RELEASE_DAYS)
+        )));
+        assert!(!is_new_release(Some(
+            today - Duration::days(NEW_RELEASE_DAYS + 1)
+        )));
+        // Pre-release dates aren't out yet
+        assert!(!is_new_release(Some(today + Duration::days(1))));
+        assert!(!is_new_release(None));
+    }
+
+    #[test]
+    fn saved_raw_release_dates_still_load() {
+        for (raw, expected) in [
+            (serde_json::json!("1969"), date(1969, 1, 1)),
+            (serde_json::json!("2024-05-17T00:00:00Z"), date(2024, 5, 17)),
+            (serde_json::json!("not a date"), None),
+            (serde_json::Value::Null, None),
+        ] {
+            let mut json = serde_json::to_value(track("1")).unwrap();
+            json["releaseDate"] = raw;
+            let loaded: Track = serde_json::from_value(json).unwrap();
+            assert_eq!(loaded.release_date, expected);
+        }
+
+        let mut json = serde_json::to_value(track("1")).unwrap();
+        json.as_object_mut().unwrap().remove("releaseDate");
+        let loaded: Track = serde_json::from_value(json).unwrap();
+        assert_eq!(loaded.release_date, None);
+    }
+
+    #[test]
+    fn a_saved_new_badge_is_recomputed() {
+        let mut json = serde_json::to_value(track("1")).unwrap();
+        json["releaseDate"] = serde_json::json!("2001-01-01");
+        json["isNew"] = serde_json::json!(true);
+        let mut loaded: Track = serde_json::from_value(json).unwrap();
+        loaded.refresh_is_new();
+        assert!(!loaded.is_new);
+
+        let mut fresh = track("2");
+        fresh.inherit_release_date(Some(Utc::now().date_naive()));
+        assert!(fresh.is_new);
+        // A track's own date wins over its album's
+        fresh.inherit_release_date(date(2001, 1, 1));
+        assert!(fresh.is_new);
     }
 }
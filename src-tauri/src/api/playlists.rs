@@ -1,170 +1,224 @@
 use crate::api::client::TidalClient;
-use crate::api::models::{Playlist, Track};
-use crate::api::search::{get_first_relationship_id, parse_playlist, parse_tracks_from_included};
+use crate::api::models::{
+    ArtworkFile, NonTrackItem, Playlist, PlaylistContributor, PlaylistScope, Track,
+};
+use crate::api::search::{
+    get_first_relationship_id, parse_artwork_files, parse_items_from_included, parse_playlist,
+};
 use crate::error::{AppError, AppResult};
-use std::collections::HashMap;
+use chrono::{DateTime, Utc};
+use std::collections::{HashMap, HashSet};
+
+/// Upper bound on pages fetched per playlist listing.
+const MAX_PLAYLIST_PAGES: usize = 20;
+
+/// Playlists for a scope. `complete` is false when one of the sources
+/// couldn't be fetched or had more than `MAX_PLAYLIST_PAGES` pages, so the
+/// list may be missing playlists.
+pub struct PlaylistCollection {
+    pub playlists: Vec<Playlist>,
+    pub complete: bool,
+}
+
+/// Everything one playlist items request returns.
+struct PlaylistItems {
+    tracks: Vec<Track>,
+    others: Vec<NonTrackItem>,
+    /// When each track was added, keyed by track id
+    added: HashMap<String, DateTime<Utc>>,
+}
 
 impl TidalClient {
-    pub async fn get_playlists(&self) -> AppResult<Vec<Playlist>> {
+    /// The user's playlists for `scope`, most recently updated first.
+    ///
+    /// Owned playlists come from `/playlists?filter[owners.id]=me`, followed
+    /// ones from the user's playlist collection minus the owned ones. A source
+    /// that fails is skipped, and `complete` is cleared, as it is when a source
+    /// is cut off at `MAX_PLAYLIST_PAGES`.
+    pub async fn get_playlists(&self, scope: PlaylistScope) -> AppResult<PlaylistCollection> {
         let config = self.config().read().await;
         let country = config.country_code.clone();
         drop(config);
 
-        let mut playlists = Vec::new();
-        let mut seen_ids = std::collections::HashSet::new();
-
-        // Strategy 1: Fetch user's playlist collection via userCollectionPlaylists.
-        // This returns playlists the user has saved/added, including those by other creators.
-        match self
-            .get_with_query(
-                "/userCollectionPlaylists/me/relationships/items",
-                &[
-                    ("countryCode", country.as_str()),
-                    ("include", "items,items.coverArt"),
-                ],
-            )
-            .await
-        {
-            Ok(response) => {
-                let body: serde_json::Value = response.json().await?;
-                let included = body.get("included").and_then(|v| v.as_array());
-
-                // Build artwork map from included artworks
-                let mut artwork_map: HashMap<String, String> = HashMap::new();
-                if let Some(items) = included {
-                    for item in items {
-                        if item.get("type").and_then(|v| v.as_str()) == Some("artworks") {
-                            let id = item
-                                .get("id")
-                                .and_then(|v| v.as_str())
-                                .unwrap_or("")
-                                .to_string();
-                            if let Some(href) = item
-                                .get("attributes")
-                                .and_then(|a| a.get("files"))
-                                .and_then(|v| v.as_array())
-                                .and_then(|arr| arr.last().or(arr.first()))
-                                .and_then(|f| f.get("href"))
-                                .and_then(|v| v.as_str())
-                            {
-                                artwork_map.insert(id, href.to_string());
-                            }
-                        }
-                    }
+        let owned_params = [
+            ("countryCode", country.as_str()),
+            ("filter[owners.id]", "me"),
+            ("include", "coverArt,owners"),
+        ];
+        let collection_params = [
+            ("countryCode", country.as_str()),
+            ("include", "items,items.coverArt,items.owners"),
+        ];
+        let wants_followed = scope != PlaylistScope::Owned;
+        // Followed playlists are told apart from owned ones by the owned list
+        let (owned, collection) = tokio::join!(
+            self.get_playlist_pages("/playlists", &owned_params),
+            async {
+                if wants_followed {
+                    Some(
+                        self.get_playlist_pages(
+                            "/userCollectionPlaylists/me/relationships/items",
+                            &collection_params,
+                        )
+                        .await,
+                    )
+                } else {
+                    None
                 }
+            }
+        );
 
-                // Parse playlists from included resources
-                if let Some(items) = included {
-                    for item in items {
-                        if item.get("type").and_then(|v| v.as_str()) == Some("playlists") {
-                            let id = item.get("id").and_then(|v| v.as_str()).unwrap_or("");
-                            let attrs = item.get("attributes").cloned().unwrap_or_default();
-                            let rels = item.get("relationships");
-                            if let Some(mut playlist) = parse_playlist(id, &attrs) {
-                                if playlist.artwork_url.is_none() {
-                                    playlist.artwork_url =
-                                        get_first_relationship_id(rels, "coverArt")
-                                            .and_then(|art_id| artwork_map.get(&art_id).cloned());
-                                }
-                                playlist.creator_id = get_first_relationship_id(rels, "owners");
-                                seen_ids.insert(playlist.id.clone());
-                                playlists.push(playlist);
-                            }
-                        }
-                    }
-                }
-                log::info!(
-                    "Fetched {} playlists from userCollectionPlaylists",
-                    playlists.len()
-                );
+        let mut complete = true;
+        let owned = match owned {
+            Ok((owned, truncated)) => {
+                complete &= !truncated;
+                owned
             }
             Err(e) => {
-                log::warn!(
-                    "userCollectionPlaylists fetch failed: {}, falling back to /playlists",
-                    e
-                );
+                log::warn!("Owned playlists fetch failed: {}", e);
+                complete = false;
+                Vec::new()
+            }
+        };
+        let owned_ids: HashSet<String> = owned.iter().map(|p| p.id.clone()).collect();
+
+        let mut playlists = Vec::new();
+        if scope != PlaylistScope::Followed {
+            playlists.extend(owned.into_iter().map(|mut p| {
+                p.owned = true;
+                p.can_edit = true;
+                p
+            }));
+        }
+        match collection {
+            Some(Ok((saved, truncated))) => {
+                complete &= !truncated;
+                playlists.extend(saved.into_iter().filter(|p| !owned_ids.contains(&p.id)));
+            }
+            Some(Err(e)) => {
+                log::warn!("userCollectionPlaylists fetch failed: {}", e);
+                complete = false;
             }
+            None => {}
+        }
+        if !complete && playlists.is_empty() {
+            return Err(AppError::NotFound("Playlists could not be fetched".into()));
         }
 
-        // Strategy 2: Also fetch user-owned playlists via /playlists?filter[owners.id]=me.
-        // This catches any playlists the user created that might not be in their collection.
-        match self
-            .get_with_query(
-                "/playlists",
-                &[
-                    ("countryCode", country.as_str()),
-                    ("filter[owners.id]", "me"),
-                    ("include", "coverArt,owners"),
-                ],
-            )
-            .await
-        {
-            Ok(response) => {
-                let body: serde_json::Value = response.json().await?;
-                let data = body.get("data").and_then(|v| v.as_array());
-                let included = body.get("included").and_then(|v| v.as_array());
-
-                let mut artwork_map: HashMap<String, String> = HashMap::new();
-                if let Some(items) = included {
-                    for item in items {
-                        if item.get("type").and_then(|v| v.as_str()) == Some("artworks") {
-                            let id = item
-                                .get("id")
-                                .and_then(|v| v.as_str())
-                                .unwrap_or("")
-                                .to_string();
-                            if let Some(href) = item
-                                .get("attributes")
-                                .and_then(|a| a.get("files"))
-                                .and_then(|v| v.as_array())
-                                .and_then(|arr| arr.last().or(arr.first()))
-                                .and_then(|f| f.get("href"))
-                                .and_then(|v| v.as_str())
-                            {
-                                artwork_map.insert(id, href.to_string());
-                            }
-                        }
+        // Most recently updated first; playlists without the attribute keep their order last
+        playlists.sort_by_key(|p| std::cmp::Reverse(p.last_updated));
+
+        log::info!(
+            "Total playlists returned for {:?}: {}",
+            scope,
+            playlists.len()
+        );
+        Ok(PlaylistCollection {
+            playlists,
+            complete,
+        })
+    }
+
+    /// Fetch every page of a playlist listing, following `links.meta.nextCursor`.
+    /// Playlists are read from both `data` and `included`, so this works for
+    /// plain listings and relationship endpoints alike. The flag is set when
+    /// pages were left unfetched after `MAX_PLAYLIST_PAGES`.
+    async fn get_playlist_pages(
+        &self,
+        path: &str,
+        params: &[(&str, &str)],
+    ) -> AppResult<(Vec<Playlist>, bool)> {
+        let mut playlists = Vec::new();
+        let mut seen_ids = HashSet::new();
+        let mut cursor: Option<String> = None;
+
+        for _ in 0..MAX_PLAYLIST_PAGES {
+            let mut page_params = params.to_vec();
+            if let Some(c) = &cursor {
+                page_params.push(("page[cursor]", c.as_str()));
+            }
+            let response = self.get_with_query(path, &page_params).await?;
+            let body: serde_json::Value = response.json().await?;
+            let data = body.get("data").and_then(|v| v.as_array());
+            let included = body.get("included").and_then(|v| v.as_array());
+
+            let mut artwork_map: HashMap<String, Vec<ArtworkFile>> = HashMap::new();
+            for item in included.into_iter().flatten() {
+                if item.get("type").and_then(|v| v.as_str()) == Some("artworks") {
+                    let id = item
+                        .get("id")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or("")
+                        .to_string();
+                    if let Some(attrs) = item.get("attributes") {
+                        artwork_map.insert(id, parse_artwork_files(attrs));
                     }
                 }
+            }
 
-                if let Some(items) = data {
-                    for item in items {
-                        let id = item.get("id").and_then(|v| v.as_str()).unwrap_or("");
-                        if seen_ids.contains(id) {
-                            continue; // Already added from collection
-                        }
-                        let attrs = item.get("attributes").cloned().unwrap_or_default();
-                        let rels = item.get("relationships");
-                        if let Some(mut playlist) = parse_playlist(id, &attrs) {
-                            if playlist.artwork_url.is_none() {
-                                playlist.artwork_url = get_first_relationship_id(rels, "coverArt")
-                                    .and_then(|art_id| artwork_map.get(&art_id).cloned());
-                            }
-                            playlist.creator_id = get_first_relationship_id(rels, "owners");
-                            playlists.push(playlist);
+            // Relationship identifiers in `data` have no attributes and are skipped
+            let resources = data.into_iter().flatten();
+            for item in resources.chain(included.into_iter().flatten()) {
+                if item.get("type").and_then(|v| v.as_str()) != Some("playlists") {
+                    continue;
+                }
+                let id = item.get("id").and_then(|v| v.as_str()).unwrap_or("");
+                if seen_ids.contains(id) {
+                    continue;
+                }
+                let attrs = item.get("attributes").cloned().unwrap_or_default();
+                let rels = item.get("relationships");
+                if let Some(mut playlist) = parse_playlist(id, &attrs) {
+                    if playlist.artwork_url.is_none() {
+                        if let Some(files) = get_first_relationship_id(rels, "coverArt")
+                            .and_then(|art_id| artwork_map.get(&art_id))
+                        {
+                            playlist.set_artwork(files.clone());
                         }
                     }
+                    playlist.creator_id = get_first_relationship_id(rels, "owners");
+                    seen_ids.insert(playlist.id.clone());
+                    playlists.push(playlist);
                 }
             }
-            Err(e) => {
-                log::warn!("Owned playlists fetch failed: {}", e);
+
+            cursor = body
+                .get("links")
+                .and_then(|l| l.get("meta"))
+                .and_then(|m| m.get("nextCursor"))
+                .and_then(|v| v.as_str())
+                .map(String::from);
+            if cursor.is_none() {
+                break;
             }
         }
 
-        log::info!("Total playlists returned: {}", playlists.len());
-        Ok(playlists)
+        let truncated = cursor.is_some();
+        if truncated {
+            log::warn!(
+                "Stopped listing {} after {} pages",
+                path,
+                MAX_PLAYLIST_PAGES
+            );
+        }
+        Ok((playlists, truncated))
     }
 
     pub async fn get_playlist(&self, playlist_id: &str) -> AppResult<Playlist> {
         let config = self.config().read().await;
         let country = config.country_code.clone();
+        let user_id = config.user_id.clone();
+        let display_name = config.display_name.clone();
         drop(config);
 
         let path = format!("/playlists/{}", playlist_id);
         let response = self
             .get_with_query(
                 &path,
-                &[("countryCode", country.as_str()), ("include", "coverArt")],
+                &[
+                    ("countryCode", country.as_str()),
+                    ("include", "coverArt,owners"),
+                ],
             )
             .await?;
 
@@ -194,24 +248,153 @@ impl TidalClient {
                     if item.get("type").and_then(|v| v.as_str()) == Some("artworks")
                         && item.get("id").and_then(|v| v.as_str()) == Some(&art_id_val)
                     {
-                        playlist.artwork_url = item
-                            .get("attributes")
-                            .and_then(|a| a.get("files"))
-                            .and_then(|v| v.as_array())
-                            .and_then(|arr| arr.last().or(arr.first()))
-                            .and_then(|f| f.get("href"))
-                            .and_then(|v| v.as_str())
-                            .map(|s| s.to_string());
+                        if let Some(attrs) = item.get("attributes") {
+                            playlist.set_artwork(parse_artwork_files(attrs));
+                        }
                         break;
                     }
                 }
             }
         }
 
+        // Collaborative playlists list every contributor as an owner
+        let owner_ids: Vec<&str> = rels
+            .and_then(|r| r.get("owners"))
+            .and_then(|o| o.get("data"))
+            .and_then(|d| d.as_array())
+            .map(|arr| {
+                arr.iter()
+                    .filter_map(|o| o.get("id").and_then(|v| v.as_str()))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let included_name = |owner_id: &str| {
+            included
+                .and_then(|items| {
+                    items.iter().find(|item| {
+                        item.get("type").and_then(|v| v.as_str()) == Some("users")
+                            && item.get("id").and_then(|v| v.as_str()) == Some(owner_id)
+                    })
+                })
+                .and_then(|item| item.get("attributes"))
+                .and_then(|a| a.get("username").or_else(|| a.get("nickname")))
+                .and_then(|v| v.as_str())
+                .map(String::from)
+        };
+        playlist.contributors = owner_ids
+            .iter()
+            .map(|owner_id| PlaylistContributor {
+                id: owner_id.to_string(),
+                name: included_name(owner_id).unwrap_or_else(|| owner_id.to_string()),
+            })
+            .collect();
+        playlist.creator_id = owner_ids.first().map(|id| id.to_string());
+        if let Some(user_id) = user_id {
+            playlist.owned = playlist.creator_id.as_deref() == Some(user_id.as_str());
+            playlist.can_edit = owner_ids.contains(&user_id.as_str());
+        }
+
+        // Fall back to the public profile when the owners include has no name
+        playlist.owner_name = match owner_ids.first() {
+            Some(_) if playlist.owned => display_name,
+            Some(owner_id) => match included_name(owner_id) {
+                Some(name) => Some(name),
+                None => match self.get_user_public_profile(owner_id).await {
+                    Ok(profile) => profile.name,
+                    Err(e) => {
+                        log::warn!("Failed to fetch owner of playlist {}: {}", playlist_id, e);
+                        None
+                    }
+                },
+            },
+            None => None,
+        };
+
         Ok(playlist)
     }
 
+    /// Let other users with the invite link add tracks to the playlist.
+    pub async fn set_playlist_collaborative(
+        &self,
+        playlist_id: &str,
+        enabled: bool,
+    ) -> AppResult<()> {
+        let config = self.config().read().await;
+        let country = config.country_code.clone();
+        drop(config);
+
+        let path = format!("/playlists/{}", playlist_id);
+        let body = serde_json::json!({
+            "data": {
+                "type": "playlists",
+                "id": playlist_id,
+                "attributes": {
+                    "collaborative": enabled,
+                }
+            }
+        });
+        self.patch_with_query(&path, &[("countryCode", country.as_str())], &body)
+            .await?;
+        Ok(())
+    }
+
+    /// Link that lets another user join the playlist as a contributor.
+    pub async fn get_playlist_invite_link(&self, playlist_id: &str) -> AppResult<String> {
+        let config = self.config().read().await;
+        let country = config.country_code.clone();
+        drop(config);
+
+        let path = format!("/playlists/{}/relationships/sharing", playlist_id);
+        let body = serde_json::json!({
+            "data": {
+                "type": "playlistShares",
+                "attributes": {
+                    "access": "CONTRIBUTOR",
+                }
+            }
+        });
+        let response = self
+            .post_with_query(&path, &[("countryCode", country.as_str())], &body)
+            .await?;
+
+        let body: serde_json::Value = response.json().await?;
+        let attrs = body.get("data").and_then(|d| d.get("attributes"));
+        attrs
+            .and_then(|a| a.get("url").or_else(|| a.get("shareUrl")))
+            .or_else(|| body.get("url"))
+            .and_then(|v| v.as_str())
+            .map(String::from)
+            .ok_or_else(|| {
+                AppError::NotFound(format!(
+                    "No invite link returned for playlist {}",
+                    playlist_id
+                ))
+            })
+    }
+
     pub async fn get_playlist_tracks(&self, playlist_id: &str) -> AppResult<Vec<Track>> {
+        Ok(self.fetch_playlist_items(playlist_id).await?.tracks)
+    }
+
+    /// Playlist tracks plus entries that aren't tracks, such as videos.
+    pub async fn get_playlist_items(
+        &self,
+        playlist_id: &str,
+    ) -> AppResult<(Vec<Track>, Vec<NonTrackItem>)> {
+        let items = self.fetch_playlist_items(playlist_id).await?;
+        Ok((items.tracks, items.others))
+    }
+
+    /// Playlist tracks plus when each was added, keyed by track id.
+    pub async fn get_playlist_tracks_with_dates(
+        &self,
+        playlist_id: &str,
+    ) -> AppResult<(Vec<Track>, HashMap<String, DateTime<Utc>>)> {
+        let items = self.fetch_playlist_items(playlist_id).await?;
+        Ok((items.tracks, items.added))
+    }
+
+    async fn fetch_playlist_items(&self, playlist_id: &str) -> AppResult<PlaylistItems> {
         let config = self.config().read().await;
         let country = config.country_code.clone();
         drop(config);
@@ -231,9 +414,29 @@ impl TidalClient {
             .await?;
 
         let body: serde_json::Value = response.json().await?;
+        let data = body.get("data").and_then(|v| v.as_array());
         let included = body.get("included").and_then(|v| v.as_array());
 
-        Ok(parse_tracks_from_included(included))
+        let added = data
+            .map(|items| {
+                items
+                    .iter()
+                    .filter_map(|item| {
+                        let id = item.get("id")?.as_str()?;
+                        let at = item.get("meta")?.get("addedAt")?.as_str()?;
+                        let at = DateTime::parse_from_rfc3339(at).ok()?;
+                        Some((id.to_string(), at.with_timezone(&Utc)))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let (tracks, others) = parse_items_from_included(data, included);
+        Ok(PlaylistItems {
+            tracks,
+            others,
+            added,
+        })
     }
 
     pub async fn create_playlist(
@@ -293,6 +496,29 @@ impl TidalClient {
         Ok(())
     }
 
+    /// Add several tracks in a single request. The API accepts a list of
+    /// resource identifiers, so callers should chunk large batches.
+    pub async fn add_tracks_to_playlist(
+        &self,
+        playlist_id: &str,
+        track_ids: &[String],
+    ) -> AppResult<()> {
+        let config = self.config().read().await;
+        let country = config.country_code.clone();
+        drop(config);
+
+        let data: Vec<serde_json::Value> = track_ids
+            .iter()
+            .map(|id| serde_json::json!({ "type": "tracks", "id": id }))
+            .collect();
+        let body = serde_json::json!({ "data": data });
+
+        let path = format!("/playlists/{}/relationships/items", playlist_id);
+        self.post_with_query(&path, &[("countryCode", country.as_str())], &body)
+            .await?;
+        Ok(())
+    }
+
     pub async fn remove_from_playlist(&self, playlist_id: &str, track_id: &str) -> AppResult<()> {
         let path = format!("/playlists/{}/relationships/items", playlist_id);
         let body = serde_json::json!({
@@ -314,3 +540,64 @@ impl TidalClient {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::mock_client;
+    use wiremock::matchers::{method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn page(id: &str, next: Option<&str>) -> serde_json::Value {
+        serde_json::json!({
+            "data": [{
+                "id": id,
+                "type": "playlists",
+                "attributes": { "name": format!("Playlist {}", id) },
+            }],
+            "links": { "meta": { "nextCursor": next } },
+        })
+    }
+
+    #[tokio::test]
+    async fn a_listing_that_ends_is_complete() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/playlists"))
+            .and(query_param("page[cursor]", "2"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(page("b", None)))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/playlists"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(page("a", Some("2"))))
+            .mount(&server)
+            .await;
+        let client = mock_client(&server);
+
+        let collection = client.get_playlists(PlaylistScope::Owned).await.unwrap();
+
+        assert!(collection.complete);
+        let ids: Vec<&str> = collection.playlists.iter().map(|p| p.id.as_str()).collect();
+        assert_eq!(ids, ["a", "b"]);
+    }
+
+    #[tokio::test]
+    async fn a_listing_cut_off_at_the_page_cap_is_incomplete() {
+        let server = MockServer::start().await;
+        // Every page points at another one
+        Mock::given(method("GET"))
+            .and(path("/playlists"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(page("a", Some("more"))))
+            .mount(&server)
+            .await;
+        let client = mock_client(&server);
+
+        let collection = client.get_playlists(PlaylistScope::Owned).await.unwrap();
+
+        assert!(!collection.complete);
+        assert_eq!(collection.playlists.len(), 1);
+        let requests = server.received_requests().await.unwrap();
+        assert_eq!(requests.len(), MAX_PLAYLIST_PAGES);
+    }
+}
@@ -1,10 +1,26 @@
 use crate::api::client::TidalClient;
-use crate::api::models::{Album, Artist, Playlist, SearchResults, Track};
+use crate::api::models::{
+    is_new_release, parse_language, parse_release_date, select_artwork, Album, Artist, ArtworkFile,
+    NonTrackItem, Playlist, SearchResults, Track, Video, DEFAULT_ARTWORK_SIZE,
+};
 use crate::error::AppResult;
+use chrono::{DateTime, NaiveDate, Utc};
 use std::collections::HashMap;
 
+/// Title, artwork URL and release date of an included album.
+pub type AlbumInfo = (String, Option<String>, Option<NaiveDate>);
+
+/// Artist name used when a track's artist couldn't be resolved.
+pub const UNKNOWN_ARTIST: &str = "Unknown Artist";
+
+/// Tracks still unresolved after the batch fetch that are looked up one by one.
+const MAX_INDIVIDUAL_LOOKUPS: usize = 3;
+
+/// Suggestions returned by `search_suggestions`.
+const SUGGESTION_LIMIT: usize = 5;
+
 impl TidalClient {
-    pub async fn search(&self, query: &str, _limit: u32) -> AppResult<SearchResults> {
+    pub async fn search(&self, query: &str, limit: u32) -> AppResult<SearchResults> {
         let config = self.config().read().await;
         let country = config.country_code.clone();
         drop(config);
@@ -18,17 +34,20 @@ impl TidalClient {
         // - albums + their artists/coverArt
         // - artists + their profileArt
         // - playlists + their coverArt
+        // - videos + their artists/thumbnailArt
         // If the API doesn't support dot-notation, it will still return
         // first-level includes and we fall back to batch fetch.
+        let limit_param = limit.to_string();
         let response = self
             .get_with_query(
                 &path,
                 &[
                     (
                         "include",
-                        "tracks,tracks.artists,tracks.albums,albums,albums.artists,albums.coverArt,artists,artists.profileArt,playlists,playlists.coverArt",
+                        "tracks,tracks.artists,tracks.albums,albums,albums.artists,albums.coverArt,artists,artists.profileArt,playlists,playlists.coverArt,videos,videos.artists,videos.thumbnailArt",
                     ),
                     ("countryCode", &country),
+                    ("page[limit]", &limit_param),
                 ],
             )
             .await?;
@@ -55,13 +74,13 @@ impl TidalClient {
             );
         }
 
-        let mut results = parse_search_results(&body);
+        let mut results = parse_search_results(&body, limit as usize);
 
         // Check if tracks have unresolved artists (dot-notation might not be supported)
         let unresolved: Vec<String> = results
             .tracks
             .iter()
-            .filter(|t| t.artist_name == "Unknown Artist")
+            .filter(|t| t.artist_name == UNKNOWN_ARTIST)
             .map(|t| t.id.clone())
             .collect();
 
@@ -101,6 +120,43 @@ impl TidalClient {
                     log::warn!("Batch track fetch failed: {}", e);
                 }
             }
+
+            // Last resort: when the batch response lacks artists too (seen with
+            // some regional content), fetch a few tracks individually
+            let still_unresolved: Vec<String> = results
+                .tracks
+                .iter()
+                .filter(|t| t.artist_name == UNKNOWN_ARTIST && !t.id.is_empty())
+                .map(|t| t.id.clone())
+                .take(MAX_INDIVIDUAL_LOOKUPS)
+                .collect();
+            let lookups = still_unresolved.iter().map(|id| self.get_track(id));
+            for result in futures_util::future::join_all(lookups).await {
+                match result {
+                    Ok(track) if track.artist_name != UNKNOWN_ARTIST => {
+                        if let Some(existing) = results.tracks.iter_mut().find(|t| t.id == track.id)
+                        {
+                            *existing = track;
+                        }
+                    }
+                    Ok(_) => {}
+                    Err(e) => log::warn!("Individual track lookup failed: {}", e),
+                }
+            }
+
+            let remaining = results
+                .tracks
+                .iter()
+                .filter(|t| t.artist_name == UNKNOWN_ARTIST)
+                .count();
+            if remaining > 0 {
+                let total = self.note_unresolved_artists(remaining);
+                log::warn!(
+                    "{} search tracks left with an unknown artist ({} this session)",
+                    remaining,
+                    total
+                );
+            }
         }
 
         log::info!(
@@ -131,7 +187,7 @@ impl TidalClient {
         if let Some(data) = body.get("data") {
             if let Some(attrs) = data.get("attributes") {
                 if let Some(suggestion_list) = attrs.get("suggestions").and_then(|v| v.as_array()) {
-                    for s in suggestion_list.iter().take(5) {
+                    for s in suggestion_list.iter().take(SUGGESTION_LIMIT) {
                         if let Some(query_str) = s.get("query").and_then(|v| v.as_str()) {
                             suggestions.push(query_str.to_string());
                         }
@@ -146,15 +202,18 @@ impl TidalClient {
             let response = self
                 .get_with_query(
                     &search_path,
-                    &[("include", "tracks,artists"), ("countryCode", &country)],
+                    &[
+                        ("include", "tracks,artists"),
+                        ("countryCode", &country),
+                        ("page[limit]", &SUGGESTION_LIMIT.to_string()),
+                    ],
                 )
                 .await?;
             let body: serde_json::Value = response.json().await?;
-            let results = parse_search_results(&body);
+            let results = parse_search_results(&body, SUGGESTION_LIMIT);
             suggestions = results
                 .tracks
                 .iter()
-                .take(5)
                 .map(|t| format!("{} - {}", t.title, t.artist_name))
                 .collect();
         }
@@ -164,42 +223,71 @@ impl TidalClient {
 }
 
 /// Parse ISO 8601 duration string (e.g., "PT2M58S") to seconds as f64.
-/// Handles hours (H), minutes (M), and seconds (S).
-pub fn parse_iso8601_duration(duration: &str) -> f64 {
+/// Handles weeks (W) and days (D) before the `T`, hours (H), minutes (M) and
+/// seconds (S) after it, fractions with either decimal separator, lowercase,
+/// and bare numbers of seconds. A missing `T` is tolerated for H/M/S; years
+/// and months have no fixed length and are rejected. Returns `None` for
+/// anything malformed.
+pub fn parse_iso8601_duration(duration: &str) -> Option<f64> {
+    let duration = duration.trim();
+    if let Ok(seconds) = duration.parse::<f64>() {
+        return Some(seconds).filter(|s| s.is_finite() && *s >= 0.0);
+    }
+
+    let upper = duration.to_ascii_uppercase();
+    let rest = upper.strip_prefix('P')?;
+
     let mut seconds = 0.0;
     let mut num_buf = String::new();
+    let mut in_time = false;
+    let mut components = 0;
 
-    for ch in duration.chars() {
-        match ch {
-            'P' | 'T' => {
-                num_buf.clear();
-            }
+    for ch in rest.chars() {
+        let unit = match ch {
             '0'..='9' | '.' => {
                 num_buf.push(ch);
+                continue;
             }
-            'H' => {
-                if let Ok(h) = num_buf.parse::<f64>() {
-                    seconds += h * 3600.0;
-                }
-                num_buf.clear();
-            }
-            'M' => {
-                if let Ok(m) = num_buf.parse::<f64>() {
-                    seconds += m * 60.0;
-                }
-                num_buf.clear();
+            ',' => {
+                num_buf.push('.');
+                continue;
             }
-            'S' => {
-                if let Ok(s) = num_buf.parse::<f64>() {
-                    seconds += s;
-                }
-                num_buf.clear();
+            'T' if !in_time && num_buf.is_empty() => {
+                in_time = true;
+                continue;
             }
-            _ => {}
-        }
+            'W' if !in_time => 604800.0,
+            'D' if !in_time => 86400.0,
+            'H' => 3600.0,
+            'M' => 60.0,
+            'S' => 1.0,
+            _ => return None,
+        };
+        let value = num_buf.parse::<f64>().ok()?;
+        seconds += value * unit;
+        num_buf.clear();
+        components += 1;
     }
 
-    seconds
+    // A trailing number without a designator, or no components at all
+    if !num_buf.is_empty() || components == 0 {
+        return None;
+    }
+    Some(seconds)
+}
+
+/// A duration attribute, either seconds or an ISO 8601 string. Strings that
+/// don't parse are logged with `context` and treated as absent.
+fn parse_duration_value(value: &serde_json::Value, context: &str) -> Option<f64> {
+    if let Some(seconds) = value.as_f64() {
+        return Some(seconds);
+    }
+    let raw = value.as_str()?;
+    let parsed = parse_iso8601_duration(raw);
+    if parsed.is_none() {
+        log::warn!("Unparseable duration {:?} on {}", raw, context);
+    }
+    parsed
 }
 
 /// Extract the first relationship ID from a JSON:API relationships object.
@@ -216,18 +304,69 @@ pub fn get_first_relationship_id(
     }
 }
 
-/// Extract artwork URL from an artworks resource's attributes.files array.
-fn extract_artwork_href(attrs: &serde_json::Value) -> Option<String> {
-    attrs
-        .get("files")
-        .and_then(|v| v.as_array())
-        .and_then(|arr| {
-            // Prefer larger images (last in the files array tends to be largest)
-            arr.last()
-                .or(arr.first())
-                .and_then(|f| f.get("href").and_then(|v| v.as_str()))
-                .map(|s| s.to_string())
+/// How an artist is credited on an album or track.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArtistRole {
+    Main,
+    Featured,
+}
+
+/// How `artist_id` is credited in a resource's `artists` relationship. An
+/// explicit `meta.role` wins; without one every listed artist is a main
+/// artist, since co-billed albums list several. An artist missing from the
+/// list (e.g. on a compilation) counts as featured. None without
+/// relationship data.
+pub fn artist_role(rels: Option<&serde_json::Value>, artist_id: &str) -> Option<ArtistRole> {
+    let data = rels?.get("artists")?.get("data")?.as_array()?;
+    if data.is_empty() {
+        return None;
+    }
+    let Some(entry) = data
+        .iter()
+        .find(|e| e.get("id").and_then(|v| v.as_str()) == Some(artist_id))
+    else {
+        return Some(ArtistRole::Featured);
+    };
+    let role = entry
+        .get("meta")
+        .and_then(|m| m.get("role"))
+        .and_then(|v| v.as_str());
+    Some(match role {
+        Some(role) if !role.eq_ignore_ascii_case("main") => ArtistRole::Featured,
+        _ => ArtistRole::Main,
+    })
+}
+
+/// Every file of an artworks resource's attributes.files array, with the
+/// dimensions from each file's `meta`.
+pub fn parse_artwork_files(attrs: &serde_json::Value) -> Vec<ArtworkFile> {
+    let Some(files) = attrs.get("files").and_then(|v| v.as_array()) else {
+        return Vec::new();
+    };
+    files
+        .iter()
+        .filter_map(|f| {
+            let url = f.get("href")?.as_str()?.to_string();
+            let dimension = |key: &str| {
+                f.get("meta")
+                    .and_then(|m| m.get(key))
+                    .or_else(|| f.get(key))
+                    .and_then(|v| v.as_u64())
+                    .map(|v| v as u32)
+            };
+            Some(ArtworkFile {
+                url,
+                width: dimension("width"),
+                height: dimension("height"),
+            })
         })
+        .collect()
+}
+
+/// Extract the artwork URL best suited to `size` pixels from an artworks
+/// resource's attributes.files array.
+pub fn extract_artwork_href(attrs: &serde_json::Value, size: u32) -> Option<String> {
+    select_artwork(&parse_artwork_files(attrs), size).map(|f| f.url.clone())
 }
 
 /// Try to extract an image URL from various possible attribute locations.
@@ -235,7 +374,7 @@ fn extract_artwork_href(attrs: &serde_json::Value) -> Option<String> {
 /// can vary between endpoints.
 fn extract_image_url(attrs: &serde_json::Value) -> Option<String> {
     // Try artworks files (v2 artworks resource format)
-    if let Some(url) = extract_artwork_href(attrs) {
+    if let Some(url) = extract_artwork_href(attrs, DEFAULT_ARTWORK_SIZE) {
         return Some(url);
     }
 
@@ -274,12 +413,12 @@ fn extract_image_url(attrs: &serde_json::Value) -> Option<String> {
 fn build_lookup_maps(
     included: &[serde_json::Value],
 ) -> (
-    HashMap<String, String>,                   // artist_id -> name
-    HashMap<String, (String, Option<String>)>, // album_id -> (title, artwork_url)
-    HashMap<String, String>,                   // artwork_id -> href URL
+    HashMap<String, String>,    // artist_id -> name
+    HashMap<String, AlbumInfo>, // album_id -> (title, artwork_url, release_date)
+    HashMap<String, String>,    // artwork_id -> href URL
 ) {
     let mut artist_map: HashMap<String, String> = HashMap::new();
-    let mut album_map: HashMap<String, (String, Option<String>)> = HashMap::new();
+    let mut album_map: HashMap<String, AlbumInfo> = HashMap::new();
     let mut artwork_map: HashMap<String, String> = HashMap::new();
 
     // First: extract all artwork URLs from artworks resources
@@ -291,7 +430,7 @@ fn build_lookup_maps(
                 .unwrap_or("")
                 .to_string();
             if let Some(attrs) = item.get("attributes") {
-                if let Some(href) = extract_artwork_href(attrs) {
+                if let Some(href) = extract_artwork_href(attrs, DEFAULT_ARTWORK_SIZE) {
                     artwork_map.insert(id, href);
                 }
             }
@@ -321,7 +460,11 @@ fn build_lookup_maps(
                     let artwork = get_first_relationship_id(rels, "coverArt")
                         .and_then(|art_id| artwork_map.get(&art_id).cloned())
                         .or_else(|| extract_image_url(&attrs.cloned().unwrap_or_default()));
-                    album_map.insert(id, (title.to_string(), artwork));
+                    let released = attrs
+                        .and_then(|a| a.get("releaseDate"))
+                        .and_then(|v| v.as_str())
+                        .and_then(parse_release_date);
+                    album_map.insert(id, (title.to_string(), artwork, released));
                 }
             }
             _ => {}
@@ -338,90 +481,195 @@ fn build_lookup_maps(
     (artist_map, album_map, artwork_map)
 }
 
-fn parse_search_results(body: &serde_json::Value) -> SearchResults {
-    let included = body.get("included").and_then(|v| v.as_array());
+/// Attributes of a resource that has none, so parsers can borrow instead of
+/// cloning each item's attributes.
+static NO_ATTRIBUTES: serde_json::Value = serde_json::Value::Null;
 
-    let empty_vec = Vec::new();
-    let items = included.unwrap_or(&empty_vec);
+/// Included resources indexed by (type, id) without copying them, so related
+/// artists, albums and artworks are only parsed for the items that are kept.
+struct IncludedIndex<'a> {
+    resources: HashMap<(&'a str, &'a str), &'a serde_json::Value>,
+}
+
+impl<'a> IncludedIndex<'a> {
+    fn new(included: &'a [serde_json::Value]) -> Self {
+        // Sized up front: growing a map of thousands of entries rehashes it
+        // several times over
+        let mut resources = HashMap::with_capacity(included.len());
+        resources.extend(included.iter().filter_map(|item| {
+            let rtype = item.get("type")?.as_str()?;
+            let id = item.get("id")?.as_str()?;
+            Some(((rtype, id), item))
+        }));
+        Self { resources }
+    }
 
-    let (artist_map, album_map, artwork_map) = build_lookup_maps(items);
+    fn attributes(&self, rtype: &str, id: &str) -> Option<&'a serde_json::Value> {
+        self.resources.get(&(rtype, id))?.get("attributes")
+    }
+
+    fn artwork(&self, id: &str) -> Option<String> {
+        extract_artwork_href(self.attributes("artworks", id)?, DEFAULT_ARTWORK_SIZE)
+    }
+
+    fn artwork_files(&self, id: &str) -> Vec<ArtworkFile> {
+        self.attributes("artworks", id)
+            .map(parse_artwork_files)
+            .unwrap_or_default()
+    }
+
+    /// Fill in a video's artist and thumbnail from its relationships.
+    fn resolve_video(&self, video: &mut Video, rels: Option<&serde_json::Value>) {
+        if let Some(artist_id) = get_first_relationship_id(rels, "artists") {
+            if let Some(name) = self.artist_name(&artist_id) {
+                video.artist_name = name;
+                video.artist_id = Some(artist_id);
+            }
+        }
+        if video.image_url.is_none() {
+            video.image_url = get_first_relationship_id(rels, "thumbnailArt")
+                .and_then(|art_id| self.artwork(&art_id));
+        }
+    }
+
+    fn artist_name(&self, id: &str) -> Option<String> {
+        let name = self.attributes("artists", id)?.get("name")?.as_str()?;
+        Some(name.to_string())
+    }
+
+    fn album_info(&self, id: &str) -> Option<AlbumInfo> {
+        let item = self.resources.get(&("albums", id))?;
+        let attrs = item.get("attributes")?;
+        let title = attrs.get("title")?.as_str()?.to_string();
+        let artwork = get_first_relationship_id(item.get("relationships"), "coverArt")
+            .and_then(|art_id| self.artwork(&art_id))
+            .or_else(|| extract_image_url(attrs));
+        let released = attrs
+            .get("releaseDate")
+            .and_then(|v| v.as_str())
+            .and_then(parse_release_date);
+        Some((title, artwork, released))
+    }
+}
+
+/// Parse search results, keeping at most `limit` items of each kind and
+/// stopping once every kind is full.
+pub fn parse_search_results(body: &serde_json::Value, limit: usize) -> SearchResults {
+    let items = body
+        .get("included")
+        .and_then(|v| v.as_array())
+        .map(Vec::as_slice)
+        .unwrap_or(&[]);
+    let index = IncludedIndex::new(items);
+
+    // A kind is full at `limit`, or sooner when the response lists fewer
+    // results of it; otherwise a kind with no results would never fill up
+    let wanted = |kind: &str| {
+        body.get("data")
+            .and_then(|d| d.get("relationships"))
+            .and_then(|r| r.get(kind))
+            .and_then(|r| r.get("data"))
+            .and_then(|d| d.as_array())
+            .map_or(limit, |listed| listed.len().min(limit))
+    };
+    let (want_tracks, want_albums, want_artists, want_playlists, want_videos) = (
+        wanted("tracks"),
+        wanted("albums"),
+        wanted("artists"),
+        wanted("playlists"),
+        wanted("videos"),
+    );
 
-    // Parse all resources with relationship resolution
     let mut tracks = Vec::new();
     let mut albums = Vec::new();
     let mut artists = Vec::new();
     let mut playlists = Vec::new();
+    let mut videos = Vec::new();
 
     for item in items {
+        if tracks.len() >= want_tracks
+            && albums.len() >= want_albums
+            && artists.len() >= want_artists
+            && playlists.len() >= want_playlists
+            && videos.len() >= want_videos
+        {
+            break;
+        }
+
         let resource_type = item.get("type").and_then(|v| v.as_str()).unwrap_or("");
-        let id = item
-            .get("id")
-            .and_then(|v| v.as_str())
-            .unwrap_or("")
-            .to_string();
-        let attrs = item.get("attributes").cloned().unwrap_or_default();
+        let id = item.get("id").and_then(|v| v.as_str()).unwrap_or("");
         let rels = item.get("relationships");
+        let attrs = item.get("attributes").unwrap_or(&NO_ATTRIBUTES);
 
         match resource_type {
-            "tracks" => {
-                if let Some(mut track) = parse_track(&id, &attrs) {
+            "tracks" if tracks.len() < limit => {
+                if let Some(mut track) = parse_track(id, attrs) {
                     // Resolve artist name from relationships -> included artists
                     if let Some(artist_id) = get_first_relationship_id(rels, "artists") {
-                        if let Some(name) = artist_map.get(&artist_id) {
-                            track.artist_name = name.clone();
+                        if let Some(name) = index.artist_name(&artist_id) {
+                            track.artist_name = name;
                             track.artist_id = Some(artist_id);
                         }
                     }
                     // Resolve album name and artwork from relationships -> included albums
                     if let Some(album_id) = get_first_relationship_id(rels, "albums") {
-                        if let Some((title, artwork)) = album_map.get(&album_id) {
-                            track.album_name = title.clone();
+                        if let Some((title, artwork, released)) = index.album_info(&album_id) {
+                            track.album_name = title;
+                            track.inherit_release_date(released);
                             track.album_id = Some(album_id);
                             if track.artwork_url.is_none() {
-                                track.artwork_url = artwork.clone();
+                                track.artwork_url = artwork;
                             }
                         }
                     }
                     tracks.push(track);
                 }
             }
-            "albums" => {
-                if let Some(mut album) = parse_album(&id, &attrs) {
+            "albums" if albums.len() < limit => {
+                if let Some(mut album) = parse_album(id, attrs) {
                     // Resolve artist name from relationships
                     if let Some(artist_id) = get_first_relationship_id(rels, "artists") {
-                        if let Some(name) = artist_map.get(&artist_id) {
-                            album.artist_name = name.clone();
+                        if let Some(name) = index.artist_name(&artist_id) {
+                            album.artist_name = name;
                             album.artist_id = Some(artist_id);
                         }
                     }
                     // Resolve artwork from coverArt relationship
                     if album.artwork_url.is_none() {
-                        album.artwork_url = get_first_relationship_id(rels, "coverArt")
-                            .and_then(|art_id| artwork_map.get(&art_id).cloned());
+                        if let Some(art_id) = get_first_relationship_id(rels, "coverArt") {
+                            album.set_artwork(index.artwork_files(&art_id));
+                        }
                     }
                     albums.push(album);
                 }
             }
-            "artists" => {
-                if let Some(mut artist) = parse_artist(&id, &attrs) {
+            "artists" if artists.len() < limit => {
+                if let Some(mut artist) = parse_artist(id, attrs) {
                     // Resolve picture from profileArt relationship
                     if artist.picture_url.is_none() {
                         artist.picture_url = get_first_relationship_id(rels, "profileArt")
-                            .and_then(|art_id| artwork_map.get(&art_id).cloned());
+                            .and_then(|art_id| index.artwork(&art_id));
                     }
                     artists.push(artist);
                 }
             }
-            "playlists" => {
-                if let Some(mut playlist) = parse_playlist(&id, &attrs) {
+            "playlists" if playlists.len() < limit => {
+                if let Some(mut playlist) = parse_playlist(id, attrs) {
                     // Resolve artwork from coverArt relationship
                     if playlist.artwork_url.is_none() {
-                        playlist.artwork_url = get_first_relationship_id(rels, "coverArt")
-                            .and_then(|art_id| artwork_map.get(&art_id).cloned());
+                        if let Some(art_id) = get_first_relationship_id(rels, "coverArt") {
+                            playlist.set_artwork(index.artwork_files(&art_id));
+                        }
                     }
                     playlists.push(playlist);
                 }
             }
+            "videos" if videos.len() < limit => {
+                if let Some(mut video) = parse_video(id, attrs) {
+                    index.resolve_video(&mut video, rels);
+                    videos.push(video);
+                }
+            }
             _ => {}
         }
     }
@@ -431,124 +679,102 @@ fn parse_search_results(body: &serde_json::Value) -> SearchResults {
         albums,
         artists,
         playlists,
+        videos,
     }
 }
 
-/// Build artist/album/artwork lookup maps from an included resources array.
-/// Shared by all endpoints that need to resolve track relationships from a JSON:API included array.
-pub fn build_track_lookup_maps(
-    included: Option<&Vec<serde_json::Value>>,
-) -> (
-    HashMap<String, String>,                   // artist_id -> name
-    HashMap<String, (String, Option<String>)>, // album_id -> (title, artwork_url)
-) {
-    let mut artist_map: HashMap<String, String> = HashMap::new();
-    let mut album_map: HashMap<String, (String, Option<String>)> = HashMap::new();
-    let mut artwork_map: HashMap<String, String> = HashMap::new();
-
-    let items = match included {
-        Some(items) => items,
-        None => return (artist_map, album_map),
-    };
-
-    // First pass: extract artworks
-    for item in items {
-        if item.get("type").and_then(|v| v.as_str()) == Some("artworks") {
-            let id = item
-                .get("id")
-                .and_then(|v| v.as_str())
-                .unwrap_or("")
-                .to_string();
-            if let Some(attrs) = item.get("attributes") {
-                if let Some(href) = extract_artwork_href(attrs) {
-                    artwork_map.insert(id, href);
-                }
-            }
-        }
-    }
-
-    // Second pass: extract artists and albums
-    for item in items {
-        let rtype = item.get("type").and_then(|v| v.as_str()).unwrap_or("");
-        let rid = item
-            .get("id")
-            .and_then(|v| v.as_str())
-            .unwrap_or("")
-            .to_string();
-        match rtype {
-            "artists" => {
-                if let Some(name) = item
-                    .get("attributes")
-                    .and_then(|a| a.get("name"))
-                    .and_then(|v| v.as_str())
-                {
-                    artist_map.insert(rid, name.to_string());
-                }
-            }
-            "albums" => {
-                if let Some(title) = item
-                    .get("attributes")
-                    .and_then(|a| a.get("title"))
-                    .and_then(|v| v.as_str())
-                {
-                    let artwork = get_first_relationship_id(item.get("relationships"), "coverArt")
-                        .and_then(|art_id| artwork_map.get(&art_id).cloned());
-                    album_map.insert(rid, (title.to_string(), artwork));
-                }
-            }
-            _ => {}
-        }
-    }
-
-    (artist_map, album_map)
-}
-
 /// Parse tracks from an included array, resolving artist/album/artwork relationships.
 /// Shared by all endpoints that return tracks within a JSON:API included array.
 pub fn parse_tracks_from_included(included: Option<&Vec<serde_json::Value>>) -> Vec<Track> {
+    let (tracks, others) = parse_items_from_included(None, included);
+    if !others.is_empty() {
+        log::debug!("Skipped {} non-track items", others.len());
+    }
+    tracks
+}
+
+/// Tracks plus the entries that aren't tracks (videos) from an album or
+/// playlist items response. The latter carry their position among all
+/// entries so they can be shown between the tracks.
+///
+/// `data` is the response's list of item identifiers and gives the order;
+/// `included` holds each resource once, in no particular order. Without
+/// `data` the included order is used.
+pub fn parse_items_from_included(
+    data: Option<&Vec<serde_json::Value>>,
+    included: Option<&Vec<serde_json::Value>>,
+) -> (Vec<Track>, Vec<NonTrackItem>) {
     let items = match included {
         Some(items) => items,
-        None => return Vec::new(),
+        None => return (Vec::new(), Vec::new()),
     };
 
-    let (artist_map, album_map) = build_track_lookup_maps(Some(items));
+    let index = IncludedIndex::new(items);
+    let entries: Vec<&serde_json::Value> = match data {
+        Some(data) => data
+            .iter()
+            .filter_map(|entry| {
+                let rtype = entry.get("type")?.as_str()?;
+                let id = entry.get("id")?.as_str()?;
+                index.resources.get(&(rtype, id)).copied()
+            })
+            .collect(),
+        None => items.iter().collect(),
+    };
 
     let mut tracks = Vec::new();
-    for item in items {
+    let mut others = Vec::new();
+    let mut position = 0;
+    for item in entries {
         let resource_type = item.get("type").and_then(|v| v.as_str()).unwrap_or("");
-        if resource_type == "tracks" {
+        if resource_type == "videos" {
+            let id = item.get("id").and_then(|v| v.as_str()).unwrap_or("");
+            let attrs = item.get("attributes").unwrap_or(&NO_ATTRIBUTES);
+            if let Some(video) = parse_video(id, attrs) {
+                others.push(NonTrackItem {
+                    id: video.id,
+                    title: video.title,
+                    duration: video.duration,
+                    kind: "video".to_string(),
+                    position,
+                });
+                position += 1;
+            }
+        } else if resource_type == "tracks" {
             let id = item.get("id").and_then(|v| v.as_str()).unwrap_or("");
-            let attrs = item.get("attributes").cloned().unwrap_or_default();
+            let attrs = item.get("attributes").unwrap_or(&NO_ATTRIBUTES);
             let rels = item.get("relationships");
-            if let Some(mut track) = parse_track(id, &attrs) {
+            if let Some(mut track) = parse_track(id, attrs) {
                 // Resolve artist from relationships
                 if let Some(artist_id) = get_first_relationship_id(rels, "artists") {
-                    if let Some(name) = artist_map.get(&artist_id) {
-                        track.artist_name = name.clone();
+                    if let Some(name) = index.artist_name(&artist_id) {
+                        track.artist_name = name;
                         track.artist_id = Some(artist_id);
                     }
                 }
                 // Resolve album from relationships
                 if let Some(album_id) = get_first_relationship_id(rels, "albums") {
-                    if let Some((title, artwork)) = album_map.get(&album_id) {
-                        track.album_name = title.clone();
+                    if let Some((title, artwork, released)) = index.album_info(&album_id) {
+                        track.album_name = title;
+                        track.inherit_release_date(released);
                         track.album_id = Some(album_id);
                         if track.artwork_url.is_none() {
-                            track.artwork_url = artwork.clone();
+                            track.artwork_url = artwork;
                         }
                     }
                 }
                 tracks.push(track);
+                position += 1;
             }
         }
     }
 
-    tracks
+    (tracks, others)
 }
 
 /// Parse a batch response from GET /tracks?filter[id]=... with include=artists,albums.
 /// Returns fully resolved Track objects.
-fn parse_tracks_batch(body: &serde_json::Value) -> Vec<Track> {
+pub fn parse_tracks_batch(body: &serde_json::Value) -> Vec<Track> {
     let data = body.get("data").and_then(|v| v.as_array());
     let included = body.get("included").and_then(|v| v.as_array());
 
@@ -564,10 +790,10 @@ fn parse_tracks_batch(body: &serde_json::Value) -> Vec<Track> {
                 .and_then(|v| v.as_str())
                 .unwrap_or("")
                 .to_string();
-            let attrs = item.get("attributes").cloned().unwrap_or_default();
+            let attrs = item.get("attributes").unwrap_or(&NO_ATTRIBUTES);
             let rels = item.get("relationships");
 
-            if let Some(mut track) = parse_track(&id, &attrs) {
+            if let Some(mut track) = parse_track(&id, attrs) {
                 if let Some(artist_id) = get_first_relationship_id(rels, "artists") {
                     if let Some(name) = artist_map.get(&artist_id) {
                         track.artist_name = name.clone();
@@ -575,8 +801,9 @@ fn parse_tracks_batch(body: &serde_json::Value) -> Vec<Track> {
                     }
                 }
                 if let Some(album_id) = get_first_relationship_id(rels, "albums") {
-                    if let Some((title, artwork)) = album_map.get(&album_id) {
+                    if let Some((title, artwork, released)) = album_map.get(&album_id) {
                         track.album_name = title.clone();
+                        track.inherit_release_date(*released);
                         track.album_id = Some(album_id);
                         if track.artwork_url.is_none() {
                             track.artwork_url = artwork.clone();
@@ -600,11 +827,15 @@ pub fn parse_track(id: &str, attrs: &serde_json::Value) -> Option<Track> {
     // but also handle f64 for backward compatibility
     let duration = attrs
         .get("duration")
-        .and_then(|v| {
-            v.as_f64()
-                .or_else(|| v.as_str().map(parse_iso8601_duration))
-        })
+        .and_then(|v| parse_duration_value(v, &format!("track {}", id)))
         .unwrap_or(0.0);
+    if duration <= 0.0 {
+        log::info!(
+            "Track {} has no usable duration (raw: {:?})",
+            id,
+            attrs.get("duration")
+        );
+    }
 
     // These default to "Unknown" when called without relationship context.
     // The caller overrides them from relationships after calling this function.
@@ -612,7 +843,7 @@ pub fn parse_track(id: &str, attrs: &serde_json::Value) -> Option<Track> {
         .get("artistName")
         .or_else(|| attrs.get("artist"))
         .and_then(|v| v.as_str())
-        .unwrap_or("Unknown Artist")
+        .unwrap_or(UNKNOWN_ARTIST)
         .to_string();
 
     let album_name = attrs
@@ -634,6 +865,17 @@ pub fn parse_track(id: &str, attrs: &serde_json::Value) -> Option<Track> {
         })
         .unwrap_or_default();
 
+    let release_date = attrs
+        .get("releaseDate")
+        .and_then(|v| v.as_str())
+        .and_then(parse_release_date);
+
+    // Tracks without the attribute are assumed streamable
+    let streamable = match attrs.get("availability").and_then(|v| v.as_array()) {
+        Some(modes) => modes.iter().any(|m| m.as_str() == Some("STREAM")),
+        None => true,
+    };
+
     Some(Track {
         id: id.to_string(),
         title,
@@ -656,6 +898,17 @@ pub fn parse_track(id: &str, attrs: &serde_json::Value) -> Option<Track> {
         album_id: None,
         artwork_url,
         media_tags,
+        explicit: attrs
+            .get("explicit")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false),
+        release_date,
+        is_new: is_new_release(release_date),
+        streamable,
+        language: attrs
+            .get("language")
+            .and_then(|v| v.as_str())
+            .and_then(parse_language),
     })
 }
 
@@ -681,7 +934,7 @@ pub fn resolve_track_relationships(
                 .unwrap_or("")
                 .to_string();
             if let Some(attrs) = item.get("attributes") {
-                if let Some(href) = extract_artwork_href(attrs) {
+                if let Some(href) = extract_artwork_href(attrs, DEFAULT_ARTWORK_SIZE) {
                     artwork_map.insert(id, href);
                 }
             }
@@ -722,6 +975,18 @@ pub fn resolve_track_relationships(
                     track.album_name = title.to_string();
                     track.album_id = Some(album_id);
                 }
+                track.inherit_release_date(
+                    item_attrs
+                        .and_then(|a| a.get("releaseDate"))
+                        .and_then(|v| v.as_str())
+                        .and_then(parse_release_date),
+                );
+                if track.language.is_none() {
+                    track.language = item_attrs
+                        .and_then(|a| a.get("language"))
+                        .and_then(|v| v.as_str())
+                        .and_then(parse_language);
+                }
                 if track.artwork_url.is_none() {
                     // Try coverArt relationship -> artwork_map
                     track.artwork_url = get_first_relationship_id(item_rels, "coverArt")
@@ -741,16 +1006,15 @@ pub fn parse_album(id: &str, attrs: &serde_json::Value) -> Option<Album> {
         .get("artistName")
         .or_else(|| attrs.get("artist"))
         .and_then(|v| v.as_str())
-        .unwrap_or("Unknown Artist")
+        .unwrap_or(UNKNOWN_ARTIST)
         .to_string();
 
     let artwork_url = extract_image_url(attrs);
 
     // Duration: handle ISO 8601 string or f64
-    let duration = attrs.get("duration").and_then(|v| {
-        v.as_f64()
-            .or_else(|| v.as_str().map(parse_iso8601_duration))
-    });
+    let duration = attrs
+        .get("duration")
+        .and_then(|v| parse_duration_value(v, &format!("album {}", id)));
 
     let media_tags = attrs
         .get("mediaTags")
@@ -762,6 +1026,11 @@ pub fn parse_album(id: &str, attrs: &serde_json::Value) -> Option<Album> {
         })
         .unwrap_or_default();
 
+    let release_date = attrs
+        .get("releaseDate")
+        .and_then(|v| v.as_str())
+        .and_then(parse_release_date);
+
     Some(Album {
         id: id.to_string(),
         title,
@@ -777,12 +1046,11 @@ pub fn parse_album(id: &str, attrs: &serde_json::Value) -> Option<Album> {
             .get("numberOfVolumes")
             .and_then(|v| v.as_u64())
             .map(|v| v as u32),
-        release_date: attrs
-            .get("releaseDate")
-            .and_then(|v| v.as_str())
-            .map(|s| s.to_string()),
+        release_date,
         artwork_url,
+        artwork: parse_artwork_files(attrs),
         media_tags,
+        is_new: is_new_release(release_date),
     })
 }
 
@@ -798,16 +1066,45 @@ pub fn parse_artist(id: &str, attrs: &serde_json::Value) -> Option<Artist> {
     })
 }
 
+pub fn parse_video(id: &str, attrs: &serde_json::Value) -> Option<Video> {
+    let title = attrs.get("title")?.as_str()?.to_string();
+    let duration = attrs
+        .get("duration")
+        .and_then(|v| parse_duration_value(v, &format!("video {}", id)))
+        .unwrap_or(0.0);
+
+    Some(Video {
+        id: id.to_string(),
+        title,
+        artist_name: attrs
+            .get("artistName")
+            .and_then(|v| v.as_str())
+            .unwrap_or(UNKNOWN_ARTIST)
+            .to_string(),
+        artist_id: None,
+        duration,
+        image_url: extract_image_url(attrs),
+    })
+}
+
+/// Resolve a video's artist and thumbnail from an included resources array.
+pub fn resolve_video_relationships(
+    video: &mut Video,
+    rels: Option<&serde_json::Value>,
+    included: &[serde_json::Value],
+) {
+    IncludedIndex::new(included).resolve_video(video, rels);
+}
+
 pub fn parse_playlist(id: &str, attrs: &serde_json::Value) -> Option<Playlist> {
     let name = attrs.get("name")?.as_str()?.to_string();
 
     let artwork_url = extract_image_url(attrs);
 
     // Duration: handle ISO 8601 string or f64
-    let duration = attrs.get("duration").and_then(|v| {
-        v.as_f64()
-            .or_else(|| v.as_str().map(parse_iso8601_duration))
-    });
+    let duration = attrs
+        .get("duration")
+        .and_then(|v| parse_duration_value(v, &format!("playlist {}", id)));
 
     Some(Playlist {
         id: id.to_string(),
@@ -826,6 +1123,199 @@ pub fn parse_playlist(id: &str, attrs: &serde_json::Value) -> Option<Playlist> {
             .and_then(|v| v.as_str())
             .map(|s| s.to_string()),
         artwork_url,
+        artwork: parse_artwork_files(attrs),
         creator_id: None,
+        pinned: false,
+        owned: false,
+        last_updated: attrs
+            .get("lastModifiedAt")
+            .and_then(|v| v.as_str())
+            .and_then(|v| DateTime::parse_from_rfc3339(v).ok())
+            .map(|at| at.with_timezone(&Utc)),
+        contributors: Vec::new(),
+        owner_name: None,
+        can_edit: false,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn track(id: &str) -> serde_json::Value {
+        json!({
+            "id": id,
+            "type": "tracks",
+            "attributes": { "title": format!("Track {}", id), "duration": "PT3M" },
+            "relationships": { "artists": { "data": [{ "id": "ar1", "type": "artists" }] } },
+        })
+    }
+
+    fn artist(id: &str) -> serde_json::Value {
+        json!({ "id": id, "type": "artists", "attributes": { "name": format!("Artist {}", id) } })
+    }
+
+    #[test]
+    fn iso8601_durations_parse_to_seconds() {
+        let cases: &[(&str, Option<f64>)] = &[
+            ("PT2M58S", Some(178.0)),
+            ("PT1H", Some(3600.0)),
+            ("PT1H30M", Some(5400.0)),
+            ("PT0S", Some(0.0)),
+            ("P1DT2H", Some(93_600.0)),
+            ("P1W", Some(604_800.0)),
+            ("PT1.5S", Some(1.5)),
+            ("PT1,5S", Some(1.5)),
+            ("pt3m", Some(180.0)),
+            (" PT3M ", Some(180.0)),
+            // No `T`: M is read as minutes, not months
+            ("P3M", Some(180.0)),
+            ("180", Some(180.0)),
+            ("12.25", Some(12.25)),
+            // Malformed
+            ("", None),
+            ("P", None),
+            ("PT", None),
+            ("garbage", None),
+            ("P1Y", None),
+            ("PT5", None),
+            ("-5", None),
+            ("PT1.2.3S", None),
+            ("P1DT2HX", None),
+            ("PTT1S", None),
+            ("PT1D", None),
+            ("2M58S", None),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(parse_iso8601_duration(input), *expected, "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn tracks_without_a_usable_duration_parse_with_zero() {
+        for duration in [json!(null), json!("soon"), json!("PT"), json!(0)] {
+            let attrs = json!({ "title": "Untimed", "duration": duration });
+            let track = parse_track("1", &attrs).unwrap();
+            assert_eq!(track.duration, 0.0, "{}", duration);
+        }
+        let track = parse_track("1", &json!({ "title": "No attribute" })).unwrap();
+        assert_eq!(track.duration, 0.0);
+        let track = parse_track("1", &json!({ "title": "Timed", "duration": 181.5 })).unwrap();
+        assert_eq!(track.duration, 181.5);
+    }
+
+    fn video(id: &str, title: &str) -> serde_json::Value {
+        json!({ "id": id, "type": "videos", "attributes": { "title": title, "duration": "PT4M" } })
+    }
+
+    fn item(rtype: &str, id: &str, number: u32) -> serde_json::Value {
+        json!({ "id": id, "type": rtype, "meta": { "itemNumber": number, "volumeNumber": 1 } })
+    }
+
+    #[test]
+    fn items_follow_the_data_order_not_the_included_order() {
+        let data = vec![
+            item("tracks", "1", 1),
+            item("videos", "v1", 2),
+            item("tracks", "2", 3),
+            item("tracks", "1", 4),
+        ];
+        // Included is deduplicated and in whatever order the API chose
+        let included = vec![artist("ar1"), track("2"), video("v1", "Live"), track("1")];
+
+        let (tracks, others) = parse_items_from_included(Some(&data), Some(&included));
+        let ids: Vec<&str> = tracks.iter().map(|t| t.id.as_str()).collect();
+        assert_eq!(ids, ["1", "2", "1"]);
+        assert_eq!(tracks[1].artist_name, "Artist ar1");
+        assert_eq!(others.len(), 1);
+        assert_eq!(others[0].id, "v1");
+        assert_eq!(others[0].position, 1);
+
+        // Without identifiers the included order is all there is
+        let (tracks, others) = parse_items_from_included(None, Some(&included));
+        let ids: Vec<&str> = tracks.iter().map(|t| t.id.as_str()).collect();
+        assert_eq!(ids, ["2", "1"]);
+        assert_eq!(others[0].position, 1);
+    }
+
+    #[test]
+    fn positions_count_only_the_items_that_are_shown() {
+        let data = vec![
+            item("videos", "v0", 1),
+            item("tracks", "1", 2),
+            item("videos", "v1", 3),
+            item("tracks", "2", 4),
+            item("videos", "v2", 5),
+        ];
+        // The first video has no title and is dropped
+        let included = vec![
+            json!({ "id": "v0", "type": "videos", "attributes": { "duration": "PT1M" } }),
+            track("1"),
+            video("v1", "Live"),
+            track("2"),
+            video("v2", "Interview"),
+        ];
+
+        let (tracks, others) = parse_items_from_included(Some(&data), Some(&included));
+        assert_eq!(tracks.len(), 2);
+        let positions: Vec<(&str, usize)> =
+            others.iter().map(|o| (o.id.as_str(), o.position)).collect();
+        assert_eq!(positions, [("v1", 1), ("v2", 3)]);
+    }
+
+    #[test]
+    fn search_results_keep_at_most_limit_of_each_kind() {
+        let body = json!({
+            "included": [track("1"), artist("ar1"), track("2"), artist("ar2"), track("3")],
+        });
+        let results = parse_search_results(&body, 2);
+        let ids: Vec<&str> = results.tracks.iter().map(|t| t.id.as_str()).collect();
+        assert_eq!(ids, ["1", "2"]);
+        assert_eq!(results.artists.len(), 2);
+        assert_eq!(results.tracks[0].artist_name, "Artist ar1");
+    }
+
+    #[test]
+    fn search_results_stop_once_every_listed_kind_is_full() {
+        // Only tracks are listed, so parsing stops after the first one even
+        // though there are no videos or albums to fill up
+        let body = json!({
+            "data": { "relationships": {
+                "tracks": { "data": [{ "id": "1", "type": "tracks" }] },
+                "artists": { "data": [] },
+                "albums": { "data": [] },
+                "playlists": { "data": [] },
+                "videos": { "data": [] },
+            }},
+            "included": [track("1"), track("2"), artist("ar1")],
+        });
+        let results = parse_search_results(&body, 5);
+        assert_eq!(results.tracks.len(), 1);
+        assert!(results.artists.is_empty());
+        // Relationships are still resolved from resources after the cut-off
+        assert_eq!(results.tracks[0].artist_name, "Artist ar1");
+    }
+
+    #[test]
+    fn every_listed_artist_is_main_unless_a_role_says_otherwise() {
+        let rels = json!({ "artists": { "data": [
+            { "id": "a", "type": "artists" },
+            { "id": "b", "type": "artists" },
+            { "id": "c", "type": "artists", "meta": { "role": "FEATURED" } },
+            { "id": "d", "type": "artists", "meta": { "role": "MAIN" } },
+        ] } });
+        let role = |id| artist_role(Some(&rels), id);
+        assert_eq!(role("a"), Some(ArtistRole::Main));
+        // Co-billed, not featured
+        assert_eq!(role("b"), Some(ArtistRole::Main));
+        assert_eq!(role("c"), Some(ArtistRole::Featured));
+        assert_eq!(role("d"), Some(ArtistRole::Main));
+        // Not listed at all, like a track on a compilation
+        assert_eq!(role("z"), Some(ArtistRole::Featured));
+
+        let empty = json!({ "artists": { "data": [] } });
+        assert_eq!(artist_role(Some(&empty), "a"), None);
+        assert_eq!(artist_role(None, "a"), None);
+    }
+}
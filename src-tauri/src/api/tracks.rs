@@ -1,6 +1,8 @@
 use crate::api::client::TidalClient;
-use crate::api::models::Track;
-use crate::api::search::{parse_track, resolve_track_relationships};
+use crate::api::models::{Track, TrackDetails, TrackMarker};
+use crate::api::search::{
+    parse_iso8601_duration, parse_track, parse_tracks_batch, resolve_track_relationships,
+};
 use crate::error::{AppError, AppResult};
 use base64::engine::general_purpose::STANDARD;
 use base64::Engine;
@@ -8,8 +10,86 @@ use base64::Engine;
 /// v1 API base URL for playback endpoints
 const V1_BASE_URL: &str = "https://api.tidal.com/v1";
 
+/// Ids sent per `filter[id]` request when fetching tracks in bulk.
+const TRACK_BATCH_SIZE: usize = 20;
+
+/// How many search matches `find_track_alternative` tries before giving up.
+const ALTERNATIVE_CANDIDATES: usize = 5;
+
+/// Segment markers from a track's attributes. Long tracks may list index
+/// points under one of a few names; times are seconds or ISO 8601 durations.
+/// Markers outside the track are dropped and the rest sorted by time.
+fn parse_track_markers(attrs: &serde_json::Value, duration: f64) -> Vec<TrackMarker> {
+    let Some(points) = ["chapters", "indexPoints", "segments"]
+        .iter()
+        .find_map(|key| attrs.get(*key).and_then(|v| v.as_array()))
+    else {
+        return Vec::new();
+    };
+
+    let mut markers: Vec<TrackMarker> = points
+        .iter()
+        .filter_map(|point| {
+            let time = ["time", "offset", "start"]
+                .iter()
+                .find_map(|key| point.get(*key))
+                .and_then(|v| {
+                    v.as_f64()
+                        .or_else(|| v.as_str().and_then(parse_iso8601_duration))
+                })?;
+            let label = ["title", "label", "name"]
+                .iter()
+                .find_map(|key| point.get(*key).and_then(|v| v.as_str()))
+                .map(String::from);
+            Some(TrackMarker { time, label })
+        })
+        .filter(|m| m.time >= 0.0 && (duration <= 0.0 || m.time < duration))
+        .collect();
+    markers.sort_by(|a, b| a.time.total_cmp(&b.time));
+    markers
+}
+
 impl TidalClient {
     pub async fn get_track(&self, track_id: &str) -> AppResult<Track> {
+        Ok(self.get_track_details(track_id).await?.track)
+    }
+
+    /// Several tracks at once, in the order given, through the `filter[id]`
+    /// batch endpoint. Ids the API doesn't return (removed or unavailable in
+    /// this region) are left out.
+    pub async fn get_tracks(&self, track_ids: &[String]) -> AppResult<Vec<Track>> {
+        let country = self.config().read().await.country_code.clone();
+        let fetches = track_ids.chunks(TRACK_BATCH_SIZE).map(|chunk| {
+            let ids_param = chunk.join(",");
+            let country = &country;
+            async move {
+                let response = self
+                    .get_with_query(
+                        "/tracks",
+                        &[
+                            ("filter[id]", ids_param.as_str()),
+                            ("include", "artists,albums"),
+                            ("countryCode", country.as_str()),
+                        ],
+                    )
+                    .await?;
+                let body: serde_json::Value = response.json().await?;
+                AppResult::Ok(parse_tracks_batch(&body))
+            }
+        });
+
+        let mut by_id = std::collections::HashMap::new();
+        for batch in futures_util::future::try_join_all(fetches).await? {
+            by_id.extend(batch.into_iter().map(|t| (t.id.clone(), t)));
+        }
+        Ok(track_ids
+            .iter()
+            .filter_map(|id| by_id.get(id).cloned())
+            .collect())
+    }
+
+    /// A track plus its segment markers, for long mixes and live recordings.
+    pub async fn get_track_details(&self, track_id: &str) -> AppResult<TrackDetails> {
         let config = self.config().read().await;
         let country = config.country_code.clone();
         drop(config);
@@ -42,16 +122,44 @@ impl TidalClient {
             .ok_or_else(|| AppError::NotFound(format!("Track {} not found", track_id)))?;
 
         resolve_track_relationships(&mut track, rels, included);
+        let markers = parse_track_markers(&attrs, track.duration);
+
+        Ok(TrackDetails { track, markers })
+    }
 
-        Ok(track)
+    /// Fetch the playback manifest for a track, subject to preview rules.
+    ///
+    /// Without a signed-in user only previews play; with
+    /// `allow_preview_playback` off those fail with `SubscriptionRequired`
+    /// instead.
+    pub async fn get_track_manifest(&self, track_id: &str) -> AppResult<TrackManifestData> {
+        let config = self.config().read().await;
+        let signed_in = config.user_id.is_some();
+        let allow_preview = config.allow_preview_playback;
+        drop(config);
+
+        // A client-credentials token never gets more than a preview
+        if !signed_in && !allow_preview {
+            return Err(AppError::SubscriptionRequired);
+        }
+
+        let mut manifest = self.fetch_track_manifest(track_id).await?;
+        manifest.preview |= !signed_in;
+        if manifest.preview {
+            if !allow_preview {
+                return Err(AppError::SubscriptionRequired);
+            }
+            log::warn!("Track {} will play as a preview only", track_id);
+        }
+        Ok(manifest)
     }
 
-    /// Fetch playback manifest for a track.
+    /// Fetch the raw manifest from whichever endpoint answers.
     ///
     /// Strategy:
     /// 1. Try the v2 trackManifests endpoint with uriScheme=DATA (as the official SDK does)
     /// 2. Fall back to the v1 /tracks/{id}/playbackinfo endpoint
-    pub async fn get_track_manifest(&self, track_id: &str) -> AppResult<TrackManifestData> {
+    async fn fetch_track_manifest(&self, track_id: &str) -> AppResult<TrackManifestData> {
         // Try v2 first
         match self.get_track_manifest_v2(track_id).await {
             Ok(data) => return Ok(data),
@@ -64,8 +172,78 @@ impl TidalClient {
             }
         }
 
-        // Fall back to v1
-        self.get_track_manifest_v1(track_id).await
+        // Fall back to v1, whose error body says why a track can't be played
+        match self.get_track_manifest_v1(track_id).await {
+            Err(e) if matches!(e.code(), Some("region_blocked" | "track_unavailable")) => {
+                log::warn!("Track {} is unavailable: {}", track_id, e);
+                let country = self.config().read().await.country_code.clone();
+                Err(AppError::TrackUnavailable {
+                    track_id: track_id.to_string(),
+                    country,
+                })
+            }
+            result => result,
+        }
+    }
+
+    /// Fetch the manifest for `track`. If the track isn't available in the
+    /// user's country and `substitute_unavailable` is enabled, fall back to a
+    /// playable alternative. Returns the track that will actually play.
+    pub async fn get_playable_manifest(
+        &self,
+        track: &Track,
+    ) -> AppResult<(Track, TrackManifestData)> {
+        let err = match self.get_track_manifest(&track.id).await {
+            Ok(manifest) => return Ok((track.clone(), manifest)),
+            Err(e @ AppError::TrackUnavailable { .. }) => e,
+            Err(e) => return Err(e),
+        };
+        if !self.config().read().await.substitute_unavailable {
+            return Err(err);
+        }
+
+        match self.find_track_alternative(track).await? {
+            Some(found) => {
+                log::info!(
+                    "Substituting track {} for unavailable track {}",
+                    found.0.id,
+                    track.id
+                );
+                Ok(found)
+            }
+            None => Err(err),
+        }
+    }
+
+    /// Search for another release of `track` (same ISRC, or same title and
+    /// artist) that is streamable in the user's country, with its manifest.
+    pub async fn find_track_alternative(
+        &self,
+        track: &Track,
+    ) -> AppResult<Option<(Track, TrackManifestData)>> {
+        let query = format!("{} {}", track.title, track.artist_name);
+        let results = self.search(&query, 20).await?;
+
+        let candidates = results.tracks.into_iter().filter(|t| {
+            if t.id == track.id {
+                return false;
+            }
+            match (&t.isrc, &track.isrc) {
+                (Some(a), Some(b)) if a.eq_ignore_ascii_case(b) => true,
+                _ => {
+                    t.title.eq_ignore_ascii_case(&track.title)
+                        && t.artist_name.eq_ignore_ascii_case(&track.artist_name)
+                }
+            }
+        });
+
+        for candidate in candidates.take(ALTERNATIVE_CANDIDATES) {
+            match self.get_track_manifest(&candidate.id).await {
+                Ok(manifest) => return Ok(Some((candidate, manifest))),
+                Err(e) => log::info!("Alternative {} not playable: {}", candidate.id, e),
+            }
+        }
+        Ok(None)
     }
 
     /// v2 API: GET /trackManifests/{id} with uriScheme=DATA
@@ -73,7 +251,7 @@ impl TidalClient {
     /// The SDK uses this path for browser/Shaka playback.
     async fn get_track_manifest_v2(&self, track_id: &str) -> AppResult<TrackManifestData> {
         let config = self.config().read().await;
-        let quality = config.audio_quality.clone();
+        let quality = self.manifest_quality(&config);
         drop(config);
 
         let formats = match quality.as_str() {
@@ -120,7 +298,12 @@ impl TidalClient {
             .unwrap_or("AACLC");
 
         // Parse the data URL: data:{mime};base64,{content}
-        parse_data_url_manifest(data_uri, codec_from_formats)
+        let manifest = parse_data_url_manifest(data_uri, codec_from_formats)?;
+        Ok(TrackManifestData {
+            preview: is_preview(attrs),
+            audio_quality: format_audio_quality(codec_from_formats).map(String::from),
+            ..manifest
+        })
     }
 
     /// v1 API: GET /tracks/{id}/playbackinfo
@@ -128,7 +311,7 @@ impl TidalClient {
     /// Used as fallback and for native player scenarios.
     async fn get_track_manifest_v1(&self, track_id: &str) -> AppResult<TrackManifestData> {
         let config = self.config().read().await;
-        let quality = config.audio_quality.clone();
+        let quality = self.manifest_quality(&config);
         let token = config.access_token.clone();
         let client_id = config.client_id.clone();
         drop(config);
@@ -217,6 +400,9 @@ impl TidalClient {
             .get("audioQuality")
             .and_then(|v| v.as_str())
             .unwrap_or("HIGH");
+        let preview = is_preview(&body);
+        let bit_depth = u32_field(&body, "bitDepth");
+        let sample_rate = u32_field(&body, "sampleRate");
 
         if manifest_mime == "application/vnd.tidal.bts" {
             let bts: serde_json::Value = serde_json::from_str(&manifest_str)?;
@@ -260,7 +446,14 @@ impl TidalClient {
                 &uri[..uri.len().min(80)],
                 codec
             );
-            Ok(TrackManifestData { uri, codec })
+            Ok(TrackManifestData {
+                uri,
+                codec,
+                preview,
+                audio_quality: Some(audio_quality_str.to_string()),
+                bit_depth,
+                sample_rate,
+            })
         } else if manifest_mime == "application/vnd.tidal.emu" {
             // EMU manifest: similar to BTS but simpler
             let emu: serde_json::Value = serde_json::from_str(&manifest_str)?;
@@ -278,7 +471,14 @@ impl TidalClient {
                 &uri[..uri.len().min(80)],
                 codec
             );
-            Ok(TrackManifestData { uri, codec })
+            Ok(TrackManifestData {
+                uri,
+                codec,
+                preview,
+                audio_quality: Some(audio_quality_str.to_string()),
+                bit_depth,
+                sample_rate,
+            })
         } else if manifest_mime == "application/dash+xml" {
             let uri = extract_dash_base_url(&manifest_str).ok_or_else(|| {
                 AppError::Decode("Could not extract URL from DASH manifest".into())
@@ -289,7 +489,14 @@ impl TidalClient {
                 &uri[..uri.len().min(80)],
                 codec
             );
-            Ok(TrackManifestData { uri, codec })
+            Ok(TrackManifestData {
+                uri,
+                codec,
+                preview,
+                audio_quality: Some(audio_quality_str.to_string()),
+                bit_depth,
+                sample_rate,
+            })
         } else {
             log::error!("Unsupported manifest type: {}", manifest_mime);
             log::debug!(
@@ -313,6 +520,45 @@ impl TidalClient {
 pub struct TrackManifestData {
     pub uri: String,
     pub codec: String,
+    /// Only a 30-second preview will play, e.g. without a signed-in
+    /// subscriber
+    pub preview: bool,
+    /// Quality tier delivered, e.g. LOSSLESS or HI_RES_LOSSLESS. "FLAC" alone
+    /// covers both 16/44.1 and 24/192.
+    pub audio_quality: Option<String>,
+    /// Bit depth and sample rate the manifest claims, when it says. The
+    /// decoder's values replace them once the stream is probed.
+    pub bit_depth: Option<u32>,
+    pub sample_rate: Option<u32>,
+}
+
+/// Quality tier a v2 manifest format is delivered at.
+fn format_audio_quality(format: &str) -> Option<&'static str> {
+    match format.to_uppercase().as_str() {
+        "FLAC_HIRES" => Some("HI_RES_LOSSLESS"),
+        "FLAC" => Some("LOSSLESS"),
+        "AACLC" => Some("HIGH"),
+        "HEAACV1" => Some("LOW"),
+        _ => None,
+    }
+}
+
+/// A positive integer field of a playback info response.
+fn u32_field(body: &serde_json::Value, key: &str) -> Option<u32> {
+    body.get(key)
+        .and_then(|v| v.as_u64())
+        .and_then(|v| u32::try_from(v).ok())
+        .filter(|v| *v > 0)
+}
+
+/// Whether a manifest response describes a preview clip rather than the
+/// full track.
+fn is_preview(attrs: &serde_json::Value) -> bool {
+    let presentation = ["assetPresentation", "trackPresentation", "presentation"]
+        .iter()
+        .filter_map(|key| attrs.get(*key).and_then(|v| v.as_str()))
+        .any(|v| v.eq_ignore_ascii_case("PREVIEW"));
+    presentation || attrs.get("previewReason").is_some_and(|v| !v.is_null())
 }
 
 /// Parse a data URL (data:{mime};base64,{content}) into a TrackManifestData.
@@ -333,6 +579,10 @@ fn parse_data_url_manifest(data_uri: &str, fallback_codec: &str) -> AppResult<Tr
         return Ok(TrackManifestData {
             uri: data_uri.to_string(),
             codec: fallback_codec.to_string(),
+            preview: false,
+            audio_quality: None,
+            bit_depth: None,
+            sample_rate: None,
         });
     } else {
         return Err(AppError::Decode(
@@ -367,7 +617,14 @@ fn parse_data_url_manifest(data_uri: &str, fallback_codec: &str) -> AppResult<Tr
                 .and_then(|v| v.as_str())
                 .unwrap_or(fallback_codec)
                 .to_string();
-            Ok(TrackManifestData { uri, codec })
+            Ok(TrackManifestData {
+                uri,
+                codec,
+                preview: false,
+                audio_quality: None,
+                bit_depth: None,
+                sample_rate: None,
+            })
         }
         "application/dash+xml" => {
             // DASH: extract BaseURL from MPD XML
@@ -377,12 +634,23 @@ fn parse_data_url_manifest(data_uri: &str, fallback_codec: &str) -> AppResult<Tr
             // Try to extract codec from DASH Representation
             let codec =
                 extract_dash_codec(&manifest_str).unwrap_or_else(|| fallback_codec.to_string());
+            // DASH has no bit depth attribute; the decoder fills it in
+            let sample_rate = extract_dash_attribute(&manifest_str, "audioSamplingRate")
+                .and_then(|rate| rate.parse().ok());
             log::info!(
-                "v2 DASH: uri={}..., codec={}",
+                "v2 DASH: uri={}..., codec={}, sample_rate={:?}",
                 &uri[..uri.len().min(80)],
-                codec
+                codec,
+                sample_rate
             );
-            Ok(TrackManifestData { uri, codec })
+            Ok(TrackManifestData {
+                uri,
+                codec,
+                preview: false,
+                audio_quality: None,
+                bit_depth: None,
+                sample_rate,
+            })
         }
         "application/vnd.apple.mpegurl" => {
             // HLS: extract first segment URL
@@ -390,7 +658,14 @@ fn parse_data_url_manifest(data_uri: &str, fallback_codec: &str) -> AppResult<Tr
                 AppError::Decode("Could not extract URL from HLS manifest".into())
             })?;
             let codec = fallback_codec.to_string();
-            Ok(TrackManifestData { uri, codec })
+            Ok(TrackManifestData {
+                uri,
+                codec,
+                preview: false,
+                audio_quality: None,
+                bit_depth: None,
+                sample_rate: None,
+            })
         }
         _ => Err(AppError::Decode(format!(
             "Unsupported data URL mime type: {}",
@@ -411,9 +686,20 @@ fn extract_dash_base_url(mpd_xml: &str) -> Option<String> {
 /// Extract codec from a DASH Representation element.
 fn extract_dash_codec(mpd_xml: &str) -> Option<String> {
     // Look for codecs="..." in a Representation element
-    let codecs_start = mpd_xml.find("codecs=\"")? + 8;
-    let codecs_end = mpd_xml[codecs_start..].find('"')? + codecs_start;
-    Some(mpd_xml[codecs_start..codecs_end].to_string())
+    extract_dash_attribute(mpd_xml, "codecs")
+}
+
+/// Extract the first `name="..."` attribute value from a DASH MPD. The
+/// name must follow whitespace, whichever kind separates the attributes,
+/// so it doesn't match the end of a longer name.
+fn extract_dash_attribute(mpd_xml: &str, name: &str) -> Option<String> {
+    let needle = format!("{}=\"", name);
+    let (at, _) = mpd_xml
+        .match_indices(&needle)
+        .find(|(at, _)| mpd_xml[..*at].ends_with(|c: char| c.is_ascii_whitespace()))?;
+    let start = at + needle.len();
+    let end = mpd_xml[start..].find('"')? + start;
+    Some(mpd_xml[start..end].to_string())
 }
 
 /// Extract a stream URL from an HLS playlist.
@@ -426,3 +712,23 @@ fn extract_hls_url(hls: &str) -> Option<String> {
     }
     None
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dash_attributes_are_found_after_any_whitespace() {
+        let mpd = "<Representation id=\"FLAC,0\"\n\tcodecs=\"flac\"\r\n  \
+                   audioSamplingRate=\"96000\"\tbandwidth=\"3000\">";
+        let attribute = |name| extract_dash_attribute(mpd, name);
+        assert_eq!(attribute("codecs"), Some("flac".into()));
+        assert_eq!(attribute("audioSamplingRate"), Some("96000".into()));
+        assert_eq!(attribute("bandwidth"), Some("3000".into()));
+        assert_eq!(attribute("mimeType"), None);
+
+        // Only whole attribute names match
+        let mpd = "<AdaptationSet xcodecs=\"mp4a\" codecs=\"flac\">";
+        assert_eq!(extract_dash_attribute(mpd, "codecs"), Some("flac".into()));
+    }
+}
@@ -1,8 +1,104 @@
 use crate::api::client::TidalClient;
-use crate::api::models::{FavoritesPage, RecommendationSection, Track};
-use crate::api::search::parse_tracks_from_included;
+use crate::api::models::{
+    is_new_release, parse_release_date, Artist, FavoritesPage, HomeSectionKind, PublicProfile,
+    RecommendationSection, Track, UserProfile, DEFAULT_ARTWORK_SIZE,
+};
+use crate::api::search::{
+    extract_artwork_href, get_first_relationship_id, parse_artist, parse_tracks_from_included,
+};
+use crate::discovery_seeds::DiscoverySeeds;
 use crate::error::{AppError, AppResult};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+
+/// Mixes fetched for each mix section on Home.
+const MIXES_PER_SECTION: usize = 6;
+
+/// Relationships of `/userRecommendations/me` holding the mixes of every mix
+/// section, fetched together for one Home page.
+const MIX_RELATIONSHIPS: &str = "myMixes,discoveryMixes,newArrivalMixes";
+
+/// Favorites the "Because you like" rows on Home are built from.
+const DISCOVERY_SEEDS: usize = 4;
+
+/// Tracks shown in the "Your Favorites" row on Home.
+const FAVORITES_TEASER_LEN: usize = 10;
+
+/// Pages of favorite artists fetched before giving up on the rest.
+const MAX_FAVORITE_ARTIST_PAGES: usize = 20;
+
+/// Pages of favorite track ids fetched before giving up on the rest.
+const MAX_FAVORITE_ID_PAGES: usize = 500;
+
+/// What the sections of one Home page share.
+#[derive(Default)]
+pub struct HomeFetch {
+    /// Track ids from the local play history, most recent first
+    recently_played: Vec<String>,
+    /// The first page of favorites, fetched by whichever section needs it first
+    favorites: tokio::sync::OnceCell<Vec<Track>>,
+    /// The user's recommended mixes, fetched by whichever mix section needs
+    /// them first
+    recommendations: tokio::sync::OnceCell<serde_json::Value>,
+}
+
+impl HomeFetch {
+    pub fn new(recently_played: Vec<String>) -> Self {
+        Self {
+            recently_played,
+            ..Self::default()
+        }
+    }
+}
+
+/// Size of a whole collection from a page's meta, when the response has one.
+fn collection_total(body: &serde_json::Value) -> Option<u32> {
+    let metas = [
+        body.get("meta"),
+        body.get("links").and_then(|l| l.get("meta")),
+    ];
+    metas
+        .into_iter()
+        .flatten()
+        .find_map(|meta| {
+            ["total", "totalNumberOfItems", "count"]
+                .iter()
+                .find_map(|key| meta.get(*key)?.as_u64())
+        })
+        .and_then(|total| u32::try_from(total).ok())
+}
+
+/// Parse a `users` resource, resolving the profile picture through the
+/// included artworks. Fields the user doesn't share are None.
+fn parse_user_profile(body: &serde_json::Value) -> UserProfile {
+    let data = body.get("data");
+    let attrs = data.and_then(|d| d.get("attributes"));
+    let string_attr = |key: &str| {
+        attrs
+            .and_then(|a| a.get(key))
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string())
+    };
+
+    let picture_id =
+        get_first_relationship_id(data.and_then(|d| d.get("relationships")), "profilePicture");
+    let avatar_url = picture_id.and_then(|id| {
+        body.get("included")
+            .and_then(|v| v.as_array())?
+            .iter()
+            .find(|item| item.get("id").and_then(|v| v.as_str()) == Some(id.as_str()))
+            .and_then(|item| item.get("attributes"))
+            .and_then(|attrs| extract_artwork_href(attrs, DEFAULT_ARTWORK_SIZE))
+    });
+
+    UserProfile {
+        username: string_attr("username"),
+        first_name: string_attr("firstName"),
+        last_name: string_attr("lastName"),
+        email: string_attr("email"),
+        avatar_url,
+        country: string_attr("country"),
+    }
+}
 
 /// Parse tracks from a v1 API mix items response.
 /// The v1 format has { items: [{ item: { id, title, duration, artists: [...], album: {...} }, type: "track" }] }
@@ -89,6 +185,12 @@ fn parse_v1_mix_items(body: &serde_json::Value) -> Vec<Track> {
                 )
             });
 
+        let release_date = album
+            .and_then(|a| a.get("releaseDate"))
+            .or_else(|| item.get("streamStartDate"))
+            .and_then(|v| v.as_str())
+            .and_then(parse_release_date);
+
         tracks.push(Track {
             id,
             title,
@@ -102,6 +204,16 @@ fn parse_v1_mix_items(body: &serde_json::Value) -> Vec<Track> {
             album_id,
             artwork_url,
             media_tags: Vec::new(),
+            explicit: item
+                .get("explicit")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false),
+            release_date,
+            is_new: is_new_release(release_date),
+            streamable: ["allowStreaming", "streamReady"]
+                .iter()
+                .all(|key| item.get(*key).and_then(|v| v.as_bool()).unwrap_or(true)),
+            language: None,
         });
     }
 
@@ -109,32 +221,50 @@ fn parse_v1_mix_items(body: &serde_json::Value) -> Vec<Track> {
 }
 
 impl TidalClient {
-    /// Fetch the authenticated user's profile from GET /users/me.
-    /// Returns (username, firstName, lastName) if available.
-    pub async fn get_user_profile(
-        &self,
-    ) -> AppResult<(Option<String>, Option<String>, Option<String>)> {
-        let response = self.get("/users/me").await?;
+    /// Fetch the authenticated user's profile from GET /users/me, resolving
+    /// the profile picture through the included artworks.
+    pub async fn get_user_profile(&self) -> AppResult<UserProfile> {
+        let response = self
+            .get_with_query("/users/me", &[("include", "profilePicture")])
+            .await?;
         let body: serde_json::Value = response.json().await?;
+        Ok(parse_user_profile(&body))
+    }
 
-        let attrs = body.get("data").and_then(|d| d.get("attributes"));
-
-        let username = attrs
-            .and_then(|a| a.get("username"))
-            .and_then(|v| v.as_str())
-            .map(|s| s.to_string());
-
-        let first_name = attrs
-            .and_then(|a| a.get("firstName"))
-            .and_then(|v| v.as_str())
-            .map(|s| s.to_string());
-
-        let last_name = attrs
-            .and_then(|a| a.get("lastName"))
-            .and_then(|v| v.as_str())
-            .map(|s| s.to_string());
+    /// Another user's name and avatar from GET /users/{id}, cached for the
+    /// session. Private and unknown users (403/404) get a profile without
+    /// either instead of an error.
+    pub async fn get_user_public_profile(&self, user_id: &str) -> AppResult<PublicProfile> {
+        if let Some(profile) = self.cached_public_profile(user_id) {
+            return Ok(profile);
+        }
 
-        Ok((username, first_name, last_name))
+        let path = format!("/users/{}", user_id);
+        let profile = match self
+            .get_with_query(&path, &[("include", "profilePicture")])
+            .await
+        {
+            Ok(response) => {
+                let body: serde_json::Value = response.json().await?;
+                let user = parse_user_profile(&body);
+                PublicProfile {
+                    id: user_id.to_string(),
+                    name: user.display_name(),
+                    avatar_url: user.avatar_url,
+                }
+            }
+            Err(e) if matches!(e.status(), Some(403 | 404)) => {
+                log::debug!("Profile of user {} is not public: {}", user_id, e);
+                PublicProfile {
+                    id: user_id.to_string(),
+                    name: None,
+                    avatar_url: None,
+                }
+            }
+            Err(e) => return Err(e),
+        };
+        self.cache_public_profile(profile.clone());
+        Ok(profile)
     }
 
     /// Fetch favorites using cursor-based pagination.
@@ -176,9 +306,79 @@ impl TidalClient {
             tracks,
             next_cursor,
             has_more,
+            total: collection_total(&body),
         })
     }
 
+    /// Number of favorite tracks, from the meta of an id-only first page.
+    /// None when the response doesn't carry a total.
+    pub async fn get_favorites_count(&self) -> AppResult<Option<u32>> {
+        self.get_collection_count("tracks").await
+    }
+
+    /// Size of one relationship of the user's collection (tracks, albums,
+    /// artists), from the meta of an id-only first page.
+    async fn get_collection_count(&self, relationship: &str) -> AppResult<Option<u32>> {
+        let config = self.config().read().await;
+        let user_id = config.user_id.clone().ok_or(AppError::AuthRequired)?;
+        let country = config.country_code.clone();
+        drop(config);
+
+        let path = format!(
+            "/userCollections/{}/relationships/{}",
+            user_id, relationship
+        );
+        let response = self
+            .get_with_query(&path, &[("countryCode", country.as_str())])
+            .await?;
+        let body: serde_json::Value = response.json().await?;
+        Ok(collection_total(&body))
+    }
+
+    /// Ids of every favorite track. Pages carry no included resources, so
+    /// this is much lighter than paging through `get_favorites`.
+    pub async fn get_favorite_track_ids(&self) -> AppResult<Vec<String>> {
+        let config = self.config().read().await;
+        let user_id = config.user_id.clone().ok_or(AppError::AuthRequired)?;
+        let country = config.country_code.clone();
+        drop(config);
+
+        let path = format!("/userCollections/{}/relationships/tracks", user_id);
+        let mut ids = Vec::new();
+        let mut cursor: Option<String> = None;
+        for _ in 0..MAX_FAVORITE_ID_PAGES {
+            let mut params: Vec<(&str, &str)> = vec![("countryCode", country.as_str())];
+            if let Some(c) = cursor.as_deref() {
+                params.push(("page[cursor]", c));
+            }
+            let response = self.get_with_query(&path, &params).await?;
+            let body: serde_json::Value = response.json().await?;
+
+            if let Some(items) = body.get("data").and_then(|v| v.as_array()) {
+                ids.extend(
+                    items
+                        .iter()
+                        .filter_map(|item| item.get("id")?.as_str().map(String::from)),
+                );
+            }
+
+            cursor = body
+                .get("links")
+                .and_then(|l| l.get("meta"))
+                .and_then(|m| m.get("nextCursor"))
+                .and_then(|v| v.as_str())
+                .map(String::from);
+            if cursor.is_none() {
+                return Ok(ids);
+            }
+        }
+        log::warn!(
+            "Stopped listing favorite track ids after {} pages",
+            MAX_FAVORITE_ID_PAGES
+        );
+        Ok(ids)
+    }
+
     pub async fn toggle_favorite(&self, track_id: &str, add: bool) -> AppResult<()> {
         let config = self.config().read().await;
         let user_id = config.user_id.clone().ok_or(AppError::AuthRequired)?;
@@ -201,58 +401,122 @@ impl TidalClient {
         Ok(())
     }
 
-    pub async fn get_recommendations(&self) -> AppResult<Vec<RecommendationSection>> {
+    /// Every artist in the user's collection, following cursor pages up to
+    /// `MAX_FAVORITE_ARTIST_PAGES`.
+    pub async fn get_favorite_artists(&self) -> AppResult<Vec<Artist>> {
         let config = self.config().read().await;
+        let user_id = config.user_id.clone().ok_or(AppError::AuthRequired)?;
         let country = config.country_code.clone();
-        let token = config.access_token.clone();
         drop(config);
 
-        let token = token.ok_or(AppError::AuthRequired)?;
+        let path = format!("/userCollections/{}/relationships/artists", user_id);
+        let mut artists = Vec::new();
+        let mut cursor: Option<String> = None;
+        for _ in 0..MAX_FAVORITE_ARTIST_PAGES {
+            let mut params: Vec<(&str, &str)> =
+                vec![("countryCode", country.as_str()), ("include", "artists")];
+            if let Some(c) = cursor.as_deref() {
+                params.push(("page[cursor]", c));
+            }
+            let response = self.get_with_query(&path, &params).await?;
+            let body: serde_json::Value = response.json().await?;
+
+            if let Some(items) = body.get("included").and_then(|v| v.as_array()) {
+                artists.extend(
+                    items
+                        .iter()
+                        .filter(|item| item.get("type").and_then(|v| v.as_str()) == Some("artists"))
+                        .filter_map(|item| {
+                            let id = item.get("id")?.as_str()?;
+                            parse_artist(id, item.get("attributes")?)
+                        }),
+                );
+            }
 
-        // Step 1: Try userRecommendations API for personalized mixes
-        let mix_sections = self.fetch_recommendation_mixes(&token, &country).await;
+            cursor = body
+                .get("links")
+                .and_then(|l| l.get("meta"))
+                .and_then(|m| m.get("nextCursor"))
+                .and_then(|v| v.as_str())
+                .map(String::from);
+            if cursor.is_none() {
+                break;
+            }
+        }
+        Ok(artists)
+    }
 
-        if !mix_sections.is_empty() {
-            return Ok(mix_sections);
+    /// Fetch the sections for one kind of Home row. Sections fetched for the
+    /// same page share `home`.
+    pub async fn get_home_section(
+        &self,
+        kind: HomeSectionKind,
+        home: &HomeFetch,
+    ) -> AppResult<Vec<RecommendationSection>> {
+        match kind {
+            HomeSectionKind::MyMixes
+            | HomeSectionKind::Discovery
+            | HomeSectionKind::NewArrivals => self.fetch_recommendation_mixes(kind, home).await,
+            HomeSectionKind::BecauseYouLike => self.fetch_because_you_like(home).await,
+            HomeSectionKind::FavoritesTeaser => self.fetch_favorites_teaser(home).await,
+            HomeSectionKind::RecentlyPlayed => self.fetch_recently_played(home).await,
         }
+    }
 
-        log::info!("No recommendation mixes available, building discovery from favorites");
+    /// The first page of favorites, fetched once per Home page however many
+    /// sections need it.
+    async fn home_favorites<'a>(&self, home: &'a HomeFetch) -> AppResult<&'a [Track]> {
+        let favorites = home
+            .favorites
+            .get_or_try_init(|| async { Ok::<_, AppError>(self.get_favorites(None).await?.tracks) })
+            .await?;
+        Ok(favorites)
+    }
 
-        // Step 2: Build discovery sections from similar tracks to user's favorites
-        self.build_discovery_from_favorites().await
+    /// The user's recommended mixes of every kind, fetched once per Home
+    /// page however many mix sections it shows.
+    async fn home_recommendations<'a>(
+        &self,
+        home: &'a HomeFetch,
+    ) -> AppResult<&'a serde_json::Value> {
+        home.recommendations
+            .get_or_try_init(|| async {
+                let country = self.config().read().await.country_code.clone();
+                let response = self
+                    .get_with_query(
+                        "/userRecommendations/me",
+                        &[
+                            ("countryCode", country.as_str()),
+                            ("include", MIX_RELATIONSHIPS),
+                        ],
+                    )
+                    .await?;
+                Ok::<_, AppError>(response.json().await?)
+            })
+            .await
     }
 
-    /// Fetch personalized mixes from the userRecommendations endpoint and v1 mix items API.
-    /// Returns sections with mix names as titles. Returns empty vec on any failure.
+    /// Take one kind of personalized mix from the userRecommendations
+    /// endpoint, then fetch each mix's tracks from the v1 mix items API.
+    /// Sections use the mix names as titles.
     async fn fetch_recommendation_mixes(
         &self,
-        token: &str,
-        country: &str,
-    ) -> Vec<RecommendationSection> {
-        let response = self
-            .get_with_query(
-                "/userRecommendations/me",
-                &[
-                    ("countryCode", country),
-                    ("include", "discoveryMixes,myMixes,newArrivalMixes"),
-                ],
-            )
-            .await;
-
-        let body: serde_json::Value = match response {
-            Ok(r) => match r.json().await {
-                Ok(b) => b,
-                Err(e) => {
-                    log::warn!("Failed to parse userRecommendations response: {}", e);
-                    return Vec::new();
-                }
-            },
-            Err(e) => {
-                log::warn!("userRecommendations request failed: {}", e);
-                return Vec::new();
-            }
+        kind: HomeSectionKind,
+        home: &HomeFetch,
+    ) -> AppResult<Vec<RecommendationSection>> {
+        let Some(rel_key) = kind.mix_relationship() else {
+            return Ok(Vec::new());
         };
 
+        let config = self.config().read().await;
+        let country = config.country_code.clone();
+        let token = config.access_token.clone();
+        drop(config);
+
+        let token = token.ok_or(AppError::AuthRequired)?;
+
+        let body = self.home_recommendations(home).await?;
+
         // Build a map of mix_id -> (title, subtitle) from included resources
         let mut mix_info: HashMap<String, (String, Option<String>)> = HashMap::new();
         if let Some(included) = body.get("included").and_then(|v| v.as_array()) {
@@ -296,152 +560,161 @@ impl TidalClient {
             }
         }
 
-        log::info!(
-            "[fetch_recommendation_mixes] mix_info has {} entries",
-            mix_info.len()
-        );
-
-        // Collect mix IDs from relationships, preserving category order
+        // Collect mix IDs from the relationship, preserving order. The
+        // included mixes are those of every kind, so only the relationship
+        // says which belong to this section.
         let mut mix_ids: Vec<String> = Vec::new();
-        if let Some(data) = body.get("data") {
-            for rel_key in &["myMixes", "discoveryMixes", "newArrivalMixes"] {
-                if let Some(refs) = data
-                    .get("relationships")
-                    .and_then(|r| r.get(*rel_key))
-                    .and_then(|r| r.get("data"))
-                    .and_then(|d| d.as_array())
-                {
-                    for r in refs {
-                        if let Some(id) = r.get("id").and_then(|v| v.as_str()) {
-                            let found = mix_info.contains_key(id);
-                            log::trace!(
-                                "[fetch_recommendation_mixes] rel={}, id={}, in_mix_info={}",
-                                rel_key,
-                                id,
-                                found
-                            );
-                            if !mix_ids.contains(&id.to_string()) {
-                                mix_ids.push(id.to_string());
-                            }
-                        }
+        if let Some(refs) = body
+            .get("data")
+            .and_then(|d| d.get("relationships"))
+            .and_then(|r| r.get(rel_key))
+            .and_then(|r| r.get("data"))
+            .and_then(|d| d.as_array())
+        {
+            for r in refs {
+                if let Some(id) = r.get("id").and_then(|v| v.as_str()) {
+                    if !mix_ids.contains(&id.to_string()) {
+                        mix_ids.push(id.to_string());
                     }
                 }
             }
         }
 
-        if mix_ids.is_empty() {
-            mix_ids = mix_info.keys().cloned().collect();
-        }
-
         log::info!(
-            "[fetch_recommendation_mixes] {} mix IDs collected, {} in mix_info",
+            "[fetch_recommendation_mixes] {}: {} mix IDs collected, {} in mix_info",
+            rel_key,
             mix_ids.len(),
             mix_info.len()
         );
 
-        if mix_ids.is_empty() {
-            return Vec::new();
-        }
-
-        let max_mixes = mix_ids.len().min(6);
-        let mut sections: Vec<RecommendationSection> = Vec::new();
-
-        for (i, mix_id) in mix_ids[..max_mixes].iter().enumerate() {
-            let url = format!("https://api.tidal.com/v1/mixes/{}/items", mix_id);
-            let resp = self
-                .http_client()
-                .get(&url)
-                .bearer_auth(token)
-                .query(&[("countryCode", country), ("limit", "15")])
-                .send()
-                .await;
-
-            match resp {
-                Ok(r) if r.status().is_success() => {
-                    if let Ok(body) = r.json::<serde_json::Value>().await {
-                        let tracks = parse_v1_mix_items(&body);
-                        if !tracks.is_empty() {
-                            let (title, subtitle) = mix_info
-                                .get(mix_id)
-                                .cloned()
-                                .unwrap_or_else(|| (format!("Mix {}", i + 1), None));
-                            sections.push(RecommendationSection {
-                                title,
-                                subtitle,
-                                tracks,
-                            });
-                        }
-                    }
-                }
-                Ok(r) => {
-                    log::warn!("v1 mix items for {} failed: {}", mix_id, r.status());
+        mix_ids.truncate(MIXES_PER_SECTION);
+        let fetches = mix_ids
+            .iter()
+            .map(|mix_id| self.fetch_mix_items(mix_id, &token, &country));
+        let results = futures_util::future::join_all(fetches).await;
+
+        let sections = mix_ids
+            .iter()
+            .zip(results)
+            .enumerate()
+            .filter(|(_, (_, tracks))| !tracks.is_empty())
+            .map(|(i, (mix_id, tracks))| {
+                let (title, subtitle) = mix_info
+                    .get(mix_id)
+                    .cloned()
+                    .unwrap_or_else(|| (format!("Mix {}", i + 1), None));
+                RecommendationSection {
+                    title,
+                    subtitle,
+                    tracks,
                 }
+            })
+            .collect();
+
+        Ok(sections)
+    }
+
+    /// Tracks of one mix from the v1 mix items API. Empty on any failure.
+    async fn fetch_mix_items(&self, mix_id: &str, token: &str, country: &str) -> Vec<Track> {
+        let url = format!("https://api.tidal.com/v1/mixes/{}/items", mix_id);
+        let resp = self
+            .http_client()
+            .get(&url)
+            .bearer_auth(token)
+            .query(&[("countryCode", country), ("limit", "15")])
+            .send()
+            .await;
+
+        match resp {
+            Ok(r) if r.status().is_success() => match r.json::<serde_json::Value>().await {
+                Ok(body) => parse_v1_mix_items(&body),
                 Err(e) => {
-                    log::warn!("v1 mix items for {} failed: {}", mix_id, e);
+                    log::warn!("v1 mix items for {} unreadable: {}", mix_id, e);
+                    Vec::new()
                 }
+            },
+            Ok(r) => {
+                log::warn!("v1 mix items for {} failed: {}", mix_id, r.status());
+                Vec::new()
+            }
+            Err(e) => {
+                log::warn!("v1 mix items for {} failed: {}", mix_id, e);
+                Vec::new()
             }
         }
-
-        sections
     }
 
-    /// Build discovery sections by fetching similar tracks for the user's top favorites.
-    async fn build_discovery_from_favorites(&self) -> AppResult<Vec<RecommendationSection>> {
-        let page = self.get_favorites(None).await?;
-        let favorites = page.tracks;
+    /// "Because you like" sections of similar tracks for a few of the user's
+    /// favorites. The seeds are kept for a day (see `DiscoverySeeds`).
+    async fn fetch_because_you_like(
+        &self,
+        home: &HomeFetch,
+    ) -> AppResult<Vec<RecommendationSection>> {
+        let favorites = self.home_favorites(home).await?;
 
         if favorites.is_empty() {
             return Ok(Vec::new());
         }
 
-        // Pick up to 4 seed tracks spread across the favorites list
-        let seed_count = favorites.len().min(4);
-        let step = if favorites.len() > seed_count {
-            favorites.len() / seed_count
-        } else {
-            1
-        };
-        let seeds: Vec<&Track> = favorites.iter().step_by(step).take(seed_count).collect();
-
-        let mut sections: Vec<RecommendationSection> = Vec::new();
-
-        for seed in &seeds {
-            match self.get_similar_tracks(&seed.id).await {
-                Ok(similar) if !similar.is_empty() => {
-                    sections.push(RecommendationSection {
-                        title: format!("Because you like {}", seed.title),
-                        subtitle: Some(seed.artist_name.clone()),
-                        tracks: similar.into_iter().take(10).collect(),
-                    });
-                }
-                Ok(_) => {}
-                Err(e) => {
-                    log::warn!("Failed to get similar tracks for {}: {}", seed.id, e);
-                }
+        let mut stored = DiscoverySeeds::load().unwrap_or_else(|e| {
+            log::warn!("Failed to load discovery seeds: {}", e);
+            DiscoverySeeds::default()
+        });
+        let (seeds, rotated) = stored.choose(favorites, DISCOVERY_SEEDS);
+        if rotated {
+            log::info!("Rotated discovery seeds: {:?}", stored.track_ids);
+            if let Err(e) = stored.save() {
+                log::warn!("Failed to save discovery seeds: {}", e);
             }
         }
 
-        if !sections.is_empty() {
-            let teaser: Vec<Track> = favorites.into_iter().take(10).collect();
-            if !teaser.is_empty() {
-                sections.push(RecommendationSection {
-                    title: "Your Favorites".to_string(),
-                    subtitle: None,
-                    tracks: teaser,
-                });
-            }
-        } else {
-            let tracks: Vec<Track> = favorites.into_iter().take(20).collect();
-            if !tracks.is_empty() {
-                sections.push(RecommendationSection {
-                    title: "Your Favorites".to_string(),
-                    subtitle: None,
-                    tracks,
-                });
-            }
+        let results = futures_util::future::join_all(
+            seeds.iter().map(|seed| self.get_similar_tracks(&seed.id)),
+        )
+        .await;
+
+        Ok(because_you_like_sections(&seeds, results, favorites))
+    }
+
+    /// A short "Your Favorites" row from the start of the user's favorites.
+    async fn fetch_favorites_teaser(
+        &self,
+        home: &HomeFetch,
+    ) -> AppResult<Vec<RecommendationSection>> {
+        let favorites = self.home_favorites(home).await?;
+        let tracks: Vec<Track> = favorites
+            .iter()
+            .take(FAVORITES_TEASER_LEN)
+            .cloned()
+            .collect();
+
+        if tracks.is_empty() {
+            return Ok(Vec::new());
         }
+        Ok(vec![RecommendationSection {
+            title: "Your Favorites".to_string(),
+            subtitle: None,
+            tracks,
+        }])
+    }
 
-        Ok(sections)
+    /// A "Recently Played" row from the local play history.
+    async fn fetch_recently_played(
+        &self,
+        home: &HomeFetch,
+    ) -> AppResult<Vec<RecommendationSection>> {
+        if home.recently_played.is_empty() {
+            return Ok(Vec::new());
+        }
+        let tracks = self.get_tracks(&home.recently_played).await?;
+        if tracks.is_empty() {
+            return Ok(Vec::new());
+        }
+        Ok(vec![RecommendationSection {
+            title: "Recently Played".to_string(),
+            subtitle: None,
+            tracks,
+        }])
     }
 
     pub async fn get_similar_tracks(&self, track_id: &str) -> AppResult<Vec<Track>> {
@@ -466,3 +739,276 @@ impl TidalClient {
         Ok(parse_tracks_from_included(included))
     }
 }
+
+/// One "Because you like" section per seed with similar tracks, in seed order.
+/// A track shown in an earlier section is left out of later ones, as are the
+/// favorites themselves; a seed with nothing new left gets no section.
+fn because_you_like_sections(
+    seeds: &[&Track],
+    results: Vec<AppResult<Vec<Track>>>,
+    favorites: &[Track],
+) -> Vec<RecommendationSection> {
+    let mut seen: HashSet<String> = favorites.iter().map(|t| t.id.clone()).collect();
+    let mut sections: Vec<RecommendationSection> = Vec::new();
+    for (seed, result) in seeds.iter().zip(results) {
+        match result {
+            Ok(similar) => {
+                let tracks: Vec<Track> = similar
+                    .into_iter()
+                    .filter(|t| !seen.contains(&t.id))
+                    .take(10)
+                    .collect();
+                // Nothing new over the earlier sections
+                if tracks.is_empty() {
+                    continue;
+                }
+                seen.extend(tracks.iter().map(|t| t.id.clone()));
+                sections.push(RecommendationSection {
+                    title: format!("Because you like {}", seed.title),
+                    subtitle: Some(seed.artist_name.clone()),
+                    tracks,
+                });
+            }
+            Err(e) => {
+                log::warn!("Failed to get similar tracks for {}: {}", seed.id, e);
+            }
+        }
+    }
+
+    sections
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{mock_client, track, tracks};
+    use std::time::Duration;
+    use wiremock::matchers::{method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn home_sections_share_one_favorites_fetch() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/userCollections/1/relationships/tracks"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!({ "data": [], "included": [] }))
+                    .set_delay(Duration::from_millis(100)),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+        let client = mock_client(&server);
+        let home = HomeFetch::default();
+
+        let (teaser, because) = tokio::join!(
+            client.get_home_section(HomeSectionKind::FavoritesTeaser, &home),
+            client.get_home_section(HomeSectionKind::BecauseYouLike, &home),
+        );
+
+        assert!(teaser.unwrap().is_empty());
+        assert!(because.unwrap().is_empty());
+        server.verify().await;
+    }
+
+    #[tokio::test]
+    async fn mix_sections_share_one_recommendations_fetch() {
+        let server = MockServer::start().await;
+        let empty = serde_json::json!({ "data": [] });
+        Mock::given(method("GET"))
+            .and(path("/userRecommendations/me"))
+            .and(query_param("include", MIX_RELATIONSHIPS))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!({
+                        "data": {
+                            "id": "1",
+                            "type": "userRecommendations",
+                            "relationships": {
+                                "myMixes": &empty,
+                                "discoveryMixes": &empty,
+                                "newArrivalMixes": &empty,
+                            },
+                        },
+                        "included": [],
+                    }))
+                    .set_delay(Duration::from_millis(100)),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+        let client = mock_client(&server);
+        let home = HomeFetch::default();
+
+        let (mine, discovery, arrivals) = tokio::join!(
+            client.get_home_section(HomeSectionKind::MyMixes, &home),
+            client.get_home_section(HomeSectionKind::Discovery, &home),
+            client.get_home_section(HomeSectionKind::NewArrivals, &home),
+        );
+
+        assert!(mine.unwrap().is_empty());
+        assert!(discovery.unwrap().is_empty());
+        assert!(arrivals.unwrap().is_empty());
+        server.verify().await;
+    }
+
+    #[tokio::test]
+    async fn recently_played_needs_no_request_without_history() {
+        let server = MockServer::start().await;
+        let client = mock_client(&server);
+
+        let sections = client
+            .get_home_section(HomeSectionKind::RecentlyPlayed, &HomeFetch::default())
+            .await
+            .unwrap();
+
+        assert!(sections.is_empty());
+        assert!(server.received_requests().await.unwrap().is_empty());
+    }
+
+    fn ids(section: &RecommendationSection) -> Vec<&str> {
+        section.tracks.iter().map(|t| t.id.as_str()).collect()
+    }
+
+    #[test]
+    fn sections_follow_seed_order_and_skip_failed_seeds() {
+        let seeds = tracks(&["s1", "s2", "s3"]);
+        let seeds: Vec<&Track> = seeds.iter().collect();
+        let results = vec![
+            Ok(tracks(&["a", "b"])),
+            Err(AppError::NotFound("similar".into())),
+            Ok(tracks(&["c"])),
+        ];
+
+        let sections = because_you_like_sections(&seeds, results, &[]);
+
+        let titles: Vec<&str> = sections.iter().map(|s| s.title.as_str()).collect();
+        assert_eq!(
+            titles,
+            ["Because you like Track s1", "Because you like Track s3"]
+        );
+        assert_eq!(ids(&sections[0]), ["a", "b"]);
+        assert_eq!(ids(&sections[1]), ["c"]);
+    }
+
+    #[test]
+    fn a_track_stays_in_the_earliest_section() {
+        let seeds = tracks(&["s1", "s2"]);
+        let seeds: Vec<&Track> = seeds.iter().collect();
+        let results = vec![Ok(tracks(&["a", "b"])), Ok(tracks(&["b", "c", "a", "d"]))];
+
+        let sections = because_you_like_sections(&seeds, results, &[]);
+
+        assert_eq!(ids(&sections[0]), ["a", "b"]);
+        assert_eq!(ids(&sections[1]), ["c", "d"]);
+    }
+
+    #[test]
+    fn a_seed_with_nothing_new_gets_no_section() {
+        let seeds = tracks(&["s1", "s2", "s3"]);
+        let seeds: Vec<&Track> = seeds.iter().collect();
+        let results = vec![
+            Ok(tracks(&["a", "b", "c"])),
+            Ok(tracks(&["c", "a"])),
+            Ok(Vec::new()),
+        ];
+
+        let sections = because_you_like_sections(&seeds, results, &[]);
+
+        assert_eq!(sections.len(), 1);
+        assert_eq!(sections[0].subtitle.as_deref(), Some("Artist"));
+    }
+
+    #[test]
+    fn favorites_are_not_recommended_back() {
+        let seed = track("s1");
+        let favorites = vec![track("s1"), track("a")];
+        let results = vec![Ok(tracks(&["s1", "a", "b"]))];
+
+        let sections = because_you_like_sections(&[&seed], results, &favorites);
+
+        assert_eq!(ids(&sections[0]), ["b"]);
+    }
+
+    #[test]
+    fn a_section_holds_at_most_ten_tracks() {
+        let similar: Vec<String> = (0..15).map(|i| i.to_string()).collect();
+        let similar: Vec<&str> = similar.iter().map(String::as_str).collect();
+        let seeds = tracks(&["s1", "s2"]);
+        let seeds: Vec<&Track> = seeds.iter().collect();
+        let results = vec![Ok(tracks(&similar)), Ok(tracks(&similar))];
+
+        let sections = because_you_like_sections(&seeds, results, &[]);
+
+        assert_eq!(ids(&sections[0]), similar[..10]);
+        assert_eq!(ids(&sections[1]), similar[10..]);
+    }
+
+    #[test]
+    fn collection_totals_come_from_either_meta() {
+        let cases = [
+            (serde_json::json!({ "meta": { "total": 1284 } }), Some(1284)),
+            (
+                serde_json::json!({ "links": { "meta": { "totalNumberOfItems": 12 } } }),
+                Some(12),
+            ),
+            (serde_json::json!({ "meta": { "count": 3 } }), Some(3)),
+            // A cursor alone says nothing about the size
+            (
+                serde_json::json!({ "links": { "meta": { "nextCursor": "abc" } } }),
+                None,
+            ),
+            (serde_json::json!({ "meta": { "total": "many" } }), None),
+            (serde_json::json!({ "meta": { "total": 1u64 << 40 } }), None),
+        ];
+        for (body, total) in cases {
+            assert_eq!(collection_total(&body), total, "{}", body);
+        }
+    }
+
+    #[tokio::test]
+    async fn favorites_report_the_size_of_the_collection() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/userCollections/1/relationships/tracks"))
+            .and(query_param(
+                "include",
+                "tracks,tracks.artists,tracks.albums,tracks.albums.coverArt",
+            ))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "data": [],
+                "included": [],
+                "links": { "meta": { "nextCursor": "next", "totalNumberOfItems": 1284 } },
+            })))
+            .mount(&server)
+            .await;
+        // The id-only page the count is read from
+        Mock::given(method("GET"))
+            .and(path("/userCollections/1/relationships/tracks"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "data": [{ "id": "1", "type": "tracks" }],
+                "meta": { "total": 1284 },
+            })))
+            .mount(&server)
+            .await;
+        let client = mock_client(&server);
+
+        let page = client.get_favorites(None).await.unwrap();
+        assert_eq!(page.total, Some(1284));
+        assert!(page.has_more);
+        assert_eq!(client.get_favorites_count().await.unwrap(), Some(1284));
+
+        // Without a total in the response there's nothing to report
+        server.reset().await;
+        Mock::given(method("GET"))
+            .and(path("/userCollections/1/relationships/tracks"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(serde_json::json!({ "data": [] })),
+            )
+            .mount(&server)
+            .await;
+        assert_eq!(client.get_favorites(None).await.unwrap().total, None);
+        assert_eq!(client.get_favorites_count().await.unwrap(), None);
+    }
+}
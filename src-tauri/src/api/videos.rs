@@ -0,0 +1,42 @@
+use crate::api::client::TidalClient;
+use crate::api::models::Video;
+use crate::api::search::{parse_video, resolve_video_relationships};
+use crate::error::{AppError, AppResult};
+
+impl TidalClient {
+    pub async fn get_video(&self, video_id: &str) -> AppResult<Video> {
+        let config = self.config().read().await;
+        let country = config.country_code.clone();
+        drop(config);
+
+        let path = format!("/videos/{}", video_id);
+        let response = self
+            .get_with_query(
+                &path,
+                &[
+                    ("countryCode", country.as_str()),
+                    ("include", "artists,thumbnailArt"),
+                ],
+            )
+            .await?;
+
+        let body: serde_json::Value = response.json().await?;
+        let data = body.get("data");
+        let id = data
+            .and_then(|d| d.get("id"))
+            .and_then(|v| v.as_str())
+            .unwrap_or(video_id);
+        let attrs = data
+            .and_then(|d| d.get("attributes"))
+            .cloned()
+            .unwrap_or_default();
+        let rels = data.and_then(|d| d.get("relationships"));
+
+        let mut video = parse_video(id, &attrs)
+            .ok_or_else(|| AppError::NotFound(format!("Video {} not found", video_id)))?;
+        if let Some(included) = body.get("included").and_then(|v| v.as_array()) {
+            resolve_video_relationships(&mut video, rels, included);
+        }
+        Ok(video)
+    }
+}
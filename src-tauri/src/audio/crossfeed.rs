@@ -0,0 +1,182 @@
+use serde::{Deserialize, Serialize};
+
+/// Cutoff of the low-pass applied to the signal fed into the opposite ear.
+const CROSSFEED_CUTOFF_HZ: f32 = 700.0;
+
+/// Interaural delay applied to the crossfed signal.
+const CROSSFEED_DELAY_SECONDS: f32 = 0.0003;
+
+/// Crossfeed level range in dB, mapped from strength 0.0..=1.0.
+const MIN_FEED_DB: f32 = -15.0;
+const MAX_FEED_DB: f32 = -4.5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CrossfeedSettings {
+    pub enabled: bool,
+    /// 0.0 (subtle) to 1.0 (strong)
+    pub strength: f32,
+}
+
+impl Default for CrossfeedSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            strength: 0.5,
+        }
+    }
+}
+
+/// Bauer-style headphone crossfeed: each channel receives a low-passed,
+/// attenuated and slightly delayed copy of the other channel.
+/// Operates on interleaved stereo f32 samples in the decode thread.
+pub struct Crossfeed {
+    direct_gain: f32,
+    cross_gain: f32,
+    /// One-pole low-pass coefficient
+    lp_coeff: f32,
+    lp_state: [f32; 2],
+    delay_left: Vec<f32>,
+    delay_right: Vec<f32>,
+    delay_pos: usize,
+}
+
+impl Crossfeed {
+    /// Build a filter for the given sample rate. Returns None when the
+    /// settings or the source (anything but stereo) call for bypass.
+    pub fn new(settings: CrossfeedSettings, sample_rate: u32, channels: usize) -> Option<Self> {
+        if !settings.enabled || channels != 2 || sample_rate == 0 {
+            return None;
+        }
+
+        let strength = settings.strength.clamp(0.0, 1.0);
+        let feed_db = MIN_FEED_DB + (MAX_FEED_DB - MIN_FEED_DB) * strength;
+        let feed = 10f32.powf(feed_db / 20.0);
+        // Normalize so a centered (mono) signal keeps its original level
+        let norm = 1.0 / (1.0 + feed);
+
+        let sr = sample_rate as f32;
+        let lp_coeff = 1.0 - (-2.0 * std::f32::consts::PI * CROSSFEED_CUTOFF_HZ / sr).exp();
+        let delay_len = ((CROSSFEED_DELAY_SECONDS * sr).round() as usize).max(1);
+
+        Some(Self {
+            direct_gain: norm,
+            cross_gain: feed * norm,
+            lp_coeff,
+            lp_state: [0.0; 2],
+            delay_left: vec![0.0; delay_len],
+            delay_right: vec![0.0; delay_len],
+            delay_pos: 0,
+        })
+    }
+
+    /// Apply crossfeed in place to interleaved stereo samples.
+    pub fn process(&mut self, samples: &mut [f32]) {
+        for frame in samples.chunks_exact_mut(2) {
+            let (left, right) = (frame[0], frame[1]);
+
+            self.lp_state[0] += self.lp_coeff * (left - self.lp_state[0]);
+            self.lp_state[1] += self.lp_coeff * (right - self.lp_state[1]);
+
+            let delayed_left = self.delay_left[self.delay_pos];
+            let delayed_right = self.delay_right[self.delay_pos];
+            self.delay_left[self.delay_pos] = self.lp_state[0];
+            self.delay_right[self.delay_pos] = self.lp_state[1];
+            self.delay_pos = (self.delay_pos + 1) % self.delay_left.len();
+
+            frame[0] = self.direct_gain * left + self.cross_gain * delayed_right;
+            frame[1] = self.direct_gain * right + self.cross_gain * delayed_left;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn enabled(strength: f32) -> CrossfeedSettings {
+        CrossfeedSettings {
+            enabled: true,
+            strength,
+        }
+    }
+
+    fn db(gain: f32) -> f32 {
+        20.0 * gain.log10()
+    }
+
+    #[test]
+    fn bypasses_when_disabled_or_not_stereo() {
+        assert!(Crossfeed::new(CrossfeedSettings::default(), 44_100, 2).is_none());
+        assert!(Crossfeed::new(enabled(0.5), 44_100, 1).is_none());
+        assert!(Crossfeed::new(enabled(0.5), 44_100, 6).is_none());
+        assert!(Crossfeed::new(enabled(0.5), 0, 2).is_none());
+    }
+
+    #[test]
+    fn strength_maps_onto_the_feed_range() {
+        let subtle = Crossfeed::new(enabled(0.0), 44_100, 2).unwrap();
+        let strong = Crossfeed::new(enabled(1.0), 44_100, 2).unwrap();
+        let feed = |f: &Crossfeed| db(f.cross_gain / f.direct_gain);
+
+        assert!((feed(&subtle) - MIN_FEED_DB).abs() < 1e-3);
+        assert!((feed(&strong) - MAX_FEED_DB).abs() < 1e-3);
+        for f in [&subtle, &strong] {
+            assert!((f.direct_gain + f.cross_gain - 1.0).abs() < 1e-6);
+        }
+
+        // Out of range strengths are clamped
+        let over = Crossfeed::new(enabled(3.0), 44_100, 2).unwrap();
+        assert_eq!(over.cross_gain, strong.cross_gain);
+    }
+
+    #[test]
+    fn filter_coefficients_follow_the_sample_rate() {
+        for (rate, delay) in [(44_100, 13), (48_000, 14), (96_000, 29), (192_000, 58)] {
+            let filter = Crossfeed::new(enabled(0.5), rate, 2).unwrap();
+            let expected =
+                1.0 - (-2.0 * std::f32::consts::PI * CROSSFEED_CUTOFF_HZ / rate as f32).exp();
+            assert!((filter.lp_coeff - expected).abs() < 1e-7, "rate {rate}");
+            assert_eq!(filter.delay_left.len(), delay, "rate {rate}");
+        }
+    }
+
+    #[test]
+    fn centered_signal_keeps_its_level() {
+        let mut filter = Crossfeed::new(enabled(1.0), 48_000, 2).unwrap();
+        let mut samples = vec![0.5f32; 48_000 * 2];
+        filter.process(&mut samples);
+
+        // Once the low-pass has settled, a DC mono signal comes out unchanged
+        for s in &samples[samples.len() - 200..] {
+            assert!((s - 0.5).abs() < 1e-4, "{s}");
+        }
+    }
+
+    #[test]
+    fn crossfed_signal_is_delayed_and_attenuated() {
+        let mut filter = Crossfeed::new(enabled(0.5), 44_100, 2).unwrap();
+        let delay = filter.delay_left.len();
+        let mut samples = vec![0.0f32; 400];
+        for frame in samples.chunks_exact_mut(2) {
+            frame[0] = 1.0;
+        }
+        filter.process(&mut samples);
+
+        // Nothing reaches the right ear until the interaural delay has passed
+        for frame in samples.chunks_exact(2).take(delay) {
+            assert_eq!(frame[1], 0.0);
+        }
+        let right_tail = samples[samples.len() - 1];
+        assert!(right_tail > 0.0 && right_tail < filter.cross_gain + 1e-6);
+        assert!((samples[0] - filter.direct_gain).abs() < 1e-6);
+    }
+
+    #[test]
+    fn ignores_a_trailing_partial_frame() {
+        let mut filter = Crossfeed::new(enabled(0.5), 44_100, 2).unwrap();
+        let mut samples = vec![0.25f32; 5];
+        filter.process(&mut samples);
+        assert_eq!(samples[4].to_bits(), 0.25f32.to_bits());
+    }
+}
@@ -1,9 +1,9 @@
-use crate::audio::stream_source::HttpStreamSource;
+use crate::audio::stream_source::IndexedSeek;
 use crate::error::{AppError, AppResult};
 use symphonia::core::audio::SampleBuffer;
 use symphonia::core::codecs::{DecoderOptions, CODEC_TYPE_NULL};
-use symphonia::core::formats::FormatOptions;
-use symphonia::core::io::MediaSourceStream;
+use symphonia::core::formats::{FormatOptions, Packet};
+use symphonia::core::io::{MediaSource, MediaSourceStream, ReadBytes, SeekBuffered};
 use symphonia::core::meta::MetadataOptions;
 use symphonia::core::probe::Hint;
 
@@ -13,6 +13,55 @@ pub struct AudioDecoder {
     track_id: u32,
     sample_rate: u32,
     channels: usize,
+    bits_per_sample: Option<u32>,
+    duration: Option<f64>,
+    codec_hint: Option<String>,
+    detected: DetectedFormat,
+    /// Set for downloads that get a FLAC frame index once complete
+    indexed_seek: Option<IndexedSeek>,
+    /// Read while looking for a seek target; decoded before the next packet
+    pending_packet: Option<Packet>,
+    /// Decoded by `verify_output_format`; returned by the next `decode_next`
+    pending_samples: Option<DecodedSamples>,
+}
+
+/// Container and codec as found in the bytes, which can differ from what the
+/// manifest announced (e.g. FLAC wrapped in fMP4).
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct DetectedFormat {
+    /// e.g. "flac", "mp4" or "ogg"; None when the leading bytes aren't recognised
+    pub container: Option<&'static str>,
+    /// Symphonia's short codec name, e.g. "flac" or "aac"
+    pub codec: Option<&'static str>,
+}
+
+/// Bytes read ahead of the probe to recognise the container.
+const SNIFF_LEN: usize = 12;
+
+/// Name the container from the first bytes of a stream.
+fn sniff_container(head: &[u8]) -> Option<&'static str> {
+    if head.starts_with(b"fLaC") {
+        Some("flac")
+    } else if head.get(4..8).is_some_and(|tag| {
+        [b"ftyp", b"styp", b"moof", b"moov"]
+            .iter()
+            .any(|t| tag == *t)
+    }) {
+        Some("mp4")
+    } else if head.starts_with(b"OggS") {
+        Some("ogg")
+    } else if head.starts_with(b"RIFF") {
+        Some("wav")
+    } else if head.starts_with(b"ID3") {
+        Some("mp3")
+    } else {
+        // Frame sync; ADTS (raw AAC) has the MPEG layer bits cleared
+        match head {
+            [0xFF, b, ..] if b & 0xE0 == 0xE0 && b & 0x06 == 0 => Some("adts"),
+            [0xFF, b, ..] if b & 0xE0 == 0xE0 => Some("mp3"),
+            _ => None,
+        }
+    }
 }
 
 pub struct DecodedSamples {
@@ -22,15 +71,28 @@ pub struct DecodedSamples {
 }
 
 impl AudioDecoder {
-    pub fn new(source: HttpStreamSource, codec_hint: Option<&str>) -> AppResult<Self> {
+    /// Probe `source` and set up a decoder for its first audio track. Any
+    /// media source works: a network stream, a cached file or a byte buffer.
+    pub fn new(source: Box<dyn MediaSource>, codec_hint: Option<&str>) -> AppResult<Self> {
         log::info!("AudioDecoder::new with codec_hint={:?}", codec_hint);
-        let mss = MediaSourceStream::new(Box::new(source), Default::default());
+        let mut mss = MediaSourceStream::new(source, Default::default());
+
+        // Peek at the leading bytes and rewind, so the probe sees the whole stream
+        let mut head = Vec::with_capacity(SNIFF_LEN);
+        while head.len() < SNIFF_LEN {
+            match mss.read_byte() {
+                Ok(byte) => head.push(byte),
+                Err(_) => break,
+            }
+        }
+        mss.seek_buffered_rev(head.len());
+        let container = sniff_container(&head);
 
         let mut hint = Hint::new();
         if let Some(codec) = codec_hint {
             let ext = match codec.to_lowercase().as_str() {
                 "flac" | "flac_hires" => Some("flac"),
-                "aac" | "aaclc" | "mp4a" | "mp4a.40.2" => Some("m4a"),
+                "aac" | "aaclc" | "mp4a" | "mp4a.40.2" | "m4a" => Some("m4a"),
                 "heaacv1" | "mp4a.40.5" => Some("m4a"),
                 "mp4" => Some("mp4"),
                 "mp3" => Some("mp3"),
@@ -66,16 +128,30 @@ impl AudioDecoder {
         let track_id = track.id;
         let sample_rate = track.codec_params.sample_rate.unwrap_or(44100);
         let channels = track.codec_params.channels.map(|c| c.count()).unwrap_or(2);
+        let bits_per_sample = track.codec_params.bits_per_sample;
+        let duration = track
+            .codec_params
+            .n_frames
+            .map(|frames| frames as f64 / sample_rate as f64);
 
-        let decoder = symphonia::default::get_codecs()
+        let codecs = symphonia::default::get_codecs();
+        let decoder = codecs
             .make(&track.codec_params, &DecoderOptions::default())
             .map_err(|e| AppError::Decode(format!("Failed to create decoder: {}", e)))?;
+        let detected = DetectedFormat {
+            container,
+            codec: codecs
+                .get_codec(track.codec_params.codec)
+                .map(|c| c.short_name),
+        };
 
         log::info!(
-            "AudioDecoder ready: track_id={}, sample_rate={}, channels={}",
+            "AudioDecoder ready: track_id={}, sample_rate={}, channels={}, container={:?}, codec={:?}",
             track_id,
             sample_rate,
-            channels
+            channels,
+            detected.container,
+            detected.codec
         );
 
         Ok(Self {
@@ -84,9 +160,21 @@ impl AudioDecoder {
             track_id,
             sample_rate,
             channels,
+            bits_per_sample,
+            duration,
+            codec_hint: codec_hint.map(String::from),
+            detected,
+            indexed_seek: None,
+            pending_packet: None,
+            pending_samples: None,
         })
     }
 
+    /// Seek through `indexed_seek` once the stream has been indexed.
+    pub fn set_indexed_seek(&mut self, indexed_seek: IndexedSeek) {
+        self.indexed_seek = Some(indexed_seek);
+    }
+
     pub fn sample_rate(&self) -> u32 {
         self.sample_rate
     }
@@ -95,11 +183,32 @@ impl AudioDecoder {
         self.channels
     }
 
+    /// Bit depth of the source, when the container reports one (lossy codecs don't).
+    pub fn bits_per_sample(&self) -> Option<u32> {
+        self.bits_per_sample
+    }
+
+    /// Container and codec found while probing.
+    pub fn detected_format(&self) -> DetectedFormat {
+        self.detected
+    }
+
+    /// Length in seconds, when the container declares its frame count.
+    pub fn duration_seconds(&self) -> Option<f64> {
+        self.duration
+    }
+
     /// Seek to a position in the stream (in seconds).
     pub fn seek(&mut self, position_seconds: f64) -> AppResult<()> {
         use symphonia::core::formats::SeekTo;
         use symphonia::core::units::Time;
 
+        self.pending_packet = None;
+        self.pending_samples = None;
+        if self.seek_indexed(position_seconds)? {
+            return Ok(());
+        }
+
         let time = Time {
             seconds: position_seconds as u64,
             frac: position_seconds.fract(),
@@ -121,10 +230,79 @@ impl AudioDecoder {
         Ok(())
     }
 
+    /// Reopen the stream at the indexed frame before the target and skip
+    /// ahead to the packet containing it, instead of having symphonia search
+    /// for it. False while there is no index.
+    fn seek_indexed(&mut self, position_seconds: f64) -> AppResult<bool> {
+        let target = (position_seconds * self.sample_rate as f64) as u64;
+        let Some(view) = self
+            .indexed_seek
+            .as_ref()
+            .and_then(|seek| seek.view_at(target))
+        else {
+            return Ok(false);
+        };
+
+        let mut reopened = Self::new(Box::new(view), self.codec_hint.as_deref())?;
+        // FLAC frames decode independently, so the skipped ones aren't decoded
+        let packet = loop {
+            let packet = reopened
+                .format_reader
+                .next_packet()
+                .map_err(|e| AppError::Decode(format!("Seek failed: {}", e)))?;
+            if packet.track_id() == reopened.track_id && packet.ts() + packet.dur() > target {
+                break packet;
+            }
+        };
+        log::info!("Seeked to {:.2}s through the frame index", position_seconds);
+
+        self.format_reader = reopened.format_reader;
+        self.decoder = reopened.decoder;
+        self.track_id = reopened.track_id;
+        self.pending_packet = Some(packet);
+        Ok(true)
+    }
+
+    /// Decode the first batch and take its sample rate and channel count
+    /// over the ones the codec parameters reported, which can be wrong (e.g.
+    /// a mono stream announced as stereo). An output opened with the wrong
+    /// ones plays at the wrong speed. Returns the reported rate and channel
+    /// count when they were corrected.
+    pub fn verify_output_format(&mut self) -> AppResult<Option<(u32, usize)>> {
+        let Some(first) = self.decode_next()? else {
+            return Ok(None);
+        };
+        let reported = (self.sample_rate, self.channels);
+        let corrected = (first.sample_rate, first.channels) != reported;
+        if corrected {
+            log::warn!(
+                "Codec parameters said {} Hz / {} ch, decoded audio is {} Hz / {} ch",
+                self.sample_rate,
+                self.channels,
+                first.sample_rate,
+                first.channels
+            );
+            self.duration = self
+                .duration
+                .map(|d| d * self.sample_rate as f64 / first.sample_rate as f64);
+            self.sample_rate = first.sample_rate;
+            self.channels = first.channels;
+        }
+        self.pending_samples = Some(first);
+        Ok(Some(reported).filter(|_| corrected))
+    }
+
     /// Decode the next batch of samples. Returns None at EOF.
     pub fn decode_next(&mut self) -> AppResult<Option<DecodedSamples>> {
+        if let Some(samples) = self.pending_samples.take() {
+            return Ok(Some(samples));
+        }
         loop {
-            let packet = match self.format_reader.next_packet() {
+            let next = match self.pending_packet.take() {
+                Some(packet) => Ok(packet),
+                None => self.format_reader.next_packet(),
+            };
+            let packet = match next {
                 Ok(packet) => packet,
                 Err(symphonia::core::errors::Error::IoError(ref e))
                     if e.kind() == std::io::ErrorKind::UnexpectedEof =>
@@ -162,3 +340,244 @@ impl AudioDecoder {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::stream_source::HttpStreamSource;
+    use crate::test_support::{adts_silence, flac, tone, wav, FLAC_BLOCK};
+    use std::io::Cursor;
+
+    fn open(bytes: Vec<u8>, hint: Option<&str>) -> AppResult<AudioDecoder> {
+        AudioDecoder::new(Box::new(Cursor::new(bytes)), hint)
+    }
+
+    /// Decode to EOF, returning the frames decoded and the first batch.
+    fn drain(decoder: &mut AudioDecoder) -> (usize, Vec<f32>) {
+        let mut frames = 0;
+        let mut first = None;
+        while let Some(batch) = decoder.decode_next().unwrap() {
+            frames += batch.samples.len() / batch.channels;
+            first.get_or_insert(batch.samples);
+        }
+        (frames, first.unwrap_or_default())
+    }
+
+    #[test]
+    fn flac_probes_with_or_without_a_hint() {
+        for hint in [
+            None,
+            Some("FLAC"),
+            Some("FLAC_HIRES"),
+            Some("AACLC"),
+            Some("nonsense"),
+        ] {
+            let decoder = open(flac(44_100, 2, 44_100), hint).unwrap();
+            assert_eq!(decoder.sample_rate(), 44_100, "{:?}", hint);
+            assert_eq!(decoder.channels(), 2);
+            assert_eq!(decoder.bits_per_sample(), Some(16));
+            assert_eq!(decoder.duration_seconds(), Some(1.0));
+            assert_eq!(
+                decoder.detected_format(),
+                DetectedFormat {
+                    container: Some("flac"),
+                    codec: Some("flac"),
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn the_bytes_win_over_a_hint_that_contradicts_them() {
+        let cases = [
+            (wav(44_100, 2, 4_410), "FLAC", "wav", "pcm_s16le"),
+            (adts_silence(44_100, 2, 4), "FLAC", "adts", "aac"),
+            (flac(44_100, 2, 4_410), "AACLC", "flac", "flac"),
+            (flac(44_100, 2, 4_410), "EAC3_JOC", "flac", "flac"),
+        ];
+        for (bytes, hint, container, codec) in cases {
+            let mut decoder = open(bytes, Some(hint)).unwrap();
+            assert_eq!(
+                decoder.detected_format(),
+                DetectedFormat {
+                    container: Some(container),
+                    codec: Some(codec),
+                },
+                "{} hinted as {}",
+                container,
+                hint
+            );
+            assert!(decoder.decode_next().unwrap().is_some());
+        }
+    }
+
+    #[test]
+    fn wrong_codec_parameters_give_way_to_the_decoded_audio() {
+        let mut decoder = open(flac(48_000, 1, 48_000), None).unwrap();
+        assert_eq!(decoder.verify_output_format().unwrap(), None);
+
+        // As if the container had announced stereo at 44.1 kHz
+        let mut decoder = open(flac(48_000, 1, 48_000), None).unwrap();
+        decoder.sample_rate = 44_100;
+        decoder.channels = 2;
+        decoder.duration = Some(48_000.0 / 44_100.0);
+        assert_eq!(decoder.verify_output_format().unwrap(), Some((44_100, 2)));
+        assert_eq!((decoder.sample_rate(), decoder.channels()), (48_000, 1));
+        assert!((decoder.duration_seconds().unwrap() - 1.0).abs() < 1e-9);
+
+        // The batch decoded to check comes back first, then the rest
+        let (frames, first) = drain(&mut decoder);
+        assert_eq!(frames, 48_000);
+        for frame in 0..64 {
+            let expected = tone(frame, 48_000) as f32 / 32768.0;
+            assert_eq!(first[frame as usize], expected);
+        }
+    }
+
+    #[test]
+    fn flac_decodes_every_frame_of_the_tone() {
+        let mut decoder = open(flac(48_000, 2, 48_000 + 123), None).unwrap();
+        let (frames, first) = drain(&mut decoder);
+        assert_eq!(frames, 48_000 + 123);
+
+        // Interleaved stereo, both channels carrying the tone
+        for frame in 0..64 {
+            let expected = tone(frame, 48_000) as f32 / 32768.0;
+            assert_eq!(first[frame as usize * 2], expected);
+            assert_eq!(first[frame as usize * 2 + 1], expected);
+        }
+    }
+
+    #[test]
+    fn adts_aac_decodes_to_silence() {
+        let mut decoder = open(adts_silence(44_100, 2, 20), Some("AACLC")).unwrap();
+        assert_eq!(decoder.sample_rate(), 44_100);
+        assert_eq!(decoder.channels(), 2);
+        assert_eq!(
+            decoder.detected_format(),
+            DetectedFormat {
+                container: Some("adts"),
+                codec: Some("aac"),
+            }
+        );
+
+        let mut frames = 0;
+        while let Some(batch) = decoder.decode_next().unwrap() {
+            assert!(batch.samples.iter().all(|&s| s == 0.0));
+            frames += batch.samples.len() / batch.channels;
+        }
+        assert_eq!(frames, 20 * 1024);
+    }
+
+    #[test]
+    fn mono_adts_probes_without_a_hint() {
+        let decoder = open(adts_silence(48_000, 1, 4), None).unwrap();
+        assert_eq!(decoder.sample_rate(), 48_000);
+        assert_eq!(decoder.channels(), 1);
+        assert_eq!(decoder.detected_format().codec, Some("aac"));
+    }
+
+    #[test]
+    fn seeking_lands_within_a_block_of_the_target() {
+        let mut decoder = open(flac(44_100, 2, 3 * 44_100), None).unwrap();
+        decoder.seek(2.0).unwrap();
+        let (left, _) = drain(&mut decoder);
+
+        // A coarse seek starts at the block holding the target
+        assert!(left >= 44_100, "{} frames left", left);
+        assert!(left < 44_100 + FLAC_BLOCK as usize, "{} frames left", left);
+    }
+
+    #[test]
+    fn an_indexed_seek_lands_within_a_block_of_the_target() {
+        let total = 20 * 44_100;
+        let bytes = flac(44_100, 2, total);
+        let (source, writer, _abort) = HttpStreamSource::new();
+        writer.write_bytes(&bytes).unwrap();
+        writer.finish();
+        let seek = source.indexed_seek();
+        let started = std::time::Instant::now();
+        while seek.view_at(0).is_none() {
+            assert!(started.elapsed().as_secs() < 5, "never indexed");
+            std::thread::sleep(std::time::Duration::from_millis(5));
+        }
+
+        let mut decoder = AudioDecoder::new(Box::new(source), None).unwrap();
+        decoder.set_indexed_seek(seek);
+        for target in [12.3, 0.5, 17.0] {
+            assert!(decoder.seek_indexed(target).unwrap());
+            let (left, first) = drain(&mut decoder);
+
+            // Decoding starts at the block holding the target
+            let target = (target * 44_100.0) as usize;
+            assert!(left >= total as usize - target, "{} frames left", left);
+            assert!(
+                left < total as usize - target + FLAC_BLOCK as usize,
+                "{} frames left",
+                left
+            );
+            let start = total - left as u32;
+            assert_eq!(start % FLAC_BLOCK, 0);
+            for frame in 0..64 {
+                let expected = tone(start + frame, 44_100) as f32 / 32768.0;
+                assert_eq!(first[frame as usize * 2], expected);
+            }
+        }
+    }
+
+    #[test]
+    fn without_an_index_seeks_fall_back_to_searching() {
+        let (source, writer, _abort) = HttpStreamSource::new();
+        writer.write_bytes(&wav(44_100, 2, 44_100)).unwrap();
+        writer.finish();
+        let seek = source.indexed_seek();
+        let mut decoder = AudioDecoder::new(Box::new(source), None).unwrap();
+        decoder.set_indexed_seek(seek);
+        assert!(!decoder.seek_indexed(0.5).unwrap());
+    }
+
+    #[test]
+    fn decoding_restarts_cleanly_after_seeking_back_from_eof() {
+        let mut decoder = open(flac(44_100, 1, 2 * 44_100), None).unwrap();
+        let (total, first) = drain(&mut decoder);
+        assert_eq!(total, 2 * 44_100);
+        assert!(decoder.decode_next().unwrap().is_none());
+
+        decoder.seek(0.0).unwrap();
+        let (again, first_again) = drain(&mut decoder);
+        assert_eq!(again, total);
+        assert_eq!(first_again, first);
+    }
+
+    #[test]
+    fn truncated_flac_stops_early_without_failing() {
+        let mut bytes = flac(44_100, 2, 44_100);
+        // Cut off partway through a block
+        bytes.truncate(bytes.len() / 2 + 1000);
+        let mut decoder = open(bytes, None).unwrap();
+
+        let mut frames = 0;
+        // Stops at the cut, with or without an error
+        while let Ok(Some(batch)) = decoder.decode_next() {
+            frames += batch.samples.len() / batch.channels;
+        }
+        assert!(frames > 0 && frames < 44_100, "{} frames", frames);
+    }
+
+    #[test]
+    fn garbage_fails_to_probe() {
+        assert!(open(vec![0x42; 4096], Some("FLAC")).is_err());
+        assert!(open(Vec::new(), None).is_err());
+    }
+
+    #[test]
+    fn containers_are_recognised_from_their_first_bytes() {
+        assert_eq!(sniff_container(&flac(44_100, 2, 16)), Some("flac"));
+        assert_eq!(sniff_container(&adts_silence(44_100, 2, 1)), Some("adts"));
+        assert_eq!(sniff_container(b"\0\0\0\x20ftypiso6"), Some("mp4"));
+        assert_eq!(sniff_container(b"OggS\0\x02"), Some("ogg"));
+        assert_eq!(sniff_container(b"ID3\x04\0"), Some("mp3"));
+        assert_eq!(sniff_container(&[0xFF, 0xFB, 0x90, 0x64]), Some("mp3"));
+        assert_eq!(sniff_container(b"hello"), None);
+    }
+}
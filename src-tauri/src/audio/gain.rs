@@ -0,0 +1,102 @@
+use crate::api::models::Track;
+use crate::config::AppConfig;
+use crate::error::AppResult;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// Allowed range for a manual gain offset, in dB.
+pub const MIN_GAIN_OFFSET_DB: f32 = -12.0;
+pub const MAX_GAIN_OFFSET_DB: f32 = 6.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GainOffsetKind {
+    Track,
+    Album,
+}
+
+/// User-set gain offsets keyed by track/album id, stored in `gain_offsets.json`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GainOffsets {
+    #[serde(default)]
+    pub tracks: HashMap<String, f32>,
+    #[serde(default)]
+    pub albums: HashMap<String, f32>,
+}
+
+impl GainOffsets {
+    pub fn path() -> AppResult<PathBuf> {
+        Ok(AppConfig::config_dir()?.join("gain_offsets.json"))
+    }
+
+    /// Load the offsets map, starting empty if none has been saved.
+    pub fn load() -> AppResult<Self> {
+        let path = Self::path()?;
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(&path)?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    pub fn save(&self) -> AppResult<()> {
+        std::fs::create_dir_all(AppConfig::config_dir()?)?;
+        let content = serde_json::to_string_pretty(self)?;
+        std::fs::write(Self::path()?, content)?;
+        Ok(())
+    }
+
+    fn map(&self, kind: GainOffsetKind) -> &HashMap<String, f32> {
+        match kind {
+            GainOffsetKind::Track => &self.tracks,
+            GainOffsetKind::Album => &self.albums,
+        }
+    }
+
+    fn map_mut(&mut self, kind: GainOffsetKind) -> &mut HashMap<String, f32> {
+        match kind {
+            GainOffsetKind::Track => &mut self.tracks,
+            GainOffsetKind::Album => &mut self.albums,
+        }
+    }
+
+    /// Set an offset, clamped to the allowed range. A 0 dB offset removes the entry.
+    pub fn set(&mut self, kind: GainOffsetKind, id: &str, db: f32) -> f32 {
+        let db = db.clamp(MIN_GAIN_OFFSET_DB, MAX_GAIN_OFFSET_DB);
+        if db == 0.0 {
+            self.map_mut(kind).remove(id);
+        } else {
+            self.map_mut(kind).insert(id.to_string(), db);
+        }
+        db
+    }
+
+    pub fn get(&self, kind: GainOffsetKind, id: &str) -> f32 {
+        self.map(kind).get(id).copied().unwrap_or(0.0)
+    }
+
+    /// Replace the contents with an imported map, clamping every entry.
+    pub fn import(&mut self, other: GainOffsets) {
+        self.tracks.clear();
+        self.albums.clear();
+        for (id, db) in other.tracks {
+            self.set(GainOffsetKind::Track, &id, db);
+        }
+        for (id, db) in other.albums {
+            self.set(GainOffsetKind::Album, &id, db);
+        }
+    }
+
+    /// Combined track + album offset for a track, in dB.
+    pub fn effective_db(&self, track: &Track) -> f32 {
+        let album_db = track
+            .album_id
+            .as_deref()
+            .map(|id| self.get(GainOffsetKind::Album, id))
+            .unwrap_or(0.0);
+        (self.get(GainOffsetKind::Track, &track.id) + album_db)
+            .clamp(MIN_GAIN_OFFSET_DB, MAX_GAIN_OFFSET_DB)
+    }
+}
@@ -0,0 +1,40 @@
+use serde::{Deserialize, Serialize};
+
+/// What to do while another app is playing audio.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InterruptionMode {
+    /// Keep playing as before
+    #[default]
+    Ignore,
+    /// Lower the volume by `duck_db` until the other app stops
+    Duck,
+    /// Pause, resuming afterwards if `resume_after` is set
+    Pause,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InterruptionSettings {
+    pub mode: InterruptionMode,
+    /// Attenuation while ducked, in dB (negative)
+    pub duck_db: f32,
+    pub resume_after: bool,
+}
+
+impl Default for InterruptionSettings {
+    fn default() -> Self {
+        Self {
+            mode: InterruptionMode::Ignore,
+            duck_db: -12.0,
+            resume_after: true,
+        }
+    }
+}
+
+impl InterruptionSettings {
+    /// Linear gain to duck to.
+    pub fn duck_gain(&self) -> f32 {
+        10f32.powf(self.duck_db.min(0.0) / 20.0)
+    }
+}
@@ -1,5 +1,12 @@
+pub mod crossfeed;
 pub mod decoder;
+pub mod gain;
+pub mod interruption;
+pub mod output;
 pub mod player;
 pub mod preloader;
 pub mod queue;
+pub(crate) mod seek_index;
 pub mod stream_source;
+pub mod track_stats;
+pub mod waveform;
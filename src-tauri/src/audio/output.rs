@@ -0,0 +1,109 @@
+use cpal::traits::{DeviceTrait, HostTrait};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Overrides the configured backend, e.g. `TAURITIDAL_AUDIO_OUTPUT=null`.
+const BACKEND_ENV: &str = "TAURITIDAL_AUDIO_OUTPUT";
+
+/// Playback speed of the null backend; 1 is real time.
+const NULL_SPEED_ENV: &str = "TAURITIDAL_NULL_OUTPUT_SPEED";
+
+/// How often the null backend pulls a block of samples.
+const NULL_PERIOD: Duration = Duration::from_millis(10);
+
+/// Device name reported for the null backend.
+pub const NULL_DEVICE_NAME: &str = "Null output";
+
+/// Where decoded audio is sent.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputBackend {
+    /// The system's default output device
+    #[default]
+    Cpal,
+    /// No device: samples are consumed at the stream's rate and discarded,
+    /// for headless runs and CI
+    Null,
+}
+
+impl OutputBackend {
+    /// The configured backend unless the environment overrides it.
+    pub fn resolve(configured: OutputBackend) -> Self {
+        match std::env::var(BACKEND_ENV).as_deref() {
+            Ok("null") => OutputBackend::Null,
+            Ok("cpal") => OutputBackend::Cpal,
+            Ok(other) => {
+                log::warn!("Ignoring unknown {}={}", BACKEND_ENV, other);
+                configured
+            }
+            Err(_) => configured,
+        }
+    }
+
+    /// Name of the device a stream opened now would play on.
+    pub fn device_name(self) -> Option<String> {
+        match self {
+            OutputBackend::Cpal => cpal::default_host().default_output_device()?.name().ok(),
+            OutputBackend::Null => Some(NULL_DEVICE_NAME.to_string()),
+        }
+    }
+}
+
+/// Stand-in for an output device. A thread calls the render callback with
+/// blocks sized to the elapsed time, so the ring buffer drains and the
+/// position advances as they would on real hardware.
+pub struct NullOutput {
+    stop: Arc<AtomicBool>,
+    handle: Option<std::thread::JoinHandle<()>>,
+}
+
+impl NullOutput {
+    pub fn start(
+        sample_rate: u32,
+        channels: usize,
+        mut render: impl FnMut(&mut [f32]) + Send + 'static,
+    ) -> Self {
+        let speed = std::env::var(NULL_SPEED_ENV)
+            .ok()
+            .and_then(|v| v.parse::<f64>().ok())
+            .filter(|s| *s > 0.0)
+            .unwrap_or(1.0);
+        let samples_per_sec = sample_rate as f64 * channels.max(1) as f64 * speed;
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let stop_thread = Arc::clone(&stop);
+        let handle = std::thread::spawn(move || {
+            let started = Instant::now();
+            let mut rendered: u64 = 0;
+            let mut block = Vec::new();
+            while !stop_thread.load(Ordering::Relaxed) {
+                std::thread::sleep(NULL_PERIOD);
+                // Catch up on however much time really passed, whole frames only
+                let due = (started.elapsed().as_secs_f64() * samples_per_sec) as u64;
+                let frames = (due - rendered) as usize / channels.max(1);
+                if frames == 0 {
+                    continue;
+                }
+                block.resize(frames * channels.max(1), 0.0);
+                render(&mut block);
+                rendered += block.len() as u64;
+            }
+        });
+
+        Self {
+            stop,
+            handle: Some(handle),
+        }
+    }
+}
+
+impl Drop for NullOutput {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
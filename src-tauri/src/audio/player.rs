@@ -1,35 +1,284 @@
-use crate::audio::decoder::AudioDecoder;
-use crate::audio::stream_source::{HttpStreamSource, StreamAbortHandle, StreamWriter};
+use crate::api::client::TidalClient;
+use crate::audio::crossfeed::{Crossfeed, CrossfeedSettings};
+use crate::audio::decoder::{AudioDecoder, DetectedFormat};
+use crate::audio::output::{NullOutput, OutputBackend, NULL_DEVICE_NAME};
+use crate::audio::stream_source::{
+    HttpStreamSource, IndexedSeek, StreamAbortHandle, StreamProgress, StreamWriter,
+};
 use crate::error::{AppError, AppResult};
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
-use std::collections::VecDeque;
-use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
 use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+use symphonia::core::io::MediaSource;
 
 /// Shared ring buffer between the decode thread and the cpal callback.
 struct SampleRingBuffer {
     buffer: VecDeque<f32>,
     finished: bool,
+    /// The decode thread ended on an error rather than at EOF
+    failed: bool,
+}
+
+/// Each stream gets its own ring, so a decode thread left to exit on its own
+/// can never write into the next track's buffer.
+type SharedRing = Arc<(Mutex<SampleRingBuffer>, Condvar)>;
+
+fn new_ring() -> SharedRing {
+    Arc::new((
+        Mutex::new(SampleRingBuffer {
+            buffer: VecDeque::with_capacity(88200),
+            finished: false,
+            failed: false,
+        }),
+        Condvar::new(),
+    ))
+}
+
+/// Held by the decode thread. Unless the thread was told to stop, its exit
+/// (EOF, a decode error or a panic) marks the ring finished, so the track
+/// ends instead of waiting on a thread that is gone. A failed exit also
+/// stops playback.
+struct DecodeExitGuard {
+    ring: SharedRing,
+    stop_signal: Arc<AtomicBool>,
+    playing: Arc<AtomicBool>,
+    /// Cleared once the thread reaches EOF
+    failed: bool,
+}
+
+impl Drop for DecodeExitGuard {
+    fn drop(&mut self) {
+        if self.stop_signal.load(Ordering::SeqCst) {
+            return;
+        }
+        let failed = self.failed || std::thread::panicking();
+        let (lock, cvar) = &*self.ring;
+        let mut ring = lock.lock().unwrap_or_else(|e| e.into_inner());
+        ring.finished = true;
+        ring.failed = failed;
+        if failed {
+            self.playing.store(false, Ordering::SeqCst);
+        }
+        cvar.notify_all();
+    }
+}
+
+/// The open output: a device stream or the null backend. It is only held to
+/// keep output running; dropping it stops playback.
+enum OutputStream {
+    Cpal { _stream: cpal::Stream },
+    Null { _output: NullOutput },
+}
+
+/// An output built by `build_output` that hasn't started yet. A null output
+/// starts as soon as it exists, so it is only created on start.
+enum PendingStream {
+    Cpal(cpal::Stream),
+    Null(Box<dyn FnOnce() -> NullOutput + Send>),
+}
+
+struct PendingOutput {
+    stream: PendingStream,
+    device_name: Option<String>,
+    device_config: Option<(u32, cpal::SampleFormat)>,
 }
 
 /// Wrapper to make cpal::Stream Send+Sync.
 /// This is safe because we only modify the stream from a single logical owner (AudioPlayer),
 /// and cpal::Stream is only non-Send due to macOS CoreAudio API requirements that
 /// are satisfied by our usage pattern (created and dropped on the same thread).
-struct SendStream(Option<cpal::Stream>);
+struct SendStream {
+    current: Option<OutputStream>,
+    /// Streams stored and not yet dropped, to catch two playing at once
+    open: usize,
+}
 unsafe impl Send for SendStream {}
 unsafe impl Sync for SendStream {}
 
+impl SendStream {
+    fn new() -> Self {
+        Self {
+            current: None,
+            open: 0,
+        }
+    }
+
+    fn is_open(&self) -> bool {
+        self.current.is_some()
+    }
+
+    /// Store `stream` as the open output, then drop the one it replaces.
+    /// Callers close the old stream before starting a new one, so two never
+    /// play at once; debug builds check that.
+    fn replace(&mut self, stream: Option<OutputStream>) {
+        if stream.is_some() {
+            debug_assert_eq!(self.open, 0, "opened a second output stream");
+            self.open += 1;
+        }
+        let old = std::mem::replace(&mut self.current, stream);
+        if old.is_some() {
+            drop(old);
+            self.open -= 1;
+        }
+    }
+
+    fn close(&mut self) {
+        self.replace(None);
+    }
+}
+
 /// Sentinel value meaning "no seek requested".
 const NO_SEEK: u64 = u64::MAX;
 
+/// Fade-in applied after moving playback to a new output device.
+const DEVICE_SWITCH_FADE_MS: u32 = 60;
+
+/// How long ducking takes to reach its level, and to restore it.
+const DUCK_FADE_MS: u32 = 400;
+
+/// Sample format the output stream is always opened with.
+const STREAM_FORMAT: cpal::SampleFormat = cpal::SampleFormat::F32;
+
+/// How the current track reaches the output device, for checking that hi-res
+/// audio isn't being resampled or truncated on the way.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutputInfo {
+    pub device_name: Option<String>,
+    /// Rate and sample format the output stream was opened with
+    pub stream_sample_rate: u32,
+    pub stream_format: String,
+    /// The device's own default rate and format, i.e. what the system mixer runs at
+    pub device_sample_rate: Option<u32>,
+    pub device_format: Option<String>,
+    pub source_sample_rate: u32,
+    pub source_bits_per_sample: Option<u32>,
+    /// False when the stream or device runs at a different rate than the source,
+    /// or the device format has fewer bits than the source
+    pub lossless_path: bool,
+}
+
+/// What the decoder found the playing stream to be.
+#[derive(Debug, Clone, Copy)]
+pub struct SourceFormat {
+    pub sample_rate: u32,
+    pub channels: usize,
+    /// Lossy codecs have no bit depth
+    pub bit_depth: Option<u32>,
+    pub detected: DetectedFormat,
+    /// Rate and channel count the codec parameters reported, when the
+    /// decoded audio turned out different and the output was opened for it
+    pub corrected_from: Option<(u32, usize)>,
+}
+
+/// A track probed and its first audio decoded, ready for `AudioPlayer::play`.
+/// Opening waits on the download, so it's done before taking the player's
+/// lock and other commands aren't held up meanwhile.
+pub struct OpenedTrack {
+    decoder: AudioDecoder,
+    abort_handle: Option<StreamAbortHandle>,
+    duration: Option<f64>,
+    corrected_from: Option<(u32, usize)>,
+}
+
+impl OpenedTrack {
+    /// Open a download, seeking through its frame index once it has been
+    /// indexed.
+    pub fn stream(
+        source: HttpStreamSource,
+        abort_handle: StreamAbortHandle,
+        codec_hint: Option<&str>,
+        duration: f64,
+    ) -> AppResult<Self> {
+        let indexed_seek = source.indexed_seek();
+        Self::open(
+            Box::new(source),
+            Some(abort_handle),
+            codec_hint,
+            Some(duration),
+            Some(indexed_seek),
+        )
+    }
+
+    /// Open any media source, e.g. a file on disk. `abort_handle` unblocks
+    /// a source that can wait for data; a missing or zero `duration` falls
+    /// back to the container's own length.
+    pub fn source(
+        source: Box<dyn MediaSource>,
+        abort_handle: Option<StreamAbortHandle>,
+        codec_hint: Option<&str>,
+        duration: Option<f64>,
+    ) -> AppResult<Self> {
+        Self::open(source, abort_handle, codec_hint, duration, None)
+    }
+
+    fn open(
+        source: Box<dyn MediaSource>,
+        abort_handle: Option<StreamAbortHandle>,
+        codec_hint: Option<&str>,
+        duration: Option<f64>,
+        indexed_seek: Option<IndexedSeek>,
+    ) -> AppResult<Self> {
+        let mut decoder = AudioDecoder::new(source, codec_hint)?;
+        if let Some(indexed_seek) = indexed_seek {
+            decoder.set_indexed_seek(indexed_seek);
+        }
+        // The output is opened from the decoded audio, not the codec
+        // parameters, so a wrong rate or channel count can't change its speed
+        let corrected_from = decoder.verify_output_format()?;
+        Ok(Self {
+            decoder,
+            abort_handle,
+            duration,
+            corrected_from,
+        })
+    }
+}
+
+fn format_name(format: cpal::SampleFormat) -> String {
+    format!("{:?}", format).to_lowercase()
+}
+
+/// Bits of precision a sample format can carry; f32 holds 24-bit audio exactly.
+fn format_bits(format: cpal::SampleFormat) -> u32 {
+    match format {
+        cpal::SampleFormat::F32 => 24,
+        cpal::SampleFormat::F64 => 53,
+        other => other.sample_size() as u32 * 8,
+    }
+}
+
+/// What `suspend` keeps while the output device is released.
+struct SuspendedOutput {
+    /// None when decoding had already reached the end; the ring buffer then
+    /// still holds the rest of the track.
+    decoder: Option<AudioDecoder>,
+}
+
 pub struct AudioPlayer {
-    /// cpal stream handle (kept alive)
+    /// Output stream handle (kept alive)
     stream: SendStream,
-    /// Sample buffer shared with the output callback
-    ring: Arc<(Mutex<SampleRingBuffer>, Condvar)>,
+    /// Device output or the null backend
+    backend: OutputBackend,
+    /// Sample buffer shared with the output callback; replaced per stream
+    ring: SharedRing,
     /// Volume [0.0, 1.0]
     volume: Arc<Mutex<f32>>,
+    /// Volume remembered for each output device, set before a stream on that
+    /// device starts. A copy of the config's `device_volumes`.
+    device_volumes: HashMap<String, f32>,
+    /// While muted, opening another device doesn't bring the volume back
+    muted: bool,
+    /// Per-track gain as a linear factor (from user gain offsets); replaced
+    /// per stream so a new track's gain never reaches the previous one
+    gain: Arc<Mutex<f32>>,
+    /// Gain the next stream starts with
+    next_gain: Mutex<f32>,
+    /// Position in seconds the next stream starts at, taken by the next play
+    next_start: Option<f64>,
     /// Samples played counter (for position tracking)
     samples_played: Arc<AtomicU64>,
     /// Sample rate of the current track
@@ -38,42 +287,68 @@ pub struct AudioPlayer {
     channels: Arc<Mutex<usize>>,
     /// Whether playback is active
     playing: Arc<AtomicBool>,
-    /// Handle to the decode thread
-    decode_handle: Option<std::thread::JoinHandle<()>>,
+    /// Handle to the decode thread, which returns its decoder when stopped
+    decode_handle: Option<std::thread::JoinHandle<Option<AudioDecoder>>>,
     /// Signal to stop the decode thread
     stop_signal: Arc<AtomicBool>,
     /// Total duration in seconds (from track metadata)
     total_duration: Arc<Mutex<f64>>,
     /// Seek target in milliseconds (NO_SEEK = no pending seek).
-    /// The decode thread reads and clears this.
+    /// The decode thread reads and clears this; replaced per stream.
     seek_target_ms: Arc<AtomicU64>,
     /// Saved samples_played value before the most recent seek.
     /// The decode thread restores this on seek failure so the UI
     /// position doesn't desync.
     pre_seek_samples: Arc<AtomicU64>,
+    /// Count of seeks the decode thread has finished, successfully or not
+    seeks_resolved: Arc<AtomicU64>,
     /// Abort handle for the current stream source, used to unblock
     /// the decode thread if it's waiting for data during a seek.
     stream_abort: Option<StreamAbortHandle>,
+    /// Headphone crossfeed settings, read by the decode thread between packets
+    crossfeed: Arc<Mutex<CrossfeedSettings>>,
+    /// When playback was paused, for releasing the device after a long pause
+    paused_at: Option<Instant>,
+    /// Set while the output device is released; see `suspend`
+    suspended: Option<SuspendedOutput>,
+    /// Name of the device the output stream was opened on
+    device_name: Option<String>,
+    /// The device's default (mixer) config when the stream was opened
+    device_config: Option<(u32, cpal::SampleFormat)>,
+    /// Bit depth reported by the current track's container
+    source_bits_per_sample: Option<u32>,
+    /// Container and codec the current track's bytes turned out to be
+    source_detected: DetectedFormat,
+    /// The current track's rate and channel count as its codec parameters
+    /// reported them, when the decoded audio differed
+    source_corrected_from: Option<(u32, usize)>,
+    /// Output samples left in the fade-in after a device switch
+    fade_in_remaining: Arc<AtomicU32>,
+    /// Ducking gain (f32 bits) the output callback ramps towards; 1.0 is not ducked
+    duck_target: Arc<AtomicU32>,
+    /// Bumped by every new stream and by `stop`, so events about an earlier
+    /// stream can be told apart
+    playback_epoch: u64,
 }
 
 impl AudioPlayer {
-    pub fn new() -> AppResult<Self> {
-        let ring = Arc::new((
-            Mutex::new(SampleRingBuffer {
-                buffer: VecDeque::with_capacity(88200),
-                finished: false,
-            }),
-            Condvar::new(),
-        ));
+    pub fn new(backend: OutputBackend) -> AppResult<Self> {
+        let ring = new_ring();
 
         let volume = Arc::new(Mutex::new(1.0f32));
         let samples_played = Arc::new(AtomicU64::new(0));
         let playing = Arc::new(AtomicBool::new(false));
 
         Ok(Self {
-            stream: SendStream(None),
+            stream: SendStream::new(),
+            backend,
             ring,
             volume,
+            device_volumes: HashMap::new(),
+            muted: false,
+            gain: Arc::new(Mutex::new(1.0)),
+            next_gain: Mutex::new(1.0),
+            next_start: None,
             samples_played,
             sample_rate: Arc::new(Mutex::new(44100)),
             channels: Arc::new(Mutex::new(2)),
@@ -83,37 +358,172 @@ impl AudioPlayer {
             total_duration: Arc::new(Mutex::new(0.0)),
             seek_target_ms: Arc::new(AtomicU64::new(NO_SEEK)),
             pre_seek_samples: Arc::new(AtomicU64::new(0)),
+            seeks_resolved: Arc::new(AtomicU64::new(0)),
             stream_abort: None,
+            crossfeed: Arc::new(Mutex::new(CrossfeedSettings::default())),
+            paused_at: None,
+            suspended: None,
+            device_name: None,
+            device_config: None,
+            source_bits_per_sample: None,
+            source_detected: DetectedFormat::default(),
+            source_corrected_from: None,
+            fade_in_remaining: Arc::new(AtomicU32::new(0)),
+            duck_target: Arc::new(AtomicU32::new(1.0f32.to_bits())),
+            playback_epoch: 0,
         })
     }
 
-    pub fn play_stream(
+    /// Open `source` and play it, holding the player throughout.
+    #[cfg(test)]
+    pub fn play_source(
         &mut self,
-        source: HttpStreamSource,
-        abort_handle: StreamAbortHandle,
+        source: Box<dyn MediaSource>,
+        abort_handle: Option<StreamAbortHandle>,
         codec_hint: Option<&str>,
-        duration: f64,
+        duration: Option<f64>,
     ) -> AppResult<()> {
-        self.stop_internal();
-        self.stream_abort = Some(abort_handle);
+        self.play(OpenedTrack::source(
+            source,
+            abort_handle,
+            codec_hint,
+            duration,
+        )?)
+    }
 
-        let mut decoder = AudioDecoder::new(source, codec_hint)?;
+    /// Start playing an opened track in place of the current one.
+    pub fn play(&mut self, track: OpenedTrack) -> AppResult<()> {
+        let handoff_started = Instant::now();
+        let start_at = self.next_start.take().filter(|s| *s > 0.0);
+        // Don't wait for the old decode thread: it sees the stop signal within
+        // a packet and exits on its own, and it only holds the old ring and
+        // seek slot. Joining it here delayed the next track's start.
+        drop(self.signal_decode_stop());
+        self.playback_epoch += 1;
+        self.reset_output();
+        self.ring = new_ring();
+        self.gain = Arc::new(Mutex::new(*self.next_gain.lock().unwrap()));
+        self.seek_target_ms = Arc::new(AtomicU64::new(NO_SEEK));
+        self.stream_abort = track.abort_handle;
+
+        let decoder = track.decoder;
+        if track.corrected_from.is_some() {
+            log::warn!(
+                "Opening output at {} Hz / {} ch to match the decoded audio",
+                decoder.sample_rate(),
+                decoder.channels()
+            );
+        }
         let sr = decoder.sample_rate();
         let ch = decoder.channels();
+        self.source_bits_per_sample = decoder.bits_per_sample();
+        self.source_detected = decoder.detected_format();
+        self.source_corrected_from = track.corrected_from;
+        let duration = track
+            .duration
+            .filter(|d| *d > 0.0)
+            .or_else(|| decoder.duration_seconds())
+            .unwrap_or(0.0);
 
         *self.sample_rate.lock().unwrap() = sr;
         *self.channels.lock().unwrap() = ch;
         *self.total_duration.lock().unwrap() = duration;
         self.samples_played.store(0, Ordering::SeqCst);
-        // Clear any stale seek from a previous track
-        self.seek_target_ms.store(NO_SEEK, Ordering::SeqCst);
+        self.pre_seek_samples.store(0, Ordering::SeqCst);
+        if let Some(start) = start_at {
+            // The decode thread seeks before it fills the ring, so the output
+            // never plays the beginning
+            self.seek_target_ms
+                .store((start * 1000.0) as u64, Ordering::SeqCst);
+            self.samples_played
+                .store((start * sr as f64 * ch as f64) as u64, Ordering::SeqCst);
+        }
 
-        {
-            let (lock, cvar) = &*self.ring;
+        self.open_output(sr, ch)?;
+        self.playing.store(true, Ordering::SeqCst);
+        self.paused_at = None;
+        self.spawn_decode_thread(decoder);
+        log::info!(
+            "Stream handoff took {} ms",
+            handoff_started.elapsed().as_millis()
+        );
+        Ok(())
+    }
+
+    /// Open the default output device (or the null backend) and start a stream
+    /// that drains the ring buffer.
+    fn open_output(&mut self, sr: u32, ch: usize) -> AppResult<()> {
+        // Close any previous stream before the new one starts, so two never
+        // play at once
+        self.stream.close();
+        let pending = self.build_output(sr, ch)?;
+        self.start_output(pending)
+    }
+
+    /// Build a stream on the default device without starting it.
+    fn build_output(&self, sr: u32, ch: usize) -> AppResult<PendingOutput> {
+        let ring_clone = Arc::clone(&self.ring);
+        let volume_clone = Arc::clone(&self.volume);
+        let gain_clone = Arc::clone(&self.gain);
+        let samples_played_clone = Arc::clone(&self.samples_played);
+        let playing_clone = Arc::clone(&self.playing);
+        let fade_remaining = Arc::clone(&self.fade_in_remaining);
+        let fade_total = (sr * ch as u32 * DEVICE_SWITCH_FADE_MS / 1000).max(1);
+        let duck_target = Arc::clone(&self.duck_target);
+        let duck_step = 1.0 / (sr * ch as u32 * DUCK_FADE_MS / 1000).max(1) as f32;
+        let mut duck = f32::from_bits(duck_target.load(Ordering::Relaxed));
+
+        // Shared by both backends so position and EOF behave the same
+        let mut render = move |data: &mut [f32]| {
+            if !playing_clone.load(Ordering::Relaxed) {
+                data.fill(0.0);
+                return;
+            }
+
+            let vol = *volume_clone.lock().unwrap();
+            let gain = *gain_clone.lock().unwrap();
+            let (lock, cvar) = &*ring_clone;
             let mut ring = lock.lock().unwrap();
-            ring.buffer.clear();
-            ring.finished = false;
+
+            let available = ring.buffer.len().min(data.len());
+            let mut fade = fade_remaining.load(Ordering::Relaxed);
+            let duck_to = f32::from_bits(duck_target.load(Ordering::Relaxed));
+            for (i, sample) in data.iter_mut().enumerate() {
+                if i < available {
+                    if duck != duck_to {
+                        duck = if duck < duck_to {
+                            (duck + duck_step).min(duck_to)
+                        } else {
+                            (duck - duck_step).max(duck_to)
+                        };
+                    }
+                    let value = ring.buffer.pop_front().unwrap_or(0.0) * gain * duck;
+                    // Positive gain can push peaks past full scale
+                    *sample = if gain > 1.0 {
+                        value.clamp(-1.0, 1.0) * vol
+                    } else {
+                        value * vol
+                    };
+                    if fade > 0 {
+                        *sample *= 1.0 - fade as f32 / fade_total as f32;
+                        fade -= 1;
+                    }
+                } else {
+                    *sample = 0.0;
+                }
+            }
+            fade_remaining.store(fade, Ordering::Relaxed);
+
+            samples_played_clone.fetch_add(available as u64, Ordering::Relaxed);
             cvar.notify_all();
+        };
+
+        if self.backend == OutputBackend::Null {
+            return Ok(PendingOutput {
+                stream: PendingStream::Null(Box::new(move || NullOutput::start(sr, ch, render))),
+                device_name: Some(NULL_DEVICE_NAME.to_string()),
+                device_config: None,
+            });
         }
 
         let host = cpal::default_host();
@@ -127,36 +537,10 @@ impl AudioPlayer {
             buffer_size: cpal::BufferSize::Default,
         };
 
-        let ring_clone = Arc::clone(&self.ring);
-        let volume_clone = Arc::clone(&self.volume);
-        let samples_played_clone = Arc::clone(&self.samples_played);
-        let playing_clone = Arc::clone(&self.playing);
-
         let cpal_stream = device
             .build_output_stream(
                 &stream_config,
-                move |data: &mut [f32], _: &cpal::OutputCallbackInfo| {
-                    if !playing_clone.load(Ordering::Relaxed) {
-                        data.fill(0.0);
-                        return;
-                    }
-
-                    let vol = *volume_clone.lock().unwrap();
-                    let (lock, cvar) = &*ring_clone;
-                    let mut ring = lock.lock().unwrap();
-
-                    let available = ring.buffer.len().min(data.len());
-                    for (i, sample) in data.iter_mut().enumerate() {
-                        if i < available {
-                            *sample = ring.buffer.pop_front().unwrap_or(0.0) * vol;
-                        } else {
-                            *sample = 0.0;
-                        }
-                    }
-
-                    samples_played_clone.fetch_add(available as u64, Ordering::Relaxed);
-                    cvar.notify_all();
-                },
+                move |data: &mut [f32], _: &cpal::OutputCallbackInfo| render(data),
                 |err| {
                     log::error!("cpal output error: {}", err);
                 },
@@ -164,28 +548,155 @@ impl AudioPlayer {
             )
             .map_err(|e| AppError::Audio(format!("Failed to build output stream: {}", e)))?;
 
-        cpal_stream
-            .play()
-            .map_err(|e| AppError::Audio(format!("Failed to start playback: {}", e)))?;
+        Ok(PendingOutput {
+            stream: PendingStream::Cpal(cpal_stream),
+            device_name: device.name().ok(),
+            device_config: device
+                .default_output_config()
+                .ok()
+                .map(|c| (c.sample_rate().0, c.sample_format())),
+        })
+    }
 
-        self.stream = SendStream(Some(cpal_stream));
-        self.playing.store(true, Ordering::SeqCst);
+    /// Start a built stream and make it the open output. The previous stream
+    /// must already be closed.
+    fn start_output(&mut self, pending: PendingOutput) -> AppResult<()> {
+        // Before the first sample, so the device never plays at the old volume
+        let remembered = pending
+            .device_name
+            .as_deref()
+            .and_then(|d| self.device_volumes.get(d));
+        if let (Some(&volume), false) = (remembered, self.muted) {
+            self.set_volume(volume);
+        }
+        let stream = match pending.stream {
+            PendingStream::Cpal(stream) => {
+                stream
+                    .play()
+                    .map_err(|e| AppError::Audio(format!("Failed to start playback: {}", e)))?;
+                OutputStream::Cpal { _stream: stream }
+            }
+            PendingStream::Null(start) => OutputStream::Null { _output: start() },
+        };
+        self.stream.replace(Some(stream));
+        self.device_name = pending.device_name;
+        self.device_config = pending.device_config;
+        Ok(())
+    }
+
+    /// Name of the system default output device, when it differs from the one
+    /// the open stream plays on.
+    pub fn default_output_changed(&self) -> Option<String> {
+        if !self.stream.is_open() || self.backend == OutputBackend::Null {
+            return None;
+        }
+        let name = cpal::default_host().default_output_device()?.name().ok()?;
+        (self.device_name.as_deref() != Some(name.as_str())).then_some(name)
+    }
+
+    /// Move the open stream to the current default device. The ring buffer and
+    /// decoder carry on untouched, so playback continues at the same position;
+    /// a short fade-in masks the switch.
+    ///
+    /// If the new device can't be opened, the old stream keeps playing.
+    pub fn switch_to_default_output(&mut self) -> AppResult<()> {
+        if !self.stream.is_open() {
+            return Ok(());
+        }
+        let sr = *self.sample_rate.lock().unwrap();
+        let ch = *self.channels.lock().unwrap();
+        let pending = self.build_output(sr, ch)?;
+        self.stream.close();
+        self.fade_in_remaining.store(
+            (sr * ch as u32 * DEVICE_SWITCH_FADE_MS / 1000).max(1),
+            Ordering::SeqCst,
+        );
+        self.start_output(pending)
+    }
+
+    /// Memory held by the playing track's download; 0 for other sources.
+    pub fn stream_buffer_bytes(&self) -> u64 {
+        self.stream_abort
+            .as_ref()
+            .map_or(0, |abort| abort.buffered_bytes())
+    }
+
+    /// How much of the playing track has downloaded. None for sources that
+    /// aren't downloaded, like local files, which are available in full.
+    pub fn stream_progress(&self) -> Option<StreamProgress> {
+        self.stream_abort.as_ref().map(|abort| abort.progress())
+    }
+
+    pub fn output_device_name(&self) -> Option<&str> {
+        self.device_name.as_deref()
+    }
 
+    /// Device the volume applies to: the open stream's, or the one the next
+    /// stream will open on.
+    pub fn active_output_name(&self) -> Option<String> {
+        if self.stream.is_open() {
+            return self.device_name.clone();
+        }
+        self.backend.device_name()
+    }
+
+    /// Details of the open output path; None while no stream is open.
+    pub fn output_info(&self) -> Option<OutputInfo> {
+        self.stream.current.as_ref()?;
+        // There is no resampler: the stream is always opened at the source rate
+        let source_rate = *self.sample_rate.lock().unwrap();
+        let source_bits = self.source_bits_per_sample.unwrap_or(16);
+        let device_lossless = match self.device_config {
+            Some((rate, format)) => rate == source_rate && format_bits(format) >= source_bits,
+            None => true,
+        };
+        Some(OutputInfo {
+            device_name: self.device_name.clone(),
+            stream_sample_rate: source_rate,
+            stream_format: format_name(STREAM_FORMAT),
+            device_sample_rate: self.device_config.map(|(rate, _)| rate),
+            device_format: self.device_config.map(|(_, format)| format_name(format)),
+            source_sample_rate: source_rate,
+            source_bits_per_sample: self.source_bits_per_sample,
+            lossless_path: format_bits(STREAM_FORMAT) >= source_bits && device_lossless,
+        })
+    }
+
+    /// Start the thread that decodes into the ring buffer. It hands the decoder
+    /// back when stopped so `suspend` can keep it; at EOF or on error it returns None.
+    fn spawn_decode_thread(&mut self, mut decoder: AudioDecoder) {
         let ring_clone = Arc::clone(&self.ring);
         let stop_signal = Arc::new(AtomicBool::new(false));
         self.stop_signal = Arc::clone(&stop_signal);
         let seek_target = Arc::clone(&self.seek_target_ms);
         let pre_seek = Arc::clone(&self.pre_seek_samples);
+        let seeks_resolved = Arc::clone(&self.seeks_resolved);
         let samples_played_decode = Arc::clone(&self.samples_played);
-        let sr_decode = sr;
-        let ch_decode = ch;
+        let sr_decode = *self.sample_rate.lock().unwrap();
+        let ch_decode = *self.channels.lock().unwrap();
+        let crossfeed_settings = Arc::clone(&self.crossfeed);
+        let exit = DecodeExitGuard {
+            ring: Arc::clone(&self.ring),
+            stop_signal: Arc::clone(&stop_signal),
+            playing: Arc::clone(&self.playing),
+            failed: true,
+        };
 
         let handle = std::thread::spawn(move || {
             const MAX_RING_SAMPLES: usize = 176400;
+            // Bind the whole guard so it moves into the thread and drops there.
+            // Assigning one field would only capture that field.
+            let mut exit = exit;
+
+            // Built per track so the filter matches this track's sample rate
+            let mut active_crossfeed = *crossfeed_settings.lock().unwrap();
+            let mut crossfeed = Crossfeed::new(active_crossfeed, sr_decode, ch_decode);
+            // The output can't change format mid-track; flag it once if the audio does
+            let mut format_mismatch_logged = false;
 
             loop {
                 if stop_signal.load(Ordering::Relaxed) {
-                    break;
+                    return Some(decoder);
                 }
 
                 // Check for pending seek request
@@ -195,7 +706,13 @@ impl AudioPlayer {
                     log::info!("Decode thread: seeking to {:.2}s", seek_seconds);
 
                     // Seek the decoder first; only clear buffer if it succeeds
-                    match decoder.seek(seek_seconds) {
+                    let result = decoder.seek(seek_seconds);
+                    // A detached thread must not touch the position the next
+                    // track now owns
+                    if stop_signal.load(Ordering::Relaxed) {
+                        return Some(decoder);
+                    }
+                    match result {
                         Ok(()) => {
                             // Clear the ring buffer so stale audio is discarded
                             {
@@ -218,6 +735,7 @@ impl AudioPlayer {
                             log::info!("Decode thread: restored position after failed seek");
                         }
                     }
+                    seeks_resolved.fetch_add(1, Ordering::SeqCst);
                     continue;
                 }
 
@@ -233,7 +751,7 @@ impl AudioPlayer {
                 }
 
                 if stop_signal.load(Ordering::Relaxed) {
-                    break;
+                    return Some(decoder);
                 }
 
                 // Re-check seek after waking from wait
@@ -242,39 +760,66 @@ impl AudioPlayer {
                 }
 
                 match decoder.decode_next() {
-                    Ok(Some(decoded)) => {
+                    Ok(Some(mut decoded)) => {
+                        if (decoded.sample_rate, decoded.channels) != (sr_decode, ch_decode)
+                            && !format_mismatch_logged
+                        {
+                            log::error!(
+                                "[decode] Audio changed to {} Hz / {} ch, output is {} Hz / {} ch",
+                                decoded.sample_rate,
+                                decoded.channels,
+                                sr_decode,
+                                ch_decode
+                            );
+                            format_mismatch_logged = true;
+                        }
+                        let settings = *crossfeed_settings.lock().unwrap();
+                        if settings != active_crossfeed {
+                            active_crossfeed = settings;
+                            crossfeed =
+                                Crossfeed::new(settings, decoded.sample_rate, decoded.channels);
+                        }
+                        if let Some(filter) = crossfeed.as_mut() {
+                            filter.process(&mut decoded.samples);
+                        }
+
                         let (lock, cvar) = &*ring_clone;
                         let mut ring = lock.lock().unwrap();
                         ring.buffer.extend(decoded.samples.iter());
                         cvar.notify_all();
                     }
                     Ok(None) => {
-                        log::info!("[decode] EOF reached, setting finished=true");
-                        let (lock, cvar) = &*ring_clone;
-                        let mut ring = lock.lock().unwrap();
-                        let buf_len = ring.buffer.len();
-                        ring.finished = true;
-                        cvar.notify_all();
-                        log::info!("[decode] Ring buffer has {} samples remaining", buf_len);
-                        break;
+                        let (lock, _) = &*ring_clone;
+                        let buf_len = lock.lock().unwrap().buffer.len();
+                        log::info!(
+                            "[decode] EOF reached, ring buffer has {} samples remaining",
+                            buf_len
+                        );
+                        exit.failed = false;
+                        return None;
                     }
                     Err(e) => {
+                        // `exit` marks the ring finished and failed
                         log::error!("[decode] Decode error: {}", e);
-                        let (lock, cvar) = &*ring_clone;
-                        let mut ring = lock.lock().unwrap();
-                        ring.finished = true;
-                        cvar.notify_all();
-                        break;
+                        return None;
                     }
                 }
             }
         });
 
         self.decode_handle = Some(handle);
-        Ok(())
     }
 
     fn stop_internal(&mut self) {
+        if let Some(handle) = self.signal_decode_stop() {
+            let _ = handle.join();
+        }
+        self.reset_output();
+    }
+
+    /// Tell the decode thread to stop and wake it wherever it is blocked.
+    /// Returns its handle for the caller to join or drop.
+    fn signal_decode_stop(&mut self) -> Option<std::thread::JoinHandle<Option<AudioDecoder>>> {
         self.stop_signal.store(true, Ordering::SeqCst);
         self.playing.store(false, Ordering::SeqCst);
 
@@ -289,28 +834,111 @@ impl AudioPlayer {
             let mut ring = lock.lock().unwrap();
             ring.buffer.clear();
             ring.finished = false;
+            ring.failed = false;
             cvar.notify_all();
         }
 
-        if let Some(handle) = self.decode_handle.take() {
-            let _ = handle.join();
+        self.decode_handle.take()
+    }
+
+    /// Close the output stream and forget any pause or suspension.
+    fn reset_output(&mut self) {
+        self.stream.close();
+        self.stop_signal = Arc::new(AtomicBool::new(false));
+        self.suspended = None;
+        self.paused_at = None;
+    }
+
+    /// Release the output device and stop decoding while paused, keeping the
+    /// downloaded stream, the decoder and the position. `resume` rebuilds both.
+    pub fn suspend(&mut self) {
+        if self.is_playing() || self.suspended.is_some() || !self.stream.is_open() {
+            return;
+        }
+
+        self.stop_signal.store(true, Ordering::SeqCst);
+        {
+            let (_lock, cvar) = &*self.ring;
+            cvar.notify_all();
         }
+        let decoder = self
+            .decode_handle
+            .take()
+            .and_then(|handle| handle.join().ok())
+            .flatten();
 
-        self.stream = SendStream(None);
+        if decoder.is_some() {
+            // Audio decoded ahead is dropped; the decoder seeks back on restore
+            let (lock, _) = &*self.ring;
+            lock.lock().unwrap().buffer.clear();
+        }
+
+        self.stream.close();
         self.stop_signal = Arc::new(AtomicBool::new(false));
+        self.suspended = Some(SuspendedOutput { decoder });
+        log::info!(
+            "Released audio output after a long pause at {:.1}s",
+            self.position_seconds()
+        );
+    }
+
+    /// Reopen the output device and restart decoding from the saved position.
+    fn restore_output(&mut self) -> AppResult<()> {
+        let Some(suspended) = self.suspended.take() else {
+            return Ok(());
+        };
+
+        let sr = *self.sample_rate.lock().unwrap();
+        let ch = *self.channels.lock().unwrap();
+        if let Err(e) = self.open_output(sr, ch) {
+            self.suspended = Some(suspended);
+            return Err(e);
+        }
+
+        if let Some(decoder) = suspended.decoder {
+            self.pre_seek_samples
+                .store(self.samples_played.load(Ordering::SeqCst), Ordering::SeqCst);
+            let position_ms = (self.position_seconds() * 1000.0) as u64;
+            // A seek made while suspended already points at the right place
+            let _ = self.seek_target_ms.compare_exchange(
+                NO_SEEK,
+                position_ms,
+                Ordering::SeqCst,
+                Ordering::SeqCst,
+            );
+            self.spawn_decode_thread(decoder);
+        }
+        log::info!("Restored audio output at {:.1}s", self.position_seconds());
+        Ok(())
+    }
+
+    pub fn is_suspended(&self) -> bool {
+        self.suspended.is_some()
+    }
+
+    /// How long playback has been paused, if it is.
+    pub fn paused_for(&self) -> Option<Duration> {
+        self.paused_at.map(|at| at.elapsed())
     }
 
     pub fn stop(&mut self) {
         self.stop_internal();
         self.samples_played.store(0, Ordering::SeqCst);
+        // Progress read before the stop is about the stream that just ended
+        self.playback_epoch += 1;
     }
 
     pub fn pause(&mut self) {
         self.playing.store(false, Ordering::SeqCst);
+        self.paused_at.get_or_insert_with(Instant::now);
     }
 
-    pub fn resume(&mut self) {
+    /// Resume playback, reopening the output device first if it was released.
+    pub fn resume(&mut self) -> AppResult<()> {
+        self.restore_output()?;
         self.playing.store(true, Ordering::SeqCst);
+        self.paused_at = None;
+        Ok(())
     }
 
     pub fn is_playing(&self) -> bool {
@@ -325,6 +953,58 @@ impl AudioPlayer {
         *self.volume.lock().unwrap()
     }
 
+    /// Volumes to use per output device, applied as a stream opens on one.
+    /// Devices without an entry keep the current volume.
+    pub fn set_device_volumes(&mut self, volumes: HashMap<String, f32>) {
+        self.device_volumes = volumes;
+    }
+
+    /// Remember `volume` for `device`, for the next time a stream opens on it.
+    pub fn remember_device_volume(&mut self, device: &str, volume: f32) {
+        self.device_volumes.insert(device.to_string(), volume);
+    }
+
+    /// Silence the output, or restore `volume` when unmuting.
+    pub fn set_muted(&mut self, muted: bool, volume: f32) {
+        self.muted = muted;
+        self.set_volume(if muted { 0.0 } else { volume });
+    }
+
+    /// Fade the output to `gain` (linear, 1.0 restores it) while another app
+    /// has the user's attention.
+    pub fn set_duck(&self, gain: f32) {
+        self.duck_target
+            .store(gain.clamp(0.0, 1.0).to_bits(), Ordering::Relaxed);
+    }
+
+    /// Set the gain applied on top of volume, in dB, for the playing stream
+    /// and the ones after it.
+    pub fn set_gain_db(&self, db: f32) {
+        let gain = 10f32.powf(db / 20.0);
+        *self.gain.lock().unwrap() = gain;
+        *self.next_gain.lock().unwrap() = gain;
+    }
+
+    /// Set the gain, in dB, the next stream starts with. The playing stream
+    /// keeps its own until it is replaced.
+    pub fn set_next_gain_db(&self, db: f32) {
+        *self.next_gain.lock().unwrap() = 10f32.powf(db / 20.0);
+    }
+
+    /// Start the next stream at `seconds` instead of the beginning. Only the
+    /// next play uses it, whether or not it succeeds.
+    pub fn set_next_start(&mut self, seconds: f64) {
+        self.next_start = Some(seconds);
+    }
+
+    pub fn set_crossfeed(&self, settings: CrossfeedSettings) {
+        *self.crossfeed.lock().unwrap() = settings;
+    }
+
+    pub fn crossfeed(&self) -> CrossfeedSettings {
+        *self.crossfeed.lock().unwrap()
+    }
+
     pub fn position_seconds(&self) -> f64 {
         let samples = self.samples_played.load(Ordering::Relaxed) as f64;
         let sr = *self.sample_rate.lock().unwrap() as f64;
@@ -340,6 +1020,24 @@ impl AudioPlayer {
         *self.total_duration.lock().unwrap()
     }
 
+    /// The playing stream as the decoder probed it.
+    pub fn source_format(&self) -> SourceFormat {
+        SourceFormat {
+            sample_rate: *self.sample_rate.lock().unwrap(),
+            channels: *self.channels.lock().unwrap(),
+            bit_depth: self.source_bits_per_sample,
+            detected: self.source_detected,
+            corrected_from: self.source_corrected_from,
+        }
+    }
+
+    /// Which stream the position and state belong to; stopping starts a new,
+    /// empty one. Changes only under a write lock, so values read under one
+    /// read lock are from the same stream.
+    pub fn playback_epoch(&self) -> u64 {
+        self.playback_epoch
+    }
+
     pub fn seek(&self, position_seconds: f64) {
         // Save current position so the decode thread can restore it if seek fails
         let old_samples = self.samples_played.load(Ordering::SeqCst);
@@ -349,31 +1047,59 @@ impl AudioPlayer {
         let ms = (position_seconds * 1000.0) as u64;
         self.seek_target_ms.store(ms, Ordering::SeqCst);
 
-        // Wake the decode thread if it's waiting on the ring buffer
-        let (_lock, cvar) = &*self.ring;
-        cvar.notify_all();
-
         // Immediately update the position counter for responsive UI
         let sr = *self.sample_rate.lock().unwrap() as f64;
         let ch = *self.channels.lock().unwrap() as f64;
         let sample_position = (position_seconds * sr * ch) as u64;
         self.samples_played.store(sample_position, Ordering::SeqCst);
+
+        // Wake the decode thread if it's waiting on the ring buffer. Notify
+        // under the lock: while paused the output callback never notifies, so
+        // a wakeup lost between its check and its wait would hold the seek
+        // until playback resumes.
+        {
+            let (lock, cvar) = &*self.ring;
+            let _ring = lock.lock().unwrap();
+            cvar.notify_all();
+        }
+
+        // Without a decode thread (suspended or stopped) there is nothing to
+        // wait for; a suspended decoder seeks when it's restored
+        if self.decode_handle.is_none() {
+            self.seeks_resolved.fetch_add(1, Ordering::SeqCst);
+        }
     }
 
+    /// Increments each time a seek has been applied by the decode thread, so
+    /// callers can report the settled position.
+    pub fn seeks_resolved(&self) -> u64 {
+        self.seeks_resolved.load(Ordering::SeqCst)
+    }
+
+    /// Whether the track has ended: played out to EOF, or cut short by a
+    /// decode error, in which case the audio still buffered is dropped.
     pub fn is_finished(&self) -> bool {
         let (lock, _) = &*self.ring;
         let ring = lock.lock().unwrap();
-        ring.finished && ring.buffer.is_empty()
+        ring.finished && (ring.failed || ring.buffer.is_empty())
+    }
+
+    /// Whether the current track ended on a decode error.
+    pub fn decode_failed(&self) -> bool {
+        let (lock, _) = &*self.ring;
+        lock.lock().unwrap().failed
     }
 
     pub fn start_download(
         writer: StreamWriter,
         url: String,
-        client: reqwest::Client,
+        client: Arc<TidalClient>,
     ) -> tokio::task::JoinHandle<()> {
-        tokio::spawn(async move {
+        let stream_abort = writer.abort_handle();
+        let handle = tokio::spawn(async move {
             log::info!("Starting audio download: {}...", &url[..url.len().min(100)]);
-            match client.get(&url).send().await {
+            let started = Instant::now();
+            match client.http_client().get(&url).send().await {
                 Ok(response) => {
                     let status = response.status();
                     let content_type = response
@@ -422,6 +1148,13 @@ impl AudioPlayer {
                     let mut stream = response.bytes_stream();
                     let mut total_bytes = 0u64;
                     while let Some(chunk) = stream.next().await {
+                        if writer.is_closed() {
+                            log::info!(
+                                "Audio download: stream aborted after {} bytes",
+                                total_bytes
+                            );
+                            return;
+                        }
                         match chunk {
                             Ok(bytes) => {
                                 total_bytes += bytes.len() as u64;
@@ -446,13 +1179,23 @@ impl AudioPlayer {
                     }
                     log::info!("Audio download complete: {} bytes", total_bytes);
                     writer.finish();
+                    client.record_download(total_bytes, started.elapsed()).await;
                 }
                 Err(e) => {
                     log::error!("Failed to start audio download: {}", e);
                     writer.set_error(format!("Failed to start download: {}", e));
                 }
             }
-        })
+        });
+        stream_abort.attach_download(handle.abort_handle());
+        handle
+    }
+
+    /// Abort the download feeding the current stream, if any.
+    pub fn abort_download(&self) {
+        if let Some(abort) = &self.stream_abort {
+            abort.abort();
+        }
     }
 }
 
@@ -461,3 +1204,496 @@ impl Drop for AudioPlayer {
         self.stop_internal();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::stream_source::HttpStreamSource;
+    use crate::config::AppConfig;
+    use crate::test_support::{adts_silence, endless_http_body, flac, wait_until, wav};
+    use std::io::Cursor;
+    use tokio::sync::RwLock;
+
+    fn client() -> Arc<TidalClient> {
+        Arc::new(TidalClient::new(Arc::new(RwLock::new(AppConfig::default()))).unwrap())
+    }
+
+    fn play_tone(player: &mut AudioPlayer, seconds: u32) {
+        let file = wav(44_100, 2, 44_100 * seconds);
+        player
+            .play_source(Box::new(Cursor::new(file)), None, None, None)
+            .unwrap();
+    }
+
+    #[test]
+    fn next_gain_waits_for_the_next_stream() {
+        let mut player = AudioPlayer::new(OutputBackend::Null).unwrap();
+        player.set_gain_db(-6.0);
+        play_tone(&mut player, 2);
+        let first = Arc::clone(&player.gain);
+
+        player.set_next_gain_db(6.0);
+        assert_eq!(*first.lock().unwrap(), 10f32.powf(-6.0 / 20.0));
+
+        play_tone(&mut player, 2);
+        assert_eq!(*first.lock().unwrap(), 10f32.powf(-6.0 / 20.0));
+        assert_eq!(*player.gain.lock().unwrap(), 10f32.powf(6.0 / 20.0));
+        player.stop();
+    }
+
+    #[test]
+    fn live_gain_change_reaches_the_playing_stream() {
+        let mut player = AudioPlayer::new(OutputBackend::Null).unwrap();
+        play_tone(&mut player, 2);
+        player.set_gain_db(-12.0);
+        assert_eq!(*player.gain.lock().unwrap(), 10f32.powf(-12.0 / 20.0));
+
+        // ...and sticks when the same track is restarted
+        play_tone(&mut player, 2);
+        assert_eq!(*player.gain.lock().unwrap(), 10f32.powf(-12.0 / 20.0));
+        player.stop();
+    }
+
+    #[test]
+    fn switching_output_keeps_playing_from_the_same_position() {
+        let mut player = AudioPlayer::new(OutputBackend::Null).unwrap();
+        play_tone(&mut player, 5);
+        std::thread::sleep(Duration::from_millis(300));
+        let before = player.position_seconds();
+        assert!(before > 0.0);
+
+        player.switch_to_default_output().unwrap();
+        assert!(player.stream.is_open());
+        assert_eq!(player.output_device_name(), Some(NULL_DEVICE_NAME));
+        // Nothing skipped or restarted
+        assert!(player.position_seconds() >= before);
+        std::thread::sleep(Duration::from_millis(300));
+        assert!(player.position_seconds() > before);
+        player.stop();
+    }
+
+    #[test]
+    fn a_seek_while_paused_lands_before_resuming() {
+        let mut player = AudioPlayer::new(OutputBackend::Null).unwrap();
+        play_tone(&mut player, 10);
+        std::thread::sleep(Duration::from_millis(200));
+        player.pause();
+        let resolved = player.seeks_resolved();
+
+        player.seek(6.0);
+        let deadline = Instant::now() + Duration::from_secs(2);
+        while player.seeks_resolved() == resolved {
+            assert!(Instant::now() < deadline, "seek not applied while paused");
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        // Settled where asked, and staying there while paused
+        assert!((player.position_seconds() - 6.0).abs() < 0.05);
+        std::thread::sleep(Duration::from_millis(200));
+        assert!((player.position_seconds() - 6.0).abs() < 0.05);
+
+        // Resuming plays on from the seek instead of the buffered old audio
+        player.resume().unwrap();
+        for _ in 0..20 {
+            std::thread::sleep(Duration::from_millis(10));
+            assert!(player.position_seconds() >= 5.99);
+        }
+        assert!(player.position_seconds() > 6.0);
+        player.stop();
+    }
+
+    #[test]
+    fn a_start_position_is_applied_before_output_opens() {
+        let mut player = AudioPlayer::new(OutputBackend::Null).unwrap();
+        player.set_next_start(6.0);
+        let resolved = player.seeks_resolved();
+        play_tone(&mut player, 10);
+        // Counted from the start position straight away, never from zero
+        assert!(player.position_seconds() >= 6.0);
+        let deadline = Instant::now() + Duration::from_secs(2);
+        while player.seeks_resolved() == resolved {
+            assert!(Instant::now() < deadline, "start position not applied");
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        for _ in 0..20 {
+            assert!(player.position_seconds() >= 6.0);
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert!(player.position_seconds() > 6.0);
+
+        // Only the next play uses it
+        play_tone(&mut player, 10);
+        assert!(player.position_seconds() < 1.0);
+        player.stop();
+    }
+
+    #[test]
+    fn a_remembered_device_volume_is_set_before_the_stream_starts() {
+        let mut player = AudioPlayer::new(OutputBackend::Null).unwrap();
+        player.set_volume(0.8);
+        player.set_device_volumes(HashMap::from([("Speakers".to_string(), 0.5)]));
+        // No entry for this device: the current volume carries over
+        play_tone(&mut player, 2);
+        assert_eq!(player.volume(), 0.8);
+
+        player.remember_device_volume(NULL_DEVICE_NAME, 0.3);
+        player.switch_to_default_output().unwrap();
+        assert_eq!(player.volume(), 0.3);
+        player.set_volume(0.9);
+        play_tone(&mut player, 2);
+        assert_eq!(player.volume(), 0.3);
+
+        // Muted stays muted whichever device opens
+        player.set_muted(true, 0.3);
+        play_tone(&mut player, 2);
+        assert_eq!(player.volume(), 0.0);
+        player.set_muted(false, 0.3);
+        assert_eq!(player.volume(), 0.3);
+        player.stop();
+    }
+
+    #[test]
+    fn the_epoch_changes_with_each_stream_and_stop() {
+        let mut player = AudioPlayer::new(OutputBackend::Null).unwrap();
+        let idle = player.playback_epoch();
+        play_tone(&mut player, 10);
+        let first = player.playback_epoch();
+        assert!(first > idle);
+
+        // Pausing, seeking and resuming stay on the same stream
+        player.pause();
+        player.seek(4.0);
+        player.resume().unwrap();
+        assert_eq!(player.playback_epoch(), first);
+
+        player.stop();
+        let stopped = player.playback_epoch();
+        assert!(stopped > first);
+        play_tone(&mut player, 10);
+        assert!(player.playback_epoch() > stopped);
+        player.stop();
+    }
+
+    #[test]
+    fn a_snapshot_never_mixes_epochs_and_positions() {
+        let player = Arc::new(std::sync::RwLock::new(
+            AudioPlayer::new(OutputBackend::Null).unwrap(),
+        ));
+        play_tone(&mut player.write().unwrap(), 10);
+        player.read().unwrap().seek(8.0);
+        let old = player.read().unwrap().playback_epoch();
+
+        // A progress loop reading both under one read lock
+        let done = Arc::new(AtomicBool::new(false));
+        let reader = {
+            let player = Arc::clone(&player);
+            let done = Arc::clone(&done);
+            std::thread::spawn(move || {
+                let mut seen = Vec::new();
+                while !done.load(Ordering::SeqCst) {
+                    let p = player.read().unwrap();
+                    seen.push((p.playback_epoch(), p.position_seconds()));
+                    drop(p);
+                    std::thread::sleep(Duration::from_micros(200));
+                }
+                seen
+            })
+        };
+
+        std::thread::sleep(Duration::from_millis(50));
+        player.write().unwrap().stop();
+        let stopped = player.read().unwrap().playback_epoch();
+        std::thread::sleep(Duration::from_millis(50));
+        play_tone(&mut player.write().unwrap(), 10);
+        let new = player.read().unwrap().playback_epoch();
+        std::thread::sleep(Duration::from_millis(50));
+        done.store(true, Ordering::SeqCst);
+        let seen = reader.join().unwrap();
+
+        assert!(old < stopped && stopped < new);
+        for (epoch, position) in &seen {
+            match *epoch {
+                e if e == old => assert!(*position >= 7.9, "{}", position),
+                e if e == stopped => assert_eq!(*position, 0.0),
+                e if e == new => assert!(*position < 1.0, "{}", position),
+                e => panic!("unexpected epoch {}", e),
+            }
+        }
+        // Each phase was seen, in order
+        let epochs: Vec<u64> = seen.iter().map(|(e, _)| *e).collect();
+        assert!(epochs.windows(2).all(|w| w[0] <= w[1]));
+        for epoch in [old, stopped, new] {
+            assert!(epochs.contains(&epoch));
+        }
+        player.write().unwrap().stop();
+    }
+
+    /// Wait for the player to play out its track, failing after `limit`.
+    fn play_out(player: &AudioPlayer, limit: Duration) {
+        let deadline = Instant::now() + limit;
+        while !player.is_finished() {
+            assert!(Instant::now() < deadline, "still playing");
+            std::thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    #[test]
+    fn a_flac_fixture_plays_to_the_end() {
+        let mut player = AudioPlayer::new(OutputBackend::Null).unwrap();
+        let file = flac(44_100, 2, 22_050);
+        player
+            .play_source(Box::new(Cursor::new(file)), None, Some("FLAC"), None)
+            .unwrap();
+        assert_eq!(player.duration_seconds(), 0.5);
+
+        play_out(&player, Duration::from_secs(5));
+        assert!(!player.decode_failed());
+        assert!((player.position_seconds() - 0.5).abs() < 0.05);
+        player.stop();
+        assert!(!player.is_finished());
+    }
+
+    /// A stream whose connection breaks after `fail_at` bytes.
+    struct BrokenStream {
+        inner: Cursor<Vec<u8>>,
+        fail_at: u64,
+    }
+
+    impl std::io::Read for BrokenStream {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let left = self.fail_at.saturating_sub(self.inner.position());
+            if left == 0 {
+                return Err(std::io::ErrorKind::ConnectionReset.into());
+            }
+            let len = buf.len().min(left as usize);
+            self.inner.read(&mut buf[..len])
+        }
+    }
+
+    impl std::io::Seek for BrokenStream {
+        fn seek(&mut self, pos: std::io::SeekFrom) -> std::io::Result<u64> {
+            self.inner.seek(pos)
+        }
+    }
+
+    impl MediaSource for BrokenStream {
+        fn is_seekable(&self) -> bool {
+            false
+        }
+
+        fn byte_len(&self) -> Option<u64> {
+            None
+        }
+    }
+
+    #[test]
+    fn a_stream_error_ends_the_track_as_failed() {
+        let mut player = AudioPlayer::new(OutputBackend::Null).unwrap();
+        let file = flac(44_100, 2, 4 * 44_100);
+        let fail_at = file.len() as u64 / 2;
+        let source = BrokenStream {
+            inner: Cursor::new(file),
+            fail_at,
+        };
+        player
+            .play_source(Box::new(source), None, Some("FLAC"), None)
+            .unwrap();
+
+        play_out(&player, Duration::from_secs(5));
+        assert!(player.decode_failed());
+        assert!(!player.is_playing());
+        // It stopped partway instead of playing on or reporting the end
+        assert!(player.position_seconds() < 2.5);
+
+        // The next track starts clean
+        player
+            .play_source(
+                Box::new(Cursor::new(flac(44_100, 2, 22_050))),
+                None,
+                Some("FLAC"),
+                None,
+            )
+            .unwrap();
+        assert!(!player.decode_failed());
+        play_out(&player, Duration::from_secs(5));
+        assert!(!player.decode_failed());
+        player.stop();
+    }
+
+    #[test]
+    fn a_missing_duration_falls_back_to_the_container() {
+        for duration in [None, Some(0.0)] {
+            let mut player = AudioPlayer::new(OutputBackend::Null).unwrap();
+            let file = flac(44_100, 2, 3 * 44_100);
+            player
+                .play_source(Box::new(Cursor::new(file)), None, None, duration)
+                .unwrap();
+            assert_eq!(player.duration_seconds(), 3.0, "{:?}", duration);
+            player.stop();
+        }
+
+        // Metadata that has a duration wins
+        let mut player = AudioPlayer::new(OutputBackend::Null).unwrap();
+        let file = flac(44_100, 2, 3 * 44_100);
+        player
+            .play_source(Box::new(Cursor::new(file)), None, None, Some(200.0))
+            .unwrap();
+        assert_eq!(player.duration_seconds(), 200.0);
+        player.stop();
+    }
+
+    #[test]
+    fn an_aac_fixture_plays_to_the_end() {
+        let mut player = AudioPlayer::new(OutputBackend::Null).unwrap();
+        // 20 frames of 1024 samples
+        let file = adts_silence(48_000, 2, 20);
+        player
+            .play_source(Box::new(Cursor::new(file)), None, Some("AACLC"), None)
+            .unwrap();
+
+        play_out(&player, Duration::from_secs(5));
+        assert!(!player.decode_failed());
+        assert!((player.position_seconds() - 20.0 * 1024.0 / 48_000.0).abs() < 0.05);
+        player.stop();
+    }
+
+    #[test]
+    fn a_file_on_disk_plays_like_a_stream() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tone.flac");
+        std::fs::write(&path, flac(48_000, 1, 9_600)).unwrap();
+
+        let mut player = AudioPlayer::new(OutputBackend::Null).unwrap();
+        let file = std::fs::File::open(&path).unwrap();
+        player
+            .play_source(Box::new(file), None, Some("flac"), None)
+            .unwrap();
+        let format = player.source_format();
+        assert_eq!(format.sample_rate, 48_000);
+        assert_eq!(format.bit_depth, Some(16));
+        assert_eq!(format.detected.container, Some("flac"));
+
+        play_out(&player, Duration::from_secs(5));
+        assert!((player.position_seconds() - 0.2).abs() < 0.05);
+        player.stop();
+    }
+
+    #[test]
+    fn the_badge_follows_the_codec_found_over_the_one_announced() {
+        let mut player = AudioPlayer::new(OutputBackend::Null).unwrap();
+        // Announced as lossless, delivered as AAC
+        let file = adts_silence(44_100, 2, 20);
+        player
+            .play_source(Box::new(Cursor::new(file)), None, Some("FLAC"), None)
+            .unwrap();
+
+        let mut stream = crate::events::StreamQuality {
+            codec: Some("FLAC".into()),
+            bit_depth: Some(16),
+            sample_rate: Some(44_100),
+            ..Default::default()
+        };
+        assert!(stream.reconcile(&player.source_format()));
+        assert_eq!(stream.codec.as_deref(), Some("FLAC"));
+        assert_eq!(stream.detected_codec.as_deref(), Some("aac"));
+        assert_eq!(stream.container.as_deref(), Some("adts"));
+        assert_eq!(stream.quality.as_deref(), Some("AAC"));
+        player.stop();
+    }
+
+    #[test]
+    fn consecutive_tracks_each_open_the_output_at_their_own_format() {
+        let mut player = AudioPlayer::new(OutputBackend::Null).unwrap();
+        let half_second = |rate: u32| rate / 2;
+        let files: [(&str, Vec<u8>, u32, usize); 5] = [
+            ("wav", wav(44_100, 2, half_second(44_100)), 44_100, 2),
+            ("flac", flac(48_000, 1, half_second(48_000)), 48_000, 1),
+            ("wav", wav(96_000, 2, half_second(96_000)), 96_000, 2),
+            ("adts", adts_silence(48_000, 2, 24), 48_000, 2),
+            ("flac", flac(44_100, 2, half_second(44_100)), 44_100, 2),
+        ];
+        for (kind, file, rate, channels) in files {
+            let track = OpenedTrack::source(Box::new(Cursor::new(file)), None, None, None).unwrap();
+            player.play(track).unwrap();
+            let format = player.source_format();
+            assert_eq!(
+                (format.sample_rate, format.channels),
+                (rate, channels),
+                "{}",
+                kind
+            );
+            assert_eq!(format.corrected_from, None);
+            assert_eq!(player.output_info().unwrap().stream_sample_rate, rate);
+
+            // An output left at the previous track's format would play this
+            // one at the wrong speed
+            let started = Instant::now();
+            play_out(&player, Duration::from_secs(5));
+            let took = started.elapsed().as_secs_f64();
+            let length = player.duration_seconds();
+            assert!(
+                took > length * 0.8 && took < length * 1.6,
+                "{} at {} Hz / {} ch: {:.2}s of audio took {:.2}s",
+                kind,
+                rate,
+                channels,
+                length,
+                took
+            );
+        }
+        player.stop();
+    }
+
+    #[test]
+    fn racing_play_and_stop_never_opens_two_streams() {
+        let player = Arc::new(Mutex::new(AudioPlayer::new(OutputBackend::Null).unwrap()));
+        let file = wav(44_100, 2, 4_410);
+
+        let workers: Vec<_> = (0..2)
+            .map(|worker| {
+                let player = Arc::clone(&player);
+                let file = file.clone();
+                std::thread::spawn(move || {
+                    for round in 0..40 {
+                        let mut player = player.lock().unwrap();
+                        match (round + worker) % 4 {
+                            0 | 1 => player
+                                .play_source(Box::new(Cursor::new(file.clone())), None, None, None)
+                                .unwrap(),
+                            2 => player.switch_to_default_output().unwrap(),
+                            _ => player.stop(),
+                        }
+                        assert!(player.stream.open <= 1);
+                        assert_eq!(player.stream.open == 1, player.stream.is_open());
+                        drop(player);
+                        std::thread::yield_now();
+                    }
+                })
+            })
+            .collect();
+        for worker in workers {
+            worker.join().unwrap();
+        }
+
+        let mut player = player.lock().unwrap();
+        player.stop();
+        assert_eq!(player.stream.open, 0);
+    }
+
+    #[tokio::test]
+    async fn aborting_the_stream_stops_the_download_task() {
+        let (url, _) = endless_http_body(4096, Duration::from_millis(2)).await;
+        let (source, writer, abort) = HttpStreamSource::new();
+        let task = AudioPlayer::start_download(writer, url, client());
+
+        wait_until(Duration::from_secs(5), || source.progress().downloaded > 0).await;
+        abort.abort();
+
+        tokio::time::timeout(Duration::from_millis(500), task)
+            .await
+            .expect("download task still running after abort")
+            .ok();
+        let downloaded = source.progress().downloaded;
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(source.progress().downloaded, downloaded);
+    }
+}
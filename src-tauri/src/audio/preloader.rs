@@ -1,23 +1,46 @@
-use crate::audio::stream_source::{HttpStreamSource, StreamAbortHandle};
+use crate::api::client::TidalClient;
+use crate::audio::stream_source::{AbortOnDrop, HttpStreamSource};
+use crate::events::StreamQuality;
+use serde::Serialize;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 /// Holds a preloaded track's stream source, ready for immediate playback.
+/// Dropping it stops the download; playing it disarms `abort_handle`.
 pub struct PreloadedTrack {
     pub source: HttpStreamSource,
-    pub abort_handle: StreamAbortHandle,
+    pub abort_handle: AbortOnDrop,
     pub codec_hint: Option<String>,
+    /// What the manifest says the stream is, until the decoder probes it
+    pub stream: StreamQuality,
+    /// The manifest only covers a preview clip
+    pub preview: bool,
     pub track_id: String,
     pub duration: f64,
-    /// Keep the download handle alive
-    _download_handle: tokio::task::JoinHandle<()>,
+    /// Where playback starts, e.g. a restored position; None for the beginning
+    pub start_at: Option<f64>,
+    /// Download task, until someone takes it to wait for completion
+    download: Option<tokio::task::JoinHandle<()>>,
+}
+
+/// How far the preload of the next track has got, for a "next track ready" hint.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PreloadStatus {
+    pub track_id: String,
+    pub bytes_downloaded: u64,
+    pub total_bytes: Option<u64>,
+    pub ready: bool,
 }
 
 impl PreloadedTrack {
     pub fn new(
         track_id: String,
-        codec_hint: Option<String>,
+        stream: StreamQuality,
+        preview: bool,
         duration: f64,
         url: String,
-        client: reqwest::Client,
+        client: Arc<TidalClient>,
     ) -> Self {
         let (source, writer, abort_handle) = HttpStreamSource::new();
 
@@ -25,11 +48,235 @@ impl PreloadedTrack {
 
         Self {
             source,
-            abort_handle,
-            codec_hint,
+            abort_handle: AbortOnDrop::new(abort_handle),
+            codec_hint: stream.codec.clone(),
+            stream,
+            preview,
             track_id,
             duration,
-            _download_handle: handle,
+            start_at: None,
+            download: Some(handle),
+        }
+    }
+
+    pub fn status(&self) -> PreloadStatus {
+        let progress = self.source.progress();
+        PreloadStatus {
+            track_id: self.track_id.clone(),
+            bytes_downloaded: progress.downloaded,
+            total_bytes: progress.total,
+            ready: progress.complete,
+        }
+    }
+
+    /// Take the download task so the caller can await it. Resolves with an
+    /// error if the preload is aborted before the download finishes.
+    pub fn take_download(&mut self) -> Option<tokio::task::JoinHandle<()>> {
+        self.download.take()
+    }
+}
+
+/// Start preloading the next track once this many seconds remain.
+pub const PRELOAD_THRESHOLD_SECS: f64 = 30.0;
+
+/// Wait before retrying a preload whose manifest fetch failed, doubled for
+/// each further failure of the same track up to `PRELOAD_RETRY_MAX`.
+const PRELOAD_RETRY_BASE: Duration = Duration::from_secs(2);
+const PRELOAD_RETRY_MAX: Duration = Duration::from_secs(16);
+
+/// Remembers which upcoming track a preload was started for during the
+/// current track, so the progress loop triggers at most once per next track.
+#[derive(Debug, Default)]
+pub struct PreloadTrigger {
+    triggered_for: Option<String>,
+    /// The last track whose preload failed, held back until `retry_at`
+    failed: Option<FailedPreload>,
+}
+
+#[derive(Debug)]
+struct FailedPreload {
+    track_id: String,
+    failures: u32,
+    retry_at: Instant,
+}
+
+impl PreloadTrigger {
+    /// Whether to start a preload now. Compares against the queue's current
+    /// `peek_next()`, so a reordered queue triggers again for the new next track.
+    pub fn should_preload(&mut self, remaining: f64, next_id: Option<&str>, now: Instant) -> bool {
+        if remaining >= PRELOAD_THRESHOLD_SECS {
+            return false;
+        }
+        let Some(id) = next_id else {
+            return false;
+        };
+        if self.triggered_for.as_deref() == Some(id) {
+            return false;
+        }
+        if self
+            .failed
+            .as_ref()
+            .is_some_and(|f| f.track_id == id && now < f.retry_at)
+        {
+            return false;
         }
+        self.triggered_for = Some(id.to_string());
+        true
+    }
+
+    /// Record that preloading `track_id` failed. It triggers again once a
+    /// backoff has passed rather than on the next progress tick.
+    pub fn preload_failed(&mut self, track_id: &str, now: Instant) {
+        let failures = match &self.failed {
+            Some(f) if f.track_id == track_id => f.failures + 1,
+            _ => 1,
+        };
+        let backoff = PRELOAD_RETRY_BASE
+            .saturating_mul(1 << (failures - 1).min(16))
+            .min(PRELOAD_RETRY_MAX);
+        self.failed = Some(FailedPreload {
+            track_id: track_id.to_string(),
+            failures,
+            retry_at: now + backoff,
+        });
+        if self.triggered_for.as_deref() == Some(track_id) {
+            self.triggered_for = None;
+        }
+    }
+
+    /// Re-arm the trigger, e.g. after a seek or when a new track starts. A
+    /// failed track still waits out its backoff.
+    pub fn reset(&mut self) {
+        self.triggered_for = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AppConfig;
+    use crate::test_support::{endless_http_body, wait_until};
+    use std::sync::atomic::Ordering;
+    use tokio::sync::RwLock;
+
+    const NEAR_END: f64 = PRELOAD_THRESHOLD_SECS - 1.0;
+
+    #[test]
+    fn waits_for_the_threshold() {
+        let mut trigger = PreloadTrigger::default();
+        let now = Instant::now();
+        assert!(!trigger.should_preload(PRELOAD_THRESHOLD_SECS, Some("b"), now));
+        assert!(!trigger.should_preload(120.0, Some("b"), now));
+        assert!(trigger.should_preload(NEAR_END, Some("b"), now));
+    }
+
+    #[test]
+    fn triggers_once_per_next_track() {
+        let mut trigger = PreloadTrigger::default();
+        let now = Instant::now();
+        assert!(trigger.should_preload(NEAR_END, Some("b"), now));
+        assert!(!trigger.should_preload(NEAR_END - 1.0, Some("b"), now));
+        assert!(!trigger.should_preload(0.0, Some("b"), now));
+
+        // The queue was reordered, so another track is next now
+        assert!(trigger.should_preload(NEAR_END, Some("c"), now));
+        assert!(!trigger.should_preload(NEAR_END, Some("c"), now));
+    }
+
+    #[test]
+    fn nothing_next_never_triggers() {
+        let mut trigger = PreloadTrigger::default();
+        assert!(!trigger.should_preload(0.0, None, Instant::now()));
+        assert!(trigger.should_preload(0.0, Some("b"), Instant::now()));
+    }
+
+    #[test]
+    fn reset_rearms_the_trigger() {
+        let mut trigger = PreloadTrigger::default();
+        let now = Instant::now();
+        assert!(trigger.should_preload(NEAR_END, Some("b"), now));
+        // Seek back out of the window and forward again
+        trigger.reset();
+        assert!(!trigger.should_preload(100.0, Some("b"), now));
+        assert!(trigger.should_preload(NEAR_END, Some("b"), now));
+    }
+
+    #[test]
+    fn a_failed_preload_backs_off() {
+        let mut trigger = PreloadTrigger::default();
+        let start = Instant::now();
+        let at = |ms: u64| start + Duration::from_millis(ms);
+
+        assert!(trigger.should_preload(NEAR_END, Some("b"), at(0)));
+        trigger.preload_failed("b", at(100));
+        // Progress ticks every 250 ms must not refetch straight away
+        assert!(!trigger.should_preload(NEAR_END, Some("b"), at(350)));
+        assert!(!trigger.should_preload(NEAR_END, Some("b"), at(2_099)));
+        assert!(trigger.should_preload(NEAR_END, Some("b"), at(2_100)));
+
+        // The second failure waits twice as long
+        trigger.preload_failed("b", at(2_200));
+        assert!(!trigger.should_preload(NEAR_END, Some("b"), at(6_199)));
+        assert!(trigger.should_preload(NEAR_END, Some("b"), at(6_200)));
+
+        // A reset doesn't skip the backoff
+        trigger.preload_failed("b", at(6_300));
+        trigger.reset();
+        assert!(!trigger.should_preload(NEAR_END, Some("b"), at(6_400)));
+    }
+
+    #[test]
+    fn the_backoff_is_capped() {
+        let mut trigger = PreloadTrigger::default();
+        let start = Instant::now();
+        for _ in 0..10 {
+            trigger.preload_failed("b", start);
+        }
+        assert!(!trigger.should_preload(NEAR_END, Some("b"), start + PRELOAD_RETRY_MAX / 2));
+        assert!(trigger.should_preload(NEAR_END, Some("b"), start + PRELOAD_RETRY_MAX));
+    }
+
+    #[test]
+    fn a_failure_only_holds_back_that_track() {
+        let mut trigger = PreloadTrigger::default();
+        let now = Instant::now();
+        assert!(trigger.should_preload(NEAR_END, Some("b"), now));
+        trigger.preload_failed("b", now);
+        assert!(trigger.should_preload(NEAR_END, Some("c"), now));
+
+        // A failure of another track starts its own count
+        trigger.preload_failed("c", now);
+        assert!(trigger.should_preload(NEAR_END, Some("b"), now + PRELOAD_RETRY_BASE));
+    }
+
+    #[tokio::test]
+    async fn dropping_a_preload_stops_its_download() {
+        let (url, sent) = endless_http_body(4096, Duration::from_millis(2)).await;
+        let client =
+            Arc::new(TidalClient::new(Arc::new(RwLock::new(AppConfig::default()))).unwrap());
+        let mut preload = PreloadedTrack::new(
+            "b".into(),
+            StreamQuality::default(),
+            false,
+            200.0,
+            url,
+            client,
+        );
+        let download = preload.take_download().unwrap();
+        wait_until(Duration::from_secs(5), || {
+            preload.source.progress().downloaded > 0
+        })
+        .await;
+
+        drop(preload);
+        tokio::time::timeout(Duration::from_millis(500), download)
+            .await
+            .expect("download task still running after the preload was dropped")
+            .ok();
+        // The server sees the connection go once what was in flight is written
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let stopped_at = sent.load(Ordering::SeqCst);
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(sent.load(Ordering::SeqCst), stopped_at);
     }
 }
@@ -1,6 +1,14 @@
+use crate::api::client::TidalClient;
 use crate::api::models::Track;
+use crate::config::AppConfig;
+use crate::error::{AppError, AppResult};
 use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use tokio::sync::RwLock;
 
 #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
@@ -10,13 +18,124 @@ pub enum RepeatMode {
     One,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum QueueSourceKind {
+    Album,
+    Playlist,
+    Artist,
+    Favorites,
+    Mix,
+    Search,
+    Other,
+}
+
+/// Where the queue's tracks came from, for "Playing from: ..." display.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueueSource {
+    pub kind: QueueSourceKind,
+    pub id: Option<String>,
+    pub name: String,
+}
+
+/// Where more tracks for the end of the queue come from, for collections too
+/// large to queue upfront. Pages are appended as playback nears the end.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", tag = "kind")]
+pub enum LazySource {
+    /// The user's favorite tracks, continuing from the page at `cursor`
+    Favorites { cursor: String },
+}
+
+impl LazySource {
+    /// Fetch this source's next page: its tracks, and the source for the page
+    /// after it if there is one.
+    pub async fn next_page(&self, client: &TidalClient) -> AppResult<(Vec<Track>, Option<Self>)> {
+        match self {
+            LazySource::Favorites { cursor } => {
+                let page = client.get_favorites(Some(cursor)).await?;
+                let next = page
+                    .next_cursor
+                    .filter(|_| page.has_more)
+                    .map(|cursor| LazySource::Favorites { cursor });
+                Ok((page.tracks, next))
+            }
+        }
+    }
+}
+
+/// A lazy-source page fetch in progress, from `PlaybackQueue::begin_lazy_fetch`.
+#[derive(Debug, Clone)]
+pub struct LazyFetch {
+    pub source: LazySource,
+    /// Queue generation the fetch was started for
+    generation: u64,
+}
+
+/// A track started with `play_track_now` that interrupts the queue.
+#[derive(Debug, Clone)]
+pub struct InterruptTrack {
+    pub track_id: String,
+    /// Go back to the interrupted queue entry when this track ends, instead of stopping
+    pub resume_queue_after: bool,
+}
+
 #[derive(Debug, Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct QueueState {
     pub tracks: Vec<Track>,
     pub current_index: Option<usize>,
+    /// Queue index of the track that is actually playing; None when the playing
+    /// track didn't come from the queue
+    pub now_playing_queue_index: Option<usize>,
     pub repeat_mode: RepeatMode,
     pub shuffled: bool,
+    pub source: Option<QueueSource>,
+    /// Index of `tracks[0]` within the full queue (non-zero for windowed requests)
+    pub offset: usize,
+    /// Length of the full queue, regardless of windowing
+    pub total_tracks: usize,
+    /// Sum of all track durations in seconds
+    pub total_duration: f64,
+    /// Sum of durations from the current track to the end, in seconds
+    pub remaining_duration: f64,
+    /// Playback position to resume from, only set when restored from disk.
+    pub resume_position: Option<f64>,
+}
+
+impl QueueState {
+    pub fn new(
+        tracks: Vec<Track>,
+        current_index: Option<usize>,
+        repeat_mode: RepeatMode,
+        shuffled: bool,
+        source: Option<QueueSource>,
+    ) -> Self {
+        let total_duration = tracks.iter().map(|t| t.duration).sum();
+        let remaining_duration = tracks
+            .iter()
+            .skip(current_index.unwrap_or(0))
+            .map(|t| t.duration)
+            .sum();
+        Self {
+            total_tracks: tracks.len(),
+            tracks,
+            current_index,
+            now_playing_queue_index: None,
+            repeat_mode,
+            shuffled,
+            source,
+            offset: 0,
+            total_duration,
+            remaining_duration,
+            resume_position: None,
+        }
+    }
+
+    pub fn empty() -> Self {
+        Self::new(Vec::new(), None, RepeatMode::Off, false, None)
+    }
 }
 
 /// Full queue state including original order, for disk persistence.
@@ -28,14 +147,133 @@ pub struct PersistedQueueState {
     pub current_index: Option<usize>,
     pub repeat_mode: RepeatMode,
     pub shuffled: bool,
+    #[serde(default)]
+    pub source: Option<QueueSource>,
+    #[serde(default)]
+    pub lazy_source: Option<LazySource>,
+    /// Playback position of the current track in seconds.
+    #[serde(default)]
+    pub position: f64,
+    /// Seed of the last shuffle, so the order can be reproduced
+    #[serde(default)]
+    pub shuffle_seed: Option<u64>,
+}
+
+/// Serializes saves so overlapping ones can't interleave their writes.
+static SAVE_LOCK: Mutex<()> = Mutex::new(());
+
+impl PersistedQueueState {
+    /// Load the saved queue, returning None if nothing has been saved yet.
+    /// A corrupt file falls back to the backup from the previous save; if that
+    /// is unreadable too the queue starts empty.
+    pub fn load() -> AppResult<Option<Self>> {
+        let path = AppConfig::queue_path()?;
+        let backup = path.with_extension("json.bak");
+        for candidate in [&path, &backup] {
+            if !candidate.exists() {
+                continue;
+            }
+            let parsed = std::fs::read_to_string(candidate)
+                .map_err(AppError::from)
+                .and_then(|content| Ok(serde_json::from_str(&content)?));
+            match parsed {
+                Ok(state) => return Ok(Some(state)),
+                Err(e) => log::warn!("Ignoring unreadable {}: {}", candidate.display(), e),
+            }
+        }
+        Ok(None)
+    }
+
+    /// Delete the saved queue, its backup and any half-written save.
+    pub fn delete() -> AppResult<()> {
+        let _guard = SAVE_LOCK.lock().unwrap();
+        let path = AppConfig::queue_path()?;
+        for file in [
+            path.with_extension("json.tmp"),
+            path.with_extension("json.bak"),
+            path,
+        ] {
+            if file.exists() {
+                std::fs::remove_file(&file)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Write to a temp file and rename it into place, so a crash mid-write
+    /// leaves the previous queue intact. The previous file is kept as a backup.
+    pub fn save(&self) -> AppResult<()> {
+        let content = serde_json::to_string_pretty(self)?;
+        let _guard = SAVE_LOCK.lock().unwrap();
+        std::fs::create_dir_all(AppConfig::config_dir()?)?;
+
+        let path = AppConfig::queue_path()?;
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, content)?;
+        if path.exists() {
+            std::fs::copy(&path, path.with_extension("json.bak"))?;
+        }
+        std::fs::rename(&tmp, &path)?;
+        Ok(())
+    }
 }
 
+/// Random shuffle seeds stay below 2^53 so they survive a round trip through
+/// a JavaScript number.
+const MAX_RANDOM_SEED: u64 = 1 << 53;
+
+/// Most tracks `peek_next_n` returns. The count comes from the frontend, and
+/// with repeat on the list would otherwise be as long as it asks.
+const MAX_PEEK: usize = 100;
+
+/// Tracks are held behind `Arc` so the play order and the original order share
+/// one allocation per track, and shuffling or unshuffling only moves pointers.
+/// This keeps whole-library queues (thousands of tracks) cheap to reorder.
+///
+/// Shuffle is a mode, not a one-off reordering. `shuffle_mode` is the user's
+/// preference; `shuffled` only says whether `tracks`, the play order, is
+/// currently a reordering of `original_order`, the order to return to. While
+/// the mode is on:
+/// - `set_tracks` shuffles the new tracks, keeping the start track first.
+/// - `add_track` and `append_tracks` put each track at a random spot after
+///   the current one, and at the end of the original order.
+/// - `insert_tracks_next` still plays the tracks next, in the given order.
+/// - `clear` empties the queue but leaves the mode on.
+///
+/// `unshuffle` switches back to the original order, so tracks added while
+/// shuffled come after everything that was queued before them.
 pub struct PlaybackQueue {
-    tracks: Vec<Track>,
-    original_order: Vec<Track>,
+    tracks: Vec<Arc<Track>>,
+    original_order: Vec<Arc<Track>>,
     current_index: Option<usize>,
+    /// Whether the playing track is the entry at `current_index`, as opposed
+    /// to a track played from outside the queue
+    playing_from_queue: bool,
     repeat_mode: RepeatMode,
+    /// Shuffle turned on by the user; kept across `set_tracks` and `clear`
+    shuffle_mode: bool,
+    /// Whether `tracks` is shuffled, so `unshuffle` has an order to restore
     shuffled: bool,
+    /// Seed the current shuffled order was drawn from, while it still is
+    shuffle_seed: Option<u64>,
+    source: Option<QueueSource>,
+    /// Where to fetch more tracks from once the queue runs low. Stays in
+    /// place while a page is fetched, so it is still saved with the queue.
+    lazy_source: Option<LazySource>,
+    /// Generation of the queue a lazy page is being fetched for, so only one
+    /// fetch runs at a time
+    lazy_fetching: Option<u64>,
+    /// Woken when a lazy page fetch ends, for refills waiting on it
+    lazy_fetched: Arc<tokio::sync::Notify>,
+    /// Bumped whenever the queue contents are replaced, so a page fetched for
+    /// an older queue is dropped instead of appended
+    generation: u64,
+}
+
+impl Default for PlaybackQueue {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl PlaybackQueue {
@@ -44,40 +282,181 @@ impl PlaybackQueue {
             tracks: Vec::new(),
             original_order: Vec::new(),
             current_index: None,
+            playing_from_queue: false,
             repeat_mode: RepeatMode::Off,
+            shuffle_mode: false,
             shuffled: false,
+            shuffle_seed: None,
+            source: None,
+            lazy_source: None,
+            lazy_fetching: None,
+            lazy_fetched: Arc::new(tokio::sync::Notify::new()),
+            generation: 0,
         }
     }
 
+    /// Record where the current tracks came from. Call after `set_tracks`.
+    pub fn set_source(&mut self, source: Option<QueueSource>) {
+        self.source = source;
+    }
+
+    pub fn source(&self) -> Option<&QueueSource> {
+        self.source.as_ref()
+    }
+
+    /// Replace the queue, starting at `start_index`. With shuffle on, the new
+    /// tracks are shuffled behind the start track.
     pub fn set_tracks(&mut self, tracks: Vec<Track>, start_index: usize) {
+        let tracks: Vec<Arc<Track>> = tracks.into_iter().map(Arc::new).collect();
         self.original_order = tracks.clone();
         self.tracks = tracks;
         self.shuffled = false;
+        self.shuffle_seed = None;
+        self.source = None;
+        self.lazy_source = None;
+        self.generation += 1;
         self.current_index = if self.tracks.is_empty() {
             None
         } else {
             Some(start_index.min(self.tracks.len() - 1))
         };
+        self.playing_from_queue = self.current_index.is_some();
+        if self.shuffle_mode {
+            self.shuffle(None);
+        }
+    }
+
+    /// Set where more tracks come from once the queue runs low. Call after `set_tracks`.
+    pub fn set_lazy_source(&mut self, source: Option<LazySource>) {
+        self.lazy_source = source;
+    }
+
+    /// Whether a lazy source should be asked for more tracks: one is set and
+    /// at most `threshold` tracks are left after the current one.
+    pub fn needs_refill(&self, threshold: usize) -> bool {
+        self.lazy_source.is_some() && self.upcoming_len() <= threshold
+    }
+
+    /// Tracks after the current one, ignoring repeat.
+    pub fn upcoming_len(&self) -> usize {
+        let start = self.current_index.map_or(0, |i| i + 1);
+        self.tracks.len().saturating_sub(start)
+    }
+
+    /// Whether a page is being fetched from the lazy source.
+    pub fn is_lazy_fetching(&self) -> bool {
+        self.lazy_fetching == Some(self.generation)
+    }
+
+    /// Start a page fetch from the lazy source. Returns None when there is no
+    /// source or a fetch is already running.
+    pub fn begin_lazy_fetch(&mut self) -> Option<LazyFetch> {
+        if self.is_lazy_fetching() {
+            return None;
+        }
+        let source = self.lazy_source.clone()?;
+        self.lazy_fetching = Some(self.generation);
+        Some(LazyFetch {
+            source,
+            generation: self.generation,
+        })
+    }
+
+    /// Mark `fetch` as over and wake the refills waiting on it.
+    fn end_lazy_fetch(&mut self, fetch: &LazyFetch) {
+        if self.lazy_fetching == Some(fetch.generation) {
+            self.lazy_fetching = None;
+        }
+        self.lazy_fetched.notify_waiters();
+    }
+
+    /// Append a fetched page and set the source for the page after it. Returns
+    /// false and changes nothing if the queue was replaced during the fetch.
+    pub fn finish_lazy_fetch(
+        &mut self,
+        fetch: LazyFetch,
+        tracks: Vec<Track>,
+        next: Option<LazySource>,
+    ) -> bool {
+        self.end_lazy_fetch(&fetch);
+        if fetch.generation != self.generation {
+            return false;
+        }
+        self.append_tracks(tracks);
+        self.lazy_source = next;
+        true
+    }
+
+    /// End a failed fetch. The source is left as it was, so a later refill
+    /// retries it.
+    pub fn abort_lazy_fetch(&mut self, fetch: LazyFetch) {
+        self.end_lazy_fetch(&fetch);
     }
 
     pub fn add_track(&mut self, track: Track) {
-        self.tracks.push(track.clone());
-        self.original_order.push(track);
-        if self.current_index.is_none() {
+        self.append_tracks(vec![track]);
+    }
+
+    /// Append tracks to the end of the queue without touching playback. In
+    /// shuffle mode they land at random spots among the upcoming tracks
+    /// instead, and the order no longer follows the shuffle seed.
+    pub fn append_tracks(&mut self, tracks: Vec<Track>) {
+        let tracks: Vec<Arc<Track>> = tracks.into_iter().map(Arc::new).collect();
+        self.original_order.extend(tracks.iter().cloned());
+        if self.shuffle_mode {
+            let mut rng = rand::thread_rng();
+            let start = self.current_index.map_or(0, |i| i + 1);
+            for track in tracks {
+                let at = rng.gen_range(start..=self.tracks.len());
+                self.tracks.insert(at, track);
+            }
+            self.shuffled = true;
+            self.shuffle_seed = None;
+        } else {
+            self.tracks.extend(tracks);
+        }
+        if self.current_index.is_none() && !self.tracks.is_empty() {
             self.current_index = Some(0);
         }
     }
 
+    /// Insert tracks right after the current one, in order. In the original
+    /// order they follow the current track too, so unshuffling keeps them next.
+    pub fn insert_tracks_next(&mut self, tracks: Vec<Track>) {
+        let Some(current) = self.current_index else {
+            self.append_tracks(tracks);
+            return;
+        };
+        let tracks: Vec<Arc<Track>> = tracks.into_iter().map(Arc::new).collect();
+
+        let original_at = self
+            .original_order
+            .iter()
+            .position(|t| t.id == self.tracks[current].id)
+            .map(|i| i + 1)
+            .unwrap_or(self.original_order.len());
+        self.original_order
+            .splice(original_at..original_at, tracks.iter().cloned());
+        self.tracks.splice(current + 1..current + 1, tracks);
+    }
+
     pub fn remove_track(&mut self, index: usize) {
         if index >= self.tracks.len() {
             return;
         }
 
-        let removed_id = self.tracks[index].id.clone();
-        self.tracks.remove(index);
-        self.original_order.retain(|t| t.id != removed_id);
+        let removed = self.tracks.remove(index);
+        // Only drop one copy so other occurrences of a duplicated track survive
+        // unshuffle; prefer the very entry that was removed
+        if let Some(pos) = self.original_position(&removed) {
+            self.original_order.remove(pos);
+        }
 
         if let Some(current) = self.current_index {
+            if index == current {
+                // The playing entry is gone; whatever plays now isn't from the queue
+                self.playing_from_queue = false;
+            }
             if index < current {
                 self.current_index = Some(current - 1);
             } else if index == current && current >= self.tracks.len() {
@@ -90,6 +469,60 @@ impl PlaybackQueue {
         }
     }
 
+    /// Remove the `occurrence`-th (0-based) copy of a track by id.
+    /// Returns false if no such occurrence exists.
+    pub fn remove_track_by_id(&mut self, track_id: &str, occurrence: usize) -> bool {
+        let index = self
+            .tracks
+            .iter()
+            .enumerate()
+            .filter(|(_, t)| t.id == track_id)
+            .nth(occurrence)
+            .map(|(i, _)| i);
+
+        match index {
+            Some(index) => {
+                self.remove_track(index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Remove later duplicates of each track, always keeping the current track's
+    /// own entry. Returns the number of tracks removed.
+    pub fn dedupe(&mut self) -> usize {
+        let current = self.current_index;
+        let current_id = self.current_track().map(|t| t.id.clone());
+        let mut seen = std::collections::HashSet::new();
+        if let Some(ref id) = current_id {
+            seen.insert(id.clone());
+        }
+
+        let before = self.tracks.len();
+        let mut new_current = None;
+        let mut kept = Vec::with_capacity(before);
+        for (i, track) in std::mem::take(&mut self.tracks).into_iter().enumerate() {
+            let keep = if Some(i) == current {
+                new_current = Some(kept.len());
+                true
+            } else {
+                seen.insert(track.id.clone())
+            };
+            if keep {
+                kept.push(track);
+            }
+        }
+        self.tracks = kept;
+        self.current_index = new_current;
+
+        let mut seen_original = std::collections::HashSet::new();
+        self.original_order
+            .retain(|t| seen_original.insert(t.id.clone()));
+
+        before - self.tracks.len()
+    }
+
     pub fn move_track(&mut self, from: usize, to: usize) {
         if from >= self.tracks.len() || to >= self.tracks.len() {
             return;
@@ -109,31 +542,110 @@ impl PlaybackQueue {
         }
     }
 
+    /// Move the contiguous block `[from_start, from_start + from_len)` so that its
+    /// first track ends up at index `to` of the resulting queue.
+    /// Out-of-range moves are ignored, like `move_track`.
+    pub fn move_range(&mut self, from_start: usize, from_len: usize, to: usize) {
+        let len = self.tracks.len();
+        let from_end = from_start.saturating_add(from_len);
+        if from_len == 0 || from_end > len || to.saturating_add(from_len) > len {
+            return;
+        }
+
+        let block: Vec<Arc<Track>> = self.tracks.drain(from_start..from_end).collect();
+        self.tracks.splice(to..to, block);
+
+        if let Some(current) = self.current_index {
+            self.current_index = Some(if (from_start..from_end).contains(&current) {
+                // Current track moved with the block
+                to + (current - from_start)
+            } else {
+                // Position once the block is removed, then shifted by its reinsertion
+                let without_block = if current >= from_end {
+                    current - from_len
+                } else {
+                    current
+                };
+                if without_block >= to {
+                    without_block + from_len
+                } else {
+                    without_block
+                }
+            });
+        }
+    }
+
     pub fn current_track(&self) -> Option<&Track> {
-        self.current_index.and_then(|i| self.tracks.get(i))
+        self.current_index.and_then(|i| self.get(i))
+    }
+
+    /// Queue index of the playing track, or None if it isn't from the queue.
+    pub fn now_playing_index(&self) -> Option<usize> {
+        self.current_index.filter(|_| self.playing_from_queue)
+    }
+
+    /// Make `index` the current entry because it's about to be played.
+    pub fn jump_to(&mut self, index: usize) -> Option<&Track> {
+        if index >= self.tracks.len() {
+            return None;
+        }
+        self.current_index = Some(index);
+        self.playing_from_queue = true;
+        self.get(index)
+    }
+
+    /// Return to the current entry after an interrupt track, without advancing.
+    pub fn resume_current(&mut self) -> Option<&Track> {
+        self.playing_from_queue = self.current_index.is_some();
+        self.current_track()
+    }
+
+    /// Record that a track from outside the queue started playing.
+    pub fn mark_off_queue(&mut self) {
+        self.playing_from_queue = false;
+    }
+
+    /// Record that `track_id` started playing. It counts as queue playback only
+    /// when it is the current entry; any other track is off-queue.
+    pub fn sync_now_playing(&mut self, track_id: &str) {
+        self.playing_from_queue = self.current_track().is_some_and(|t| t.id == track_id);
+    }
+
+    /// Track at a play-order index.
+    pub fn get(&self, index: usize) -> Option<&Track> {
+        self.tracks.get(index).map(Arc::as_ref)
+    }
+
+    pub fn len(&self) -> usize {
+        self.tracks.len()
+    }
+
+    /// Track ids in play order, without cloning whole tracks.
+    pub fn track_ids(&self) -> Vec<String> {
+        self.tracks.iter().map(|t| t.id.clone()).collect()
     }
 
     pub fn next_track(&mut self) -> Option<&Track> {
         let len = self.tracks.len();
         if len == 0 {
+            self.playing_from_queue = false;
             return None;
         }
 
-        match self.repeat_mode {
-            RepeatMode::One => self.current_track(),
-            RepeatMode::All => {
-                let next = self.current_index.map(|i| (i + 1) % len).unwrap_or(0);
-                self.current_index = Some(next);
-                self.tracks.get(next)
-            }
+        let next = match self.repeat_mode {
+            RepeatMode::One => self.current_index,
+            RepeatMode::All => Some(self.current_index.map(|i| (i + 1) % len).unwrap_or(0)),
             RepeatMode::Off => {
                 let current = self.current_index.unwrap_or(0);
-                if current + 1 < len {
-                    self.current_index = Some(current + 1);
-                    self.tracks.get(current + 1)
-                } else {
-                    None
-                }
+                (current + 1 < len).then_some(current + 1)
+            }
+        };
+        match next {
+            Some(next) => self.jump_to(next),
+            None => {
+                // End of the queue, playback stops
+                self.playing_from_queue = false;
+                None
             }
         }
     }
@@ -150,9 +662,59 @@ impl PlaybackQueue {
         } else if self.repeat_mode == RepeatMode::All {
             self.current_index = Some(len - 1);
         }
+        self.playing_from_queue = self.current_index.is_some();
         self.current_track()
     }
 
+    /// Advance like `next_track`, passing over tracks for which `skip` returns
+    /// true. Gives up after one pass over the queue.
+    pub fn next_track_skipping(&mut self, skip: impl Fn(&Track) -> bool) -> Option<&Track> {
+        for _ in 0..self.tracks.len() {
+            let skipped = match self.next_track() {
+                Some(track) => skip(track),
+                None => return None,
+            };
+            if !skipped {
+                return self.current_track();
+            }
+        }
+        self.playing_from_queue = false;
+        None
+    }
+
+    /// Land where `count` calls to `next_track` would, in one step. The tracks
+    /// passed over stay in place. Going past the end with RepeatMode::Off
+    /// stops playback like `next_track` does.
+    pub fn advance_by(&mut self, count: usize) -> Option<&Track> {
+        let len = self.tracks.len();
+        if count == 0 {
+            return self.resume_current();
+        }
+        if len == 0 {
+            self.playing_from_queue = false;
+            return None;
+        }
+
+        let target = match self.repeat_mode {
+            RepeatMode::One => self.current_index,
+            RepeatMode::All => Some(
+                self.current_index
+                    .map_or((count - 1) % len, |i| (i + count) % len),
+            ),
+            RepeatMode::Off => {
+                let target = self.current_index.unwrap_or(0) + count;
+                (target < len).then_some(target)
+            }
+        };
+        match target {
+            Some(target) => self.jump_to(target),
+            None => {
+                self.playing_from_queue = false;
+                None
+            }
+        }
+    }
+
     pub fn peek_next(&self) -> Option<&Track> {
         let len = self.tracks.len();
         if len == 0 {
@@ -163,51 +725,145 @@ impl PlaybackQueue {
             RepeatMode::One => self.current_track(),
             RepeatMode::All => {
                 let next = self.current_index.map(|i| (i + 1) % len).unwrap_or(0);
-                self.tracks.get(next)
+                self.get(next)
             }
             RepeatMode::Off => {
                 let current = self.current_index.unwrap_or(0);
-                self.tracks.get(current + 1)
+                self.get(current + 1)
             }
         }
     }
 
-    pub fn shuffle(&mut self) {
-        if self.tracks.len() <= 1 {
-            return;
+    /// The next `count` tracks that `next_track` would return, in order, up
+    /// to `MAX_PEEK`. RepeatMode::One repeats the current track, All wraps
+    /// around, Off stops at the end.
+    pub fn peek_next_n(&self, count: usize) -> Vec<Track> {
+        let len = self.tracks.len();
+        let count = count.min(MAX_PEEK);
+        if len == 0 || count == 0 {
+            return Vec::new();
         }
 
-        let current_track = self.current_track().cloned();
-        let mut rng = rand::thread_rng();
+        match self.repeat_mode {
+            RepeatMode::One => self
+                .current_track()
+                .map(|t| vec![t.clone(); count])
+                .unwrap_or_default(),
+            RepeatMode::All => {
+                let start = self.current_index.map(|i| i + 1).unwrap_or(0);
+                (0..count)
+                    .map(|k| Track::clone(&self.tracks[(start + k) % len]))
+                    .collect()
+            }
+            RepeatMode::Off => {
+                let start = self.current_index.unwrap_or(0) + 1;
+                self.tracks
+                    .iter()
+                    .skip(start)
+                    .take(count)
+                    .map(|t| Track::clone(t))
+                    .collect()
+            }
+        }
+    }
 
-        if !self.shuffled {
+    /// The track `previous_track` would move to, or None if it would only
+    /// restart the current track.
+    pub fn peek_previous(&self) -> Option<&Track> {
+        let len = self.tracks.len();
+        if len == 0 {
+            return None;
+        }
+
+        match self.current_index.unwrap_or(0) {
+            0 if self.repeat_mode == RepeatMode::All && len > 1 => self.get(len - 1),
+            0 => None,
+            current => self.get(current - 1),
+        }
+    }
+
+    /// Shuffle the queue, keeping the current track at the front. The order is
+    /// drawn from `seed`, or a random seed when None, and always starts from
+    /// the original order, so the same seed gives the same order for the same
+    /// queue. Turns shuffle mode on even for an empty queue. Returns the seed
+    /// used.
+    pub fn shuffle(&mut self, seed: Option<u64>) -> u64 {
+        let seed = seed.unwrap_or_else(|| rand::thread_rng().gen_range(0..MAX_RANDOM_SEED));
+        let current = self.current_index.and_then(|i| self.tracks.get(i)).cloned();
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+
+        if self.shuffled {
+            self.tracks = self.original_order.clone();
+        } else {
             self.original_order = self.tracks.clone();
         }
 
         self.tracks.shuffle(&mut rng);
+        self.shuffle_mode = true;
         self.shuffled = true;
+        self.shuffle_seed = Some(seed);
 
-        // Put current track at position 0
-        if let Some(current) = current_track {
-            if let Some(pos) = self.tracks.iter().position(|t| t.id == current.id) {
+        // Put current track at position 0. Entries are compared by pointer so
+        // the playing copy of a duplicated track stays current.
+        if let Some(current) = current {
+            if let Some(pos) = self.tracks.iter().position(|t| Arc::ptr_eq(t, &current)) {
                 self.tracks.swap(0, pos);
             }
             self.current_index = Some(0);
         }
+        seed
+    }
+
+    /// Move upcoming tracks matching `defer` behind the other upcoming tracks,
+    /// keeping the relative order of both groups.
+    pub fn defer_upcoming(&mut self, defer: impl Fn(&Track) -> bool) {
+        let start = self.current_index.map_or(0, |i| i + 1);
+        if start >= self.tracks.len() {
+            return;
+        }
+        self.tracks[start..].sort_by_key(|t| defer(t));
     }
 
+    /// Turn shuffle mode off and go back to the original order.
     pub fn unshuffle(&mut self) {
+        self.shuffle_mode = false;
+        self.shuffle_seed = None;
         if !self.shuffled {
             return;
         }
 
-        let current_track = self.current_track().cloned();
+        let current = self.current_index.and_then(|i| self.tracks.get(i)).cloned();
+        if let Some(current) = current {
+            self.current_index = self.original_position(&current);
+        }
         self.tracks = self.original_order.clone();
         self.shuffled = false;
+    }
 
-        if let Some(current) = current_track {
-            self.current_index = self.tracks.iter().position(|t| t.id == current.id);
-        }
+    /// Index of `track` in the original order: the same entry if it's there,
+    /// otherwise the first copy with the same id.
+    fn original_position(&self, track: &Arc<Track>) -> Option<usize> {
+        self.original_order
+            .iter()
+            .position(|t| Arc::ptr_eq(t, track))
+            .or_else(|| self.original_order.iter().position(|t| t.id == track.id))
+    }
+
+    pub fn repeat_mode(&self) -> RepeatMode {
+        self.repeat_mode
+    }
+
+    pub fn set_repeat_mode(&mut self, mode: RepeatMode) {
+        self.repeat_mode = mode;
+    }
+
+    /// Whether shuffle mode is on.
+    pub fn is_shuffled(&self) -> bool {
+        self.shuffle_mode
+    }
+
+    pub fn shuffle_seed(&self) -> Option<u64> {
+        self.shuffle_seed
     }
 
     pub fn toggle_repeat(&mut self) -> RepeatMode {
@@ -220,40 +876,1024 @@ impl PlaybackQueue {
     }
 
     pub fn state(&self) -> QueueState {
+        self.state_window(0, None)
+    }
+
+    /// Queue state with only `limit` tracks starting at `offset`, so the UI can
+    /// window very large queues. Durations always cover the full queue.
+    pub fn state_window(&self, offset: usize, limit: Option<usize>) -> QueueState {
+        let total_duration = self.tracks.iter().map(|t| t.duration).sum();
+        let remaining_duration = self
+            .tracks
+            .iter()
+            .skip(self.current_index.unwrap_or(0))
+            .map(|t| t.duration)
+            .sum();
+        let tracks = self
+            .tracks
+            .iter()
+            .skip(offset)
+            .take(limit.unwrap_or(usize::MAX))
+            .map(|t| Track::clone(t))
+            .collect();
+
         QueueState {
-            tracks: self.tracks.clone(),
+            tracks,
             current_index: self.current_index,
+            now_playing_queue_index: self.now_playing_index(),
             repeat_mode: self.repeat_mode,
-            shuffled: self.shuffled,
+            shuffled: self.shuffle_mode,
+            source: self.source.clone(),
+            offset,
+            total_tracks: self.tracks.len(),
+            total_duration,
+            remaining_duration,
+            resume_position: None,
         }
     }
 
+    /// Empty the queue. Shuffle mode stays on if it was.
     pub fn clear(&mut self) {
         self.tracks.clear();
         self.original_order.clear();
         self.current_index = None;
+        self.playing_from_queue = false;
         self.shuffled = false;
+        self.shuffle_seed = None;
+        self.source = None;
+        self.lazy_source = None;
+        self.generation += 1;
     }
 
     pub fn is_empty(&self) -> bool {
         self.tracks.is_empty()
     }
 
+    /// How many times the queue contents were replaced or cleared this session.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
     pub fn persisted_state(&self) -> PersistedQueueState {
         PersistedQueueState {
-            tracks: self.tracks.clone(),
-            original_order: self.original_order.clone(),
+            tracks: self.tracks.iter().map(|t| Track::clone(t)).collect(),
+            original_order: self
+                .original_order
+                .iter()
+                .map(|t| Track::clone(t))
+                .collect(),
             current_index: self.current_index,
             repeat_mode: self.repeat_mode,
-            shuffled: self.shuffled,
+            // The order is only ever shuffled in shuffle mode, so restoring
+            // it as shuffled just gives unshuffle an order to restore
+            shuffled: self.shuffle_mode,
+            source: self.source.clone(),
+            lazy_source: self.lazy_source.clone(),
+            position: 0.0,
+            shuffle_seed: self.shuffle_seed,
         }
     }
 
     pub fn restore_from_persisted(&mut self, state: PersistedQueueState) {
-        self.tracks = state.tracks;
-        self.original_order = state.original_order;
-        self.current_index = state.current_index;
+        let restore = |mut track: Track| {
+            // The saved queue may be older than the NEW badge
+            track.refresh_is_new();
+            Arc::new(track)
+        };
+        self.tracks = state.tracks.into_iter().map(restore).collect();
+        // Share allocations between both orders where the ids match. Each copy
+        // of a duplicated track pairs with one copy in the other order, so
+        // removal and unshuffle can still tell the copies apart.
+        let mut copies: HashMap<&str, VecDeque<&Arc<Track>>> = HashMap::new();
+        for track in &self.tracks {
+            copies
+                .entry(track.id.as_str())
+                .or_default()
+                .push_back(track);
+        }
+        self.original_order = state
+            .original_order
+            .into_iter()
+            .map(
+                |t| match copies.get_mut(t.id.as_str()).and_then(VecDeque::pop_front) {
+                    Some(copy) => Arc::clone(copy),
+                    None => restore(t),
+                },
+            )
+            .collect();
+        // A hand-edited or truncated file may point past the end
+        self.current_index = state.current_index.filter(|&i| i < self.tracks.len());
+        // Nothing plays until the restored track is started with `play_track`
+        self.playing_from_queue = false;
         self.repeat_mode = state.repeat_mode;
+        self.shuffle_mode = state.shuffled;
         self.shuffled = state.shuffled;
+        self.shuffle_seed = state.shuffle_seed.filter(|_| state.shuffled);
+        self.source = state.source;
+        self.lazy_source = state.lazy_source;
+        self.generation += 1;
+    }
+}
+
+/// Append the next page from the queue's lazy source, unless it has none.
+/// If a fetch is already running, wait for it to end instead of starting
+/// another. Returns whether this call added tracks.
+pub async fn refill(queue: &RwLock<PlaybackQueue>, client: &TidalClient) -> bool {
+    let mut guard = queue.write().await;
+    let Some(fetch) = guard.begin_lazy_fetch() else {
+        if guard.is_lazy_fetching() {
+            let fetched = Arc::clone(&guard.lazy_fetched);
+            // Registered before the lock is released, so the wakeup can't be missed
+            let done = fetched.notified();
+            drop(guard);
+            done.await;
+        }
+        return false;
+    };
+    drop(guard);
+    let result = fetch.source.next_page(client).await;
+
+    let mut queue = queue.write().await;
+    match result {
+        Ok((mut tracks, next)) => {
+            for track in &mut tracks {
+                track.resolve_artwork();
+            }
+            let count = tracks.len();
+            if !queue.finish_lazy_fetch(fetch, tracks, next) {
+                log::info!("Dropping lazy queue page fetched for a replaced queue");
+                return false;
+            }
+            log::info!("Appended {} tracks from the lazy queue source", count);
+            count > 0
+        }
+        Err(e) => {
+            log::warn!("Lazy queue refill failed: {}", e);
+            queue.abort_lazy_fetch(fetch);
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{track, tracks};
+    use proptest::prelude::*;
+
+    fn ids(queue: &PlaybackQueue) -> Vec<String> {
+        queue.track_ids()
+    }
+
+    fn album_source() -> QueueSource {
+        QueueSource {
+            kind: QueueSourceKind::Album,
+            id: Some("42".into()),
+            name: "Album X".into(),
+        }
+    }
+
+    #[test]
+    fn state_serializes_source_and_durations() {
+        let mut queue = PlaybackQueue::new();
+        let mut list = tracks(&["a", "b", "c"]);
+        list[0].duration = 100.0;
+        list[1].duration = 200.0;
+        list[2].duration = 300.5;
+        queue.set_tracks(list, 1);
+        queue.set_source(Some(album_source()));
+
+        let json = serde_json::to_value(queue.state()).unwrap();
+        assert_eq!(json["totalDuration"], 600.5);
+        assert_eq!(json["remainingDuration"], 500.5);
+        assert_eq!(json["totalTracks"], 3);
+        assert_eq!(
+            json["source"],
+            serde_json::json!({ "kind": "album", "id": "42", "name": "Album X" })
+        );
+    }
+
+    #[test]
+    fn windowed_state_still_covers_the_whole_queue() {
+        let mut queue = PlaybackQueue::new();
+        queue.set_tracks(tracks(&["a", "b", "c", "d"]), 0);
+
+        let state = queue.state_window(2, Some(1));
+        assert_eq!(state.tracks.len(), 1);
+        assert_eq!(state.tracks[0].id, "c");
+        assert_eq!(state.offset, 2);
+        assert_eq!(state.total_tracks, 4);
+        assert_eq!(state.total_duration, 720.0);
+        assert_eq!(state.remaining_duration, 720.0);
+    }
+
+    #[test]
+    fn empty_state_has_no_source_or_duration() {
+        let json = serde_json::to_value(QueueState::empty()).unwrap();
+        assert!(json["source"].is_null());
+        assert_eq!(json["totalDuration"], 0.0);
+        assert_eq!(json["remainingDuration"], 0.0);
+    }
+
+    #[test]
+    fn persisted_state_keeps_the_source() {
+        let mut queue = PlaybackQueue::new();
+        queue.set_tracks(tracks(&["a", "b"]), 1);
+        queue.set_source(Some(album_source()));
+
+        let json = serde_json::to_string(&queue.persisted_state()).unwrap();
+        let restored: PersistedQueueState = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.source, Some(album_source()));
+
+        let mut other = PlaybackQueue::new();
+        other.restore_from_persisted(restored);
+        assert_eq!(other.source(), Some(&album_source()));
+        assert_eq!(ids(&other), ["a", "b"]);
+    }
+
+    #[test]
+    fn persisted_state_without_a_source_still_loads() {
+        let json = serde_json::json!({
+            "tracks": [track("a")],
+            "originalOrder": [track("a")],
+            "currentIndex": 0,
+            "repeatMode": "off",
+            "shuffled": false,
+        });
+        let restored: PersistedQueueState = serde_json::from_value(json).unwrap();
+        assert_eq!(restored.source, None);
+        assert_eq!(restored.position, 0.0);
+    }
+
+    /// Five tracks starting at `start`, shuffled with a fixed seed if asked.
+    fn five(repeat: RepeatMode, shuffled: bool, start: usize) -> PlaybackQueue {
+        let mut queue = PlaybackQueue::new();
+        queue.set_tracks(tracks(&["a", "b", "c", "d", "e"]), start);
+        queue.set_repeat_mode(repeat);
+        if shuffled {
+            queue.shuffle(Some(7));
+        }
+        queue
+    }
+
+    fn peek_ids(queue: &PlaybackQueue, count: usize) -> Vec<String> {
+        queue.peek_next_n(count).into_iter().map(|t| t.id).collect()
+    }
+
+    #[test]
+    fn peek_next_n_matches_what_next_track_plays() {
+        for repeat in [RepeatMode::Off, RepeatMode::All, RepeatMode::One] {
+            for shuffled in [false, true] {
+                for start in [0, 2, 4] {
+                    let peeked = peek_ids(&five(repeat, shuffled, start), 8);
+                    let mut queue = five(repeat, shuffled, start);
+                    let played: Vec<String> = (0..8)
+                        .map_while(|_| queue.next_track().map(|t| t.id.clone()))
+                        .collect();
+                    assert_eq!(
+                        peeked, played,
+                        "{repeat:?} shuffled={shuffled} start={start}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn peek_next_n_per_repeat_mode() {
+        assert_eq!(peek_ids(&five(RepeatMode::Off, false, 3), 4), ["e"]);
+        assert!(peek_ids(&five(RepeatMode::Off, false, 4), 4).is_empty());
+        assert_eq!(
+            peek_ids(&five(RepeatMode::All, false, 3), 4),
+            ["e", "a", "b", "c"]
+        );
+        assert_eq!(
+            peek_ids(&five(RepeatMode::One, false, 1), 3),
+            ["b", "b", "b"]
+        );
+
+        // Shuffled, the current track is first and the rest follow the shuffled order
+        let queue = five(RepeatMode::Off, true, 2);
+        assert_eq!(queue.current_track().unwrap().id, "c");
+        assert_eq!(peek_ids(&queue, 10), ids(&queue)[1..]);
+    }
+
+    #[test]
+    fn peek_next_n_caps_the_count() {
+        for repeat in [RepeatMode::Off, RepeatMode::All, RepeatMode::One] {
+            let peeked = peek_ids(&five(repeat, false, 0), usize::MAX);
+            assert!(peeked.len() <= MAX_PEEK, "{repeat:?}");
+        }
+        assert_eq!(
+            peek_ids(&five(RepeatMode::All, false, 0), usize::MAX).len(),
+            MAX_PEEK
+        );
+        assert_eq!(
+            peek_ids(&five(RepeatMode::Off, false, 0), usize::MAX).len(),
+            4
+        );
+    }
+
+    #[test]
+    fn peek_next_n_on_an_empty_queue() {
+        let queue = PlaybackQueue::new();
+        assert!(queue.peek_next_n(3).is_empty());
+        assert!(five(RepeatMode::All, false, 0).peek_next_n(0).is_empty());
+    }
+
+    #[test]
+    fn peek_previous_wraps_only_with_repeat_all() {
+        assert_eq!(
+            five(RepeatMode::Off, false, 2).peek_previous().unwrap().id,
+            "b"
+        );
+        assert!(five(RepeatMode::Off, false, 0).peek_previous().is_none());
+        assert_eq!(
+            five(RepeatMode::All, false, 0).peek_previous().unwrap().id,
+            "e"
+        );
+    }
+
+    #[test]
+    fn now_playing_points_at_one_copy_of_a_duplicate() {
+        let mut queue = PlaybackQueue::new();
+        queue.set_tracks(tracks(&["a", "b", "a", "c", "a"]), 0);
+        assert_eq!(queue.now_playing_index(), Some(0));
+
+        queue.next_track();
+        queue.next_track();
+        assert_eq!(queue.now_playing_index(), Some(2));
+        assert_eq!(queue.state().now_playing_queue_index, Some(2));
+
+        // Playing "a" again by id keeps the copy at the cursor, not the first one
+        queue.sync_now_playing("a");
+        assert_eq!(queue.now_playing_index(), Some(2));
+
+        queue.jump_to(4);
+        assert_eq!(queue.now_playing_index(), Some(4));
+        queue.remove_track(0);
+        assert_eq!(queue.now_playing_index(), Some(3));
+    }
+
+    #[test]
+    fn off_queue_playback_has_no_now_playing_index() {
+        let mut queue = five(RepeatMode::Off, false, 1);
+        queue.sync_now_playing("z");
+        assert_eq!(queue.now_playing_index(), None);
+        assert_eq!(queue.state().now_playing_queue_index, None);
+        // The cursor stays put so the queue resumes where it was
+        assert_eq!(queue.current_index, Some(1));
+
+        // A track that is in the queue, but not at the cursor, is off-queue too
+        queue.sync_now_playing("d");
+        assert_eq!(queue.now_playing_index(), None);
+
+        // Advancing picks the queue back up
+        assert_eq!(queue.next_track().unwrap().id, "c");
+        assert_eq!(queue.now_playing_index(), Some(2));
+    }
+
+    #[test]
+    fn now_playing_clears_when_playback_leaves_the_queue() {
+        let mut queue = five(RepeatMode::Off, false, 4);
+        assert!(queue.next_track().is_none());
+        assert_eq!(queue.now_playing_index(), None);
+
+        assert!(queue.jump_to(5).is_none());
+        assert_eq!(queue.now_playing_index(), None);
+
+        let mut queue = five(RepeatMode::Off, false, 2);
+        queue.clear();
+        assert_eq!(queue.now_playing_index(), None);
+    }
+
+    #[test]
+    fn a_restored_queue_is_not_playing_yet() {
+        let state = five(RepeatMode::Off, false, 3).persisted_state();
+        let mut queue = PlaybackQueue::new();
+        queue.restore_from_persisted(state);
+        assert_eq!(queue.current_index, Some(3));
+        assert_eq!(queue.now_playing_index(), None);
+
+        queue.sync_now_playing("d");
+        assert_eq!(queue.now_playing_index(), Some(3));
+    }
+
+    fn current_id(queue: &PlaybackQueue) -> Option<String> {
+        queue.current_track().map(|t| t.id.clone())
+    }
+
+    #[test]
+    fn removing_before_the_current_track_shifts_the_index() {
+        let mut queue = five(RepeatMode::Off, false, 2);
+        queue.remove_track(0);
+        assert_eq!(ids(&queue), ["b", "c", "d", "e"]);
+        assert_eq!(queue.current_index, Some(1));
+        assert_eq!(current_id(&queue).as_deref(), Some("c"));
+        assert_eq!(queue.now_playing_index(), Some(1));
+    }
+
+    #[test]
+    fn removing_after_the_current_track_keeps_the_index() {
+        let mut queue = five(RepeatMode::Off, false, 2);
+        queue.remove_track(4);
+        assert_eq!(queue.current_index, Some(2));
+        assert_eq!(current_id(&queue).as_deref(), Some("c"));
+    }
+
+    #[test]
+    fn removing_the_current_track_moves_to_the_next_entry() {
+        let mut queue = five(RepeatMode::Off, false, 2);
+        queue.remove_track(2);
+        assert_eq!(current_id(&queue).as_deref(), Some("d"));
+        // The removed track is still playing, not the entry that took its place
+        assert_eq!(queue.now_playing_index(), None);
+
+        let mut queue = five(RepeatMode::Off, false, 4);
+        queue.remove_track(4);
+        assert_eq!(current_id(&queue).as_deref(), Some("d"));
+
+        let mut queue = PlaybackQueue::new();
+        queue.set_tracks(tracks(&["a"]), 0);
+        queue.remove_track(0);
+        assert_eq!(queue.current_index, None);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn removing_out_of_range_is_ignored() {
+        let mut queue = five(RepeatMode::Off, false, 2);
+        queue.remove_track(5);
+        assert_eq!(queue.len(), 5);
+        assert_eq!(queue.current_index, Some(2));
+    }
+
+    #[test]
+    fn remove_by_id_picks_the_requested_occurrence() {
+        let mut queue = PlaybackQueue::new();
+        queue.set_tracks(tracks(&["a", "x", "b", "x", "c", "x"]), 4);
+
+        assert!(queue.remove_track_by_id("x", 1));
+        assert_eq!(ids(&queue), ["a", "x", "b", "c", "x"]);
+        assert_eq!(current_id(&queue).as_deref(), Some("c"));
+
+        assert!(!queue.remove_track_by_id("x", 2));
+        assert!(!queue.remove_track_by_id("missing", 0));
+        assert_eq!(queue.len(), 5);
+    }
+
+    #[test]
+    fn removing_a_duplicate_keeps_the_other_copies_through_unshuffle() {
+        let mut queue = PlaybackQueue::new();
+        queue.set_tracks(tracks(&["x", "a", "x", "b", "x"]), 0);
+        queue.shuffle(Some(3));
+        let second_x = queue
+            .tracks
+            .iter()
+            .position(|t| Arc::ptr_eq(t, &queue.original_order[2]))
+            .unwrap();
+        queue.remove_track(second_x);
+        queue.unshuffle();
+
+        assert_eq!(ids(&queue), ["x", "a", "b", "x"]);
+        // The surviving copies are the first and third entries, not the second
+        assert!(Arc::ptr_eq(&queue.tracks[3], &queue.original_order[3]));
+    }
+
+    #[test]
+    fn dedupe_keeps_the_current_entry() {
+        let mut queue = PlaybackQueue::new();
+        queue.set_tracks(tracks(&["a", "b", "a", "c", "b"]), 2);
+
+        assert_eq!(queue.dedupe(), 2);
+        // The current copy of "a" is kept even though an earlier copy exists
+        assert_eq!(ids(&queue), ["b", "a", "c"]);
+        assert_eq!(current_id(&queue).as_deref(), Some("a"));
+        assert_eq!(queue.current_index, Some(1));
+        assert_eq!(queue.dedupe(), 0);
+    }
+
+    #[test]
+    fn a_seeded_shuffle_is_the_same_every_time() {
+        let names = ["a", "b", "c", "d", "e", "f", "g", "h"];
+        let shuffled = |seed| {
+            let mut queue = PlaybackQueue::new();
+            queue.set_tracks(tracks(&names), 5);
+            queue.shuffle(Some(seed));
+            assert_eq!(current_id(&queue).as_deref(), Some("f"));
+            assert_eq!(queue.current_index, Some(0));
+            ids(&queue)
+        };
+
+        // Pinned so a change of RNG, which would reorder saved queues, shows up
+        assert_eq!(shuffled(42), ["f", "d", "e", "g", "c", "h", "b", "a"]);
+        for seed in [0, 1, 42, u64::MAX] {
+            assert_eq!(shuffled(seed), shuffled(seed), "seed {}", seed);
+        }
+        assert_ne!(shuffled(1), shuffled(2));
+
+        // Reshuffling with the same seed starts over from the original order
+        let mut queue = PlaybackQueue::new();
+        queue.set_tracks(tracks(&names), 5);
+        queue.shuffle(Some(9));
+        queue.shuffle(Some(42));
+        assert_eq!(ids(&queue), shuffled(42));
+    }
+
+    #[test]
+    fn unshuffle_restores_the_order_and_the_playing_copy() {
+        for seed in 0..20 {
+            let mut queue = PlaybackQueue::new();
+            queue.set_tracks(tracks(&["a", "x", "b", "x", "c", "x"]), 3);
+            let playing = Arc::clone(&queue.tracks[3]);
+
+            queue.shuffle(Some(seed));
+            assert_eq!(queue.current_index, Some(0));
+            assert!(Arc::ptr_eq(&queue.tracks[0], &playing));
+            queue.unshuffle();
+
+            assert_eq!(ids(&queue), ["a", "x", "b", "x", "c", "x"]);
+            assert_eq!(queue.current_index, Some(3), "seed {}", seed);
+            assert!(!queue.is_shuffled());
+        }
+    }
+
+    #[test]
+    fn removing_next_to_the_current_entry_while_shuffled() {
+        let mut queue = five(RepeatMode::Off, true, 2);
+        let current = current_id(&queue);
+        let after = queue.tracks[1].id.clone();
+
+        queue.remove_track(1);
+        assert_eq!(queue.current_index, Some(0));
+        assert_eq!(current_id(&queue), current);
+
+        // Removing the current entry moves on to the one after it
+        let next = queue.tracks[1].id.clone();
+        queue.remove_track(0);
+        assert_eq!(current_id(&queue), Some(next));
+        assert_eq!(queue.now_playing_index(), None);
+
+        queue.unshuffle();
+        assert_eq!(queue.len(), 3);
+        assert!(!ids(&queue).contains(&after));
+    }
+
+    #[test]
+    fn advance_by_lands_where_repeated_next_track_would() {
+        for repeat in [RepeatMode::Off, RepeatMode::All, RepeatMode::One] {
+            for count in 1..8 {
+                let mut stepped = five(repeat, true, 1);
+                let mut jumped = five(repeat, true, 1);
+                let mut expected = None;
+                for _ in 0..count {
+                    expected = stepped.next_track().map(|t| t.id.clone());
+                }
+
+                let landed = jumped.advance_by(count).map(|t| t.id.clone());
+                assert_eq!(landed, expected, "{:?} by {}", repeat, count);
+                assert_eq!(jumped.now_playing_index(), stepped.now_playing_index());
+            }
+        }
+    }
+
+    #[test]
+    fn advance_by_wraps_around_with_repeat_all() {
+        let mut queue = five(RepeatMode::All, false, 3);
+        assert_eq!(
+            queue.advance_by(4).map(|t| t.id.clone()).as_deref(),
+            Some("c")
+        );
+        assert_eq!(queue.now_playing_index(), Some(2));
+
+        // More than a whole lap
+        assert_eq!(
+            queue.advance_by(11).map(|t| t.id.clone()).as_deref(),
+            Some("d")
+        );
+
+        // Nothing playing yet: the first step lands on the first track
+        for (count, expected) in [(1, "a"), (5, "e"), (6, "a"), (12, "b")] {
+            let mut queue = five(RepeatMode::All, false, 0);
+            queue.current_index = None;
+            assert_eq!(
+                queue.advance_by(count).map(|t| t.id.clone()).as_deref(),
+                Some(expected),
+                "by {}",
+                count
+            );
+        }
+    }
+
+    #[test]
+    fn advance_by_past_the_end_stops_without_repeat() {
+        let mut queue = five(RepeatMode::Off, false, 3);
+        assert!(queue.advance_by(2).is_none());
+        assert_eq!(queue.now_playing_index(), None);
+        assert_eq!(queue.current_index, Some(3));
+
+        let mut queue = five(RepeatMode::Off, false, 3);
+        assert_eq!(
+            queue.advance_by(1).map(|t| t.id.clone()).as_deref(),
+            Some("e")
+        );
+    }
+
+    #[test]
+    fn advance_by_follows_the_shuffled_order() {
+        let mut queue = five(RepeatMode::Off, true, 1);
+        let order = ids(&queue);
+        let start = queue.current_index.unwrap();
+        assert_eq!(order[start], "b");
+
+        let remaining = order.len() - start - 1;
+        for count in 1..=remaining {
+            let mut jumped = five(RepeatMode::Off, true, 1);
+            assert_eq!(
+                jumped.advance_by(count).map(|t| t.id.clone()),
+                Some(order[start + count].clone())
+            );
+        }
+        assert!(queue.advance_by(remaining + 1).is_none());
+        // Unshuffling afterwards still knows the original order
+        queue.unshuffle();
+        assert_eq!(ids(&queue), ["a", "b", "c", "d", "e"]);
+    }
+
+    #[test]
+    fn advance_by_zero_resumes_the_current_entry() {
+        let mut queue = five(RepeatMode::Off, false, 2);
+        queue.mark_off_queue();
+        assert_eq!(
+            queue.advance_by(0).map(|t| t.id.clone()).as_deref(),
+            Some("c")
+        );
+        assert_eq!(queue.now_playing_index(), Some(2));
+    }
+
+    #[test]
+    fn a_shuffled_queue_survives_a_save_and_restore() {
+        let mut queue = PlaybackQueue::new();
+        queue.set_tracks(tracks(&["x", "a", "x", "b", "x"]), 2);
+        queue.set_repeat_mode(RepeatMode::All);
+        let seed = queue.shuffle(None);
+
+        let json = serde_json::to_string(&queue.persisted_state()).unwrap();
+        let saved: PersistedQueueState = serde_json::from_str(&json).unwrap();
+        let mut restored = PlaybackQueue::new();
+        restored.restore_from_persisted(saved);
+
+        assert_eq!(ids(&restored), ids(&queue));
+        assert_eq!(restored.current_index, Some(0));
+        assert_eq!(restored.repeat_mode(), RepeatMode::All);
+        assert!(restored.is_shuffled());
+        assert_eq!(restored.shuffle_seed(), Some(seed));
+        assert_eq!(restored.now_playing_index(), None);
+
+        restored.unshuffle();
+        assert_eq!(ids(&restored), ["x", "a", "x", "b", "x"]);
+        assert_eq!(
+            restored
+                .current_index
+                .map(|i| ids(&restored)[i].clone())
+                .as_deref(),
+            Some("x")
+        );
+    }
+
+    #[test]
+    fn restored_duplicates_stay_separate_copies() {
+        let mut queue = PlaybackQueue::new();
+        queue.set_tracks(tracks(&["x", "a", "x", "b", "x"]), 0);
+        queue.shuffle(Some(5));
+        let mut restored = PlaybackQueue::new();
+        restored.restore_from_persisted(queue.persisted_state());
+
+        // Every entry is shared with exactly one entry of the other order
+        for track in &restored.tracks {
+            let shared = restored
+                .original_order
+                .iter()
+                .filter(|t| Arc::ptr_eq(t, track))
+                .count();
+            assert_eq!(shared, 1, "{}", track.id);
+        }
+
+        // So removing one copy leaves the other two through unshuffle
+        let x = restored.tracks.iter().position(|t| t.id == "x").unwrap();
+        restored.remove_track(x);
+        restored.unshuffle();
+        let xs = ids(&restored).iter().filter(|id| *id == "x").count();
+        assert_eq!(xs, 2);
+        assert_eq!(restored.len(), 4);
+    }
+
+    /// Straightforward `move_range`: cut the block out, splice it back in and
+    /// follow the current entry by identity.
+    fn reference_move_range(
+        list: &[usize],
+        current: usize,
+        start: usize,
+        len: usize,
+        to: usize,
+    ) -> (Vec<usize>, usize) {
+        let mut rest = list.to_vec();
+        let block: Vec<usize> = rest.drain(start..start + len).collect();
+        rest.splice(to..to, block);
+        let current = rest.iter().position(|&t| t == list[current]).unwrap();
+        (rest, current)
+    }
+
+    proptest! {
+        #[test]
+        fn move_range_matches_the_reference(
+            (len, current, start, block, to) in (1usize..12).prop_flat_map(|len| {
+                (Just(len), 0..len, 0..len).prop_flat_map(|(len, current, start)| {
+                    (Just(len), Just(current), Just(start), 1..=len - start)
+                        .prop_flat_map(|(len, current, start, block)| {
+                            (Just(len), Just(current), Just(start), Just(block), 0..=len - block)
+                        })
+                })
+            })
+        ) {
+            let names: Vec<String> = (0..len).map(|i| i.to_string()).collect();
+            let mut queue = PlaybackQueue::new();
+            queue.set_tracks(names.iter().map(|n| track(n)).collect(), current);
+            queue.move_range(start, block, to);
+
+            let list: Vec<usize> = (0..len).collect();
+            let (expected, expected_current) = reference_move_range(&list, current, start, block, to);
+            let expected: Vec<String> = expected.iter().map(|i| i.to_string()).collect();
+            prop_assert_eq!(ids(&queue), expected);
+            prop_assert_eq!(queue.current_index, Some(expected_current));
+        }
+
+        #[test]
+        fn move_range_out_of_bounds_changes_nothing(
+            len in 0usize..8, start in 0usize..10, block in 0usize..10, to in 0usize..10
+        ) {
+            prop_assume!(block == 0 || start + block > len || to + block > len);
+            let names: Vec<String> = (0..len).map(|i| i.to_string()).collect();
+            let mut queue = PlaybackQueue::new();
+            queue.set_tracks(names.iter().map(|n| track(n)).collect(), 0);
+            queue.move_range(start, block, to);
+            prop_assert_eq!(ids(&queue), names);
+        }
+    }
+
+    #[test]
+    fn move_range_carries_the_current_track_with_the_block() {
+        let mut queue = five(RepeatMode::Off, false, 1);
+        queue.move_range(0, 2, 3);
+        assert_eq!(ids(&queue), ["c", "d", "e", "a", "b"]);
+        assert_eq!(current_id(&queue).as_deref(), Some("b"));
+    }
+
+    mod lazy {
+        use super::*;
+        use crate::test_support::mock_client;
+        use std::time::Duration;
+        use wiremock::matchers::{method, path, query_param};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        const FAVORITES: &str = "/userCollections/1/relationships/tracks";
+
+        fn page(ids: &[&str], next: Option<&str>) -> serde_json::Value {
+            let included: Vec<serde_json::Value> = ids
+                .iter()
+                .map(|id| {
+                    serde_json::json!({
+                        "id": id,
+                        "type": "tracks",
+                        "attributes": { "title": format!("Track {}", id), "duration": "PT3M" },
+                    })
+                })
+                .collect();
+            let mut body = serde_json::json!({ "data": [], "included": included });
+            if let Some(next) = next {
+                body["links"] = serde_json::json!({ "meta": { "nextCursor": next } });
+            }
+            body
+        }
+
+        async fn mount_page(server: &MockServer, cursor: &str, response: ResponseTemplate) {
+            Mock::given(method("GET"))
+                .and(path(FAVORITES))
+                .and(query_param("page[cursor]", cursor))
+                .respond_with(response)
+                .mount(server)
+                .await;
+        }
+
+        fn favorites_queue(cursor: &str) -> RwLock<PlaybackQueue> {
+            let mut queue = PlaybackQueue::new();
+            queue.set_tracks(tracks(&["1", "2"]), 0);
+            queue.set_lazy_source(Some(LazySource::Favorites {
+                cursor: cursor.into(),
+            }));
+            RwLock::new(queue)
+        }
+
+        #[tokio::test]
+        async fn pages_are_appended_until_the_last_one() {
+            let server = MockServer::start().await;
+            let ok = |body| ResponseTemplate::new(200).set_body_json(body);
+            mount_page(&server, "c2", ok(page(&["3", "4"], Some("c3")))).await;
+            mount_page(&server, "c3", ok(page(&["5"], None))).await;
+            let client = mock_client(&server);
+            let queue = favorites_queue("c2");
+
+            assert!(queue.read().await.needs_refill(1));
+            assert!(refill(&queue, &client).await);
+            assert_eq!(ids(&*queue.read().await), ["1", "2", "3", "4"]);
+            assert_eq!(
+                queue.read().await.lazy_source,
+                Some(LazySource::Favorites {
+                    cursor: "c3".into()
+                })
+            );
+
+            assert!(refill(&queue, &client).await);
+            let queue = queue.read().await;
+            assert_eq!(ids(&queue), ["1", "2", "3", "4", "5"]);
+            assert_eq!(queue.lazy_source, None);
+            assert!(!queue.needs_refill(10));
+            // The current track didn't move
+            assert_eq!(current_id(&queue).as_deref(), Some("1"));
+        }
+
+        #[tokio::test]
+        async fn a_failed_page_is_retried() {
+            let server = MockServer::start().await;
+            Mock::given(method("GET"))
+                .and(path(FAVORITES))
+                .respond_with(ResponseTemplate::new(500))
+                .up_to_n_times(1)
+                .mount(&server)
+                .await;
+            let ok = ResponseTemplate::new(200).set_body_json(page(&["3"], None));
+            mount_page(&server, "c2", ok).await;
+            let client = mock_client(&server);
+            let queue = favorites_queue("c2");
+
+            assert!(!refill(&queue, &client).await);
+            assert_eq!(ids(&*queue.read().await), ["1", "2"]);
+            assert!(queue.read().await.needs_refill(1));
+
+            assert!(refill(&queue, &client).await);
+            assert_eq!(ids(&*queue.read().await), ["1", "2", "3"]);
+        }
+
+        #[tokio::test]
+        async fn only_one_refill_runs_at_a_time() {
+            let server = MockServer::start().await;
+            Mock::given(method("GET"))
+                .and(path(FAVORITES))
+                .respond_with(
+                    ResponseTemplate::new(200)
+                        .set_body_json(page(&["3"], None))
+                        .set_delay(Duration::from_millis(100)),
+                )
+                .expect(1)
+                .mount(&server)
+                .await;
+            let client = mock_client(&server);
+            let queue = favorites_queue("c2");
+
+            let (first, second) = tokio::join!(refill(&queue, &client), refill(&queue, &client));
+            assert!(first ^ second);
+            assert_eq!(ids(&*queue.read().await), ["1", "2", "3"]);
+            server.verify().await;
+        }
+
+        #[tokio::test]
+        async fn advancing_during_a_prefetch_waits_for_its_page() {
+            let server = MockServer::start().await;
+            let slow = ResponseTemplate::new(200)
+                .set_body_json(page(&["3"], Some("c3")))
+                .set_delay(Duration::from_millis(200));
+            mount_page(&server, "c2", slow).await;
+            let client = mock_client(&server);
+            let queue = favorites_queue("c2");
+
+            // The last track ends while the prefetch is still running
+            let advance = async {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                let mut guard = queue.write().await;
+                assert!(guard.is_lazy_fetching());
+                assert_eq!(guard.next_track().unwrap().id, "2");
+                // A snapshot taken meanwhile keeps the source
+                assert_eq!(
+                    guard.persisted_state().lazy_source,
+                    Some(LazySource::Favorites {
+                        cursor: "c2".into()
+                    })
+                );
+                assert!(guard.needs_refill(0));
+                drop(guard);
+
+                assert!(!refill(&queue, &client).await);
+                queue.write().await.next_track().map(|t| t.id.clone())
+            };
+            let (added, next) = tokio::join!(refill(&queue, &client), advance);
+
+            assert!(added);
+            assert_eq!(next.as_deref(), Some("3"));
+            let queue = queue.read().await;
+            assert!(!queue.is_lazy_fetching());
+            assert_eq!(
+                queue.lazy_source,
+                Some(LazySource::Favorites {
+                    cursor: "c3".into()
+                })
+            );
+            assert_eq!(server.received_requests().await.unwrap().len(), 1);
+        }
+
+        #[tokio::test]
+        async fn a_page_for_a_replaced_queue_is_dropped() {
+            let server = MockServer::start().await;
+            let slow = ResponseTemplate::new(200)
+                .set_body_json(page(&["3"], Some("c3")))
+                .set_delay(Duration::from_millis(200));
+            mount_page(&server, "c2", slow).await;
+            let client = mock_client(&server);
+            let queue = favorites_queue("c2");
+
+            let replace = async {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                queue.write().await.set_tracks(tracks(&["x", "y"]), 0);
+            };
+            let (added, ()) = tokio::join!(refill(&queue, &client), replace);
+
+            assert!(!added);
+            let queue = queue.read().await;
+            assert_eq!(ids(&queue), ["x", "y"]);
+            assert_eq!(queue.lazy_source, None);
+        }
+    }
+
+    #[test]
+    fn shuffle_mode_outlasts_new_tracks_and_clear() {
+        let mut queue = five(RepeatMode::Off, true, 0);
+        let names = ["n0", "n1", "n2", "n3", "n4", "n5"];
+
+        queue.set_tracks(tracks(&names), 3);
+        assert!(queue.is_shuffled());
+        assert_eq!(current_id(&queue).as_deref(), Some("n3"));
+        assert_eq!(queue.current_index, Some(0));
+        let mut sorted = ids(&queue);
+        sorted.sort();
+        assert_eq!(sorted, names);
+        // Tracks the user keeps skipping can still be moved back afterwards
+        queue.defer_upcoming(|t| t.id == "n1");
+        assert_eq!(ids(&queue).last().map(String::as_str), Some("n1"));
+
+        queue.clear();
+        assert!(queue.is_shuffled());
+        assert!(queue.state().shuffled);
+        assert_eq!(queue.shuffle_seed(), None);
+        // Still on after a save and restore of the empty queue
+        let mut restored = PlaybackQueue::new();
+        restored.restore_from_persisted(queue.persisted_state());
+        assert!(restored.is_shuffled());
+
+        // Turning it off on an empty queue sticks too
+        queue.unshuffle();
+        assert!(!queue.is_shuffled());
+        queue.set_tracks(tracks(&names), 3);
+        assert_eq!(ids(&queue), names);
+    }
+
+    #[test]
+    fn tracks_added_in_shuffle_mode_drop_the_seed() {
+        let mut queue = five(RepeatMode::Off, true, 2);
+        assert_eq!(queue.shuffle_seed(), Some(7));
+        queue.append_tracks(tracks(&["x", "y", "z"]));
+
+        // The order no longer follows seed 7, so it isn't offered for reuse
+        assert_eq!(queue.shuffle_seed(), None);
+        assert_eq!(queue.persisted_state().shuffle_seed, None);
+        assert_eq!(current_id(&queue).as_deref(), Some("c"));
+        assert_eq!(queue.current_index, Some(0));
+        assert_eq!(queue.upcoming_len(), 7);
+
+        queue.unshuffle();
+        assert_eq!(ids(&queue), ["a", "b", "c", "d", "e", "x", "y", "z"]);
+        assert_eq!(current_id(&queue).as_deref(), Some("c"));
+    }
+
+    #[test]
+    fn tracks_added_after_a_clear_in_shuffle_mode_can_be_unshuffled() {
+        let mut queue = five(RepeatMode::Off, true, 0);
+        queue.clear();
+        let names: Vec<String> = (0..20).map(|i| i.to_string()).collect();
+        let names: Vec<&str> = names.iter().map(String::as_str).collect();
+        queue.append_tracks(tracks(&names));
+        assert_eq!(queue.len(), 20);
+
+        queue.unshuffle();
+        assert_eq!(ids(&queue), names);
+        assert!(!queue.is_shuffled());
     }
 }
@@ -0,0 +1,363 @@
+/// Distance between index points, in seconds of audio.
+const INDEX_INTERVAL_SECS: u64 = 5;
+
+/// Bytes copied out of the stream buffer per read while scanning.
+const SCAN_CHUNK: usize = 1024 * 1024;
+
+/// Longest possible FLAC frame header, including its CRC.
+const MAX_FRAME_HEADER: usize = 16;
+
+/// Byte offsets of FLAC frames a few seconds apart, for streams without a
+/// SEEKTABLE. Symphonia has to bisect those by resyncing on frame headers;
+/// with the index a seek can start decoding right at a nearby frame.
+pub struct FlacSeekIndex {
+    /// Length of the "fLaC" marker and metadata blocks
+    header_len: usize,
+    /// First sample and byte offset of each indexed frame, ascending. The
+    /// first frame of the stream is always included.
+    points: Vec<(u64, usize)>,
+}
+
+struct FrameHeader {
+    variable_block_size: bool,
+    /// Sample number for variable block sizes, frame number otherwise
+    number: u64,
+    block_size: u64,
+}
+
+impl FlacSeekIndex {
+    /// Scan a complete FLAC stream for frame offsets. `read` fills the buffer
+    /// with up to `len` bytes from an offset (fewer at the end of the
+    /// stream), returning false to give up. None if the stream isn't FLAC or
+    /// no frames were found.
+    pub fn build(mut read: impl FnMut(usize, usize, &mut Vec<u8>) -> bool) -> Option<Self> {
+        let mut buf = Vec::new();
+        if !read(0, 4, &mut buf) || buf != b"fLaC" {
+            return None;
+        }
+
+        // Metadata blocks follow until the one flagged as last
+        let mut offset = 4;
+        let mut sample_rate = 0;
+        loop {
+            if !read(offset, 4 + 13, &mut buf) || buf.len() < 4 {
+                return None;
+            }
+            let last = buf[0] & 0x80 != 0;
+            let block_type = buf[0] & 0x7F;
+            let len = u32::from_be_bytes([0, buf[1], buf[2], buf[3]]) as usize;
+            if block_type == 0 && buf.len() == 4 + 13 {
+                // STREAMINFO: a 20-bit sample rate after the block and frame sizes
+                let info = &buf[4..];
+                sample_rate = (u32::from(info[10]) << 12)
+                    | (u32::from(info[11]) << 4)
+                    | (u32::from(info[12]) >> 4);
+            }
+            offset += 4 + len;
+            if last {
+                break;
+            }
+        }
+        if sample_rate == 0 {
+            return None;
+        }
+        let header_len = offset;
+        let interval = u64::from(sample_rate) * INDEX_INTERVAL_SECS;
+
+        let mut points: Vec<(u64, usize)> = Vec::new();
+        // Frame numbers count blocks of this size when it's fixed
+        let mut fixed_block_size = None;
+        // Where the next real frame starts; a valid-looking header anywhere
+        // else is a false sync inside audio data
+        let mut expected_sample = 0;
+        let mut pos = header_len;
+        loop {
+            if !read(pos, SCAN_CHUNK + MAX_FRAME_HEADER, &mut buf) {
+                return None;
+            }
+            let scan_len = buf.len().min(SCAN_CHUNK);
+            for (i, &byte) in buf[..scan_len].iter().enumerate() {
+                if byte != 0xFF {
+                    continue;
+                }
+                let Some(header) = parse_frame_header(&buf[i..]) else {
+                    continue;
+                };
+                let first_sample = if header.variable_block_size {
+                    header.number
+                } else {
+                    header.number * *fixed_block_size.get_or_insert(header.block_size)
+                };
+                if first_sample != expected_sample {
+                    continue;
+                }
+                expected_sample = first_sample + header.block_size;
+                if points
+                    .last()
+                    .is_none_or(|&(last, _)| first_sample >= last + interval)
+                {
+                    points.push((first_sample, pos + i));
+                }
+            }
+            if buf.len() <= SCAN_CHUNK {
+                break;
+            }
+            pos += SCAN_CHUNK;
+        }
+
+        if points.is_empty() {
+            return None;
+        }
+        Some(Self { header_len, points })
+    }
+
+    pub fn header_len(&self) -> usize {
+        self.header_len
+    }
+
+    /// The last indexed frame starting at or before `sample`: its first
+    /// sample and byte offset.
+    pub fn frame_before(&self, sample: u64) -> (u64, usize) {
+        let i = self.points.partition_point(|&(first, _)| first <= sample);
+        self.points[i.saturating_sub(1)]
+    }
+}
+
+/// Parse a frame header at the start of `bytes`, rejecting anything with
+/// reserved values or a bad CRC.
+fn parse_frame_header(bytes: &[u8]) -> Option<FrameHeader> {
+    // 14-bit sync code and a reserved zero bit, then the blocking strategy
+    if bytes.len() < 6 || bytes[0] != 0xFF || bytes[1] & 0xFE != 0xF8 {
+        return None;
+    }
+    let variable_block_size = bytes[1] & 1 == 1;
+    let block_code = bytes[2] >> 4;
+    let rate_code = bytes[2] & 0x0F;
+    let channels = bytes[3] >> 4;
+    let size_code = (bytes[3] >> 1) & 0x07;
+    if block_code == 0 || rate_code == 0x0F || channels > 10 || size_code == 3 || bytes[3] & 1 != 0
+    {
+        return None;
+    }
+
+    // Frame or sample number, coded like UTF-8
+    let lead = bytes[4];
+    let len = lead.leading_ones() as usize;
+    if len == 1 || len > 7 {
+        return None;
+    }
+    let mut number = u64::from(lead) & (0xFF >> (len + 1));
+    let mut i = 5;
+    for _ in 1..len {
+        let byte = *bytes.get(i)?;
+        if byte & 0xC0 != 0x80 {
+            return None;
+        }
+        number = (number << 6) | u64::from(byte & 0x3F);
+        i += 1;
+    }
+
+    let block_size = match block_code {
+        1 => 192,
+        2..=5 => 576 << (block_code - 2),
+        6 => {
+            let size = u64::from(*bytes.get(i)?) + 1;
+            i += 1;
+            size
+        }
+        7 => {
+            let size = u64::from(u16::from_be_bytes([*bytes.get(i)?, *bytes.get(i + 1)?])) + 1;
+            i += 2;
+            size
+        }
+        _ => 256 << (block_code - 8),
+    };
+    // Uncommon sample rates are stored after the block size
+    i += match rate_code {
+        12 => 1,
+        13 | 14 => 2,
+        _ => 0,
+    };
+
+    let crc = *bytes.get(i)?;
+    (crc8(&bytes[..i]) == crc).then_some(FrameHeader {
+        variable_block_size,
+        number,
+        block_size,
+    })
+}
+
+/// CRC-8 with polynomial 0x07, as used for FLAC frame headers.
+pub(crate) fn crc8(bytes: &[u8]) -> u8 {
+    bytes.iter().fold(0, |mut crc, &byte| {
+        crc ^= byte;
+        for _ in 0..8 {
+            crc = if crc & 0x80 != 0 {
+                (crc << 1) ^ 0x07
+            } else {
+                crc << 1
+            };
+        }
+        crc
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{flac, FLAC_BLOCK};
+
+    /// Bytes before the first frame of a `flac` fixture.
+    const FIXTURE_HEADER: usize = 4 + 4 + 34;
+
+    fn build(bytes: &[u8]) -> Option<FlacSeekIndex> {
+        FlacSeekIndex::build(|offset, len, buf| {
+            let start = offset.min(bytes.len());
+            let end = offset.saturating_add(len).min(bytes.len());
+            buf.clear();
+            buf.extend_from_slice(&bytes[start..end]);
+            true
+        })
+    }
+
+    /// First sample and byte offset of every frame in a `flac` fixture.
+    fn fixture_frames(channels: usize, frames: u32) -> Vec<(u64, usize)> {
+        let mut offset = FIXTURE_HEADER;
+        (0..frames)
+            .step_by(FLAC_BLOCK as usize)
+            .enumerate()
+            .map(|(number, start)| {
+                let len = FLAC_BLOCK.min(frames - start) as usize;
+                let number_len = if number < 0x80 { 1 } else { 2 };
+                let frame = (start as u64, offset);
+                offset += 4 + number_len + 2 + 1 + channels * (1 + 2 * len) + 2;
+                frame
+            })
+            .collect()
+    }
+
+    #[test]
+    fn build_indexes_a_frame_every_interval() {
+        // Several scan chunks long, with frame numbers past one byte
+        let frames = 30 * 44_100 + 123;
+        let bytes = flac(44_100, 2, frames);
+        assert!(bytes.len() > 2 * SCAN_CHUNK);
+        let index = build(&bytes).unwrap();
+        assert_eq!(index.header_len(), FIXTURE_HEADER);
+
+        // A frame is only accepted right after the one before it, so every
+        // boundary has to be found for the later points to line up
+        let interval = 44_100 * INDEX_INTERVAL_SECS;
+        let mut expected: Vec<(u64, usize)> = Vec::new();
+        for frame in fixture_frames(2, frames) {
+            if expected
+                .last()
+                .is_none_or(|&(last, _)| frame.0 >= last + interval)
+            {
+                expected.push(frame);
+            }
+        }
+        assert_eq!(expected.len(), 6);
+        assert_eq!(index.points, expected);
+    }
+
+    #[test]
+    fn build_rejects_streams_that_are_not_flac() {
+        assert!(build(b"RIFF\0\0\0\0WAVE").is_none());
+        assert!(build(b"").is_none());
+
+        // Metadata but no frames
+        let bytes = flac(44_100, 2, 44_100);
+        assert!(build(&bytes[..FIXTURE_HEADER]).is_none());
+    }
+
+    #[test]
+    fn build_gives_up_when_reading_fails() {
+        let bytes = flac(44_100, 2, 44_100);
+        let index = FlacSeekIndex::build(|offset, len, buf| {
+            if offset >= FIXTURE_HEADER {
+                return false;
+            }
+            buf.clear();
+            buf.extend_from_slice(&bytes[offset..(offset + len).min(bytes.len())]);
+            true
+        });
+        assert!(index.is_none());
+    }
+
+    #[test]
+    fn headers_with_a_bad_crc_or_sync_code_are_rejected() {
+        let bytes = flac(44_100, 2, 44_100);
+        let frame = &bytes[FIXTURE_HEADER..];
+        let header = parse_frame_header(frame).unwrap();
+        assert!(!header.variable_block_size);
+        assert_eq!(header.number, 0);
+        assert_eq!(header.block_size, FLAC_BLOCK as u64);
+
+        // CRC byte after the sync, codes, frame number and block size
+        let mut corrupted = frame[..16].to_vec();
+        corrupted[7] ^= 0x01;
+        assert!(parse_frame_header(&corrupted).is_none());
+
+        // Any other header byte changed no longer matches its CRC
+        let mut corrupted = frame[..16].to_vec();
+        corrupted[4] = 0x05;
+        assert!(parse_frame_header(&corrupted).is_none());
+
+        // Sync code with the reserved bit set
+        let mut false_sync = frame[..16].to_vec();
+        false_sync[1] = 0xFA;
+        false_sync[7] = crc8(&false_sync[..7]);
+        assert!(parse_frame_header(&false_sync).is_none());
+
+        // Reserved block size code, with a CRC that matches
+        let mut reserved = frame[..16].to_vec();
+        reserved[2] &= 0x0F;
+        reserved[7] = crc8(&reserved[..7]);
+        assert!(parse_frame_header(&reserved).is_none());
+
+        // A 0xFF byte that isn't followed by a sync code, or is cut short
+        assert!(parse_frame_header(&[0xFF, 0x00, 0x70, 0x18, 0x00, 0x0F]).is_none());
+        assert!(parse_frame_header(&frame[..5]).is_none());
+    }
+
+    #[test]
+    fn a_valid_header_inside_audio_data_is_not_indexed() {
+        let frames = 12 * 44_100;
+        let mut bytes = flac(44_100, 2, frames);
+        let real = build(&bytes).unwrap().points;
+
+        // The header of the first frame, copied into the samples of a frame
+        // just before the second point
+        let header = bytes[FIXTURE_HEADER..FIXTURE_HEADER + 8].to_vec();
+        let (_, second) = real[1];
+        bytes[second - 100..second - 92].copy_from_slice(&header);
+        assert!(parse_frame_header(&bytes[second - 100..]).is_some());
+
+        assert_eq!(build(&bytes).unwrap().points, real);
+    }
+
+    #[test]
+    fn frame_before_picks_the_last_point_at_or_before_the_sample() {
+        let index = FlacSeekIndex {
+            header_len: FIXTURE_HEADER,
+            points: vec![(0, 42), (221_184, 10_000), (442_368, 20_000)],
+        };
+        // First frame
+        assert_eq!(index.frame_before(0), (0, 42));
+        assert_eq!(index.frame_before(221_183), (0, 42));
+        // Exactly on a point, and between points
+        assert_eq!(index.frame_before(221_184), (221_184, 10_000));
+        assert_eq!(index.frame_before(300_000), (221_184, 10_000));
+        // Last frame, and past the end of the stream
+        assert_eq!(index.frame_before(442_368), (442_368, 20_000));
+        assert_eq!(index.frame_before(u64::MAX), (442_368, 20_000));
+    }
+
+    #[test]
+    fn crc8_matches_the_flac_check_value() {
+        // CRC-8/SMBUS, the variant FLAC uses
+        assert_eq!(crc8(b"123456789"), 0xF4);
+        assert_eq!(crc8(&[]), 0);
+    }
+}
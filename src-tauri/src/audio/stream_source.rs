@@ -1,3 +1,4 @@
+use crate::audio::seek_index::FlacSeekIndex;
 use std::io::{self, Read, Seek, SeekFrom};
 use std::sync::{Arc, Condvar, Mutex};
 
@@ -8,8 +9,6 @@ const INITIAL_CAPACITY: usize = 1024 * 1024; // 1MB
 struct StreamBuffer {
     /// All downloaded bytes (append-only from writer side).
     data: Vec<u8>,
-    /// Read cursor position.
-    position: usize,
     /// Whether the download has completed.
     finished: bool,
     /// Download error, if any.
@@ -17,11 +16,26 @@ struct StreamBuffer {
     /// Total expected length from HTTP Content-Length header.
     /// Set before data arrives so symphonia can see the stream as seekable.
     total_length: Option<u64>,
+    /// Tokio task feeding this buffer, aborted together with the stream.
+    download_task: Option<tokio::task::AbortHandle>,
+    /// Frame offsets of a finished FLAC download, built in the background.
+    seek_index: Option<Arc<FlacSeekIndex>>,
+}
+
+/// Maps a view that starts at an indexed frame: the stream header, then the
+/// data from `resume_at` on. The decoder sees a valid FLAC stream whose first
+/// frame is near the seek target.
+#[derive(Clone, Copy)]
+struct Splice {
+    header_len: usize,
+    resume_at: usize,
 }
 
 /// Handle to abort a stream source, unblocking any pending reads.
 /// Stored by AudioPlayer so stop_internal() can break the decode thread
 /// out of a blocking read when it has seeked past the downloaded data.
+/// Aborting also cancels the download task attached to the stream.
+#[derive(Clone)]
 pub struct StreamAbortHandle {
     shared: Arc<(Mutex<StreamBuffer>, Condvar)>,
 }
@@ -32,14 +46,88 @@ impl StreamAbortHandle {
         let mut state = lock.lock().unwrap();
         state.error = Some("aborted".to_string());
         state.finished = true;
+        if let Some(task) = state.download_task.take() {
+            task.abort();
+        }
         cvar.notify_all();
     }
+
+    /// Memory held by the downloaded bytes.
+    pub fn buffered_bytes(&self) -> u64 {
+        buffered_bytes(&self.shared)
+    }
+
+    pub fn progress(&self) -> StreamProgress {
+        stream_progress(&self.shared)
+    }
+
+    /// Attach the download task so `abort()` stops it instead of letting it
+    /// stream the rest of the file into a buffer nobody reads.
+    pub fn attach_download(&self, task: tokio::task::AbortHandle) {
+        let (lock, _) = &*self.shared;
+        let mut state = lock.lock().unwrap();
+        if state.error.is_some() {
+            // Already aborted before the task was attached
+            task.abort();
+        } else {
+            state.download_task = Some(task);
+        }
+    }
+}
+
+/// Aborts a stream when dropped, so a buffer nobody is going to read stops
+/// downloading. `disarm` hands the handle on to whoever reads the stream.
+pub struct AbortOnDrop(Option<StreamAbortHandle>);
+
+impl AbortOnDrop {
+    pub fn new(handle: StreamAbortHandle) -> Self {
+        Self(Some(handle))
+    }
+
+    /// Take the handle back without aborting.
+    pub fn disarm(mut self) -> StreamAbortHandle {
+        self.0.take().expect("only taken by disarm or drop")
+    }
+}
+
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        if let Some(handle) = self.0.take() {
+            handle.abort();
+        }
+    }
+}
+
+/// Snapshot of how much of a stream has been downloaded.
+#[derive(Debug, Clone, Copy)]
+pub struct StreamProgress {
+    pub downloaded: u64,
+    /// From the Content-Length header, when the server sent one
+    pub total: Option<u64>,
+    /// The download finished without an error
+    pub complete: bool,
+}
+
+impl StreamProgress {
+    /// Whether the stream is downloaded up to `fraction` of its length,
+    /// estimating that bytes are spread evenly over the track's duration.
+    /// False while the total length is unknown.
+    pub fn covers(&self, fraction: f64) -> bool {
+        if self.complete {
+            return true;
+        }
+        self.total
+            .is_some_and(|total| fraction.clamp(0.0, 1.0) * total as f64 <= self.downloaded as f64)
+    }
 }
 
 /// Adapter that makes an HTTP byte stream look like a seekable `Read` + `symphonia::core::io::MediaSource`.
 /// All downloaded bytes are retained in memory so symphonia can seek backwards.
 pub struct HttpStreamSource {
     shared: Arc<(Mutex<StreamBuffer>, Condvar)>,
+    /// Read cursor, in this view's coordinates.
+    position: usize,
+    splice: Option<Splice>,
 }
 
 impl HttpStreamSource {
@@ -47,16 +135,19 @@ impl HttpStreamSource {
         let shared = Arc::new((
             Mutex::new(StreamBuffer {
                 data: Vec::with_capacity(INITIAL_CAPACITY),
-                position: 0,
                 finished: false,
                 error: None,
                 total_length: None,
+                download_task: None,
+                seek_index: None,
             }),
             Condvar::new(),
         ));
 
         let source = Self {
             shared: Arc::clone(&shared),
+            position: 0,
+            splice: None,
         };
         let writer = StreamWriter {
             shared: Arc::clone(&shared),
@@ -65,6 +156,105 @@ impl HttpStreamSource {
 
         (source, writer, abort_handle)
     }
+
+    pub fn progress(&self) -> StreamProgress {
+        stream_progress(&self.shared)
+    }
+
+    /// Memory held by the downloaded bytes.
+    pub fn buffered_bytes(&self) -> u64 {
+        buffered_bytes(&self.shared)
+    }
+
+    /// Handle the decoder uses to seek through the FLAC frame index.
+    pub fn indexed_seek(&self) -> IndexedSeek {
+        IndexedSeek {
+            shared: Arc::clone(&self.shared),
+        }
+    }
+
+    /// Buffer offset of a position in this view, and how many bytes from
+    /// there are contiguous in the view (None: up to the end).
+    fn physical(&self, position: usize) -> (usize, Option<usize>) {
+        match self.splice {
+            Some(s) if position < s.header_len => (position, Some(s.header_len - position)),
+            Some(s) => (position - s.header_len + s.resume_at, None),
+            None => (position, None),
+        }
+    }
+
+    /// This view's length for a buffer of `len` bytes.
+    fn view_len(&self, len: u64) -> u64 {
+        match self.splice {
+            Some(s) => s.header_len as u64 + len.saturating_sub(s.resume_at as u64),
+            None => len,
+        }
+    }
+}
+
+fn buffered_bytes(shared: &(Mutex<StreamBuffer>, Condvar)) -> u64 {
+    let (lock, _) = shared;
+    lock.lock().unwrap().data.capacity() as u64
+}
+
+fn stream_progress(shared: &(Mutex<StreamBuffer>, Condvar)) -> StreamProgress {
+    let (lock, _) = shared;
+    let state = lock.lock().unwrap();
+    StreamProgress {
+        downloaded: state.data.len() as u64,
+        total: state.total_length,
+        complete: state.finished && state.error.is_none(),
+    }
+}
+
+/// Opens views of a finished FLAC stream that start at an indexed frame.
+#[derive(Clone)]
+pub struct IndexedSeek {
+    shared: Arc<(Mutex<StreamBuffer>, Condvar)>,
+}
+
+impl IndexedSeek {
+    /// A source starting at the indexed frame at or before `sample`, or None
+    /// while the stream has no index.
+    pub fn view_at(&self, sample: u64) -> Option<HttpStreamSource> {
+        let (lock, _) = &*self.shared;
+        let index = lock.lock().unwrap().seek_index.clone()?;
+        let (_, resume_at) = index.frame_before(sample);
+        Some(HttpStreamSource {
+            shared: Arc::clone(&self.shared),
+            position: 0,
+            splice: Some(Splice {
+                header_len: index.header_len(),
+                resume_at,
+            }),
+        })
+    }
+}
+
+/// Index the frames of a finished FLAC download. The buffer is copied out a
+/// chunk at a time so reads aren't held up while it scans.
+fn build_seek_index(shared: &(Mutex<StreamBuffer>, Condvar)) {
+    let started = std::time::Instant::now();
+    let (lock, _) = shared;
+    let index = FlacSeekIndex::build(|offset, len, buf| {
+        let state = lock.lock().unwrap();
+        // Aborted while scanning: nobody will seek in it
+        if state.error.is_some() {
+            return false;
+        }
+        let start = offset.min(state.data.len());
+        let end = offset.saturating_add(len).min(state.data.len());
+        buf.clear();
+        buf.extend_from_slice(&state.data[start..end]);
+        true
+    });
+    if let Some(index) = index {
+        log::info!(
+            "Built FLAC seek index in {} ms",
+            started.elapsed().as_millis()
+        );
+        lock.lock().unwrap().seek_index = Some(Arc::new(index));
+    }
 }
 
 impl Read for HttpStreamSource {
@@ -78,7 +268,8 @@ impl Read for HttpStreamSource {
         let mut waited = std::time::Duration::ZERO;
         const MAX_WAIT: std::time::Duration = std::time::Duration::from_secs(3);
 
-        while state.position >= state.data.len() && !state.finished && state.error.is_none() {
+        let (physical, contiguous) = self.physical(self.position);
+        while physical >= state.data.len() && !state.finished && state.error.is_none() {
             let (new_state, wait_result) = cvar.wait_timeout(state, timeout).unwrap();
             state = new_state;
             if wait_result.timed_out() {
@@ -96,14 +287,17 @@ impl Read for HttpStreamSource {
             return Err(io::Error::new(io::ErrorKind::Other, err.clone()));
         }
 
-        let available = state.data.len().saturating_sub(state.position);
+        let mut available = state.data.len().saturating_sub(physical);
+        if let Some(contiguous) = contiguous {
+            available = available.min(contiguous);
+        }
         if available == 0 && state.finished {
             return Ok(0); // EOF
         }
 
         let to_read = buf.len().min(available);
-        buf[..to_read].copy_from_slice(&state.data[state.position..state.position + to_read]);
-        state.position += to_read;
+        buf[..to_read].copy_from_slice(&state.data[physical..physical + to_read]);
+        self.position += to_read;
 
         // Notify writer (for back-pressure, though we no longer drain bytes)
         cvar.notify_all();
@@ -115,17 +309,19 @@ impl Read for HttpStreamSource {
 impl Seek for HttpStreamSource {
     fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
         let (lock, _cvar) = &*self.shared;
-        let mut state = lock.lock().unwrap();
+        let state = lock.lock().unwrap();
 
         let end = if state.finished {
-            state.data.len() as i64
+            state.data.len() as u64
         } else {
-            state.total_length.unwrap_or(state.data.len() as u64) as i64
+            state.total_length.unwrap_or(state.data.len() as u64)
         };
+        drop(state);
+        let end = self.view_len(end) as i64;
 
         let new_pos = match pos {
             SeekFrom::Start(offset) => offset as i64,
-            SeekFrom::Current(offset) => state.position as i64 + offset,
+            SeekFrom::Current(offset) => self.position as i64 + offset,
             SeekFrom::End(offset) => end + offset,
         };
 
@@ -136,8 +332,8 @@ impl Seek for HttpStreamSource {
             ));
         }
 
-        state.position = new_pos as usize;
-        Ok(state.position as u64)
+        self.position = new_pos as usize;
+        Ok(self.position as u64)
     }
 }
 
@@ -149,13 +345,14 @@ impl symphonia::core::io::MediaSource for HttpStreamSource {
     fn byte_len(&self) -> Option<u64> {
         let (lock, _) = &*self.shared;
         let state = lock.lock().unwrap();
-        if state.finished {
+        let len = if state.finished {
             Some(state.data.len() as u64)
         } else {
             // Return the Content-Length so symphonia treats the stream as seekable
             // even before the download completes.
             state.total_length
-        }
+        };
+        len.map(|len| self.view_len(len))
     }
 }
 
@@ -178,7 +375,7 @@ impl StreamWriter {
         let mut state = lock.lock().unwrap();
 
         if state.finished {
-            return Ok(());
+            return Err("stream closed".to_string());
         }
 
         // No back-pressure: download as fast as possible so seeking
@@ -189,11 +386,34 @@ impl StreamWriter {
         Ok(())
     }
 
+    /// Whether the stream was finished or aborted, meaning further bytes are discarded.
+    /// The download loop checks this between chunks so it stops promptly.
+    pub fn is_closed(&self) -> bool {
+        let (lock, _) = &*self.shared;
+        lock.lock().unwrap().finished
+    }
+
+    /// Abort handle sharing this writer's buffer.
+    pub fn abort_handle(&self) -> StreamAbortHandle {
+        StreamAbortHandle {
+            shared: Arc::clone(&self.shared),
+        }
+    }
+
+    /// Mark the download complete. A FLAC stream is then indexed in the
+    /// background so seeks don't have to search it.
     pub fn finish(&self) {
         let (lock, cvar) = &*self.shared;
         let mut state = lock.lock().unwrap();
+        let index = !state.finished && state.error.is_none() && state.data.starts_with(b"fLaC");
         state.finished = true;
         cvar.notify_all();
+        drop(state);
+
+        if index {
+            let shared = Arc::clone(&self.shared);
+            std::thread::spawn(move || build_seek_index(&shared));
+        }
     }
 
     pub fn set_error(&self, error: String) {
@@ -204,3 +424,155 @@ impl StreamWriter {
         cvar.notify_all();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{flac, FLAC_BLOCK};
+    use std::time::{Duration, Instant};
+    use symphonia::core::io::MediaSource;
+
+    /// A finished download of `bytes`.
+    fn finished(bytes: &[u8]) -> HttpStreamSource {
+        let (source, writer, _abort) = HttpStreamSource::new();
+        writer.set_total_length(bytes.len() as u64);
+        writer.write_bytes(bytes).unwrap();
+        writer.finish();
+        source
+    }
+
+    /// Wait for the background index of a finished FLAC download.
+    fn wait_for_index(source: &HttpStreamSource) -> Arc<FlacSeekIndex> {
+        let started = Instant::now();
+        loop {
+            if let Some(index) = source.shared.0.lock().unwrap().seek_index.clone() {
+                return index;
+            }
+            assert!(started.elapsed() < Duration::from_secs(5), "never indexed");
+            std::thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    fn read_to_end(source: &mut HttpStreamSource) -> Vec<u8> {
+        let mut out = Vec::new();
+        source.read_to_end(&mut out).unwrap();
+        out
+    }
+
+    #[test]
+    fn abort_rejects_further_chunks() {
+        let (_source, writer, abort) = HttpStreamSource::new();
+        writer.write_bytes(b"fLaC").unwrap();
+        abort.abort();
+        assert!(writer.is_closed());
+        assert!(writer.write_bytes(b"more").is_err());
+    }
+
+    #[test]
+    fn abort_wakes_a_blocked_read() {
+        let (mut source, _writer, abort) = HttpStreamSource::new();
+        let reader = std::thread::spawn(move || {
+            let started = Instant::now();
+            let result = source.read(&mut [0u8; 16]);
+            (result, started.elapsed())
+        });
+        std::thread::sleep(Duration::from_millis(50));
+        abort.abort();
+
+        let (result, waited) = reader.join().unwrap();
+        assert!(result.is_err());
+        assert!(
+            waited < Duration::from_millis(400),
+            "read took {:?}",
+            waited
+        );
+    }
+
+    #[test]
+    fn a_splice_reads_the_header_then_the_data_from_the_resume_point() {
+        let bytes: Vec<u8> = (0..=255).collect();
+        let mut source = finished(&bytes);
+        source.splice = Some(Splice {
+            header_len: 10,
+            resume_at: 100,
+        });
+        let expected = [&bytes[..10], &bytes[100..]].concat();
+
+        assert_eq!(source.byte_len(), Some(expected.len() as u64));
+        assert_eq!(read_to_end(&mut source), expected);
+
+        // A read never runs across the splice point
+        source.seek(SeekFrom::Start(6)).unwrap();
+        let mut buf = [0u8; 16];
+        assert_eq!(source.read(&mut buf).unwrap(), 4);
+        assert_eq!(&buf[..4], &bytes[6..10]);
+        assert_eq!(source.read(&mut buf).unwrap(), 16);
+        assert_eq!(buf, bytes[100..116]);
+
+        // Seeks are in the view's coordinates
+        assert_eq!(source.seek(SeekFrom::Start(13)).unwrap(), 13);
+        source.read_exact(&mut buf[..1]).unwrap();
+        assert_eq!(buf[0], bytes[103]);
+        assert_eq!(source.seek(SeekFrom::Current(-4)).unwrap(), 10);
+        source.read_exact(&mut buf[..1]).unwrap();
+        assert_eq!(buf[0], bytes[100]);
+        let end = source.seek(SeekFrom::End(0)).unwrap();
+        assert_eq!(end, expected.len() as u64);
+        assert_eq!(source.read(&mut buf).unwrap(), 0);
+        assert_eq!(source.seek(SeekFrom::End(-1)).unwrap(), end - 1);
+        source.read_exact(&mut buf[..1]).unwrap();
+        assert_eq!(buf[0], 255);
+    }
+
+    #[test]
+    fn a_splice_uses_the_announced_length_until_the_download_finishes() {
+        let (mut source, writer, _abort) = HttpStreamSource::new();
+        writer.set_total_length(1000);
+        writer.write_bytes(&[0; 300]).unwrap();
+        source.splice = Some(Splice {
+            header_len: 42,
+            resume_at: 200,
+        });
+        assert_eq!(source.byte_len(), Some(42 + 800));
+        assert_eq!(source.seek(SeekFrom::End(0)).unwrap(), 42 + 800);
+
+        // Resuming past the end leaves just the header
+        source.splice = Some(Splice {
+            header_len: 42,
+            resume_at: 2000,
+        });
+        assert_eq!(source.byte_len(), Some(42));
+    }
+
+    #[test]
+    fn a_finished_flac_download_opens_views_at_indexed_frames() {
+        let bytes = flac(44_100, 2, 12 * 44_100);
+        let source = finished(&bytes);
+        let index = wait_for_index(&source);
+
+        let seek = source.indexed_seek();
+        for sample in [0, 6 * 44_100, 11 * 44_100] {
+            let (first, resume_at) = index.frame_before(sample);
+            assert!(first <= sample && sample - first < 6 * 44_100);
+            assert_eq!(first % FLAC_BLOCK as u64, 0);
+
+            let mut view = seek.view_at(sample).unwrap();
+            let expected = [&bytes[..index.header_len()], &bytes[resume_at..]].concat();
+            assert_eq!(view.byte_len(), Some(expected.len() as u64));
+            assert_eq!(read_to_end(&mut view), expected);
+        }
+    }
+
+    #[test]
+    fn only_complete_flac_downloads_are_indexed() {
+        let (source, writer, _abort) = HttpStreamSource::new();
+        writer.write_bytes(&flac(44_100, 2, 44_100)).unwrap();
+        writer.set_error("connection reset".to_string());
+        writer.finish();
+
+        let wav = finished(&crate::test_support::wav(44_100, 2, 44_100));
+        std::thread::sleep(Duration::from_millis(100));
+        assert!(source.indexed_seek().view_at(0).is_none());
+        assert!(wav.indexed_seek().view_at(0).is_none());
+    }
+}
@@ -0,0 +1,360 @@
+use crate::api::models::Track;
+use crate::config::AppConfig;
+use crate::error::AppResult;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// Leaving a track before this many seconds counts as a skip.
+pub const EARLY_SKIP_SECS: f64 = 30.0;
+
+/// Tracks kept in the store; the least recently played are dropped beyond this.
+const MAX_ENTRIES: usize = 5000;
+
+/// Counts are halved once per interval so old listening habits fade out.
+const DECAY_INTERVAL_DAYS: i64 = 30;
+
+/// Plays needed before a track's skip ratio is trusted.
+const MIN_PLAYS_FOR_SKIP_RATIO: u32 = 3;
+
+/// Skip ratio at or above which a track counts as habitually skipped.
+const HABITUAL_SKIP_RATIO: f32 = 0.6;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrackStats {
+    pub play_count: u32,
+    pub completion_count: u32,
+    /// Plays left within the first `EARLY_SKIP_SECS`
+    pub skip_count: u32,
+    pub last_played: Option<DateTime<Utc>>,
+}
+
+impl TrackStats {
+    pub fn skip_ratio(&self) -> f32 {
+        if self.play_count == 0 {
+            return 0.0;
+        }
+        (self.skip_count as f32 / self.play_count as f32).min(1.0)
+    }
+
+    fn halve(&mut self) {
+        self.play_count /= 2;
+        self.completion_count /= 2;
+        self.skip_count /= 2;
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrackStatsEntry {
+    pub track_id: String,
+    #[serde(flatten)]
+    pub stats: TrackStats,
+}
+
+/// Local per-track listening stats keyed by track id, stored in `track_stats.json`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrackStatsStore {
+    #[serde(default)]
+    pub tracks: HashMap<String, TrackStats>,
+    #[serde(default)]
+    pub last_decay: Option<DateTime<Utc>>,
+}
+
+impl TrackStatsStore {
+    pub fn path() -> AppResult<PathBuf> {
+        Ok(AppConfig::config_dir()?.join("track_stats.json"))
+    }
+
+    /// Load the stats, starting empty if none have been saved.
+    pub fn load() -> AppResult<Self> {
+        let path = Self::path()?;
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(&path)?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    pub fn save(&self) -> AppResult<()> {
+        std::fs::create_dir_all(AppConfig::config_dir()?)?;
+        let content = serde_json::to_string(self)?;
+        std::fs::write(Self::path()?, content)?;
+        Ok(())
+    }
+
+    /// Remove the saved stats, if any.
+    pub fn delete() -> AppResult<()> {
+        let path = Self::path()?;
+        if path.exists() {
+            std::fs::remove_file(&path)?;
+        }
+        Ok(())
+    }
+
+    pub fn get(&self, track_id: &str) -> TrackStats {
+        self.tracks.get(track_id).cloned().unwrap_or_default()
+    }
+
+    pub fn record_play(&mut self, track_id: &str) {
+        let now = Utc::now();
+        self.decay(now);
+        let stats = self.tracks.entry(track_id.to_string()).or_default();
+        stats.play_count += 1;
+        stats.last_played = Some(now);
+        self.prune();
+    }
+
+    pub fn record_completed(&mut self, track_id: &str) {
+        if let Some(stats) = self.tracks.get_mut(track_id) {
+            stats.completion_count = (stats.completion_count + 1).min(stats.play_count);
+        }
+    }
+
+    pub fn record_skip(&mut self, track_id: &str) {
+        if let Some(stats) = self.tracks.get_mut(track_id) {
+            stats.skip_count = (stats.skip_count + 1).min(stats.play_count);
+        }
+    }
+
+    /// Whether the track has been played enough and skipped often enough that
+    /// autoplay and shuffle should push it back.
+    pub fn is_habitual_skip(&self, track_id: &str) -> bool {
+        self.tracks.get(track_id).is_some_and(|s| {
+            s.play_count >= MIN_PLAYS_FOR_SKIP_RATIO && s.skip_ratio() >= HABITUAL_SKIP_RATIO
+        })
+    }
+
+    /// Move habitually skipped tracks behind the rest, keeping the order otherwise.
+    pub fn down_rank(&self, tracks: &mut [Track]) {
+        tracks.sort_by_key(|t| self.is_habitual_skip(&t.id));
+    }
+
+    /// Most played tracks first, ties broken by the most recent play.
+    pub fn most_played(&self, limit: usize) -> Vec<TrackStatsEntry> {
+        let mut entries: Vec<TrackStatsEntry> = self
+            .tracks
+            .iter()
+            .filter(|(_, s)| s.play_count > 0)
+            .map(|(id, s)| TrackStatsEntry {
+                track_id: id.clone(),
+                stats: s.clone(),
+            })
+            .collect();
+        entries.sort_by(|a, b| {
+            b.stats
+                .play_count
+                .cmp(&a.stats.play_count)
+                .then(b.stats.last_played.cmp(&a.stats.last_played))
+        });
+        entries.truncate(limit);
+        entries
+    }
+
+    /// Ids of the most recently played tracks, most recent first.
+    pub fn recently_played(&self, limit: usize) -> Vec<String> {
+        let mut played: Vec<(&String, DateTime<Utc>)> = self
+            .tracks
+            .iter()
+            .filter_map(|(id, s)| Some((id, s.last_played?)))
+            .collect();
+        played.sort_by_key(|&(_, at)| std::cmp::Reverse(at));
+        played
+            .into_iter()
+            .take(limit)
+            .map(|(id, _)| id.clone())
+            .collect()
+    }
+
+    /// Halve every count once per `DECAY_INTERVAL_DAYS`, dropping tracks that
+    /// reach zero plays.
+    fn decay(&mut self, now: DateTime<Utc>) {
+        let Some(last) = self.last_decay else {
+            self.last_decay = Some(now);
+            return;
+        };
+        let intervals = (now - last).num_days() / DECAY_INTERVAL_DAYS;
+        if intervals <= 0 {
+            return;
+        }
+        for _ in 0..intervals.min(32) {
+            for stats in self.tracks.values_mut() {
+                stats.halve();
+            }
+        }
+        self.tracks.retain(|_, s| s.play_count > 0);
+        self.last_decay = Some(last + Duration::days(intervals * DECAY_INTERVAL_DAYS));
+    }
+
+    /// Keep at most `MAX_ENTRIES` tracks, dropping the least recently played.
+    fn prune(&mut self) {
+        if self.tracks.len() <= MAX_ENTRIES {
+            return;
+        }
+        let mut by_age: Vec<(String, Option<DateTime<Utc>>)> = self
+            .tracks
+            .iter()
+            .map(|(id, s)| (id.clone(), s.last_played))
+            .collect();
+        by_age.sort_by_key(|&(_, at)| at);
+        let excess = self.tracks.len() - MAX_ENTRIES;
+        for (id, _) in by_age.into_iter().take(excess) {
+            self.tracks.remove(&id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{temp_dirs, tracks};
+
+    fn played(store: &mut TrackStatsStore, id: &str, plays: u32, skips: u32) {
+        for _ in 0..plays {
+            store.record_play(id);
+        }
+        for _ in 0..skips {
+            store.record_skip(id);
+        }
+    }
+
+    fn stats(plays: u32, days_ago: i64) -> TrackStats {
+        TrackStats {
+            play_count: plays,
+            completion_count: plays,
+            skip_count: 0,
+            last_played: Some(Utc::now() - Duration::days(days_ago)),
+        }
+    }
+
+    #[test]
+    fn counts_never_exceed_plays() {
+        let mut store = TrackStatsStore::default();
+        store.record_skip("a");
+        store.record_completed("a");
+        assert_eq!(store.get("a").play_count, 0);
+
+        played(&mut store, "a", 2, 5);
+        store.record_completed("a");
+        store.record_completed("a");
+        store.record_completed("a");
+        let a = store.get("a");
+        assert_eq!((a.play_count, a.skip_count, a.completion_count), (2, 2, 2));
+        assert_eq!(a.skip_ratio(), 1.0);
+        assert!(a.last_played.is_some());
+        assert_eq!(TrackStats::default().skip_ratio(), 0.0);
+    }
+
+    #[test]
+    fn habitual_skips_need_enough_plays() {
+        let mut store = TrackStatsStore::default();
+        played(&mut store, "few", 2, 2);
+        played(&mut store, "often", 5, 3);
+        played(&mut store, "sometimes", 5, 2);
+        assert!(!store.is_habitual_skip("few"));
+        assert!(store.is_habitual_skip("often"));
+        assert!(!store.is_habitual_skip("sometimes"));
+        assert!(!store.is_habitual_skip("unknown"));
+    }
+
+    #[test]
+    fn down_rank_moves_skipped_tracks_back_in_order() {
+        let mut store = TrackStatsStore::default();
+        played(&mut store, "b", 3, 3);
+        played(&mut store, "d", 4, 4);
+        let mut list = tracks(&["a", "b", "c", "d", "e"]);
+        store.down_rank(&mut list);
+        let ids: Vec<&str> = list.iter().map(|t| t.id.as_str()).collect();
+        assert_eq!(ids, ["a", "c", "e", "b", "d"]);
+    }
+
+    #[test]
+    fn most_played_breaks_ties_by_recency() {
+        let mut store = TrackStatsStore::default();
+        store.tracks.insert("old".into(), stats(3, 10));
+        store.tracks.insert("new".into(), stats(3, 1));
+        store.tracks.insert("top".into(), stats(9, 30));
+        store.tracks.insert("zero".into(), stats(0, 0));
+        let ids: Vec<String> = store
+            .most_played(10)
+            .into_iter()
+            .map(|e| e.track_id)
+            .collect();
+        assert_eq!(ids, ["top", "new", "old"]);
+        assert_eq!(store.most_played(1).len(), 1);
+    }
+
+    #[test]
+    fn recently_played_is_most_recent_first() {
+        let mut store = TrackStatsStore::default();
+        store.tracks.insert("a".into(), stats(1, 3));
+        store.tracks.insert("b".into(), stats(1, 1));
+        store.tracks.insert("c".into(), stats(1, 2));
+        store.tracks.insert(
+            "never".into(),
+            TrackStats {
+                last_played: None,
+                ..stats(1, 0)
+            },
+        );
+        assert_eq!(store.recently_played(10), ["b", "c", "a"]);
+        assert_eq!(store.recently_played(2), ["b", "c"]);
+    }
+
+    #[test]
+    fn decay_halves_once_per_interval() {
+        let now = Utc::now();
+        let mut store = TrackStatsStore::default();
+        store.decay(now);
+        assert_eq!(store.last_decay, Some(now));
+
+        store.tracks.insert("a".into(), stats(8, 0));
+        store.tracks.insert("b".into(), stats(1, 0));
+        // Not a full interval yet
+        store.decay(now + Duration::days(DECAY_INTERVAL_DAYS - 1));
+        assert_eq!(store.get("a").play_count, 8);
+
+        store.decay(now + Duration::days(DECAY_INTERVAL_DAYS * 2 + 5));
+        assert_eq!(store.get("a").play_count, 2);
+        assert_eq!(store.get("a").completion_count, 2);
+        // Tracks that reach zero plays are forgotten
+        assert!(!store.tracks.contains_key("b"));
+        // The leftover days count towards the next interval
+        assert_eq!(
+            store.last_decay,
+            Some(now + Duration::days(DECAY_INTERVAL_DAYS * 2))
+        );
+    }
+
+    #[test]
+    fn prune_drops_the_least_recently_played() {
+        let mut store = TrackStatsStore::default();
+        for i in 0..MAX_ENTRIES as i64 {
+            store.tracks.insert(i.to_string(), stats(1, i + 1));
+        }
+        store.record_play("fresh");
+        assert_eq!(store.tracks.len(), MAX_ENTRIES);
+        assert!(store.tracks.contains_key("fresh"));
+        assert!(store.tracks.contains_key("0"));
+        assert!(!store.tracks.contains_key(&(MAX_ENTRIES - 1).to_string()));
+    }
+
+    #[test]
+    fn saves_and_loads() {
+        let _dirs = temp_dirs();
+        assert!(TrackStatsStore::load().unwrap().tracks.is_empty());
+
+        let mut store = TrackStatsStore::default();
+        played(&mut store, "a", 3, 1);
+        store.record_completed("a");
+        store.save().unwrap();
+
+        let loaded = TrackStatsStore::load().unwrap();
+        let a = loaded.get("a");
+        assert_eq!((a.play_count, a.skip_count, a.completion_count), (3, 1, 1));
+        assert_eq!(loaded.last_decay, store.last_decay);
+    }
+}
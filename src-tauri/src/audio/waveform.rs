@@ -0,0 +1,162 @@
+use crate::audio::decoder::AudioDecoder;
+use crate::config::AppConfig;
+use crate::error::AppResult;
+use crate::resource_usage::{dir_usage, DiskUsage};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// Peaks across the whole track.
+pub const WAVEFORM_BUCKETS: usize = 200;
+
+/// Audio decoded for the envelope; the rest of the track is estimated.
+pub const PREFETCH_SECS: u64 = 30;
+
+/// Rough compressed bytes per second, used to size the range request.
+fn bytes_per_sec(codec: &str) -> u64 {
+    match codec.to_lowercase().as_str() {
+        "flac_hires" => 400_000,
+        "flac" => 120_000,
+        // AAC/MP3 top out at 320 kbps
+        _ => 40_000,
+    }
+}
+
+/// Bytes to request so roughly `PREFETCH_SECS` of audio arrive.
+pub fn prefetch_bytes(codec: &str) -> u64 {
+    bytes_per_sec(codec) * PREFETCH_SECS
+}
+
+/// Coarse peak envelope for the seek bar.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Waveform {
+    pub track_id: String,
+    /// `WAVEFORM_BUCKETS` peaks in 0..=1, evenly spaced over the track
+    pub peaks: Vec<f32>,
+    /// Share of the buckets that were measured; later buckets hold the
+    /// average of the measured ones
+    pub measured_fraction: f32,
+}
+
+impl Waveform {
+    /// The peak at `fraction` of the track, if that part was measured.
+    pub fn peak_at(&self, fraction: f64) -> Option<f32> {
+        if fraction < 0.0 || fraction > f64::from(self.measured_fraction) {
+            return None;
+        }
+        let last = self.peaks.len().checked_sub(1)?;
+        let bucket = (fraction * self.peaks.len() as f64) as usize;
+        Some(self.peaks[bucket.min(last)])
+    }
+}
+
+/// Decode the start of a track and spread its peaks over `duration` seconds.
+/// Blocking; run it off the async runtime.
+pub fn compute_waveform(
+    track_id: &str,
+    bytes: &[u8],
+    codec: &str,
+    duration: f64,
+) -> AppResult<Waveform> {
+    let source = std::io::Cursor::new(bytes.to_vec());
+    let mut decoder = AudioDecoder::new(Box::new(source), Some(codec))?;
+
+    let channels = decoder.channels().max(1);
+    let bucket_secs = duration.max(1.0) / WAVEFORM_BUCKETS as f64;
+    let frames_per_bucket = ((bucket_secs * decoder.sample_rate() as f64) as usize).max(1);
+
+    let mut peaks: Vec<f32> = Vec::new();
+    let mut current = 0.0f32;
+    let mut frames_in_bucket = 0usize;
+    loop {
+        // The prefix ends mid-packet, so a late decode error just means we
+        // ran out of data
+        let chunk = match decoder.decode_next() {
+            Ok(Some(chunk)) => chunk,
+            Ok(None) => break,
+            Err(e) if !peaks.is_empty() => {
+                log::debug!("Waveform decode stopped for {}: {}", track_id, e);
+                break;
+            }
+            Err(e) => return Err(e),
+        };
+        for frame in chunk.samples.chunks(channels) {
+            let level = frame.iter().fold(0.0f32, |max, s| max.max(s.abs()));
+            current = current.max(level);
+            frames_in_bucket += 1;
+            if frames_in_bucket == frames_per_bucket {
+                peaks.push(current.min(1.0));
+                current = 0.0;
+                frames_in_bucket = 0;
+                if peaks.len() == WAVEFORM_BUCKETS {
+                    break;
+                }
+            }
+        }
+        if peaks.len() == WAVEFORM_BUCKETS {
+            break;
+        }
+    }
+    // Only count a partial bucket if it covers at least half its span
+    if peaks.len() < WAVEFORM_BUCKETS && frames_in_bucket * 2 >= frames_per_bucket {
+        peaks.push(current.min(1.0));
+    }
+
+    let measured = peaks.len();
+    let average = if measured > 0 {
+        peaks.iter().sum::<f32>() / measured as f32
+    } else {
+        0.0
+    };
+    peaks.resize(WAVEFORM_BUCKETS, average);
+
+    Ok(Waveform {
+        track_id: track_id.to_string(),
+        peaks,
+        measured_fraction: measured as f32 / WAVEFORM_BUCKETS as f32,
+    })
+}
+
+/// Computed waveforms stored as JSON under `~/.tauritidal/waveforms`, one
+/// file per track id.
+pub struct WaveformCache;
+
+impl WaveformCache {
+    pub fn dir() -> AppResult<PathBuf> {
+        Ok(AppConfig::config_dir()?.join("waveforms"))
+    }
+
+    fn path(track_id: &str) -> AppResult<PathBuf> {
+        // Ids are used as file names, so keep only safe characters
+        let id: String = track_id
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect();
+        Ok(Self::dir()?.join(format!("{}.json", id)))
+    }
+
+    /// A missing or unreadable file is treated as a miss.
+    pub fn load(track_id: &str) -> Option<Waveform> {
+        let content = std::fs::read_to_string(Self::path(track_id).ok()?).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    pub fn store(waveform: &Waveform) -> AppResult<()> {
+        std::fs::create_dir_all(Self::dir()?)?;
+        let content = serde_json::to_string(waveform)?;
+        std::fs::write(Self::path(&waveform.track_id)?, content)?;
+        Ok(())
+    }
+
+    pub fn usage() -> DiskUsage {
+        Self::dir().map(|dir| dir_usage(&dir)).unwrap_or_default()
+    }
+
+    pub fn clear() -> AppResult<()> {
+        let dir = Self::dir()?;
+        if dir.exists() {
+            std::fs::remove_dir_all(&dir)?;
+        }
+        Ok(())
+    }
+}
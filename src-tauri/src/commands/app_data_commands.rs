@@ -0,0 +1,229 @@
+use crate::audio::queue::{PersistedQueueState, RepeatMode};
+use crate::audio::track_stats::TrackStatsStore;
+use crate::audio::waveform::WaveformCache;
+use crate::error::{AppError, AppResult};
+use crate::events::{
+    self, AuthStatePayload, ModeChangedPayload, PlaybackState, StateChangedPayload,
+};
+use crate::image_cache::ImageCache;
+use crate::metadata_cache::MetadataCache;
+use serde::Deserialize;
+use tauri::{Emitter, State};
+
+use crate::AppState;
+
+/// Track ids that failed as unavailable this session and are kept out of
+/// suggestions.
+#[tauri::command]
+pub async fn get_session_blocklist(state: State<'_, AppState>) -> Result<Vec<String>, AppError> {
+    let mut ids: Vec<String> = state
+        .session_blocklist
+        .lock()
+        .unwrap()
+        .iter()
+        .cloned()
+        .collect();
+    ids.sort();
+    Ok(ids)
+}
+
+/// What `reset_app_data` wipes.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ResetScope {
+    /// Artwork, listing and waveform caches on disk, and the in-memory
+    /// palettes, profiles and favorite ids
+    Cache,
+    /// Tokens, the user's profile, favorite ids, any pending device login
+    /// and the client's rejected login and region block state
+    Auth,
+    /// The queue in memory and its saved copies
+    Queue,
+    /// Listening stats: plays, completions and skips
+    Stats,
+    /// All of the above
+    All,
+}
+
+impl ResetScope {
+    fn covers(self, scope: ResetScope) -> bool {
+        self == ResetScope::All || self == scope
+    }
+}
+
+/// Wipe app data for support and debugging. Playback is stopped and
+/// downloads aborted first, so nothing writes into what is being removed;
+/// then in-memory state is reset before its files are deleted, so a save
+/// can't bring them back. Settings outside the scope are left alone. A step
+/// that fails doesn't stop the others; the failures are reported together.
+#[tauri::command]
+pub async fn reset_app_data(
+    state: State<'_, AppState>,
+    app: tauri::AppHandle,
+    scope: ResetScope,
+) -> Result<(), AppError> {
+    log::info!("[reset_app_data] scope={:?}", scope);
+    let mut errors = Vec::new();
+
+    // Abort downloads first so the decode thread isn't blocked on a read
+    // when stop() joins it
+    state.abort_downloads().await;
+    let mut player = state.audio_player.write().await;
+    player.stop();
+    let playback_epoch = player.playback_epoch();
+    drop(player);
+    *state.current_track.write().await = None;
+    *state.playing_stream.lock().unwrap() = None;
+    state.take_interrupt();
+    let _ = app.emit(
+        events::PLAYBACK_STATE_CHANGED,
+        StateChangedPayload {
+            state: PlaybackState::Stopped,
+            playback_epoch,
+        },
+    );
+    #[cfg(target_os = "macos")]
+    crate::macos::now_playing::clear_now_playing();
+
+    if scope.covers(ResetScope::Queue) {
+        let mut queue = state.playback_queue.write().await;
+        queue.clear();
+        queue.unshuffle();
+        queue.set_repeat_mode(RepeatMode::Off);
+        drop(queue);
+        let _ = app.emit(events::PLAYBACK_QUEUE_CHANGED, ());
+        let _ = app.emit(
+            events::PLAYBACK_MODE_CHANGED,
+            ModeChangedPayload {
+                repeat_mode: RepeatMode::Off,
+                shuffled: false,
+            },
+        );
+    }
+
+    if scope.covers(ResetScope::Auth) {
+        *state.pkce_verifier.lock().await = None;
+        let mut config = state.tidal_client.config().write().await;
+        config.clear_user();
+        errors.extend(config.save().err());
+        drop(config);
+        state.tidal_client.clear_reauth_required();
+        state.tidal_client.clear_region_block();
+        let _ = app.emit(
+            events::AUTH_STATE_CHANGED,
+            AuthStatePayload {
+                authenticated: false,
+                user_id: None,
+                reauth_required: false,
+            },
+        );
+    }
+
+    if scope.covers(ResetScope::Stats) {
+        state.clear_track_stats();
+    }
+
+    if scope.covers(ResetScope::Cache) {
+        state.artwork_palettes.lock().unwrap().clear();
+        state.tidal_client.clear_public_profiles();
+    }
+    if scope.covers(ResetScope::Cache) || scope.covers(ResetScope::Auth) {
+        state.clear_favorites();
+    }
+
+    // Removing files is blocking file IO
+    match tokio::task::spawn_blocking(move || delete_files(scope)).await {
+        Ok(failed) => errors.extend(failed),
+        Err(e) => errors.push(AppError::Config(format!("Reset task failed: {}", e))),
+    }
+
+    if errors.is_empty() {
+        return Ok(());
+    }
+    let messages: Vec<String> = errors.iter().map(ToString::to_string).collect();
+    log::error!("[reset_app_data] failed: {}", messages.join("; "));
+    Err(AppError::Config(format!(
+        "Some data couldn't be reset: {}",
+        messages.join("; ")
+    )))
+}
+
+/// Delete the saved files `scope` covers, carrying on past failures.
+/// Returns what failed.
+fn delete_files(scope: ResetScope) -> Vec<AppError> {
+    let mut steps: Vec<fn() -> AppResult<()>> = Vec::new();
+    if scope.covers(ResetScope::Queue) {
+        steps.push(PersistedQueueState::delete);
+    }
+    if scope.covers(ResetScope::Stats) {
+        steps.push(TrackStatsStore::delete);
+    }
+    if scope.covers(ResetScope::Cache) {
+        steps.push(ImageCache::clear);
+        steps.push(MetadataCache::clear);
+        steps.push(WaveformCache::clear);
+    }
+    steps.into_iter().filter_map(|step| step().err()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AppConfig;
+    use crate::test_support::temp_dirs;
+    use std::path::PathBuf;
+
+    /// Saved queue, saved stats and one file in each cache, in that order.
+    fn saved_files() -> [PathBuf; 5] {
+        let queue = AppConfig::queue_path().unwrap();
+        let stats = TrackStatsStore::path().unwrap();
+        let [images, listings, waveforms] = [
+            ImageCache::dir().unwrap(),
+            MetadataCache::dir().unwrap(),
+            WaveformCache::dir().unwrap(),
+        ]
+        .map(|dir| dir.join("entry"));
+        let files = [queue, stats, images, listings, waveforms];
+        for file in &files {
+            std::fs::create_dir_all(file.parent().unwrap()).unwrap();
+            std::fs::write(file, b"{}").unwrap();
+        }
+        files
+    }
+
+    fn left(files: &[PathBuf]) -> Vec<bool> {
+        files.iter().map(|file| file.exists()).collect()
+    }
+
+    #[test]
+    fn each_scope_deletes_only_its_files() {
+        let _dirs = temp_dirs();
+        let cases = [
+            (ResetScope::Queue, [false, true, true, true, true]),
+            (ResetScope::Stats, [true, false, true, true, true]),
+            (ResetScope::Cache, [true, true, false, false, false]),
+            (ResetScope::Auth, [true, true, true, true, true]),
+            (ResetScope::All, [false; 5]),
+        ];
+        for (scope, expected) in cases {
+            let files = saved_files();
+            assert!(delete_files(scope).is_empty(), "{:?}", scope);
+            assert_eq!(left(&files), expected, "{:?}", scope);
+        }
+    }
+
+    #[test]
+    fn a_failed_step_does_not_stop_the_rest() {
+        let _dirs = temp_dirs();
+        let files = saved_files();
+        // A directory where the queue file should be can't be removed as a file
+        let queue = &files[0];
+        std::fs::remove_file(queue).unwrap();
+        std::fs::create_dir(queue).unwrap();
+
+        let errors = delete_files(ResetScope::All);
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(errors[0], AppError::Io(_)));
+        assert_eq!(left(&files), [true, false, false, false, false]);
+    }
+}
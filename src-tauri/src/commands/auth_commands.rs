@@ -1,55 +1,95 @@
 use crate::api::auth;
+use crate::api::health::{NetworkStatus, RegionBlock};
 use crate::api::models::{AuthStatus, DeviceAuthResponse};
+use crate::config::AppConfig;
 use crate::error::AppError;
 use tauri::State;
 
 use crate::AppState;
 
+/// How long the cached profile (name, email, avatar) is used before refetching.
+const PROFILE_MAX_AGE_HOURS: i64 = 24;
+
+/// Fetch the user's profile and cache it in the config. Failures are logged
+/// and leave the previous cache in place.
+async fn refresh_user_profile(state: &State<'_, AppState>) {
+    let profile = match state.tidal_client.get_user_profile().await {
+        Ok(profile) => profile,
+        Err(e) => {
+            log::warn!("Failed to fetch user profile: {}", e);
+            return;
+        }
+    };
+
+    let mut config = state.tidal_client.config().write().await;
+    if let Some(name) = profile.display_name() {
+        config.display_name = Some(name);
+    }
+    config.email = profile.email;
+    config.avatar_url = profile.avatar_url;
+    if let Some(country) = profile.country {
+        config.country_code = country;
+    }
+    config.profile_fetched_at = Some(chrono::Utc::now());
+    let _ = config.save();
+}
+
+fn auth_status(config: &AppConfig, authenticated: bool) -> AuthStatus {
+    AuthStatus {
+        authenticated,
+        reauth_required: !authenticated && config.reauth_required,
+        user_id: config.user_id.clone(),
+        display_name: config.display_name.clone(),
+        country_code: config.country_code.clone(),
+        email: config.email.clone(),
+        avatar_url: config.avatar_url.clone(),
+    }
+}
+
 #[tauri::command]
 pub async fn check_auth_status(state: State<'_, AppState>) -> Result<AuthStatus, AppError> {
     let config = state.tidal_client.config().read().await;
     // Only report authenticated when we have a user-level token (user_id present),
-    // not just a client credentials token (catalog-only access)
-    let has_user_auth =
-        config.user_id.is_some() && config.access_token.is_some() && !config.is_token_expired();
-    let display_name = config.display_name.clone();
-    let user_id = config.user_id.clone();
-    let country_code = config.country_code.clone();
+    // not just a client credentials token (catalog-only access). An expired token
+    // still counts while a refresh token can renew it, e.g. while the startup
+    // refresh waits for the network to come back.
+    let token_valid = config.access_token.is_some() && !config.is_token_expired();
+    let has_user_auth = config.user_id.is_some() && (token_valid || config.refresh_token.is_some());
+    // Refresh the cached profile when it's missing or more than a day old
+    let profile_stale = config.display_name.is_none()
+        || config.profile_fetched_at.is_none_or(|at| {
+            chrono::Utc::now() - at > chrono::Duration::hours(PROFILE_MAX_AGE_HOURS)
+        });
     drop(config);
 
-    // If authenticated but no display name cached, fetch it from the API
-    let display_name = if has_user_auth && display_name.is_none() {
-        match state.tidal_client.get_user_profile().await {
-            Ok((username, first_name, last_name)) => {
-                // Build display name: prefer "firstName lastName", fall back to username
-                let name = match (&first_name, &last_name) {
-                    (Some(f), Some(l)) if !f.is_empty() => Some(format!("{} {}", f, l)),
-                    (Some(f), _) if !f.is_empty() => Some(f.clone()),
-                    _ => username.clone(),
-                };
-                // Cache the display name in config
-                if let Some(ref n) = name {
-                    let mut config = state.tidal_client.config().write().await;
-                    config.display_name = Some(n.clone());
-                    let _ = config.save();
-                }
-                name
-            }
-            Err(e) => {
-                log::warn!("Failed to fetch user profile: {}", e);
-                None
-            }
-        }
-    } else {
-        display_name
-    };
+    if has_user_auth && profile_stale {
+        refresh_user_profile(&state).await;
+    }
 
-    Ok(AuthStatus {
-        authenticated: has_user_auth,
-        user_id,
-        display_name,
-        country_code,
-    })
+    let config = state.tidal_client.config().read().await;
+    Ok(auth_status(&config, has_user_auth))
+}
+
+/// Health of the connection to the API, from recent request latency and errors.
+/// Changes are also pushed as `network:status-changed`.
+#[tauri::command]
+pub async fn get_network_status(state: State<'_, AppState>) -> Result<NetworkStatus, AppError> {
+    Ok(state.tidal_client.network_status())
+}
+
+/// Set while the whole catalog is refused in the user's country. Changes are
+/// also pushed as `network:region-block-changed`.
+#[tauri::command]
+pub async fn get_region_block(state: State<'_, AppState>) -> Result<Option<RegionBlock>, AppError> {
+    Ok(state.tidal_client.region_block())
+}
+
+/// Leave region blocked mode now instead of waiting for the next retry,
+/// e.g. after connecting to a VPN.
+#[tauri::command]
+pub async fn clear_region_block(state: State<'_, AppState>) -> Result<(), AppError> {
+    state.tidal_client.clear_region_block();
+    Ok(())
 }
 
 /// Device code flow step 1: get a device code + user code.
@@ -109,46 +149,26 @@ pub async fn poll_login(state: State<'_, AppState>) -> Result<AuthStatus, AppErr
             if let Some(user_id) = &token_response.user_id {
                 config.user_id = Some(user_id.to_string());
             }
+            config.reauth_required = false;
             config.save()?;
-            let user_id = config.user_id.clone();
-            let country_code = config.country_code.clone();
             drop(config);
 
-            // Fetch user profile to get display name
-            let display_name = match state.tidal_client.get_user_profile().await {
-                Ok((username, first_name, last_name)) => {
-                    let name = match (&first_name, &last_name) {
-                        (Some(f), Some(l)) if !f.is_empty() => Some(format!("{} {}", f, l)),
-                        (Some(f), _) if !f.is_empty() => Some(f.clone()),
-                        _ => username.clone(),
-                    };
-                    if let Some(ref n) = name {
-                        let mut config = state.tidal_client.config().write().await;
-                        config.display_name = Some(n.clone());
-                        let _ = config.save();
-                    }
-                    name
-                }
-                Err(e) => {
-                    log::warn!("Failed to fetch user profile after login: {}", e);
-                    None
-                }
-            };
+            // Fetch user profile to get display name, email and avatar
+            refresh_user_profile(&state).await;
 
-            Ok(AuthStatus {
-                authenticated: true,
-                user_id,
-                display_name,
-                country_code,
-            })
+            let config = state.tidal_client.config().read().await;
+            Ok(auth_status(&config, true))
         }
         None => {
             // Still pending, user hasn't authorized yet
             Ok(AuthStatus {
                 authenticated: false,
+                reauth_required: false,
                 user_id: None,
                 display_name: None,
                 country_code: "US".into(),
+                email: None,
+                avatar_url: None,
             })
         }
     }
@@ -160,6 +180,12 @@ pub async fn handle_auth_callback(
     state: State<'_, AppState>,
     code: String,
 ) -> Result<AuthStatus, AppError> {
+    complete_auth_callback(&state, &code).await
+}
+
+/// Exchange a PKCE callback code for tokens. Also called directly when the
+/// callback arrives as a deep link.
+pub async fn complete_auth_callback(state: &AppState, code: &str) -> Result<AuthStatus, AppError> {
     let verifier = state
         .pkce_verifier
         .lock()
@@ -170,7 +196,7 @@ pub async fn handle_auth_callback(
     let token_response = auth::exchange_code(
         state.tidal_client.http_client(),
         state.tidal_client.config(),
-        &code,
+        code,
         &verifier,
     )
     .await?;
@@ -185,17 +211,10 @@ pub async fn handle_auth_callback(
     if let Some(user_id) = &token_response.user_id {
         config.user_id = Some(user_id.to_string());
     }
+    config.reauth_required = false;
     config.save()?;
-    let user_id = config.user_id.clone();
-    let country_code = config.country_code.clone();
-    drop(config);
 
-    Ok(AuthStatus {
-        authenticated: true,
-        user_id,
-        display_name: None,
-        country_code,
-    })
+    Ok(auth_status(&config, true))
 }
 
 #[tauri::command]
@@ -204,15 +223,14 @@ pub async fn logout(state: State<'_, AppState>) -> Result<(), AppError> {
     let mut player = state.audio_player.write().await;
     player.stop();
     drop(player);
+    state.discard_preloaded().await;
 
     // Clear user auth fields from config, keep client_id/secret/country/quality
     let mut config = state.tidal_client.config().write().await;
-    config.access_token = None;
-    config.refresh_token = None;
-    config.expires_at = None;
-    config.user_id = None;
-    config.display_name = None;
+    config.clear_user();
     config.save()?;
+    drop(config);
+    state.clear_favorites();
 
     Ok(())
 }
@@ -220,22 +238,13 @@ pub async fn logout(state: State<'_, AppState>) -> Result<(), AppError> {
 #[tauri::command]
 pub async fn init_client_credentials(state: State<'_, AppState>) -> Result<(), AppError> {
     let config = state.tidal_client.config().read().await;
-    let client_id = config.client_id.clone();
-    let client_secret = config.client_secret.clone();
+    let stale = config.access_token.clone();
     drop(config);
 
-    let token_response = auth::client_credentials_token(
-        state.tidal_client.http_client(),
-        &client_id,
-        &client_secret,
-    )
-    .await?;
-
-    let mut config = state.tidal_client.config().write().await;
-    config.access_token = Some(token_response.access_token);
-    config.expires_at =
-        Some(chrono::Utc::now() + chrono::Duration::seconds(token_response.expires_in as i64));
-    config.save()?;
+    state
+        .tidal_client
+        .renew_client_credentials(stale.as_deref())
+        .await?;
 
     Ok(())
 }
@@ -1,5 +1,12 @@
-use crate::api::models::{Album, Artist, RecommendationSection, Track};
-use crate::error::AppError;
+use crate::api::models::{
+    Album, AlbumPage, Artist, ArtistPage, HomeSectionKind, PublicProfile, RecommendationSection,
+    Track, TrackDetails, Video,
+};
+use crate::api::user::HomeFetch;
+use crate::discovery_seeds::DiscoverySeeds;
+use crate::error::{AppError, AppResult};
+use crate::metadata_cache::{self, AlbumListing, CacheKind, Cached, MetadataCache};
+use std::sync::Arc;
 use tauri::State;
 
 use crate::AppState;
@@ -17,12 +24,160 @@ pub async fn get_album_tracks(
     album_id: String,
 ) -> Result<Vec<Track>, AppError> {
     let mut tracks = state.tidal_client.get_album_tracks(&album_id).await?;
+    if state.hide_explicit().await {
+        tracks.retain(|t| !t.explicit);
+    }
     for track in &mut tracks {
         track.resolve_artwork();
     }
     Ok(tracks)
 }
 
+/// Album header and tracks in one call, served from the on-disk cache when
+/// available (`stale` marks data being refreshed in the background).
+#[tauri::command]
+pub async fn get_album_listing(
+    state: State<'_, AppState>,
+    app: tauri::AppHandle,
+    album_id: String,
+) -> Result<Cached<AlbumListing>, AppError> {
+    let client = Arc::clone(&state.tidal_client);
+    let id = album_id.clone();
+    let mut listing =
+        metadata_cache::cached_or_fetch(&app, CacheKind::Album, &album_id, move || async move {
+            let (tracks, other_items) = client.get_album_items(&id).await?;
+            Ok::<_, AppError>(AlbumListing {
+                album: client.get_album(&id).await?,
+                tracks,
+                other_items,
+            })
+        })
+        .await?;
+
+    if state.hide_explicit().await {
+        listing.data.tracks.retain(|t| !t.explicit);
+    }
+    // A cached listing may be from before the release stopped being new
+    listing.data.album.refresh_is_new();
+    listing.data.album.resolve_artwork();
+    for track in &mut listing.data.tracks {
+        track.refresh_is_new();
+        track.resolve_artwork();
+    }
+    Ok(listing)
+}
+
+/// Albums shown under "More from this artist" on the album page.
+const MORE_FROM_ARTIST_LEN: usize = 12;
+
+/// Top tracks shown on the artist page.
+const ARTIST_TOP_TRACKS_LEN: usize = 10;
+
+/// Log a failed optional page section and fall back to an empty one.
+fn or_empty<T>(result: AppResult<Vec<T>>, section: &str, id: &str) -> Vec<T> {
+    result.unwrap_or_else(|e| {
+        log::warn!("Loading {} for {} failed: {}", section, id, e);
+        Vec::new()
+    })
+}
+
+/// Album header, tracks and more albums by the same artist in one call. The
+/// album and its tracks are fetched concurrently; only they can fail the call.
+#[tauri::command]
+pub async fn get_album_page(
+    state: State<'_, AppState>,
+    album_id: String,
+) -> Result<AlbumPage, AppError> {
+    let client = &state.tidal_client;
+    let (album, tracks) = tokio::join!(
+        client.get_album(&album_id),
+        client.get_album_tracks(&album_id)
+    );
+    let mut album = album?;
+    let mut tracks = tracks?;
+
+    let mut more_from_artist = match &album.artist_id {
+        Some(artist_id) => or_empty(
+            client.get_artist_albums(artist_id).await,
+            "more from artist",
+            &album_id,
+        ),
+        None => Vec::new(),
+    };
+    more_from_artist.retain(|a| a.id != album.id);
+    more_from_artist.truncate(MORE_FROM_ARTIST_LEN);
+
+    if state.hide_explicit().await {
+        tracks.retain(|t| !t.explicit);
+    }
+    album.resolve_artwork();
+    for track in &mut tracks {
+        track.resolve_artwork();
+    }
+    for other in &mut more_from_artist {
+        other.resolve_artwork();
+    }
+    Ok(AlbumPage {
+        album,
+        tracks,
+        more_from_artist,
+    })
+}
+
+/// Artist header, top tracks, albums, appearances and similar artists in one
+/// call, fetched concurrently. Only the artist itself can fail the call.
+#[tauri::command]
+pub async fn get_artist_page(
+    state: State<'_, AppState>,
+    artist_id: String,
+) -> Result<ArtistPage, AppError> {
+    let client = &state.tidal_client;
+    let (artist, top_tracks, discography, similar_artists) = tokio::join!(
+        client.get_artist(&artist_id),
+        client.get_artist_top_tracks(&artist_id),
+        client.get_artist_discography(&artist_id),
+        client.get_similar_artists(&artist_id)
+    );
+    let mut artist = artist?;
+    let mut top_tracks = or_empty(top_tracks, "top tracks", &artist_id);
+    let (mut albums, mut appears_on) = match discography {
+        Ok(discography) => (discography.main, discography.appears_on),
+        Err(e) => {
+            log::warn!("Loading albums for {} failed: {}", artist_id, e);
+            (Vec::new(), Vec::new())
+        }
+    };
+    let mut similar_artists = or_empty(similar_artists, "similar artists", &artist_id);
+
+    if state.hide_explicit().await {
+        top_tracks.retain(|t| !t.explicit);
+    }
+    top_tracks.truncate(ARTIST_TOP_TRACKS_LEN);
+    artist.resolve_artwork();
+    for track in &mut top_tracks {
+        track.resolve_artwork();
+    }
+    for album in albums.iter_mut().chain(appears_on.iter_mut()) {
+        album.resolve_artwork();
+    }
+    for similar in &mut similar_artists {
+        similar.resolve_artwork();
+    }
+    Ok(ArtistPage {
+        artist,
+        top_tracks,
+        albums,
+        appears_on,
+        similar_artists,
+    })
+}
+
+/// Drop every cached album and playlist listing.
+#[tauri::command]
+pub async fn clear_metadata_cache() -> Result<(), AppError> {
+    MetadataCache::clear()
+}
+
 #[tauri::command]
 pub async fn get_artist(state: State<'_, AppState>, artist_id: String) -> Result<Artist, AppError> {
     let mut artist = state.tidal_client.get_artist(&artist_id).await?;
@@ -42,12 +197,75 @@ pub async fn get_artist_albums(
     Ok(albums)
 }
 
+/// Compilations and other artists' albums the artist is featured on, without
+/// the ones already in their own discography.
+#[tauri::command]
+pub async fn get_artist_appears_on(
+    state: State<'_, AppState>,
+    artist_id: String,
+) -> Result<Vec<Album>, AppError> {
+    let mut albums = state.tidal_client.get_artist_appears_on(&artist_id).await?;
+    for album in &mut albums {
+        album.resolve_artwork();
+    }
+    Ok(albums)
+}
+
+/// Tracks shown in the "Recently Played" row on Home.
+const RECENTLY_PLAYED_LEN: usize = 10;
+
+/// Build the Home sections in the configured order. Each kind is fetched
+/// concurrently; a kind that fails is logged and left out.
 #[tauri::command]
 pub async fn get_recommendations(
     state: State<'_, AppState>,
 ) -> Result<Vec<RecommendationSection>, AppError> {
-    let mut sections = state.tidal_client.get_recommendations().await?;
+    let config = state.tidal_client.config().read().await;
+    let kinds = config.home_sections.clone();
+    drop(config);
+    let home = HomeFetch::new(
+        state
+            .track_stats
+            .lock()
+            .unwrap()
+            .recently_played(RECENTLY_PLAYED_LEN),
+    );
+
+    let fetches = kinds
+        .iter()
+        .map(|&kind| state.tidal_client.get_home_section(kind, &home));
+    let results = futures_util::future::join_all(fetches).await;
+
+    let mut sections = Vec::new();
+    let mut errors = Vec::new();
+    for (kind, result) in kinds.iter().zip(results) {
+        match result {
+            Ok(kind_sections) => sections.extend(kind_sections),
+            Err(e) => {
+                log::warn!("Home section {:?} failed: {}", kind, e);
+                errors.push(e);
+            }
+        }
+    }
+    // Only fail the whole page when there is nothing to show for it
+    if sections.is_empty() && !errors.is_empty() {
+        return Err(errors.remove(0));
+    }
+
     for section in &mut sections {
+        state
+            .retain_allowed_languages(&mut section.tracks, &section.title)
+            .await;
+    }
+
+    let hide_explicit = state.hide_explicit().await;
+    let stats = state.track_stats.lock().unwrap();
+    for section in &mut sections {
+        if hide_explicit {
+            section.tracks.retain(|t| !t.explicit);
+        }
+        state.retain_playable(&mut section.tracks);
+        stats.down_rank(&mut section.tracks);
         for track in &mut section.tracks {
             track.resolve_artwork();
         }
@@ -55,14 +273,84 @@ pub async fn get_recommendations(
     Ok(sections)
 }
 
+#[tauri::command]
+pub async fn get_home_sections_config(
+    state: State<'_, AppState>,
+) -> Result<Vec<HomeSectionKind>, AppError> {
+    let config = state.tidal_client.config().read().await;
+    Ok(config.home_sections.clone())
+}
+
+/// Set which sections appear on Home and in what order. Duplicates are dropped.
+#[tauri::command]
+pub async fn set_home_sections_config(
+    state: State<'_, AppState>,
+    sections: Vec<HomeSectionKind>,
+) -> Result<(), AppError> {
+    let mut config = state.tidal_client.config().write().await;
+    config.set_home_sections(sections);
+    config.save()?;
+    Ok(())
+}
+
+/// Pick new seeds for the "Because you like" rows; they take effect on the
+/// next `get_recommendations`.
+#[tauri::command]
+pub async fn refresh_discovery() -> Result<(), AppError> {
+    // A corrupt file is replaced, as refreshing would pick new seeds anyway
+    let mut seeds = DiscoverySeeds::load().unwrap_or_else(|e| {
+        log::warn!("Failed to load discovery seeds: {}", e);
+        DiscoverySeeds::default()
+    });
+    seeds.expire();
+    seeds.save()
+}
+
 #[tauri::command]
 pub async fn get_similar_tracks(
     state: State<'_, AppState>,
     track_id: String,
 ) -> Result<Vec<Track>, AppError> {
     let mut tracks = state.tidal_client.get_similar_tracks(&track_id).await?;
+    if state.hide_explicit().await {
+        tracks.retain(|t| !t.explicit);
+    }
+    state.retain_playable(&mut tracks);
+    state
+        .retain_allowed_languages(&mut tracks, "similar tracks")
+        .await;
+    // Radio: tracks the user habitually skips go to the back
+    state.track_stats.lock().unwrap().down_rank(&mut tracks);
     for track in &mut tracks {
         track.resolve_artwork();
     }
     Ok(tracks)
 }
+
+/// A track with its segment markers, for tick marks on the seek bar.
+#[tauri::command]
+pub async fn get_track_details(
+    state: State<'_, AppState>,
+    track_id: String,
+) -> Result<TrackDetails, AppError> {
+    let mut details = state.tidal_client.get_track_details(&track_id).await?;
+    details.track.resolve_artwork();
+    Ok(details)
+}
+
+#[tauri::command]
+pub async fn get_video(state: State<'_, AppState>, video_id: String) -> Result<Video, AppError> {
+    let mut video = state.tidal_client.get_video(&video_id).await?;
+    video.resolve_artwork();
+    Ok(video)
+}
+
+/// Another user's public name and avatar, e.g. a playlist owner. Both are
+/// None when the profile is private or the user doesn't exist.
+#[tauri::command]
+pub async fn get_user_public_profile(
+    state: State<'_, AppState>,
+    user_id: String,
+) -> Result<PublicProfile, AppError> {
+    state.tidal_client.get_user_public_profile(&user_id).await
+}
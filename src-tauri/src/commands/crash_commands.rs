@@ -0,0 +1,32 @@
+use crate::crash::{self, CrashReport};
+use crate::error::AppError;
+use tauri::State;
+
+use crate::AppState;
+
+/// Return the most recent crash from a previous session, unless it has
+/// already been acknowledged, so the UI can offer to show the report.
+#[tauri::command]
+pub async fn get_last_crash(state: State<'_, AppState>) -> Result<Option<CrashReport>, AppError> {
+    let report = match crash::last_crash() {
+        Some(report) => report,
+        None => return Ok(None),
+    };
+
+    let config = state.tidal_client.config().read().await;
+    let acknowledged = config
+        .acknowledged_crash_at
+        .is_some_and(|at| report.timestamp <= at);
+    Ok(if acknowledged { None } else { Some(report) })
+}
+
+/// Mark the current last crash as seen so the prompt only shows once per crash.
+#[tauri::command]
+pub async fn acknowledge_crash(state: State<'_, AppState>) -> Result<(), AppError> {
+    if let Some(report) = crash::last_crash() {
+        let mut config = state.tidal_client.config().write().await;
+        config.acknowledged_crash_at = Some(report.timestamp);
+        config.save()?;
+    }
+    Ok(())
+}
@@ -0,0 +1,64 @@
+use crate::commands::auth_commands::complete_auth_callback;
+use crate::error::AppError;
+use crate::events::{AuthStatePayload, AUTH_STATE_CHANGED};
+use tauri::{Emitter, Manager, State};
+
+use crate::AppState;
+
+/// The code from a `tauritidal://auth/callback?code=...` link.
+fn auth_callback_code(url: &url::Url) -> Option<String> {
+    if url.host_str() != Some("auth") && url.path() != "/auth/callback" {
+        return None;
+    }
+    url.query_pairs()
+        .find(|(key, _)| key == "code")
+        .map(|(_, value)| value.into_owned())
+}
+
+/// Route links the app was opened with. Auth callbacks finish login here so it
+/// completes even while the window is still loading; other links are held
+/// until the frontend collects them with `get_pending_deep_link`.
+pub fn handle_deep_links(app: &tauri::AppHandle, urls: Vec<url::Url>) {
+    for url in urls {
+        if let Some(code) = auth_callback_code(&url) {
+            let app = app.clone();
+            tauri::async_runtime::spawn(async move {
+                let state = app.state::<AppState>();
+                match complete_auth_callback(&state, &code).await {
+                    Ok(status) => {
+                        let _ = app.emit(
+                            AUTH_STATE_CHANGED,
+                            AuthStatePayload {
+                                authenticated: status.authenticated,
+                                user_id: status.user_id,
+                                reauth_required: status.reauth_required,
+                            },
+                        );
+                    }
+                    Err(e) => log::error!("Auth callback from deep link failed: {}", e),
+                }
+            });
+            continue;
+        }
+
+        // Once the frontend has collected the buffer it receives links itself
+        let state = app.state::<AppState>();
+        let mut pending = state.pending_deep_links.lock().unwrap();
+        if let Some(pending) = pending.as_mut() {
+            log::info!("Holding deep link until the frontend is ready: {}", url);
+            pending.push(url.to_string());
+        }
+    }
+}
+
+/// Links received before the frontend was listening. Called once on mount;
+/// later links are delivered through the deep-link plugin's own event.
+#[tauri::command]
+pub async fn get_pending_deep_link(state: State<'_, AppState>) -> Result<Vec<String>, AppError> {
+    Ok(state
+        .pending_deep_links
+        .lock()
+        .unwrap()
+        .take()
+        .unwrap_or_default())
+}
@@ -0,0 +1,84 @@
+use crate::audio::player::OutputInfo;
+use crate::audio::waveform::WaveformCache;
+use crate::error::AppError;
+use crate::image_cache::ImageCache;
+use crate::metadata_cache::MetadataCache;
+use crate::resource_usage::{self, DiskUsage};
+use serde::Serialize;
+use tauri::State;
+
+use crate::AppState;
+
+/// Counters that show how often fallback paths are hit in practice.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Diagnostics {
+    /// Search tracks shown with an unknown artist this session
+    pub unresolved_artist_tracks: u64,
+    /// The current output path; None when nothing is playing
+    pub output: Option<OutputInfo>,
+}
+
+#[tauri::command]
+pub async fn get_diagnostics(state: State<'_, AppState>) -> Result<Diagnostics, AppError> {
+    Ok(Diagnostics {
+        unresolved_artist_tracks: state.tidal_client.unresolved_artist_count(),
+        output: state.audio_player.read().await.output_info(),
+    })
+}
+
+/// Where memory and disk go: audio buffers, caches and the queue.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceUsage {
+    /// Downloaded audio of the playing track
+    pub stream_buffer_bytes: u64,
+    /// Downloaded audio of the preloaded next track
+    pub preload_buffer_bytes: u64,
+    pub image_cache: DiskUsage,
+    /// Cached album and playlist listings (the API response cache)
+    pub metadata_cache: DiskUsage,
+    pub waveform_cache: DiskUsage,
+    /// Artwork palettes held in memory
+    pub artwork_palettes: usize,
+    /// Other users' profiles held in memory
+    pub cached_profiles: usize,
+    pub queue_length: usize,
+    /// None where it can't be read cheaply
+    pub process_rss_bytes: Option<u64>,
+}
+
+#[tauri::command]
+pub async fn get_resource_usage(state: State<'_, AppState>) -> Result<ResourceUsage, AppError> {
+    let stream_buffer_bytes = state.audio_player.read().await.stream_buffer_bytes();
+    let preload_buffer_bytes = state
+        .preloaded_track
+        .lock()
+        .await
+        .as_ref()
+        .map_or(0, |p| p.source.buffered_bytes());
+    let queue_length = state.playback_queue.read().await.len();
+
+    // Walking the cache directories is blocking file IO
+    let (image_cache, metadata_cache, waveform_cache) = tokio::task::spawn_blocking(|| {
+        (
+            ImageCache::usage(),
+            MetadataCache::usage(),
+            WaveformCache::usage(),
+        )
+    })
+    .await
+    .map_err(|e| AppError::Config(format!("Cache usage task failed: {}", e)))?;
+
+    Ok(ResourceUsage {
+        stream_buffer_bytes,
+        preload_buffer_bytes,
+        image_cache,
+        metadata_cache,
+        waveform_cache,
+        artwork_palettes: state.artwork_palettes.lock().unwrap().len(),
+        cached_profiles: state.tidal_client.cached_public_profile_count(),
+        queue_length,
+        process_rss_bytes: resource_usage::process_rss(),
+    })
+}
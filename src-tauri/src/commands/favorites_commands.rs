@@ -1,6 +1,8 @@
 use crate::api::models::FavoritesPage;
 use crate::error::AppError;
-use tauri::State;
+use crate::events::{FavoritesChangedPayload, FAVORITES_CHANGED};
+use crate::release_radar::{self, NewRelease};
+use tauri::{Emitter, State};
 
 use crate::AppState;
 
@@ -10,17 +12,77 @@ pub async fn get_favorites(
     cursor: Option<String>,
 ) -> Result<FavoritesPage, AppError> {
     let mut page = state.tidal_client.get_favorites(cursor.as_deref()).await?;
+    state.note_favorites(&page.tracks);
+    if page.total.is_none() {
+        page.total = state
+            .known_favorite_count()
+            .and_then(|count| u32::try_from(count).ok());
+    }
     for track in &mut page.tracks {
         track.resolve_artwork();
     }
     Ok(page)
 }
 
+/// Number of favorite tracks, e.g. for "1,284 liked songs". Uses the
+/// collection total when the API reports one, otherwise counts the favorite
+/// ids, which are fetched once.
+#[tauri::command]
+pub async fn get_favorites_count(state: State<'_, AppState>) -> Result<u32, AppError> {
+    if let Some(total) = state.tidal_client.get_favorites_count().await? {
+        return Ok(total);
+    }
+    let count = state.favorite_count().await?;
+    Ok(u32::try_from(count).unwrap_or(u32::MAX))
+}
+
+/// Whether a track is a favorite, answered from memory once the favorite ids
+/// are loaded.
+#[tauri::command]
+pub async fn is_favorite(state: State<'_, AppState>, track_id: String) -> Result<bool, AppError> {
+    state.is_favorite(&track_id).await
+}
+
+/// Add or remove a favorite and tell every view. Does nothing when the
+/// loaded favorite ids already have the track in the requested state; it
+/// doesn't wait for them to load, the API call decides.
 #[tauri::command]
 pub async fn toggle_favorite(
     state: State<'_, AppState>,
+    app: tauri::AppHandle,
     track_id: String,
     add: bool,
 ) -> Result<(), AppError> {
-    state.tidal_client.toggle_favorite(&track_id, add).await
+    if state.known_favorite(&track_id) == Some(add) {
+        log::debug!("Track {} already has favorite={}", track_id, add);
+        return Ok(());
+    }
+
+    state.tidal_client.toggle_favorite(&track_id, add).await?;
+    state.set_favorite(&track_id, add);
+    let _ = app.emit(
+        FAVORITES_CHANGED,
+        FavoritesChangedPayload {
+            track_id,
+            favorite: add,
+        },
+    );
+    Ok(())
+}
+
+/// Check followed artists for new releases now instead of waiting for the
+/// daily run. Returns only the releases this check found.
+#[tauri::command]
+pub async fn check_new_releases(app: tauri::AppHandle) -> Result<Vec<NewRelease>, AppError> {
+    release_radar::check(&app).await
+}
+
+/// Every release the radar has found, most recently found first.
+#[tauri::command]
+pub async fn get_release_radar(state: State<'_, AppState>) -> Result<Vec<NewRelease>, AppError> {
+    let mut releases = state.release_radar.read().await.releases.clone();
+    for release in &mut releases {
+        release.album.refresh_is_new();
+    }
+    Ok(releases)
 }
@@ -0,0 +1,71 @@
+use crate::audio::gain::{GainOffsetKind, GainOffsets};
+use crate::error::AppError;
+use tauri::State;
+
+use crate::AppState;
+
+/// Re-apply the effective gain if the current track is affected by a change.
+async fn reapply_current_gain(state: &State<'_, AppState>) {
+    let current = state.current_track.read().await.clone();
+    if let Some(track) = current {
+        state.apply_gain_for(&track).await;
+    }
+}
+
+/// Set a manual gain offset (−12 to +6 dB) for a track or album.
+/// Returns the clamped value that was stored.
+#[tauri::command]
+pub async fn set_gain_offset(
+    state: State<'_, AppState>,
+    kind: GainOffsetKind,
+    id: String,
+    db: f32,
+) -> Result<f32, AppError> {
+    let mut offsets = state.gain_offsets.write().await;
+    let stored = offsets.set(kind, &id, db);
+    offsets.save()?;
+    drop(offsets);
+
+    reapply_current_gain(&state).await;
+    Ok(stored)
+}
+
+#[tauri::command]
+pub async fn get_gain_offset(
+    state: State<'_, AppState>,
+    kind: GainOffsetKind,
+    id: String,
+) -> Result<f32, AppError> {
+    Ok(state.gain_offsets.read().await.get(kind, &id))
+}
+
+#[tauri::command]
+pub async fn clear_gain_offsets(state: State<'_, AppState>) -> Result<(), AppError> {
+    let mut offsets = state.gain_offsets.write().await;
+    *offsets = GainOffsets::default();
+    offsets.save()?;
+    drop(offsets);
+
+    reapply_current_gain(&state).await;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn export_gain_offsets(state: State<'_, AppState>) -> Result<GainOffsets, AppError> {
+    Ok(state.gain_offsets.read().await.clone())
+}
+
+/// Replace all offsets with an imported map (e.g. from another machine).
+#[tauri::command]
+pub async fn import_gain_offsets(
+    state: State<'_, AppState>,
+    offsets: GainOffsets,
+) -> Result<(), AppError> {
+    let mut current = state.gain_offsets.write().await;
+    current.import(offsets);
+    current.save()?;
+    drop(current);
+
+    reapply_current_gain(&state).await;
+    Ok(())
+}
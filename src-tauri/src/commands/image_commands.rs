@@ -1,36 +1,313 @@
 use crate::error::AppError;
+use crate::image_cache::{CachePolicy, ImageCache, ImageMeta};
+use crate::image_prefetch::PrefetchQueue;
+use crate::palette::{self, ArtworkPalette};
 use crate::AppState;
 use base64::Engine;
+use chrono::Utc;
+use std::sync::Arc;
 use tauri::State;
 
-/// Proxy an image URL through the backend to avoid CDN referer restrictions.
-/// Returns a data URI (e.g. "data:image/jpeg;base64,...").
-#[tauri::command]
-pub async fn proxy_image(state: State<'_, AppState>, url: String) -> Result<String, AppError> {
-    let response = reqwest::Client::new()
-        .get(&url)
+/// Palettes kept in memory; the cache is emptied when it grows past this.
+const MAX_CACHED_PALETTES: usize = 256;
+
+/// Artwork prefetch downloads running at once.
+const PREFETCH_CONCURRENCY: usize = 4;
+
+/// Download an image, returning its content type and bytes.
+///
+/// Served from the disk cache while the entry is within its `max-age`; older
+/// entries are revalidated with `If-None-Match`/`If-Modified-Since`. If the
+/// revalidation fails for any reason other than the image being gone, the
+/// stale copy is served instead. 404 and 410 map to `NotFound` so the UI can
+/// show a placeholder rather than retrying.
+async fn fetch_image(url: &str) -> Result<(String, Vec<u8>), AppError> {
+    let owned = url.to_string();
+    let mut cached = cache_io(move || ImageCache::load(&owned)).await?;
+    if let Some((meta, bytes)) = &cached {
+        if meta.is_fresh() {
+            return Ok((meta.content_type.clone(), bytes.clone()));
+        }
+    }
+
+    let mut request = reqwest::Client::new()
+        .get(url)
         .header("Accept", "image/jpeg,image/jpg,image/png,image/*")
-        .header("User-Agent", "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36")
-        .send()
-        .await?;
+        .header("User-Agent", "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36");
+    if let Some((meta, _)) = &cached {
+        if let Some(etag) = &meta.etag {
+            request = request.header("If-None-Match", etag);
+        }
+        if let Some(last_modified) = &meta.last_modified {
+            request = request.header("If-Modified-Since", last_modified);
+        }
+    }
 
-    if !response.status().is_success() {
-        return Err(AppError::Http(
+    let response = match request.send().await {
+        Ok(response) => response,
+        Err(e) => return serve_stale(url, cached, AppError::Http(e)),
+    };
+    let status = response.status();
+    let header = |name: &str| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string)
+    };
+    let policy = CachePolicy::from_header(header("cache-control").as_deref());
+
+    if status == reqwest::StatusCode::NOT_MODIFIED {
+        if let Some((mut meta, bytes)) = cached.take() {
+            meta.fetched_at = Utc::now();
+            meta.max_age_secs = policy.max_age_secs;
+            if let Some(etag) = header("etag") {
+                meta.etag = Some(etag);
+            }
+            let content_type = meta.content_type.clone();
+            if let Err(e) = cache_io(move || ImageCache::touch(&meta)).await? {
+                log::warn!("Failed to update image cache for {}: {}", url, e);
+            }
+            return Ok((content_type, bytes));
+        }
+    }
+
+    if status == reqwest::StatusCode::NOT_FOUND || status == reqwest::StatusCode::GONE {
+        let owned = url.to_string();
+        cache_io(move || ImageCache::invalidate(&owned)).await?;
+        return Err(AppError::NotFound(format!("Image {}", url)));
+    }
+
+    if !status.is_success() {
+        let error = AppError::Http(
             response
                 .error_for_status()
                 .expect_err("status was not success"),
-        ));
+        );
+        return serve_stale(url, cached, error);
     }
 
-    let content_type = response
-        .headers()
-        .get("content-type")
-        .and_then(|v| v.to_str().ok())
-        .unwrap_or("image/jpeg")
-        .to_string();
+    let meta = ImageMeta {
+        url: url.to_string(),
+        content_type: header("content-type").unwrap_or_else(|| "image/jpeg".to_string()),
+        etag: header("etag"),
+        last_modified: header("last-modified"),
+        fetched_at: Utc::now(),
+        max_age_secs: policy.max_age_secs,
+    };
 
-    let bytes = response.bytes().await?;
+    let bytes = match response.bytes().await {
+        Ok(bytes) => bytes.to_vec(),
+        Err(e) => return serve_stale(url, cached, AppError::Http(e)),
+    };
+    let content_type = meta.content_type.clone();
+    if !policy.store {
+        return Ok((content_type, bytes));
+    }
+    let (stored, bytes) = cache_io(move || (ImageCache::store(&meta, &bytes), bytes)).await?;
+    if let Err(e) = stored {
+        log::warn!("Failed to cache image {}: {}", url, e);
+    }
+    Ok((content_type, bytes))
+}
+
+/// Run image cache file IO on the blocking pool.
+async fn cache_io<T: Send + 'static>(
+    io: impl FnOnce() -> T + Send + 'static,
+) -> Result<T, AppError> {
+    tokio::task::spawn_blocking(io)
+        .await
+        .map_err(|e| AppError::Config(format!("Image cache task failed: {}", e)))
+}
+
+/// Fall back to an expired cache entry when revalidating it failed.
+fn serve_stale(
+    url: &str,
+    cached: Option<(ImageMeta, Vec<u8>)>,
+    error: AppError,
+) -> Result<(String, Vec<u8>), AppError> {
+    match cached {
+        Some((meta, bytes)) => {
+            log::warn!("Serving stale image for {}: {}", url, error);
+            Ok((meta.content_type, bytes))
+        }
+        None => Err(error),
+    }
+}
+
+/// Proxy an image URL through the backend to avoid CDN referer restrictions.
+/// Returns a data URI (e.g. "data:image/jpeg;base64,...").
+#[tauri::command]
+pub async fn proxy_image(state: State<'_, AppState>, url: String) -> Result<String, AppError> {
+    let (content_type, bytes) = fetch_image(&url).await?;
 
     let b64 = base64::engine::general_purpose::STANDARD.encode(&bytes);
     Ok(format!("data:{};base64,{}", content_type, b64))
 }
+
+/// Dominant colors of an artwork image plus a readable text color, for tinting
+/// the player. Results are cached by URL.
+#[tauri::command]
+pub async fn get_artwork_palette(
+    state: State<'_, AppState>,
+    url: String,
+) -> Result<ArtworkPalette, AppError> {
+    if let Some(cached) = state.artwork_palettes.lock().unwrap().get(&url) {
+        return Ok(cached.clone());
+    }
+
+    let (_, bytes) = fetch_image(&url).await?;
+    let palette = tokio::task::spawn_blocking(move || palette::extract_palette(&bytes))
+        .await
+        .map_err(|e| AppError::Decode(format!("Palette task failed: {}", e)))??;
+
+    let mut cache = state.artwork_palettes.lock().unwrap();
+    if cache.len() >= MAX_CACHED_PALETTES {
+        cache.clear();
+    }
+    cache.insert(url, palette.clone());
+    Ok(palette)
+}
+
+/// Download artwork into the image cache ahead of display. Higher `priority`
+/// goes first and cancels pending lower-priority entries, so the visible
+/// range can be sent high and the next page low. Fresh cached URLs are
+/// skipped. Returns how many URLs were queued.
+#[tauri::command]
+pub async fn prefetch_artwork(
+    state: State<'_, AppState>,
+    urls: Vec<String>,
+    priority: u8,
+) -> Result<usize, AppError> {
+    let uncached: Vec<String> = cache_io(move || {
+        urls.into_iter()
+            .filter(|url| !ImageCache::is_fresh(url))
+            .collect()
+    })
+    .await?;
+    Ok(state.image_prefetch.push_batch(uncached, priority))
+}
+
+/// Start the tasks that work through the artwork prefetch queue.
+pub fn spawn_prefetch_workers(queue: Arc<PrefetchQueue>) {
+    for _ in 0..PREFETCH_CONCURRENCY {
+        let queue = Arc::clone(&queue);
+        tauri::async_runtime::spawn(async move {
+            loop {
+                let url = queue.next().await;
+                if let Err(e) = fetch_image(&url).await {
+                    log::debug!("Artwork prefetch failed for {}: {}", url, e);
+                }
+                queue.finish(&url);
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::temp_dirs;
+    use wiremock::matchers::{header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    const ART: &[u8] = b"jpeg bytes";
+
+    /// Serve `ART` at `/art.jpg` with an ETag, expiring right away so every
+    /// later fetch revalidates.
+    async fn mount_art(server: &MockServer, cache_control: &str) -> String {
+        Mock::given(method("GET"))
+            .and(path("/art.jpg"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("content-type", "image/jpeg")
+                    .insert_header("etag", "\"v1\"")
+                    .insert_header("cache-control", cache_control)
+                    .set_body_bytes(ART),
+            )
+            .up_to_n_times(1)
+            .mount(server)
+            .await;
+        format!("{}/art.jpg", server.uri())
+    }
+
+    async fn requests(server: &MockServer) -> Vec<wiremock::Request> {
+        server.received_requests().await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn a_fresh_entry_is_served_without_a_request() {
+        let _dirs = temp_dirs();
+        let server = MockServer::start().await;
+        let url = mount_art(&server, "max-age=3600").await;
+
+        assert_eq!(fetch_image(&url).await.unwrap().1, ART);
+        assert_eq!(fetch_image(&url).await.unwrap().1, ART);
+        assert_eq!(requests(&server).await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn a_stale_entry_is_revalidated_with_its_etag() {
+        let _dirs = temp_dirs();
+        let server = MockServer::start().await;
+        let url = mount_art(&server, "no-cache").await;
+        fetch_image(&url).await.unwrap();
+
+        Mock::given(method("GET"))
+            .and(path("/art.jpg"))
+            .and(header("if-none-match", "\"v1\""))
+            .respond_with(ResponseTemplate::new(304).insert_header("cache-control", "max-age=3600"))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let (content_type, bytes) = fetch_image(&url).await.unwrap();
+        assert_eq!(content_type, "image/jpeg");
+        assert_eq!(bytes, ART);
+
+        // The 304 refreshed the entry, so this one is served from disk
+        fetch_image(&url).await.unwrap();
+        assert_eq!(requests(&server).await.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn a_failed_revalidation_serves_the_stale_copy() {
+        let _dirs = temp_dirs();
+        let server = MockServer::start().await;
+        let url = mount_art(&server, "no-cache").await;
+        fetch_image(&url).await.unwrap();
+
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(503))
+            .mount(&server)
+            .await;
+        assert_eq!(fetch_image(&url).await.unwrap().1, ART);
+    }
+
+    #[tokio::test]
+    async fn a_gone_image_is_not_found_and_dropped_from_the_cache() {
+        let _dirs = temp_dirs();
+        let server = MockServer::start().await;
+        let url = mount_art(&server, "no-cache").await;
+        fetch_image(&url).await.unwrap();
+
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&server)
+            .await;
+        assert!(matches!(
+            fetch_image(&url).await,
+            Err(AppError::NotFound(_))
+        ));
+        assert!(ImageCache::load(&url).is_none());
+    }
+
+    #[tokio::test]
+    async fn no_store_is_not_cached() {
+        let _dirs = temp_dirs();
+        let server = MockServer::start().await;
+        let url = mount_art(&server, "no-store").await;
+
+        assert_eq!(fetch_image(&url).await.unwrap().1, ART);
+        assert!(ImageCache::load(&url).is_none());
+    }
+}
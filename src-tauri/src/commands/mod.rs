@@ -1,8 +1,15 @@
+pub mod app_data_commands;
 pub mod auth_commands;
 pub mod browse_commands;
+pub mod crash_commands;
+pub mod deep_link_commands;
+pub mod diagnostics_commands;
 pub mod favorites_commands;
+pub mod gain_commands;
 pub mod image_commands;
 pub mod playback_commands;
 pub mod playlist_commands;
 pub mod queue_commands;
+pub mod remote_commands;
 pub mod search_commands;
+pub mod stats_commands;
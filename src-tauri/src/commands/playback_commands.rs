@@ -1,8 +1,24 @@
-use crate::audio::player::AudioPlayer;
+use crate::api::models::parse_language;
+use crate::audio::crossfeed::CrossfeedSettings;
+use crate::audio::interruption::{InterruptionMode, InterruptionSettings};
+use crate::audio::player::{AudioPlayer, OpenedTrack, OutputInfo};
+use crate::audio::preloader::{PreloadStatus, PreloadedTrack};
+use crate::audio::queue::{
+    InterruptTrack, LazySource, PersistedQueueState, QueueSource, QueueSourceKind, RepeatMode,
+};
 use crate::audio::stream_source::HttpStreamSource;
+use crate::audio::waveform::{self, Waveform, WaveformCache};
 use crate::error::AppError;
-use crate::events::{PlaybackState, StateChangedPayload, TrackChangedPayload};
+use crate::events::{
+    AutoresumePendingPayload, FavoritesLoadProgressPayload, PlaybackState, SettingsChangedPayload,
+    StateChangedPayload, StreamQuality, TrackArtwork, TrackChangeReason, TrackChangedPayload,
+    VolumeChangedPayload, AUTORESUME_PENDING, FAVORITES_LOAD_PROGRESS, SETTINGS_CHANGED,
+    VOLUME_CHANGED,
+};
+use rand::Rng;
 use serde::Serialize;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
 use tauri::{Emitter, State};
 
 use crate::AppState;
@@ -14,39 +30,109 @@ pub struct PlayerPrefs {
     pub muted: bool,
 }
 
+/// `reason` defaults to `user`; the frontend passes `restore` when resuming
+/// the track from a saved session.
 #[tauri::command]
 pub async fn play_track(
     state: State<'_, AppState>,
     app: tauri::AppHandle,
     track_id: String,
+    reason: Option<TrackChangeReason>,
 ) -> Result<(), AppError> {
     log::info!("[play_track] track_id={}", track_id);
     let mut track = state.tidal_client.get_track(&track_id).await?;
     track.resolve_artwork();
-    {
-        let mut pl = state.preloaded_track.lock().await;
-        *pl = None;
-    }
-    play_track_internal(&state, &app, &track).await
+    state.discard_preloaded().await;
+    state
+        .playback_queue
+        .write()
+        .await
+        .sync_now_playing(&track.id);
+    let reason = reason.unwrap_or(TrackChangeReason::User);
+    play_track_internal(&state, &app, &track, reason).await
+}
+
+/// Play a track right away without touching the queue. When it ends the queue
+/// picks up again at its current entry if `resume_queue_after` is set;
+/// otherwise playback stops.
+#[tauri::command]
+pub async fn play_track_now(
+    state: State<'_, AppState>,
+    app: tauri::AppHandle,
+    track_id: String,
+    resume_queue_after: bool,
+) -> Result<(), AppError> {
+    log::info!(
+        "[play_track_now] track_id={} resume_queue_after={}",
+        track_id,
+        resume_queue_after
+    );
+    let mut track = state.tidal_client.get_track(&track_id).await?;
+    track.resolve_artwork();
+    state.discard_preloaded().await;
+    // Not queue playback, even if the same track happens to be the current entry
+    state.playback_queue.write().await.mark_off_queue();
+    play_track_internal(&state, &app, &track, TrackChangeReason::Interrupt).await?;
+
+    *state.interrupt.lock().unwrap() = Some(InterruptTrack {
+        track_id: track.id,
+        resume_queue_after,
+    });
+    Ok(())
 }
 
 /// Play a list of tracks, setting them as the queue with a starting index.
+/// `source` describes where the tracks came from (album, playlist, ...).
+///
+/// Deprecated: the frontend should send ids to `play_track_ids` rather than
+/// serialized tracks.
 #[tauri::command]
 pub async fn play_tracks(
     state: State<'_, AppState>,
     app: tauri::AppHandle,
+    tracks: Vec<crate::api::models::Track>,
+    start_index: usize,
+    source: Option<QueueSource>,
+) -> Result<(), AppError> {
+    log::warn!("[play_tracks] Deprecated, use play_track_ids instead");
+    play_queue(&state, &app, tracks, start_index, source).await
+}
+
+/// Play tracks by id, setting them as the queue with a starting index. The
+/// tracks are fetched in batches; ids that can't be fetched are dropped and
+/// `start_index` still points at the same track when it survives.
+#[tauri::command]
+pub async fn play_track_ids(
+    state: State<'_, AppState>,
+    app: tauri::AppHandle,
+    track_ids: Vec<String>,
+    start_index: usize,
+    source: Option<QueueSource>,
+) -> Result<(), AppError> {
+    let tracks = state.tidal_client.get_tracks(&track_ids).await?;
+    let fetched: std::collections::HashSet<&str> = tracks.iter().map(|t| t.id.as_str()).collect();
+    let start_index = track_ids
+        .iter()
+        .take(start_index)
+        .filter(|id| fetched.contains(id.as_str()))
+        .count();
+    play_queue(&state, &app, tracks, start_index, source).await
+}
+
+/// Replace the queue with `tracks` and start playing at `start_index`.
+async fn play_queue(
+    state: &State<'_, AppState>,
+    app: &tauri::AppHandle,
     mut tracks: Vec<crate::api::models::Track>,
     start_index: usize,
+    source: Option<QueueSource>,
 ) -> Result<(), AppError> {
     log::info!(
         "[play_tracks] {} tracks, start_index={}",
         tracks.len(),
         start_index
     );
-    {
-        let mut pl = state.preloaded_track.lock().await;
-        *pl = None;
-    }
+    state.discard_preloaded().await;
 
     for track in &mut tracks {
         track.resolve_artwork();
@@ -54,6 +140,8 @@ pub async fn play_tracks(
 
     let mut queue = state.playback_queue.write().await;
     queue.set_tracks(tracks, start_index);
+    state.defer_habitual_skips(&mut queue);
+    queue.set_source(source);
     let track = queue.current_track().cloned();
     drop(queue);
 
@@ -63,7 +151,7 @@ pub async fn play_tracks(
             track.artist_name,
             track.title
         );
-        play_track_internal(&state, &app, &track).await?;
+        play_track_internal(state, app, &track, TrackChangeReason::User).await?;
         let _ = app.emit(crate::events::PLAYBACK_QUEUE_CHANGED, ());
     } else {
         log::warn!("[play_tracks] No track at index {}", start_index);
@@ -71,15 +159,205 @@ pub async fn play_tracks(
     Ok(())
 }
 
+/// Replace the queue with an album's tracks and start playing at `start_index`.
+#[tauri::command]
+pub async fn play_album(
+    state: State<'_, AppState>,
+    app: tauri::AppHandle,
+    album_id: String,
+    start_index: Option<usize>,
+) -> Result<(), AppError> {
+    let album = state.tidal_client.get_album(&album_id).await?;
+    let tracks = state.tidal_client.get_album_tracks(&album_id).await?;
+    let source = QueueSource {
+        kind: QueueSourceKind::Album,
+        id: Some(album.id),
+        name: album.title,
+    };
+    play_queue(&state, &app, tracks, start_index.unwrap_or(0), Some(source)).await
+}
+
+/// Replace the queue with a playlist's tracks and start playing at `start_index`.
+#[tauri::command]
+pub async fn play_playlist(
+    state: State<'_, AppState>,
+    app: tauri::AppHandle,
+    playlist_id: String,
+    start_index: Option<usize>,
+) -> Result<(), AppError> {
+    let playlist = state.tidal_client.get_playlist(&playlist_id).await?;
+    let tracks = state.tidal_client.get_playlist_tracks(&playlist_id).await?;
+    let source = QueueSource {
+        kind: QueueSourceKind::Playlist,
+        id: Some(playlist.id),
+        name: playlist.name,
+    };
+    play_queue(&state, &app, tracks, start_index.unwrap_or(0), Some(source)).await
+}
+
+/// Play the user's favorites as one queue, newest first, starting from
+/// `start_track_id` or the newest favorite. Only the pages up to the start
+/// track are fetched now; later pages are appended as playback nears the end
+/// of the queue. Returns the number of tracks queued so far.
+#[tauri::command]
+pub async fn play_favorites(
+    state: State<'_, AppState>,
+    app: tauri::AppHandle,
+    start_track_id: Option<String>,
+) -> Result<usize, AppError> {
+    let mut tracks = Vec::new();
+    let mut cursor: Option<String> = None;
+    let start_index = loop {
+        let page = state.tidal_client.get_favorites(cursor.as_deref()).await?;
+        let offset = tracks.len();
+        tracks.extend(page.tracks);
+        let found = match &start_track_id {
+            Some(id) => tracks[offset..]
+                .iter()
+                .position(|t| &t.id == id)
+                .map(|i| offset + i),
+            None => Some(0),
+        };
+        cursor = page.next_cursor.filter(|_| page.has_more);
+        // Give up looking for the start track once past the shuffle limit
+        if found.is_some() || cursor.is_none() || tracks.len() >= FAVORITES_SHUFFLE_LIMIT {
+            break found;
+        }
+    };
+
+    if tracks.is_empty() {
+        return Err(AppError::NotFound("No favorite tracks".into()));
+    }
+    let start_index = start_index.unwrap_or_else(|| {
+        log::warn!(
+            "[play_favorites] {:?} not found in {} favorites, starting from the newest",
+            start_track_id,
+            tracks.len()
+        );
+        0
+    });
+    log::info!(
+        "[play_favorites] {} tracks loaded, more: {}",
+        tracks.len(),
+        cursor.is_some()
+    );
+
+    state.discard_preloaded().await;
+    for track in &mut tracks {
+        track.resolve_artwork();
+    }
+
+    let count = tracks.len();
+    let mut queue = state.playback_queue.write().await;
+    queue.set_tracks(tracks, start_index);
+    state.defer_habitual_skips(&mut queue);
+    queue.set_source(Some(QueueSource {
+        kind: QueueSourceKind::Favorites,
+        id: None,
+        name: "Favorites".into(),
+    }));
+    queue.set_lazy_source(cursor.map(|cursor| LazySource::Favorites { cursor }));
+    let track = queue.current_track().cloned();
+    drop(queue);
+
+    if let Some(track) = track {
+        play_track_internal(&state, &app, &track, TrackChangeReason::User).await?;
+        let _ = app.emit(crate::events::PLAYBACK_QUEUE_CHANGED, ());
+    }
+    Ok(count)
+}
+
+/// Upper bound on favorites gathered by `play_favorites_shuffled`.
+const FAVORITES_SHUFFLE_LIMIT: usize = 1000;
+
+/// Page through the user's favorites, then shuffle them into the queue and
+/// start playing, without the frontend downloading the collection first.
+/// If a later page fails, whatever was gathered so far is still played.
+/// Returns the number of tracks queued.
+#[tauri::command]
+pub async fn play_favorites_shuffled(
+    state: State<'_, AppState>,
+    app: tauri::AppHandle,
+) -> Result<usize, AppError> {
+    let mut tracks = Vec::new();
+    let mut cursor: Option<String> = None;
+    let mut pages = 0;
+    loop {
+        let page = match state.tidal_client.get_favorites(cursor.as_deref()).await {
+            Ok(page) => page,
+            Err(e) if tracks.is_empty() => return Err(e),
+            Err(e) => {
+                log::warn!(
+                    "[play_favorites_shuffled] Stopping after {} tracks: {}",
+                    tracks.len(),
+                    e
+                );
+                break;
+            }
+        };
+        pages += 1;
+        tracks.extend(page.tracks);
+        let _ = app.emit(
+            FAVORITES_LOAD_PROGRESS,
+            FavoritesLoadProgressPayload {
+                loaded: tracks.len().min(FAVORITES_SHUFFLE_LIMIT),
+                pages,
+            },
+        );
+
+        match page.next_cursor {
+            Some(next) if page.has_more && tracks.len() < FAVORITES_SHUFFLE_LIMIT => {
+                cursor = Some(next)
+            }
+            _ => break,
+        }
+    }
+    tracks.truncate(FAVORITES_SHUFFLE_LIMIT);
+
+    if tracks.is_empty() {
+        return Err(AppError::NotFound("No favorite tracks".into()));
+    }
+    log::info!("[play_favorites_shuffled] {} tracks", tracks.len());
+
+    state.discard_preloaded().await;
+    for track in &mut tracks {
+        track.resolve_artwork();
+    }
+
+    // Start from a random track; shuffling keeps it at the front and leaves the
+    // favorites order intact for unshuffle.
+    let count = tracks.len();
+    let start_index = rand::thread_rng().gen_range(0..count);
+    let mut queue = state.playback_queue.write().await;
+    queue.set_tracks(tracks, start_index);
+    queue.set_source(Some(QueueSource {
+        kind: QueueSourceKind::Favorites,
+        id: None,
+        name: "Favorites".into(),
+    }));
+    queue.shuffle(None);
+    state.defer_habitual_skips(&mut queue);
+    let track = queue.current_track().cloned();
+    drop(queue);
+
+    if let Some(track) = track {
+        play_track_internal(&state, &app, &track, TrackChangeReason::User).await?;
+        let _ = app.emit(crate::events::PLAYBACK_QUEUE_CHANGED, ());
+    }
+    Ok(count)
+}
+
 #[tauri::command]
 pub async fn pause(state: State<'_, AppState>, app: tauri::AppHandle) -> Result<(), AppError> {
     let mut player = state.audio_player.write().await;
     player.pause();
+    state.paused_for_focus.store(false, Ordering::SeqCst);
 
     let _ = app.emit(
         crate::events::PLAYBACK_STATE_CHANGED,
         StateChangedPayload {
             state: PlaybackState::Paused,
+            playback_epoch: player.playback_epoch(),
         },
     );
 
@@ -102,12 +380,14 @@ pub async fn pause(state: State<'_, AppState>, app: tauri::AppHandle) -> Result<
 #[tauri::command]
 pub async fn resume(state: State<'_, AppState>, app: tauri::AppHandle) -> Result<(), AppError> {
     let mut player = state.audio_player.write().await;
-    player.resume();
+    player.resume()?;
+    state.paused_for_focus.store(false, Ordering::SeqCst);
 
     let _ = app.emit(
         crate::events::PLAYBACK_STATE_CHANGED,
         StateChangedPayload {
             state: PlaybackState::Playing,
+            playback_epoch: player.playback_epoch(),
         },
     );
 
@@ -131,6 +411,9 @@ pub async fn resume(state: State<'_, AppState>, app: tauri::AppHandle) -> Result
 pub async fn stop(state: State<'_, AppState>, app: tauri::AppHandle) -> Result<(), AppError> {
     let mut player = state.audio_player.write().await;
     player.stop();
+    let playback_epoch = player.playback_epoch();
+    drop(player);
+    state.discard_preloaded().await;
 
     *state.current_track.write().await = None;
 
@@ -138,6 +421,7 @@ pub async fn stop(state: State<'_, AppState>, app: tauri::AppHandle) -> Result<(
         crate::events::PLAYBACK_STATE_CHANGED,
         StateChangedPayload {
             state: PlaybackState::Stopped,
+            playback_epoch,
         },
     );
 
@@ -156,7 +440,11 @@ pub async fn seek(
     let player = state.audio_player.read().await;
     player.seek(position);
     let duration = player.duration_seconds();
+    let playback_epoch = player.playback_epoch();
     drop(player);
+    // Seeking back out of the preload window and forward again should re-check
+    // the upcoming track; an existing matching preload is reused.
+    state.preload_trigger.lock().unwrap().reset();
 
     // Emit progress immediately so the UI reflects the seek position
     let fraction = if duration > 0.0 {
@@ -170,25 +458,259 @@ pub async fn seek(
             position,
             duration,
             position_fraction: fraction,
+            session_epoch: state.session_epoch(),
+            playback_epoch,
         },
     );
 
     Ok(())
 }
 
+/// Seek to a fraction (0..1) of the track's duration, e.g. for number-key seeking.
+/// Fails, leaving the position alone, while the duration isn't known yet.
 #[tauri::command]
-pub async fn set_volume(state: State<'_, AppState>, volume: f32) -> Result<(), AppError> {
-    let player = state.audio_player.read().await;
-    player.set_volume(volume);
+pub async fn seek_fraction(
+    state: State<'_, AppState>,
+    app: tauri::AppHandle,
+    fraction: f64,
+) -> Result<(), AppError> {
+    let duration = state.audio_player.read().await.duration_seconds();
+    if duration <= 0.0 {
+        return Err(AppError::Audio(
+            "Can't seek by fraction before the duration is known".into(),
+        ));
+    }
+    seek(state, app, fraction.clamp(0.0, 1.0) * duration).await
+}
+
+/// Set the (unmuted) volume. Persisted to the config after a short debounce.
+#[tauri::command]
+pub async fn set_volume(
+    state: State<'_, AppState>,
+    app: tauri::AppHandle,
+    volume: f32,
+) -> Result<(), AppError> {
+    let volume = volume.clamp(0.0, 1.0);
+    let mut player = state.audio_player.write().await;
+    player.set_muted(false, volume);
+    let device = player.active_output_name();
+    if let Some(device) = device.as_deref() {
+        player.remember_device_volume(device, volume);
+    }
+    drop(player);
+
+    let mut config = state.tidal_client.config().write().await;
+    config.set_volume_for(device.as_deref(), volume);
+    config.muted = false;
+    drop(config);
+
+    volume_changed(&state, &app).await;
+    state.schedule_config_save();
+    Ok(())
+}
+
+/// Mute or unmute, keeping the stored volume for unmuting.
+#[tauri::command]
+pub async fn set_muted(
+    state: State<'_, AppState>,
+    app: tauri::AppHandle,
+    muted: bool,
+) -> Result<(), AppError> {
+    let mut config = state.tidal_client.config().write().await;
+    config.muted = muted;
+    let volume = config.volume;
+    drop(config);
+    state.audio_player.write().await.set_muted(muted, volume);
+
+    volume_changed(&state, &app).await;
+    state.schedule_config_save();
     Ok(())
 }
 
+/// Tell every window about the current volume prefs so sliders stay in sync.
+async fn volume_changed(state: &State<'_, AppState>, app: &tauri::AppHandle) {
+    let config = state.tidal_client.config().read().await;
+    let _ = app.emit(
+        VOLUME_CHANGED,
+        VolumeChangedPayload {
+            volume: config.volume,
+            muted: config.muted,
+        },
+    );
+}
+
 #[tauri::command]
 pub async fn get_volume(state: State<'_, AppState>) -> Result<f32, AppError> {
     let player = state.audio_player.read().await;
     Ok(player.volume())
 }
 
+/// Everything the transport controls need in one call.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlaybackStatus {
+    pub state: PlaybackState,
+    pub position: f64,
+    pub duration: f64,
+    pub track_id: Option<String>,
+    pub repeat_mode: RepeatMode,
+    pub shuffled: bool,
+    /// Queue index of the playing track; None when it isn't from the queue
+    pub now_playing_queue_index: Option<usize>,
+    /// Codec the playing track was fetched as, which may predate a quality change
+    pub codec: Option<String>,
+    pub quality: Option<String>,
+}
+
+#[tauri::command]
+pub async fn get_playback_status(state: State<'_, AppState>) -> Result<PlaybackStatus, AppError> {
+    let player = state.audio_player.read().await;
+    let playback_state = if player.is_playing() {
+        PlaybackState::Playing
+    } else {
+        PlaybackState::Paused
+    };
+    let position = player.position_seconds();
+    let duration = player.duration_seconds();
+    drop(player);
+
+    let track_id = state
+        .current_track
+        .read()
+        .await
+        .as_ref()
+        .map(|t| t.id.clone());
+    let stream = track_id
+        .as_ref()
+        .and(state.playing_stream.lock().unwrap().clone())
+        .unwrap_or_default();
+    let queue = state.playback_queue.read().await;
+    Ok(PlaybackStatus {
+        state: if track_id.is_none() {
+            PlaybackState::Stopped
+        } else {
+            playback_state
+        },
+        position,
+        duration,
+        now_playing_queue_index: track_id.as_ref().and(queue.now_playing_index()),
+        track_id,
+        repeat_mode: queue.repeat_mode(),
+        shuffled: queue.is_shuffled(),
+        codec: stream.codec,
+        quality: stream.quality,
+    })
+}
+
+/// What the playing track's stream was delivered as: announced and detected
+/// codec, container, quality tier, bit depth and sample rate. None while nothing is playing.
+#[tauri::command]
+pub async fn get_stream_info(
+    state: State<'_, AppState>,
+) -> Result<Option<StreamQuality>, AppError> {
+    if state.current_track.read().await.is_none() {
+        return Ok(None);
+    }
+    Ok(state.playing_stream.lock().unwrap().clone())
+}
+
+/// Download progress of the preloaded next track, or None when nothing is preloaded.
+#[tauri::command]
+pub async fn get_preload_status(
+    state: State<'_, AppState>,
+) -> Result<Option<PreloadStatus>, AppError> {
+    let preloaded = state.preloaded_track.lock().await;
+    Ok(preloaded.as_ref().map(|p| p.status()))
+}
+
+/// What the seek bar shows while hovering over a point of the track.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SeekPreview {
+    /// Seconds into the track
+    pub position: f64,
+    /// Already downloaded, so seeking there starts without waiting
+    pub buffered: bool,
+    /// Waveform peak there (0..=1), when the track's waveform is cached
+    pub peak: Option<f32>,
+}
+
+/// Preview seeking to `fraction` (0..1) of the playing track. Buffering is
+/// estimated from the share of bytes downloaded.
+#[tauri::command]
+pub async fn get_seek_preview(
+    state: State<'_, AppState>,
+    fraction: f64,
+) -> Result<SeekPreview, AppError> {
+    let fraction = fraction.clamp(0.0, 1.0);
+    let player = state.audio_player.read().await;
+    let position = fraction * player.duration_seconds();
+    let progress = player.stream_progress();
+    drop(player);
+
+    let track_id = state
+        .current_track
+        .read()
+        .await
+        .as_ref()
+        .map(|t| t.id.clone());
+    let peak = track_id
+        .and_then(|id| WaveformCache::load(&id))
+        .and_then(|waveform| waveform.peak_at(fraction));
+
+    Ok(SeekPreview {
+        position,
+        buffered: progress.is_none_or(|p| p.covers(fraction)),
+        peak,
+    })
+}
+
+/// Approximate waveform for the seek bar. Opt-in: an uncached call downloads
+/// and decodes the first `PREFETCH_SECS` of the track.
+#[tauri::command]
+pub async fn get_waveform(
+    state: State<'_, AppState>,
+    track_id: String,
+) -> Result<Waveform, AppError> {
+    if let Some(cached) = WaveformCache::load(&track_id) {
+        return Ok(cached);
+    }
+
+    let client = &state.tidal_client;
+    let (track, manifest) = tokio::join!(
+        client.get_track(&track_id),
+        client.get_track_manifest(&track_id)
+    );
+    let (track, manifest) = (track?, manifest?);
+
+    let max_bytes = waveform::prefetch_bytes(&manifest.codec);
+    let response = client
+        .get_stream_url(&manifest.uri, Some(max_bytes))
+        .await?;
+    // Stop at the budget even if the server ignored the range
+    use futures_util::StreamExt;
+    let mut bytes = Vec::new();
+    let mut stream = response.bytes_stream();
+    while let Some(chunk) = stream.next().await {
+        bytes.extend_from_slice(&chunk?);
+        if bytes.len() as u64 >= max_bytes {
+            break;
+        }
+    }
+
+    let codec = manifest.codec;
+    let waveform = tokio::task::spawn_blocking(move || {
+        waveform::compute_waveform(&track_id, &bytes, &codec, track.duration)
+    })
+    .await
+    .map_err(|e| AppError::Decode(format!("Waveform task failed: {}", e)))??;
+
+    if let Err(e) = WaveformCache::store(&waveform) {
+        log::warn!("Failed to cache waveform for {}: {}", waveform.track_id, e);
+    }
+    Ok(waveform)
+}
+
 #[tauri::command]
 pub async fn get_playback_state(state: State<'_, AppState>) -> Result<String, AppError> {
     let player = state.audio_player.read().await;
@@ -201,30 +723,84 @@ pub async fn get_playback_state(state: State<'_, AppState>) -> Result<String, Ap
 
 #[tauri::command]
 pub async fn next_track(state: State<'_, AppState>, app: tauri::AppHandle) -> Result<(), AppError> {
+    let hide_explicit = state.hide_explicit().await;
+    let interrupt = state.take_interrupt();
+    let needs_refill = state.playback_queue.read().await.needs_refill(0);
+    if needs_refill && state.refill_queue().await {
+        let _ = app.emit(crate::events::PLAYBACK_QUEUE_CHANGED, ());
+    }
     let mut queue = state.playback_queue.write().await;
-    let next = queue.next_track().cloned();
+    let next = match interrupt {
+        // Skipping an interrupt track returns to the queue entry it interrupted
+        Some(i) if i.resume_queue_after => queue.resume_current().cloned(),
+        _ => queue
+            .next_track_skipping(|t| hide_explicit && t.explicit)
+            .cloned(),
+    };
     drop(queue);
 
     match next {
-        Some(track) => play_track_internal(&state, &app, &track).await,
+        Some(track) => play_track_internal(&state, &app, &track, TrackChangeReason::User).await,
         None => {
-            let mut player = state.audio_player.write().await;
-            player.stop();
-            drop(player);
-            *state.current_track.write().await = None;
-            let _ = app.emit(
-                crate::events::PLAYBACK_STATE_CHANGED,
-                StateChangedPayload {
-                    state: PlaybackState::Stopped,
-                },
-            );
-            #[cfg(target_os = "macos")]
-            crate::macos::now_playing::clear_now_playing();
+            stop_at_queue_end(&state, &app).await;
+            Ok(())
+        }
+    }
+}
+
+/// Play the track `offset` places ahead in the queue, as `offset` presses of
+/// next would, but fetching only the landing track's manifest. Explicit
+/// tracks hidden by the filter are passed over from there.
+#[tauri::command]
+pub async fn skip_to_queue_offset(
+    state: State<'_, AppState>,
+    app: tauri::AppHandle,
+    offset: usize,
+) -> Result<(), AppError> {
+    let hide_explicit = state.hide_explicit().await;
+    // An interrupt track isn't part of the queue, so offsets count from the queue
+    let _ = state.take_interrupt();
+    let needs_refill = state
+        .playback_queue
+        .read()
+        .await
+        .needs_refill(offset.saturating_sub(1));
+    if needs_refill && state.refill_queue().await {
+        let _ = app.emit(crate::events::PLAYBACK_QUEUE_CHANGED, ());
+    }
+    let mut queue = state.playback_queue.write().await;
+    let mut next = queue.advance_by(offset).cloned();
+    if next.as_ref().is_some_and(|t| hide_explicit && t.explicit) {
+        next = queue.next_track_skipping(|t| t.explicit).cloned();
+    }
+    drop(queue);
+
+    match next {
+        Some(track) => play_track_internal(&state, &app, &track, TrackChangeReason::User).await,
+        None => {
+            stop_at_queue_end(&state, &app).await;
             Ok(())
         }
     }
 }
 
+async fn stop_at_queue_end(state: &AppState, app: &tauri::AppHandle) {
+    let mut player = state.audio_player.write().await;
+    player.stop();
+    let playback_epoch = player.playback_epoch();
+    drop(player);
+    *state.current_track.write().await = None;
+    let _ = app.emit(
+        crate::events::PLAYBACK_STATE_CHANGED,
+        StateChangedPayload {
+            state: PlaybackState::Stopped,
+            playback_epoch,
+        },
+    );
+    #[cfg(target_os = "macos")]
+    crate::macos::now_playing::clear_now_playing();
+}
+
 #[tauri::command]
 pub async fn previous_track(
     state: State<'_, AppState>,
@@ -237,7 +813,7 @@ pub async fn previous_track(
     if position > 15.0 {
         let current = state.current_track.read().await.clone();
         if let Some(track) = current {
-            play_track_internal(&state, &app, &track).await?;
+            play_track_internal(&state, &app, &track, TrackChangeReason::User).await?;
         }
     } else {
         let mut queue = state.playback_queue.write().await;
@@ -245,7 +821,7 @@ pub async fn previous_track(
         drop(queue);
 
         if let Some(track) = prev {
-            play_track_internal(&state, &app, &track).await?;
+            play_track_internal(&state, &app, &track, TrackChangeReason::User).await?;
         }
     }
     Ok(())
@@ -260,24 +836,474 @@ pub async fn get_player_prefs(state: State<'_, AppState>) -> Result<PlayerPrefs,
     })
 }
 
+/// Kept for older frontends. Volume is now persisted by `set_volume` and
+/// `set_muted`, so this does nothing.
 #[tauri::command]
-pub async fn save_player_prefs(
+pub async fn save_player_prefs(volume: f32, muted: bool) -> Result<(), AppError> {
+    log::debug!(
+        "[save_player_prefs] ignored (volume={}, muted={})",
+        volume,
+        muted
+    );
+    Ok(())
+}
+
+/// Enable/disable headphone crossfeed. Applied to the current track within
+/// the buffered audio and persisted for future launches.
+#[tauri::command]
+pub async fn set_crossfeed(
     state: State<'_, AppState>,
-    volume: f32,
-    muted: bool,
+    enabled: bool,
+    strength: f32,
 ) -> Result<(), AppError> {
+    let settings = CrossfeedSettings {
+        enabled,
+        strength: strength.clamp(0.0, 1.0),
+    };
+    state.audio_player.read().await.set_crossfeed(settings);
+
     let mut config = state.tidal_client.config().write().await;
-    config.volume = volume;
-    config.muted = muted;
+    config.crossfeed = settings;
     config.save()?;
     Ok(())
 }
 
+#[tauri::command]
+pub async fn get_crossfeed(state: State<'_, AppState>) -> Result<CrossfeedSettings, AppError> {
+    Ok(state.audio_player.read().await.crossfeed())
+}
+
+/// What to do while another app plays audio: keep playing, duck by
+/// `duck_db`, or pause (resuming afterwards if `resume_after`). Only macOS
+/// reports other apps' audio.
+#[tauri::command]
+pub async fn set_interruption_settings(
+    state: State<'_, AppState>,
+    mode: InterruptionMode,
+    duck_db: f32,
+    resume_after: bool,
+) -> Result<(), AppError> {
+    let settings = InterruptionSettings {
+        mode,
+        duck_db: duck_db.clamp(-60.0, 0.0),
+        resume_after,
+    };
+    if mode != InterruptionMode::Duck {
+        state.audio_player.read().await.set_duck(1.0);
+    }
+
+    let mut config = state.tidal_client.config().write().await;
+    config.interruption = settings;
+    config.save()?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_interruption_settings(
+    state: State<'_, AppState>,
+) -> Result<InterruptionSettings, AppError> {
+    Ok(state.tidal_client.config().read().await.interruption)
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LaunchSettings {
+    pub start_minimized: bool,
+    pub autoresume_on_launch: bool,
+}
+
+#[tauri::command]
+pub async fn get_launch_settings(state: State<'_, AppState>) -> Result<LaunchSettings, AppError> {
+    let config = state.tidal_client.config().read().await;
+    Ok(LaunchSettings {
+        start_minimized: config.start_minimized,
+        autoresume_on_launch: config.autoresume_on_launch,
+    })
+}
+
+/// Minimize the main window on the next launch.
+#[tauri::command]
+pub async fn set_start_minimized(
+    state: State<'_, AppState>,
+    enabled: bool,
+) -> Result<(), AppError> {
+    let mut config = state.tidal_client.config().write().await;
+    config.start_minimized = enabled;
+    config.save()?;
+    Ok(())
+}
+
+/// Play the saved queue on the next launch. See `autoresume_on_launch`.
+#[tauri::command]
+pub async fn set_autoresume_on_launch(
+    state: State<'_, AppState>,
+    enabled: bool,
+) -> Result<(), AppError> {
+    let mut config = state.tidal_client.config().write().await;
+    config.autoresume_on_launch = enabled;
+    config.save()?;
+    Ok(())
+}
+
+/// Stop a pending launch autoresume. Does nothing once it has started playing.
+#[tauri::command]
+pub async fn cancel_autoresume(state: State<'_, AppState>) -> Result<(), AppError> {
+    state.autoresume_cancelled.store(true, Ordering::Relaxed);
+    Ok(())
+}
+
+/// How long the frontend has to cancel a launch autoresume.
+const AUTORESUME_GRACE: std::time::Duration = std::time::Duration::from_secs(2);
+
+/// Restore the saved queue and play its current track from where it was left.
+/// Runs once on launch, after the token refresh, when `autoresume_on_launch`
+/// is set. Nothing plays if the last session crashed (so a track that crashes
+/// the player can't do it on every launch), if only a preview would play, if
+/// `cancel_autoresume` is called during the grace window, or if the user
+/// started something else meanwhile.
+pub async fn autoresume_on_launch(app: &tauri::AppHandle) {
+    use tauri::Manager;
+    let state = app.state::<AppState>();
+
+    let config = state.tidal_client.config().read().await;
+    let signed_in = config.user_id.is_some();
+    let acknowledged_crash_at = config.acknowledged_crash_at;
+    drop(config);
+
+    if !signed_in {
+        log::info!("[autoresume] Not signed in, skipping");
+        return;
+    }
+    if let Some(report) = crate::crash::last_crash() {
+        if acknowledged_crash_at.is_none_or(|at| report.timestamp > at) {
+            log::warn!("[autoresume] Previous session crashed, skipping");
+            return;
+        }
+    }
+
+    let mut persisted = match PersistedQueueState::load() {
+        Ok(Some(persisted)) => persisted,
+        Ok(None) => return,
+        Err(e) => {
+            log::warn!("[autoresume] Failed to load saved queue: {}", e);
+            return;
+        }
+    };
+    for track in &mut persisted.tracks {
+        track.resolve_artwork();
+    }
+    let position = persisted.position;
+    let Some(track) = persisted
+        .current_index
+        .and_then(|i| persisted.tracks.get(i))
+        .cloned()
+    else {
+        return;
+    };
+
+    // A refresh that failed leaves a token that only plays previews (or none)
+    let manifest = match state.tidal_client.get_track_manifest(&track.id).await {
+        Ok(manifest) if manifest.preview => {
+            log::info!("[autoresume] Only a preview of {} would play", track.id);
+            return;
+        }
+        Ok(manifest) => manifest,
+        Err(e) => {
+            log::warn!("[autoresume] Can't play {}: {}", track.id, e);
+            return;
+        }
+    };
+
+    state.autoresume_cancelled.store(false, Ordering::Relaxed);
+    let epoch = state.session_epoch();
+    let queue_generation = state.playback_queue.read().await.generation();
+    let _ = app.emit(
+        AUTORESUME_PENDING,
+        AutoresumePendingPayload {
+            track_id: track.id.clone(),
+            grace_ms: AUTORESUME_GRACE.as_millis() as u64,
+        },
+    );
+    tokio::time::sleep(AUTORESUME_GRACE).await;
+
+    if state.autoresume_cancelled.load(Ordering::Relaxed) {
+        log::info!("[autoresume] Cancelled");
+        return;
+    }
+    if state.session_epoch() != epoch {
+        log::info!("[autoresume] Something else started playing, skipping");
+        return;
+    }
+
+    // Only now replace the (empty) session queue, so a skipped autoresume
+    // leaves it as the frontend expects
+    let mut queue = state.playback_queue.write().await;
+    if queue.generation() != queue_generation {
+        log::info!("[autoresume] The queue was filled meanwhile, skipping");
+        return;
+    }
+    queue.restore_from_persisted(persisted);
+    queue.resume_current();
+    drop(queue);
+    let _ = app.emit(crate::events::PLAYBACK_QUEUE_CHANGED, ());
+
+    // Play from the manifest checked above, starting at the saved position
+    // so the beginning of the track is never heard
+    let mut preloaded = PreloadedTrack::new(
+        track.id.clone(),
+        StreamQuality::for_manifest(&manifest),
+        manifest.preview,
+        track.duration,
+        manifest.uri,
+        Arc::clone(&state.tidal_client),
+    );
+    preloaded.start_at = Some(position);
+    drop(state.preloaded_track.lock().await.replace(preloaded));
+
+    log::info!("[autoresume] Resuming {} at {:.1}s", track.id, position);
+    if let Err(e) = play_track_internal(&state, app, &track, TrackChangeReason::Restore).await {
+        log::warn!("[autoresume] Failed to start {}: {}", track.id, e);
+    }
+}
+
+/// Opt in to playing a matching version of tracks that aren't available in
+/// the user's country.
+#[tauri::command]
+pub async fn set_substitute_unavailable(
+    state: State<'_, AppState>,
+    enabled: bool,
+) -> Result<(), AppError> {
+    let mut config = state.tidal_client.config().write().await;
+    config.substitute_unavailable = enabled;
+    config.save()?;
+    Ok(())
+}
+
+/// Hide explicit tracks from search, recommendations, album listings and
+/// similar tracks, and skip them when the queue advances.
+#[tauri::command]
+pub async fn set_hide_explicit(state: State<'_, AppState>, enabled: bool) -> Result<(), AppError> {
+    let mut config = state.tidal_client.config().write().await;
+    config.hide_explicit = enabled;
+    config.save()?;
+    Ok(())
+}
+
+/// Values accepted by `set_audio_quality`.
+const AUDIO_QUALITIES: &[&str] = &["LOW", "HIGH", "LOSSLESS", "HI_RES", "HI_RES_LOSSLESS"];
+
+/// Change the streaming quality. The playing stream is left alone; the
+/// preloaded next track was fetched at the old quality, so it is dropped and
+/// the preload re-armed to fetch it again.
+#[tauri::command]
+pub async fn set_audio_quality(
+    state: State<'_, AppState>,
+    app: tauri::AppHandle,
+    quality: String,
+) -> Result<(), AppError> {
+    if !AUDIO_QUALITIES.contains(&quality.as_str()) {
+        return Err(AppError::Config(format!("Unknown quality: {}", quality)));
+    }
+
+    let mut config = state.tidal_client.config().write().await;
+    if config.audio_quality == quality {
+        return Ok(());
+    }
+    config.audio_quality = quality.clone();
+    config.save()?;
+    drop(config);
+
+    state.tidal_client.reset_adaptive_quality();
+    state.discard_preloaded().await;
+    state.preload_trigger.lock().unwrap().reset();
+
+    let _ = app.emit(
+        SETTINGS_CHANGED,
+        SettingsChangedPayload {
+            audio_quality: quality,
+        },
+    );
+    Ok(())
+}
+
+/// Step streaming quality down for later tracks while downloads are too slow
+/// for it, and back up once they recover. Turning it off returns to the
+/// configured quality.
+#[tauri::command]
+pub async fn set_adaptive_quality(
+    state: State<'_, AppState>,
+    enabled: bool,
+) -> Result<(), AppError> {
+    let mut config = state.tidal_client.config().write().await;
+    config.adaptive_quality = enabled;
+    config.save()?;
+    drop(config);
+
+    state.tidal_client.reset_adaptive_quality();
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_adaptive_quality(state: State<'_, AppState>) -> Result<bool, AppError> {
+    Ok(state.tidal_client.config().read().await.adaptive_quality)
+}
+
+/// Minutes of pause after which the audio device is released; 0 keeps it open.
+#[tauri::command]
+pub async fn set_release_output_after_minutes(
+    state: State<'_, AppState>,
+    minutes: u32,
+) -> Result<(), AppError> {
+    let mut config = state.tidal_client.config().write().await;
+    config.release_output_after_minutes = minutes;
+    config.save()?;
+    Ok(())
+}
+
+/// Seconds before a track ends that the next track is announced; 0 turns the
+/// notice off.
+#[tauri::command]
+pub async fn set_upnext_notice_secs(state: State<'_, AppState>, secs: u32) -> Result<(), AppError> {
+    let mut config = state.tidal_client.config().write().await;
+    config.upnext_notice_secs = secs;
+    config.save()?;
+    Ok(())
+}
+
+/// Milliseconds between progress updates, clamped to 100-1000. Longer
+/// intervals wake the app less often, for low-power mode.
+#[tauri::command]
+pub async fn set_progress_interval_ms(state: State<'_, AppState>, ms: u32) -> Result<(), AppError> {
+    let mut config = state.tidal_client.config().write().await;
+    config.progress_interval_ms = ms.clamp(
+        crate::config::MIN_PROGRESS_INTERVAL_MS,
+        crate::config::MAX_PROGRESS_INTERVAL_MS,
+    );
+    config.save()?;
+    Ok(())
+}
+
+/// Follow the system default output device when it changes during playback.
+#[tauri::command]
+pub async fn set_follow_default_output(
+    state: State<'_, AppState>,
+    enabled: bool,
+) -> Result<(), AppError> {
+    let mut config = state.tidal_client.config().write().await;
+    config.follow_default_output = enabled;
+    config.save()?;
+    Ok(())
+}
+
+/// Whether to play 30-second previews when full tracks aren't available.
+/// Off makes playback fail with `subscription_required` instead.
+#[tauri::command]
+pub async fn set_allow_preview_playback(
+    state: State<'_, AppState>,
+    enabled: bool,
+) -> Result<(), AppError> {
+    let mut config = state.tidal_client.config().write().await;
+    config.allow_preview_playback = enabled;
+    config.save()?;
+    Ok(())
+}
+
+/// Name of the device audio is currently playing on.
+#[tauri::command]
+pub async fn get_output_device(state: State<'_, AppState>) -> Result<Option<String>, AppError> {
+    let player = state.audio_player.read().await;
+    Ok(player.output_device_name().map(String::from))
+}
+
+/// Sample rates and formats along the output path, and whether it is lossless.
+#[tauri::command]
+pub async fn get_output_info(state: State<'_, AppState>) -> Result<Option<OutputInfo>, AppError> {
+    Ok(state.audio_player.read().await.output_info())
+}
+
+/// Play an audio file from disk, bypassing the API. Meant for checking the
+/// audio path without signing in; the queue and now-playing are left alone,
+/// and the queue doesn't advance when it ends. Debug builds only.
+#[cfg(debug_assertions)]
+#[tauri::command]
+pub async fn play_local_file(state: State<'_, AppState>, path: String) -> Result<(), AppError> {
+    let path = std::path::PathBuf::from(path);
+    let codec = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_lowercase());
+    let file = std::fs::File::open(&path)?;
+    log::info!("Playing local file {}", path.display());
+
+    state.discard_preloaded().await;
+    state.playback_queue.write().await.mark_off_queue();
+    *state.current_track.write().await = None;
+
+    // Opening blocks on the probe, like a stream's
+    let player_ref = state.audio_player.clone();
+    player_ref.read().await.set_next_gain_db(0.0);
+    tokio::task::spawn_blocking(move || {
+        let track = OpenedTrack::source(Box::new(file), None, codec.as_deref(), None)?;
+        let rt = tokio::runtime::Handle::current();
+        let mut player = rt.block_on(player_ref.write());
+        player.play(track)
+    })
+    .await
+    .map_err(|e| AppError::Audio(format!("spawn_blocking join error: {}", e)))?
+}
+
+#[tauri::command]
+pub async fn get_hide_explicit(state: State<'_, AppState>) -> Result<bool, AppError> {
+    Ok(state.hide_explicit().await)
+}
+
+/// Limit radio and recommendations to these languages; an empty list allows
+/// all. Codes are normalized to ISO 639-1 where one exists and invalid ones
+/// dropped.
+#[tauri::command]
+pub async fn set_language_allowlist(
+    state: State<'_, AppState>,
+    languages: Vec<String>,
+) -> Result<Vec<String>, AppError> {
+    let mut allowlist: Vec<String> = Vec::with_capacity(languages.len());
+    for lang in languages.iter().filter_map(|l| parse_language(l)) {
+        if !allowlist.contains(&lang) {
+            allowlist.push(lang);
+        }
+    }
+
+    let mut config = state.tidal_client.config().write().await;
+    config.language_allowlist = allowlist.clone();
+    config.save()?;
+    Ok(allowlist)
+}
+
+#[tauri::command]
+pub async fn get_language_allowlist(state: State<'_, AppState>) -> Result<Vec<String>, AppError> {
+    let config = state.tidal_client.config().read().await;
+    Ok(config.language_allowlist.clone())
+}
+
+/// Look for a playable version of a track, e.g. to offer when it's unavailable.
+#[tauri::command]
+pub async fn find_track_alternative(
+    state: State<'_, AppState>,
+    track_id: String,
+) -> Result<Option<crate::api::models::Track>, AppError> {
+    let track = state.tidal_client.get_track(&track_id).await?;
+    let alternative = state.tidal_client.find_track_alternative(&track).await?;
+    Ok(alternative.map(|(mut track, _)| {
+        track.resolve_artwork();
+        track
+    }))
+}
+
 /// Internal helper to start playing a track (used by next/previous/play commands)
 async fn play_track_internal(
     state: &State<'_, AppState>,
     app: &tauri::AppHandle,
     track: &crate::api::models::Track,
+    reason: TrackChangeReason,
 ) -> Result<(), AppError> {
     log::info!(
         "[play_track_internal] Starting: id={} title={} artist={}",
@@ -286,77 +1312,125 @@ async fn play_track_internal(
         track.artist_name
     );
 
+    if track.explicit && state.hide_explicit().await {
+        return Err(AppError::ExplicitFiltered(track.id.clone()));
+    }
+
+    let mut attempt = state.begin_play_attempt();
+
+    // Playing over the queue isn't a verdict on the track being interrupted
+    if reason != TrackChangeReason::Interrupt {
+        state.record_early_skip(&track.id).await;
+    }
+
+    // Any other track replaces an interrupt track; `play_track_now` re-arms the slot
+    state.take_interrupt();
+
     // Check for preloaded track first
     let preloaded = {
         let mut pl = state.preloaded_track.lock().await;
         pl.take()
     };
 
-    let mut playback_codec: Option<String> = None;
+    let playback_stream;
+    let preview_only;
+    // Set when an unavailable track was replaced by an alternative
+    let mut substitute = None;
 
-    if let Some(preloaded) = preloaded.filter(|p| p.track_id == track.id) {
+    // A stale preload is dropped, stopping its download
+    let preloaded = preloaded.filter(|p| p.track_id == track.id);
+
+    if let Some(preloaded) = preloaded {
         log::info!("[play_track_internal] Using preloaded track");
-        playback_codec = preloaded.codec_hint.clone();
-        let codec_hint = preloaded.codec_hint.as_deref();
+        playback_stream = preloaded.stream.clone();
+        preview_only = preloaded.preview;
+        let start_at = preloaded.start_at;
+        state.prepare_gain_for(track).await;
+        // Opened before taking the player, as its first audio may still be downloading
+        let opened = tokio::task::spawn_blocking(move || {
+            OpenedTrack::stream(
+                preloaded.source,
+                preloaded.abort_handle.disarm(),
+                preloaded.codec_hint.as_deref(),
+                preloaded.duration,
+            )
+        })
+        .await
+        .map_err(|e| AppError::Audio(format!("spawn_blocking join error: {}", e)))??;
         let mut player = state.audio_player.write().await;
-        player.play_stream(preloaded.source, preloaded.abort_handle, codec_hint, preloaded.duration)?;
+        if let Some(start) = start_at {
+            player.set_next_start(start);
+        }
+        player.play(opened)?;
     } else {
         // Fetch manifest (contains both URI and codec) and play
         log::info!(
             "[play_track_internal] Fetching manifest for track {}",
             track.id
         );
-        let manifest = state.tidal_client.get_track_manifest(&track.id).await?;
+        // Dropping the fetch when a newer play starts cancels its HTTP request
+        let fetch = state.tidal_client.get_playable_manifest(track);
+        let Some(fetched) = attempt.unless_superseded(fetch).await else {
+            log::info!(
+                "[play_track_internal] Superseded while fetching {}",
+                track.id
+            );
+            return Ok(());
+        };
+        let (playable, manifest) = fetched?;
+        if playable.id != track.id {
+            substitute = Some(playable);
+        }
         log::info!(
             "[play_track_internal] Got manifest: codec={}, uri={}...",
             manifest.codec,
             &manifest.uri[..manifest.uri.len().min(80)]
         );
 
-        playback_codec = Some(manifest.codec.clone());
+        playback_stream = StreamQuality::for_manifest(&manifest);
+        preview_only = manifest.preview;
 
         let (source, writer, abort_handle) = HttpStreamSource::new();
-        let client = state.tidal_client.http_client().clone();
+        let client = Arc::clone(&state.tidal_client);
 
         // Start the download on a background task
         AudioPlayer::start_download(writer, manifest.uri, client);
 
-        // CRITICAL: play_stream blocks the thread while AudioDecoder probes the format.
+        // CRITICAL: opening the track blocks the thread while AudioDecoder probes the format.
         // We must use spawn_blocking so we don't block a tokio worker thread,
         // which would prevent the download task from making progress.
-        log::info!("[play_track_internal] Starting play_stream (via spawn_blocking)...");
+        log::info!("[play_track_internal] Opening the stream (via spawn_blocking)...");
         let player_ref = state.audio_player.clone();
         let codec = manifest.codec.clone();
-        let duration = track.duration;
+        let duration = substitute.as_ref().unwrap_or(track).duration;
+        state
+            .prepare_gain_for(substitute.as_ref().unwrap_or(track))
+            .await;
 
         let result = tokio::task::spawn_blocking(move || {
-            // We need to acquire the write lock inside the blocking task.
-            // Use tokio's Handle to enter the async context for the lock.
+            // Opened before the write lock, so other commands aren't held up
+            // while the first audio downloads. Use tokio's Handle to enter
+            // the async context for the lock.
+            let track = OpenedTrack::stream(source, abort_handle, Some(&codec), duration)?;
             let rt = tokio::runtime::Handle::current();
             let mut player = rt.block_on(player_ref.write());
-            player.play_stream(source, abort_handle, Some(&codec), duration)
+            player.play(track)
         })
         .await
         .map_err(|e| AppError::Audio(format!("spawn_blocking join error: {}", e)))?;
 
         result?;
-        log::info!("[play_track_internal] play_stream succeeded");
-    }
-
-    // Derive a human-friendly quality label from the codec
-    let quality_label = playback_codec.as_deref().map(|c| {
-        match c.to_lowercase().as_str() {
-            "flac" | "flac_hires" => "FLAC",
-            "aaclc" | "mp4a.40.2" | "mp4a" | "aac" => "AAC",
-            "heaacv1" | "mp4a.40.5" => "AAC",
-            "mp3" => "MP3",
-            "eac3_joc" => "Atmos",
-            other => other,
-        }
-        .to_string()
-    });
+        log::info!("[play_track_internal] Playback started");
+    }
+
+    let track = substitute.as_ref().unwrap_or(track);
 
     *state.current_track.write().await = Some(track.clone());
+    let stream = state.settle_stream(app, &track.id, playback_stream).await;
+    state.preload_trigger.lock().unwrap().reset();
+    state.upnext_announced.store(false, Ordering::Relaxed);
+    state.record_track_started(&track.id);
+    let playback_epoch = state.audio_player.read().await.playback_epoch();
 
     let _ = app.emit(
         crate::events::PLAYBACK_TRACK_CHANGED,
@@ -366,9 +1440,12 @@ async fn play_track_internal(
             artist: track.artist_name.clone(),
             album: track.album_name.clone(),
             duration: track.duration,
-            artwork_url: track.artwork_url_sized(640, 640),
-            codec: playback_codec,
-            quality: quality_label,
+            artwork: TrackArtwork::for_track(track),
+            stream,
+            reason,
+            preview_only,
+            session_epoch: state.session_epoch(),
+            playback_epoch,
         },
     );
 
@@ -376,6 +1453,7 @@ async fn play_track_internal(
         crate::events::PLAYBACK_STATE_CHANGED,
         StateChangedPayload {
             state: PlaybackState::Playing,
+            playback_epoch,
         },
     );
 
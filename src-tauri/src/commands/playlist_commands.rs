@@ -1,15 +1,41 @@
-use crate::api::models::{Playlist, Track};
+use crate::api::models::{Playlist, PlaylistScope, Track};
 use crate::error::AppError;
-use tauri::State;
+use crate::events::{PlaylistSaveProgressPayload, PLAYLIST_SAVE_PROGRESS};
+use crate::metadata_cache::{self, CacheKind, Cached, MetadataCache, PlaylistListing};
+use crate::playlist_prefs::{PlaylistPrefs, PlaylistSort};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tauri::{Emitter, State};
 
 use crate::AppState;
 
+/// The user's playlists for `scope` (all by default), pinned ones first.
 #[tauri::command]
-pub async fn get_playlists(state: State<'_, AppState>) -> Result<Vec<Playlist>, AppError> {
-    let mut playlists = state.tidal_client.get_playlists().await?;
+pub async fn get_playlists(
+    state: State<'_, AppState>,
+    scope: Option<PlaylistScope>,
+) -> Result<Vec<Playlist>, AppError> {
+    let scope = scope.unwrap_or_default();
+    let collection = state.tidal_client.get_playlists(scope).await?;
+    let mut playlists = collection.playlists;
+
+    let mut prefs = state.playlist_prefs.write().await;
+    // Forget prefs for playlists deleted elsewhere. Only a complete list of
+    // every playlist shows which ones are gone.
+    if scope == PlaylistScope::All
+        && collection.complete
+        && prefs.prune(playlists.iter().map(|p| p.id.as_str()))
+    {
+        prefs.save()?;
+    }
     for playlist in &mut playlists {
+        playlist.pinned = prefs.get(&playlist.id).pinned;
         playlist.resolve_artwork();
     }
+    drop(prefs);
+
+    playlists.sort_by_key(|p| !p.pinned);
     Ok(playlists)
 }
 
@@ -23,18 +49,64 @@ pub async fn get_playlist(
     Ok(playlist)
 }
 
+/// Tracks of a playlist. With `sorted`, they come in the playlist's stored sort.
 #[tauri::command]
 pub async fn get_playlist_tracks(
     state: State<'_, AppState>,
     playlist_id: String,
+    sorted: Option<bool>,
 ) -> Result<Vec<Track>, AppError> {
-    let mut tracks = state.tidal_client.get_playlist_tracks(&playlist_id).await?;
+    let (mut tracks, added) = state
+        .tidal_client
+        .get_playlist_tracks_with_dates(&playlist_id)
+        .await?;
+    if sorted.unwrap_or(false) {
+        let sort = state.playlist_prefs.read().await.get(&playlist_id).sort;
+        sort.apply(&mut tracks, &added);
+    }
     for track in &mut tracks {
         track.resolve_artwork();
     }
     Ok(tracks)
 }
 
+/// Playlist header and tracks in one call, served from the on-disk cache when
+/// available (`stale` marks data being refreshed in the background).
+#[tauri::command]
+pub async fn get_playlist_listing(
+    state: State<'_, AppState>,
+    app: tauri::AppHandle,
+    playlist_id: String,
+) -> Result<Cached<PlaylistListing>, AppError> {
+    let client = Arc::clone(&state.tidal_client);
+    let id = playlist_id.clone();
+    let mut listing = metadata_cache::cached_or_fetch(
+        &app,
+        CacheKind::Playlist,
+        &playlist_id,
+        move || async move {
+            let (tracks, other_items) = client.get_playlist_items(&id).await?;
+            Ok::<_, AppError>(PlaylistListing {
+                playlist: client.get_playlist(&id).await?,
+                tracks,
+                other_items,
+            })
+        },
+    )
+    .await?;
+
+    if state.hide_explicit().await {
+        listing.data.tracks.retain(|t| !t.explicit);
+    }
+    listing.data.playlist.resolve_artwork();
+    for track in &mut listing.data.tracks {
+        // A cached listing may be from before the release stopped being new
+        track.refresh_is_new();
+        track.resolve_artwork();
+    }
+    Ok(listing)
+}
+
 #[tauri::command]
 pub async fn create_playlist(
     state: State<'_, AppState>,
@@ -56,7 +128,9 @@ pub async fn add_to_playlist(
     state
         .tidal_client
         .add_to_playlist(&playlist_id, &track_id)
-        .await
+        .await?;
+    MetadataCache::invalidate(CacheKind::Playlist, &playlist_id);
+    Ok(())
 }
 
 #[tauri::command]
@@ -68,7 +142,9 @@ pub async fn remove_from_playlist(
     state
         .tidal_client
         .remove_from_playlist(&playlist_id, &track_id)
-        .await
+        .await?;
+    MetadataCache::invalidate(CacheKind::Playlist, &playlist_id);
+    Ok(())
 }
 
 #[tauri::command]
@@ -76,5 +152,131 @@ pub async fn delete_playlist(
     state: State<'_, AppState>,
     playlist_id: String,
 ) -> Result<(), AppError> {
-    state.tidal_client.delete_playlist(&playlist_id).await
+    state.tidal_client.delete_playlist(&playlist_id).await?;
+    MetadataCache::invalidate(CacheKind::Playlist, &playlist_id);
+
+    let mut prefs = state.playlist_prefs.write().await;
+    if prefs.remove(&playlist_id) {
+        prefs.save()?;
+    }
+    Ok(())
+}
+
+/// Pin a playlist and/or set its track sort. Stored locally only.
+#[tauri::command]
+pub async fn set_playlist_prefs(
+    state: State<'_, AppState>,
+    playlist_id: String,
+    pinned: bool,
+    sort: PlaylistSort,
+) -> Result<(), AppError> {
+    let mut prefs = state.playlist_prefs.write().await;
+    prefs.set(&playlist_id, PlaylistPrefs { pinned, sort });
+    prefs.save()?;
+    Ok(())
+}
+
+/// Stored prefs keyed by playlist id. Playlists without an entry use the defaults.
+#[tauri::command]
+pub async fn get_playlist_prefs(
+    state: State<'_, AppState>,
+) -> Result<HashMap<String, PlaylistPrefs>, AppError> {
+    Ok(state.playlist_prefs.read().await.playlists.clone())
+}
+
+#[tauri::command]
+pub async fn set_playlist_collaborative(
+    state: State<'_, AppState>,
+    playlist_id: String,
+    enabled: bool,
+) -> Result<(), AppError> {
+    state
+        .tidal_client
+        .set_playlist_collaborative(&playlist_id, enabled)
+        .await?;
+    MetadataCache::invalidate(CacheKind::Playlist, &playlist_id);
+    Ok(())
+}
+
+/// Invite link for adding contributors to a playlist.
+#[tauri::command]
+pub async fn get_playlist_invite_link(
+    state: State<'_, AppState>,
+    playlist_id: String,
+) -> Result<String, AppError> {
+    state
+        .tidal_client
+        .get_playlist_invite_link(&playlist_id)
+        .await
+}
+
+/// Tracks added per request when saving the queue as a playlist.
+const PLAYLIST_ADD_CHUNK: usize = 20;
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SavedQueuePlaylist {
+    pub playlist: Playlist,
+    pub added: usize,
+    pub total: usize,
+    /// Set when adding tracks failed part-way. The partial playlist is kept.
+    pub error: Option<AppError>,
+}
+
+/// Create a playlist from the current queue, adding tracks in chunks and
+/// emitting a progress event after each one.
+#[tauri::command]
+pub async fn save_queue_as_playlist(
+    state: State<'_, AppState>,
+    app: tauri::AppHandle,
+    name: String,
+    description: Option<String>,
+) -> Result<SavedQueuePlaylist, AppError> {
+    let track_ids = state.playback_queue.read().await.track_ids();
+    if track_ids.is_empty() {
+        return Err(AppError::NotFound("Queue is empty".into()));
+    }
+
+    let mut playlist = state
+        .tidal_client
+        .create_playlist(&name, description.as_deref())
+        .await?;
+    playlist.resolve_artwork();
+
+    let total = track_ids.len();
+    let mut added = 0;
+    let mut error = None;
+    for chunk in track_ids.chunks(PLAYLIST_ADD_CHUNK) {
+        if let Err(e) = state
+            .tidal_client
+            .add_tracks_to_playlist(&playlist.id, chunk)
+            .await
+        {
+            log::error!(
+                "Saving queue as playlist {} failed after {}/{} tracks: {}",
+                playlist.id,
+                added,
+                total,
+                e
+            );
+            error = Some(e);
+            break;
+        }
+        added += chunk.len();
+        let _ = app.emit(
+            PLAYLIST_SAVE_PROGRESS,
+            PlaylistSaveProgressPayload {
+                playlist_id: playlist.id.clone(),
+                added,
+                total,
+            },
+        );
+    }
+
+    Ok(SavedQueuePlaylist {
+        playlist,
+        added,
+        total,
+        error,
+    })
 }
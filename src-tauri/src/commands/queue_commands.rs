@@ -1,36 +1,194 @@
-use crate::audio::queue::{PersistedQueueState, QueueState, RepeatMode};
-use crate::config::AppConfig;
+use crate::api::models::Track;
+use crate::audio::queue::{PersistedQueueState, PlaybackQueue, QueueState, RepeatMode};
 use crate::error::AppError;
-use tauri::State;
+use crate::events::{ModeChangedPayload, PLAYBACK_MODE_CHANGED};
+use serde::Serialize;
+use tauri::{Emitter, State};
 
 use crate::AppState;
 
+/// `offset`/`limit` let the UI window very large queues; omitting both returns
+/// every track as before.
 #[tauri::command]
-pub async fn get_queue(state: State<'_, AppState>) -> Result<QueueState, AppError> {
+pub async fn get_queue(
+    state: State<'_, AppState>,
+    offset: Option<usize>,
+    limit: Option<usize>,
+) -> Result<QueueState, AppError> {
     let queue = state.playback_queue.read().await;
-    let mut qs = queue.state();
+    let mut qs = queue.state_window(offset.unwrap_or(0), limit);
+    drop(queue);
     for track in &mut qs.tracks {
         track.resolve_artwork();
     }
     Ok(qs)
 }
 
+/// The tracks the backend will actually play next, plus the previous track
+/// for the back button state.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpNext {
+    pub tracks: Vec<Track>,
+    pub previous: Option<Track>,
+}
+
+#[tauri::command]
+pub async fn get_up_next(state: State<'_, AppState>, count: usize) -> Result<UpNext, AppError> {
+    let queue = state.playback_queue.read().await;
+    let mut up_next = UpNext {
+        tracks: queue.peek_next_n(count),
+        previous: queue.peek_previous().cloned(),
+    };
+    drop(queue);
+
+    for track in up_next.tracks.iter_mut().chain(up_next.previous.iter_mut()) {
+        track.resolve_artwork();
+    }
+    Ok(up_next)
+}
+
 #[tauri::command]
 pub async fn add_to_queue(state: State<'_, AppState>, track_id: String) -> Result<(), AppError> {
     let mut track = state.tidal_client.get_track(&track_id).await?;
     track.resolve_artwork();
     let mut queue = state.playback_queue.write().await;
     queue.add_track(track);
+    drop(queue);
+    state.note_queue_edit();
     Ok(())
 }
 
+/// Where `queue_tracks` puts the new tracks.
+enum QueuePlacement {
+    End,
+    Next,
+}
+
+/// Add tracks to the queue without touching current playback and notify the
+/// UI once. Returns the number of tracks added.
+async fn queue_tracks(
+    state: &State<'_, AppState>,
+    app: &tauri::AppHandle,
+    mut tracks: Vec<Track>,
+    placement: QueuePlacement,
+) -> usize {
+    if state.hide_explicit().await {
+        tracks.retain(|t| !t.explicit);
+    }
+    for track in &mut tracks {
+        track.resolve_artwork();
+    }
+    let count = tracks.len();
+    if count == 0 {
+        return 0;
+    }
+
+    let mut queue = state.playback_queue.write().await;
+    match placement {
+        QueuePlacement::End => queue.append_tracks(tracks),
+        QueuePlacement::Next => queue.insert_tracks_next(tracks),
+    }
+    drop(queue);
+    state.note_queue_edit();
+    let _ = app.emit(crate::events::PLAYBACK_QUEUE_CHANGED, ());
+    count
+}
+
+/// Append tracks to the end of the queue by id, fetching them in batches.
+/// Returns the number added, which leaves out ids that couldn't be fetched.
+#[tauri::command]
+pub async fn add_track_ids_to_queue(
+    state: State<'_, AppState>,
+    app: tauri::AppHandle,
+    track_ids: Vec<String>,
+) -> Result<usize, AppError> {
+    let tracks = state.tidal_client.get_tracks(&track_ids).await?;
+    Ok(queue_tracks(&state, &app, tracks, QueuePlacement::End).await)
+}
+
+/// Append an album's tracks to the end of the queue.
+#[tauri::command]
+pub async fn append_album_to_queue(
+    state: State<'_, AppState>,
+    app: tauri::AppHandle,
+    album_id: String,
+) -> Result<usize, AppError> {
+    let tracks = state.tidal_client.get_album_tracks(&album_id).await?;
+    Ok(queue_tracks(&state, &app, tracks, QueuePlacement::End).await)
+}
+
+/// Append a playlist's tracks to the end of the queue.
+#[tauri::command]
+pub async fn append_playlist_to_queue(
+    state: State<'_, AppState>,
+    app: tauri::AppHandle,
+    playlist_id: String,
+) -> Result<usize, AppError> {
+    let tracks = state.tidal_client.get_playlist_tracks(&playlist_id).await?;
+    Ok(queue_tracks(&state, &app, tracks, QueuePlacement::End).await)
+}
+
+/// Queue an album's tracks to play right after the current track.
+#[tauri::command]
+pub async fn insert_album_next(
+    state: State<'_, AppState>,
+    app: tauri::AppHandle,
+    album_id: String,
+) -> Result<usize, AppError> {
+    let tracks = state.tidal_client.get_album_tracks(&album_id).await?;
+    Ok(queue_tracks(&state, &app, tracks, QueuePlacement::Next).await)
+}
+
 #[tauri::command]
 pub async fn remove_from_queue(state: State<'_, AppState>, index: usize) -> Result<(), AppError> {
     let mut queue = state.playback_queue.write().await;
     queue.remove_track(index);
+    drop(queue);
+    state.note_queue_edit();
     Ok(())
 }
 
+/// Remove a track by id rather than index, so a queue that shifted since the
+/// UI last rendered doesn't lose the wrong song. `occurrence` picks which copy
+/// of a duplicated track to remove (defaults to the first).
+#[tauri::command]
+pub async fn remove_from_queue_by_id(
+    state: State<'_, AppState>,
+    app: tauri::AppHandle,
+    track_id: String,
+    occurrence: Option<usize>,
+) -> Result<(), AppError> {
+    let mut queue = state.playback_queue.write().await;
+    if !queue.remove_track_by_id(&track_id, occurrence.unwrap_or(0)) {
+        return Err(AppError::NotFound(format!(
+            "Track {} is not in the queue",
+            track_id
+        )));
+    }
+    drop(queue);
+    state.note_queue_edit();
+    let _ = app.emit(crate::events::PLAYBACK_QUEUE_CHANGED, ());
+    Ok(())
+}
+
+/// Remove duplicate tracks from the queue, keeping the current track.
+/// Returns the number of tracks removed.
+#[tauri::command]
+pub async fn dedupe_queue(
+    state: State<'_, AppState>,
+    app: tauri::AppHandle,
+) -> Result<usize, AppError> {
+    let mut queue = state.playback_queue.write().await;
+    let removed = queue.dedupe();
+    drop(queue);
+    if removed > 0 {
+        state.note_queue_edit();
+        let _ = app.emit(crate::events::PLAYBACK_QUEUE_CHANGED, ());
+    }
+    Ok(removed)
+}
+
 #[tauri::command]
 pub async fn reorder_queue(
     state: State<'_, AppState>,
@@ -39,33 +197,120 @@ pub async fn reorder_queue(
 ) -> Result<(), AppError> {
     let mut queue = state.playback_queue.write().await;
     queue.move_track(from, to);
+    drop(queue);
+    state.note_queue_edit();
     Ok(())
 }
 
+/// Move a contiguous multi-selection in one operation. `to` is the index the
+/// first selected track ends up at.
 #[tauri::command]
-pub async fn shuffle_queue(state: State<'_, AppState>) -> Result<(), AppError> {
+pub async fn reorder_queue_range(
+    state: State<'_, AppState>,
+    app: tauri::AppHandle,
+    from_start: usize,
+    from_len: usize,
+    to: usize,
+) -> Result<(), AppError> {
+    let mut queue = state.playback_queue.write().await;
+    queue.move_range(from_start, from_len, to);
+    drop(queue);
+    state.note_queue_edit();
+    let _ = app.emit(crate::events::PLAYBACK_QUEUE_CHANGED, ());
+    Ok(())
+}
+
+/// Tell every window the current repeat/shuffle modes.
+fn emit_mode_changed(app: &tauri::AppHandle, queue: &PlaybackQueue) {
+    let _ = app.emit(
+        PLAYBACK_MODE_CHANGED,
+        ModeChangedPayload {
+            repeat_mode: queue.repeat_mode(),
+            shuffled: queue.is_shuffled(),
+        },
+    );
+}
+
+#[tauri::command]
+pub async fn shuffle_queue(
+    state: State<'_, AppState>,
+    app: tauri::AppHandle,
+) -> Result<(), AppError> {
     let mut queue = state.playback_queue.write().await;
-    queue.shuffle();
+    queue.shuffle(None);
+    // Tracks the user keeps skipping come up last
+    state.defer_habitual_skips(&mut queue);
+    emit_mode_changed(&app, &queue);
+    drop(queue);
+    state.note_queue_edit();
     Ok(())
 }
 
+/// Shuffle again from the original order. Passing the seed saved with a
+/// queue draws the same shuffle again; None draws a new one. Habitual skips
+/// are then moved back using the current listening stats, so the result only
+/// matches the saved order while those are unchanged. Returns the seed used.
+#[tauri::command]
+pub async fn reshuffle(
+    state: State<'_, AppState>,
+    app: tauri::AppHandle,
+    seed: Option<u64>,
+) -> Result<u64, AppError> {
+    let mut queue = state.playback_queue.write().await;
+    let seed = queue.shuffle(seed);
+    log::info!("[reshuffle] seed={}", seed);
+    state.defer_habitual_skips(&mut queue);
+    emit_mode_changed(&app, &queue);
+    drop(queue);
+    state.note_queue_edit();
+    let _ = app.emit(crate::events::PLAYBACK_QUEUE_CHANGED, ());
+    Ok(seed)
+}
+
 #[tauri::command]
-pub async fn unshuffle_queue(state: State<'_, AppState>) -> Result<(), AppError> {
+pub async fn unshuffle_queue(
+    state: State<'_, AppState>,
+    app: tauri::AppHandle,
+) -> Result<(), AppError> {
     let mut queue = state.playback_queue.write().await;
     queue.unshuffle();
+    emit_mode_changed(&app, &queue);
+    drop(queue);
+    state.note_queue_edit();
     Ok(())
 }
 
 #[tauri::command]
-pub async fn toggle_repeat(state: State<'_, AppState>) -> Result<RepeatMode, AppError> {
+pub async fn toggle_repeat(
+    state: State<'_, AppState>,
+    app: tauri::AppHandle,
+) -> Result<RepeatMode, AppError> {
     let mut queue = state.playback_queue.write().await;
-    Ok(queue.toggle_repeat())
+    let mode = queue.toggle_repeat();
+    emit_mode_changed(&app, &queue);
+    Ok(mode)
+}
+
+/// Set the repeat mode directly instead of cycling through it.
+#[tauri::command]
+pub async fn set_repeat_mode(
+    state: State<'_, AppState>,
+    app: tauri::AppHandle,
+    mode: RepeatMode,
+) -> Result<(), AppError> {
+    let mut queue = state.playback_queue.write().await;
+    queue.set_repeat_mode(mode);
+    emit_mode_changed(&app, &queue);
+    Ok(())
 }
 
 #[tauri::command]
 pub async fn clear_queue(state: State<'_, AppState>) -> Result<(), AppError> {
     let mut queue = state.playback_queue.write().await;
     queue.clear();
+    drop(queue);
+    // The preloaded next track no longer belongs to any queue
+    state.discard_preloaded().await;
     Ok(())
 }
 
@@ -75,46 +320,35 @@ pub async fn play_queue_track(
     app: tauri::AppHandle,
     index: usize,
 ) -> Result<(), AppError> {
+    // Move the queue position first so auto-advance continues after this entry
     let track_id = {
-        let queue = state.playback_queue.read().await;
-        let tracks = &queue.state().tracks;
-        tracks
-            .get(index)
+        let mut queue = state.playback_queue.write().await;
+        queue
+            .jump_to(index)
             .map(|t| t.id.clone())
             .ok_or_else(|| AppError::NotFound("Track index out of bounds".into()))?
     };
 
-    crate::commands::playback_commands::play_track(state, app, track_id).await
+    crate::commands::playback_commands::play_track(state, app, track_id, None).await
 }
 
 #[tauri::command]
 pub async fn save_queue_state(state: State<'_, AppState>) -> Result<(), AppError> {
     let queue = state.playback_queue.read().await;
-    let persisted = queue.persisted_state();
+    let mut persisted = queue.persisted_state();
     drop(queue);
+    persisted.position = state.audio_player.read().await.position_seconds();
 
-    let path = AppConfig::queue_path()?;
-    let dir = AppConfig::config_dir()?;
-    std::fs::create_dir_all(&dir)?;
-    let content = serde_json::to_string_pretty(&persisted)?;
-    std::fs::write(&path, content)?;
+    persisted.save()?;
     Ok(())
 }
 
 #[tauri::command]
 pub async fn load_saved_queue() -> Result<QueueState, AppError> {
-    let path = AppConfig::queue_path()?;
-    if !path.exists() {
-        return Ok(QueueState {
-            tracks: Vec::new(),
-            current_index: None,
-            repeat_mode: RepeatMode::Off,
-            shuffled: false,
-        });
-    }
-
-    let content = std::fs::read_to_string(&path)?;
-    let persisted: PersistedQueueState = serde_json::from_str(&content)?;
+    let persisted = match PersistedQueueState::load()? {
+        Some(persisted) => persisted,
+        None => return Ok(QueueState::empty()),
+    };
 
     let mut tracks = persisted.tracks;
     for track in &mut tracks {
@@ -124,10 +358,13 @@ pub async fn load_saved_queue() -> Result<QueueState, AppError> {
     // Return the persisted state for the frontend to restore the current track display,
     // but do NOT load it into the backend queue. The queue starts empty on each launch
     // so the queue page only shows tracks from the current session.
-    Ok(QueueState {
+    let mut qs = QueueState::new(
         tracks,
-        current_index: persisted.current_index,
-        repeat_mode: persisted.repeat_mode,
-        shuffled: persisted.shuffled,
-    })
+        persisted.current_index,
+        persisted.repeat_mode,
+        persisted.shuffled,
+        persisted.source,
+    );
+    qs.resume_position = Some(persisted.position);
+    Ok(qs)
 }
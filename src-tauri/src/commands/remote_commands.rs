@@ -0,0 +1,77 @@
+use crate::error::AppError;
+use crate::remote_control::{self, RemoteControlSettings};
+use serde::Serialize;
+use tauri::State;
+
+use crate::AppState;
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoteControlStatus {
+    #[serde(flatten)]
+    pub settings: RemoteControlSettings,
+    /// Port the server is listening on; None when it isn't running
+    pub listening_port: Option<u16>,
+}
+
+async fn remote_control_status(state: &State<'_, AppState>) -> RemoteControlStatus {
+    let settings = state
+        .tidal_client
+        .config()
+        .read()
+        .await
+        .remote_control
+        .clone();
+    let listening_port = state.remote_server.lock().await.as_ref().map(|s| s.port());
+    RemoteControlStatus {
+        settings,
+        listening_port,
+    }
+}
+
+#[tauri::command]
+pub async fn get_remote_control(
+    state: State<'_, AppState>,
+) -> Result<RemoteControlStatus, AppError> {
+    Ok(remote_control_status(&state).await)
+}
+
+/// Turn the remote control server on or off and choose where it listens.
+/// The server is restarted to apply the change; a port that can't be bound
+/// is an error, with the settings still saved.
+#[tauri::command]
+pub async fn set_remote_control(
+    state: State<'_, AppState>,
+    app: tauri::AppHandle,
+    enabled: bool,
+    port: u16,
+    allow_lan: bool,
+) -> Result<RemoteControlStatus, AppError> {
+    if port == 0 {
+        return Err(AppError::Config("Remote control needs a fixed port".into()));
+    }
+    let mut config = state.tidal_client.config().write().await;
+    config.remote_control.enabled = enabled;
+    config.remote_control.port = port;
+    config.remote_control.allow_lan = allow_lan;
+    config.save()?;
+    drop(config);
+
+    remote_control::apply_settings(&app).await?;
+    Ok(remote_control_status(&state).await)
+}
+
+/// Replace the access token, disconnecting clients that use the old one.
+#[tauri::command]
+pub async fn regenerate_remote_control_token(
+    state: State<'_, AppState>,
+    app: tauri::AppHandle,
+) -> Result<RemoteControlStatus, AppError> {
+    let mut config = state.tidal_client.config().write().await;
+    config.remote_control.token = remote_control::new_token();
+    config.save()?;
+    drop(config);
+
+    remote_control::apply_settings(&app).await?;
+    Ok(remote_control_status(&state).await)
+}
@@ -12,6 +12,9 @@ pub async fn search(
 ) -> Result<SearchResults, AppError> {
     let limit = limit.unwrap_or(20);
     let mut results = state.tidal_client.search(&query, limit).await?;
+    if state.hide_explicit().await {
+        results.tracks.retain(|t| !t.explicit);
+    }
     results.resolve_all_artwork();
     Ok(results)
 }
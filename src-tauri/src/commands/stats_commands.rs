@@ -0,0 +1,22 @@
+use crate::audio::track_stats::{TrackStats, TrackStatsEntry};
+use crate::error::AppError;
+use tauri::State;
+
+use crate::AppState;
+
+#[tauri::command]
+pub async fn get_track_stats(
+    state: State<'_, AppState>,
+    track_id: String,
+) -> Result<TrackStats, AppError> {
+    Ok(state.track_stats.lock().unwrap().get(&track_id))
+}
+
+/// The `limit` most played tracks, by local play count.
+#[tauri::command]
+pub async fn get_most_played(
+    state: State<'_, AppState>,
+    limit: usize,
+) -> Result<Vec<TrackStatsEntry>, AppError> {
+    Ok(state.track_stats.lock().unwrap().most_played(limit))
+}
@@ -1,8 +1,22 @@
+use crate::api::models::HomeSectionKind;
+use crate::audio::crossfeed::CrossfeedSettings;
+use crate::audio::interruption::InterruptionSettings;
+use crate::audio::output::OutputBackend;
 use crate::error::{AppError, AppResult};
+use crate::remote_control::RemoteControlSettings;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::PathBuf;
 
+/// Bounds on `progress_interval_ms`.
+pub const MIN_PROGRESS_INTERVAL_MS: u32 = 100;
+pub const MAX_PROGRESS_INTERVAL_MS: u32 = 1000;
+
+/// Override the config and cache directories.
+pub const CONFIG_DIR_ENV: &str = "TAURITIDAL_CONFIG_DIR";
+pub const CACHE_DIR_ENV: &str = "TAURITIDAL_CACHE_DIR";
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AppConfig {
     pub client_id: String,
@@ -17,14 +31,83 @@ pub struct AppConfig {
     pub user_id: Option<String>,
     #[serde(default)]
     pub display_name: Option<String>,
+    #[serde(default)]
+    pub email: Option<String>,
+    #[serde(default)]
+    pub avatar_url: Option<String>,
+    /// When the cached profile fields above were last refreshed
+    #[serde(default)]
+    pub profile_fetched_at: Option<DateTime<Utc>>,
+    /// The saved login was rejected (e.g. its refresh token was revoked) and
+    /// the user has to log in again. Cleared by logging in or out.
+    #[serde(default)]
+    pub reauth_required: bool,
     #[serde(default = "default_country_code")]
     pub country_code: String,
     #[serde(default = "default_audio_quality")]
     pub audio_quality: String,
+    /// Fetch later tracks at a lower quality while downloads can't keep up
+    #[serde(default)]
+    pub adaptive_quality: bool,
+    /// Volume in effect, and the starting volume on devices not seen before
     #[serde(default = "default_volume")]
     pub volume: f32,
+    /// Volume last used on each output device, keyed by device name
+    #[serde(default)]
+    pub device_volumes: HashMap<String, f32>,
     #[serde(default)]
     pub muted: bool,
+    /// Timestamp of the last crash report the user has seen
+    #[serde(default)]
+    pub acknowledged_crash_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub crossfeed: CrossfeedSettings,
+    /// Play a matching version of a track that isn't available in the user's country
+    #[serde(default)]
+    pub substitute_unavailable: bool,
+    /// Play 30-second previews when full tracks aren't available (not signed
+    /// in, no subscription); off fails playback with an explanation instead
+    #[serde(default = "default_true")]
+    pub allow_preview_playback: bool,
+    /// Filter explicit tracks out of browsing and skip them in the queue
+    #[serde(default)]
+    pub hide_explicit: bool,
+    /// Languages (ISO 639 codes) radio and recommendations are limited to;
+    /// empty allows all. Tracks without language metadata always pass.
+    #[serde(default)]
+    pub language_allowlist: Vec<String>,
+    /// Release the audio device after this many minutes paused; 0 never releases it
+    #[serde(default = "default_release_output_after_minutes")]
+    pub release_output_after_minutes: u32,
+    /// Seconds before a track ends to announce the next one; 0 turns it off
+    #[serde(default = "default_upnext_notice_secs")]
+    pub upnext_notice_secs: u32,
+    /// Milliseconds between playback progress updates; longer saves power
+    #[serde(default = "default_progress_interval_ms")]
+    pub progress_interval_ms: u32,
+    /// Move playback to the new system default output device when it changes
+    #[serde(default = "default_true")]
+    pub follow_default_output: bool,
+    /// Where audio is played; `null` runs without an output device
+    #[serde(default)]
+    pub audio_output: OutputBackend,
+    /// Ducking or pausing while another app plays audio (macOS)
+    #[serde(default)]
+    pub interruption: InterruptionSettings,
+    /// Minimize the main window on launch. There is no tray icon, so it stays
+    /// in the dock/taskbar.
+    #[serde(default)]
+    pub start_minimized: bool,
+    /// Start playing the saved queue on launch, after a short grace window
+    /// the user can cancel
+    #[serde(default)]
+    pub autoresume_on_launch: bool,
+    /// Local HTTP/WebSocket server for controlling playback from another device
+    #[serde(default)]
+    pub remote_control: RemoteControlSettings,
+    /// Sections shown on Home, in display order
+    #[serde(default = "default_home_sections")]
+    pub home_sections: Vec<HomeSectionKind>,
 }
 
 fn default_country_code() -> String {
@@ -39,6 +122,32 @@ fn default_volume() -> f32 {
     1.0
 }
 
+fn default_true() -> bool {
+    true
+}
+
+fn default_release_output_after_minutes() -> u32 {
+    5
+}
+
+fn default_upnext_notice_secs() -> u32 {
+    5
+}
+
+fn default_progress_interval_ms() -> u32 {
+    250
+}
+
+fn default_home_sections() -> Vec<HomeSectionKind> {
+    vec![
+        HomeSectionKind::MyMixes,
+        HomeSectionKind::Discovery,
+        HomeSectionKind::NewArrivals,
+        HomeSectionKind::BecauseYouLike,
+        HomeSectionKind::FavoritesTeaser,
+    ]
+}
+
 impl Default for AppConfig {
     fn default() -> Self {
         Self {
@@ -49,21 +158,60 @@ impl Default for AppConfig {
             expires_at: None,
             user_id: None,
             display_name: None,
+            email: None,
+            avatar_url: None,
+            profile_fetched_at: None,
+            reauth_required: false,
             country_code: default_country_code(),
             audio_quality: default_audio_quality(),
+            adaptive_quality: false,
             volume: default_volume(),
+            device_volumes: HashMap::new(),
             muted: false,
+            acknowledged_crash_at: None,
+            crossfeed: CrossfeedSettings::default(),
+            substitute_unavailable: false,
+            allow_preview_playback: true,
+            hide_explicit: false,
+            language_allowlist: Vec::new(),
+            release_output_after_minutes: default_release_output_after_minutes(),
+            upnext_notice_secs: default_upnext_notice_secs(),
+            progress_interval_ms: default_progress_interval_ms(),
+            follow_default_output: true,
+            audio_output: OutputBackend::default(),
+            interruption: InterruptionSettings::default(),
+            start_minimized: false,
+            autoresume_on_launch: false,
+            remote_control: RemoteControlSettings::default(),
+            home_sections: default_home_sections(),
         }
     }
 }
 
 impl AppConfig {
+    /// `~/.tauritidal`, unless `TAURITIDAL_CONFIG_DIR` points elsewhere (a
+    /// second profile, or tests).
     pub fn config_dir() -> AppResult<PathBuf> {
+        if let Some(dir) = std::env::var_os(CONFIG_DIR_ENV) {
+            return Ok(PathBuf::from(dir));
+        }
         let home = dirs::home_dir()
             .ok_or_else(|| AppError::Config("Cannot find home directory".into()))?;
         Ok(home.join(".tauritidal"))
     }
 
+    /// Where data that can be fetched again (images, listings) is kept, so the
+    /// OS and cleanup tools treat it as a cache. `TAURITIDAL_CACHE_DIR`
+    /// overrides it.
+    pub fn cache_dir() -> AppResult<PathBuf> {
+        if let Some(dir) = std::env::var_os(CACHE_DIR_ENV) {
+            return Ok(PathBuf::from(dir));
+        }
+        let cache = dirs::cache_dir()
+            .ok_or_else(|| AppError::Config("Cannot find cache directory".into()))?;
+        Ok(cache.join("tauritidal"))
+    }
+
     pub fn config_path() -> AppResult<PathBuf> {
         Ok(Self::config_dir()?.join("config.json"))
     }
@@ -103,4 +251,128 @@ impl AppConfig {
             None => true,
         }
     }
+
+    /// Forget the signed-in user: tokens and profile. Client credentials,
+    /// country and playback settings are kept.
+    pub fn clear_user(&mut self) {
+        self.access_token = None;
+        self.refresh_token = None;
+        self.expires_at = None;
+        self.user_id = None;
+        self.display_name = None;
+        self.email = None;
+        self.avatar_url = None;
+        self.profile_fetched_at = None;
+        self.reauth_required = false;
+    }
+
+    /// Drop the rejected tokens and flag that the user has to log in again.
+    /// The user id and profile stay so the UI can say whose session ended.
+    pub fn require_reauth(&mut self) {
+        self.access_token = None;
+        self.refresh_token = None;
+        self.expires_at = None;
+        self.reauth_required = true;
+    }
+
+    /// Volume remembered for an output device, or the current volume for
+    /// devices without one. Configs from before per-device volumes have an
+    /// empty map, so every device starts at their single volume.
+    pub fn volume_for(&self, device: Option<&str>) -> f32 {
+        device
+            .and_then(|d| self.device_volumes.get(d))
+            .copied()
+            .unwrap_or(self.volume)
+    }
+
+    /// Time between progress updates, kept within bounds for hand-edited configs.
+    pub fn progress_interval(&self) -> std::time::Duration {
+        let ms = self
+            .progress_interval_ms
+            .clamp(MIN_PROGRESS_INTERVAL_MS, MAX_PROGRESS_INTERVAL_MS);
+        std::time::Duration::from_millis(u64::from(ms))
+    }
+
+    /// Set the volume in effect and remember it for `device`.
+    pub fn set_volume_for(&mut self, device: Option<&str>, volume: f32) {
+        self.volume = volume;
+        if let Some(device) = device {
+            self.device_volumes.insert(device.to_string(), volume);
+        }
+    }
+
+    /// Show `sections` on Home in the order given, dropping repeats.
+    pub fn set_home_sections(&mut self, sections: Vec<HomeSectionKind>) {
+        self.home_sections.clear();
+        for kind in sections {
+            if !self.home_sections.contains(&kind) {
+                self.home_sections.push(kind);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn volume_for_falls_back_to_the_current_volume() {
+        let mut config = AppConfig {
+            volume: 0.7,
+            ..AppConfig::default()
+        };
+        assert_eq!(config.volume_for(Some("Speakers")), 0.7);
+        assert_eq!(config.volume_for(None), 0.7);
+
+        config.set_volume_for(Some("Headphones"), 0.2);
+        assert_eq!(config.volume, 0.2);
+        assert_eq!(config.volume_for(Some("Headphones")), 0.2);
+        // A device without an entry follows the volume in effect
+        assert_eq!(config.volume_for(Some("Speakers")), 0.2);
+
+        config.set_volume_for(Some("Speakers"), 0.9);
+        assert_eq!(config.volume_for(Some("Headphones")), 0.2);
+        assert_eq!(config.volume_for(Some("Speakers")), 0.9);
+
+        // Without a known device only the volume in effect changes
+        config.set_volume_for(None, 0.4);
+        assert_eq!(config.volume, 0.4);
+        assert_eq!(config.device_volumes.len(), 2);
+    }
+
+    #[test]
+    fn home_sections_keep_their_order_without_repeats() {
+        let mut config = AppConfig::default();
+        config.set_home_sections(vec![
+            HomeSectionKind::FavoritesTeaser,
+            HomeSectionKind::MyMixes,
+            HomeSectionKind::FavoritesTeaser,
+            HomeSectionKind::RecentlyPlayed,
+            HomeSectionKind::MyMixes,
+        ]);
+        assert_eq!(
+            config.home_sections,
+            [
+                HomeSectionKind::FavoritesTeaser,
+                HomeSectionKind::MyMixes,
+                HomeSectionKind::RecentlyPlayed,
+            ]
+        );
+
+        config.set_home_sections(Vec::new());
+        assert!(config.home_sections.is_empty());
+    }
+
+    #[test]
+    fn configs_without_device_volumes_keep_their_volume() {
+        let mut saved = serde_json::to_value(AppConfig::default()).unwrap();
+        let fields = saved.as_object_mut().unwrap();
+        fields.remove("device_volumes");
+        fields.insert("volume".into(), serde_json::json!(0.35));
+
+        let config: AppConfig = serde_json::from_value(saved).unwrap();
+        assert!(config.device_volumes.is_empty());
+        assert_eq!(config.volume_for(Some("Speakers")), 0.35);
+    }
 }
@@ -0,0 +1,217 @@
+use crate::config::AppConfig;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+/// Rotate the crash log once it grows past this size.
+const MAX_CRASH_LOG_BYTES: u64 = 256 * 1024;
+
+const ENTRY_SEPARATOR: &str = "\n---\n";
+
+static SESSION_ID: OnceLock<String> = OnceLock::new();
+
+/// Random id for this process, recorded with every crash entry so the
+/// current session's panics can be told apart from the previous run's.
+pub fn session_id() -> &'static str {
+    SESSION_ID.get_or_init(|| uuid::Uuid::new_v4().to_string())
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CrashReport {
+    pub session_id: String,
+    pub version: String,
+    pub timestamp: DateTime<Utc>,
+    /// Full entry text (location, message and backtrace), suitable for copying.
+    pub report: String,
+}
+
+/// `crash.log` in the config directory, so a second profile keeps its own.
+fn crash_log_path() -> Option<PathBuf> {
+    AppConfig::config_dir()
+        .ok()
+        .map(|dir| dir.join("crash.log"))
+}
+
+/// Move an oversized crash log to `crash.log.1`, replacing the previous rotation.
+fn rotate_if_needed(path: &PathBuf) {
+    let too_big = std::fs::metadata(path)
+        .map(|m| m.len() > MAX_CRASH_LOG_BYTES)
+        .unwrap_or(false);
+    if too_big {
+        let _ = std::fs::rename(path, path.with_extension("log.1"));
+    }
+}
+
+/// Install a panic hook that writes the panic message to a file.
+/// This captures the error before abort() kills the process when
+/// a panic occurs inside an extern "C" (ObjC) callback frame.
+pub fn install_panic_hook() {
+    // Generate the session id up front rather than inside the hook
+    session_id();
+
+    std::panic::set_hook(Box::new(|info| {
+        let msg = format!(
+            "PANIC time={} session={} version={}\nat {}: {}\nBacktrace:\n{}{}",
+            Utc::now().to_rfc3339(),
+            session_id(),
+            env!("CARGO_PKG_VERSION"),
+            info.location()
+                .map(|l| format!("{}:{}:{}", l.file(), l.line(), l.column()))
+                .unwrap_or_else(|| "unknown".into()),
+            info.payload()
+                .downcast_ref::<&str>()
+                .copied()
+                .or_else(|| info.payload().downcast_ref::<String>().map(|s| s.as_str()))
+                .unwrap_or("(no message)"),
+            std::backtrace::Backtrace::force_capture(),
+            ENTRY_SEPARATOR,
+        );
+        append_entry(&msg);
+        eprintln!("{}", msg);
+    }));
+}
+
+/// Add an entry to the crash log, rotating it first if it's too big.
+fn append_entry(entry: &str) {
+    use std::io::Write;
+    let Some(crash_path) = crash_log_path() else {
+        return;
+    };
+    if let Some(dir) = crash_path.parent() {
+        let _ = std::fs::create_dir_all(dir);
+    }
+    rotate_if_needed(&crash_path);
+    // Append so we capture the FIRST panic before the "cannot unwind" overwrites it
+    if let Ok(mut f) = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&crash_path)
+    {
+        let _ = f.write_all(entry.as_bytes());
+    }
+}
+
+/// Parse one crash log entry. Entries written before session ids were
+/// recorded have no header and are skipped.
+fn parse_entry(entry: &str) -> Option<CrashReport> {
+    let header = entry.lines().next()?.strip_prefix("PANIC ")?;
+    let mut timestamp = None;
+    let mut session_id = None;
+    let mut version = None;
+    for field in header.split_whitespace() {
+        match field.split_once('=') {
+            Some(("time", v)) => timestamp = DateTime::parse_from_rfc3339(v).ok(),
+            Some(("session", v)) => session_id = Some(v.to_string()),
+            Some(("version", v)) => version = Some(v.to_string()),
+            _ => {}
+        }
+    }
+
+    Some(CrashReport {
+        session_id: session_id?,
+        version: version.unwrap_or_default(),
+        timestamp: timestamp?.with_timezone(&Utc),
+        report: entry.trim().to_string(),
+    })
+}
+
+/// Most recent panic from a previous session, if any.
+pub fn last_crash() -> Option<CrashReport> {
+    let content = std::fs::read_to_string(crash_log_path()?).ok()?;
+    content
+        .split(ENTRY_SEPARATOR)
+        .filter(|e| !e.trim().is_empty())
+        .filter_map(parse_entry)
+        .filter(|report| report.session_id != session_id())
+        .last()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::temp_dirs;
+
+    fn entry(time: &str, session: &str, message: &str) -> String {
+        format!(
+            "PANIC time={} session={} version=1.2.3\nat src/lib.rs:1:1: {}\nBacktrace:\n{}",
+            time, session, message, ENTRY_SEPARATOR
+        )
+    }
+
+    fn write_log(entries: &[String]) {
+        let path = crash_log_path().unwrap();
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, entries.concat()).unwrap();
+    }
+
+    #[test]
+    fn entries_are_parsed_from_their_header() {
+        let log = entry("2024-05-01T10:00:00+02:00", "abc", "boom");
+        let report = parse_entry(log.split(ENTRY_SEPARATOR).next().unwrap()).unwrap();
+        assert_eq!(report.session_id, "abc");
+        assert_eq!(report.version, "1.2.3");
+        assert_eq!(report.timestamp.to_rfc3339(), "2024-05-01T08:00:00+00:00");
+        assert!(report.report.starts_with("PANIC "));
+        assert!(report.report.ends_with("Backtrace:"));
+
+        // A version is optional, a session and a valid time aren't
+        let report = parse_entry("PANIC session=abc time=2024-05-01T10:00:00Z\nat x").unwrap();
+        assert_eq!(report.version, "");
+        assert!(parse_entry("PANIC time=2024-05-01T10:00:00Z version=1\nat x").is_none());
+        assert!(parse_entry("PANIC time=yesterday session=abc\nat x").is_none());
+        // Entries from before the header was written
+        assert!(parse_entry("at src/lib.rs:1:1: boom\nBacktrace:").is_none());
+    }
+
+    #[test]
+    fn last_crash_is_the_newest_from_another_session() {
+        let _dirs = temp_dirs();
+        assert!(last_crash().is_none());
+
+        write_log(&[
+            "at src/old.rs:1:1: from before headers\n---\n".to_string(),
+            entry("2024-05-01T10:00:00Z", "first", "older"),
+            entry("2024-05-02T10:00:00Z", "second", "newer"),
+            entry("2024-05-03T10:00:00Z", session_id(), "this session"),
+        ]);
+        let report = last_crash().unwrap();
+        assert_eq!(report.session_id, "second");
+        assert!(report.report.contains("newer"));
+
+        // Only this session's panics: nothing to report
+        write_log(&[entry("2024-05-03T10:00:00Z", session_id(), "this session")]);
+        assert!(last_crash().is_none());
+    }
+
+    #[test]
+    fn an_oversized_log_is_rotated_before_appending() {
+        let _dirs = temp_dirs();
+        let path = crash_log_path().unwrap();
+        assert!(path.starts_with(AppConfig::config_dir().unwrap()));
+
+        // Below the limit the log just grows
+        append_entry("first");
+        append_entry("second");
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "firstsecond");
+        assert!(!path.with_extension("log.1").exists());
+
+        let big = "x".repeat(MAX_CRASH_LOG_BYTES as usize + 1);
+        std::fs::write(&path, &big).unwrap();
+        append_entry("third");
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "third");
+        assert_eq!(
+            std::fs::read_to_string(path.with_extension("log.1")).unwrap(),
+            big
+        );
+
+        // The next rotation replaces the previous one
+        std::fs::write(&path, format!("{}y", big)).unwrap();
+        append_entry("fourth");
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "fourth");
+        assert!(std::fs::read_to_string(path.with_extension("log.1"))
+            .unwrap()
+            .ends_with('y'));
+    }
+}
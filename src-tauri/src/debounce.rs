@@ -0,0 +1,125 @@
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Runs the most recently scheduled task once `delay` has passed without
+/// another one being scheduled, so a burst of changes is written once.
+/// Clones share the same schedule.
+#[derive(Clone)]
+pub struct Debouncer {
+    delay: Duration,
+    /// Bumped on every `schedule` and `cancel`; a task only runs if it is
+    /// still the latest when its delay is up
+    generation: Arc<AtomicU64>,
+}
+
+impl Debouncer {
+    pub fn new(delay: Duration) -> Self {
+        Self {
+            delay,
+            generation: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Run `task` after the delay, replacing any task scheduled before it
+    /// that hasn't run yet. Must be called from the async runtime.
+    pub fn schedule<F>(&self, task: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let generation = self.generation.fetch_add(1, Ordering::SeqCst) + 1;
+        let latest = Arc::clone(&self.generation);
+        let delay = self.delay;
+        tokio::spawn(async move {
+            tokio::time::sleep(delay).await;
+            if latest.load(Ordering::SeqCst) == generation {
+                task.await;
+            }
+        });
+    }
+
+    /// Drop the scheduled task, e.g. because its work is being done right away.
+    pub fn cancel(&self) {
+        self.generation.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    const DELAY: Duration = Duration::from_millis(1500);
+
+    type Runs = Arc<Mutex<Vec<u32>>>;
+
+    fn record(runs: &Runs, n: u32) -> impl Future<Output = ()> + Send + 'static {
+        let runs = Arc::clone(runs);
+        async move { runs.lock().unwrap().push(n) }
+    }
+
+    async fn sleep_ms(ms: u64) {
+        tokio::time::sleep(Duration::from_millis(ms)).await;
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn runs_once_after_the_quiet_period() {
+        let debouncer = Debouncer::new(DELAY);
+        let runs = Runs::default();
+
+        debouncer.schedule(record(&runs, 1));
+        sleep_ms(1499).await;
+        assert!(runs.lock().unwrap().is_empty());
+        sleep_ms(2).await;
+        assert_eq!(*runs.lock().unwrap(), [1]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn a_burst_runs_only_the_last_task() {
+        let debouncer = Debouncer::new(DELAY);
+        let runs = Runs::default();
+
+        // Dragging a slider: a change every 100 ms for a second
+        for n in 0..10 {
+            debouncer.schedule(record(&runs, n));
+            sleep_ms(100).await;
+        }
+        // The delay counts from the last change, not the first
+        sleep_ms(1399).await;
+        assert!(runs.lock().unwrap().is_empty());
+        sleep_ms(2).await;
+        assert_eq!(*runs.lock().unwrap(), [9]);
+
+        sleep_ms(10_000).await;
+        assert_eq!(*runs.lock().unwrap(), [9]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn cancel_drops_the_pending_task() {
+        let debouncer = Debouncer::new(DELAY);
+        let runs = Runs::default();
+
+        debouncer.schedule(record(&runs, 1));
+        sleep_ms(500).await;
+        debouncer.cancel();
+        sleep_ms(5_000).await;
+        assert!(runs.lock().unwrap().is_empty());
+
+        // Scheduling again after a cancel works as before
+        debouncer.schedule(record(&runs, 2));
+        sleep_ms(1501).await;
+        assert_eq!(*runs.lock().unwrap(), [2]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn clones_share_the_schedule() {
+        let debouncer = Debouncer::new(DELAY);
+        let runs = Runs::default();
+
+        debouncer.schedule(record(&runs, 1));
+        debouncer.clone().schedule(record(&runs, 2));
+        sleep_ms(1501).await;
+        assert_eq!(*runs.lock().unwrap(), [2]);
+    }
+}
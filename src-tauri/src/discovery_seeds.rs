@@ -0,0 +1,84 @@
+use crate::api::models::Track;
+use crate::config::AppConfig;
+use crate::error::AppResult;
+use chrono::{DateTime, Duration, Utc};
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+/// How long the same seeds are reused before rotating to others.
+const SEED_LIFETIME_HOURS: i64 = 24;
+
+/// Favorites the "Because you like" rows on Home are built from, kept for a
+/// day so Home doesn't change on every visit. Stored in `discovery_seeds.json`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiscoverySeeds {
+    #[serde(default)]
+    pub track_ids: Vec<String>,
+    /// When the seeds were chosen; `None` rotates on the next Home load
+    #[serde(default)]
+    pub chosen_at: Option<DateTime<Utc>>,
+}
+
+impl DiscoverySeeds {
+    pub fn path() -> AppResult<PathBuf> {
+        Ok(AppConfig::config_dir()?.join("discovery_seeds.json"))
+    }
+
+    /// Load the seeds, starting empty if none have been saved.
+    pub fn load() -> AppResult<Self> {
+        let path = Self::path()?;
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(&path)?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    pub fn save(&self) -> AppResult<()> {
+        std::fs::create_dir_all(AppConfig::config_dir()?)?;
+        let content = serde_json::to_string_pretty(self)?;
+        std::fs::write(Self::path()?, content)?;
+        Ok(())
+    }
+
+    /// Rotate on the next Home load, even if the seeds are still fresh.
+    pub fn expire(&mut self) {
+        self.chosen_at = None;
+    }
+
+    /// Up to `count` seeds from `favorites`. The saved seeds are reused while
+    /// fresh and still favorited; otherwise new ones are picked at random,
+    /// preferring favorites that weren't seeds last time. Returns whether the
+    /// seeds changed and need saving.
+    pub fn choose<'a>(&mut self, favorites: &'a [Track], count: usize) -> (Vec<&'a Track>, bool) {
+        let fresh = self
+            .chosen_at
+            .is_some_and(|at| Utc::now() - at < Duration::hours(SEED_LIFETIME_HOURS));
+        if fresh {
+            let kept: Vec<&Track> = self
+                .track_ids
+                .iter()
+                .filter_map(|id| favorites.iter().find(|t| t.id == *id))
+                .collect();
+            if !kept.is_empty() {
+                return (kept, false);
+            }
+        }
+
+        let previous: HashSet<&str> = self.track_ids.iter().map(String::as_str).collect();
+        let (mut fresh_picks, mut repeats): (Vec<&Track>, Vec<&Track>) = favorites
+            .iter()
+            .partition(|t| !previous.contains(t.id.as_str()));
+        let mut rng = rand::thread_rng();
+        fresh_picks.shuffle(&mut rng);
+        repeats.shuffle(&mut rng);
+
+        let seeds: Vec<&Track> = fresh_picks.into_iter().chain(repeats).take(count).collect();
+        self.track_ids = seeds.iter().map(|t| t.id.clone()).collect();
+        self.chosen_at = Some(Utc::now());
+        (seeds, true)
+    }
+}
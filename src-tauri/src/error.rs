@@ -5,6 +5,11 @@ pub enum AppError {
     #[error("HTTP error: {0}")]
     Http(#[from] reqwest::Error),
 
+    /// A request that failed without an HTTP status, copied from an `Http`
+    /// error (which can't be cloned) to answer with again
+    #[error("Network error: {0}")]
+    Network(String),
+
     #[error("JSON error: {0}")]
     Json(#[from] serde_json::Error),
 
@@ -20,6 +25,9 @@ pub enum AppError {
     #[error("Token expired")]
     TokenExpired,
 
+    #[error("The saved login is no longer valid, please log in again")]
+    ReauthRequired,
+
     #[error("Tidal API error: {status} - {message}")]
     TidalApi { status: u16, message: String },
 
@@ -29,6 +37,18 @@ pub enum AppError {
     #[error("Not found: {0}")]
     NotFound(String),
 
+    #[error("Track {0} is explicit and explicit content is hidden")]
+    ExplicitFiltered(String),
+
+    #[error("Track {track_id} is not available in {country}")]
+    TrackUnavailable { track_id: String, country: String },
+
+    #[error("Full tracks need a signed-in account with a Tidal subscription")]
+    SubscriptionRequired,
+
+    #[error("The Tidal catalog isn't available in {country}")]
+    RegionBlocked { country: String },
+
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 }
@@ -39,28 +59,321 @@ impl Serialize for AppError {
         S: serde::Serializer,
     {
         use serde::ser::SerializeStruct;
-        let mut state = serializer.serialize_struct("AppError", 2)?;
+        let mut state = serializer.serialize_struct("AppError", 5)?;
         state.serialize_field("kind", &self.kind())?;
         state.serialize_field("message", &self.to_string())?;
+        state.serialize_field("retryable", &self.is_retryable())?;
+        state.serialize_field("status", &self.status())?;
+        state.serialize_field("code", &self.code())?;
         state.end()
     }
 }
 
 impl AppError {
-    fn kind(&self) -> &str {
+    pub fn kind(&self) -> &str {
         match self {
             AppError::Http(_) => "http",
+            AppError::Network(_) => "network",
             AppError::Json(_) => "json",
             AppError::Audio(_) => "audio",
             AppError::Decode(_) => "decode",
             AppError::AuthRequired => "auth_required",
             AppError::TokenExpired => "token_expired",
+            AppError::ReauthRequired => "reauth_required",
             AppError::TidalApi { .. } => "tidal_api",
             AppError::Config(_) => "config",
             AppError::NotFound(_) => "not_found",
+            AppError::ExplicitFiltered(_) => "explicit_filtered",
+            AppError::TrackUnavailable { .. } => "track_unavailable",
+            AppError::SubscriptionRequired => "subscription_required",
+            AppError::RegionBlocked { .. } => "region_blocked",
             AppError::Io(_) => "io",
         }
     }
+
+    /// Whether retrying the same operation could reasonably succeed.
+    /// True for network timeouts/connection failures, 5xx responses and rate limits.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            AppError::Http(e) => e.is_timeout() || e.is_connect(),
+            AppError::Network(_) => true,
+            AppError::TidalApi { status, .. } => *status == 429 || *status >= 500,
+            _ => false,
+        }
+    }
+
+    /// HTTP status code for errors that came back from the Tidal API.
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            AppError::TidalApi { status, .. } => Some(*status),
+            AppError::Http(e) => e.status().map(|s| s.as_u16()),
+            _ => None,
+        }
+    }
+
+    /// Stable machine-readable code for error cases the UI handles specially.
+    /// Derived from the status and the (usually JSON) error body Tidal returns.
+    pub fn code(&self) -> Option<&'static str> {
+        match self {
+            AppError::TidalApi { status, message } => tidal_error_code(*status, message),
+            AppError::TrackUnavailable { .. } => Some("track_unavailable"),
+            AppError::SubscriptionRequired => Some("subscription_required"),
+            AppError::RegionBlocked { .. } => Some("region_blocked"),
+            AppError::ReauthRequired => Some("reauth_required"),
+            _ => None,
+        }
+    }
+}
+
+/// Classify a Tidal API error body into one of the stable error codes.
+///
+/// v2 returns JSON:API `{"errors":[{"code":..,"detail":..}]}`, v1 returns
+/// `{"status":..,"subStatus":..,"userMessage":..}`. Both are reduced to the
+/// words of their codes and messages, which are matched against whole words
+/// and phrases. Anything else (e.g. a proxy's HTML error page) only gets a
+/// code from its status, and server errors are never blamed on the track.
+fn tidal_error_code(status: u16, message: &str) -> Option<&'static str> {
+    if status == 429 {
+        return Some("rate_limited");
+    }
+    if status == 451 {
+        return Some("region_blocked");
+    }
+    if status >= 500 {
+        return None;
+    }
+    let Ok(body) = serde_json::from_str::<serde_json::Value>(message) else {
+        return None;
+    };
+
+    // v1 subStatus values observed for playback restrictions
+    match body.get("subStatus").and_then(|v| v.as_u64()) {
+        Some(4032) | Some(4033) | Some(4035) => return Some("subscription_required"),
+        Some(4005) => return Some("track_unavailable"),
+        _ => {}
+    }
+
+    let mut texts = Vec::new();
+    if let Some(errors) = body.get("errors").and_then(|v| v.as_array()) {
+        for err in errors {
+            for key in ["code", "detail"] {
+                texts.extend(err.get(key).and_then(|v| v.as_str()));
+            }
+        }
+    }
+    texts.extend(body.get("userMessage").and_then(|v| v.as_str()));
+    let haystack = texts.join(" ").to_lowercase();
+    // Codes like NOT_FOUND stay one word
+    let words: Vec<&str> = haystack
+        .split(|c: char| !c.is_alphanumeric() && c != '_')
+        .filter(|w| !w.is_empty())
+        .collect();
+    let mentions = |phrases: &[&str]| {
+        phrases.iter().any(|phrase| {
+            let phrase: Vec<&str> = phrase.split(' ').collect();
+            words.windows(phrase.len()).any(|w| w == phrase)
+        })
+    };
+
+    if mentions(&["subscription", "subscription_required"]) {
+        Some("subscription_required")
+    } else if mentions(&[
+        "region",
+        "geo",
+        "geoblocked",
+        "geo_blocked",
+        "region_blocked",
+        "in your country",
+        "in this country",
+    ]) {
+        Some("region_blocked")
+    } else if mentions(&[
+        "not ready for playback",
+        "unavailable",
+        "not available",
+        "not_found",
+    ]) {
+        Some("track_unavailable")
+    } else {
+        None
+    }
 }
 
 pub type AppResult<T> = Result<T, AppError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn api_error(status: u16, body: serde_json::Value) -> AppError {
+        AppError::TidalApi {
+            status,
+            message: body.to_string(),
+        }
+    }
+
+    #[test]
+    fn serialized_error_keeps_kind_and_message() {
+        let value = serde_json::to_value(AppError::NotFound("Track 1".into())).unwrap();
+        assert_eq!(
+            value,
+            json!({
+                "kind": "not_found",
+                "message": "Not found: Track 1",
+                "retryable": false,
+                "status": null,
+                "code": null,
+            })
+        );
+    }
+
+    #[test]
+    fn serialized_api_error_carries_status_and_retryability() {
+        let value = serde_json::to_value(AppError::TidalApi {
+            status: 429,
+            message: "Too Many Requests".into(),
+        })
+        .unwrap();
+        assert_eq!(value["kind"], "tidal_api");
+        assert_eq!(value["status"], 429);
+        assert_eq!(value["retryable"], true);
+        assert_eq!(value["code"], "rate_limited");
+
+        let value = serde_json::to_value(AppError::TidalApi {
+            status: 503,
+            message: String::new(),
+        })
+        .unwrap();
+        assert_eq!(value["retryable"], true);
+        assert_eq!(value["code"], serde_json::Value::Null);
+
+        let value = serde_json::to_value(AppError::TidalApi {
+            status: 404,
+            message: String::new(),
+        })
+        .unwrap();
+        assert_eq!(value["status"], 404);
+        assert_eq!(value["retryable"], false);
+    }
+
+    #[test]
+    fn non_api_errors_are_not_retryable() {
+        for error in [
+            AppError::AuthRequired,
+            AppError::Decode("bad frame".into()),
+            AppError::Config("missing".into()),
+        ] {
+            assert!(!error.is_retryable(), "{:?}", error);
+            assert_eq!(error.status(), None);
+        }
+    }
+
+    #[test]
+    fn classifies_v2_error_bodies() {
+        let error = api_error(
+            403,
+            json!({"errors": [{"code": "NOT_FOUND", "detail": "Not available in your country"}]}),
+        );
+        assert_eq!(error.code(), Some("region_blocked"));
+
+        let error = api_error(
+            401,
+            json!({"errors": [{"detail": "A valid subscription is required"}]}),
+        );
+        assert_eq!(error.code(), Some("subscription_required"));
+    }
+
+    #[test]
+    fn classifies_v1_sub_status() {
+        let error = api_error(
+            401,
+            json!({"status": 401, "subStatus": 4032, "userMessage": "Not allowed"}),
+        );
+        assert_eq!(error.code(), Some("subscription_required"));
+
+        let error = api_error(
+            404,
+            json!({"status": 404, "subStatus": 4005, "userMessage": "Asset is not ready"}),
+        );
+        assert_eq!(error.code(), Some("track_unavailable"));
+    }
+
+    #[test]
+    fn server_errors_are_not_blamed_on_the_track() {
+        let page = "<html><head><title>503 Service Unavailable</title></head>\
+                    <body>Service Unavailable: not available in your region</body></html>";
+        for status in [502, 503] {
+            let error = AppError::TidalApi {
+                status,
+                message: page.into(),
+            };
+            assert_eq!(error.code(), None, "{}", status);
+            assert!(error.is_retryable());
+        }
+
+        // Even when the body is a JSON error that would otherwise match
+        let error = api_error(
+            503,
+            json!({"errors": [{"code": "UNAVAILABLE", "detail": "Service unavailable"}]}),
+        );
+        assert_eq!(error.code(), None);
+    }
+
+    #[test]
+    fn only_parsed_error_fields_are_matched() {
+        // A plain text body that happens to mention a keyword
+        let error = AppError::TidalApi {
+            status: 403,
+            message: "Track unavailable in your region".into(),
+        };
+        assert_eq!(error.code(), None);
+
+        // Titles and other fields aren't classified
+        let error = api_error(
+            403,
+            json!({"errors": [{"title": "Unavailable", "meta": {"region": "EU"}}]}),
+        );
+        assert_eq!(error.code(), None);
+    }
+
+    #[test]
+    fn country_code_validation_is_not_a_region_block() {
+        let error = api_error(
+            400,
+            json!({"errors": [{
+                "code": "INVALID_COUNTRY_CODE",
+                "detail": "countryCode must be a valid ISO 3166 country code",
+                "source": {"parameter": "countryCode"},
+            }]}),
+        );
+        assert_eq!(error.code(), None);
+
+        // Keywords only count as whole words
+        let error = api_error(
+            400,
+            json!({"errors": [{"code": "INVALID_PARAMETER", "detail": "Unsupported regionCode"}]}),
+        );
+        assert_eq!(error.code(), None);
+    }
+
+    #[test]
+    fn unrecognized_bodies_have_no_code() {
+        assert_eq!(
+            AppError::TidalApi {
+                status: 500,
+                message: "<html>Internal error</html>".into(),
+            }
+            .code(),
+            None
+        );
+        assert_eq!(
+            AppError::TidalApi {
+                status: 451,
+                message: String::new(),
+            }
+            .code(),
+            Some("region_blocked")
+        );
+    }
+}
@@ -1,17 +1,47 @@
-use serde::Serialize;
+use crate::api::health::RegionBlock;
+use crate::api::models::{Track, DEFAULT_ARTWORK_SIZE, LARGE_ARTWORK_SIZE, SMALL_ARTWORK_SIZE};
+use crate::api::tracks::TrackManifestData;
+use crate::audio::player::SourceFormat;
+use crate::audio::queue::RepeatMode;
+use crate::metadata_cache::CacheKind;
+use crate::release_radar::NewRelease;
+use serde::{Deserialize, Serialize};
 
 pub const PLAYBACK_PROGRESS: &str = "playback:progress";
 pub const PLAYBACK_TRACK_CHANGED: &str = "playback:track-changed";
 pub const PLAYBACK_STATE_CHANGED: &str = "playback:state-changed";
 pub const PLAYBACK_TRACK_ENDED: &str = "playback:track-ended";
 pub const PLAYBACK_QUEUE_CHANGED: &str = "playback:queue-changed";
+pub const PLAYBACK_ERROR: &str = "playback:error";
+pub const PLAYBACK_MODE_CHANGED: &str = "playback:mode-changed";
+pub const VOLUME_CHANGED: &str = "playback:volume-changed";
 pub const AUTH_STATE_CHANGED: &str = "auth:state-changed";
+pub const PLAYLIST_SAVE_PROGRESS: &str = "playlist:save-progress";
+pub const FAVORITES_LOAD_PROGRESS: &str = "favorites:load-progress";
+pub const METADATA_UPDATED: &str = "metadata:updated";
+pub const OUTPUT_DEVICE_CHANGED: &str = "playback:output-device-changed";
+pub const SETTINGS_CHANGED: &str = "settings:changed";
+pub const PLAYBACK_PRELOAD_READY: &str = "playback:preload-ready";
+pub const NETWORK_STATUS_CHANGED: &str = "network:status-changed";
+pub const QUALITY_ADAPTED: &str = "playback:quality-adapted";
+pub const AUTORESUME_PENDING: &str = "playback:autoresume-pending";
+pub const PLAYBACK_UPNEXT: &str = "playback:upnext";
+#[cfg(target_os = "macos")]
+pub const PLAYBACK_STREAM_CHANGED: &str = "playback:stream-changed";
+pub const PLAYBACK_FORMAT_CORRECTED: &str = "playback:format-corrected";
+pub const NEW_RELEASES_FOUND: &str = "release-radar:new-releases";
+pub const FAVORITES_CHANGED: &str = "favorites:changed";
+pub const REGION_BLOCK_CHANGED: &str = "network:region-block-changed";
 
 #[derive(Debug, Clone, Serialize)]
 pub struct ProgressPayload {
     pub position: f64,
     pub duration: f64,
     pub position_fraction: f64,
+    /// See `TrackChangedPayload::session_epoch`
+    pub session_epoch: u64,
+    /// See `TrackChangedPayload::playback_epoch`
+    pub playback_epoch: u64,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -21,14 +51,179 @@ pub struct TrackChangedPayload {
     pub artist: String,
     pub album: String,
     pub duration: f64,
+    #[serde(flatten)]
+    pub artwork: TrackArtwork,
+    #[serde(flatten)]
+    pub stream: StreamQuality,
+    pub reason: TrackChangeReason,
+    /// Only a 30-second preview will play (not signed in, or no subscription)
+    pub preview_only: bool,
+    /// Bumped by every play request as it starts, before its manifest is
+    /// fetched. With several windows issuing commands, a window drops events
+    /// older than the newest epoch it has seen. Requests that are superseded
+    /// while fetching bump it without ever starting a stream.
+    pub session_epoch: u64,
+    /// Bumped by every stream the player starts and by every stop, whatever
+    /// caused them (including auto-advance, which makes no play request).
+    /// Progress, state and track-ended events carry it too; ones with an
+    /// older epoch than the latest track change or stop describe a stream
+    /// that has already ended.
+    pub playback_epoch: u64,
+}
+
+/// A track's artwork at the sizes players show it at. `artwork_url` is the
+/// default; the others are for thumbnails and full-screen views.
+#[derive(Debug, Clone, Serialize)]
+pub struct TrackArtwork {
     pub artwork_url: Option<String>,
+    pub artwork_url_small: Option<String>,
+    pub artwork_url_large: Option<String>,
+}
+
+impl TrackArtwork {
+    pub fn for_track(track: &Track) -> Self {
+        let sized = |size| track.artwork_url_sized(size, size);
+        Self {
+            artwork_url: sized(DEFAULT_ARTWORK_SIZE),
+            artwork_url_small: sized(SMALL_ARTWORK_SIZE),
+            artwork_url_large: sized(LARGE_ARTWORK_SIZE),
+        }
+    }
+}
+
+/// What a track's stream was delivered as, for the quality badge. Bit depth
+/// and sample rate start as the manifest's claims and are replaced by what
+/// the decoder finds once it has probed the stream.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct StreamQuality {
+    /// Codec the manifest announced
     pub codec: Option<String>,
+    /// Codec the decoder found, e.g. "flac" or "aac"; the badge goes by this
+    /// one once it's known
+    pub detected_codec: Option<String>,
+    /// Container the decoder found, e.g. "flac" or "mp4"; the manifest doesn't say
+    pub container: Option<String>,
+    /// Badge text, e.g. "FLAC 24/96" or "AAC"
     pub quality: Option<String>,
+    /// Tidal's quality tier, e.g. LOSSLESS or HI_RES_LOSSLESS
+    pub audio_quality: Option<String>,
+    pub bit_depth: Option<u32>,
+    pub sample_rate: Option<u32>,
+}
+
+impl StreamQuality {
+    pub fn for_manifest(manifest: &TrackManifestData) -> Self {
+        let mut stream = Self {
+            codec: Some(manifest.codec.clone()),
+            detected_codec: None,
+            container: None,
+            quality: None,
+            audio_quality: manifest.audio_quality.clone(),
+            bit_depth: manifest.bit_depth,
+            sample_rate: manifest.sample_rate,
+        };
+        stream.quality = stream.label();
+        stream
+    }
+
+    /// Take the bit depth and sample rate the decoder probed over the
+    /// manifest's, and record the codec and container it found. Lossy codecs
+    /// report no bit depth, so that one is only replaced when the decoder has
+    /// one. Returns whether the decoder disagreed with the manifest.
+    pub fn reconcile(&mut self, source: &SourceFormat) -> bool {
+        let claimed = (self.quality.clone(), self.bit_depth, self.sample_rate);
+        self.detected_codec = source.detected.codec.map(String::from);
+        self.container = source.detected.container.map(String::from);
+        self.bit_depth = source.bit_depth.or(self.bit_depth);
+        self.sample_rate = Some(source.sample_rate);
+        self.quality = self.label();
+        claimed != (self.quality.clone(), self.bit_depth, self.sample_rate)
+    }
+
+    /// The codec's badge text, with bit depth and sample rate for lossless
+    /// streams once both are known.
+    fn label(&self) -> Option<String> {
+        let codec = self.detected_codec.as_deref().or(self.codec.as_deref())?;
+        let codec = quality_label(codec);
+        Some(match (self.bit_depth, self.sample_rate) {
+            (Some(bits), Some(rate)) if codec == "FLAC" => {
+                format!("{} {}/{}", codec, bits, rate as f64 / 1000.0)
+            }
+            _ => codec,
+        })
+    }
+}
+
+/// Human-friendly quality label for a manifest codec, for the quality badge.
+fn quality_label(codec: &str) -> String {
+    match codec.to_lowercase().as_str() {
+        "flac" | "flac_hires" => "FLAC",
+        "aaclc" | "mp4a.40.2" | "mp4a" | "aac" => "AAC",
+        "heaacv1" | "mp4a.40.5" => "AAC",
+        "mp3" => "MP3",
+        "eac3_joc" => "Atmos",
+        other => other,
+    }
+    .to_string()
+}
+
+/// The playing track's stream changed without a track change, e.g. after a
+/// restart refetched it at another quality.
+#[cfg(target_os = "macos")]
+#[derive(Debug, Clone, Serialize)]
+pub struct StreamChangedPayload {
+    pub track_id: String,
+    #[serde(flatten)]
+    pub stream: StreamQuality,
+    /// See `TrackChangedPayload::playback_epoch`
+    pub playback_epoch: u64,
+}
+
+/// The playing track's codec parameters disagreed with its decoded audio, so
+/// the output was opened at the decoded rate and channel count instead.
+#[derive(Debug, Clone, Serialize)]
+pub struct FormatCorrectedPayload {
+    pub track_id: String,
+    /// As the codec parameters reported them
+    pub reported_sample_rate: u32,
+    pub reported_channels: usize,
+    /// As decoded, and as the output plays them
+    pub sample_rate: u32,
+    pub channels: usize,
+    /// See `TrackChangedPayload::playback_epoch`
+    pub playback_epoch: u64,
+}
+
+/// Why the current track changed, so listeners can tell skips from
+/// auto-advance (e.g. for scrobbling).
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum TrackChangeReason {
+    /// Play, next or previous requested from the UI
+    User,
+    /// The previous track finished and the queue advanced
+    AutoAdvance,
+    /// A system media key (next/previous)
+    MediaKey,
+    /// Resuming the track from a restored session
+    Restore,
+    /// A one-off track played over the queue with `play_track_now`
+    Interrupt,
+    /// The queue picked up again after an interrupt track
+    QueueResumed,
 }
 
 #[derive(Debug, Clone, Serialize)]
 pub struct StateChangedPayload {
     pub state: PlaybackState,
+    /// See `TrackChangedPayload::playback_epoch`
+    pub playback_epoch: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TrackEndedPayload {
+    /// See `TrackChangedPayload::playback_epoch`
+    pub playback_epoch: u64,
 }
 
 #[derive(Debug, Clone, Serialize, PartialEq)]
@@ -40,8 +235,126 @@ pub enum PlaybackState {
     Buffering,
 }
 
+/// A track failed to play. `skipped` is set when the queue moved past it.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlaybackErrorPayload {
+    pub track_id: String,
+    pub kind: String,
+    pub message: String,
+    pub skipped: bool,
+}
+
 #[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModeChangedPayload {
+    pub repeat_mode: RepeatMode,
+    pub shuffled: bool,
+}
+
+/// `volume` is the unmuted level, so it survives a mute/unmute round trip.
+#[derive(Debug, Clone, Serialize)]
+pub struct VolumeChangedPayload {
+    pub volume: f32,
+    pub muted: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct AuthStatePayload {
     pub authenticated: bool,
     pub user_id: Option<String>,
+    /// The saved login was rejected and the user has to log in again
+    pub reauth_required: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlaylistSaveProgressPayload {
+    pub playlist_id: String,
+    pub added: usize,
+    pub total: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FavoritesLoadProgressPayload {
+    pub loaded: usize,
+    pub pages: usize,
+}
+
+/// A cached album/playlist listing was refreshed and differs from what was served.
+#[derive(Debug, Clone, Serialize)]
+pub struct MetadataUpdatedPayload {
+    pub kind: CacheKind,
+    pub id: String,
+}
+
+/// Playback moved to a new output device after the system default changed.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutputDeviceChangedPayload {
+    pub device_name: String,
+}
+
+/// A playback setting changed. Only affects tracks fetched from now on.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SettingsChangedPayload {
+    pub audio_quality: String,
+}
+
+/// The next track finished downloading and will start without buffering.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PreloadReadyPayload {
+    pub track_id: String,
+}
+
+/// The track that will play after the current one ends, shortly before it
+/// does. Fields match `TrackChangedPayload`.
+#[derive(Debug, Clone, Serialize)]
+pub struct UpNextPayload {
+    pub track_id: String,
+    pub title: String,
+    pub artist: String,
+    pub album: String,
+    pub duration: f64,
+    #[serde(flatten)]
+    pub artwork: TrackArtwork,
+    pub session_epoch: u64,
+}
+
+/// The saved queue will start playing after `grace_ms` unless `cancel_autoresume`
+/// is called first.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AutoresumePendingPayload {
+    pub track_id: String,
+    pub grace_ms: u64,
+}
+
+/// The release radar found albums by followed artists newer than the ones
+/// it had seen.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NewReleasesFoundPayload {
+    pub releases: Vec<NewRelease>,
+}
+
+/// A track was added to or removed from favorites, from any view.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FavoritesChangedPayload {
+    pub track_id: String,
+    pub favorite: bool,
+}
+
+/// The catalog started refusing the user's country (`block` is set) or
+/// stopped (`block` is null). Catalog calls fail with `region_blocked`
+/// in between.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RegionBlockChangedPayload {
+    pub block: Option<RegionBlock>,
 }
@@ -0,0 +1,407 @@
+use crate::api::client::TidalClient;
+use crate::error::{AppError, AppResult};
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
+
+/// Age after which loaded ids are fetched again in the background, to pick
+/// up favorites changed in another app. Answers keep coming from memory
+/// meanwhile.
+const RESYNC_AFTER: Duration = Duration::from_secs(15 * 60);
+
+/// How long a failed load is answered with its error before trying again.
+const RETRY_FAILED_AFTER: Duration = Duration::from_secs(30);
+
+/// Ids of the user's favorite tracks. Listing them can take hundreds of
+/// pages, so they're loaded by a background task the first time they're
+/// needed; nothing is locked while it runs. Clones share the same ids.
+#[derive(Clone)]
+pub struct FavoriteIds {
+    shared: Arc<Shared>,
+    resync_after: Duration,
+    retry_failed_after: Duration,
+}
+
+struct Shared {
+    state: Mutex<State>,
+    /// Woken when a load finishes or the ids are cleared
+    changed: Notify,
+}
+
+#[derive(Default)]
+struct State {
+    ids: Option<HashSet<String>>,
+    loaded_at: Option<Instant>,
+    loading: bool,
+    /// Changes made while loading, applied over what the load returns
+    pending: Vec<(String, bool)>,
+    /// Why the last load failed, and when
+    failed: Option<(Instant, AppError)>,
+    /// Bumped by `clear`, so a load started before it is thrown away
+    generation: u64,
+}
+
+impl Default for FavoriteIds {
+    fn default() -> Self {
+        Self::with_timings(RESYNC_AFTER, RETRY_FAILED_AFTER)
+    }
+}
+
+impl FavoriteIds {
+    fn with_timings(resync_after: Duration, retry_failed_after: Duration) -> Self {
+        Self {
+            shared: Arc::new(Shared {
+                state: Mutex::new(State::default()),
+                changed: Notify::new(),
+            }),
+            resync_after,
+            retry_failed_after,
+        }
+    }
+
+    /// Whether `track_id` is a favorite, loading the ids first if needed.
+    pub async fn contains(&self, client: &Arc<TidalClient>, track_id: &str) -> AppResult<bool> {
+        self.with_ids(client, |ids| ids.contains(track_id)).await
+    }
+
+    /// Number of favorite tracks, loading the ids first if needed.
+    pub async fn count(&self, client: &Arc<TidalClient>) -> AppResult<usize> {
+        self.with_ids(client, HashSet::len).await
+    }
+
+    /// Whether `track_id` is a favorite, if the ids are loaded.
+    pub fn known(&self, track_id: &str) -> Option<bool> {
+        let state = self.shared.state.lock().unwrap();
+        state.ids.as_ref().map(|ids| ids.contains(track_id))
+    }
+
+    /// Number of favorite tracks, if the ids are loaded.
+    pub fn known_count(&self) -> Option<usize> {
+        let state = self.shared.state.lock().unwrap();
+        state.ids.as_ref().map(HashSet::len)
+    }
+
+    /// Record a favorite added or removed.
+    pub fn set(&self, track_id: &str, favorite: bool) {
+        let mut state = self.shared.state.lock().unwrap();
+        if state.loading {
+            state.pending.push((track_id.to_string(), favorite));
+        }
+        if let Some(ids) = state.ids.as_mut() {
+            if favorite {
+                ids.insert(track_id.to_string());
+            } else {
+                ids.remove(track_id);
+            }
+        }
+    }
+
+    /// Record tracks seen in the favorites collection.
+    pub fn extend<'a>(&self, track_ids: impl IntoIterator<Item = &'a str>) {
+        for track_id in track_ids {
+            self.set(track_id, true);
+        }
+    }
+
+    /// Forget the ids, e.g. when another user signs in. A load still
+    /// running is thrown away when it finishes.
+    pub fn clear(&self) {
+        let mut state = self.shared.state.lock().unwrap();
+        *state = State {
+            generation: state.generation + 1,
+            ..State::default()
+        };
+        drop(state);
+        self.shared.changed.notify_waiters();
+    }
+
+    async fn with_ids<R>(
+        &self,
+        client: &Arc<TidalClient>,
+        answer: impl FnOnce(&HashSet<String>) -> R,
+    ) -> AppResult<R> {
+        loop {
+            // Registered before checking, so a load finishing in between still wakes us
+            let changed = self.shared.changed.notified();
+            {
+                let mut state = self.shared.state.lock().unwrap();
+                let retry = state
+                    .failed
+                    .as_ref()
+                    .is_none_or(|(at, _)| at.elapsed() >= self.retry_failed_after);
+                let stale = state
+                    .loaded_at
+                    .is_none_or(|at| at.elapsed() >= self.resync_after);
+                if stale && retry {
+                    self.start_load(&mut state, client);
+                }
+                if let Some(ids) = &state.ids {
+                    return Ok(answer(ids));
+                }
+                if let Some((_, error)) = state.failed.as_ref().filter(|_| !retry) {
+                    return Err(repeat_error(error));
+                }
+            }
+            changed.await;
+        }
+    }
+
+    fn start_load(&self, state: &mut State, client: &Arc<TidalClient>) {
+        if state.loading {
+            return;
+        }
+        state.loading = true;
+        let generation = state.generation;
+        let shared = Arc::clone(&self.shared);
+        let client = Arc::clone(client);
+        tokio::spawn(async move {
+            let result = client.get_favorite_track_ids().await;
+            let mut state = shared.state.lock().unwrap();
+            if state.generation != generation {
+                return;
+            }
+            state.loading = false;
+            let pending = std::mem::take(&mut state.pending);
+            match result {
+                Ok(fetched) => {
+                    log::info!("Loaded {} favorite track ids", fetched.len());
+                    let mut ids: HashSet<String> = fetched.into_iter().collect();
+                    for (track_id, favorite) in pending {
+                        if favorite {
+                            ids.insert(track_id);
+                        } else {
+                            ids.remove(&track_id);
+                        }
+                    }
+                    state.ids = Some(ids);
+                    state.loaded_at = Some(Instant::now());
+                    state.failed = None;
+                }
+                // Ids loaded before stay in use, with the changes already applied
+                Err(e) => {
+                    log::warn!("Couldn't load favorite track ids: {}", e);
+                    state.failed = Some((Instant::now(), e));
+                }
+            }
+            drop(state);
+            shared.changed.notify_waiters();
+        });
+    }
+}
+
+/// The error a cached failure answers with: a copy of `error`, which can't
+/// be cloned. HTTP errors keep the status they came back with; one without a
+/// status becomes a network error rather than gaining one.
+fn repeat_error(error: &AppError) -> AppError {
+    match error {
+        AppError::Http(e) => match e.status() {
+            Some(status) => AppError::TidalApi {
+                status: status.as_u16(),
+                message: e.to_string(),
+            },
+            None => AppError::Network(e.to_string()),
+        },
+        AppError::Network(message) => AppError::Network(message.clone()),
+        AppError::Json(e) => AppError::Json(serde::de::Error::custom(e)),
+        AppError::Audio(message) => AppError::Audio(message.clone()),
+        AppError::Decode(message) => AppError::Decode(message.clone()),
+        AppError::AuthRequired => AppError::AuthRequired,
+        AppError::TokenExpired => AppError::TokenExpired,
+        AppError::ReauthRequired => AppError::ReauthRequired,
+        AppError::TidalApi { status, message } => AppError::TidalApi {
+            status: *status,
+            message: message.clone(),
+        },
+        AppError::Config(message) => AppError::Config(message.clone()),
+        AppError::NotFound(message) => AppError::NotFound(message.clone()),
+        AppError::ExplicitFiltered(track_id) => AppError::ExplicitFiltered(track_id.clone()),
+        AppError::TrackUnavailable { track_id, country } => AppError::TrackUnavailable {
+            track_id: track_id.clone(),
+            country: country.clone(),
+        },
+        AppError::SubscriptionRequired => AppError::SubscriptionRequired,
+        AppError::RegionBlocked { country } => AppError::RegionBlocked {
+            country: country.clone(),
+        },
+        AppError::Io(e) => AppError::Io(std::io::Error::new(e.kind(), e.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{mock_client, wait_until};
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    const FAVORITES: &str = "/userCollections/1/relationships/tracks";
+
+    fn page(ids: &[&str]) -> ResponseTemplate {
+        let data: Vec<_> = ids
+            .iter()
+            .map(|id| serde_json::json!({ "id": id, "type": "tracks" }))
+            .collect();
+        ResponseTemplate::new(200).set_body_json(serde_json::json!({ "data": data }))
+    }
+
+    #[tokio::test]
+    async fn callers_share_one_load_and_nothing_waits_on_a_lock() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path(FAVORITES))
+            .respond_with(page(&["1", "2"]).set_delay(Duration::from_millis(300)))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let client = mock_client(&server);
+        let favorites = FavoriteIds::default();
+
+        let first = tokio::spawn({
+            let (favorites, client) = (favorites.clone(), Arc::clone(&client));
+            async move { favorites.contains(&client, "1").await }
+        });
+        wait_until(Duration::from_secs(1), || {
+            favorites.shared.state.lock().unwrap().loading
+        })
+        .await;
+
+        // Answered straight away while the load runs
+        let started = Instant::now();
+        assert_eq!(favorites.known_count(), None);
+        favorites.set("3", true);
+        favorites.set("2", false);
+        assert!(started.elapsed() < Duration::from_millis(50));
+
+        let (count, second) =
+            tokio::join!(favorites.count(&client), favorites.contains(&client, "2"));
+        assert!(first.await.unwrap().unwrap());
+        // Changes made during the load are kept over its result
+        assert_eq!(count.unwrap(), 2);
+        assert!(!second.unwrap());
+        assert_eq!(favorites.known("3"), Some(true));
+        server.verify().await;
+    }
+
+    #[tokio::test]
+    async fn a_failed_load_is_answered_from_memory_for_a_while() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path(FAVORITES))
+            .respond_with(ResponseTemplate::new(403))
+            .up_to_n_times(1)
+            .expect(1)
+            .mount(&server)
+            .await;
+        let client = mock_client(&server);
+        let favorites = FavoriteIds::with_timings(RESYNC_AFTER, Duration::from_millis(300));
+
+        for _ in 0..3 {
+            let error = favorites.contains(&client, "1").await.unwrap_err();
+            assert_eq!(error.status(), Some(403));
+        }
+        server.verify().await;
+
+        Mock::given(method("GET"))
+            .and(path(FAVORITES))
+            .respond_with(page(&["1"]))
+            .mount(&server)
+            .await;
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        assert!(favorites.contains(&client, "1").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn repeated_errors_keep_their_kind_and_invent_no_status() {
+        // A port nothing listens on
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        drop(listener);
+        let network = AppError::from(reqwest::get(&url).await.unwrap_err());
+        let json = AppError::from(serde_json::from_str::<u32>("[").unwrap_err());
+        let io = AppError::from(std::io::Error::new(
+            std::io::ErrorKind::PermissionDenied,
+            "denied",
+        ));
+
+        for (error, kind) in [(network, "network"), (json, "json"), (io, "io")] {
+            let repeated = repeat_error(&error);
+            assert_eq!(repeated.kind(), kind);
+            assert_eq!(repeated.status(), None, "{}", kind);
+            assert_eq!(repeated.is_retryable(), error.is_retryable(), "{}", kind);
+            if kind != "network" {
+                assert_eq!(repeated.to_string(), error.to_string());
+            }
+        }
+
+        let api = AppError::TidalApi {
+            status: 503,
+            message: "down".into(),
+        };
+        let repeated = repeat_error(&api);
+        assert_eq!(repeated.status(), Some(503));
+        assert_eq!(repeated.to_string(), api.to_string());
+    }
+
+    #[tokio::test]
+    async fn old_ids_are_synced_again_in_the_background() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path(FAVORITES))
+            .respond_with(page(&["1"]))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        let client = mock_client(&server);
+        let favorites = FavoriteIds::with_timings(Duration::from_millis(200), RETRY_FAILED_AFTER);
+        assert!(favorites.contains(&client, "1").await.unwrap());
+
+        // Favorited in another app
+        Mock::given(method("GET"))
+            .and(path(FAVORITES))
+            .respond_with(page(&["1", "2"]).set_delay(Duration::from_millis(200)))
+            .mount(&server)
+            .await;
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        // The old answer comes back at once while the sync runs
+        assert!(!favorites.contains(&client, "2").await.unwrap());
+        wait_until(Duration::from_secs(2), || {
+            favorites.known("2") == Some(true)
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn clearing_drops_a_load_for_the_previous_user() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path(FAVORITES))
+            .respond_with(page(&["old"]).set_delay(Duration::from_millis(300)))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        let client = mock_client(&server);
+        let favorites = FavoriteIds::default();
+
+        let waiting = tokio::spawn({
+            let (favorites, client) = (favorites.clone(), Arc::clone(&client));
+            async move { favorites.contains(&client, "old").await }
+        });
+        wait_until(Duration::from_secs(1), || {
+            favorites.shared.state.lock().unwrap().loading
+        })
+        .await;
+        Mock::given(method("GET"))
+            .and(path(FAVORITES))
+            .respond_with(page(&["new"]))
+            .mount(&server)
+            .await;
+        favorites.clear();
+
+        // The waiting call moves on to a fresh load
+        assert!(!waiting.await.unwrap().unwrap());
+        tokio::time::sleep(Duration::from_millis(400)).await;
+        assert_eq!(favorites.known("old"), Some(false));
+        assert_eq!(favorites.known("new"), Some(true));
+    }
+}
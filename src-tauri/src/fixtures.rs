@@ -0,0 +1,169 @@
+//! Audio files generated for unit tests and benchmarks.
+
+use crate::audio::seek_index::crc8;
+
+/// A 16-bit PCM WAV file holding a 440 Hz tone.
+pub fn wav(sample_rate: u32, channels: u16, frames: u32) -> Vec<u8> {
+    let data_len = frames * channels as u32 * 2;
+    let mut out = Vec::with_capacity(44 + data_len as usize);
+    out.extend_from_slice(b"RIFF");
+    out.extend_from_slice(&(36 + data_len).to_le_bytes());
+    out.extend_from_slice(b"WAVEfmt ");
+    out.extend_from_slice(&16u32.to_le_bytes());
+    out.extend_from_slice(&1u16.to_le_bytes());
+    out.extend_from_slice(&channels.to_le_bytes());
+    out.extend_from_slice(&sample_rate.to_le_bytes());
+    out.extend_from_slice(&(sample_rate * channels as u32 * 2).to_le_bytes());
+    out.extend_from_slice(&(channels * 2).to_le_bytes());
+    out.extend_from_slice(&16u16.to_le_bytes());
+    out.extend_from_slice(b"data");
+    out.extend_from_slice(&data_len.to_le_bytes());
+    for frame in 0..frames {
+        let value = tone(frame, sample_rate);
+        for _ in 0..channels {
+            out.extend_from_slice(&value.to_le_bytes());
+        }
+    }
+    out
+}
+
+/// Sample `frame` of the 440 Hz test tone.
+pub fn tone(frame: u32, sample_rate: u32) -> i16 {
+    let t = frame as f32 / sample_rate as f32;
+    ((t * 440.0 * std::f32::consts::TAU).sin() * 8000.0) as i16
+}
+
+/// Frames per block in `flac` files.
+pub const FLAC_BLOCK: u32 = 4096;
+
+/// A 16-bit FLAC file holding the same tone as `wav`, stored uncompressed in
+/// blocks of `FLAC_BLOCK` frames.
+pub fn flac(sample_rate: u32, channels: u16, frames: u32) -> Vec<u8> {
+    let mut out = b"fLaC".to_vec();
+    // STREAMINFO, the last metadata block: 34 bytes
+    out.extend_from_slice(&[0x80, 0, 0, 34]);
+    out.extend_from_slice(&(FLAC_BLOCK as u16).to_be_bytes());
+    out.extend_from_slice(&(FLAC_BLOCK as u16).to_be_bytes());
+    // Frame sizes unknown
+    out.extend_from_slice(&[0; 6]);
+    let info =
+        (sample_rate as u64) << 44 | ((channels as u64 - 1) << 41) | (15 << 36) | frames as u64;
+    out.extend_from_slice(&info.to_be_bytes());
+    // No MD5
+    out.extend_from_slice(&[0; 16]);
+
+    for (number, start) in (0..frames).step_by(FLAC_BLOCK as usize).enumerate() {
+        let len = FLAC_BLOCK.min(frames - start);
+        let frame_start = out.len();
+        // Fixed block size; size from the header's end, rate from STREAMINFO
+        out.extend_from_slice(&[0xFF, 0xF8, 0x70, ((channels as u8 - 1) << 4) | 0x08]);
+        // Frame number, UTF-8 coded
+        match number {
+            0..=0x7F => out.push(number as u8),
+            _ => out.extend_from_slice(&[0xC0 | (number >> 6) as u8, 0x80 | (number & 0x3F) as u8]),
+        }
+        out.extend_from_slice(&(len as u16 - 1).to_be_bytes());
+        let crc = crc8(&out[frame_start..]);
+        out.push(crc);
+        // Verbatim subframe, the same for every channel
+        let mut subframe = vec![0x02];
+        for frame in start..start + len {
+            subframe.extend_from_slice(&tone(frame, sample_rate).to_be_bytes());
+        }
+        for _ in 0..channels {
+            out.extend_from_slice(&subframe);
+        }
+        let crc = crc16(&out[frame_start..]);
+        out.extend_from_slice(&crc.to_be_bytes());
+    }
+    out
+}
+
+fn crc16(bytes: &[u8]) -> u16 {
+    bytes.iter().fold(0u16, |mut crc, &byte| {
+        crc ^= (byte as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x8005
+            } else {
+                crc << 1
+            };
+        }
+        crc
+    })
+}
+
+/// Raw AAC-LC in ADTS frames of 1024 silent samples each. Every channel
+/// element codes no spectral data, which decodes to silence.
+pub fn adts_silence(sample_rate: u32, channels: u16, aac_frames: u32) -> Vec<u8> {
+    const RATES: [u32; 13] = [
+        96000, 88200, 64000, 48000, 44100, 32000, 24000, 22050, 16000, 12000, 11025, 8000, 7350,
+    ];
+    let rate_index = RATES
+        .iter()
+        .position(|&r| r == sample_rate)
+        .expect("ADTS sample rate") as u64;
+
+    // One empty individual channel stream: global gain, then a long window
+    // with no scale factor bands and no pulse, TNS or gain control data
+    let ics = |bits: &mut Vec<bool>| {
+        push_bits(bits, 100, 8);
+        push_bits(bits, 0, 11);
+        push_bits(bits, 0, 3);
+    };
+    let mut block = Vec::new();
+    match channels {
+        1 => {
+            push_bits(&mut block, 0, 3 + 4);
+            ics(&mut block);
+        }
+        2 => {
+            // Channel pair without a common window
+            push_bits(&mut block, 1, 3);
+            push_bits(&mut block, 0, 4 + 1);
+            ics(&mut block);
+            ics(&mut block);
+        }
+        _ => panic!("ADTS fixture supports mono and stereo"),
+    }
+    // END element
+    push_bits(&mut block, 7, 3);
+    let payload = pack_bits(&block);
+
+    let frame_len = 7 + payload.len() as u64;
+    let mut header = Vec::new();
+    push_bits(&mut header, 0xFFF, 12);
+    // MPEG-4, layer 0, no CRC, AAC LC
+    push_bits(&mut header, 0b0001, 4);
+    push_bits(&mut header, 1, 2);
+    push_bits(&mut header, rate_index, 4);
+    push_bits(&mut header, 0, 1);
+    push_bits(&mut header, channels as u64, 3);
+    push_bits(&mut header, 0, 4);
+    push_bits(&mut header, frame_len, 13);
+    push_bits(&mut header, 0x7FF, 11);
+    push_bits(&mut header, 0, 2);
+    let header = pack_bits(&header);
+
+    let mut out = Vec::new();
+    for _ in 0..aac_frames {
+        out.extend_from_slice(&header);
+        out.extend_from_slice(&payload);
+    }
+    out
+}
+
+fn push_bits(bits: &mut Vec<bool>, value: u64, count: u32) {
+    bits.extend((0..count).rev().map(|i| value >> i & 1 == 1));
+}
+
+/// Pack bits MSB first, zero-padding the last byte.
+fn pack_bits(bits: &[bool]) -> Vec<u8> {
+    bits.chunks(8)
+        .map(|byte| {
+            byte.iter()
+                .enumerate()
+                .fold(0u8, |acc, (i, &bit)| acc | (bit as u8) << (7 - i))
+        })
+        .collect()
+}
@@ -0,0 +1,181 @@
+use crate::config::AppConfig;
+use crate::error::AppResult;
+use crate::resource_usage::{dir_usage, DiskUsage};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Used when the CDN sends no `max-age`.
+const DEFAULT_MAX_AGE_SECS: i64 = 24 * 60 * 60;
+
+/// Images kept on disk; the least recently fetched are dropped beyond this.
+const MAX_ENTRIES: usize = 2000;
+
+/// Stores between directory scans for `prune`. The cache can run this far
+/// over `MAX_ENTRIES` before it is trimmed.
+const PRUNE_EVERY: usize = 100;
+
+/// Stores this session; the first and every `PRUNE_EVERY`th one prune.
+static STORES: AtomicUsize = AtomicUsize::new(0);
+
+/// Validators and freshness for a cached image, stored next to its bytes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImageMeta {
+    pub url: String,
+    pub content_type: String,
+    #[serde(default)]
+    pub etag: Option<String>,
+    #[serde(default)]
+    pub last_modified: Option<String>,
+    pub fetched_at: DateTime<Utc>,
+    pub max_age_secs: i64,
+}
+
+impl ImageMeta {
+    pub fn is_fresh(&self) -> bool {
+        Utc::now() - self.fetched_at < chrono::Duration::seconds(self.max_age_secs)
+    }
+}
+
+/// What a response's `Cache-Control` header allows.
+pub struct CachePolicy {
+    pub store: bool,
+    pub max_age_secs: i64,
+}
+
+impl CachePolicy {
+    pub fn from_header(cache_control: Option<&str>) -> Self {
+        let mut policy = Self {
+            store: true,
+            max_age_secs: DEFAULT_MAX_AGE_SECS,
+        };
+        for directive in cache_control.unwrap_or_default().split(',') {
+            let directive = directive.trim().to_ascii_lowercase();
+            if directive == "no-store" {
+                policy.store = false;
+            } else if directive == "no-cache" {
+                // Storable, but must be revalidated before every use
+                policy.max_age_secs = 0;
+            } else if let Some(secs) = directive.strip_prefix("max-age=") {
+                if let Ok(secs) = secs.trim_matches('"').parse::<i64>() {
+                    policy.max_age_secs = secs.max(0);
+                }
+            }
+        }
+        policy
+    }
+}
+
+/// Proxied artwork stored under the cache dir's `image_cache` as a `.bin`
+/// file with a `.json` sidecar, both named by the SHA-256 of the URL.
+///
+/// Everything here is blocking file IO; async callers go through
+/// `spawn_blocking`.
+pub struct ImageCache;
+
+impl ImageCache {
+    pub fn dir() -> AppResult<PathBuf> {
+        Ok(AppConfig::cache_dir()?.join("image_cache"))
+    }
+
+    fn paths(url: &str) -> AppResult<(PathBuf, PathBuf)> {
+        let key: String = Sha256::digest(url.as_bytes())
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect();
+        let dir = Self::dir()?;
+        Ok((
+            dir.join(format!("{}.json", key)),
+            dir.join(format!("{}.bin", key)),
+        ))
+    }
+
+    /// Read a cached image. A missing or unreadable entry is treated as a miss.
+    pub fn load(url: &str) -> Option<(ImageMeta, Vec<u8>)> {
+        let (meta_path, data_path) = Self::paths(url).ok()?;
+        let meta: ImageMeta = serde_json::from_str(&std::fs::read_to_string(meta_path).ok()?)
+            .map_err(|e| log::warn!("Ignoring corrupt image cache for {}: {}", url, e))
+            .ok()?;
+        let bytes = std::fs::read(data_path).ok()?;
+        Some((meta, bytes))
+    }
+
+    /// Whether a fresh copy is cached, reading only the sidecar.
+    pub fn is_fresh(url: &str) -> bool {
+        let Ok((meta_path, _)) = Self::paths(url) else {
+            return false;
+        };
+        std::fs::read_to_string(meta_path)
+            .ok()
+            .and_then(|content| serde_json::from_str::<ImageMeta>(&content).ok())
+            .is_some_and(|meta| meta.is_fresh())
+    }
+
+    pub fn store(meta: &ImageMeta, bytes: &[u8]) -> AppResult<()> {
+        std::fs::create_dir_all(Self::dir()?)?;
+        let (meta_path, data_path) = Self::paths(&meta.url)?;
+        std::fs::write(data_path, bytes)?;
+        std::fs::write(meta_path, serde_json::to_string(meta)?)?;
+        if STORES.fetch_add(1, Ordering::Relaxed) % PRUNE_EVERY == 0 {
+            Self::prune();
+        }
+        Ok(())
+    }
+
+    /// Rewrite only the sidecar, after a `304 Not Modified`.
+    pub fn touch(meta: &ImageMeta) -> AppResult<()> {
+        let (meta_path, _) = Self::paths(&meta.url)?;
+        std::fs::write(meta_path, serde_json::to_string(meta)?)?;
+        Ok(())
+    }
+
+    /// Disk space taken by cached images and their sidecars.
+    pub fn usage() -> DiskUsage {
+        Self::dir().map(|dir| dir_usage(&dir)).unwrap_or_default()
+    }
+
+    pub fn clear() -> AppResult<()> {
+        let dir = Self::dir()?;
+        if dir.exists() {
+            std::fs::remove_dir_all(&dir)?;
+        }
+        // Images used to be kept with the config
+        let legacy = AppConfig::config_dir()?.join("image_cache");
+        if legacy.exists() {
+            std::fs::remove_dir_all(&legacy)?;
+        }
+        Ok(())
+    }
+
+    pub fn invalidate(url: &str) {
+        if let Ok((meta_path, data_path)) = Self::paths(url) {
+            let _ = std::fs::remove_file(meta_path);
+            let _ = std::fs::remove_file(data_path);
+        }
+    }
+
+    /// Keep at most `MAX_ENTRIES` images, dropping the least recently fetched.
+    fn prune() {
+        let Ok(dir) = Self::dir().and_then(|d| Ok(std::fs::read_dir(d)?)) else {
+            return;
+        };
+        let mut sidecars: Vec<(std::time::SystemTime, PathBuf)> = dir
+            .filter_map(|e| e.ok())
+            .map(|e| e.path())
+            .filter(|p| p.extension().is_some_and(|ext| ext == "json"))
+            .filter_map(|p| Some((p.metadata().ok()?.modified().ok()?, p)))
+            .collect();
+        if sidecars.len() <= MAX_ENTRIES {
+            return;
+        }
+        sidecars.sort_by_key(|(modified, _)| *modified);
+        let excess = sidecars.len() - MAX_ENTRIES;
+        for (_, meta_path) in sidecars.into_iter().take(excess) {
+            let _ = std::fs::remove_file(meta_path.with_extension("bin"));
+            let _ = std::fs::remove_file(meta_path);
+        }
+    }
+}
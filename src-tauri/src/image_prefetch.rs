@@ -0,0 +1,166 @@
+use std::collections::HashSet;
+use std::sync::Mutex;
+use tokio::sync::Notify;
+
+/// Artwork waiting to be downloaded into the image cache. Higher priorities
+/// go first, oldest first within a priority.
+#[derive(Default)]
+pub struct PrefetchQueue {
+    pending: Mutex<Pending>,
+    ready: Notify,
+}
+
+#[derive(Default)]
+struct Pending {
+    /// (priority, url) in arrival order
+    entries: Vec<(u8, String)>,
+    in_flight: HashSet<String>,
+}
+
+impl PrefetchQueue {
+    /// Queue `urls` at `priority`. Pending entries of a lower priority are
+    /// dropped: a newly visible range makes the old lookahead stale. URLs
+    /// already queued or downloading are skipped. Returns how many were queued.
+    pub fn push_batch(&self, urls: Vec<String>, priority: u8) -> usize {
+        let mut pending = self.pending.lock().unwrap();
+        let before = pending.entries.len();
+        pending.entries.retain(|(p, _)| *p >= priority);
+        let cancelled = before - pending.entries.len();
+        if cancelled > 0 {
+            log::debug!("Cancelled {} lower-priority artwork prefetches", cancelled);
+        }
+
+        let mut added = 0;
+        for url in urls {
+            let queued = pending.entries.iter().any(|(_, u)| *u == url);
+            if queued || pending.in_flight.contains(&url) {
+                continue;
+            }
+            pending.entries.push((priority, url));
+            added += 1;
+        }
+        drop(pending);
+
+        if added > 0 {
+            self.ready.notify_waiters();
+        }
+        added
+    }
+
+    /// Wait for the next URL to download. Call `finish` once it's done.
+    pub async fn next(&self) -> String {
+        loop {
+            // Registered before checking, so a push in between still wakes us
+            let notified = self.ready.notified();
+            if let Some(url) = self.take_next() {
+                return url;
+            }
+            notified.await;
+        }
+    }
+
+    fn take_next(&self) -> Option<String> {
+        let mut pending = self.pending.lock().unwrap();
+        let top = pending.entries.iter().map(|(p, _)| *p).max()?;
+        let index = pending.entries.iter().position(|(p, _)| *p == top)?;
+        let (_, url) = pending.entries.remove(index);
+        pending.in_flight.insert(url.clone());
+        Some(url)
+    }
+
+    pub fn finish(&self, url: &str) {
+        self.pending.lock().unwrap().in_flight.remove(url);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    fn urls(names: &[&str]) -> Vec<String> {
+        names.iter().map(|n| n.to_string()).collect()
+    }
+
+    fn drain(queue: &PrefetchQueue) -> Vec<String> {
+        std::iter::from_fn(|| queue.take_next()).collect()
+    }
+
+    #[test]
+    fn higher_priorities_go_first_then_oldest_first() {
+        let queue = PrefetchQueue::default();
+        queue.push_batch(urls(&["x", "y"]), 5);
+        queue.push_batch(urls(&["a", "b"]), 1);
+        queue.push_batch(urls(&["c", "d"]), 1);
+
+        assert_eq!(drain(&queue), ["x", "y", "a", "b", "c", "d"]);
+    }
+
+    #[test]
+    fn a_higher_priority_batch_cancels_lower_pending_entries() {
+        let queue = PrefetchQueue::default();
+        queue.push_batch(urls(&["low1", "low2"]), 1);
+        queue.push_batch(urls(&["mid"]), 3);
+
+        assert_eq!(queue.push_batch(urls(&["top"]), 3), 1);
+        assert_eq!(drain(&queue), ["mid", "top"]);
+
+        // A lower batch leaves the higher entries alone
+        queue.push_batch(urls(&["top2"]), 9);
+        queue.push_batch(urls(&["low3"]), 1);
+        assert_eq!(drain(&queue), ["top2", "low3"]);
+    }
+
+    #[test]
+    fn queued_and_downloading_urls_are_not_queued_again() {
+        let queue = PrefetchQueue::default();
+        assert_eq!(queue.push_batch(urls(&["a", "b", "a"]), 1), 2);
+        assert_eq!(queue.take_next().as_deref(), Some("a"));
+
+        // "a" is downloading and "b" still queued
+        assert_eq!(queue.push_batch(urls(&["a", "b", "c"]), 1), 1);
+        queue.finish("a");
+        assert_eq!(queue.push_batch(urls(&["a"]), 1), 1);
+        assert_eq!(drain(&queue), ["b", "c", "a"]);
+    }
+
+    #[tokio::test]
+    async fn a_waiting_worker_wakes_for_a_new_batch() {
+        let queue = Arc::new(PrefetchQueue::default());
+        let worker = tokio::spawn({
+            let queue = Arc::clone(&queue);
+            async move { queue.next().await }
+        });
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(!worker.is_finished());
+
+        queue.push_batch(urls(&["a"]), 1);
+        let url = tokio::time::timeout(Duration::from_secs(1), worker)
+            .await
+            .expect("worker still waiting")
+            .unwrap();
+        assert_eq!(url, "a");
+    }
+
+    #[tokio::test]
+    async fn every_worker_gets_a_different_url() {
+        let queue = Arc::new(PrefetchQueue::default());
+        let workers: Vec<_> = (0..3)
+            .map(|_| {
+                let queue = Arc::clone(&queue);
+                tokio::spawn(async move { queue.next().await })
+            })
+            .collect();
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        queue.push_batch(urls(&["a", "b", "c", "d"]), 1);
+
+        let mut taken = Vec::new();
+        for worker in workers {
+            taken.push(worker.await.unwrap());
+        }
+        taken.sort();
+        assert_eq!(taken, ["a", "b", "c"]);
+        assert_eq!(drain(&queue), ["d"]);
+    }
+}
@@ -2,19 +2,62 @@ mod api;
 mod audio;
 mod commands;
 mod config;
+mod crash;
+mod debounce;
+mod discovery_seeds;
 mod error;
 mod events;
+mod favorite_ids;
+#[cfg(any(test, feature = "bench"))]
+mod fixtures;
+mod image_cache;
+mod image_prefetch;
 #[cfg(target_os = "macos")]
 mod macos;
+mod metadata_cache;
+mod palette;
+mod playlist_prefs;
+mod release_radar;
+mod remote_control;
+mod resource_usage;
+#[cfg(test)]
+mod test_support;
+mod text;
+
+/// Internals used by the benchmarks in `benches/`.
+#[cfg(feature = "bench")]
+#[doc(hidden)]
+pub mod bench {
+    pub use crate::api::models::Track;
+    pub use crate::api::search::{parse_items_from_included, parse_search_results};
+    pub use crate::audio::decoder::AudioDecoder;
+    pub use crate::audio::queue::PlaybackQueue;
+    pub use crate::audio::seek_index::FlacSeekIndex;
+    pub use crate::audio::stream_source::HttpStreamSource;
+    pub use crate::fixtures::{adts_silence, flac, tone, wav, FLAC_BLOCK};
+}
 
 use api::client::TidalClient;
 use api::models::Track;
-use audio::player::AudioPlayer;
-use audio::preloader::PreloadedTrack;
-use audio::queue::PlaybackQueue;
+use audio::gain::GainOffsets;
+#[cfg(target_os = "macos")]
+use audio::interruption::InterruptionMode;
+use audio::output::OutputBackend;
+use audio::player::{AudioPlayer, OpenedTrack};
+use audio::preloader::{PreloadTrigger, PreloadedTrack, PRELOAD_THRESHOLD_SECS};
+use audio::queue::{InterruptTrack, PersistedQueueState, PlaybackQueue, RepeatMode};
+use audio::track_stats::{TrackStatsStore, EARLY_SKIP_SECS};
 use config::AppConfig;
+use debounce::Debouncer;
+use favorite_ids::FavoriteIds;
+use image_prefetch::PrefetchQueue;
+use palette::ArtworkPalette;
+use playlist_prefs::PlaylistPrefsStore;
+use release_radar::ReleaseRadarStore;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use tokio::sync::{Mutex, RwLock};
+use tokio::sync::{watch, Mutex, RwLock};
 
 /// Wrapper to make ObjC retained objects Send+Sync.
 /// These tokens are only kept alive, never accessed across threads.
@@ -32,11 +75,484 @@ pub struct AppState {
     pub current_track: Arc<RwLock<Option<Track>>>,
     pub pkce_verifier: Mutex<Option<String>>,
     pub preloaded_track: Mutex<Option<PreloadedTrack>>,
+    pub preload_trigger: std::sync::Mutex<PreloadTrigger>,
+    /// Set while a `play_track_now` track is playing over the queue
+    pub interrupt: std::sync::Mutex<Option<InterruptTrack>>,
+    pub gain_offsets: RwLock<GainOffsets>,
+    pub playlist_prefs: RwLock<PlaylistPrefsStore>,
+    pub track_stats: Arc<std::sync::Mutex<TrackStatsStore>>,
+    /// What the playing track was actually fetched as. A quality change only
+    /// affects later fetches, so this can differ from the configured quality.
+    pub playing_stream: std::sync::Mutex<Option<events::StreamQuality>>,
+    /// Artwork palettes keyed by image URL
+    pub artwork_palettes: std::sync::Mutex<HashMap<String, ArtworkPalette>>,
+    pub image_prefetch: Arc<PrefetchQueue>,
+    /// Deep links received before the frontend was listening; None once it
+    /// has collected them
+    pub pending_deep_links: std::sync::Mutex<Option<Vec<String>>>,
+    /// Tracks that failed as unavailable this session, kept out of suggestions
+    pub session_blocklist: std::sync::Mutex<HashSet<String>>,
+    /// Set by `cancel_autoresume` during the launch autoresume grace window
+    pub autoresume_cancelled: AtomicBool,
+    /// Set when another app taking audio focus paused playback. Any pause or
+    /// play by the user clears it, so regaining focus doesn't undo their choice.
+    pub paused_for_focus: AtomicBool,
+    /// The remote control server, while it is enabled and listening
+    pub remote_server: Mutex<Option<remote_control::RemoteServer>>,
+    pub release_radar: RwLock<ReleaseRadarStore>,
+    /// Held while the release radar runs, so runs don't overlap
+    pub release_radar_check: Mutex<()>,
+    /// Ids of the user's favorite tracks, loaded when first needed
+    favorite_ids: FavoriteIds,
+    /// Set once `PLAYBACK_UPNEXT` went out for the playing track
+    pub upnext_announced: AtomicBool,
+    /// When a queue command last changed the queue
+    queue_edited_at: std::sync::Mutex<Option<std::time::Instant>>,
+    /// Bumped by every `begin_play_attempt`; earlier attempts see they were superseded
+    play_generation: watch::Sender<u64>,
+    /// Config save scheduled by `schedule_config_save`
+    config_save: Debouncer,
+    /// Stats save scheduled by `update_track_stats`
+    stats_save: Debouncer,
     /// Keep media key handler tokens alive for the lifetime of the app (macOS only)
     #[cfg(target_os = "macos")]
     _media_key_tokens: std::sync::Mutex<SendRetainedTokens>,
 }
 
+impl AppState {
+    /// Re-apply the user's gain offset to the track that is playing.
+    pub async fn apply_gain_for(&self, track: &Track) {
+        let db = self.gain_offsets.read().await.effective_db(track);
+        self.audio_player.read().await.set_gain_db(db);
+    }
+
+    /// Set the gain offset a track starts with. Called before its stream is
+    /// opened, so the change lands exactly at the track boundary.
+    pub async fn prepare_gain_for(&self, track: &Track) {
+        let db = self.gain_offsets.read().await.effective_db(track);
+        if db != 0.0 {
+            log::info!("Applying {:+.1} dB gain offset to {}", db, track.id);
+        }
+        self.audio_player.read().await.set_next_gain_db(db);
+    }
+
+    /// Whether explicit tracks should be filtered out and skipped.
+    pub async fn hide_explicit(&self) -> bool {
+        self.tidal_client.config().read().await.hide_explicit
+    }
+
+    /// Drop suggested tracks in a language outside the configured allowlist.
+    /// Tracks without language metadata are kept.
+    pub async fn retain_allowed_languages(&self, tracks: &mut Vec<Track>, source: &str) {
+        let config = self.tidal_client.config().read().await;
+        let filtered = api::models::retain_languages(tracks, &config.language_allowlist);
+        if filtered > 0 {
+            log::info!("Filtered {} tracks from {} by language", filtered, source);
+        }
+    }
+
+    /// Keep a track that turned out to be unavailable out of suggestions for
+    /// the rest of the session.
+    pub fn block_for_session(&self, track_id: &str) {
+        self.session_blocklist
+            .lock()
+            .unwrap()
+            .insert(track_id.to_string());
+    }
+
+    /// Drop suggested tracks that can't be streamed here, either per the API's
+    /// availability or because they already failed this session.
+    pub fn retain_playable(&self, tracks: &mut Vec<Track>) {
+        let blocked = self.session_blocklist.lock().unwrap();
+        tracks.retain(|t| t.streamable && !blocked.contains(&t.id));
+    }
+
+    /// Apply `update` to the listening stats and schedule a save. A track
+    /// change records a play and maybe a skip; they are written together.
+    fn update_track_stats(&self, update: impl FnOnce(&mut TrackStatsStore)) {
+        update(&mut self.track_stats.lock().unwrap());
+        let stats = Arc::clone(&self.track_stats);
+        self.stats_save.schedule(async move {
+            if let Err(e) = stats.lock().unwrap().save() {
+                log::warn!("Failed to save track stats: {}", e);
+            }
+        });
+    }
+
+    pub fn record_track_started(&self, track_id: &str) {
+        self.update_track_stats(|stats| stats.record_play(track_id));
+    }
+
+    pub fn record_track_completed(&self, track_id: &str) {
+        self.update_track_stats(|stats| stats.record_completed(track_id));
+    }
+
+    /// Count a skip when the current track is left for `next_id` within its
+    /// first `EARLY_SKIP_SECS`. Restarting the same track is not a skip.
+    pub async fn record_early_skip(&self, next_id: &str) {
+        let current = self.current_track.read().await;
+        let Some(current_id) = current.as_ref().map(|t| t.id.clone()) else {
+            return;
+        };
+        drop(current);
+        if current_id == next_id {
+            return;
+        }
+        let position = self.audio_player.read().await.position_seconds();
+        if position < EARLY_SKIP_SECS {
+            self.update_track_stats(|stats| stats.record_skip(&current_id));
+        }
+    }
+
+    /// Record the stream that just started, with the bit depth and sample
+    /// rate the decoder probed in place of the manifest's and the codec and
+    /// container it found, and return it for the track-changed event. If the
+    /// output had to be opened for a different rate or channel count than
+    /// the codec parameters reported, listeners are told.
+    pub async fn settle_stream(
+        &self,
+        app: &tauri::AppHandle,
+        track_id: &str,
+        mut stream: events::StreamQuality,
+    ) -> events::StreamQuality {
+        let player = self.audio_player.read().await;
+        let (source, playback_epoch) = (player.source_format(), player.playback_epoch());
+        drop(player);
+        if let Some((reported_sample_rate, reported_channels)) = source.corrected_from {
+            use tauri::Emitter;
+            let _ = app.emit(
+                events::PLAYBACK_FORMAT_CORRECTED,
+                events::FormatCorrectedPayload {
+                    track_id: track_id.to_string(),
+                    reported_sample_rate,
+                    reported_channels,
+                    sample_rate: source.sample_rate,
+                    channels: source.channels,
+                    playback_epoch,
+                },
+            );
+        }
+        let claimed = stream.quality.clone();
+        if stream.reconcile(&source) {
+            log::info!(
+                "Stream probed as {:?} ({:?} in {:?}), manifest said {:?} ({:?})",
+                stream.quality,
+                stream.detected_codec,
+                stream.container,
+                claimed,
+                stream.codec
+            );
+        }
+        *self.playing_stream.lock().unwrap() = Some(stream.clone());
+        stream
+    }
+
+    /// The configured streaming quality, used for new manifest fetches.
+    pub async fn audio_quality(&self) -> String {
+        self.tidal_client
+            .config()
+            .read()
+            .await
+            .audio_quality
+            .clone()
+    }
+
+    /// Current playback session epoch, carried by track and progress events.
+    pub fn session_epoch(&self) -> u64 {
+        *self.play_generation.borrow()
+    }
+
+    /// Start a play attempt, superseding any earlier one that is still fetching
+    /// its manifest.
+    pub fn begin_play_attempt(&self) -> PlayAttempt {
+        PlayAttempt::begin(&self.play_generation)
+    }
+
+    /// Move upcoming tracks the user keeps skipping behind the rest, after
+    /// the queue was shuffled. Does nothing outside shuffle mode.
+    pub fn defer_habitual_skips(&self, queue: &mut PlaybackQueue) {
+        if queue.is_shuffled() {
+            let stats = self.track_stats.lock().unwrap();
+            queue.defer_upcoming(|t| stats.is_habitual_skip(&t.id));
+        }
+    }
+
+    /// Record that the user just changed the queue.
+    pub fn note_queue_edit(&self) {
+        *self.queue_edited_at.lock().unwrap() = Some(std::time::Instant::now());
+    }
+
+    /// Whether the user changed the queue within the last `window`.
+    pub fn queue_edited_within(&self, window: std::time::Duration) -> bool {
+        self.queue_edited_at
+            .lock()
+            .unwrap()
+            .is_some_and(|at| at.elapsed() < window)
+    }
+
+    /// Whether a track is a favorite. The first call loads every favorite
+    /// id; later ones answer from memory.
+    pub async fn is_favorite(&self, track_id: &str) -> error::AppResult<bool> {
+        self.favorite_ids
+            .contains(&self.tidal_client, track_id)
+            .await
+    }
+
+    /// Whether a track is a favorite, if the favorite ids are loaded.
+    pub fn known_favorite(&self, track_id: &str) -> Option<bool> {
+        self.favorite_ids.known(track_id)
+    }
+
+    /// Number of favorite tracks, counted from the favorite ids.
+    pub async fn favorite_count(&self) -> error::AppResult<usize> {
+        self.favorite_ids.count(&self.tidal_client).await
+    }
+
+    /// Number of favorite tracks if the ids are already loaded.
+    pub fn known_favorite_count(&self) -> Option<usize> {
+        self.favorite_ids.known_count()
+    }
+
+    /// Record a favorite added or removed.
+    pub fn set_favorite(&self, track_id: &str, favorite: bool) {
+        self.favorite_ids.set(track_id, favorite);
+    }
+
+    /// Record tracks just fetched from the favorites collection.
+    pub fn note_favorites(&self, tracks: &[Track]) {
+        self.favorite_ids
+            .extend(tracks.iter().map(|t| t.id.as_str()));
+    }
+
+    /// Forget the favorite ids, e.g. when another user signs in.
+    pub fn clear_favorites(&self) {
+        self.favorite_ids.clear();
+    }
+
+    /// Forget the listening stats in memory. A save still scheduled is
+    /// dropped; the saved file is left to the caller.
+    pub fn clear_track_stats(&self) {
+        self.stats_save.cancel();
+        *self.track_stats.lock().unwrap() = TrackStatsStore::default();
+    }
+
+    /// Clear the interrupt slot, returning the track that was interrupting.
+    pub fn take_interrupt(&self) -> Option<InterruptTrack> {
+        self.interrupt.lock().unwrap().take()
+    }
+
+    /// Append the next page from the queue's lazy source; see `queue::refill`.
+    pub async fn refill_queue(&self) -> bool {
+        audio::queue::refill(&self.playback_queue, &self.tidal_client).await
+    }
+
+    /// Drop the preloaded track, which aborts its in-flight download.
+    pub async fn discard_preloaded(&self) {
+        if let Some(preloaded) = self.preloaded_track.lock().await.take() {
+            log::info!("Discarding preloaded track {}", preloaded.track_id);
+        }
+    }
+
+    /// Abort every download task: the current stream's and the preloaded track's.
+    pub async fn abort_downloads(&self) {
+        self.audio_player.read().await.abort_download();
+        self.discard_preloaded().await;
+    }
+
+    /// Save the config once `CONFIG_SAVE_DELAY` has passed without another call,
+    /// so dragging the volume slider writes to disk once at the end.
+    pub fn schedule_config_save(&self) {
+        let client = Arc::clone(&self.tidal_client);
+        self.config_save.schedule(async move {
+            if let Err(e) = client.config().read().await.save() {
+                log::error!("Failed to save config: {}", e);
+            }
+        });
+    }
+
+    /// Coordinated shutdown: persist the queue and position, then release audio
+    /// and network resources. Called from the exit handler under a time budget.
+    pub async fn shutdown(&self) {
+        log::info!("Shutting down...");
+
+        let position = self.audio_player.read().await.position_seconds();
+        let queue = self.playback_queue.read().await;
+        if !queue.is_empty() {
+            let mut persisted = queue.persisted_state();
+            persisted.position = position;
+            if let Err(e) = persisted.save() {
+                log::error!("Failed to persist queue on shutdown: {}", e);
+            }
+        } else if queue.generation() > 0 {
+            // Cleared this session, so it mustn't come back on the next launch
+            if let Err(e) = PersistedQueueState::delete() {
+                log::error!("Failed to delete the saved queue on shutdown: {}", e);
+            }
+        }
+        // Otherwise nothing was queued this session; keep the last save
+        drop(queue);
+
+        // Abort downloads first so the decode thread isn't blocked on a read
+        // when stop() joins it.
+        self.abort_downloads().await;
+        self.audio_player.write().await.stop();
+
+        // Flushes any pending debounced save; cancel it so it can't run after exit
+        self.config_save.cancel();
+        if let Err(e) = self.tidal_client.config().read().await.save() {
+            log::error!("Failed to save config on shutdown: {}", e);
+        }
+        self.stats_save.cancel();
+        if let Err(e) = self.track_stats.lock().unwrap().save() {
+            log::error!("Failed to save track stats on shutdown: {}", e);
+        }
+
+        log::info!("Shutdown complete");
+    }
+}
+
+/// One request to start a track. A newer attempt supersedes it, so a slow
+/// manifest fetch for a track the user already skipped past is abandoned.
+pub struct PlayAttempt {
+    generation: u64,
+    latest: watch::Receiver<u64>,
+}
+
+impl PlayAttempt {
+    fn begin(generations: &watch::Sender<u64>) -> Self {
+        let mut generation = 0;
+        generations.send_modify(|g| {
+            *g += 1;
+            generation = *g;
+        });
+        PlayAttempt {
+            generation,
+            latest: generations.subscribe(),
+        }
+    }
+
+    pub fn is_current(&self) -> bool {
+        *self.latest.borrow() == self.generation
+    }
+
+    /// Resolves once a newer attempt has started.
+    pub async fn superseded(&mut self) {
+        let generation = self.generation;
+        // The sender lives in AppState, so this only errors during shutdown
+        let _ = self.latest.wait_for(|g| *g != generation).await;
+    }
+
+    /// Run `work` unless a newer attempt starts first. A superseded `work` is
+    /// dropped, cancelling any request it has in flight, and gives `None`, as
+    /// does one that finishes after it was superseded.
+    pub async fn unless_superseded<F: std::future::Future>(
+        &mut self,
+        work: F,
+    ) -> Option<F::Output> {
+        let output = tokio::select! {
+            output = work => output,
+            _ = self.superseded() => return None,
+        };
+        self.is_current().then_some(output)
+    }
+}
+
+/// Quiet period after the last change before `schedule_config_save` writes.
+const CONFIG_SAVE_DELAY: std::time::Duration = std::time::Duration::from_millis(1500);
+
+/// Quiet period after the last listening stats change before they are written.
+const STATS_SAVE_DELAY: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Consecutive unavailable tracks auto-advance skips before it stops trying.
+const MAX_UNAVAILABLE_SKIPS: usize = 10;
+
+/// Whether any window is on screen, from each window's visible and minimized
+/// state. A state that can't be read counts as showing, so a failure is never
+/// reported twice.
+#[cfg(any(target_os = "macos", test))]
+fn any_window_showing(windows: impl IntoIterator<Item = (Option<bool>, Option<bool>)>) -> bool {
+    windows
+        .into_iter()
+        .any(|(visible, minimized)| visible.unwrap_or(true) && !minimized.unwrap_or(false))
+}
+
+/// Whether a track that failed to start from a media key is skipped like
+/// another key press would: only while no window shows the error, and for at
+/// most `MAX_UNAVAILABLE_SKIPS` tracks in a row.
+#[cfg(any(target_os = "macos", test))]
+fn skips_failed_media_key(failed_skips: usize, window_showing: bool) -> bool {
+    !window_showing && failed_skips < MAX_UNAVAILABLE_SKIPS
+}
+
+/// Upcoming tracks left when a lazily loaded queue fetches its next page.
+const LAZY_REFILL_THRESHOLD: usize = 5;
+
+/// How often the API is probed while it is unreachable.
+const OFFLINE_PROBE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(15);
+
+/// Upper bound on how long quitting may spend in `AppState::shutdown`.
+const SHUTDOWN_BUDGET: std::time::Duration = std::time::Duration::from_secs(3);
+
+/// The "up next" notice is held back this long after a queue edit, since the
+/// UI already shows the queue the user is looking at.
+const UPNEXT_QUIET_AFTER_EDIT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Smallest position change reported on its own; anything less is a
+/// repeat of the previous progress event.
+const MIN_PROGRESS_DELTA: f64 = 0.05;
+
+/// What the last progress event said, to skip sending it again.
+struct ReportedProgress {
+    session_epoch: u64,
+    playback_epoch: u64,
+    playing: bool,
+    position: f64,
+    duration: f64,
+}
+
+impl ReportedProgress {
+    /// Whether this tells the UI nothing `last` didn't.
+    fn repeats(&self, last: &Self) -> bool {
+        self.session_epoch == last.session_epoch
+            && self.playback_epoch == last.playback_epoch
+            && self.playing == last.playing
+            && self.duration == last.duration
+            && (self.position - last.position).abs() < MIN_PROGRESS_DELTA
+    }
+}
+
+/// Picks which progress loop ticks go out as `PLAYBACK_PROGRESS`.
+#[derive(Default)]
+struct ProgressReporter {
+    last: Option<ReportedProgress>,
+    /// Seeks resolved as of the last tick, to report seeks made while paused
+    seeks_seen: u64,
+}
+
+impl ProgressReporter {
+    /// Whether to send `progress`. Playing ticks report the position
+    /// unless it repeats the last event; while paused, only where a seek
+    /// actually landed or a corrected duration is reported.
+    fn should_report(
+        &mut self,
+        progress: ReportedProgress,
+        seeks_resolved: u64,
+        duration_corrected: bool,
+    ) -> bool {
+        let paused_change = seeks_resolved != self.seeks_seen || duration_corrected;
+        self.seeks_seen = seeks_resolved;
+        let repeat = self
+            .last
+            .as_ref()
+            .is_some_and(|last| progress.repeats(last));
+        if repeat || !(progress.playing || paused_change) {
+            return false;
+        }
+        self.last = Some(progress);
+        true
+    }
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     env_logger::Builder::from_env(
@@ -44,40 +560,9 @@ pub fn run() {
     )
     .init();
 
-    // Install a panic hook that writes the panic message to a file.
-    // This captures the error before abort() kills the process when
-    // a panic occurs inside an extern "C" (ObjC) callback frame.
-    std::panic::set_hook(Box::new(|info| {
-        use std::io::Write;
-        let crash_path = dirs::home_dir()
-            .unwrap_or_default()
-            .join(".tauritidal")
-            .join("crash.log");
-        let _ = std::fs::create_dir_all(crash_path.parent().unwrap());
-        let msg = format!(
-            "PANIC at {}: {}\nBacktrace:\n{}\n---\n",
-            info.location()
-                .map(|l| format!("{}:{}:{}", l.file(), l.line(), l.column()))
-                .unwrap_or_else(|| "unknown".into()),
-            info.payload()
-                .downcast_ref::<&str>()
-                .copied()
-                .or_else(|| info.payload().downcast_ref::<String>().map(|s| s.as_str()))
-                .unwrap_or("(no message)"),
-            std::backtrace::Backtrace::force_capture(),
-        );
-        // Append so we capture the FIRST panic before the "cannot unwind" overwrites it
-        if let Ok(mut f) = std::fs::OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(&crash_path)
-        {
-            let _ = f.write_all(msg.as_bytes());
-        }
-        eprintln!("{}", msg);
-    }));
+    crash::install_panic_hook();
 
-    let config = AppConfig::load().unwrap_or_else(|e| {
+    let mut config = AppConfig::load().unwrap_or_else(|e| {
         log::warn!("Failed to load config: {}. Using defaults.", e);
         let default_config = AppConfig::default();
         // Save defaults so the config file exists for next launch
@@ -87,16 +572,29 @@ pub fn run() {
         default_config
     });
 
+    let output_backend = OutputBackend::resolve(config.audio_output);
+    // Start at the volume remembered for the device playback will open on
+    config.volume = config.volume_for(output_backend.device_name().as_deref());
+
     // Read volume/muted before wrapping config in Arc<RwLock>
-    let restored_volume = if config.muted { 0.0 } else { config.volume };
+    let restored_volume = config.volume;
+    let restored_muted = config.muted;
+    let device_volumes = config.device_volumes.clone();
+    let restored_crossfeed = config.crossfeed;
+    let start_minimized = config.start_minimized;
+    let autoresume = config.autoresume_on_launch;
+    let remote_control = config.remote_control.enabled;
 
     let config = Arc::new(RwLock::new(config));
     let tidal_client =
         Arc::new(TidalClient::new(Arc::clone(&config)).expect("Failed to create Tidal client"));
 
     let audio_player = Arc::new(RwLock::new({
-        let player = AudioPlayer::new().expect("Failed to initialize audio player");
-        player.set_volume(restored_volume);
+        let mut player =
+            AudioPlayer::new(output_backend).expect("Failed to initialize audio player");
+        player.set_device_volumes(device_volumes);
+        player.set_muted(restored_muted, restored_volume);
+        player.set_crossfeed(restored_crossfeed);
         player
     }));
 
@@ -108,9 +606,11 @@ pub fn run() {
     let queue_for_progress = Arc::clone(&playback_queue);
     let client_for_progress = Arc::clone(&tidal_client);
 
-    // Auto-acquire client credentials token on startup if no token exists
+    // Auto-refresh or acquire a token on startup
     let client_for_init = Arc::clone(&tidal_client);
-    let config_for_init = Arc::clone(&config);
+
+    let image_prefetch = Arc::new(PrefetchQueue::default());
+    let prefetch_for_init = Arc::clone(&image_prefetch);
 
     let app_state = AppState {
         tidal_client,
@@ -119,6 +619,41 @@ pub fn run() {
         current_track,
         pkce_verifier: Mutex::new(None),
         preloaded_track: Mutex::new(None),
+        preload_trigger: std::sync::Mutex::new(PreloadTrigger::default()),
+        interrupt: std::sync::Mutex::new(None),
+        gain_offsets: RwLock::new(GainOffsets::load().unwrap_or_else(|e| {
+            log::warn!("Failed to load gain offsets: {}", e);
+            GainOffsets::default()
+        })),
+        playlist_prefs: RwLock::new(PlaylistPrefsStore::load().unwrap_or_else(|e| {
+            log::warn!("Failed to load playlist prefs: {}", e);
+            PlaylistPrefsStore::default()
+        })),
+        track_stats: Arc::new(std::sync::Mutex::new(
+            TrackStatsStore::load().unwrap_or_else(|e| {
+                log::warn!("Failed to load track stats: {}", e);
+                TrackStatsStore::default()
+            }),
+        )),
+        artwork_palettes: std::sync::Mutex::new(HashMap::new()),
+        image_prefetch,
+        session_blocklist: std::sync::Mutex::new(HashSet::new()),
+        pending_deep_links: std::sync::Mutex::new(Some(Vec::new())),
+        autoresume_cancelled: AtomicBool::new(false),
+        paused_for_focus: AtomicBool::new(false),
+        remote_server: Mutex::new(None),
+        release_radar: RwLock::new(ReleaseRadarStore::load().unwrap_or_else(|e| {
+            log::warn!("Failed to load release radar: {}", e);
+            ReleaseRadarStore::default()
+        })),
+        release_radar_check: Mutex::new(()),
+        favorite_ids: FavoriteIds::default(),
+        upnext_announced: AtomicBool::new(false),
+        queue_edited_at: std::sync::Mutex::new(None),
+        playing_stream: std::sync::Mutex::new(None),
+        play_generation: watch::Sender::new(0),
+        config_save: Debouncer::new(CONFIG_SAVE_DELAY),
+        stats_save: Debouncer::new(STATS_SAVE_DELAY),
         #[cfg(target_os = "macos")]
         _media_key_tokens: std::sync::Mutex::new(SendRetainedTokens(Vec::new())),
     };
@@ -127,115 +662,157 @@ pub fn run() {
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_deep_link::init())
         .manage(app_state)
+        .on_window_event(|window, event| {
+            if let tauri::WindowEvent::CloseRequested { api, .. } = event {
+                // Stop CDN downloads so they don't outlive the window. Blocking
+                // the event loop on that could deadlock with a command holding
+                // the player lock, so the close is held until a task has done it.
+                api.prevent_close();
+                let window = window.clone();
+                tauri::async_runtime::spawn(async move {
+                    use tauri::Manager;
+                    window.state::<AppState>().abort_downloads().await;
+                    // destroy() closes without raising CloseRequested again
+                    if let Err(e) = window.destroy() {
+                        log::error!("Failed to close window: {}", e);
+                    }
+                });
+            }
+        })
         .setup(move |app| {
             let app_handle = app.handle().clone();
 
+            // Listings moved to the OS cache dir; drop the copy older versions left
+            tauri::async_runtime::spawn_blocking(metadata_cache::MetadataCache::remove_legacy_dir);
+
+            // Tell the UI when the saved login is rejected. Subscribed before
+            // the startup refresh below so its verdict isn't missed.
+            let reauth_client = Arc::clone(&client_for_init);
+            let reauth_handle = app.handle().clone();
+            let mut rejections = reauth_client.subscribe_reauth_required();
+            tauri::async_runtime::spawn(async move {
+                use tauri::Emitter;
+                while rejections.changed().await.is_ok() {
+                    if !*rejections.borrow_and_update() {
+                        continue;
+                    }
+                    let user_id = reauth_client.config().read().await.user_id.clone();
+                    let _ = reauth_handle.emit(
+                        events::AUTH_STATE_CHANGED,
+                        events::AuthStatePayload {
+                            authenticated: false,
+                            user_id,
+                            reauth_required: true,
+                        },
+                    );
+                }
+            });
+
             // Auto-refresh or acquire token on startup.
             // Priority: refresh user token > client credentials fallback.
             let init_client = Arc::clone(&client_for_init);
-            let init_config = Arc::clone(&config_for_init);
+            let autoresume_handle = app.handle().clone();
             tauri::async_runtime::spawn(async move {
-                let config = init_config.read().await;
-                let client_id = config.client_id.clone();
-                let client_secret = config.client_secret.clone();
-                let refresh_token = config.refresh_token.clone();
-                let has_user_id = config.user_id.is_some();
-                drop(config);
-
-                if client_id.is_empty() {
-                    return;
+                init_client.startup_token_refresh().await;
+                if autoresume {
+                    commands::playback_commands::autoresume_on_launch(&autoresume_handle).await;
                 }
+            });
 
-                // If user was previously logged in (has refresh_token), ALWAYS refresh.
-                // We cannot tell if the saved token is a user PKCE token or a client_credentials
-                // token just by looking at it. A previous bug could have overwritten the user
-                // token with a client_credentials one that appears "valid" but only gives
-                // 30-second previews. Refreshing always gives us a proper user token.
-                if let Some(ref rt) = refresh_token {
-                    if has_user_id {
-                        log::info!(
-                            "Refreshing user PKCE token (always refresh for logged-in users)..."
-                        );
-                        match api::auth::refresh_user_token(
-                            init_client.http_client(),
-                            &client_id,
-                            rt,
-                        )
-                        .await
-                        {
-                            Ok(token) => {
-                                let mut config = init_config.write().await;
-                                config.access_token = Some(token.access_token);
-                                config.expires_at = Some(
-                                    chrono::Utc::now()
-                                        + chrono::Duration::seconds(token.expires_in as i64),
-                                );
-                                // Update refresh token if a new one was provided
-                                if let Some(new_rt) = token.refresh_token {
-                                    config.refresh_token = Some(new_rt);
-                                }
-                                if let Err(e) = config.save() {
-                                    log::error!("Failed to save refreshed token: {}", e);
-                                } else {
-                                    log::info!("User token refreshed successfully");
-                                }
-                                return;
-                            }
-                            Err(e) => {
-                                log::warn!(
-                                    "Token refresh failed: {}. User will need to re-login.",
-                                    e
-                                );
-                                // Do NOT fall through to client_credentials when a user was
-                                // previously logged in. Client credentials tokens only give
-                                // 30-second previews, silently degrading the experience.
-                                return;
-                            }
-                        }
+            if remote_control {
+                let remote_handle = app.handle().clone();
+                tauri::async_runtime::spawn(async move {
+                    if let Err(e) = remote_control::apply_settings(&remote_handle).await {
+                        log::error!("Failed to start remote control server: {}", e);
+                    }
+                });
+            }
+
+            if start_minimized {
+                use tauri::Manager;
+                if let Some(window) = app.get_webview_window("main") {
+                    if let Err(e) = window.minimize() {
+                        log::warn!("Failed to minimize window on launch: {}", e);
                     }
                 }
+            }
 
-                // Client credentials require a client_secret and only provide
-                // catalog-only (30s preview) access. Skip if no secret or user was logged in.
-                if has_user_id || client_secret.is_empty() {
-                    log::info!(
-                        "Skipping client credentials (no secret or user was previously logged in)"
-                    );
-                    return;
+            // A link that launched the app arrives before the frontend listens
+            {
+                use commands::deep_link_commands::handle_deep_links;
+                use tauri_plugin_deep_link::DeepLinkExt;
+                let deep_link_handle = app.handle().clone();
+                app.deep_link().on_open_url(move |event| {
+                    handle_deep_links(&deep_link_handle, event.urls());
+                });
+                match app.deep_link().get_current() {
+                    Ok(Some(urls)) => handle_deep_links(app.handle(), urls),
+                    Ok(None) => {}
+                    Err(e) => log::warn!("Failed to read launch deep link: {}", e),
                 }
+            }
 
-                // Check if we already have a valid client credentials token
-                let config = init_config.read().await;
-                let needs_token = config.access_token.is_none() || config.is_token_expired();
-                drop(config);
+            commands::image_commands::spawn_prefetch_workers(prefetch_for_init);
 
-                if !needs_token {
-                    log::info!("Client credentials token still valid, skipping");
-                    return;
-                }
+            // Look for new releases by followed artists once a day
+            tauri::async_runtime::spawn(release_radar::run_daily(app.handle().clone()));
 
-                log::info!("Acquiring client credentials token (no user login history)...");
-                match api::auth::client_credentials_token(
-                    init_client.http_client(),
-                    &client_id,
-                    &client_secret,
-                )
-                .await
-                {
-                    Ok(token) => {
-                        let mut config = init_config.write().await;
-                        config.access_token = Some(token.access_token);
-                        config.expires_at = Some(
-                            chrono::Utc::now() + chrono::Duration::seconds(token.expires_in as i64),
-                        );
-                        if let Err(e) = config.save() {
-                            log::error!("Failed to save token: {}", e);
-                        } else {
-                            log::info!("Client credentials token acquired (catalog-only access)");
+            // Forward network health changes to the UI
+            let health_client = Arc::clone(&client_for_init);
+            let health_handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                use tauri::Emitter;
+                let mut health = health_client.subscribe_network_health();
+                loop {
+                    let offline = *health.borrow() == api::health::NetworkHealth::Offline;
+                    if offline {
+                        // Background work is paused, so check for ourselves
+                        let changed =
+                            tokio::time::timeout(OFFLINE_PROBE_INTERVAL, health.changed()).await;
+                        match changed {
+                            Ok(Ok(())) => {}
+                            Ok(Err(_)) => break,
+                            Err(_) => {
+                                health_client.probe_connectivity().await;
+                                continue;
+                            }
                         }
+                    } else if health.changed().await.is_err() {
+                        break;
                     }
-                    Err(e) => {
-                        log::error!("Failed to acquire client credentials: {}", e);
+                    let _ = health_handle.emit(
+                        events::NETWORK_STATUS_CHANGED,
+                        health_client.network_status(),
+                    );
+                }
+            });
+
+            // Tell the UI once when the catalog starts or stops refusing the
+            // user's country, rather than through every failed request
+            let region_client = Arc::clone(&client_for_init);
+            let region_handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                use tauri::Emitter;
+                let mut blocks = region_client.subscribe_region_block();
+                while blocks.changed().await.is_ok() {
+                    let block = blocks.borrow_and_update().clone();
+                    let _ = region_handle.emit(
+                        events::REGION_BLOCK_CHANGED,
+                        events::RegionBlockChangedPayload { block },
+                    );
+                }
+            });
+
+            // Forward adaptive quality changes to the UI
+            let adaptive_client = Arc::clone(&client_for_init);
+            let adaptive_handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                use tauri::Emitter;
+                let mut adaptations = adaptive_client.subscribe_quality_adaptation();
+                while adaptations.changed().await.is_ok() {
+                    let adaptation = adaptations.borrow_and_update().clone();
+                    if let Some(adaptation) = adaptation {
+                        let _ = adaptive_handle.emit(events::QUALITY_ADAPTED, adaptation);
                     }
                 }
             });
@@ -268,6 +845,93 @@ pub fn run() {
                     // Set up event listeners for media key events
                     use tauri::Listener;
 
+                    // Duck or pause while another app plays audio
+                    macos::audio_focus::start_audio_focus_monitor(deferred_handle.clone());
+
+                    let focus_player = Arc::clone(&deferred_player);
+                    let focus_client = Arc::clone(&deferred_client);
+                    let focus_handle = deferred_handle.clone();
+                    deferred_handle.listen(
+                        macos::audio_focus::AUDIO_FOCUS_LOST,
+                        move |_event: tauri::Event| {
+                            let player = Arc::clone(&focus_player);
+                            let client = Arc::clone(&focus_client);
+                            let handle = focus_handle.clone();
+                            tauri::async_runtime::spawn(async move {
+                                use tauri::{Emitter, Manager};
+                                let settings = client.config().read().await.interruption;
+                                match settings.mode {
+                                    InterruptionMode::Ignore => {}
+                                    InterruptionMode::Duck => {
+                                        player.read().await.set_duck(settings.duck_gain());
+                                    }
+                                    InterruptionMode::Pause => {
+                                        let mut p = player.write().await;
+                                        if p.is_playing() {
+                                            p.pause();
+                                            let playback_epoch = p.playback_epoch();
+                                            drop(p);
+                                            handle
+                                                .state::<AppState>()
+                                                .paused_for_focus
+                                                .store(true, Ordering::SeqCst);
+                                            let _ = handle.emit(
+                                                events::PLAYBACK_STATE_CHANGED,
+                                                events::StateChangedPayload {
+                                                    state: events::PlaybackState::Paused,
+                                                    playback_epoch,
+                                                },
+                                            );
+                                        }
+                                    }
+                                }
+                            });
+                        },
+                    );
+
+                    let focus_player = Arc::clone(&deferred_player);
+                    let focus_client = Arc::clone(&deferred_client);
+                    let focus_handle = deferred_handle.clone();
+                    deferred_handle.listen(
+                        macos::audio_focus::AUDIO_FOCUS_REGAINED,
+                        move |_event: tauri::Event| {
+                            let player = Arc::clone(&focus_player);
+                            let client = Arc::clone(&focus_client);
+                            let handle = focus_handle.clone();
+                            tauri::async_runtime::spawn(async move {
+                                use tauri::{Emitter, Manager};
+                                // Undo ducking even if the setting changed meanwhile
+                                player.read().await.set_duck(1.0);
+                                let resume_after =
+                                    client.config().read().await.interruption.resume_after;
+                                let paused = handle
+                                    .state::<AppState>()
+                                    .paused_for_focus
+                                    .swap(false, Ordering::SeqCst);
+                                if !paused || !resume_after {
+                                    return;
+                                }
+                                let mut p = player.write().await;
+                                if p.is_playing() {
+                                    return;
+                                }
+                                if let Err(e) = p.resume() {
+                                    log::error!("Resume after interruption failed: {}", e);
+                                    return;
+                                }
+                                let playback_epoch = p.playback_epoch();
+                                drop(p);
+                                let _ = handle.emit(
+                                    events::PLAYBACK_STATE_CHANGED,
+                                    events::StateChangedPayload {
+                                        state: events::PlaybackState::Playing,
+                                        playback_epoch,
+                                    },
+                                );
+                            });
+                        },
+                    );
+
                     // Toggle play/pause
                     let media_player = Arc::clone(&deferred_player);
                     let media_track = Arc::clone(&deferred_track);
@@ -280,16 +944,28 @@ pub fn run() {
                             let handle = media_handle.clone();
                             let payload = event.payload().to_string();
                             tauri::async_runtime::spawn(async move {
-                                use tauri::Emitter;
-                                let is_playing = player.read().await.is_playing();
+                                use tauri::{Emitter, Manager};
+                                // The user decided; regaining focus won't resume
+                                handle
+                                    .state::<AppState>()
+                                    .paused_for_focus
+                                    .store(false, Ordering::SeqCst);
+                                let (is_playing, playback_epoch) = {
+                                    let p = player.read().await;
+                                    (p.is_playing(), p.playback_epoch())
+                                };
                                 match payload.trim_matches('"') {
                                     "play" => {
                                         if !is_playing {
-                                            player.write().await.resume();
+                                            if let Err(e) = player.write().await.resume() {
+                                                log::error!("Media key resume failed: {}", e);
+                                                return;
+                                            }
                                             let _ = handle.emit(
                                                 events::PLAYBACK_STATE_CHANGED,
                                                 events::StateChangedPayload {
                                                     state: events::PlaybackState::Playing,
+                                                    playback_epoch,
                                                 },
                                             );
                                         }
@@ -301,6 +977,7 @@ pub fn run() {
                                                 events::PLAYBACK_STATE_CHANGED,
                                                 events::StateChangedPayload {
                                                     state: events::PlaybackState::Paused,
+                                                    playback_epoch,
                                                 },
                                             );
                                         }
@@ -313,14 +990,19 @@ pub fn run() {
                                                 events::PLAYBACK_STATE_CHANGED,
                                                 events::StateChangedPayload {
                                                     state: events::PlaybackState::Paused,
+                                                    playback_epoch,
                                                 },
                                             );
                                         } else {
-                                            player.write().await.resume();
+                                            if let Err(e) = player.write().await.resume() {
+                                                log::error!("Media key resume failed: {}", e);
+                                                return;
+                                            }
                                             let _ = handle.emit(
                                                 events::PLAYBACK_STATE_CHANGED,
                                                 events::StateChangedPayload {
                                                     state: events::PlaybackState::Playing,
+                                                    playback_epoch,
                                                 },
                                             );
                                         }
@@ -350,36 +1032,56 @@ pub fn run() {
                     let next_handle = deferred_handle.clone();
                     deferred_handle.listen(
                         macos::media_keys::MEDIA_KEY_NEXT,
-                        move |_event: tauri::Event| {
+                        move |event: tauri::Event| {
+                            // Tracks already skipped for failing while no window was showing
+                            let failed_skips: usize = event.payload().parse().unwrap_or(0);
                             let player = Arc::clone(&next_player);
                             let queue = Arc::clone(&next_queue);
                             let track_ref = Arc::clone(&next_track);
                             let client = Arc::clone(&next_client);
                             let handle = next_handle.clone();
                             tauri::async_runtime::spawn(async move {
-                                use tauri::Emitter;
+                                use tauri::{Emitter, Manager};
+                                // Skipping an interrupt track returns to the queue entry it interrupted
+                                let interrupt = handle.state::<AppState>().take_interrupt();
                                 let mut q = queue.write().await;
-                                let next = q.next_track().cloned();
+                                let next = match interrupt {
+                                    Some(i) if i.resume_queue_after => q.resume_current().cloned(),
+                                    _ => q.next_track().cloned(),
+                                };
                                 drop(q);
 
                                 if let Some(next_trk) = next {
+                                    // Abandon a play still fetching its manifest
+                                    let app_state = handle.state::<AppState>();
+                                    app_state.begin_play_attempt();
+                                    app_state.record_early_skip(&next_trk.id).await;
                                     match client.get_track_manifest(&next_trk.id).await {
                                         Ok(manifest) => {
+                                            app_state.prepare_gain_for(&next_trk).await;
+                                            let stream =
+                                                events::StreamQuality::for_manifest(&manifest);
                                             let (source, writer, abort_handle) =
                                                 audio::stream_source::HttpStreamSource::new();
                                             AudioPlayer::start_download(
                                                 writer,
                                                 manifest.uri,
-                                                client.http_client().clone(),
+                                                Arc::clone(&client),
                                             );
                                             // Use spawn_blocking to avoid deadlocking tokio
                                             let player_ref = Arc::clone(&player);
                                             let codec = manifest.codec.clone();
                                             let duration = next_trk.duration;
                                             let result = tokio::task::spawn_blocking(move || {
+                                                let track = OpenedTrack::stream(
+                                                    source,
+                                                    abort_handle,
+                                                    Some(&codec),
+                                                    duration,
+                                                )?;
                                                 let rt = tokio::runtime::Handle::current();
                                                 let mut p = rt.block_on(player_ref.write());
-                                                p.play_stream(source, abort_handle, Some(&codec), duration)
+                                                p.play(track)
                                             })
                                             .await;
                                             match result {
@@ -396,7 +1098,14 @@ pub fn run() {
                                                     return;
                                                 }
                                             }
+                                            let playback_epoch =
+                                                player.read().await.playback_epoch();
                                             *track_ref.write().await = Some(next_trk.clone());
+                                            let app_state = handle.state::<AppState>();
+                                            app_state.record_track_started(&next_trk.id);
+                                            let stream = app_state
+                                                .settle_stream(&handle, &next_trk.id, stream)
+                                                .await;
                                             let _ = handle.emit(
                                                 events::PLAYBACK_TRACK_CHANGED,
                                                 events::TrackChangedPayload {
@@ -405,16 +1114,21 @@ pub fn run() {
                                                     artist: next_trk.artist_name.clone(),
                                                     album: next_trk.album_name.clone(),
                                                     duration: next_trk.duration,
-                                                    artwork_url: next_trk
-                                                        .artwork_url_sized(640, 640),
-                                                    codec: None,
-                                                    quality: None,
+                                                    artwork: events::TrackArtwork::for_track(
+                                                        &next_trk,
+                                                    ),
+                                                    stream,
+                                                    reason: events::TrackChangeReason::MediaKey,
+                                                    preview_only: manifest.preview,
+                                                    session_epoch: app_state.session_epoch(),
+                                                    playback_epoch,
                                                 },
                                             );
                                             let _ = handle.emit(
                                                 events::PLAYBACK_STATE_CHANGED,
                                                 events::StateChangedPayload {
                                                     state: events::PlaybackState::Playing,
+                                                    playback_epoch,
                                                 },
                                             );
                                             macos::now_playing::update_now_playing(
@@ -427,7 +1141,31 @@ pub fn run() {
                                             );
                                         }
                                         Err(e) => {
-                                            log::error!("Media key next failed: {}", e)
+                                            log::error!("Media key next failed: {}", e);
+                                            // With no window to show the error, carry on as
+                                            // another key press would rather than go silent
+                                            let skipped = skips_failed_media_key(
+                                                failed_skips,
+                                                macos::headless::any_window_visible(&handle),
+                                            );
+                                            if skipped {
+                                                macos::headless::report_skipped(&next_trk, &e);
+                                            }
+                                            let _ = handle.emit(
+                                                events::PLAYBACK_ERROR,
+                                                events::PlaybackErrorPayload {
+                                                    track_id: next_trk.id.clone(),
+                                                    kind: e.kind().to_string(),
+                                                    message: e.to_string(),
+                                                    skipped,
+                                                },
+                                            );
+                                            if skipped {
+                                                let _ = handle.emit(
+                                                    macos::media_keys::MEDIA_KEY_NEXT,
+                                                    failed_skips + 1,
+                                                );
+                                            }
                                         }
                                     }
                                 }
@@ -450,28 +1188,36 @@ pub fn run() {
                             let client = Arc::clone(&prev_client);
                             let handle = prev_handle.clone();
                             tauri::async_runtime::spawn(async move {
-                                use tauri::Emitter;
+                                use tauri::{Emitter, Manager};
                                 let position = player.read().await.position_seconds();
                                 if position > 15.0 {
                                     // Restart current track
                                     if let Some(current) = track_ref.read().await.clone() {
                                         match client.get_track_manifest(&current.id).await {
                                             Ok(manifest) => {
+                                                let stream =
+                                                    events::StreamQuality::for_manifest(&manifest);
                                                 let (source, writer, abort_handle) =
                                                     audio::stream_source::HttpStreamSource::new();
                                                 AudioPlayer::start_download(
                                                     writer,
                                                     manifest.uri,
-                                                    client.http_client().clone(),
+                                                    Arc::clone(&client),
                                                 );
                                                 let player_ref = Arc::clone(&player);
                                                 let codec = manifest.codec.clone();
                                                 let dur = current.duration;
                                                 let result =
                                                     tokio::task::spawn_blocking(move || {
+                                                        let track = OpenedTrack::stream(
+                                                            source,
+                                                            abort_handle,
+                                                            Some(&codec),
+                                                            dur,
+                                                        )?;
                                                         let rt = tokio::runtime::Handle::current();
                                                         let mut p = rt.block_on(player_ref.write());
-                                                        p.play_stream(source, abort_handle, Some(&codec), dur)
+                                                        p.play(track)
                                                     })
                                                     .await;
                                                 if let Err(e) = result.unwrap_or_else(|e| {
@@ -484,6 +1230,30 @@ pub fn run() {
                                                         "Media key prev restart failed: {}",
                                                         e
                                                     );
+                                                    return;
+                                                }
+                                                // The refetch may be at another quality
+                                                let app_state = handle.state::<AppState>();
+                                                let previous = app_state
+                                                    .playing_stream
+                                                    .lock()
+                                                    .unwrap()
+                                                    .clone();
+                                                let stream = app_state
+                                                    .settle_stream(&handle, &current.id, stream)
+                                                    .await;
+                                                if previous.as_ref() != Some(&stream) {
+                                                    let _ = handle.emit(
+                                                        events::PLAYBACK_STREAM_CHANGED,
+                                                        events::StreamChangedPayload {
+                                                            track_id: current.id.clone(),
+                                                            stream,
+                                                            playback_epoch: player
+                                                                .read()
+                                                                .await
+                                                                .playback_epoch(),
+                                                        },
+                                                    );
                                                 }
                                                 macos::now_playing::update_now_playing(
                                                     &current.title,
@@ -501,28 +1271,42 @@ pub fn run() {
                                         }
                                     }
                                 } else {
+                                    handle.state::<AppState>().take_interrupt();
                                     let mut q = queue.write().await;
                                     let prev = q.previous_track().cloned();
                                     drop(q);
 
                                     if let Some(prev_trk) = prev {
+                                        // Abandon a play still fetching its manifest
+                                        let app_state = handle.state::<AppState>();
+                                        app_state.begin_play_attempt();
+                                        app_state.record_early_skip(&prev_trk.id).await;
                                         match client.get_track_manifest(&prev_trk.id).await {
                                             Ok(manifest) => {
+                                                app_state.prepare_gain_for(&prev_trk).await;
+                                                let stream =
+                                                    events::StreamQuality::for_manifest(&manifest);
                                                 let (source, writer, abort_handle) =
                                                     audio::stream_source::HttpStreamSource::new();
                                                 AudioPlayer::start_download(
                                                     writer,
                                                     manifest.uri,
-                                                    client.http_client().clone(),
+                                                    Arc::clone(&client),
                                                 );
                                                 let player_ref = Arc::clone(&player);
                                                 let codec = manifest.codec.clone();
                                                 let dur = prev_trk.duration;
                                                 let result =
                                                     tokio::task::spawn_blocking(move || {
+                                                        let track = OpenedTrack::stream(
+                                                            source,
+                                                            abort_handle,
+                                                            Some(&codec),
+                                                            dur,
+                                                        )?;
                                                         let rt = tokio::runtime::Handle::current();
                                                         let mut p = rt.block_on(player_ref.write());
-                                                        p.play_stream(source, abort_handle, Some(&codec), dur)
+                                                        p.play(track)
                                                     })
                                                     .await;
                                                 match result {
@@ -542,7 +1326,14 @@ pub fn run() {
                                                         return;
                                                     }
                                                 }
+                                                let playback_epoch =
+                                                    player.read().await.playback_epoch();
                                                 *track_ref.write().await = Some(prev_trk.clone());
+                                                let app_state = handle.state::<AppState>();
+                                                app_state.record_track_started(&prev_trk.id);
+                                                let stream = app_state
+                                                    .settle_stream(&handle, &prev_trk.id, stream)
+                                                    .await;
                                                 let _ = handle.emit(
                                                     events::PLAYBACK_TRACK_CHANGED,
                                                     events::TrackChangedPayload {
@@ -551,16 +1342,21 @@ pub fn run() {
                                                         artist: prev_trk.artist_name.clone(),
                                                         album: prev_trk.album_name.clone(),
                                                         duration: prev_trk.duration,
-                                                        artwork_url: prev_trk
-                                                            .artwork_url_sized(640, 640),
-                                                        codec: None,
-                                                        quality: None,
+                                                        artwork: events::TrackArtwork::for_track(
+                                                            &prev_trk,
+                                                        ),
+                                                        stream,
+                                                        reason: events::TrackChangeReason::MediaKey,
+                                                        preview_only: manifest.preview,
+                                                        session_epoch: app_state.session_epoch(),
+                                                        playback_epoch,
                                                     },
                                                 );
                                                 let _ = handle.emit(
                                                     events::PLAYBACK_STATE_CHANGED,
                                                     events::StateChangedPayload {
                                                         state: events::PlaybackState::Playing,
+                                                        playback_epoch,
                                                     },
                                                 );
                                                 macos::now_playing::update_now_playing(
@@ -585,23 +1381,134 @@ pub fn run() {
             // Start progress emission + auto-advance + preload loop
             tauri::async_runtime::spawn(async move {
                 use tauri::{Emitter, Manager};
-                let mut preload_triggered = false;
                 let mut advancing = false; // Guard against re-entering auto-advance
 
+                // Unavailable tracks skipped in a row; set while moving past one
+                let mut skipped_unavailable = 0usize;
+
+                // Default output device is checked every DEVICE_CHECK_INTERVAL
+                const DEVICE_CHECK_INTERVAL: std::time::Duration =
+                    std::time::Duration::from_secs(2);
+                let mut device_checked_at = std::time::Instant::now();
+
+                // Output device whose remembered volume is applied
+                let mut volume_device: Option<String> = None;
+
+                // Last progress reported, so unchanged ticks aren't sent again
+                let mut progress_reporter = ProgressReporter::default();
+
                 loop {
-                    tokio::time::sleep(std::time::Duration::from_millis(250)).await;
+                    let interval = client_for_progress
+                        .config()
+                        .read()
+                        .await
+                        .progress_interval();
+                    tokio::time::sleep(interval).await;
+                    let check_devices = device_checked_at.elapsed() >= DEVICE_CHECK_INTERVAL;
+                    if check_devices {
+                        device_checked_at = std::time::Instant::now();
+                    }
 
                     // Skip polling while we're in the middle of advancing to the next track
                     if advancing {
                         continue;
                     }
 
+                    // One read lock, so the epoch and position are from the same stream
                     let player = player_for_progress.read().await;
+                    let playback_epoch = player.playback_epoch();
                     let is_playing = player.is_playing();
                     let is_finished = player.is_finished();
+                    let decode_failed = player.decode_failed();
                     let position = player.position_seconds();
                     let duration = player.duration_seconds();
+                    let paused_for = player.paused_for().filter(|_| !player.is_suspended());
+                    let seeks_resolved = player.seeks_resolved();
                     drop(player);
+                    let session_epoch = app_handle.state::<AppState>().session_epoch();
+
+                    // Release the output device once paused for long enough
+                    if let Some(paused_for) = paused_for {
+                        let minutes = client_for_progress
+                            .config()
+                            .read()
+                            .await
+                            .release_output_after_minutes;
+                        if minutes > 0 && paused_for.as_secs() >= u64::from(minutes) * 60 {
+                            player_for_progress.write().await.suspend();
+                        }
+                    }
+
+                    // Follow the system default output (e.g. headphones connected)
+                    if check_devices
+                        && client_for_progress
+                            .config()
+                            .read()
+                            .await
+                            .follow_default_output
+                    {
+                        let changed = player_for_progress.read().await.default_output_changed();
+                        if let Some(device_name) = changed {
+                            log::info!("Default output changed, switching to {}", device_name);
+                            match player_for_progress.write().await.switch_to_default_output() {
+                                Ok(()) => {
+                                    let _ = app_handle.emit(
+                                        events::OUTPUT_DEVICE_CHANGED,
+                                        events::OutputDeviceChangedPayload { device_name },
+                                    );
+                                }
+                                // The old device keeps playing if it's still there
+                                Err(e) => {
+                                    log::error!("Failed to switch output device: {}", e);
+                                    let track_id = track_for_progress
+                                        .read()
+                                        .await
+                                        .as_ref()
+                                        .map(|t| t.id.clone())
+                                        .unwrap_or_default();
+                                    let _ = app_handle.emit(
+                                        events::PLAYBACK_ERROR,
+                                        events::PlaybackErrorPayload {
+                                            track_id,
+                                            kind: e.kind().to_string(),
+                                            message: e.to_string(),
+                                            skipped: false,
+                                        },
+                                    );
+                                }
+                            }
+                        }
+                    }
+
+                    // The player set the volume remembered for a newly opened
+                    // device as it opened; bring the config and sliders in line
+                    if check_devices {
+                        let device = player_for_progress
+                            .read()
+                            .await
+                            .output_device_name()
+                            .map(String::from);
+                        if device.is_some() && device != volume_device {
+                            volume_device = device;
+                            let mut config = client_for_progress.config().write().await;
+                            let volume = config.volume_for(volume_device.as_deref());
+                            if volume != config.volume {
+                                config.volume = volume;
+                                let muted = config.muted;
+                                drop(config);
+                                log::info!(
+                                    "Using volume {:.2} for {}",
+                                    volume,
+                                    volume_device.as_deref().unwrap_or_default()
+                                );
+                                app_handle.state::<AppState>().schedule_config_save();
+                                let _ = app_handle.emit(
+                                    events::VOLUME_CHANGED,
+                                    events::VolumeChangedPayload { volume, muted },
+                                );
+                            }
+                        }
+                    }
 
                     // Debug: log state near end of track
                     if duration > 0.0 && position > 0.0 {
@@ -614,7 +1521,41 @@ pub fn run() {
                         }
                     }
 
-                    if is_playing {
+                    // A track whose metadata had no duration takes the
+                    // decoder's estimate once probing has found one
+                    let mut duration_corrected = false;
+                    if duration > 0.0 {
+                        let missing = track_for_progress
+                            .read()
+                            .await
+                            .as_ref()
+                            .is_some_and(|t| t.duration <= 0.0);
+                        if missing {
+                            if let Some(track) = track_for_progress.write().await.as_mut() {
+                                log::info!(
+                                    "Track {} had no duration, using decoded {:.1}s",
+                                    track.id,
+                                    duration
+                                );
+                                track.duration = duration;
+                                duration_corrected = true;
+                            }
+                        }
+                    }
+
+                    let progress = ReportedProgress {
+                        session_epoch,
+                        playback_epoch,
+                        playing: is_playing,
+                        position,
+                        duration,
+                    };
+                    let report = progress_reporter.should_report(
+                        progress,
+                        seeks_resolved,
+                        duration_corrected,
+                    );
+                    if report {
                         let fraction = if duration > 0.0 {
                             position / duration
                         } else {
@@ -626,6 +1567,8 @@ pub fn run() {
                                 position,
                                 duration,
                                 position_fraction: fraction,
+                                session_epoch,
+                                playback_epoch,
                             },
                         );
 
@@ -637,89 +1580,269 @@ pub fn run() {
                                 &track.album_name,
                                 track.duration,
                                 position,
-                                true,
+                                is_playing,
                             );
                         }
+                    }
+
+                    if is_playing {
+                        // Fetch the next page of a lazily loaded queue before it runs out
+                        let needs_refill = {
+                            let queue = queue_for_progress.read().await;
+                            queue.needs_refill(LAZY_REFILL_THRESHOLD) && !queue.is_lazy_fetching()
+                        };
+                        if needs_refill && !client_for_progress.is_offline() {
+                            let app_h = app_handle.clone();
+                            tauri::async_runtime::spawn(async move {
+                                if app_h.state::<AppState>().refill_queue().await {
+                                    let _ = app_h.emit(events::PLAYBACK_QUEUE_CHANGED, ());
+                                }
+                            });
+                        }
 
                         // Preload next track when within 30s of the end.
                         // Use duration > 0.0 to avoid div-by-zero; drop the remaining > 0.0
                         // check since position can slightly overshoot duration due to
                         // sample counting vs API metadata mismatch.
                         let remaining = duration - position;
-                        if duration > 0.0 && remaining < 30.0 && !preload_triggered {
-                            preload_triggered = true;
+                        if duration > 0.0
+                            && remaining < PRELOAD_THRESHOLD_SECS
+                            && !client_for_progress.is_offline()
+                        {
+                            let interrupt = app_handle
+                                .state::<AppState>()
+                                .interrupt
+                                .lock()
+                                .unwrap()
+                                .as_ref()
+                                .map(|i| i.resume_queue_after);
                             let queue = queue_for_progress.read().await;
-                            if let Some(next) = queue.peek_next() {
-                                let next_id = next.id.clone();
-                                let next_duration = next.duration;
+                            let next = match interrupt {
+                                // An interrupt track returns to the entry it
+                                // interrupted, or stops playback
+                                Some(true) => queue.current_track(),
+                                Some(false) => None,
+                                None => queue.peek_next(),
+                            }
+                            .map(|t| (t.id.clone(), t.duration));
+                            drop(queue);
+                            let next_id = next.as_ref().map(|(id, _)| id.as_str());
+                            let state: tauri::State<'_, AppState> = app_handle.state::<AppState>();
+                            let trigger = state.preload_trigger.lock().unwrap().should_preload(
+                                remaining,
+                                next_id,
+                                std::time::Instant::now(),
+                            );
+                            if let (true, Some((next_id, next_duration))) = (trigger, next) {
                                 let client = Arc::clone(&client_for_progress);
                                 let app_h = app_handle.clone();
                                 tauri::async_runtime::spawn(async move {
+                                    let state: tauri::State<'_, AppState> =
+                                        app_h.state::<AppState>();
+                                    // A preload started before a seek may still be for this track
+                                    let existing = state.preloaded_track.lock().await;
+                                    if existing.as_ref().is_some_and(|p| p.track_id == next_id) {
+                                        log::info!("Reusing existing preload for {}", next_id);
+                                        return;
+                                    }
+                                    drop(existing);
+
                                     log::info!("Preloading next track: {}", next_id);
+                                    let quality = state.audio_quality().await;
                                     match client.get_track_manifest(&next_id).await {
                                         Ok(manifest) => {
-                                            let preloaded = PreloadedTrack::new(
-                                                next_id,
-                                                Some(manifest.codec),
+                                            // The quality changed mid-fetch; `set_audio_quality`
+                                            // re-arms the trigger so the next tick refetches
+                                            if state.audio_quality().await != quality {
+                                                log::info!("Dropping stale-quality preload");
+                                                return;
+                                            }
+                                            let mut preloaded = PreloadedTrack::new(
+                                                next_id.clone(),
+                                                events::StreamQuality::for_manifest(&manifest),
+                                                manifest.preview,
                                                 next_duration,
                                                 manifest.uri,
-                                                client.http_client().clone(),
+                                                Arc::clone(&client),
                                             );
-                                            let state: tauri::State<'_, AppState> =
-                                                app_h.state::<AppState>();
+                                            let download = preloaded.take_download();
                                             let mut pl = state.preloaded_track.lock().await;
-                                            *pl = Some(preloaded);
+                                            // A preload for a track that is no longer
+                                            // next is dropped here, stopping its download
+                                            drop(pl.replace(preloaded));
+                                            drop(pl);
                                             log::info!("Next track preloaded successfully");
+
+                                            // An aborted preload resolves with an error
+                                            let Some(download) = download else { return };
+                                            if download.await.is_err() {
+                                                return;
+                                            }
+                                            let ready = state
+                                                .preloaded_track
+                                                .lock()
+                                                .await
+                                                .as_ref()
+                                                .is_some_and(|p| {
+                                                    p.track_id == next_id && p.status().ready
+                                                });
+                                            if ready {
+                                                let _ = app_h.emit(
+                                                    events::PLAYBACK_PRELOAD_READY,
+                                                    events::PreloadReadyPayload {
+                                                        track_id: next_id,
+                                                    },
+                                                );
+                                            }
+                                        }
+                                        Err(e) => {
+                                            log::warn!("Preload manifest failed: {}", e);
+                                            // Retried after a backoff, not on the next tick
+                                            state.preload_trigger.lock().unwrap().preload_failed(
+                                                &next_id,
+                                                std::time::Instant::now(),
+                                            );
                                         }
-                                        Err(e) => log::warn!("Preload manifest failed: {}", e),
                                     }
                                 });
                             }
                         }
+
+                        // Announce the next track shortly before this one ends,
+                        // once per track. Held back during an interrupt track
+                        // (the queue isn't what plays next) and right after a
+                        // queue edit; nothing is announced on repeat-one or
+                        // when the queue is about to run out.
+                        let notice_secs =
+                            client_for_progress.config().read().await.upnext_notice_secs;
+                        if duration > 0.0 && notice_secs > 0 && remaining < f64::from(notice_secs) {
+                            let state = app_handle.state::<AppState>();
+                            let held_back = state.interrupt.lock().unwrap().is_some()
+                                || state.queue_edited_within(UPNEXT_QUIET_AFTER_EDIT);
+                            if !held_back && !state.upnext_announced.swap(true, Ordering::Relaxed) {
+                                let queue = queue_for_progress.read().await;
+                                let next = match queue.repeat_mode() {
+                                    RepeatMode::One => None,
+                                    _ => queue.peek_next().cloned(),
+                                };
+                                drop(queue);
+                                if let Some(next) = next {
+                                    let _ = app_handle.emit(
+                                        events::PLAYBACK_UPNEXT,
+                                        events::UpNextPayload {
+                                            track_id: next.id.clone(),
+                                            title: next.title.clone(),
+                                            artist: next.artist_name.clone(),
+                                            album: next.album_name.clone(),
+                                            duration: next.duration,
+                                            artwork: events::TrackArtwork::for_track(&next),
+                                            session_epoch,
+                                        },
+                                    );
+                                }
+                            }
+                        }
                     }
 
-                    // Auto-advance when track finishes
-                    if is_finished && duration > 0.0 {
+                    // Auto-advance when track finishes. A stream with no known
+                    // duration counts once anything of it has played
+                    let skipping = skipped_unavailable > 0;
+                    let started = duration > 0.0 || position > 0.0;
+                    // A local file plays without a current track, and ends
+                    // there instead of moving the queue on
+                    if is_finished
+                        && started
+                        && !skipping
+                        && track_for_progress.read().await.is_none()
+                    {
+                        let playback_epoch = {
+                            let mut player = player_for_progress.write().await;
+                            player.stop();
+                            player.playback_epoch()
+                        };
+                        let _ = app_handle.emit(
+                            events::PLAYBACK_STATE_CHANGED,
+                            events::StateChangedPayload {
+                                state: events::PlaybackState::Stopped,
+                                playback_epoch,
+                            },
+                        );
+                        continue;
+                    }
+                    if (is_finished && started) || skipping {
                         advancing = true; // Block re-entry while we fetch/play
-                        log::info!("Track finished, auto-advancing...");
-                        let _ = app_handle.emit(events::PLAYBACK_TRACK_ENDED, ());
+                        if skipping {
+                            log::info!("Skipping unavailable track, auto-advancing...");
+                        } else if decode_failed {
+                            log::warn!("Track stopped on a decode error, auto-advancing...");
+                        } else {
+                            log::info!("Track finished, auto-advancing...");
+                            let _ = app_handle.emit(
+                                events::PLAYBACK_TRACK_ENDED,
+                                events::TrackEndedPayload { playback_epoch },
+                            );
+                            if let Some(finished) = track_for_progress.read().await.as_ref() {
+                                app_handle
+                                    .state::<AppState>()
+                                    .record_track_completed(&finished.id);
+                            }
+                        }
 
                         // Stop the old player immediately so is_finished resets
-                        {
+                        let stopped_epoch = {
                             let mut player = player_for_progress.write().await;
                             player.stop();
-                        }
+                            player.playback_epoch()
+                        };
 
-                        // Advance queue
+                        // Advance queue, or return to it after an interrupt track
+                        let hide_explicit = app_handle.state::<AppState>().hide_explicit().await;
+                        let interrupt = app_handle.state::<AppState>().take_interrupt();
+                        // A lazily loaded queue that ran dry fetches its next page first
+                        let needs_refill = queue_for_progress.read().await.needs_refill(0);
+                        if needs_refill && app_handle.state::<AppState>().refill_queue().await {
+                            let _ = app_handle.emit(events::PLAYBACK_QUEUE_CHANGED, ());
+                        }
                         let mut queue = queue_for_progress.write().await;
-                        let next = queue.next_track().cloned();
+                        let next = match &interrupt {
+                            Some(i) if i.resume_queue_after => queue.resume_current().cloned(),
+                            Some(_) => None,
+                            None => queue
+                                .next_track_skipping(|t| hide_explicit && t.explicit)
+                                .cloned(),
+                        };
                         drop(queue);
 
-                        if let Some(next_track) = next {
+                        if let Some(mut next_track) = next {
                             // Check if we have a preloaded track
                             let state: tauri::State<'_, AppState> = app_handle.state::<AppState>();
                             let preloaded: Option<PreloadedTrack> = {
                                 let mut pl = state.preloaded_track.lock().await;
                                 pl.take()
                             };
+                            // A stale preload is dropped, stopping its download
+                            let preloaded = preloaded.filter(|p| p.track_id == next_track.id);
 
-                            if let Some(preloaded) =
-                                preloaded.filter(|p| p.track_id == next_track.id)
-                            {
+                            let playing_stream;
+                            let playing_preview;
+                            if let Some(preloaded) = preloaded {
                                 log::info!("Using preloaded track for gapless playback");
+                                playing_stream = preloaded.stream.clone();
+                                playing_preview = preloaded.preview;
+                                state.prepare_gain_for(&next_track).await;
                                 // Use spawn_blocking so the blocking format-probe
-                                // inside play_stream doesn't stall the Tokio runtime.
+                                // of the new track doesn't stall the Tokio runtime.
                                 let player_ref = Arc::clone(&player_for_progress);
                                 let result = tokio::task::spawn_blocking(move || {
-                                    let rt = tokio::runtime::Handle::current();
-                                    let mut player = rt.block_on(player_ref.write());
-                                    let codec_hint = preloaded.codec_hint.as_deref();
-                                    player.play_stream(
+                                    let track = OpenedTrack::stream(
                                         preloaded.source,
-                                        preloaded.abort_handle,
-                                        codec_hint,
+                                        preloaded.abort_handle.disarm(),
+                                        preloaded.codec_hint.as_deref(),
                                         preloaded.duration,
-                                    )
+                                    )?;
+                                    let rt = tokio::runtime::Handle::current();
+                                    let mut player = rt.block_on(player_ref.write());
+                                    player.play(track)
                                 })
                                 .await;
                                 match result {
@@ -738,30 +1861,36 @@ pub fn run() {
                             } else {
                                 // Fetch and play normally
                                 let client = &client_for_progress;
-                                match client.get_track_manifest(&next_track.id).await {
-                                    Ok(manifest) => {
+                                match client.get_playable_manifest(&next_track).await {
+                                    Ok((playable, manifest)) => {
+                                        next_track = playable;
+                                        state.prepare_gain_for(&next_track).await;
+                                        playing_stream =
+                                            events::StreamQuality::for_manifest(&manifest);
                                         let (source, writer, abort_handle) =
                                             audio::stream_source::HttpStreamSource::new();
                                         AudioPlayer::start_download(
                                             writer,
                                             manifest.uri,
-                                            client.http_client().clone(),
+                                            Arc::clone(client),
                                         );
                                         // Use spawn_blocking so the blocking format-probe
-                                        // inside play_stream doesn't stall the Tokio runtime
+                                        // of the new track doesn't stall the Tokio runtime
                                         // and deadlock with the download task.
                                         let player_ref = Arc::clone(&player_for_progress);
                                         let codec = manifest.codec.clone();
+                                        playing_preview = manifest.preview;
                                         let duration = next_track.duration;
                                         let result = tokio::task::spawn_blocking(move || {
-                                            let rt = tokio::runtime::Handle::current();
-                                            let mut player = rt.block_on(player_ref.write());
-                                            player.play_stream(
+                                            let track = OpenedTrack::stream(
                                                 source,
                                                 abort_handle,
                                                 Some(&codec),
                                                 duration,
-                                            )
+                                            )?;
+                                            let rt = tokio::runtime::Handle::current();
+                                            let mut player = rt.block_on(player_ref.write());
+                                            player.play(track)
                                         })
                                         .await;
                                         match result {
@@ -778,15 +1907,44 @@ pub fn run() {
                                             }
                                         }
                                     }
+                                    Err(e @ error::AppError::TrackUnavailable { .. }) => {
+                                        // Don't stall the queue: move on to the track after it,
+                                        // unless every recent track was unavailable too.
+                                        skipped_unavailable += 1;
+                                        let skipped = skipped_unavailable < MAX_UNAVAILABLE_SKIPS;
+                                        log::warn!("Auto-advance: {}", e);
+                                        state.block_for_session(&next_track.id);
+                                        let _ = app_handle.emit(
+                                            events::PLAYBACK_ERROR,
+                                            events::PlaybackErrorPayload {
+                                                track_id: next_track.id.clone(),
+                                                kind: e.kind().to_string(),
+                                                message: e.to_string(),
+                                                skipped,
+                                            },
+                                        );
+                                        if !skipped {
+                                            skipped_unavailable = 0;
+                                        }
+                                        advancing = false;
+                                        continue;
+                                    }
                                     Err(e) => {
                                         log::error!("Failed to get manifest for next track: {}", e);
+                                        skipped_unavailable = 0;
                                         advancing = false;
                                         continue;
                                     }
                                 }
                             }
 
+                            skipped_unavailable = 0;
+                            let playback_epoch = player_for_progress.read().await.playback_epoch();
                             *track_for_progress.write().await = Some(next_track.clone());
+                            state.record_track_started(&next_track.id);
+                            let stream = state
+                                .settle_stream(&app_handle, &next_track.id, playing_stream)
+                                .await;
 
                             let _ = app_handle.emit(
                                 events::PLAYBACK_TRACK_CHANGED,
@@ -796,9 +1954,16 @@ pub fn run() {
                                     artist: next_track.artist_name.clone(),
                                     album: next_track.album_name.clone(),
                                     duration: next_track.duration,
-                                    artwork_url: next_track.artwork_url_sized(640, 640),
-                                    codec: None,
-                                    quality: None,
+                                    artwork: events::TrackArtwork::for_track(&next_track),
+                                    stream,
+                                    reason: if interrupt.is_some() {
+                                        events::TrackChangeReason::QueueResumed
+                                    } else {
+                                        events::TrackChangeReason::AutoAdvance
+                                    },
+                                    preview_only: playing_preview,
+                                    session_epoch: state.session_epoch(),
+                                    playback_epoch,
                                 },
                             );
 
@@ -806,20 +1971,24 @@ pub fn run() {
                                 events::PLAYBACK_STATE_CHANGED,
                                 events::StateChangedPayload {
                                     state: events::PlaybackState::Playing,
+                                    playback_epoch,
                                 },
                             );
 
                             let _ = app_handle.emit(events::PLAYBACK_QUEUE_CHANGED, ());
 
-                            preload_triggered = false;
+                            state.preload_trigger.lock().unwrap().reset();
+                            state.upnext_announced.store(false, Ordering::Relaxed);
                         } else {
                             // No next track, already stopped above
+                            skipped_unavailable = 0;
                             *track_for_progress.write().await = None;
 
                             let _ = app_handle.emit(
                                 events::PLAYBACK_STATE_CHANGED,
                                 events::StateChangedPayload {
                                     state: events::PlaybackState::Stopped,
+                                    playback_epoch: stopped_epoch,
                                 },
                             );
                             #[cfg(target_os = "macos")]
@@ -835,33 +2004,87 @@ pub fn run() {
         .invoke_handler(tauri::generate_handler![
             // Auth
             commands::auth_commands::check_auth_status,
+            commands::auth_commands::get_network_status,
+            commands::auth_commands::get_region_block,
+            commands::auth_commands::clear_region_block,
             commands::auth_commands::login,
             commands::auth_commands::poll_login,
             commands::auth_commands::handle_auth_callback,
             commands::auth_commands::init_client_credentials,
             commands::auth_commands::logout,
+            commands::deep_link_commands::get_pending_deep_link,
             // Playback
             commands::playback_commands::play_track,
+            commands::playback_commands::play_track_now,
             commands::playback_commands::play_tracks,
+            commands::playback_commands::play_track_ids,
+            commands::playback_commands::play_album,
+            commands::playback_commands::play_playlist,
+            commands::playback_commands::play_favorites,
+            commands::playback_commands::play_favorites_shuffled,
             commands::playback_commands::pause,
             commands::playback_commands::resume,
             commands::playback_commands::stop,
             commands::playback_commands::seek,
+            commands::playback_commands::seek_fraction,
             commands::playback_commands::set_volume,
+            commands::playback_commands::set_muted,
             commands::playback_commands::get_volume,
             commands::playback_commands::get_playback_state,
+            commands::playback_commands::get_playback_status,
+            commands::playback_commands::get_stream_info,
+            commands::playback_commands::get_preload_status,
+            commands::playback_commands::get_waveform,
+            commands::playback_commands::get_seek_preview,
             commands::playback_commands::get_player_prefs,
             commands::playback_commands::save_player_prefs,
+            commands::playback_commands::set_crossfeed,
+            commands::playback_commands::get_crossfeed,
+            commands::playback_commands::set_interruption_settings,
+            commands::playback_commands::get_interruption_settings,
+            commands::playback_commands::get_launch_settings,
+            commands::playback_commands::set_start_minimized,
+            commands::playback_commands::set_autoresume_on_launch,
+            commands::playback_commands::cancel_autoresume,
+            commands::playback_commands::set_substitute_unavailable,
+            commands::playback_commands::set_hide_explicit,
+            commands::playback_commands::set_audio_quality,
+            commands::playback_commands::set_adaptive_quality,
+            commands::playback_commands::get_adaptive_quality,
+            commands::playback_commands::get_hide_explicit,
+            commands::playback_commands::set_language_allowlist,
+            commands::playback_commands::get_language_allowlist,
+            commands::playback_commands::set_release_output_after_minutes,
+            commands::playback_commands::set_upnext_notice_secs,
+            commands::playback_commands::set_progress_interval_ms,
+            commands::playback_commands::set_follow_default_output,
+            commands::playback_commands::set_allow_preview_playback,
+            commands::playback_commands::get_output_device,
+            commands::playback_commands::get_output_info,
+            #[cfg(debug_assertions)]
+            commands::playback_commands::play_local_file,
+            commands::playback_commands::find_track_alternative,
             commands::playback_commands::next_track,
+            commands::playback_commands::skip_to_queue_offset,
             commands::playback_commands::previous_track,
             // Queue
             commands::queue_commands::get_queue,
+            commands::queue_commands::get_up_next,
             commands::queue_commands::add_to_queue,
+            commands::queue_commands::add_track_ids_to_queue,
+            commands::queue_commands::append_album_to_queue,
+            commands::queue_commands::append_playlist_to_queue,
+            commands::queue_commands::insert_album_next,
             commands::queue_commands::remove_from_queue,
+            commands::queue_commands::remove_from_queue_by_id,
+            commands::queue_commands::dedupe_queue,
             commands::queue_commands::reorder_queue,
+            commands::queue_commands::reorder_queue_range,
             commands::queue_commands::shuffle_queue,
+            commands::queue_commands::reshuffle,
             commands::queue_commands::unshuffle_queue,
             commands::queue_commands::toggle_repeat,
+            commands::queue_commands::set_repeat_mode,
             commands::queue_commands::clear_queue,
             commands::queue_commands::play_queue_track,
             commands::queue_commands::save_queue_state,
@@ -873,23 +2096,263 @@ pub fn run() {
             commands::playlist_commands::get_playlists,
             commands::playlist_commands::get_playlist,
             commands::playlist_commands::get_playlist_tracks,
+            commands::playlist_commands::get_playlist_listing,
             commands::playlist_commands::create_playlist,
             commands::playlist_commands::add_to_playlist,
             commands::playlist_commands::remove_from_playlist,
             commands::playlist_commands::delete_playlist,
+            commands::playlist_commands::set_playlist_prefs,
+            commands::playlist_commands::get_playlist_prefs,
+            commands::playlist_commands::set_playlist_collaborative,
+            commands::playlist_commands::get_playlist_invite_link,
+            commands::playlist_commands::save_queue_as_playlist,
             // Favorites
             commands::favorites_commands::get_favorites,
+            commands::favorites_commands::get_favorites_count,
+            commands::favorites_commands::is_favorite,
             commands::favorites_commands::toggle_favorite,
+            commands::favorites_commands::check_new_releases,
+            commands::favorites_commands::get_release_radar,
             // Browse
             commands::browse_commands::get_album,
             commands::browse_commands::get_album_tracks,
+            commands::browse_commands::get_album_listing,
+            commands::browse_commands::get_album_page,
+            commands::browse_commands::clear_metadata_cache,
             commands::browse_commands::get_artist,
             commands::browse_commands::get_artist_albums,
+            commands::browse_commands::get_artist_appears_on,
+            commands::browse_commands::get_artist_page,
             commands::browse_commands::get_recommendations,
+            commands::browse_commands::get_home_sections_config,
+            commands::browse_commands::set_home_sections_config,
+            commands::browse_commands::refresh_discovery,
             commands::browse_commands::get_similar_tracks,
+            commands::browse_commands::get_track_details,
+            commands::browse_commands::get_video,
+            commands::browse_commands::get_user_public_profile,
             // Images
             commands::image_commands::proxy_image,
+            commands::image_commands::prefetch_artwork,
+            commands::image_commands::get_artwork_palette,
+            // Crash reports
+            commands::crash_commands::get_last_crash,
+            commands::crash_commands::acknowledge_crash,
+            // Diagnostics
+            commands::diagnostics_commands::get_diagnostics,
+            commands::diagnostics_commands::get_resource_usage,
+            // App data
+            commands::app_data_commands::get_session_blocklist,
+            commands::app_data_commands::reset_app_data,
+            // Gain offsets
+            commands::gain_commands::set_gain_offset,
+            commands::gain_commands::get_gain_offset,
+            commands::gain_commands::clear_gain_offsets,
+            commands::gain_commands::export_gain_offsets,
+            commands::gain_commands::import_gain_offsets,
+            // Remote control
+            commands::remote_commands::get_remote_control,
+            commands::remote_commands::set_remote_control,
+            commands::remote_commands::regenerate_remote_control_token,
+            // Listening stats
+            commands::stats_commands::get_track_stats,
+            commands::stats_commands::get_most_played,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app_handle, event| {
+            if let tauri::RunEvent::Exit = event {
+                use tauri::Manager;
+                let state = app_handle.state::<AppState>();
+                let finished = tauri::async_runtime::block_on(async {
+                    tokio::time::timeout(SHUTDOWN_BUDGET, state.shutdown()).await
+                });
+                if finished.is_err() {
+                    log::warn!("Shutdown exceeded {:?}, exiting anyway", SHUTDOWN_BUDGET);
+                }
+                log::logger().flush();
+            }
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{mock_client, wav};
+    use std::io::Cursor;
+    use std::time::{Duration, Instant};
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    /// A v2 manifest pointing straight at a CDN URL, answered after `delay`.
+    async fn mount_slow_manifest(server: &MockServer, track_id: &str, delay: Duration) {
+        let body = serde_json::json!({
+            "data": { "attributes": {
+                "uri": format!("https://cdn.test/{}.flac", track_id),
+                "formats": ["FLAC"],
+            }}
+        });
+        Mock::given(method("GET"))
+            .and(path(format!("/trackManifests/{}", track_id)))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(body)
+                    .set_delay(delay),
+            )
+            .mount(server)
+            .await;
+    }
+
+    #[tokio::test]
+    async fn a_newer_attempt_abandons_a_slow_manifest_fetch() {
+        let server = MockServer::start().await;
+        mount_slow_manifest(&server, "1", Duration::from_secs(5)).await;
+        mount_slow_manifest(&server, "2", Duration::from_millis(100)).await;
+        let client = mock_client(&server);
+        let generations = watch::Sender::new(0);
+
+        let started = Instant::now();
+        let mut first = PlayAttempt::begin(&generations);
+        let skipped = {
+            let client = Arc::clone(&client);
+            tokio::spawn(async move {
+                first
+                    .unless_superseded(client.get_track_manifest("1"))
+                    .await
+            })
+        };
+        // Wait until the first request is in flight before skipping ahead
+        while server
+            .received_requests()
+            .await
+            .unwrap_or_default()
+            .is_empty()
+        {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        let mut second = PlayAttempt::begin(&generations);
+        let played = second
+            .unless_superseded(client.get_track_manifest("2"))
+            .await;
+
+        // The first fetch gave up as soon as it was superseded, without
+        // waiting out its response, so nothing was left to start a download
+        assert!(skipped.await.unwrap().is_none());
+        assert!(started.elapsed() < Duration::from_secs(5));
+        let manifest = played.expect("latest attempt is current").unwrap();
+        assert_eq!(manifest.uri, "https://cdn.test/2.flac");
+    }
+
+    #[tokio::test]
+    async fn a_fetch_that_finishes_after_being_superseded_is_dropped() {
+        let generations = watch::Sender::new(0);
+        let mut attempt = PlayAttempt::begin(&generations);
+        let next = generations.clone();
+        // The work completes in the same poll that a newer attempt starts
+        let output = attempt
+            .unless_superseded(async move {
+                PlayAttempt::begin(&next);
+                "manifest"
+            })
+            .await;
+        assert_eq!(output, None);
+        assert!(!attempt.is_current());
+    }
+
+    #[test]
+    fn only_a_window_that_is_up_counts_as_showing() {
+        assert!(!any_window_showing([]));
+        assert!(any_window_showing([(Some(true), Some(false))]));
+        // Hidden or minimized windows don't show the error toast
+        assert!(!any_window_showing([(Some(false), Some(false))]));
+        assert!(!any_window_showing([(Some(true), Some(true))]));
+        assert!(!any_window_showing([
+            (Some(false), Some(false)),
+            (Some(true), Some(true)),
+        ]));
+        assert!(any_window_showing([
+            (Some(false), Some(false)),
+            (Some(true), Some(false)),
+        ]));
+        // A state that can't be read counts as showing
+        assert!(any_window_showing([(None, Some(false))]));
+        assert!(any_window_showing([(Some(true), None)]));
+        assert!(!any_window_showing([(None, Some(true))]));
+    }
+
+    #[test]
+    fn failed_media_key_skips_only_run_unseen_and_stop_at_the_limit() {
+        assert!(skips_failed_media_key(0, false));
+        assert!(!skips_failed_media_key(0, true));
+        assert!(skips_failed_media_key(MAX_UNAVAILABLE_SKIPS - 1, false));
+        assert!(!skips_failed_media_key(MAX_UNAVAILABLE_SKIPS, false));
+
+        // Each re-sent key counts one more failure; the chain ends on its own
+        let mut failed_skips = 0;
+        while skips_failed_media_key(failed_skips, false) {
+            failed_skips += 1;
+        }
+        assert_eq!(failed_skips, MAX_UNAVAILABLE_SKIPS);
+    }
+
+    /// Progress events the loop sends in one second of ticks every `interval`.
+    fn progress_events_per_second(
+        player: &AudioPlayer,
+        reporter: &mut ProgressReporter,
+        interval: Duration,
+    ) -> usize {
+        let mut events = 0;
+        for _ in 0..Duration::from_secs(1).as_millis() / interval.as_millis() {
+            std::thread::sleep(interval);
+            let progress = ReportedProgress {
+                session_epoch: 1,
+                playback_epoch: player.playback_epoch(),
+                playing: player.is_playing(),
+                position: player.position_seconds(),
+                duration: player.duration_seconds(),
+            };
+            if reporter.should_report(progress, player.seeks_resolved(), false) {
+                events += 1;
+            }
+        }
+        events
+    }
+
+    #[test]
+    fn progress_events_follow_the_interval_and_stop_while_paused() {
+        let mut player = AudioPlayer::new(OutputBackend::Null).unwrap();
+        let file = wav(44_100, 2, 44_100 * 30);
+        player
+            .play_source(Box::new(Cursor::new(file)), None, None, None)
+            .unwrap();
+        let mut reporter = ProgressReporter::default();
+
+        // One event per tick while playing, whatever the interval
+        let ms = Duration::from_millis;
+        let playing = progress_events_per_second(&player, &mut reporter, ms(250));
+        assert!((3..=4).contains(&playing), "{} events at 250ms", playing);
+        let playing = progress_events_per_second(&player, &mut reporter, ms(100));
+        assert!((9..=10).contains(&playing), "{} events at 100ms", playing);
+        let playing = progress_events_per_second(&player, &mut reporter, ms(1000));
+        assert_eq!(playing, 1);
+
+        // Nothing while paused...
+        player.pause();
+        let paused = progress_events_per_second(&player, &mut reporter, ms(100));
+        assert_eq!(paused, 0);
+
+        // ...except once for where a seek landed
+        player.seek(20.0);
+        let paused = progress_events_per_second(&player, &mut reporter, ms(100));
+        assert_eq!(paused, 1);
+        assert_eq!(reporter.last.as_ref().map(|p| p.playing), Some(false));
+        let paused = progress_events_per_second(&player, &mut reporter, ms(100));
+        assert_eq!(paused, 0);
+
+        player.resume().unwrap();
+        let resumed = progress_events_per_second(&player, &mut reporter, ms(250));
+        assert!((3..=4).contains(&resumed), "{} events resumed", resumed);
+        player.stop();
+    }
 }
@@ -0,0 +1,143 @@
+use std::os::raw::c_void;
+use std::time::Duration;
+
+/// Event names emitted when other apps start and stop playing audio
+pub const AUDIO_FOCUS_LOST: &str = "audio-focus:lost";
+pub const AUDIO_FOCUS_REGAINED: &str = "audio-focus:regained";
+
+/// How often the audio process list is checked.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+#[repr(C)]
+struct AudioObjectPropertyAddress {
+    selector: u32,
+    scope: u32,
+    element: u32,
+}
+
+#[link(name = "CoreAudio", kind = "framework")]
+extern "C" {
+    fn AudioObjectGetPropertyDataSize(
+        object_id: u32,
+        address: *const AudioObjectPropertyAddress,
+        qualifier_size: u32,
+        qualifier: *const c_void,
+        data_size: *mut u32,
+    ) -> i32;
+    fn AudioObjectGetPropertyData(
+        object_id: u32,
+        address: *const AudioObjectPropertyAddress,
+        qualifier_size: u32,
+        qualifier: *const c_void,
+        data_size: *mut u32,
+        data: *mut c_void,
+    ) -> i32;
+}
+
+const fn fourcc(code: &[u8; 4]) -> u32 {
+    u32::from_be_bytes(*code)
+}
+
+const SYSTEM_OBJECT: u32 = 1;
+const SCOPE_GLOBAL: u32 = fourcc(b"glob");
+const ELEMENT_MAIN: u32 = 0;
+const PROCESS_OBJECT_LIST: u32 = fourcc(b"prs#");
+const PROCESS_PID: u32 = fourcc(b"ppid");
+const PROCESS_IS_RUNNING_OUTPUT: u32 = fourcc(b"piro");
+
+fn address(selector: u32) -> AudioObjectPropertyAddress {
+    AudioObjectPropertyAddress {
+        selector,
+        scope: SCOPE_GLOBAL,
+        element: ELEMENT_MAIN,
+    }
+}
+
+/// Read a fixed-size property, or None if the object doesn't have it.
+fn read_property<T: Default>(object_id: u32, selector: u32) -> Option<T> {
+    let mut value = T::default();
+    let mut size = std::mem::size_of::<T>() as u32;
+    let status = unsafe {
+        AudioObjectGetPropertyData(
+            object_id,
+            &address(selector),
+            0,
+            std::ptr::null(),
+            &mut size,
+            &mut value as *mut T as *mut c_void,
+        )
+    };
+    (status == 0).then_some(value)
+}
+
+/// CoreAudio's audio process objects. Requires macOS 14.2; older systems
+/// return None.
+fn process_objects() -> Option<Vec<u32>> {
+    let list = address(PROCESS_OBJECT_LIST);
+    let mut size = 0u32;
+    let status = unsafe {
+        AudioObjectGetPropertyDataSize(SYSTEM_OBJECT, &list, 0, std::ptr::null(), &mut size)
+    };
+    if status != 0 {
+        return None;
+    }
+    let mut ids = vec![0u32; size as usize / std::mem::size_of::<u32>()];
+    let status = unsafe {
+        AudioObjectGetPropertyData(
+            SYSTEM_OBJECT,
+            &list,
+            0,
+            std::ptr::null(),
+            &mut size,
+            ids.as_mut_ptr() as *mut c_void,
+        )
+    };
+    if status != 0 {
+        return None;
+    }
+    ids.truncate(size as usize / std::mem::size_of::<u32>());
+    Some(ids)
+}
+
+/// Whether any process other than this one is playing audio.
+fn other_app_playing(own_pid: i32) -> Option<bool> {
+    let playing = process_objects()?.into_iter().any(|id| {
+        read_property::<i32>(id, PROCESS_PID).is_some_and(|pid| pid != own_pid)
+            && read_property::<u32>(id, PROCESS_IS_RUNNING_OUTPUT).is_some_and(|r| r != 0)
+    });
+    Some(playing)
+}
+
+/// Watch for other apps playing audio, emitting `AUDIO_FOCUS_LOST` when one
+/// starts and `AUDIO_FOCUS_REGAINED` once none are left. macOS has no audio
+/// session interruptions, so this polls CoreAudio's process list; on systems
+/// without one nothing is ever emitted.
+pub fn start_audio_focus_monitor(app_handle: tauri::AppHandle) {
+    let own_pid = std::process::id() as i32;
+    if other_app_playing(own_pid).is_none() {
+        log::info!("Audio process list unavailable, not watching for other apps' audio");
+        return;
+    }
+
+    std::thread::spawn(move || {
+        use tauri::Emitter;
+        let mut focus_lost = false;
+        loop {
+            std::thread::sleep(POLL_INTERVAL);
+            let Some(playing) = other_app_playing(own_pid) else {
+                continue;
+            };
+            if playing != focus_lost {
+                focus_lost = playing;
+                let (event, change) = if playing {
+                    (AUDIO_FOCUS_LOST, "started")
+                } else {
+                    (AUDIO_FOCUS_REGAINED, "stopped")
+                };
+                log::info!("Another app {} playing audio", change);
+                let _ = app_handle.emit(event, ());
+            }
+        }
+    });
+    log::info!("Audio focus monitor started");
+}
@@ -0,0 +1,30 @@
+use crate::api::models::Track;
+use crate::error::AppError;
+use tauri::{AppHandle, Manager};
+
+/// Whether any window is on screen; see `any_window_showing`.
+pub fn any_window_visible(app: &AppHandle) -> bool {
+    crate::any_window_showing(
+        app.webview_windows()
+            .values()
+            .map(|window| (window.is_visible().ok(), window.is_minimized().ok())),
+    )
+}
+
+/// Report a track that failed to start from a media key while no window is
+/// showing the `PLAYBACK_ERROR` toast. The Now Playing widget gets an error
+/// marker until the next track replaces it.
+pub fn report_skipped(track: &Track, error: &AppError) {
+    log::info!(
+        "No window visible, reporting failed track {} through Now Playing",
+        track.id
+    );
+    super::now_playing::update_now_playing(
+        &format!("Error — skipped {}", track.title),
+        &track.artist_name,
+        &error.to_string(),
+        0.0,
+        0.0,
+        false,
+    );
+}
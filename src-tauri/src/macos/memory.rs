@@ -0,0 +1,35 @@
+/// `MACH_TASK_BASIC_INFO` flavor for `task_info`.
+const MACH_TASK_BASIC_INFO: u32 = 20;
+
+/// `mach_task_basic_info` from <mach/task_info.h>.
+#[repr(C, packed(4))]
+#[derive(Default)]
+struct MachTaskBasicInfo {
+    _virtual_size: u64,
+    resident_size: u64,
+    _resident_size_max: u64,
+    _user_time: [i32; 2],
+    _system_time: [i32; 2],
+    _policy: i32,
+    _suspend_count: i32,
+}
+
+extern "C" {
+    static mach_task_self_: u32;
+    fn task_info(task: u32, flavor: u32, info: *mut i32, count: *mut u32) -> i32;
+}
+
+/// Resident memory of this process, or None if the kernel call fails.
+pub fn resident_bytes() -> Option<u64> {
+    let mut info = MachTaskBasicInfo::default();
+    let mut count = (std::mem::size_of::<MachTaskBasicInfo>() / std::mem::size_of::<i32>()) as u32;
+    let status = unsafe {
+        task_info(
+            mach_task_self_,
+            MACH_TASK_BASIC_INFO,
+            &mut info as *mut MachTaskBasicInfo as *mut i32,
+            &mut count,
+        )
+    };
+    (status == 0).then_some(info.resident_size)
+}
@@ -1,2 +1,5 @@
+pub mod audio_focus;
+pub mod headless;
 pub mod media_keys;
+pub mod memory;
 pub mod now_playing;
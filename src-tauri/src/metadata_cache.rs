@@ -0,0 +1,211 @@
+use crate::api::models::{Album, NonTrackItem, Playlist, Track};
+use crate::config::AppConfig;
+use crate::error::AppResult;
+use crate::events::{MetadataUpdatedPayload, METADATA_UPDATED};
+use crate::resource_usage::{dir_usage, DiskUsage};
+use crate::AppState;
+use chrono::{DateTime, Utc};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::path::PathBuf;
+use tauri::{Emitter, Manager};
+
+/// Cached listings younger than this are served without a background refresh.
+const FRESH_FOR_MINUTES: i64 = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CacheKind {
+    Album,
+    Playlist,
+}
+
+impl CacheKind {
+    fn prefix(self) -> &'static str {
+        match self {
+            CacheKind::Album => "album",
+            CacheKind::Playlist => "playlist",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AlbumListing {
+    pub album: Album,
+    pub tracks: Vec<Track>,
+    /// Entries that can't be played as tracks, such as videos
+    #[serde(default)]
+    pub other_items: Vec<NonTrackItem>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlaylistListing {
+    pub playlist: Playlist,
+    pub tracks: Vec<Track>,
+    /// Entries that can't be played as tracks, such as videos
+    #[serde(default)]
+    pub other_items: Vec<NonTrackItem>,
+}
+
+/// A cached value with its fetch time. `stale` is set when the data is old
+/// enough that a refresh has been started in the background.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Cached<T> {
+    pub data: T,
+    pub fetched_at: DateTime<Utc>,
+    #[serde(default)]
+    pub stale: bool,
+}
+
+impl<T> Cached<T> {
+    pub fn fresh(data: T) -> Self {
+        Self {
+            data,
+            fetched_at: Utc::now(),
+            stale: false,
+        }
+    }
+
+    pub fn is_expired(&self) -> bool {
+        Utc::now() - self.fetched_at > chrono::Duration::minutes(FRESH_FOR_MINUTES)
+    }
+}
+
+/// Parsed album/playlist listings stored as JSON files under
+/// `<cache dir>/tauritidal/metadata_cache`, one file per id.
+pub struct MetadataCache;
+
+impl MetadataCache {
+    pub fn dir() -> AppResult<PathBuf> {
+        Ok(AppConfig::cache_dir()?.join("metadata_cache"))
+    }
+
+    /// Delete the cache left in the config dir by older versions.
+    pub fn remove_legacy_dir() {
+        let Ok(dir) = AppConfig::config_dir().map(|d| d.join("metadata_cache")) else {
+            return;
+        };
+        if dir.exists() {
+            if let Err(e) = std::fs::remove_dir_all(&dir) {
+                log::warn!(
+                    "Failed to remove old metadata cache {}: {}",
+                    dir.display(),
+                    e
+                );
+            }
+        }
+    }
+
+    fn path(kind: CacheKind, id: &str) -> AppResult<PathBuf> {
+        // Ids are used as file names, so keep only safe characters
+        let id: String = id
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '-' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        Ok(Self::dir()?.join(format!("{}_{}.json", kind.prefix(), id)))
+    }
+
+    /// Read a cached entry. A missing or unreadable file is treated as a miss.
+    pub fn load<T: DeserializeOwned>(kind: CacheKind, id: &str) -> Option<Cached<T>> {
+        let content = std::fs::read_to_string(Self::path(kind, id).ok()?).ok()?;
+        match serde_json::from_str(&content) {
+            Ok(cached) => Some(cached),
+            Err(e) => {
+                log::warn!("Ignoring corrupt {} cache for {}: {}", kind.prefix(), id, e);
+                None
+            }
+        }
+    }
+
+    pub fn store<T: Serialize>(kind: CacheKind, id: &str, cached: &Cached<T>) -> AppResult<()> {
+        std::fs::create_dir_all(Self::dir()?)?;
+        let content = serde_json::to_string(cached)?;
+        std::fs::write(Self::path(kind, id)?, content)?;
+        Ok(())
+    }
+
+    pub fn invalidate(kind: CacheKind, id: &str) {
+        if let Ok(path) = Self::path(kind, id) {
+            if path.exists() {
+                if let Err(e) = std::fs::remove_file(&path) {
+                    log::warn!(
+                        "Failed to invalidate {} cache for {}: {}",
+                        kind.prefix(),
+                        id,
+                        e
+                    );
+                }
+            }
+        }
+    }
+
+    pub fn usage() -> DiskUsage {
+        Self::dir().map(|dir| dir_usage(&dir)).unwrap_or_default()
+    }
+
+    pub fn clear() -> AppResult<()> {
+        let dir = Self::dir()?;
+        if dir.exists() {
+            std::fs::remove_dir_all(&dir)?;
+        }
+        Ok(())
+    }
+}
+
+/// Serve `kind`/`id` from the cache, starting a background refresh when the
+/// entry has expired and the API is reachable. On a miss the listing is fetched and cached before
+/// returning. A refresh that changes the data emits `METADATA_UPDATED`.
+pub async fn cached_or_fetch<T, F, Fut>(
+    app: &tauri::AppHandle,
+    kind: CacheKind,
+    id: &str,
+    fetch: F,
+) -> AppResult<Cached<T>>
+where
+    T: Serialize + DeserializeOwned + Send + 'static,
+    F: FnOnce() -> Fut + Send + 'static,
+    Fut: Future<Output = AppResult<T>> + Send + 'static,
+{
+    let Some(mut cached) = MetadataCache::load::<T>(kind, id) else {
+        let cached = Cached::fresh(fetch().await?);
+        if let Err(e) = MetadataCache::store(kind, id, &cached) {
+            log::warn!("Failed to cache {} {}: {}", kind.prefix(), id, e);
+        }
+        return Ok(cached);
+    };
+
+    let offline = app.state::<AppState>().tidal_client.is_offline();
+    if cached.is_expired() && !offline {
+        cached.stale = true;
+        let previous = serde_json::to_value(&cached.data).ok();
+        let app = app.clone();
+        let id = id.to_string();
+        tauri::async_runtime::spawn(async move {
+            let data = match fetch().await {
+                Ok(data) => data,
+                Err(e) => {
+                    log::warn!("Refreshing {} {} failed: {}", kind.prefix(), id, e);
+                    return;
+                }
+            };
+            let changed = serde_json::to_value(&data).ok() != previous;
+            if let Err(e) = MetadataCache::store(kind, &id, &Cached::fresh(data)) {
+                log::warn!("Failed to cache {} {}: {}", kind.prefix(), id, e);
+            }
+            if changed {
+                let _ = app.emit(METADATA_UPDATED, MetadataUpdatedPayload { kind, id });
+            }
+        });
+    }
+    Ok(cached)
+}
@@ -0,0 +1,145 @@
+use crate::error::{AppError, AppResult};
+use serde::Serialize;
+
+/// Artwork is shrunk to this size before clustering; plenty for dominant colors.
+const SAMPLE_SIZE: u32 = 64;
+
+/// Dominant colors returned per image.
+const PALETTE_SIZE: usize = 3;
+
+const KMEANS_ITERATIONS: usize = 8;
+
+/// Dominant colors of a cover image, as `#rrggbb` strings.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArtworkPalette {
+    /// Most common color first
+    pub colors: Vec<String>,
+    /// Black or white, whichever contrasts more with the dominant color
+    pub text_color: String,
+}
+
+type Rgb = [f32; 3];
+
+/// Decode an image and compute its palette with k-means over a downscaled copy.
+pub fn extract_palette(bytes: &[u8]) -> AppResult<ArtworkPalette> {
+    let image = image::load_from_memory(bytes)
+        .map_err(|e| AppError::Decode(format!("Artwork: {}", e)))?
+        .thumbnail(SAMPLE_SIZE, SAMPLE_SIZE)
+        .to_rgb8();
+    let pixels: Vec<Rgb> = image
+        .pixels()
+        .map(|p| [p[0] as f32, p[1] as f32, p[2] as f32])
+        .collect();
+    if pixels.is_empty() {
+        return Err(AppError::Decode("Artwork has no pixels".into()));
+    }
+
+    let clusters = kmeans(&pixels, initial_centroids(&pixels));
+    let dominant = clusters[0].0;
+    Ok(ArtworkPalette {
+        colors: clusters.iter().map(|(c, _)| to_hex(*c)).collect(),
+        text_color: text_color_for(dominant).to_string(),
+    })
+}
+
+/// Seed centroids from the most common colors of a coarse 4-bit-per-channel
+/// histogram, so results are deterministic for the same image.
+fn initial_centroids(pixels: &[Rgb]) -> Vec<Rgb> {
+    let bucket =
+        |p: &Rgb| ((p[0] as usize >> 4) << 8) | ((p[1] as usize >> 4) << 4) | (p[2] as usize >> 4);
+    let mut counts = vec![(0usize, [0.0f32; 3]); 4096];
+    for p in pixels {
+        let entry = &mut counts[bucket(p)];
+        entry.0 += 1;
+        for (sum, v) in entry.1.iter_mut().zip(p) {
+            *sum += v;
+        }
+    }
+    let mut buckets: Vec<(usize, Rgb)> = counts
+        .into_iter()
+        .filter(|(n, _)| *n > 0)
+        .map(|(n, sum)| (n, sum.map(|v| v / n as f32)))
+        .collect();
+    buckets.sort_by_key(|&(n, _)| std::cmp::Reverse(n));
+    buckets
+        .into_iter()
+        .take(PALETTE_SIZE)
+        .map(|(_, c)| c)
+        .collect()
+}
+
+/// Refine `centroids` and return them with their pixel counts, largest first.
+/// Clusters that end up empty are dropped.
+fn kmeans(pixels: &[Rgb], mut centroids: Vec<Rgb>) -> Vec<(Rgb, usize)> {
+    let mut assignments = vec![0usize; pixels.len()];
+    for _ in 0..KMEANS_ITERATIONS {
+        for (p, slot) in pixels.iter().zip(assignments.iter_mut()) {
+            *slot = nearest(&centroids, p);
+        }
+        let mut sums = vec![([0.0f32; 3], 0usize); centroids.len()];
+        for (p, &k) in pixels.iter().zip(&assignments) {
+            for (sum, v) in sums[k].0.iter_mut().zip(p) {
+                *sum += v;
+            }
+            sums[k].1 += 1;
+        }
+        for (centroid, (sum, n)) in centroids.iter_mut().zip(&sums) {
+            if *n > 0 {
+                *centroid = sum.map(|v| v / *n as f32);
+            }
+        }
+    }
+
+    let mut sizes = vec![0usize; centroids.len()];
+    for &k in &assignments {
+        sizes[k] += 1;
+    }
+    let mut clusters: Vec<(Rgb, usize)> = centroids
+        .into_iter()
+        .zip(sizes)
+        .filter(|(_, n)| *n > 0)
+        .collect();
+    clusters.sort_by_key(|&(_, n)| std::cmp::Reverse(n));
+    clusters
+}
+
+fn nearest(centroids: &[Rgb], p: &Rgb) -> usize {
+    let distance = |c: &Rgb| (0..3).map(|ch| (c[ch] - p[ch]).powi(2)).sum::<f32>();
+    centroids
+        .iter()
+        .enumerate()
+        .min_by(|a, b| distance(a.1).total_cmp(&distance(b.1)))
+        .map(|(i, _)| i)
+        .unwrap_or(0)
+}
+
+fn to_hex(c: Rgb) -> String {
+    let [r, g, b] = c.map(|v| v.round().clamp(0.0, 255.0) as u8);
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+/// WCAG relative luminance of an sRGB color.
+fn relative_luminance(c: Rgb) -> f32 {
+    let [r, g, b] = c.map(|v| {
+        let v = v / 255.0;
+        if v <= 0.03928 {
+            v / 12.92
+        } else {
+            ((v + 0.055) / 1.055).powf(2.4)
+        }
+    });
+    0.2126 * r + 0.7152 * g + 0.0722 * b
+}
+
+/// Pick white or black text by WCAG contrast ratio against `background`.
+fn text_color_for(background: Rgb) -> &'static str {
+    let l = relative_luminance(background);
+    let contrast_white = 1.05 / (l + 0.05);
+    let contrast_black = (l + 0.05) / 0.05;
+    if contrast_white >= contrast_black {
+        "#ffffff"
+    } else {
+        "#000000"
+    }
+}
@@ -0,0 +1,111 @@
+use crate::api::models::Track;
+use crate::config::AppConfig;
+use crate::error::AppResult;
+use crate::text::sort_key;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+
+/// Track order to show a playlist in. `Default` keeps the playlist's own order.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PlaylistSort {
+    #[default]
+    Default,
+    Title,
+    Artist,
+    DateAdded,
+}
+
+impl PlaylistSort {
+    /// Sort `tracks` in place. `added` maps track ids to when they were added;
+    /// tracks without a date go last.
+    pub fn apply(self, tracks: &mut [Track], added: &HashMap<String, DateTime<Utc>>) {
+        match self {
+            PlaylistSort::Default => {}
+            PlaylistSort::Title => {
+                tracks.sort_by_cached_key(|t| sort_key(&t.title));
+            }
+            PlaylistSort::Artist => {
+                tracks.sort_by_cached_key(|t| (sort_key(&t.artist_name), sort_key(&t.title)));
+            }
+            PlaylistSort::DateAdded => {
+                tracks.sort_by_key(|t| (added.get(&t.id).is_none(), added.get(&t.id).copied()));
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlaylistPrefs {
+    #[serde(default)]
+    pub pinned: bool,
+    #[serde(default)]
+    pub sort: PlaylistSort,
+}
+
+impl PlaylistPrefs {
+    fn is_default(&self) -> bool {
+        !self.pinned && self.sort == PlaylistSort::Default
+    }
+}
+
+/// Local-only per-playlist preferences keyed by playlist id, stored in
+/// `playlist_prefs.json`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlaylistPrefsStore {
+    #[serde(default)]
+    pub playlists: HashMap<String, PlaylistPrefs>,
+}
+
+impl PlaylistPrefsStore {
+    pub fn path() -> AppResult<PathBuf> {
+        Ok(AppConfig::config_dir()?.join("playlist_prefs.json"))
+    }
+
+    /// Load the prefs, starting empty if none have been saved.
+    pub fn load() -> AppResult<Self> {
+        let path = Self::path()?;
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(&path)?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    pub fn save(&self) -> AppResult<()> {
+        std::fs::create_dir_all(AppConfig::config_dir()?)?;
+        let content = serde_json::to_string_pretty(self)?;
+        std::fs::write(Self::path()?, content)?;
+        Ok(())
+    }
+
+    pub fn get(&self, playlist_id: &str) -> PlaylistPrefs {
+        self.playlists.get(playlist_id).copied().unwrap_or_default()
+    }
+
+    /// Store prefs for a playlist. Default prefs remove the entry.
+    pub fn set(&mut self, playlist_id: &str, prefs: PlaylistPrefs) {
+        if prefs.is_default() {
+            self.playlists.remove(playlist_id);
+        } else {
+            self.playlists.insert(playlist_id.to_string(), prefs);
+        }
+    }
+
+    pub fn remove(&mut self, playlist_id: &str) -> bool {
+        self.playlists.remove(playlist_id).is_some()
+    }
+
+    /// Drop prefs for playlists that no longer exist. Returns whether any were removed.
+    pub fn prune<'a>(&mut self, existing: impl IntoIterator<Item = &'a str>) -> bool {
+        let existing: HashSet<&str> = existing.into_iter().collect();
+        let before = self.playlists.len();
+        self.playlists
+            .retain(|id, _| existing.contains(id.as_str()));
+        self.playlists.len() != before
+    }
+}
@@ -0,0 +1,435 @@
+use crate::api::models::{Album, Artist};
+use crate::config::AppConfig;
+use crate::error::AppResult;
+use crate::events;
+use crate::AppState;
+use chrono::{DateTime, NaiveDate, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use tauri::{AppHandle, Emitter, Manager};
+
+/// Followed artists checked per run. Larger collections are covered over
+/// several runs, least recently checked first.
+const ARTISTS_PER_RUN: usize = 25;
+
+/// Pause between artist album lookups, to stay clear of rate limits.
+const REQUEST_SPACING: std::time::Duration = std::time::Duration::from_secs(2);
+
+/// Time between scheduled runs, counted from the last one.
+const RUN_INTERVAL: TimeDelta = TimeDelta::hours(24);
+
+/// How long after launch the first scheduled run may start.
+const STARTUP_DELAY: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// How often the schedule checks whether a run is due.
+const SCHEDULE_POLL: std::time::Duration = std::time::Duration::from_secs(60 * 60);
+
+/// Releases kept on the radar; older ones are dropped.
+const MAX_RELEASES: usize = 100;
+
+/// An album by a followed artist that came out after their previous newest one.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NewRelease {
+    pub artist_id: String,
+    pub artist_name: String,
+    pub album: Album,
+    pub found_at: DateTime<Utc>,
+}
+
+/// What the release radar has seen, stored in `release_radar.json`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReleaseRadarStore {
+    /// Newest release date seen per artist id
+    #[serde(default)]
+    pub last_seen: HashMap<String, NaiveDate>,
+    /// When each artist was last checked
+    #[serde(default)]
+    pub checked_at: HashMap<String, DateTime<Utc>>,
+    /// Releases found so far, most recently found first
+    #[serde(default)]
+    pub releases: Vec<NewRelease>,
+    #[serde(default)]
+    pub last_run: Option<DateTime<Utc>>,
+}
+
+impl ReleaseRadarStore {
+    pub fn path() -> AppResult<PathBuf> {
+        Ok(AppConfig::config_dir()?.join("release_radar.json"))
+    }
+
+    /// Load the radar, starting empty if it has never run.
+    pub fn load() -> AppResult<Self> {
+        let path = Self::path()?;
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(&path)?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    pub fn save(&self) -> AppResult<()> {
+        std::fs::create_dir_all(AppConfig::config_dir()?)?;
+        let content = serde_json::to_string_pretty(self)?;
+        std::fs::write(Self::path()?, content)?;
+        Ok(())
+    }
+
+    /// Whether a scheduled run is due at `now`.
+    pub fn run_due(&self, now: DateTime<Utc>) -> bool {
+        self.last_run.is_none_or(|last| now - last >= RUN_INTERVAL)
+    }
+
+    /// Forget artists that are no longer followed. Their found releases stay.
+    pub fn retain_artists<'a>(&mut self, followed: impl IntoIterator<Item = &'a str>) {
+        let followed: HashSet<&str> = followed.into_iter().collect();
+        self.last_seen
+            .retain(|id, _| followed.contains(id.as_str()));
+        self.checked_at
+            .retain(|id, _| followed.contains(id.as_str()));
+    }
+
+    /// Up to `limit` artists to check next: never checked ones first, then
+    /// the least recently checked.
+    pub fn due<'a>(&self, followed: &'a [Artist], limit: usize) -> Vec<&'a Artist> {
+        let mut due: Vec<&Artist> = followed.iter().collect();
+        due.sort_by_key(|a| self.checked_at.get(&a.id));
+        due.truncate(limit);
+        due
+    }
+
+    /// Compare an artist's albums against the newest release seen for them
+    /// and return the ones that are newer. The first check of an artist only
+    /// records what's there, so following someone doesn't fill the radar
+    /// with their back catalogue. Albums dated after `now` aren't out yet
+    /// and wait for a later check.
+    pub fn record(
+        &mut self,
+        artist: &Artist,
+        albums: Vec<Album>,
+        now: DateTime<Utc>,
+    ) -> Vec<NewRelease> {
+        self.checked_at.insert(artist.id.clone(), now);
+        let today = now.date_naive();
+        let released: Vec<(NaiveDate, Album)> = albums
+            .into_iter()
+            .filter_map(|album| Some((album.release_date?, album)))
+            .filter(|(date, _)| *date <= today)
+            .collect();
+        let Some(newest) = released.iter().map(|(date, _)| *date).max() else {
+            return Vec::new();
+        };
+
+        // Kept at the latest date ever seen, so a pulled album can't bring
+        // older ones back as new
+        let previous = self.last_seen.get(&artist.id).copied();
+        self.last_seen.insert(
+            artist.id.clone(),
+            previous.map_or(newest, |p| p.max(newest)),
+        );
+        let Some(previous) = previous else {
+            return Vec::new();
+        };
+        let known: HashSet<&str> = self.releases.iter().map(|r| r.album.id.as_str()).collect();
+        let mut found: Vec<NewRelease> = released
+            .into_iter()
+            .filter(|(date, album)| *date > previous && !known.contains(album.id.as_str()))
+            .map(|(_, album)| NewRelease {
+                artist_id: artist.id.clone(),
+                artist_name: artist.name.clone(),
+                album,
+                found_at: now,
+            })
+            .collect();
+        found.sort_by_key(|r| std::cmp::Reverse(r.album.release_date));
+
+        self.releases.splice(0..0, found.iter().cloned());
+        self.releases.truncate(MAX_RELEASES);
+        found
+    }
+}
+
+/// Check followed artists for albums newer than the ones seen before, at
+/// most `ARTISTS_PER_RUN` at a time. New releases are saved, announced with
+/// `NEW_RELEASES_FOUND` and returned.
+pub async fn check(app: &AppHandle) -> AppResult<Vec<NewRelease>> {
+    let state = app.state::<AppState>();
+    let _running = state.release_radar_check.lock().await;
+    let client = &state.tidal_client;
+
+    let followed = client.get_favorite_artists().await?;
+    let due: Vec<Artist> = {
+        let mut store = state.release_radar.write().await;
+        store.retain_artists(followed.iter().map(|a| a.id.as_str()));
+        store
+            .due(&followed, ARTISTS_PER_RUN)
+            .into_iter()
+            .cloned()
+            .collect()
+    };
+    log::info!(
+        "Release radar checking {} of {} followed artists",
+        due.len(),
+        followed.len()
+    );
+
+    let mut found = Vec::new();
+    for (i, artist) in due.iter().enumerate() {
+        if i > 0 {
+            tokio::time::sleep(REQUEST_SPACING).await;
+        }
+        if client.is_offline() {
+            log::info!("Release radar stopped early, offline");
+            break;
+        }
+        match client.get_artist_albums(&artist.id).await {
+            Ok(albums) => {
+                let mut store = state.release_radar.write().await;
+                found.extend(store.record(artist, albums, Utc::now()));
+            }
+            Err(e) => log::warn!(
+                "Release radar couldn't fetch albums of {}: {}",
+                artist.id,
+                e
+            ),
+        }
+    }
+
+    let mut store = state.release_radar.write().await;
+    store.last_run = Some(Utc::now());
+    store.save()?;
+    drop(store);
+
+    if !found.is_empty() {
+        log::info!("Release radar found {} new releases", found.len());
+        let _ = app.emit(
+            events::NEW_RELEASES_FOUND,
+            events::NewReleasesFoundPayload {
+                releases: found.clone(),
+            },
+        );
+    }
+    Ok(found)
+}
+
+/// Whether there is a user whose followed artists can be checked. A
+/// client credentials token alone has no collection to read.
+fn signed_in(config: &AppConfig) -> bool {
+    config.is_authenticated() && config.user_id.is_some()
+}
+
+/// Run `check` once a day for as long as the app is open. The interval is
+/// counted from the last saved run, so restarting doesn't check again.
+pub async fn run_daily(app: AppHandle) {
+    tokio::time::sleep(STARTUP_DELAY).await;
+    loop {
+        let state = app.state::<AppState>();
+        let due = state.release_radar.read().await.run_due(Utc::now());
+        let ready = signed_in(&*state.tidal_client.config().read().await)
+            && !state.tidal_client.is_offline();
+        if due && ready {
+            if let Err(e) = check(&app).await {
+                log::warn!("Release radar check failed: {}", e);
+            }
+        }
+        tokio::time::sleep(SCHEDULE_POLL).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn artist(id: &str) -> Artist {
+        Artist {
+            id: id.into(),
+            name: format!("Artist {}", id),
+            picture_url: None,
+        }
+    }
+
+    fn album(id: &str, released: &str) -> Album {
+        Album {
+            id: id.into(),
+            title: format!("Album {}", id),
+            artist_name: "Artist".into(),
+            artist_id: None,
+            duration: None,
+            number_of_tracks: None,
+            number_of_volumes: None,
+            release_date: Some(released.parse().unwrap()),
+            artwork_url: None,
+            artwork: Vec::new(),
+            media_tags: Vec::new(),
+            is_new: false,
+        }
+    }
+
+    fn at(date: &str) -> DateTime<Utc> {
+        format!("{}T12:00:00Z", date).parse().unwrap()
+    }
+
+    fn ids(releases: &[NewRelease]) -> Vec<&str> {
+        releases.iter().map(|r| r.album.id.as_str()).collect()
+    }
+
+    #[test]
+    fn the_first_check_only_records_the_back_catalogue() {
+        let mut store = ReleaseRadarStore::default();
+        let a = artist("1");
+        let found = store.record(
+            &a,
+            vec![album("old", "2019-05-01"), album("newest", "2024-02-01")],
+            at("2024-03-01"),
+        );
+        assert!(found.is_empty());
+        assert!(store.releases.is_empty());
+        assert_eq!(store.last_seen["1"], "2024-02-01".parse().unwrap());
+        assert_eq!(store.checked_at["1"], at("2024-03-01"));
+    }
+
+    #[test]
+    fn albums_newer_than_the_last_seen_are_new_releases() {
+        let mut store = ReleaseRadarStore::default();
+        let a = artist("1");
+        store.record(&a, vec![album("old", "2024-02-01")], at("2024-03-01"));
+
+        let found = store.record(
+            &a,
+            vec![
+                album("old", "2024-02-01"),
+                album("single", "2024-03-10"),
+                album("lp", "2024-03-20"),
+            ],
+            at("2024-03-25"),
+        );
+        // Newest first, on the radar as well as returned
+        assert_eq!(ids(&found), ["lp", "single"]);
+        assert_eq!(ids(&store.releases), ["lp", "single"]);
+        assert_eq!(found[0].artist_name, "Artist 1");
+        assert_eq!(found[0].found_at, at("2024-03-25"));
+
+        // Seen now, so the next check finds nothing
+        let again = store.record(
+            &a,
+            vec![album("single", "2024-03-10"), album("lp", "2024-03-20")],
+            at("2024-03-26"),
+        );
+        assert!(again.is_empty());
+        assert_eq!(store.releases.len(), 2);
+    }
+
+    #[test]
+    fn unreleased_albums_wait_for_their_release_date() {
+        let mut store = ReleaseRadarStore::default();
+        let a = artist("1");
+        store.record(&a, vec![album("old", "2024-01-01")], at("2024-03-01"));
+
+        let upcoming = vec![album("old", "2024-01-01"), album("soon", "2024-04-01")];
+        assert!(store
+            .record(&a, upcoming.clone(), at("2024-03-15"))
+            .is_empty());
+        assert_eq!(store.last_seen["1"], "2024-01-01".parse().unwrap());
+        assert_eq!(ids(&store.record(&a, upcoming, at("2024-04-01"))), ["soon"]);
+    }
+
+    #[test]
+    fn a_pulled_album_doesnt_bring_older_ones_back() {
+        let mut store = ReleaseRadarStore::default();
+        let a = artist("1");
+        store.record(
+            &a,
+            vec![album("old", "2023-01-01"), album("new", "2024-01-01")],
+            at("2024-02-01"),
+        );
+        // The newest album disappears from the catalogue
+        assert!(store
+            .record(&a, vec![album("old", "2023-01-01")], at("2024-02-02"))
+            .is_empty());
+        assert_eq!(store.last_seen["1"], "2024-01-01".parse().unwrap());
+        // An artist without dated albums is still marked checked
+        assert!(store
+            .record(&artist("2"), Vec::new(), at("2024-02-02"))
+            .is_empty());
+        assert_eq!(store.checked_at["2"], at("2024-02-02"));
+    }
+
+    #[test]
+    fn an_album_already_on_the_radar_isnt_added_twice() {
+        let mut store = ReleaseRadarStore::default();
+        let (a, b) = (artist("1"), artist("2"));
+        store.record(&a, vec![album("old", "2024-01-01")], at("2024-02-01"));
+        store.record(&b, vec![album("older", "2023-01-01")], at("2024-02-01"));
+
+        // A collaboration listed under both artists
+        let collab = album("collab", "2024-02-10");
+        assert_eq!(
+            ids(&store.record(&a, vec![collab.clone()], at("2024-02-15"))),
+            ["collab"]
+        );
+        assert!(store.record(&b, vec![collab], at("2024-02-15")).is_empty());
+        assert_eq!(ids(&store.releases), ["collab"]);
+    }
+
+    #[test]
+    fn the_radar_keeps_the_most_recent_releases() {
+        let mut store = ReleaseRadarStore::default();
+        let a = artist("1");
+        store.record(&a, vec![album("0", "2020-01-01")], at("2020-01-02"));
+        for day in 1..=MAX_RELEASES + 5 {
+            let date = at("2020-01-02") + TimeDelta::days(day as i64);
+            let released = date.date_naive().to_string();
+            store.record(&a, vec![album(&day.to_string(), &released)], date);
+        }
+        assert_eq!(store.releases.len(), MAX_RELEASES);
+        assert_eq!(store.releases[0].album.id, (MAX_RELEASES + 5).to_string());
+    }
+
+    #[test]
+    fn unfollowed_artists_are_forgotten_and_the_oldest_checks_come_first() {
+        let mut store = ReleaseRadarStore::default();
+        for (id, day) in [
+            ("1", "2024-03-02"),
+            ("2", "2024-03-01"),
+            ("3", "2024-03-03"),
+        ] {
+            store.record(&artist(id), vec![album(id, "2024-01-01")], at(day));
+        }
+        store.retain_artists(["1", "2"]);
+        assert!(!store.last_seen.contains_key("3"));
+        assert!(!store.checked_at.contains_key("3"));
+
+        let followed = [artist("1"), artist("2"), artist("4")];
+        let due: Vec<&str> = store
+            .due(&followed, 2)
+            .into_iter()
+            .map(|a| a.id.as_str())
+            .collect();
+        assert_eq!(due, ["4", "2"]);
+    }
+
+    #[test]
+    fn runs_are_due_a_day_after_the_last_one() {
+        let mut store = ReleaseRadarStore::default();
+        assert!(store.run_due(at("2024-03-01")));
+        store.last_run = Some(at("2024-03-01"));
+        assert!(!store.run_due(at("2024-03-01") + TimeDelta::hours(23)));
+        assert!(store.run_due(at("2024-03-02")));
+    }
+
+    #[test]
+    fn scheduled_runs_need_a_signed_in_user() {
+        let mut config = AppConfig {
+            access_token: Some("token".into()),
+            ..AppConfig::default()
+        };
+        // Client credentials: a token but no collection
+        assert!(!signed_in(&config));
+        config.user_id = Some("1".into());
+        assert!(signed_in(&config));
+        config.access_token = None;
+        assert!(!signed_in(&config));
+    }
+}
@@ -0,0 +1,329 @@
+use crate::api::models::Track;
+use crate::commands::playback_commands::{self, PlaybackStatus};
+use crate::error::{AppError, AppResult};
+use crate::events;
+use crate::AppState;
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{Request, State};
+use axum::http::{header, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use serde::{Deserialize, Serialize};
+use std::net::Ipv4Addr;
+use std::sync::Arc;
+use tauri::{AppHandle, EventId, Listener, Manager};
+use tokio::sync::{broadcast, oneshot};
+
+/// Playback events passed on to WebSocket clients.
+const FORWARDED_EVENTS: &[&str] = &[
+    events::PLAYBACK_PROGRESS,
+    events::PLAYBACK_TRACK_CHANGED,
+    events::PLAYBACK_STATE_CHANGED,
+    events::PLAYBACK_TRACK_ENDED,
+    events::PLAYBACK_QUEUE_CHANGED,
+    events::PLAYBACK_ERROR,
+    events::PLAYBACK_MODE_CHANGED,
+    events::PLAYBACK_UPNEXT,
+    events::VOLUME_CHANGED,
+];
+
+/// Events buffered per WebSocket client; a client further behind misses some.
+const EVENT_BUFFER: usize = 64;
+
+/// How long stopping waits for open requests before dropping them.
+const STOP_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(2);
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoteControlSettings {
+    pub enabled: bool,
+    pub port: u16,
+    /// Listen on every interface so phones on the LAN can connect; otherwise
+    /// only localhost
+    pub allow_lan: bool,
+    /// Sent as `Authorization: Bearer <token>` or a `token` query parameter
+    /// (browsers can't set headers on WebSockets)
+    pub token: String,
+}
+
+impl Default for RemoteControlSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: 47823,
+            allow_lan: false,
+            token: new_token(),
+        }
+    }
+}
+
+/// A fresh random access token.
+pub fn new_token() -> String {
+    uuid::Uuid::new_v4().simple().to_string()
+}
+
+/// A running server and the event listeners feeding its WebSockets.
+pub struct RemoteServer {
+    port: u16,
+    shutdown: oneshot::Sender<()>,
+    task: tauri::async_runtime::JoinHandle<()>,
+    listeners: Vec<EventId>,
+}
+
+impl RemoteServer {
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    /// Stop listening, giving open requests `STOP_TIMEOUT` to finish.
+    pub async fn stop(self, app: &AppHandle) {
+        for id in self.listeners {
+            app.unlisten(id);
+        }
+        let _ = self.shutdown.send(());
+        let mut task = self.task;
+        if tokio::time::timeout(STOP_TIMEOUT, &mut task).await.is_err() {
+            task.abort();
+        }
+        log::info!("Remote control server on port {} stopped", self.port);
+    }
+}
+
+/// Shared by every request handler.
+struct Remote {
+    app: AppHandle,
+    token: String,
+    events: broadcast::Sender<String>,
+}
+
+/// Start or stop the server to match the saved settings. Any running server
+/// is stopped first, so this also applies a new port or token.
+pub async fn apply_settings(app: &AppHandle) -> AppResult<()> {
+    let state = app.state::<AppState>();
+    let mut server = state.remote_server.lock().await;
+    if let Some(running) = server.take() {
+        running.stop(app).await;
+    }
+    let settings = state
+        .tidal_client
+        .config()
+        .read()
+        .await
+        .remote_control
+        .clone();
+    if settings.enabled {
+        *server = Some(start(app, &settings).await?);
+    }
+    Ok(())
+}
+
+async fn start(app: &AppHandle, settings: &RemoteControlSettings) -> AppResult<RemoteServer> {
+    let ip = if settings.allow_lan {
+        Ipv4Addr::UNSPECIFIED
+    } else {
+        Ipv4Addr::LOCALHOST
+    };
+    let listener = tokio::net::TcpListener::bind((ip, settings.port))
+        .await
+        .map_err(|e| {
+            AppError::Config(format!(
+                "Can't listen for remote control on port {}: {}",
+                settings.port, e
+            ))
+        })?;
+
+    let (events_tx, _) = broadcast::channel(EVENT_BUFFER);
+    let listeners = FORWARDED_EVENTS
+        .iter()
+        .map(|&name| {
+            let tx = events_tx.clone();
+            app.listen_any(name, move |event| {
+                // Payloads are already JSON; no receivers just means no clients
+                let _ = tx.send(format!(
+                    r#"{{"event":"{}","payload":{}}}"#,
+                    name,
+                    event.payload()
+                ));
+            })
+        })
+        .collect();
+
+    let remote = Arc::new(Remote {
+        app: app.clone(),
+        token: settings.token.clone(),
+        events: events_tx,
+    });
+    let router = Router::new()
+        .route("/status", get(status))
+        .route("/play", post(play))
+        .route("/pause", post(pause))
+        .route("/next", post(next))
+        .route("/previous", post(previous))
+        .route("/seek", post(seek))
+        .route("/volume", post(volume))
+        .route("/events", get(events_socket))
+        .layer(middleware::from_fn_with_state(
+            Arc::clone(&remote),
+            require_token,
+        ))
+        .with_state(remote);
+
+    let (shutdown, shutdown_rx) = oneshot::channel();
+    let task = tauri::async_runtime::spawn(async move {
+        let served = axum::serve(listener, router)
+            .with_graceful_shutdown(async {
+                let _ = shutdown_rx.await;
+            })
+            .await;
+        if let Err(e) = served {
+            log::error!("Remote control server failed: {}", e);
+        }
+    });
+    log::info!(
+        "Remote control server listening on {}:{}",
+        ip,
+        settings.port
+    );
+
+    Ok(RemoteServer {
+        port: settings.port,
+        shutdown,
+        task,
+        listeners,
+    })
+}
+
+/// Reject requests without the access token.
+async fn require_token(
+    State(remote): State<Arc<Remote>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let bearer = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .map(String::from);
+    let query = request.uri().query().and_then(|q| {
+        url::form_urlencoded::parse(q.as_bytes())
+            .find(|(key, _)| key == "token")
+            .map(|(_, value)| value.into_owned())
+    });
+    match bearer.or(query) {
+        Some(token) if tokens_match(&token, &remote.token) => next.run(request).await,
+        _ => StatusCode::UNAUTHORIZED.into_response(),
+    }
+}
+
+/// Compare tokens in time independent of where they differ.
+fn tokens_match(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0, |diff, (x, y)| diff | (x ^ y))
+            == 0
+}
+
+/// A command's result as JSON, or its error with a 500.
+fn reply<T: Serialize>(result: Result<T, AppError>) -> Response {
+    match result {
+        Ok(value) => Json(value).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, Json(e)).into_response(),
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RemoteStatus {
+    #[serde(flatten)]
+    playback: PlaybackStatus,
+    track: Option<Track>,
+    volume: f32,
+    muted: bool,
+}
+
+async fn status(State(remote): State<Arc<Remote>>) -> Response {
+    reply(remote_status(&remote.app).await)
+}
+
+async fn remote_status(app: &AppHandle) -> Result<RemoteStatus, AppError> {
+    let playback = playback_commands::get_playback_status(app.state()).await?;
+    let state = app.state::<AppState>();
+    let track = state.current_track.read().await.clone();
+    let config = state.tidal_client.config().read().await;
+    Ok(RemoteStatus {
+        playback,
+        track,
+        volume: config.volume,
+        muted: config.muted,
+    })
+}
+
+async fn play(State(remote): State<Arc<Remote>>) -> Response {
+    reply(playback_commands::resume(remote.app.state(), remote.app.clone()).await)
+}
+
+async fn pause(State(remote): State<Arc<Remote>>) -> Response {
+    reply(playback_commands::pause(remote.app.state(), remote.app.clone()).await)
+}
+
+async fn next(State(remote): State<Arc<Remote>>) -> Response {
+    reply(playback_commands::next_track(remote.app.state(), remote.app.clone()).await)
+}
+
+async fn previous(State(remote): State<Arc<Remote>>) -> Response {
+    reply(playback_commands::previous_track(remote.app.state(), remote.app.clone()).await)
+}
+
+#[derive(Debug, Deserialize)]
+struct SeekRequest {
+    /// Seconds from the start of the track
+    position: f64,
+}
+
+async fn seek(State(remote): State<Arc<Remote>>, Json(body): Json<SeekRequest>) -> Response {
+    reply(playback_commands::seek(remote.app.state(), remote.app.clone(), body.position).await)
+}
+
+#[derive(Debug, Deserialize)]
+struct VolumeRequest {
+    /// 0.0 to 1.0
+    volume: f32,
+}
+
+async fn volume(State(remote): State<Arc<Remote>>, Json(body): Json<VolumeRequest>) -> Response {
+    reply(playback_commands::set_volume(remote.app.state(), remote.app.clone(), body.volume).await)
+}
+
+/// Stream playback events as `{"event": name, "payload": ...}` text messages.
+async fn events_socket(State(remote): State<Arc<Remote>>, ws: WebSocketUpgrade) -> Response {
+    let events = remote.events.subscribe();
+    ws.on_upgrade(move |socket| forward_events(socket, events))
+}
+
+async fn forward_events(mut socket: WebSocket, mut events: broadcast::Receiver<String>) {
+    loop {
+        tokio::select! {
+            event = events.recv() => match event {
+                Ok(text) => {
+                    if socket.send(Message::Text(text)).await.is_err() {
+                        break;
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(missed)) => {
+                    log::debug!("Remote control client missed {} events", missed);
+                }
+                // The server stopped
+                Err(broadcast::error::RecvError::Closed) => break,
+            },
+            // Clients only listen; anything but a close is ignored
+            incoming = socket.recv() => match incoming {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+}
@@ -0,0 +1,45 @@
+use serde::Serialize;
+use std::path::Path;
+
+/// Files and bytes a disk cache takes up.
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiskUsage {
+    pub files: u64,
+    pub bytes: u64,
+}
+
+/// Total size of the files directly in `dir`. A missing or unreadable
+/// directory counts as empty.
+pub fn dir_usage(dir: &Path) -> DiskUsage {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return DiskUsage::default();
+    };
+    entries
+        .filter_map(|e| e.ok()?.metadata().ok())
+        .filter(|m| m.is_file())
+        .fold(DiskUsage::default(), |usage, m| DiskUsage {
+            files: usage.files + 1,
+            bytes: usage.bytes + m.len(),
+        })
+}
+
+/// Resident memory of this process, on platforms where it's cheap to read.
+pub fn process_rss() -> Option<u64> {
+    #[cfg(target_os = "macos")]
+    {
+        crate::macos::memory::resident_bytes()
+    }
+    #[cfg(target_os = "linux")]
+    {
+        // "VmRSS:   123456 kB"
+        let status = std::fs::read_to_string("/proc/self/status").ok()?;
+        let line = status.lines().find(|l| l.starts_with("VmRSS:"))?;
+        let kb: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+        Some(kb * 1024)
+    }
+    #[cfg(not(any(target_os = "macos", target_os = "linux")))]
+    {
+        None
+    }
+}
@@ -0,0 +1,136 @@
+//! Helpers shared by unit tests.
+
+use crate::api::client::TidalClient;
+use crate::api::models::Track;
+use crate::config::{AppConfig, CACHE_DIR_ENV, CONFIG_DIR_ENV};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio::sync::RwLock;
+
+pub use crate::fixtures::{adts_silence, flac, tone, wav, FLAC_BLOCK};
+
+/// Serve an endless HTTP response, one `chunk`-sized block every `interval`.
+/// Returns its URL and a count of the body bytes written so far.
+pub async fn endless_http_body(chunk: usize, interval: Duration) -> (String, Arc<AtomicU64>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/audio", listener.local_addr().unwrap());
+    let sent = Arc::new(AtomicU64::new(0));
+    let counter = Arc::clone(&sent);
+    tokio::spawn(async move {
+        while let Ok((mut socket, _)) = listener.accept().await {
+            let counter = Arc::clone(&counter);
+            tokio::spawn(async move {
+                read_request_head(&mut socket).await;
+                let head = "HTTP/1.1 200 OK\r\ncontent-type: audio/flac\r\n\
+                            transfer-encoding: chunked\r\n\r\n";
+                if socket.write_all(head.as_bytes()).await.is_err() {
+                    return;
+                }
+                let block = format!("{:x}\r\n{}\r\n", chunk, "x".repeat(chunk));
+                while socket.write_all(block.as_bytes()).await.is_ok() {
+                    counter.fetch_add(chunk as u64, Ordering::SeqCst);
+                    tokio::time::sleep(interval).await;
+                }
+            });
+        }
+    });
+    (url, sent)
+}
+
+async fn read_request_head(socket: &mut tokio::net::TcpStream) {
+    let mut head = Vec::new();
+    let mut buf = [0u8; 1024];
+    while !head.windows(4).any(|w| w == b"\r\n\r\n") {
+        match socket.read(&mut buf).await {
+            Ok(0) | Err(_) => return,
+            Ok(n) => head.extend_from_slice(&buf[..n]),
+        }
+    }
+}
+
+/// Poll `done` until it holds, failing the test after `limit`.
+pub async fn wait_until(limit: Duration, mut done: impl FnMut() -> bool) {
+    tokio::time::timeout(limit, async {
+        while !done() {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+    })
+    .await
+    .expect("condition not reached in time");
+}
+
+/// A minimal track; the title repeats the id so tests can tell tracks apart.
+pub fn track(id: &str) -> Track {
+    Track {
+        id: id.to_string(),
+        title: format!("Track {}", id),
+        duration: 180.0,
+        track_number: None,
+        volume_number: None,
+        isrc: None,
+        artist_name: "Artist".to_string(),
+        artist_id: None,
+        album_name: "Album".to_string(),
+        album_id: None,
+        artwork_url: None,
+        media_tags: Vec::new(),
+        explicit: false,
+        release_date: None,
+        is_new: false,
+        streamable: true,
+        language: None,
+    }
+}
+
+/// Tracks with the given ids, in order.
+pub fn tracks(ids: &[&str]) -> Vec<Track> {
+    ids.iter().map(|id| track(id)).collect()
+}
+
+/// Held by a test using the temp config and cache dirs; the next such test
+/// starts once it's dropped. Async tests hold it across awaits on purpose:
+/// what waits on it is another test's thread, never a task of their own.
+pub struct TempDirs {
+    _lock: MutexGuard<'static, ()>,
+}
+
+/// Point the config and cache dirs at a temp dir for the whole test run, so
+/// no test touches the real ones. Tests that read back what was saved hold
+/// the returned guard, which starts them with both dirs empty.
+pub fn temp_dirs() -> TempDirs {
+    static ROOT: OnceLock<tempfile::TempDir> = OnceLock::new();
+    static LOCK: Mutex<()> = Mutex::new(());
+
+    let root = ROOT.get_or_init(|| {
+        let root = tempfile::tempdir().unwrap();
+        std::env::set_var(CONFIG_DIR_ENV, root.path().join("config"));
+        std::env::set_var(CACHE_DIR_ENV, root.path().join("cache"));
+        root
+    });
+    let lock = LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    for dir in ["config", "cache"] {
+        let _ = std::fs::remove_dir_all(root.path().join(dir));
+    }
+    TempDirs { _lock: lock }
+}
+
+/// A client logged in as user `1`, talking to `server` for both the API and
+/// tokens, with no startup refresh to wait for.
+pub fn mock_client(server: &wiremock::MockServer) -> Arc<TidalClient> {
+    let config = AppConfig {
+        client_id: "client".into(),
+        user_id: Some("1".into()),
+        access_token: Some("token".into()),
+        refresh_token: Some("rt".into()),
+        country_code: "US".into(),
+        ..AppConfig::default()
+    };
+    let token_url = format!("{}/oauth2/token", server.uri());
+    let config = Arc::new(RwLock::new(config));
+    let client = TidalClient::with_endpoints(config, &server.uri(), &token_url).unwrap();
+    client.skip_startup_refresh();
+    Arc::new(client)
+}
@@ -0,0 +1,88 @@
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
+/// Key for sorting and matching names regardless of case and accents, so
+/// "Björk" sorts with "bjork" instead of after "Z". Compatibility forms are
+/// folded too ("ﬁ" becomes "fi", full-width letters become ASCII). Marks are
+/// only dropped from Latin letters: in other scripts they tell letters apart
+/// (Cyrillic "й", kana "ガ") or carry vowels (Devanagari), so those names are
+/// just lowercased. Only for comparison; display the original string.
+pub fn sort_key(s: &str) -> String {
+    let mut after_latin = false;
+    let folded: String = s
+        .nfkd()
+        .filter(|&c| {
+            if is_combining_mark(c) {
+                return !after_latin;
+            }
+            after_latin = is_latin(c);
+            true
+        })
+        .collect();
+    // Recompose what was kept, so "й" compares as one letter after "и"
+    folded.nfc().flat_map(char::to_lowercase).collect()
+}
+
+/// Latin letters, which decompose into a base letter and accents.
+fn is_latin(c: char) -> bool {
+    c.is_ascii_alphabetic() || matches!(c, '\u{C0}'..='\u{24F}' | '\u{1E00}'..='\u{1EFF}')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted(names: &[&str]) -> Vec<String> {
+        let mut names: Vec<String> = names.iter().map(|n| n.to_string()).collect();
+        names.sort_by_key(|n| sort_key(n));
+        names
+    }
+
+    #[test]
+    fn latin_accents_case_and_compatibility_forms_fold() {
+        assert_eq!(sort_key("Björk"), "bjork");
+        assert_eq!(sort_key("Ｂｊｏｒｋ"), "bjork");
+        assert_eq!(sort_key("ﬁre"), "fire");
+        assert_eq!(sort_key("Sơn Tùng M-TP"), "son tung m-tp");
+        assert_eq!(sort_key("İstanbul"), "istanbul");
+        // Precomposed and decomposed spellings are the same name
+        assert_eq!(sort_key("Beyonc\u{e9}"), sort_key("Beyonce\u{301}"));
+        assert_eq!(sort_key("Zoë"), "zoe");
+        assert_eq!(
+            sorted(&["Zebra", "Ólafur Arnalds", "abba", "Édith Piaf"]),
+            ["abba", "Édith Piaf", "Ólafur Arnalds", "Zebra"]
+        );
+    }
+
+    #[test]
+    fn cjk_names_are_left_alone() {
+        assert_eq!(sort_key("坂本龍一"), "坂本龍一");
+        assert_eq!(sort_key("방탄소년단"), "방탄소년단");
+        // The voicing mark makes a different syllable
+        assert_eq!(sort_key("ガ"), "ガ");
+        assert_ne!(sort_key("ガ"), sort_key("カ"));
+        // Half-width katakana folds to the full-width letters
+        assert_eq!(sort_key("ｶﾞﾝﾀﾞﾑ"), "ガンダム");
+    }
+
+    #[test]
+    fn cyrillic_keeps_its_letters() {
+        assert_eq!(sort_key("Кино"), "кино");
+        assert_eq!(sort_key("Йога"), "йога");
+        assert_ne!(sort_key("Йога"), sort_key("Иога"));
+        assert_eq!(sort_key("Ёлка"), "ёлка");
+        // й comes after every и word, as in the alphabet
+        assert_eq!(
+            sorted(&["Кино", "Йога", "Иван", "Ия"]),
+            ["Иван", "Ия", "Йога", "Кино"]
+        );
+    }
+
+    #[test]
+    fn combining_vowel_signs_survive() {
+        assert_eq!(sort_key("हिन्दी"), "हिन्दी");
+        assert_eq!(sort_key("தமிழ்"), "தமிழ்");
+        // A stray mark with no letter before it is kept as is
+        assert_eq!(sort_key("\u{301}a"), "\u{301}a");
+    }
+}
@@ -9,15 +9,17 @@ import { useQueueStore } from "@/stores/queueStore";
 import { useLibrary } from "@/hooks/useLibrary";
 import { useAuth } from "@/hooks/useAuth";
 import { useAuthStore } from "@/stores/authStore";
+import { useLibraryStore } from "@/stores/libraryStore";
 import * as tauri from "@/lib/tauri";
 import type { ProgressPayload } from "@/types/events";
 import type { TrackChangedPayload } from "@/types/events";
 import type { StateChangedPayload } from "@/types/events";
+import type { StreamChangedPayload } from "@/types/events";
 
 export function AppLayout() {
   useKeyboardShortcuts();
   const { loadPlaylists, loadFavorites } = useLibrary();
-  const { checkAuth, handleCallback } = useAuth();
+  const { checkAuth } = useAuth();
 
   const setProgress = usePlayerStore((s) => s.setProgress);
   const setState = usePlayerStore((s) => s.setState);
@@ -27,8 +29,15 @@ export function AppLayout() {
   const setRepeatMode = useQueueStore((s) => s.setRepeatMode);
   const setShuffled = useQueueStore((s) => s.setShuffled);
 
+  // Newest playback session seen; another window's play can make events stale
+  const sessionEpoch = useRef(0);
+  // Newest player stream seen; progress read just before a skip is stale
+  const playbackEpoch = useRef(0);
+
   const handleProgress = useCallback(
     (payload: ProgressPayload) => {
+      if (isStaleEpoch(sessionEpoch, payload.session_epoch)) return;
+      if (isStaleEpoch(playbackEpoch, payload.playback_epoch)) return;
       setProgress(payload.position, payload.duration);
     },
     [setProgress],
@@ -36,6 +45,8 @@ export function AppLayout() {
 
   const handleTrackChanged = useCallback(
     (payload: TrackChangedPayload) => {
+      if (isStaleEpoch(sessionEpoch, payload.session_epoch)) return;
+      playbackEpoch.current = Math.max(playbackEpoch.current, payload.playback_epoch);
       setCurrentTrack({
         id: payload.track_id,
         title: payload.title,
@@ -50,8 +61,18 @@ export function AppLayout() {
     [setCurrentTrack, setCodecInfo],
   );
 
+  const handleStreamChanged = useCallback(
+    (payload: StreamChangedPayload) => {
+      if (isStaleEpoch(playbackEpoch, payload.playback_epoch)) return;
+      playbackEpoch.current = payload.playback_epoch;
+      setCodecInfo(payload.codec ?? null, payload.quality ?? null);
+    },
+    [setCodecInfo],
+  );
+
   const handleStateChanged = useCallback(
     (payload: StateChangedPayload) => {
+      if (isStaleEpoch(playbackEpoch, payload.playback_epoch)) return;
       setState(payload.state);
     },
     [setState],
@@ -73,6 +94,7 @@ export function AppLayout() {
     const unlisteners = Promise.all([
       tauri.onProgress(handleProgress),
       tauri.onTrackChanged(handleTrackChanged),
+      tauri.onStreamChanged(handleStreamChanged),
       tauri.onStateChanged(handleStateChanged),
       tauri.onQueueChanged(syncQueue),
       tauri.onTrackEnded(syncQueue),
@@ -81,7 +103,13 @@ export function AppLayout() {
     return () => {
       unlisteners.then((fns) => fns.forEach((fn) => fn()));
     };
-  }, [handleProgress, handleTrackChanged, handleStateChanged, syncQueue]);
+  }, [
+    handleProgress,
+    handleTrackChanged,
+    handleStreamChanged,
+    handleStateChanged,
+    syncQueue,
+  ]);
 
   const authenticated = useAuthStore((s) => s.authenticated);
 
@@ -98,6 +126,21 @@ export function AppLayout() {
     }
   }, [authenticated, loadPlaylists, loadFavorites]);
 
+  // Keep every heart in sync when a favorite is toggled from any view
+  useEffect(() => {
+    const unlisten = tauri.onFavoritesChanged(({ trackId, favorite }) => {
+      const library = useLibraryStore.getState();
+      if (favorite) {
+        library.addFavorite(trackId);
+      } else {
+        library.removeFavorite(trackId);
+      }
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
   // Restore persisted player preferences and queue on mount
   useEffect(() => {
     tauri.getPlayerPrefs().then((prefs) => {
@@ -122,26 +165,27 @@ export function AppLayout() {
   // eslint-disable-next-line react-hooks/exhaustive-deps
   }, []);
 
-  // Persist volume/muted changes with debounce
-  const savePrefsTimer = useRef<ReturnType<typeof setTimeout>>();
+  // Any key pressed while the saved queue is about to resume on launch cancels it
   useEffect(() => {
-    const unsub = usePlayerStore.subscribe(
-      (state, prev) => {
-        if (state.volume !== prev.volume || state.muted !== prev.muted) {
-          clearTimeout(savePrefsTimer.current);
-          savePrefsTimer.current = setTimeout(() => {
-            const { volume, muted, previousVolume } = usePlayerStore.getState();
-            const realVolume = muted ? previousVolume : volume;
-            tauri.savePlayerPrefs(realVolume, muted).catch((err) =>
-              console.error("Failed to save player prefs:", err),
-            );
-          }, 500);
-        }
-      },
-    );
+    let stopWatching: (() => void) | undefined;
+    const unlisten = tauri.onAutoresumePending(({ graceMs }) => {
+      const cancel = () => {
+        stopWatching?.();
+        tauri.cancelAutoresume().catch((err) =>
+          console.error("Failed to cancel autoresume:", err),
+        );
+      };
+      window.addEventListener("keydown", cancel);
+      const timer = setTimeout(() => stopWatching?.(), graceMs);
+      stopWatching = () => {
+        window.removeEventListener("keydown", cancel);
+        clearTimeout(timer);
+        stopWatching = undefined;
+      };
+    });
     return () => {
-      unsub();
-      clearTimeout(savePrefsTimer.current);
+      stopWatching?.();
+      unlisten.then((fn) => fn());
     };
   }, []);
 
@@ -167,31 +211,30 @@ export function AppLayout() {
     };
   }, []);
 
-  // Listen for deep link auth callbacks
+  // Auth callback deep links are completed by the backend, which reports back
+  // here; other links received before mount are collected from the backend
   useEffect(() => {
     let cancelled = false;
+    const openLinks = (urls: string[]) => {
+      for (const url of urls) {
+        console.info("[AppLayout] Unhandled deep link:", url);
+      }
+    };
+    const unsubAuth = tauri.onAuthStateChanged(() => checkAuth());
+    // Not gated on `cancelled`: the buffer is emptied by the first call
+    tauri
+      .getPendingDeepLink()
+      .then(openLinks)
+      .catch((err) => console.error("Failed to get pending deep links:", err));
     import("@tauri-apps/plugin-deep-link").then(({ onOpenUrl }) => {
       if (cancelled) return;
-      onOpenUrl((urls) => {
-        for (const url of urls) {
-          try {
-            const parsed = new URL(url);
-            if (parsed.pathname === "/auth/callback" || parsed.host === "auth") {
-              const code = parsed.searchParams.get("code");
-              if (code) {
-                handleCallback(code);
-              }
-            }
-          } catch {
-            // Not a valid URL, ignore
-          }
-        }
-      });
+      onOpenUrl((urls) => openLinks(urls.filter((url) => !isAuthCallback(url))));
     });
     return () => {
       cancelled = true;
+      unsubAuth.then((fn) => fn());
     };
-  }, [handleCallback]);
+  }, [checkAuth]);
 
   return (
     <div className="flex h-dvh flex-col">
@@ -206,3 +249,19 @@ export function AppLayout() {
     </div>
   );
 }
+
+function isAuthCallback(url: string): boolean {
+  try {
+    const parsed = new URL(url);
+    return parsed.pathname === "/auth/callback" || parsed.host === "auth";
+  } catch {
+    return false;
+  }
+}
+
+// An event from an older playback session is stale; otherwise record its epoch
+function isStaleEpoch(latest: { current: number }, epoch: number): boolean {
+  if (epoch < latest.current) return true;
+  latest.current = epoch;
+  return false;
+}
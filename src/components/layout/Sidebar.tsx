@@ -33,7 +33,7 @@ export function Sidebar() {
   const playlists = useLibraryStore((s) => s.playlists);
   const { authenticated, userId, displayName, checking } = useAuthStore();
   const { startLogin, cancelLogin, handleLogout } = useAuth();
-  const { loginPending, userCode, verificationUri, loginError } = useAuthStore();
+  const { loginPending, userCode, verificationUri, loginError, reauthRequired } = useAuthStore();
 
   return (
     <div className="flex h-full w-56 flex-col border-r border-border bg-card">
@@ -99,8 +99,15 @@ export function Sidebar() {
                 <LogIn className="size-4" />
                 Login to Tidal
               </Button>
-              {loginError && (
+              {loginError ? (
                 <p className="px-1 text-xs/4 text-destructive">{loginError}</p>
+              ) : (
+                reauthRequired && (
+                  <p className="px-1 text-xs/4 text-muted-foreground">
+                    Your session{displayName ? ` as ${displayName}` : ""} has ended. Log in again to keep
+                    listening.
+                  </p>
+                )
               )}
             </div>
           )}
@@ -2,6 +2,8 @@ import { useEffect, useState } from "react";
 import { invoke } from "@tauri-apps/api/core";
 
 const cache = new Map<string, string>();
+// Images the CDN reported as gone; shown as a placeholder without refetching
+const missing = new Set<string>();
 
 export function ProxiedImage({
   src,
@@ -18,7 +20,8 @@ export function ProxiedImage({
   const [failed, setFailed] = useState(false);
 
   useEffect(() => {
-    setFailed(false);
+    setFailed(missing.has(src));
+    if (missing.has(src)) return;
 
     const cached = cache.get(src);
     if (cached) {
@@ -37,7 +40,11 @@ export function ProxiedImage({
         }
       })
       .catch((err) => {
-        console.error("[ProxiedImage] proxy_image failed for", src, err);
+        if (err?.kind === "not_found") {
+          missing.add(src);
+        } else {
+          console.error("[ProxiedImage] proxy_image failed for", src, err);
+        }
         if (!cancelled) setFailed(true);
       });
 
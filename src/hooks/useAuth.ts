@@ -7,6 +7,7 @@ export function useAuth() {
   const setChecking = useAuthStore((s) => s.setChecking);
   const setLoginPending = useAuthStore((s) => s.setLoginPending);
   const setLoginError = useAuthStore((s) => s.setLoginError);
+  const setReauthRequired = useAuthStore((s) => s.setReauthRequired);
   const pollingRef = useRef(false);
 
   const checkAuth = useCallback(async () => {
@@ -19,11 +20,12 @@ export function useAuth() {
         status.displayName ?? null,
         status.countryCode,
       );
+      setReauthRequired(status.reauthRequired);
     } catch (err) {
       console.error("Auth check failed:", err);
       setAuth(false, null, null, "US");
     }
-  }, [setAuth, setChecking]);
+  }, [setAuth, setChecking, setReauthRequired]);
 
   const startLogin = useCallback(async () => {
     if (pollingRef.current) return;
@@ -7,6 +7,7 @@ export function useLibrary() {
     setPlaylists,
     setFavorites,
     appendFavorites,
+    setFavoritesTotal,
     addFavorite,
     removeFavorite,
     setLoading,
@@ -29,10 +30,17 @@ export function useLibrary() {
     try {
       const page = await tauri.getFavorites();
       setFavorites(page.tracks, page.nextCursor ?? null, page.hasMore);
+      setFavoritesTotal(page.total ?? null);
+      if (page.total == null && page.hasMore) {
+        tauri
+          .getFavoritesCount()
+          .then(setFavoritesTotal)
+          .catch((err) => console.error("Failed to count favorites:", err));
+      }
     } catch (err) {
       console.error("Failed to load favorites:", err);
     }
-  }, [setFavorites]);
+  }, [setFavorites, setFavoritesTotal]);
 
   const loadMoreFavorites = useCallback(async () => {
     const cursor = useLibraryStore.getState().favoritesNextCursor;
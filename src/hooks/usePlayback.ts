@@ -17,7 +17,7 @@ export function usePlayback() {
   const playTracks = useCallback(
     async (tracks: Track[], startIndex: number) => {
       try {
-        await tauri.playTracks(tracks, startIndex);
+        await tauri.playTrackIds(tracks.map((t) => t.id), startIndex);
       } catch (err) {
         console.error("Play tracks failed:", err);
       }
@@ -78,16 +78,11 @@ export function usePlayback() {
   }, []);
 
   const toggleMute = useCallback(async () => {
-    const { muted, previousVolume } = usePlayerStore.getState();
+    const { muted } = usePlayerStore.getState();
     usePlayerStore.getState().toggleMute();
     try {
-      if (muted) {
-        // Unmuting: restore previous volume
-        await tauri.setVolume(previousVolume);
-      } else {
-        // Muting: set volume to 0
-        await tauri.setVolume(0);
-      }
+      // The backend keeps the unmuted volume and restores it on unmute
+      await tauri.setMuted(!muted);
     } catch (err) {
       console.error("Toggle mute failed:", err);
     }
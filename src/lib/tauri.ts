@@ -1,59 +1,212 @@
 import { invoke } from "@tauri-apps/api/core";
 import { listen, type UnlistenFn } from "@tauri-apps/api/event";
-import type { AuthStatus, DeviceAuthResponse } from "@/types/api";
-import type { Album, Artist, FavoritesPage, Playlist, RecommendationSection, Track } from "@/types/track";
-import type { QueueState, RepeatMode } from "@/types/player";
+import type {
+  AuthStatus,
+  DeviceAuthResponse,
+  NetworkStatus,
+  RegionBlock,
+} from "@/types/api";
+import type {
+  Album,
+  AlbumListing,
+  AlbumPage,
+  Artist,
+  ArtistPage,
+  Cached,
+  FavoritesPage,
+  HomeSectionKind,
+  NewRelease,
+  Playlist,
+  PlaylistListing,
+  PlaylistPrefs,
+  PlaylistScope,
+  PlaylistSort,
+  PublicProfile,
+  RecommendationSection,
+  Track,
+  TrackDetails,
+  Video,
+} from "@/types/track";
+import type {
+  ArtworkPalette,
+  InterruptionMode,
+  InterruptionSettings,
+  LaunchSettings,
+  OutputInfo,
+  PreloadStatus,
+  QueueState,
+  RemoteControlStatus,
+  RepeatMode,
+  ResetScope,
+  ResourceUsage,
+  SeekPreview,
+  TrackStats,
+  TrackStatsEntry,
+  Waveform,
+} from "@/types/player";
 import type { SearchResults } from "@/types/search";
 import type {
+  AuthStatePayload,
+  AutoresumePendingPayload,
+  FavoritesChangedPayload,
+  FormatCorrectedPayload,
   ProgressPayload,
   TrackChangedPayload,
+  TrackChangeReason,
   StateChangedPayload,
+  StreamChangedPayload,
+  StreamQuality,
+  PlaybackErrorPayload,
+  ModeChangedPayload,
+  MetadataUpdatedPayload,
+  NewReleasesFoundPayload,
+  OutputDeviceChangedPayload,
+  PreloadReadyPayload,
+  QualityAdaptedPayload,
+  RegionBlockChangedPayload,
+  SettingsChangedPayload,
+  TrackEndedPayload,
+  UpNextPayload,
+  VolumeChangedPayload,
 } from "@/types/events";
 
 // Auth commands
 export const checkAuthStatus = () => invoke<AuthStatus>("check_auth_status");
+export const getNetworkStatus = () => invoke<NetworkStatus>("get_network_status");
+export const getRegionBlock = () =>
+  invoke<RegionBlock | null>("get_region_block");
+export const clearRegionBlock = () => invoke<void>("clear_region_block");
 export const login = () => invoke<DeviceAuthResponse>("login");
 export const pollLogin = () => invoke<AuthStatus>("poll_login");
 export const handleAuthCallback = (code: string) =>
   invoke<AuthStatus>("handle_auth_callback", { code });
 export const initClientCredentials = () =>
   invoke<void>("init_client_credentials");
+export const getPendingDeepLink = () => invoke<string[]>("get_pending_deep_link");
 export const logout = () => invoke<void>("logout");
 
 // Playback commands
-export const playTrack = (trackId: string) =>
-  invoke<void>("play_track", { trackId });
+export const playTrack = (trackId: string, reason?: TrackChangeReason) =>
+  invoke<void>("play_track", { trackId, reason });
+export const playTrackNow = (trackId: string, resumeQueueAfter: boolean) =>
+  invoke<void>("play_track_now", { trackId, resumeQueueAfter });
+/** @deprecated Send ids with playTrackIds instead. */
 export const playTracks = (tracks: Track[], startIndex: number) =>
   invoke<void>("play_tracks", { tracks, startIndex });
+export const playTrackIds = (trackIds: string[], startIndex: number) =>
+  invoke<void>("play_track_ids", { trackIds, startIndex });
 export const pausePlayback = () => invoke<void>("pause");
 export const resumePlayback = () => invoke<void>("resume");
 export const stopPlayback = () => invoke<void>("stop");
 export const seekTo = (position: number) =>
   invoke<void>("seek", { position });
+export const seekFraction = (fraction: number) =>
+  invoke<void>("seek_fraction", { fraction });
 export const setVolume = (volume: number) =>
   invoke<void>("set_volume", { volume });
+export const setMuted = (muted: boolean) =>
+  invoke<void>("set_muted", { muted });
 export const getVolume = () => invoke<number>("get_volume");
+export const setSubstituteUnavailable = (enabled: boolean) =>
+  invoke<void>("set_substitute_unavailable", { enabled });
+export const setHideExplicit = (enabled: boolean) =>
+  invoke<void>("set_hide_explicit", { enabled });
+export const getHideExplicit = () => invoke<boolean>("get_hide_explicit");
+export const setLanguageAllowlist = (languages: string[]) =>
+  invoke<string[]>("set_language_allowlist", { languages });
+export const getLanguageAllowlist = () =>
+  invoke<string[]>("get_language_allowlist");
+export const setReleaseOutputAfterMinutes = (minutes: number) =>
+  invoke<void>("set_release_output_after_minutes", { minutes });
+export const setUpnextNoticeSecs = (secs: number) =>
+  invoke<void>("set_upnext_notice_secs", { secs });
+export const setProgressIntervalMs = (ms: number) =>
+  invoke<void>("set_progress_interval_ms", { ms });
+export const setFollowDefaultOutput = (enabled: boolean) =>
+  invoke<void>("set_follow_default_output", { enabled });
+export const setAllowPreviewPlayback = (enabled: boolean) =>
+  invoke<void>("set_allow_preview_playback", { enabled });
+export const setAudioQuality = (quality: string) =>
+  invoke<void>("set_audio_quality", { quality });
+export const setAdaptiveQuality = (enabled: boolean) =>
+  invoke<void>("set_adaptive_quality", { enabled });
+export const getAdaptiveQuality = () => invoke<boolean>("get_adaptive_quality");
+export const getOutputDevice = () => invoke<string | null>("get_output_device");
+export const getOutputInfo = () => invoke<OutputInfo | null>("get_output_info");
+export const getResourceUsage = () => invoke<ResourceUsage>("get_resource_usage");
+/** Stops playback, then wipes the given app data; for support and debugging */
+export const resetAppData = (scope: ResetScope) =>
+  invoke<void>("reset_app_data", { scope });
+export const getRemoteControl = () =>
+  invoke<RemoteControlStatus>("get_remote_control");
+export const setRemoteControl = (
+  enabled: boolean,
+  port: number,
+  allowLan: boolean
+) =>
+  invoke<RemoteControlStatus>("set_remote_control", { enabled, port, allowLan });
+export const regenerateRemoteControlToken = () =>
+  invoke<RemoteControlStatus>("regenerate_remote_control_token");
+export const setInterruptionSettings = (
+  mode: InterruptionMode,
+  duckDb: number,
+  resumeAfter: boolean,
+) =>
+  invoke<void>("set_interruption_settings", { mode, duckDb, resumeAfter });
+export const getInterruptionSettings = () =>
+  invoke<InterruptionSettings>("get_interruption_settings");
+export const getLaunchSettings = () =>
+  invoke<LaunchSettings>("get_launch_settings");
+export const setStartMinimized = (enabled: boolean) =>
+  invoke<void>("set_start_minimized", { enabled });
+export const setAutoresumeOnLaunch = (enabled: boolean) =>
+  invoke<void>("set_autoresume_on_launch", { enabled });
+export const cancelAutoresume = () => invoke<void>("cancel_autoresume");
+export const playLocalFile = (path: string) =>
+  invoke<void>("play_local_file", { path });
+export const findTrackAlternative = (trackId: string) =>
+  invoke<Track | null>("find_track_alternative", { trackId });
+export const getPreloadStatus = () =>
+  invoke<PreloadStatus | null>("get_preload_status");
+export const getStreamInfo = () =>
+  invoke<StreamQuality | null>("get_stream_info");
+export const getWaveform = (trackId: string) =>
+  invoke<Waveform>("get_waveform", { trackId });
+export const getSeekPreview = (fraction: number) =>
+  invoke<SeekPreview>("get_seek_preview", { fraction });
 export const getPlaybackState = () => invoke<string>("get_playback_state");
 export const nextTrack = () => invoke<void>("next_track");
+export const skipToQueueOffset = (offset: number) =>
+  invoke<void>("skip_to_queue_offset", { offset });
 export const previousTrack = () => invoke<void>("previous_track");
 
 // Player prefs
 export const getPlayerPrefs = () =>
   invoke<{ volume: number; muted: boolean }>("get_player_prefs");
-export const savePlayerPrefs = (volume: number, muted: boolean) =>
-  invoke<void>("save_player_prefs", { volume, muted });
 
 // Queue commands
 export const getQueue = () => invoke<QueueState>("get_queue");
 export const addToQueue = (trackId: string) =>
   invoke<void>("add_to_queue", { trackId });
+export const addTrackIdsToQueue = (trackIds: string[]) =>
+  invoke<number>("add_track_ids_to_queue", { trackIds });
+export const appendAlbumToQueue = (albumId: string) =>
+  invoke<number>("append_album_to_queue", { albumId });
+export const appendPlaylistToQueue = (playlistId: string) =>
+  invoke<number>("append_playlist_to_queue", { playlistId });
+export const insertAlbumNext = (albumId: string) =>
+  invoke<number>("insert_album_next", { albumId });
 export const removeFromQueue = (index: number) =>
   invoke<void>("remove_from_queue", { index });
 export const reorderQueue = (from: number, to: number) =>
   invoke<void>("reorder_queue", { from, to });
 export const shuffleQueue = () => invoke<void>("shuffle_queue");
+export const reshuffle = (seed?: number) =>
+  invoke<number>("reshuffle", { seed });
 export const unshuffleQueue = () => invoke<void>("unshuffle_queue");
 export const toggleRepeat = () => invoke<RepeatMode>("toggle_repeat");
+export const setRepeatMode = (mode: RepeatMode) =>
+  invoke<void>("set_repeat_mode", { mode });
 export const clearQueue = () => invoke<void>("clear_queue");
 export const playQueueTrack = (index: number) =>
   invoke<void>("play_queue_track", { index });
@@ -67,11 +220,14 @@ export const searchSuggestions = (query: string) =>
   invoke<string[]>("search_suggestions", { query });
 
 // Playlist commands
-export const getPlaylists = () => invoke<Playlist[]>("get_playlists");
+export const getPlaylists = (scope?: PlaylistScope) =>
+  invoke<Playlist[]>("get_playlists", { scope });
 export const getPlaylist = (playlistId: string) =>
   invoke<Playlist>("get_playlist", { playlistId });
-export const getPlaylistTracks = (playlistId: string) =>
-  invoke<Track[]>("get_playlist_tracks", { playlistId });
+export const getPlaylistTracks = (playlistId: string, sorted?: boolean) =>
+  invoke<Track[]>("get_playlist_tracks", { playlistId, sorted });
+export const getPlaylistListing = (playlistId: string) =>
+  invoke<Cached<PlaylistListing>>("get_playlist_listing", { playlistId });
 export const createPlaylist = (name: string, description?: string) =>
   invoke<Playlist>("create_playlist", { name, description });
 export const addToPlaylist = (playlistId: string, trackId: string) =>
@@ -80,26 +236,77 @@ export const removeFromPlaylist = (playlistId: string, trackId: string) =>
   invoke<void>("remove_from_playlist", { playlistId, trackId });
 export const deletePlaylist = (playlistId: string) =>
   invoke<void>("delete_playlist", { playlistId });
+export const setPlaylistPrefs = (
+  playlistId: string,
+  pinned: boolean,
+  sort: PlaylistSort
+) => invoke<void>("set_playlist_prefs", { playlistId, pinned, sort });
+export const getPlaylistPrefs = () =>
+  invoke<Record<string, PlaylistPrefs>>("get_playlist_prefs");
+export const setPlaylistCollaborative = (playlistId: string, enabled: boolean) =>
+  invoke<void>("set_playlist_collaborative", { playlistId, enabled });
+export const getPlaylistInviteLink = (playlistId: string) =>
+  invoke<string>("get_playlist_invite_link", { playlistId });
 
 // Favorites commands
+export const getFavoritesCount = () => invoke<number>("get_favorites_count");
 export const getFavorites = (cursor?: string) =>
   invoke<FavoritesPage>("get_favorites", { cursor });
+export const isFavorite = (trackId: string) =>
+  invoke<boolean>("is_favorite", { trackId });
 export const toggleFavorite = (trackId: string, add: boolean) =>
   invoke<void>("toggle_favorite", { trackId, add });
+export const playFavorites = (startTrackId?: string) =>
+  invoke<number>("play_favorites", { startTrackId });
+export const checkNewReleases = () =>
+  invoke<NewRelease[]>("check_new_releases");
+export const getReleaseRadar = () => invoke<NewRelease[]>("get_release_radar");
 
 // Browse commands
 export const getAlbum = (albumId: string) =>
   invoke<Album>("get_album", { albumId });
 export const getAlbumTracks = (albumId: string) =>
   invoke<Track[]>("get_album_tracks", { albumId });
+export const getAlbumListing = (albumId: string) =>
+  invoke<Cached<AlbumListing>>("get_album_listing", { albumId });
+export const getAlbumPage = (albumId: string) =>
+  invoke<AlbumPage>("get_album_page", { albumId });
+export const clearMetadataCache = () => invoke<void>("clear_metadata_cache");
 export const getArtist = (artistId: string) =>
   invoke<Artist>("get_artist", { artistId });
 export const getArtistAlbums = (artistId: string) =>
   invoke<Album[]>("get_artist_albums", { artistId });
+export const getArtistAppearsOn = (artistId: string) =>
+  invoke<Album[]>("get_artist_appears_on", { artistId });
+export const getArtistPage = (artistId: string) =>
+  invoke<ArtistPage>("get_artist_page", { artistId });
 export const getRecommendations = () =>
   invoke<RecommendationSection[]>("get_recommendations");
+export const getHomeSectionsConfig = () =>
+  invoke<HomeSectionKind[]>("get_home_sections_config");
+export const setHomeSectionsConfig = (sections: HomeSectionKind[]) =>
+  invoke<void>("set_home_sections_config", { sections });
+export const refreshDiscovery = () => invoke<void>("refresh_discovery");
 export const getSimilarTracks = (trackId: string) =>
   invoke<Track[]>("get_similar_tracks", { trackId });
+export const getTrackDetails = (trackId: string) =>
+  invoke<TrackDetails>("get_track_details", { trackId });
+export const getVideo = (videoId: string) =>
+  invoke<Video>("get_video", { videoId });
+export const getUserPublicProfile = (userId: string) =>
+  invoke<PublicProfile>("get_user_public_profile", { userId });
+
+// Images
+export const prefetchArtwork = (urls: string[], priority: number) =>
+  invoke<number>("prefetch_artwork", { urls, priority });
+export const getArtworkPalette = (url: string) =>
+  invoke<ArtworkPalette>("get_artwork_palette", { url });
+
+// Listening stats
+export const getTrackStats = (trackId: string) =>
+  invoke<TrackStats>("get_track_stats", { trackId });
+export const getMostPlayed = (limit: number) =>
+  invoke<TrackStatsEntry[]>("get_most_played", { limit });
 
 // Event listeners
 export const onProgress = (
@@ -114,6 +321,20 @@ export const onTrackChanged = (
     handler(e.payload)
   );
 
+export const onStreamChanged = (
+  handler: (payload: StreamChangedPayload) => void
+): Promise<UnlistenFn> =>
+  listen<StreamChangedPayload>("playback:stream-changed", (e) =>
+    handler(e.payload)
+  );
+
+export const onFormatCorrected = (
+  handler: (payload: FormatCorrectedPayload) => void
+): Promise<UnlistenFn> =>
+  listen<FormatCorrectedPayload>("playback:format-corrected", (e) =>
+    handler(e.payload)
+  );
+
 export const onStateChanged = (
   handler: (payload: StateChangedPayload) => void
 ): Promise<UnlistenFn> =>
@@ -121,8 +342,99 @@ export const onStateChanged = (
     handler(e.payload)
   );
 
-export const onTrackEnded = (handler: () => void): Promise<UnlistenFn> =>
-  listen("playback:track-ended", () => handler());
+export const onVolumeChanged = (
+  handler: (payload: VolumeChangedPayload) => void
+): Promise<UnlistenFn> =>
+  listen<VolumeChangedPayload>("playback:volume-changed", (e) =>
+    handler(e.payload)
+  );
+
+export const onPlaybackError = (
+  handler: (payload: PlaybackErrorPayload) => void
+): Promise<UnlistenFn> =>
+  listen<PlaybackErrorPayload>("playback:error", (e) => handler(e.payload));
+
+export const onMetadataUpdated = (
+  handler: (payload: MetadataUpdatedPayload) => void
+): Promise<UnlistenFn> =>
+  listen<MetadataUpdatedPayload>("metadata:updated", (e) => handler(e.payload));
+
+export const onAuthStateChanged = (
+  handler: (payload: AuthStatePayload) => void
+): Promise<UnlistenFn> =>
+  listen<AuthStatePayload>("auth:state-changed", (e) => handler(e.payload));
+
+export const onNetworkStatusChanged = (
+  handler: (payload: NetworkStatus) => void
+): Promise<UnlistenFn> =>
+  listen<NetworkStatus>("network:status-changed", (e) => handler(e.payload));
+
+export const onRegionBlockChanged = (
+  handler: (payload: RegionBlockChangedPayload) => void
+): Promise<UnlistenFn> =>
+  listen<RegionBlockChangedPayload>("network:region-block-changed", (e) =>
+    handler(e.payload)
+  );
+
+export const onOutputDeviceChanged = (
+  handler: (payload: OutputDeviceChangedPayload) => void
+): Promise<UnlistenFn> =>
+  listen<OutputDeviceChangedPayload>("playback:output-device-changed", (e) =>
+    handler(e.payload)
+  );
+
+export const onSettingsChanged = (
+  handler: (payload: SettingsChangedPayload) => void
+): Promise<UnlistenFn> =>
+  listen<SettingsChangedPayload>("settings:changed", (e) => handler(e.payload));
+
+export const onQualityAdapted = (
+  handler: (payload: QualityAdaptedPayload) => void
+): Promise<UnlistenFn> =>
+  listen<QualityAdaptedPayload>("playback:quality-adapted", (e) =>
+    handler(e.payload)
+  );
+
+export const onPreloadReady = (
+  handler: (payload: PreloadReadyPayload) => void
+): Promise<UnlistenFn> =>
+  listen<PreloadReadyPayload>("playback:preload-ready", (e) => handler(e.payload));
+
+export const onUpNext = (
+  handler: (payload: UpNextPayload) => void
+): Promise<UnlistenFn> =>
+  listen<UpNextPayload>("playback:upnext", (e) => handler(e.payload));
+
+export const onFavoritesChanged = (
+  handler: (payload: FavoritesChangedPayload) => void
+): Promise<UnlistenFn> =>
+  listen<FavoritesChangedPayload>("favorites:changed", (e) =>
+    handler(e.payload)
+  );
+
+export const onNewReleasesFound = (
+  handler: (payload: NewReleasesFoundPayload) => void
+): Promise<UnlistenFn> =>
+  listen<NewReleasesFoundPayload>("release-radar:new-releases", (e) =>
+    handler(e.payload)
+  );
+
+export const onAutoresumePending = (
+  handler: (payload: AutoresumePendingPayload) => void
+): Promise<UnlistenFn> =>
+  listen<AutoresumePendingPayload>("playback:autoresume-pending", (e) =>
+    handler(e.payload)
+  );
+
+export const onModeChanged = (
+  handler: (payload: ModeChangedPayload) => void
+): Promise<UnlistenFn> =>
+  listen<ModeChangedPayload>("playback:mode-changed", (e) => handler(e.payload));
+
+export const onTrackEnded = (
+  handler: (payload: TrackEndedPayload) => void
+): Promise<UnlistenFn> =>
+  listen<TrackEndedPayload>("playback:track-ended", (e) => handler(e.payload));
 
 export const onQueueChanged = (handler: () => void): Promise<UnlistenFn> =>
   listen("playback:queue-changed", () => handler());
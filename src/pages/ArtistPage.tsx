@@ -13,6 +13,7 @@ export function ArtistPage() {
   const { id } = useParams<{ id: string }>();
   const [artist, setArtist] = useState<Artist | null>(null);
   const [albums, setAlbums] = useState<Album[]>([]);
+  const [appearsOn, setAppearsOn] = useState<Album[]>([]);
   const [allTracks, setAllTracks] = useState<Track[]>([]);
   const [loading, setLoading] = useState(true);
   const { playTracks } = usePlayback();
@@ -20,6 +21,8 @@ export function ArtistPage() {
   useEffect(() => {
     if (!id) return;
     setLoading(true);
+    setAppearsOn([]);
+    tauri.getArtistAppearsOn(id).then(setAppearsOn).catch(console.error);
     Promise.all([tauri.getArtist(id), tauri.getArtistAlbums(id)])
       .then(async ([artistData, albumData]) => {
         setArtist(artistData);
@@ -108,6 +111,17 @@ export function ArtistPage() {
           ))}
         </div>
       </div>
+
+      {appearsOn.length > 0 && (
+        <div>
+          <h2 className="mb-4 text-xl/7 font-semibold">Appears On</h2>
+          <div className="grid grid-cols-[repeat(auto-fill,minmax(160px,1fr))] gap-2">
+            {appearsOn.map((album) => (
+              <AlbumCard key={album.id} album={album} />
+            ))}
+          </div>
+        </div>
+      )}
     </div>
   );
 }
@@ -13,6 +13,7 @@ export function FavoritesPage() {
   const loading = useLibraryStore((s) => s.loading);
   const loadingMore = useLibraryStore((s) => s.loadingMore);
   const favoritesHasMore = useLibraryStore((s) => s.favoritesHasMore);
+  const favoritesTotal = useLibraryStore((s) => s.favoritesTotal);
   const { loadFavorites, loadMoreFavorites } = useLibrary();
   const { playTracks } = usePlayback();
 
@@ -54,7 +55,9 @@ export function FavoritesPage() {
         <h1 className="text-3xl/9 font-bold">Favorites</h1>
         {favorites.length > 0 && (
           <span className="text-sm/5 text-muted-foreground">
-            {favorites.length} tracks{favoritesHasMore ? "+" : ""}
+            {favoritesTotal != null
+              ? `${favoritesTotal.toLocaleString()} tracks`
+              : `${favorites.length} tracks${favoritesHasMore ? "+" : ""}`}
           </span>
         )}
       </div>
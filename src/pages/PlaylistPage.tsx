@@ -59,6 +59,11 @@ export function PlaylistPage() {
             Playlist
           </span>
           <h1 className="text-3xl/9 font-bold">{playlist.name}</h1>
+          {playlist.ownerName && !playlist.owned && (
+            <p className="text-sm/5 text-muted-foreground">
+              by {playlist.ownerName}
+            </p>
+          )}
           {playlist.description && (
             <p className="text-sm/5 text-muted-foreground">
               {playlist.description}
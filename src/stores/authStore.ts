@@ -10,10 +10,13 @@ interface AuthState {
   userCode: string | null;
   verificationUri: string | null;
   loginError: string | null;
+  /** The saved login was rejected; shown next to the login button */
+  reauthRequired: boolean;
   setAuth: (authenticated: boolean, userId: string | null, displayName: string | null, countryCode: string) => void;
   setChecking: (checking: boolean) => void;
   setLoginPending: (pending: boolean, userCode?: string | null, verificationUri?: string | null) => void;
   setLoginError: (error: string | null) => void;
+  setReauthRequired: (reauthRequired: boolean) => void;
 }
 
 export const useAuthStore = create<AuthState>((set) => ({
@@ -26,11 +29,13 @@ export const useAuthStore = create<AuthState>((set) => ({
   userCode: null,
   verificationUri: null,
   loginError: null,
+  reauthRequired: false,
   setAuth: (authenticated, userId, displayName, countryCode) =>
-    set({ authenticated, userId, displayName, countryCode, checking: false, loginPending: false, userCode: null, verificationUri: null, loginError: null }),
+    set({ authenticated, userId, displayName, countryCode, checking: false, loginPending: false, userCode: null, verificationUri: null, loginError: null, reauthRequired: false }),
   setChecking: (checking) => set({ checking }),
   setLoginPending: (pending, userCode = null, verificationUri = null) =>
     set({ loginPending: pending, userCode, verificationUri, loginError: null }),
   setLoginError: (error) =>
     set({ loginError: error, loginPending: false, userCode: null, verificationUri: null }),
+  setReauthRequired: (reauthRequired) => set({ reauthRequired }),
 }));
@@ -7,12 +7,15 @@ interface LibraryStoreState {
   favoriteTrackIds: Set<string>;
   favoritesNextCursor: string | null;
   favoritesHasMore: boolean;
+  /** Size of the whole favorites collection, when known */
+  favoritesTotal: number | null;
   loading: boolean;
   loadingMore: boolean;
 
   setPlaylists: (playlists: Playlist[]) => void;
   setFavorites: (favorites: Track[], nextCursor: string | null, hasMore: boolean) => void;
   appendFavorites: (tracks: Track[], nextCursor: string | null, hasMore: boolean) => void;
+  setFavoritesTotal: (total: number | null) => void;
   addFavorite: (trackId: string) => void;
   removeFavorite: (trackId: string) => void;
   isFavorite: (trackId: string) => boolean;
@@ -26,6 +29,7 @@ export const useLibraryStore = create<LibraryStoreState>((set, get) => ({
   favoriteTrackIds: new Set(),
   favoritesNextCursor: null,
   favoritesHasMore: false,
+  favoritesTotal: null,
   loading: false,
   loadingMore: false,
 
@@ -47,6 +51,7 @@ export const useLibraryStore = create<LibraryStoreState>((set, get) => ({
         favoritesHasMore: hasMore,
       };
     }),
+  setFavoritesTotal: (favoritesTotal) => set({ favoritesTotal }),
   addFavorite: (trackId) =>
     set((state) => ({
       favoriteTrackIds: new Set([...state.favoriteTrackIds, trackId]),
@@ -5,9 +5,28 @@ export interface AppError {
 
 export interface AuthStatus {
   authenticated: boolean;
+  /** The saved login was rejected; userId and displayName are the old login's */
+  reauthRequired: boolean;
   userId?: string;
   displayName?: string;
   countryCode: string;
+  email?: string;
+  avatarUrl?: string;
+}
+
+export type NetworkHealth = "good" | "degraded" | "offline";
+
+export interface NetworkStatus {
+  health: NetworkHealth;
+  medianLatencyMs?: number;
+  errorRate: number;
+  samples: number;
+}
+
+/** The whole catalog being refused in the user's country */
+export interface RegionBlock {
+  country: string;
+  since: string;
 }
 
 export interface DeviceAuthResponse {
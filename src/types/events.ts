@@ -1,22 +1,169 @@
-import type { PlaybackState } from "./player";
+import type { PlaybackState, RepeatMode } from "./player";
+import type { RegionBlock } from "./api";
+import type { NewRelease } from "./track";
 
 export interface ProgressPayload {
   position: number;
   duration: number;
   position_fraction: number;
+  session_epoch: number;
+  playback_epoch: number;
 }
 
-export interface TrackChangedPayload {
+/** What a track's stream was delivered as, for the quality badge */
+export interface StreamQuality {
+  /** Codec the manifest announced */
+  codec?: string;
+  /** Codec the decoder found, e.g. "flac" or "aac" */
+  detected_codec?: string;
+  /** Container the decoder found, e.g. "flac" or "mp4" */
+  container?: string;
+  /** Badge text, e.g. "FLAC 24/96" or "AAC" */
+  quality?: string;
+  /** Tidal's quality tier, e.g. "LOSSLESS" or "HI_RES_LOSSLESS" */
+  audio_quality?: string;
+  /** Probed by the decoder once playback starts; lossy codecs have none */
+  bit_depth?: number;
+  sample_rate?: number;
+}
+
+export interface TrackChangedPayload extends StreamQuality {
   track_id: string;
   title: string;
   artist: string;
   album: string;
   duration: number;
   artwork_url?: string;
-  codec?: string;
-  quality?: string;
+  /** Thumbnail size, e.g. for the mini player */
+  artwork_url_small?: string;
+  /** Full-screen size */
+  artwork_url_large?: string;
+  reason: TrackChangeReason;
+  /** Only a 30-second preview will play (not signed in, or no subscription) */
+  preview_only: boolean;
+  /**
+   * Bumped by every play request as it starts, even one superseded before it
+   * plays; events older than the newest seen are stale
+   */
+  session_epoch: number;
+  /**
+   * Bumped by every stream the player starts and by every stop, including
+   * auto-advance. Progress, state and track-ended events with an older epoch
+   * than the latest track change or stop are about a stream that has ended.
+   */
+  playback_epoch: number;
+}
+
+export type TrackChangeReason =
+  | "user"
+  | "auto_advance"
+  | "media_key"
+  | "restore"
+  | "interrupt"
+  | "queue_resumed";
+
+/** The playing track's stream changed without a track change */
+export interface StreamChangedPayload extends StreamQuality {
+  track_id: string;
+  playback_epoch: number;
+}
+
+/** The output was opened at the decoded rate and channel count, which the
+ * codec parameters got wrong */
+export interface FormatCorrectedPayload {
+  track_id: string;
+  reported_sample_rate: number;
+  reported_channels: number;
+  sample_rate: number;
+  channels: number;
+  playback_epoch: number;
 }
 
 export interface StateChangedPayload {
   state: PlaybackState;
+  playback_epoch: number;
+}
+
+export interface TrackEndedPayload {
+  playback_epoch: number;
+}
+
+export interface PlaybackErrorPayload {
+  trackId: string;
+  kind: string;
+  message: string;
+  skipped: boolean;
+}
+
+export interface VolumeChangedPayload {
+  volume: number;
+  muted: boolean;
+}
+
+export interface MetadataUpdatedPayload {
+  kind: "album" | "playlist";
+  id: string;
+}
+
+export interface OutputDeviceChangedPayload {
+  deviceName: string;
+}
+
+export interface SettingsChangedPayload {
+  audioQuality: string;
+}
+
+export interface QualityAdaptedPayload {
+  from: string;
+  to: string;
+  throughputKbps: number;
+  reason: string;
+}
+
+export interface AuthStatePayload {
+  authenticated: boolean;
+  userId?: string;
+  /** The saved login was rejected and the user has to log in again */
+  reauthRequired: boolean;
+}
+
+export interface PreloadReadyPayload {
+  trackId: string;
+}
+
+export interface AutoresumePendingPayload {
+  trackId: string;
+  /** Time left to call `cancelAutoresume` before playback starts */
+  graceMs: number;
+}
+
+/** The track after the current one, sent shortly before the current one ends */
+export interface UpNextPayload {
+  track_id: string;
+  title: string;
+  artist: string;
+  album: string;
+  duration: number;
+  artwork_url?: string;
+  artwork_url_small?: string;
+  artwork_url_large?: string;
+  session_epoch: number;
+}
+
+export interface ModeChangedPayload {
+  repeatMode: RepeatMode;
+  shuffled: boolean;
+}
+
+export interface NewReleasesFoundPayload {
+  releases: NewRelease[];
+}
+
+export interface FavoritesChangedPayload {
+  trackId: string;
+  favorite: boolean;
+}
+
+export interface RegionBlockChangedPayload {
+  block: RegionBlock | null;
 }
@@ -5,6 +5,111 @@ export type RepeatMode = "off" | "all" | "one";
 export interface QueueState {
   tracks: import("./track").Track[];
   currentIndex: number | null;
+  /** Queue index of the playing track; null when it isn't from the queue */
+  nowPlayingQueueIndex: number | null;
   repeatMode: RepeatMode;
   shuffled: boolean;
 }
+
+export interface TrackStats {
+  playCount: number;
+  completionCount: number;
+  /** Plays left within the first 30 seconds */
+  skipCount: number;
+  lastPlayed: string | null;
+}
+
+export interface TrackStatsEntry extends TrackStats {
+  trackId: string;
+}
+
+export interface ArtworkPalette {
+  /** Hex colors, most common first */
+  colors: string[];
+  /** "#ffffff" or "#000000", whichever reads better on colors[0] */
+  textColor: string;
+}
+
+export interface PreloadStatus {
+  trackId: string;
+  bytesDownloaded: number;
+  totalBytes: number | null;
+  ready: boolean;
+}
+
+export interface Waveform {
+  trackId: string;
+  /** Evenly spaced peaks (0-1) over the whole track */
+  peaks: number[];
+  /** Share of the peaks that were measured; the rest are an average */
+  measuredFraction: number;
+}
+
+export interface SeekPreview {
+  /** Seconds into the track */
+  position: number;
+  /** Already downloaded, so seeking there starts without waiting */
+  buffered: boolean;
+  /** Waveform peak (0-1), when the track's waveform is cached */
+  peak: number | null;
+}
+
+export interface OutputInfo {
+  deviceName: string | null;
+  streamSampleRate: number;
+  streamFormat: string;
+  deviceSampleRate: number | null;
+  deviceFormat: string | null;
+  sourceSampleRate: number;
+  sourceBitsPerSample: number | null;
+  /** False when anything along the path resamples or drops bit depth */
+  losslessPath: boolean;
+}
+
+export interface DiskUsage {
+  files: number;
+  bytes: number;
+}
+
+/** Where memory and disk go; for a debug panel */
+export interface ResourceUsage {
+  streamBufferBytes: number;
+  preloadBufferBytes: number;
+  imageCache: DiskUsage;
+  /** Cached album and playlist listings */
+  metadataCache: DiskUsage;
+  waveformCache: DiskUsage;
+  artworkPalettes: number;
+  cachedProfiles: number;
+  queueLength: number;
+  /** Null where the platform doesn't report it cheaply */
+  processRssBytes: number | null;
+}
+
+/** What `resetAppData` wipes; "all" covers the others */
+export type ResetScope = "cache" | "auth" | "queue" | "stats" | "all";
+
+export interface RemoteControlStatus {
+  enabled: boolean;
+  port: number;
+  /** Listen on the LAN rather than only localhost */
+  allowLan: boolean;
+  /** Required on every request, as a bearer token or `token` query parameter */
+  token: string;
+  /** Null when the server isn't running */
+  listeningPort: number | null;
+}
+
+export interface LaunchSettings {
+  startMinimized: boolean;
+  autoresumeOnLaunch: boolean;
+}
+
+export type InterruptionMode = "ignore" | "duck" | "pause";
+
+export interface InterruptionSettings {
+  mode: InterruptionMode;
+  /** Attenuation while ducked, in dB */
+  duckDb: number;
+  resumeAfter: boolean;
+}
@@ -1,8 +1,9 @@
-import type { Album, Artist, Playlist, Track } from "./track";
+import type { Album, Artist, Playlist, Track, Video } from "./track";
 
 export interface SearchResults {
   tracks: Track[];
   albums: Album[];
   artists: Artist[];
   playlists: Playlist[];
+  videos: Video[];
 }
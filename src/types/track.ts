@@ -11,12 +11,35 @@ export interface Track {
   albumId?: string;
   artworkUrl?: string;
   mediaTags: string[];
+  explicit: boolean;
+  /** ISO date (YYYY-MM-DD), the album's when the track has none */
+  releaseDate?: string;
+  /** Released within the last 14 days */
+  isNew: boolean;
+  /** Streamable in the user's country */
+  streamable: boolean;
+  /** ISO 639 code, when the API reports one */
+  language?: string;
+}
+
+/** A point inside a long track, e.g. where the next song starts in a mix */
+export interface TrackMarker {
+  /** Seconds from the start of the track */
+  time: number;
+  label?: string;
+}
+
+export interface TrackDetails {
+  track: Track;
+  markers: TrackMarker[];
 }
 
 export interface FavoritesPage {
   tracks: Track[];
   nextCursor?: string;
   hasMore: boolean;
+  /** Size of the whole collection, when known */
+  total?: number;
 }
 
 export interface Album {
@@ -29,7 +52,24 @@ export interface Album {
   numberOfVolumes?: number;
   releaseDate?: string;
   artworkUrl?: string;
+  /** Every size of the cover; artworkUrl is the default pick */
+  artwork: ArtworkFile[];
   mediaTags: string[];
+  isNew: boolean;
+}
+
+/** An album by a followed artist, found by the release radar */
+export interface NewRelease {
+  artistId: string;
+  artistName: string;
+  album: Album;
+  foundAt: string;
+}
+
+export interface ArtworkFile {
+  url: string;
+  width?: number;
+  height?: number;
 }
 
 export interface Artist {
@@ -38,6 +78,14 @@ export interface Artist {
   pictureUrl?: string;
 }
 
+export type HomeSectionKind =
+  | "my_mixes"
+  | "discovery"
+  | "new_arrivals"
+  | "favorites_teaser"
+  | "because_you_like"
+  | "recently_played";
+
 export interface RecommendationSection {
   title: string;
   subtitle?: string;
@@ -52,5 +100,88 @@ export interface Playlist {
   numberOfItems?: number;
   playlistType?: string;
   artworkUrl?: string;
+  /** Every size of the cover; artworkUrl is the default pick */
+  artwork: ArtworkFile[];
   creatorId?: string;
+  /** Pinned to the top of the sidebar (local preference) */
+  pinned: boolean;
+  /** Created by the user, as opposed to followed */
+  owned: boolean;
+  lastUpdated?: string;
+  /** Owners and collaborators; only filled in by getPlaylist */
+  contributors: PlaylistContributor[];
+  /** Name of the creator; only filled in by getPlaylist, absent for private profiles */
+  ownerName?: string;
+  /** Whether the current user may edit the playlist's tracks */
+  canEdit: boolean;
+}
+
+export interface PlaylistContributor {
+  id: string;
+  name: string;
+}
+
+/** Another user's public profile; both fields are absent when it's private */
+export interface PublicProfile {
+  id: string;
+  name?: string;
+  avatarUrl?: string;
+}
+
+export type PlaylistScope = "owned" | "followed" | "all";
+
+export type PlaylistSort = "default" | "title" | "artist" | "date_added";
+
+export interface PlaylistPrefs {
+  pinned: boolean;
+  sort: PlaylistSort;
+}
+
+export interface Video {
+  id: string;
+  title: string;
+  artistName: string;
+  artistId?: string;
+  duration: number;
+  imageUrl?: string;
+}
+
+export interface NonTrackItem {
+  id: string;
+  title: string;
+  duration: number;
+  kind: string;
+  position: number;
+}
+
+export interface AlbumListing {
+  album: Album;
+  tracks: Track[];
+  otherItems: NonTrackItem[];
+}
+
+export interface AlbumPage {
+  album: Album;
+  tracks: Track[];
+  moreFromArtist: Album[];
+}
+
+export interface ArtistPage {
+  artist: Artist;
+  topTracks: Track[];
+  albums: Album[];
+  appearsOn: Album[];
+  similarArtists: Artist[];
+}
+
+export interface PlaylistListing {
+  playlist: Playlist;
+  tracks: Track[];
+  otherItems: NonTrackItem[];
+}
+
+export interface Cached<T> {
+  data: T;
+  fetchedAt: string;
+  stale: boolean;
 }